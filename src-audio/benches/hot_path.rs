@@ -0,0 +1,161 @@
+//! Time an audio callback spends handing one mixed buffer to the processing
+//! thread, with the `Mutex` flags and `mpsc` channel the backends used to
+//! share and with `MixerControls` and the mixed queue.
+//!
+//! Run with `cargo bench -p flowstt-audio`. Each path hands off buffers at a
+//! steady pace, as a capture callback does, to a processing thread that
+//! waits for them the way the audio loop did and does now: polling every
+//! millisecond, or sleeping in `recv_timeout`. The mixed queue has the
+//! capacity the backends use and, like the mixer, drops a buffer when it is
+//! full rather than waiting, so the drop count is reported alongside the
+//! times. Buffers are allocated before each timed hand-off on both paths.
+//!
+//! The mixed queue saves the processing thread from polling, not the
+//! callback from waiting: a hand-off that wakes the sleeping thread pays for
+//! the wake-up, and on a single core for the woken thread running first, so
+//! its times can be well above the polled channel's.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use flowstt_audio::mixer::{mixed_queue, MixedSamples, MixerControls, MIXER_SAMPLE_RATE};
+use flowstt_common::RecordingMode;
+
+/// Buffers handed off per path
+const BUFFERS: usize = 20_000;
+
+/// Time between hand-offs; a callback period sped up so the run stays short
+const PERIOD: Duration = Duration::from_micros(200);
+
+/// One 10 ms AEC frame of mono audio at the mixer rate
+fn chunk() -> MixedSamples {
+    MixedSamples {
+        samples: vec![0.0; MIXER_SAMPLE_RATE as usize / 100],
+        channels: 1,
+        sample_rate: MIXER_SAMPLE_RATE,
+        sources: None,
+    }
+}
+
+/// Hand-off times and the number of buffers dropped on a full queue
+struct Run {
+    times: Vec<Duration>,
+    dropped: usize,
+}
+
+/// Mutex flags read per buffer, and an mpsc channel whose receiver is itself
+/// behind a mutex, polled every millisecond as the audio loop used to.
+fn locked() -> Run {
+    let aec_enabled = Arc::new(Mutex::new(true));
+    let mode = Arc::new(Mutex::new(RecordingMode::Mixed));
+    let (tx, rx) = mpsc::channel::<MixedSamples>();
+    let rx = Arc::new(Mutex::new(rx));
+    let done = Arc::new(AtomicBool::new(false));
+    let processing = {
+        let (aec_enabled, mode, rx, done) =
+            (aec_enabled.clone(), mode.clone(), rx.clone(), done.clone());
+        thread::spawn(move || {
+            let mut received = 0;
+            loop {
+                let finished = done.load(Ordering::SeqCst);
+                let _ = *aec_enabled.lock().unwrap();
+                let _ = *mode.lock().unwrap();
+                while rx.lock().unwrap().try_recv().is_ok() {
+                    received += 1;
+                }
+                if finished {
+                    return received;
+                }
+                thread::sleep(Duration::from_millis(1));
+            }
+        })
+    };
+
+    let mut times = Vec::with_capacity(BUFFERS);
+    for _ in 0..BUFFERS {
+        let samples = chunk();
+        let start = Instant::now();
+        let _ = *aec_enabled.lock().unwrap();
+        let _ = *mode.lock().unwrap();
+        tx.send(samples).unwrap();
+        times.push(start.elapsed());
+        thread::sleep(PERIOD);
+    }
+    done.store(true, Ordering::SeqCst);
+    let received = processing.join().unwrap();
+    Run {
+        times,
+        dropped: BUFFERS - received,
+    }
+}
+
+/// Atomic controls read per buffer, and the mixed queue, dropping a buffer
+/// when it is full, to a processing thread waiting in `recv_timeout`.
+fn queued() -> Run {
+    let controls = Arc::new(MixerControls::default());
+    controls.set_aec_enabled(true);
+    let (mut tx, mut rx) = mixed_queue();
+    let done = Arc::new(AtomicBool::new(false));
+    let processing = {
+        let (controls, done) = (controls.clone(), done.clone());
+        thread::spawn(move || {
+            let mut received = 0;
+            loop {
+                let finished = done.load(Ordering::SeqCst);
+                let _ = controls.aec_enabled();
+                let _ = controls.recording_mode();
+                match rx.recv_timeout(Duration::from_millis(100)) {
+                    Some(_) => received += 1,
+                    None if finished => return received,
+                    None => {}
+                }
+            }
+        })
+    };
+
+    let mut times = Vec::with_capacity(BUFFERS);
+    let mut dropped = 0;
+    for _ in 0..BUFFERS {
+        let samples = chunk();
+        let start = Instant::now();
+        let _ = controls.aec_enabled();
+        let _ = controls.recording_mode();
+        let full = tx.push(samples).is_err();
+        times.push(start.elapsed());
+        dropped += full as usize;
+        thread::sleep(PERIOD);
+    }
+    done.store(true, Ordering::SeqCst);
+    let received = processing.join().unwrap();
+    assert_eq!(received + dropped, BUFFERS, "queue lost buffers");
+    Run { times, dropped }
+}
+
+/// Print the median, tail percentiles and worst case of a run's hand-offs,
+/// and how many buffers it dropped.
+fn report(name: &str, run: Run) {
+    let mut times = run.times;
+    times.sort_unstable();
+    let percentile = |p: f64| times[((times.len() - 1) as f64 * p).round() as usize];
+    println!(
+        "{:<14} p50 {:>10?}  p99 {:>10?}  p99.9 {:>10?}  max {:>10?}  dropped {}",
+        name,
+        percentile(0.5),
+        percentile(0.99),
+        percentile(0.999),
+        times[times.len() - 1],
+        run.dropped
+    );
+}
+
+fn main() {
+    println!(
+        "per-buffer hand-off over {} buffers, one every {:?}",
+        BUFFERS, PERIOD
+    );
+    report("mutex + mpsc", locked());
+    report("atomics + ring", queued());
+}
@@ -0,0 +1,30 @@
+//! FlowSTT Audio Library
+//!
+//! Platform-independent audio processing for the FlowSTT capture backends:
+//! mixing the microphone with system audio, echo cancellation, resampling and
+//! the lock-free queue carrying mixed audio to the processing thread. Backends
+//! only do device I/O and hand their streams to an [`mixer::AudioMixer`].
+
+pub mod mixer;
+
+/// Sample rate of 16kHz mono capture, the format transcription uses
+pub const SPEECH_SAMPLE_RATE: u32 = 16000;
+
+/// Microphone and system audio kept apart, for drawing them separately
+pub struct SourceSamples {
+    /// Microphone samples, after echo cancellation when it is on
+    pub input: Vec<f32>,
+    /// System audio samples
+    pub system: Vec<f32>,
+    /// Channels in both buffers, which may differ from the combined output's
+    pub channels: u16,
+}
+
+/// Duration of `frames` frames at `sample_rate`, in nanoseconds; zero while
+/// the rate isn't known.
+pub fn frames_to_ns(frames: usize, sample_rate: u32) -> u64 {
+    if sample_rate == 0 {
+        return 0;
+    }
+    (frames as u128 * 1_000_000_000 / sample_rate as u128) as u64
+}
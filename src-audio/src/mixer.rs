@@ -0,0 +1,1488 @@
+//! Audio mixing shared by the platform backends.
+//!
+//! Backends only do device I/O: they convert each stream to 48kHz interleaved
+//! f32 and hand it to an `AudioMixer`, which runs echo cancellation and
+//! combines the microphone and system audio according to the recording mode.
+//! When source recording is enabled, the mixer also hands each source's raw
+//! pre-AEC capture to a `SourceRecorder` for the length of the capture session.
+//! Each source's level is metered before mixing for the GUI's input meters,
+//! and with two streams each mixed chunk carries the sources it was made from
+//! so the GUI can draw them as separate waveforms.
+//! The echo delay can be estimated by cross-correlating the two sources.
+//! Backends capture chosen channels of multichannel devices by downmixing them
+//! before the mixer (see `selected_channels`).
+//! Backends that can convert formats capture a single source as 16kHz mono
+//! when configured to (see `speech_capture`); the mixer then passes it through
+//! at that rate.
+//! Backends timestamp each buffer on the host clock (see `clock`), and the
+//! mixer uses the timestamps to keep the two sources aligned as their device
+//! clocks drift apart.
+//!
+//! Settings the mixer reads per frame are atomics (`MixerControls`) and mixed
+//! audio leaves through a single-producer ring (`mixed_queue`), so the audio
+//! path only takes a lock to wake the processing thread when it sleeps on an
+//! empty queue.
+
+use std::collections::BTreeMap;
+use std::sync::atomic::{fence, AtomicBool, AtomicU32, AtomicU64, AtomicU8, Ordering};
+use std::sync::{Arc, Condvar, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use aec3::config::EchoCanceller3Config;
+use aec3::voip::VoipAec3;
+use flowstt_common::{
+    AecMetrics, AecSettings, AudioLevel, AudioSourceType, CaptureLatency, ChannelSelection,
+    FlowSttError, RecordingMode, SourceFormat, SourceLevels, SyncMetrics, MAX_AEC_DELAY_MS,
+};
+use rtrb::{Consumer, Producer, PushError, RingBuffer};
+
+use crate::{frames_to_ns, SourceSamples, SPEECH_SAMPLE_RATE};
+
+/// Sample rate the mixer and AEC run at
+pub const MIXER_SAMPLE_RATE: u32 = 48000;
+
+/// AEC3 frame size: 10ms at 48kHz = 480 samples per channel
+const AEC_FRAME_SAMPLES: usize = 480;
+
+/// Duration of an AEC3 filter block
+const AEC_BLOCK_MS: u32 = 4;
+
+/// Echo cancellation tuning; `None` uses the defaults
+static AEC_SETTINGS: Mutex<Option<AecSettings>> = Mutex::new(None);
+
+/// Set when `AEC_SETTINGS` changes so a running mixer rebuilds its AEC
+static AEC_SETTINGS_CHANGED: AtomicBool = AtomicBool::new(false);
+
+/// Set the echo cancellation tuning, restarting a running AEC with it.
+pub fn set_aec_settings(settings: AecSettings) {
+    *AEC_SETTINGS.lock().unwrap() = Some(settings);
+    AEC_SETTINGS_CHANGED.store(true, Ordering::SeqCst);
+}
+
+fn aec_settings() -> AecSettings {
+    AEC_SETTINGS.lock().unwrap().unwrap_or_default()
+}
+
+/// How often AEC metrics are published
+const AEC_METRICS_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Latest AEC metrics; `None` while echo cancellation isn't running
+static AEC_METRICS: Mutex<Option<AecMetrics>> = Mutex::new(None);
+
+/// Set when `AEC_METRICS` is updated, cleared when the audio loop takes them
+static AEC_METRICS_FRESH: AtomicBool = AtomicBool::new(false);
+
+/// Latest AEC metrics, if echo cancellation is running.
+pub fn aec_metrics() -> Option<AecMetrics> {
+    *AEC_METRICS.lock().unwrap()
+}
+
+/// Take the AEC metrics published since the last call.
+pub fn take_aec_metrics() -> Option<AecMetrics> {
+    if AEC_METRICS_FRESH.swap(false, Ordering::SeqCst) {
+        aec_metrics()
+    } else {
+        None
+    }
+}
+
+fn publish_aec_metrics(metrics: Option<AecMetrics>) {
+    *AEC_METRICS.lock().unwrap() = metrics;
+    AEC_METRICS_FRESH.store(metrics.is_some(), Ordering::SeqCst);
+}
+
+/// How often stream sync metrics are published
+const SYNC_METRICS_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Latest stream sync metrics; `None` unless two sources are being mixed
+static SYNC_METRICS: Mutex<Option<SyncMetrics>> = Mutex::new(None);
+
+/// Latest stream sync metrics, if two sources are being mixed.
+pub fn sync_metrics() -> Option<SyncMetrics> {
+    *SYNC_METRICS.lock().unwrap()
+}
+
+/// Echo delay measurement in progress
+static DELAY_ESTIMATOR: Mutex<Option<DelayEstimator>> = Mutex::new(None);
+
+/// Set while `DELAY_ESTIMATOR` holds a measurement, so the audio path only
+/// locks it during one
+static DELAY_ESTIMATING: AtomicBool = AtomicBool::new(false);
+
+/// Start measuring the echo delay between system audio and the microphone.
+pub fn start_delay_estimation() {
+    *DELAY_ESTIMATOR.lock().unwrap() = Some(DelayEstimator::default());
+    DELAY_ESTIMATING.store(true, Ordering::SeqCst);
+}
+
+/// Stop measuring and return the estimated echo delay in milliseconds.
+pub fn finish_delay_estimation() -> Result<u32, FlowSttError> {
+    DELAY_ESTIMATING.store(false, Ordering::SeqCst);
+    DELAY_ESTIMATOR
+        .lock()
+        .unwrap()
+        .take()
+        .ok_or_else(|| FlowSttError::Capture("Delay estimation was interrupted".to_string()))?
+        .estimate()
+        .map_err(FlowSttError::Capture)
+}
+
+/// Writes the raw capture of each source in a capture session as the mixer
+/// receives it. The service implements it, since it names and encodes
+/// recordings.
+pub trait SourceRecorder: Send {
+    /// Write a buffer from the system audio (`is_render`) or microphone stream.
+    fn write(&mut self, samples: &[f32], is_render: bool);
+}
+
+/// Starts a `SourceRecorder` for a capture session delivering audio at a
+/// sample rate and channel count
+pub type SourceRecording = dyn Fn(u32, u16) -> Box<dyn SourceRecorder> + Send + Sync;
+
+/// How raw per-source recordings are started; `None` disables them
+static SOURCE_RECORDING: Mutex<Option<Arc<SourceRecording>>> = Mutex::new(None);
+
+/// Decides whether the mixer gets a buffer from a source (see `set_buffer_filter`)
+static BUFFER_FILTER: OnceLock<fn(bool) -> bool> = OnceLock::new();
+
+/// Ask `filter` about every buffer, with whether it is system audio, before
+/// using it; buffers it refuses are dropped. The service injects capture
+/// faults this way in debug builds. Only the first filter set is used.
+pub fn set_buffer_filter(filter: fn(bool) -> bool) {
+    let _ = BUFFER_FILTER.set(filter);
+}
+
+/// How often per-source levels are published
+const LEVEL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Floor for level meters, reported for digital silence
+const LEVEL_FLOOR_DB: f32 = -100.0;
+
+/// Levels published since the audio loop last took them
+static SOURCE_LEVELS: Mutex<Option<SourceLevels>> = Mutex::new(None);
+
+/// Take the per-source levels published since the last call.
+pub fn take_source_levels() -> Option<SourceLevels> {
+    SOURCE_LEVELS.lock().unwrap().take()
+}
+
+/// Native source formats reported by the backends, not yet taken by the audio loop
+static SOURCE_FORMATS: Mutex<Vec<SourceFormat>> = Mutex::new(Vec::new());
+
+/// Record the native format a backend negotiated for a source.
+pub fn report_source_format(is_render: bool, sample_rate: u32, channels: u16) {
+    let source_type = if is_render {
+        AudioSourceType::System
+    } else {
+        AudioSourceType::Input
+    };
+    SOURCE_FORMATS.lock().unwrap().push(SourceFormat {
+        source_type,
+        sample_rate,
+        channels,
+    });
+}
+
+/// Take the source formats reported since the last call.
+pub fn take_source_formats() -> Vec<SourceFormat> {
+    std::mem::take(&mut *SOURCE_FORMATS.lock().unwrap())
+}
+
+/// Requested capture buffer duration in milliseconds; 0 keeps each backend's
+/// default
+static CAPTURE_BUFFER_MS: AtomicU32 = AtomicU32::new(0);
+
+/// Buffer latency the backends negotiated for the current capture session
+static CAPTURE_LATENCY: Mutex<Vec<CaptureLatency>> = Mutex::new(Vec::new());
+
+/// Set the capture buffer duration backends request when capture next starts.
+pub fn set_capture_buffer_ms(ms: Option<u32>) {
+    CAPTURE_BUFFER_MS.store(ms.unwrap_or(0), Ordering::SeqCst);
+}
+
+/// Capture buffer duration to request, if one is configured.
+pub fn capture_buffer_ms() -> Option<u32> {
+    match CAPTURE_BUFFER_MS.load(Ordering::SeqCst) {
+        0 => None,
+        ms => Some(ms),
+    }
+}
+
+/// Capture buffer duration to request, in frames at `sample_rate`.
+pub fn capture_buffer_frames(sample_rate: u32) -> Option<usize> {
+    capture_buffer_ms().map(|ms| (sample_rate as usize * ms as usize / 1000).max(1))
+}
+
+/// Record the buffer duration a backend ended up with for a source.
+pub fn report_capture_latency(is_render: bool, frames: usize, sample_rate: u32) {
+    if sample_rate == 0 {
+        return;
+    }
+    let source_type = if is_render {
+        AudioSourceType::System
+    } else {
+        AudioSourceType::Input
+    };
+    let buffer_ms = frames as f32 * 1000.0 / sample_rate as f32;
+    tracing::info!("{:?} capture buffer: {:.1} ms", source_type, buffer_ms);
+
+    let mut latency = CAPTURE_LATENCY.lock().unwrap();
+    latency.retain(|l| l.source_type != source_type);
+    latency.push(CaptureLatency {
+        source_type,
+        requested_ms: capture_buffer_ms(),
+        buffer_ms,
+    });
+}
+
+/// Buffer latency of each source in the current capture session.
+pub fn capture_latency() -> Vec<CaptureLatency> {
+    CAPTURE_LATENCY.lock().unwrap().clone()
+}
+
+/// Whether single sources are captured as 16kHz mono where the backend can
+/// convert to it
+static SPEECH_CAPTURE: AtomicBool = AtomicBool::new(false);
+
+/// Set the capture sample rate backends use when capture next starts; 16kHz
+/// selects speech capture, anything else the mixer's rate.
+pub fn set_capture_sample_rate(sample_rate: Option<u32>) {
+    SPEECH_CAPTURE.store(sample_rate == Some(SPEECH_SAMPLE_RATE), Ordering::SeqCst);
+}
+
+/// Whether capturing these sources should deliver 16kHz mono: speech capture
+/// is configured and there is a single source with no channel selection.
+/// Only backends that can convert to the format ask.
+pub fn speech_capture(source1_id: Option<&str>, source2_id: Option<&str>) -> bool {
+    if !SPEECH_CAPTURE.load(Ordering::SeqCst) {
+        return false;
+    }
+    match (source1_id, source2_id) {
+        (Some(id), None) | (None, Some(id)) => channel_selection(id).is_none(),
+        _ => false,
+    }
+}
+
+/// Channels to capture from multichannel devices, by device ID
+static CHANNEL_SELECTIONS: Mutex<BTreeMap<String, ChannelSelection>> = Mutex::new(BTreeMap::new());
+
+/// Set the channels captured from multichannel devices when capture next starts.
+pub fn set_channel_selections(selections: BTreeMap<String, ChannelSelection>) {
+    *CHANNEL_SELECTIONS.lock().unwrap() = selections;
+}
+
+/// Channels selected for a device, if any.
+pub fn channel_selection(device_id: &str) -> Option<ChannelSelection> {
+    CHANNEL_SELECTIONS
+        .lock()
+        .unwrap()
+        .get(device_id)
+        .filter(|selection| !selection.is_all())
+        .cloned()
+}
+
+/// Zero-based channels to downmix for a device with `device_channels`
+/// channels, or None to capture its own channels. Selected channels the
+/// device doesn't have are left out.
+pub fn selected_channels(device_id: &str, device_channels: usize) -> Option<Vec<usize>> {
+    let selection = channel_selection(device_id)?;
+    let channels: Vec<usize> = selection
+        .channels
+        .iter()
+        .map(|&channel| channel as usize - 1)
+        .filter(|&channel| channel < device_channels)
+        .collect();
+    if channels.len() < selection.channels.len() {
+        tracing::warn!(
+            "Device {} has {} channels; ignoring channels of selection {} beyond them",
+            device_id,
+            device_channels,
+            selection
+        );
+    }
+    if channels.is_empty() {
+        return None;
+    }
+    tracing::info!("Capturing channels {} of device {}", selection, device_id);
+    Some(channels)
+}
+
+/// Downmix the selected channels of interleaved audio to mono, as stereo.
+pub fn downmix_channels(samples: &[f32], device_channels: usize, selected: &[usize]) -> Vec<f32> {
+    let scale = 1.0 / selected.len().max(1) as f32;
+    let mut stereo = Vec::with_capacity(samples.len() / device_channels.max(1) * 2);
+    for frame in samples.chunks_exact(device_channels.max(1)) {
+        let sample = selected.iter().map(|&channel| frame[channel]).sum::<f32>() * scale;
+        stereo.push(sample);
+        stereo.push(sample);
+    }
+    stereo
+}
+
+/// Enable raw per-source recording with `recording`, or disable it with
+/// `None`. Takes effect when capture next starts.
+pub fn set_source_recording(recording: Option<Arc<SourceRecording>>) {
+    *SOURCE_RECORDING.lock().unwrap() = recording;
+}
+
+/// Mixed audio ready for the processing pipeline
+pub struct MixedSamples {
+    pub samples: Vec<f32>,
+    pub channels: u16,
+    pub sample_rate: u32,
+    pub sources: Option<SourceSamples>,
+}
+
+/// Mixed chunks the queue to the processing thread holds: about 5s of 10ms
+/// frames, so only a stalled processing thread ever fills it
+const MIXED_QUEUE_CAPACITY: usize = 512;
+
+/// Mixed chunks dropped because the queue was full
+static MIXED_DROPPED: AtomicU64 = AtomicU64::new(0);
+
+/// Create the queue carrying mixed audio from the mixer to the backend's
+/// `recv_timeout`. The mixer is its only producer and the processing thread its
+/// only consumer, so neither side ever waits on the other.
+pub fn mixed_queue() -> (MixedSender, MixedReceiver) {
+    let (tx, rx) = RingBuffer::new(MIXED_QUEUE_CAPACITY);
+    let signal = Arc::new(QueueSignal::default());
+    (
+        MixedSender {
+            tx,
+            signal: Arc::clone(&signal),
+        },
+        MixedReceiver { rx, signal },
+    )
+}
+
+/// Wakes the processing thread when it sleeps on an empty queue. The mixer
+/// only takes the lock while the processing thread is waiting on it.
+#[derive(Default)]
+struct QueueSignal {
+    waiting: AtomicBool,
+    lock: Mutex<()>,
+    wake: Condvar,
+}
+
+/// Mixer side of the mixed audio queue
+pub struct MixedSender {
+    tx: Producer<MixedSamples>,
+    signal: Arc<QueueSignal>,
+}
+
+impl MixedSender {
+    /// Queue mixed audio, handing it back if the queue is full.
+    pub fn push(&mut self, mixed: MixedSamples) -> Result<(), MixedSamples> {
+        if let Err(PushError::Full(mixed)) = self.tx.push(mixed) {
+            return Err(mixed);
+        }
+        // Pairs with the fence in `recv_timeout`: either the receiver sees
+        // this chunk before sleeping, or we see it waiting and wake it
+        fence(Ordering::SeqCst);
+        if self.signal.waiting.load(Ordering::SeqCst) {
+            let _guard = self.signal.lock.lock().unwrap();
+            self.signal.wake.notify_one();
+        }
+        Ok(())
+    }
+}
+
+/// Processing thread side of the mixed audio queue
+pub struct MixedReceiver {
+    rx: Consumer<MixedSamples>,
+    signal: Arc<QueueSignal>,
+}
+
+impl MixedReceiver {
+    /// Take queued audio without waiting.
+    pub fn try_recv(&mut self) -> Option<MixedSamples> {
+        self.rx.pop().ok()
+    }
+
+    /// Take queued audio, waiting up to `timeout` for the mixer to produce
+    /// some.
+    pub fn recv_timeout(&mut self, timeout: Duration) -> Option<MixedSamples> {
+        if let Some(mixed) = self.try_recv() {
+            return Some(mixed);
+        }
+        let deadline = Instant::now() + timeout;
+        let signal = Arc::clone(&self.signal);
+        let mut guard = signal.lock.lock().unwrap();
+        loop {
+            signal.waiting.store(true, Ordering::SeqCst);
+            fence(Ordering::SeqCst);
+            if let Some(mixed) = self.try_recv() {
+                signal.waiting.store(false, Ordering::SeqCst);
+                return Some(mixed);
+            }
+            let Some(remaining) = deadline.checked_duration_since(Instant::now()) else {
+                signal.waiting.store(false, Ordering::SeqCst);
+                return None;
+            };
+            guard = signal.wake.wait_timeout(guard, remaining).unwrap().0;
+        }
+    }
+}
+
+/// Settings the mixer reads for every frame, shared with the backend that
+/// changes them. Atomics, so the audio path never waits on a lock.
+#[derive(Default)]
+pub struct MixerControls {
+    aec_enabled: AtomicBool,
+    recording_mode: AtomicU8,
+}
+
+impl MixerControls {
+    pub fn aec_enabled(&self) -> bool {
+        self.aec_enabled.load(Ordering::Relaxed)
+    }
+
+    pub fn set_aec_enabled(&self, enabled: bool) {
+        self.aec_enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    pub fn recording_mode(&self) -> RecordingMode {
+        match self.recording_mode.load(Ordering::Relaxed) {
+            1 => RecordingMode::EchoCancel,
+            2 => RecordingMode::SplitChannels,
+            _ => RecordingMode::Mixed,
+        }
+    }
+
+    pub fn set_recording_mode(&self, mode: RecordingMode) {
+        let value = match mode {
+            RecordingMode::Mixed => 0,
+            RecordingMode::EchoCancel => 1,
+            RecordingMode::SplitChannels => 2,
+        };
+        self.recording_mode.store(value, Ordering::Relaxed);
+    }
+}
+
+/// Mixer state for combining audio from multiple streams
+/// Uses separate render-first AEC processing pattern for proper echo cancellation.
+pub struct AudioMixer {
+    /// Backend name used in log messages
+    backend: &'static str,
+    /// Buffer for capture samples (microphone/input)
+    capture_buffer: Vec<f32>,
+    /// Buffer for render samples (system audio/reference) - fed to AEC
+    render_buffer: Vec<f32>,
+    /// Buffer for render samples to mix with processed capture (for Mixed mode)
+    render_mix_buffer: Vec<f32>,
+    /// Number of active streams (1 or 2)
+    num_streams: usize,
+    /// Channels per stream
+    channels: u16,
+    /// Sample rate of the streams
+    sample_rate: u32,
+    /// Sending side of the queue to the processing thread
+    output_tx: MixedSender,
+    /// AEC flag and recording mode (shared with the backend)
+    controls: Arc<MixerControls>,
+    /// AEC3 pipeline (created when in mixed mode with 2 streams)
+    aec: Option<VoipAec3>,
+    /// Raw per-source recording for the current capture session
+    source_recorder: Option<Box<dyn SourceRecorder>>,
+    /// Level of the capture stream since levels were last published
+    input_meter: LevelMeter,
+    /// Level of the render stream since levels were last published
+    system_meter: LevelMeter,
+    levels_published: Instant,
+    /// AEC metrics since they were last published
+    aec_meter: AecMeter,
+    aec_metrics_published: Instant,
+    /// Alignment of the render stream with the capture stream
+    sync: StreamSync,
+    sync_metrics_published: Instant,
+}
+
+impl AudioMixer {
+    pub fn new(
+        backend: &'static str,
+        output_tx: MixedSender,
+        controls: Arc<MixerControls>,
+    ) -> Self {
+        Self {
+            backend,
+            capture_buffer: Vec::new(),
+            render_buffer: Vec::new(),
+            render_mix_buffer: Vec::new(),
+            num_streams: 0,
+            channels: 2,
+            sample_rate: MIXER_SAMPLE_RATE,
+            output_tx,
+            controls,
+            aec: None,
+            source_recorder: None,
+            input_meter: LevelMeter::default(),
+            system_meter: LevelMeter::default(),
+            levels_published: Instant::now(),
+            aec_meter: AecMeter::default(),
+            aec_metrics_published: Instant::now(),
+            sync: StreamSync::default(),
+            sync_metrics_published: Instant::now(),
+        }
+    }
+
+    pub fn set_num_streams(&mut self, num: usize) {
+        self.num_streams = num;
+        self.capture_buffer.clear();
+        self.render_buffer.clear();
+        self.render_mix_buffer.clear();
+        self.input_meter = LevelMeter::default();
+        self.system_meter = LevelMeter::default();
+        self.aec_meter = AecMeter::default();
+        publish_aec_metrics(None);
+        self.sync = StreamSync::default();
+        *SYNC_METRICS.lock().unwrap() = None;
+        CAPTURE_LATENCY.lock().unwrap().clear();
+
+        // Start a new raw recording for each capture session
+        self.source_recorder = None;
+        if num > 0 {
+            if let Some(recording) = SOURCE_RECORDING.lock().unwrap().clone() {
+                self.source_recorder = Some(recording(self.sample_rate, self.channels));
+            }
+        }
+
+        // Create AEC3 pipeline when we have 2 streams (mic + system audio)
+        if num == 2 {
+            self.build_aec();
+        } else {
+            self.aec = None;
+        }
+    }
+
+    /// Create the AEC3 pipeline with the current tuning.
+    fn build_aec(&mut self) {
+        let settings = aec_settings();
+        let filter_blocks = settings.filter_length_ms.div_ceil(AEC_BLOCK_MS) as usize;
+        let mut config = EchoCanceller3Config::default();
+        config.filter.refined.length_blocks = filter_blocks;
+        config.filter.coarse.length_blocks = filter_blocks;
+
+        match VoipAec3::builder(
+            MIXER_SAMPLE_RATE as usize,
+            self.channels as usize,
+            self.channels as usize,
+        )
+        .with_config(config)
+        .enable_high_pass(true)
+        .initial_delay_ms(settings.initial_delay_ms as i32)
+        .build()
+        {
+            Ok(aec) => {
+                tracing::info!(
+                    "{}: AEC3 initialized: 48kHz, {} channels, {}ms frames, {}ms delay, {}ms filter",
+                    self.backend,
+                    self.channels,
+                    AEC_FRAME_SAMPLES * 1000 / MIXER_SAMPLE_RATE as usize,
+                    settings.initial_delay_ms,
+                    settings.filter_length_ms
+                );
+                self.aec = Some(aec);
+            }
+            Err(e) => {
+                tracing::error!("{}: Failed to initialize AEC3: {:?}", self.backend, e);
+                self.aec = None;
+            }
+        }
+    }
+
+    pub fn set_channels(&mut self, channels: u16) {
+        self.channels = channels;
+    }
+
+    /// Set the rate streams are delivered at, which is passed on with the
+    /// mixed audio.
+    pub fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.sample_rate = sample_rate;
+    }
+
+    /// Add samples from a stream, routing based on source type
+    /// - System audio (render) is fed IMMEDIATELY to the AEC render path
+    /// - Microphone (capture) is buffered and processed when enough data is available
+    ///
+    /// `timestamp` is the host clock time the first frame was captured, in
+    /// nanoseconds (see `clock`).
+    pub fn push_samples(&mut self, samples: &[f32], is_render: bool, timestamp: u64) {
+        if let Some(filter) = BUFFER_FILTER.get() {
+            if !filter(is_render) {
+                return;
+            }
+        }
+
+        if let Some(recorder) = self.source_recorder.as_mut() {
+            recorder.write(samples, is_render);
+        }
+        if DELAY_ESTIMATING.load(Ordering::Relaxed) {
+            if let Some(estimator) = DELAY_ESTIMATOR.lock().unwrap().as_mut() {
+                estimator.push(samples, is_render, self.channels);
+            }
+        }
+
+        if is_render {
+            self.system_meter.add(samples);
+        } else {
+            self.input_meter.add(samples);
+        }
+        if self.levels_published.elapsed() >= LEVEL_INTERVAL {
+            *SOURCE_LEVELS.lock().unwrap() = Some(SourceLevels {
+                input: self.input_meter.take(),
+                system: self.system_meter.take(),
+            });
+            self.levels_published = Instant::now();
+        }
+
+        if self.num_streams == 1 {
+            // Only one stream - send directly (no AEC possible)
+            self.send(MixedSamples {
+                samples: samples.to_vec(),
+                channels: self.channels,
+                sample_rate: self.sample_rate,
+                sources: None,
+            });
+            return;
+        }
+
+        // Two streams mode
+        self.sync.push(
+            timestamp,
+            samples.len() / self.channels.max(1) as usize,
+            is_render,
+        );
+        if AEC_SETTINGS_CHANGED.swap(false, Ordering::SeqCst) {
+            self.build_aec();
+        }
+        let frame_size = AEC_FRAME_SAMPLES * self.channels as usize;
+
+        if is_render {
+            // System audio (render) - feed to AEC immediately in frame-sized chunks
+            // This is critical: AEC needs to see render BEFORE corresponding capture
+            self.render_buffer.extend_from_slice(samples);
+            // Also keep a copy for mixing
+            self.render_mix_buffer.extend_from_slice(samples);
+
+            if let Some(ref mut aec) = self.aec {
+                while self.render_buffer.len() >= frame_size {
+                    let render_frame: Vec<f32> = self.render_buffer.drain(0..frame_size).collect();
+                    if let Err(e) = aec.handle_render_frame(&render_frame) {
+                        tracing::error!(
+                            "{}: AEC3 handle_render_frame error: {:?}",
+                            self.backend,
+                            e
+                        );
+                    }
+                }
+            }
+        } else {
+            // Microphone (capture) - buffer and process
+            self.capture_buffer.extend_from_slice(samples);
+            self.process_capture();
+        }
+    }
+
+    /// Process buffered capture samples through AEC
+    fn process_capture(&mut self) {
+        let aec_enabled = self.controls.aec_enabled();
+        let recording_mode = self.controls.recording_mode();
+
+        let channels = self.channels.max(1) as usize;
+        let frame_size = AEC_FRAME_SAMPLES * channels;
+
+        // Process capture frames when we have enough data from both sources
+        while self.capture_buffer.len() >= frame_size && self.render_mix_buffer.len() >= frame_size
+        {
+            // Line the system audio up with the microphone before pairing frames
+            self.sync.align(
+                &mut self.render_mix_buffer,
+                self.capture_buffer.len() / channels,
+                channels,
+            );
+            if self.sync_metrics_published.elapsed() >= SYNC_METRICS_INTERVAL {
+                *SYNC_METRICS.lock().unwrap() = Some(self.sync.metrics());
+                self.sync_metrics_published = Instant::now();
+            }
+            if self.render_mix_buffer.len() < frame_size {
+                break;
+            }
+
+            let capture_frame: Vec<f32> = self.capture_buffer.drain(0..frame_size).collect();
+            let render_frame: Vec<f32> = self.render_mix_buffer.drain(0..frame_size).collect();
+
+            // Apply AEC if enabled and we have an AEC instance
+            let processed_capture = match self.aec.as_mut() {
+                Some(aec) if aec_enabled => {
+                    let mut out = vec![0.0f32; capture_frame.len()];
+                    match aec.process_capture_frame(&capture_frame, false, &mut out) {
+                        Ok(metrics) => {
+                            self.aec_meter.add(
+                                metrics.echo_return_loss as f32,
+                                metrics.echo_return_loss_enhancement as f32,
+                                metrics.delay_ms,
+                            );
+                            out
+                        }
+                        Err(e) => {
+                            tracing::error!(
+                                "{}: AEC3 process_capture_frame error: {:?}",
+                                self.backend,
+                                e
+                            );
+                            capture_frame
+                        }
+                    }
+                }
+                _ => capture_frame,
+            };
+
+            if self.aec_metrics_published.elapsed() >= AEC_METRICS_INTERVAL {
+                publish_aec_metrics(self.aec_meter.take());
+                self.aec_metrics_published = Instant::now();
+            }
+
+            // Generate output based on recording mode
+            let output: Vec<f32> = match recording_mode {
+                RecordingMode::Mixed => {
+                    // Mix processed capture with system audio using soft clipping
+                    processed_capture
+                        .iter()
+                        .zip(render_frame.iter())
+                        .map(|(&s1, &s2)| soft_clip(s1 + s2))
+                        .collect()
+                }
+                RecordingMode::EchoCancel => {
+                    // Output only the processed capture signal - no mixing
+                    processed_capture.clone()
+                }
+                RecordingMode::SplitChannels => {
+                    // Keep sources apart: mic on the left, system audio on the right
+                    split_channels(&processed_capture, &render_frame, self.channels)
+                }
+            };
+
+            // Debug logging (periodic)
+            static LOG_COUNTER: AtomicU32 = AtomicU32::new(0);
+            let count = LOG_COUNTER.fetch_add(1, Ordering::Relaxed);
+            if count.is_multiple_of(500) {
+                tracing::debug!(
+                    "{} AudioMixer: mode={:?}, aec={}, render_rms={:.4}, out_rms={:.4}, sync={:?}",
+                    self.backend,
+                    recording_mode,
+                    aec_enabled,
+                    rms(&render_frame),
+                    rms(&output),
+                    self.sync.metrics()
+                );
+            }
+
+            // Send output (split mode is always stereo)
+            let channels = if recording_mode == RecordingMode::SplitChannels {
+                2
+            } else {
+                self.channels
+            };
+            self.send(MixedSamples {
+                samples: output,
+                channels,
+                sample_rate: self.sample_rate,
+                sources: Some(SourceSamples {
+                    input: processed_capture,
+                    system: render_frame,
+                    channels: self.channels,
+                }),
+            });
+        }
+    }
+
+    /// Queue mixed audio for the processing thread, dropping it if the queue
+    /// is full rather than waiting.
+    fn send(&mut self, mixed: MixedSamples) {
+        if self.output_tx.push(mixed).is_err() {
+            let dropped = MIXED_DROPPED.fetch_add(1, Ordering::Relaxed) + 1;
+            if dropped.is_power_of_two() {
+                tracing::warn!(
+                    "{}: Processing thread is behind, {} mixed chunks dropped",
+                    self.backend,
+                    dropped
+                );
+            }
+        }
+    }
+}
+
+/// Accumulates RMS and peak level over a metering window.
+#[derive(Default)]
+struct LevelMeter {
+    sum_squares: f64,
+    count: usize,
+    peak: f32,
+}
+
+impl LevelMeter {
+    fn add(&mut self, samples: &[f32]) {
+        for &sample in samples {
+            self.sum_squares += f64::from(sample * sample);
+            self.peak = self.peak.max(sample.abs());
+        }
+        self.count += samples.len();
+    }
+
+    /// Level since the last call, or `None` if no samples arrived.
+    fn take(&mut self) -> Option<AudioLevel> {
+        let meter = std::mem::take(self);
+        if meter.count == 0 {
+            return None;
+        }
+        let rms = (meter.sum_squares / meter.count as f64).sqrt() as f32;
+        Some(AudioLevel {
+            rms_db: to_db(rms),
+            peak_db: to_db(meter.peak),
+        })
+    }
+}
+
+/// Averages AEC3 metrics over a publishing interval.
+#[derive(Default)]
+struct AecMeter {
+    erl_sum: f32,
+    erle_sum: f32,
+    delay_ms: i32,
+    count: u32,
+}
+
+impl AecMeter {
+    fn add(&mut self, erl_db: f32, erle_db: f32, delay_ms: i32) {
+        self.erl_sum += erl_db;
+        self.erle_sum += erle_db;
+        self.delay_ms = delay_ms;
+        self.count += 1;
+    }
+
+    /// Averages since the last call, or `None` if the AEC processed no frames.
+    fn take(&mut self) -> Option<AecMetrics> {
+        let meter = std::mem::take(self);
+        if meter.count == 0 {
+            return None;
+        }
+        Some(AecMetrics {
+            erl_db: meter.erl_sum / meter.count as f32,
+            erle_db: meter.erle_sum / meter.count as f32,
+            delay_ms: meter.delay_ms,
+        })
+    }
+}
+
+/// Offset between the sources tolerated before frames are dropped or
+/// inserted (1ms)
+const SYNC_TOLERANCE_FRAMES: f64 = 48.0;
+
+/// Offset beyond which the sources are realigned at once rather than a frame
+/// at a time (50ms)
+const SYNC_REALIGN_FRAMES: f64 = 2400.0;
+
+/// Weight of each offset measurement in the smoothed offset, evening out the
+/// jitter of timestamps taken on arrival
+const SYNC_SMOOTHING: f64 = 0.02;
+
+/// Shortest span of timestamps a stream's rate is measured over (10s)
+const MIN_RATE_SPAN_NS: u64 = 10_000_000_000;
+
+/// Frames a stream delivered against its capture timestamps.
+#[derive(Default)]
+struct StreamClock {
+    /// Timestamp the rate is measured from; `None` until the first buffer
+    start_ns: Option<u64>,
+    /// Frames delivered from `start_ns` up to the latest buffer
+    frames: u64,
+    /// Timestamp of the latest buffer
+    last_ns: u64,
+    /// Frames in the latest buffer
+    last_frames: u64,
+}
+
+impl StreamClock {
+    fn push(&mut self, timestamp_ns: u64, frames: usize) {
+        if self.start_ns.is_none() {
+            self.start_ns = Some(timestamp_ns);
+        } else {
+            self.frames += self.last_frames;
+        }
+        self.last_ns = timestamp_ns;
+        self.last_frames = frames as u64;
+    }
+
+    /// Capture time just past the latest buffer's last frame.
+    fn end_ns(&self) -> Option<u64> {
+        self.start_ns?;
+        Some(self.last_ns + frames_to_ns(self.last_frames as usize, MIXER_SAMPLE_RATE))
+    }
+
+    /// Frames per second delivered on the host clock, once the timestamps
+    /// span long enough to tell.
+    fn rate(&self) -> Option<f64> {
+        let span = self.last_ns.checked_sub(self.start_ns?)?;
+        (span >= MIN_RATE_SPAN_NS).then(|| self.frames as f64 * 1e9 / span as f64)
+    }
+
+    /// Measure the rate afresh from the latest buffer, after a discontinuity.
+    fn restart(&mut self) {
+        if self.start_ns.is_some() {
+            self.start_ns = Some(self.last_ns);
+            self.frames = 0;
+        }
+    }
+}
+
+/// Keeps system audio aligned with the microphone for mixing.
+///
+/// The timestamps give the capture time of the audio at the head of each
+/// source's buffer. Their smoothed difference is corrected by dropping or
+/// repeating one frame of system audio at a time, at most once per AEC frame,
+/// which keeps up with far more than the tens of ppm device clocks typically
+/// drift by. Offsets too large to catch up on that way, from a source starting
+/// late or stalling, are dropped or padded with silence at once. The
+/// microphone is never altered, and the AEC reference is left continuous
+/// since AEC3 tracks the echo delay itself.
+#[derive(Default)]
+struct StreamSync {
+    capture: StreamClock,
+    render: StreamClock,
+    /// Smoothed capture head time minus render head time, in frames
+    offset: Option<f64>,
+    dropped_frames: u64,
+    inserted_frames: u64,
+}
+
+impl StreamSync {
+    fn push(&mut self, timestamp_ns: u64, frames: usize, is_render: bool) {
+        if is_render {
+            self.render.push(timestamp_ns, frames);
+        } else {
+            self.capture.push(timestamp_ns, frames);
+        }
+    }
+
+    /// Drop or insert frames at the head of the interleaved `render` buffer
+    /// so it lines up with the head of `capture_frames` buffered capture
+    /// frames.
+    fn align(&mut self, render: &mut Vec<f32>, capture_frames: usize, channels: usize) {
+        let (Some(capture_end), Some(render_end)) = (self.capture.end_ns(), self.render.end_ns())
+        else {
+            return;
+        };
+        let render_frames = render.len() / channels;
+        let end_difference = (capture_end as i128 - render_end as i128) as f64;
+        let measured = end_difference * MIXER_SAMPLE_RATE as f64 / 1e9 - capture_frames as f64
+            + render_frames as f64;
+
+        let correction = if measured.abs() >= SYNC_REALIGN_FRAMES {
+            tracing::debug!(
+                "Stream sync: realigning sources {:.1}ms apart",
+                measured / (MIXER_SAMPLE_RATE as f64 / 1000.0)
+            );
+            self.capture.restart();
+            self.render.restart();
+            self.offset = Some(0.0);
+            measured.round() as i64
+        } else {
+            let offset = self
+                .offset
+                .map_or(measured, |o| o + (measured - o) * SYNC_SMOOTHING);
+            let (correction, corrected) = if offset > SYNC_TOLERANCE_FRAMES {
+                (1, offset - 1.0)
+            } else if offset < -SYNC_TOLERANCE_FRAMES {
+                (-1, offset + 1.0)
+            } else {
+                (0, offset)
+            };
+            self.offset = Some(corrected);
+            correction
+        };
+
+        if correction > 0 {
+            // Render audio older than any capture audio: drop it
+            let frames = (correction as usize).min(render_frames);
+            render.drain(..frames * channels);
+            self.dropped_frames += frames as u64;
+        } else if correction < 0 {
+            // Capture audio older than any render audio: repeat the head frame,
+            // or pad with silence when realigning
+            let frames = correction.unsigned_abs() as usize;
+            let padding = if frames == 1 {
+                render[..channels].to_vec()
+            } else {
+                vec![0.0; frames * channels]
+            };
+            render.splice(..0, padding);
+            self.inserted_frames += frames as u64;
+        }
+    }
+
+    fn metrics(&self) -> SyncMetrics {
+        let drift_ppm = match (self.render.rate(), self.capture.rate()) {
+            (Some(render), Some(capture)) if capture > 0.0 => {
+                Some(((render / capture - 1.0) * 1e6) as f32)
+            }
+            _ => None,
+        };
+        SyncMetrics {
+            drift_ppm,
+            offset_ms: (self.offset.unwrap_or(0.0) / (MIXER_SAMPLE_RATE as f64 / 1000.0)) as f32,
+            dropped_frames: self.dropped_frames,
+            inserted_frames: self.inserted_frames,
+        }
+    }
+}
+
+/// Convert a linear amplitude to dBFS, clamped to `LEVEL_FLOOR_DB`.
+fn to_db(amplitude: f32) -> f32 {
+    if amplitude > 0.0 {
+        (20.0 * amplitude.log10()).max(LEVEL_FLOOR_DB)
+    } else {
+        LEVEL_FLOOR_DB
+    }
+}
+
+/// Envelope resolution for delay estimation: 1ms at 48kHz
+const DELAY_BLOCK_FRAMES: usize = 48;
+
+/// Shortest overlap of both sources that gives a usable estimate (1s)
+const MIN_DELAY_BLOCKS: usize = 1000;
+
+/// Correlation below which no echo is considered found
+const MIN_DELAY_CORRELATION: f32 = 0.3;
+
+/// Estimates the echo delay by cross-correlating the level envelopes of
+/// system audio and microphone capture.
+///
+/// Each source is reduced to its mean absolute level per millisecond, and the
+/// lag of the microphone envelope that best matches the system envelope is
+/// the delay. Only works while audio is playing through the speakers.
+#[derive(Default)]
+struct DelayEstimator {
+    render: EnvelopeBuilder,
+    capture: EnvelopeBuilder,
+}
+
+impl DelayEstimator {
+    fn push(&mut self, samples: &[f32], is_render: bool, channels: u16) {
+        let envelope = if is_render {
+            &mut self.render
+        } else {
+            &mut self.capture
+        };
+        envelope.push(samples, channels as usize);
+    }
+
+    /// Lag in milliseconds at which the capture envelope best matches the render envelope.
+    fn estimate(&self) -> Result<u32, String> {
+        let len = self.render.blocks.len().min(self.capture.blocks.len());
+        if len < MIN_DELAY_BLOCKS {
+            return Err("Not enough audio from both sources to estimate the delay".to_string());
+        }
+        let render = remove_mean(&self.render.blocks[..len]);
+        let capture = remove_mean(&self.capture.blocks[..len]);
+        if render.iter().all(|&v| v.abs() < 1e-6) {
+            return Err(
+                "No system audio playing; play something through the speakers while estimating"
+                    .to_string(),
+            );
+        }
+
+        let max_lag = (MAX_AEC_DELAY_MS as usize).min(len / 2);
+        let (delay, correlation) = (0..=max_lag)
+            .map(|lag| {
+                let r = &render[..len - lag];
+                let c = &capture[lag..];
+                let dot: f32 = r.iter().zip(c).map(|(a, b)| a * b).sum();
+                let energy: f32 =
+                    r.iter().map(|a| a * a).sum::<f32>() * c.iter().map(|b| b * b).sum::<f32>();
+                let correlation = if energy > 0.0 {
+                    dot / energy.sqrt()
+                } else {
+                    0.0
+                };
+                (lag, correlation)
+            })
+            .fold((0, f32::MIN), |best, current| {
+                if current.1 > best.1 {
+                    current
+                } else {
+                    best
+                }
+            });
+
+        tracing::debug!(
+            "Echo delay estimate: {}ms (correlation {:.2})",
+            delay,
+            correlation
+        );
+        if correlation < MIN_DELAY_CORRELATION {
+            return Err("No echo of the system audio found at the microphone".to_string());
+        }
+        Ok(delay as u32)
+    }
+}
+
+/// Reduces interleaved audio to mean absolute level per `DELAY_BLOCK_FRAMES` frames.
+#[derive(Default)]
+struct EnvelopeBuilder {
+    blocks: Vec<f32>,
+    sum: f32,
+    count: usize,
+}
+
+impl EnvelopeBuilder {
+    fn push(&mut self, samples: &[f32], channels: usize) {
+        let block_samples = DELAY_BLOCK_FRAMES * channels.max(1);
+        for &sample in samples {
+            self.sum += sample.abs();
+            self.count += 1;
+            if self.count == block_samples {
+                self.blocks.push(self.sum / block_samples as f32);
+                self.sum = 0.0;
+                self.count = 0;
+            }
+        }
+    }
+}
+
+fn remove_mean(values: &[f32]) -> Vec<f32> {
+    let mean = values.iter().sum::<f32>() / values.len() as f32;
+    values.iter().map(|v| v - mean).collect()
+}
+
+/// Limit a mixed sample to (-1, 1). Samples above half scale are compressed
+/// exponentially, joining the linear range without a jump in level or slope.
+fn soft_clip(sum: f32) -> f32 {
+    if sum > 0.5 {
+        1.0 - (-2.0 * (sum - 0.5)).exp() * 0.5
+    } else if sum < -0.5 {
+        -1.0 + (-2.0 * (-sum - 0.5)).exp() * 0.5
+    } else {
+        sum
+    }
+}
+
+fn rms(samples: &[f32]) -> f32 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+}
+
+/// Route two interleaved streams to separate stereo channels.
+///
+/// Each frame of `primary` is downmixed to the left channel and each frame of
+/// `secondary` to the right channel. The result is always 2-channel.
+pub fn split_channels(primary: &[f32], secondary: &[f32], channels: u16) -> Vec<f32> {
+    let channels = channels.max(1) as usize;
+    primary
+        .chunks(channels)
+        .zip(secondary.chunks(channels))
+        .flat_map(|(p, s)| {
+            [
+                p.iter().sum::<f32>() / p.len() as f32,
+                s.iter().sum::<f32>() / s.len() as f32,
+            ]
+        })
+        .collect()
+}
+
+/// Convert mono audio to stereo by duplicating channels
+pub fn mono_to_stereo(mono: &[f32]) -> Vec<f32> {
+    let mut stereo = Vec::with_capacity(mono.len() * 2);
+    for &sample in mono {
+        stereo.push(sample);
+        stereo.push(sample);
+    }
+    stereo
+}
+
+/// Simple linear resampler
+pub struct Resampler {
+    source_rate: u32,
+    target_rate: u32,
+    buffer: Vec<f32>,
+    position: f64,
+}
+
+impl Resampler {
+    pub fn new(source_rate: u32, target_rate: u32) -> Self {
+        Self {
+            source_rate,
+            target_rate,
+            buffer: Vec::new(),
+            position: 0.0,
+        }
+    }
+
+    pub fn process(&mut self, samples: &[f32], channels: usize) -> Vec<f32> {
+        self.buffer.extend_from_slice(samples);
+
+        let ratio = self.source_rate as f64 / self.target_rate as f64;
+        let input_frames = self.buffer.len() / channels;
+        let output_frames = ((input_frames as f64 - self.position) / ratio) as usize;
+
+        if output_frames == 0 {
+            return Vec::new();
+        }
+
+        let mut output = Vec::with_capacity(output_frames * channels);
+
+        for _ in 0..output_frames {
+            let src_frame = self.position as usize;
+            let frac = self.position - src_frame as f64;
+
+            for ch in 0..channels {
+                let idx0 = src_frame * channels + ch;
+                let idx1 = (src_frame + 1) * channels + ch;
+
+                let sample = if idx1 < self.buffer.len() {
+                    self.buffer[idx0] * (1.0 - frac as f32) + self.buffer[idx1] * frac as f32
+                } else if idx0 < self.buffer.len() {
+                    self.buffer[idx0]
+                } else {
+                    0.0
+                };
+                output.push(sample);
+            }
+
+            self.position += ratio;
+        }
+
+        let consumed_frames = self.position as usize;
+        if consumed_frames > 0 {
+            let consumed_samples = consumed_frames * channels;
+            if consumed_samples < self.buffer.len() {
+                self.buffer.drain(0..consumed_samples);
+                self.position -= consumed_frames as f64;
+            } else {
+                self.buffer.clear();
+                self.position = 0.0;
+            }
+        }
+
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delay_estimator_finds_echo_lag() {
+        // Noise bursts switching on and off every 10-70ms
+        let mut seed = 7u32;
+        let mut next = || {
+            seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+            (seed >> 16) as f32 / 65536.0
+        };
+        let mut render = Vec::new();
+        while render.len() < 48000 * 3 {
+            let burst = (10.0 + next() * 60.0) as usize * 48;
+            let level = if next() > 0.5 { 0.5 } else { 0.0 };
+            render.extend((0..burst).map(|_| (next() - 0.5) * level));
+        }
+        let delay_samples = 120 * 48;
+        let capture: Vec<f32> = (0..render.len())
+            .map(|i| {
+                let echo = i.checked_sub(delay_samples).map_or(0.0, |j| render[j]);
+                echo * 0.3 + (next() - 0.5) * 0.01
+            })
+            .collect();
+
+        let mut estimator = DelayEstimator::default();
+        for (r, c) in render.chunks(480).zip(capture.chunks(480)) {
+            estimator.push(r, true, 1);
+            estimator.push(c, false, 1);
+        }
+        assert_eq!(estimator.estimate(), Ok(120));
+
+        let mut silent = DelayEstimator::default();
+        silent.push(&vec![0.0; 48000 * 2], true, 1);
+        silent.push(&capture[..48000 * 2], false, 1);
+        assert!(silent.estimate().is_err());
+    }
+
+    #[test]
+    fn test_stream_sync_realigns_and_follows_drift() {
+        // System audio starts 100ms ahead and its clock runs 500ppm fast: it
+        // delivers 480 frames every 9.995ms against the microphone's 10ms
+        const FRAMES: usize = 480;
+        let mut sync = StreamSync::default();
+        let mut render: Vec<f32> = Vec::new();
+        let mut capture_frames = 0;
+        let mut next_render = 0u64;
+        let render_ns = |j: u64| 900_000_000 + j * 9_995_000;
+
+        for k in 0..3000u64 {
+            let capture_ns = 1_000_000_000 + k * 10_000_000;
+            sync.push(capture_ns, FRAMES, false);
+            capture_frames += FRAMES;
+            while render_ns(next_render) <= capture_ns {
+                sync.push(render_ns(next_render), FRAMES, true);
+                render.extend(std::iter::repeat_n(0.1, FRAMES));
+                next_render += 1;
+            }
+
+            while capture_frames >= FRAMES && render.len() >= FRAMES {
+                sync.align(&mut render, capture_frames, 1);
+                if render.len() < FRAMES {
+                    break;
+                }
+                render.drain(..FRAMES);
+                capture_frames -= FRAMES;
+            }
+        }
+
+        let metrics = sync.metrics();
+        let drift = metrics.drift_ppm.unwrap();
+        assert!((drift - 500.0).abs() < 10.0, "drift {}", drift);
+        assert!(
+            metrics.offset_ms.abs() < 2.0,
+            "offset {}",
+            metrics.offset_ms
+        );
+        // The 100ms head start plus 30s of drift (about 720 frames)
+        assert!(metrics.dropped_frames > 4800 + 600);
+        assert_eq!(metrics.inserted_frames, 0);
+    }
+
+    fn mixed(level: f32) -> MixedSamples {
+        MixedSamples {
+            samples: vec![level; 4],
+            channels: 2,
+            sample_rate: MIXER_SAMPLE_RATE,
+            sources: None,
+        }
+    }
+
+    #[test]
+    fn test_mixed_queue_times_out_when_empty() {
+        let (mut tx, mut rx) = mixed_queue();
+        let start = Instant::now();
+        assert!(rx.recv_timeout(Duration::from_millis(30)).is_none());
+        assert!(start.elapsed() >= Duration::from_millis(30));
+        assert!(!rx.signal.waiting.load(Ordering::SeqCst));
+
+        // Audio already queued is taken without waiting
+        assert!(tx.push(mixed(0.25)).is_ok());
+        let start = Instant::now();
+        let queued = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert!(start.elapsed() < Duration::from_secs(1));
+        assert_eq!(queued.samples, vec![0.25; 4]);
+    }
+
+    #[test]
+    fn test_mixed_queue_wakes_waiting_receiver() {
+        let (mut tx, mut rx) = mixed_queue();
+
+        let sender = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(20));
+            assert!(tx.push(mixed(0.5)).is_ok());
+        });
+        let start = Instant::now();
+        let woken = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert!(start.elapsed() < Duration::from_secs(1));
+        assert_eq!(woken.samples, vec![0.5; 4]);
+        assert!(!rx.signal.waiting.load(Ordering::SeqCst));
+        sender.join().unwrap();
+    }
+
+    #[test]
+    fn test_split_channels_routes_sources_left_and_right() {
+        let mic = [0.2, 0.4, 0.6, 0.8];
+        let system = [-0.5, -0.5, 0.0, 1.0];
+        let out = split_channels(&mic, &system, 2);
+        assert_eq!(out.len(), 4);
+        assert!((out[0] - 0.3).abs() < 1e-6);
+        assert!((out[1] + 0.5).abs() < 1e-6);
+        assert!((out[2] - 0.7).abs() < 1e-6);
+        assert!((out[3] - 0.5).abs() < 1e-6);
+
+        // Mono sources each fill one output channel
+        assert_eq!(
+            split_channels(&[0.1, 0.2], &[0.3, 0.4], 1),
+            vec![0.1, 0.3, 0.2, 0.4]
+        );
+    }
+
+    #[test]
+    fn test_selected_channels_are_downmixed() {
+        set_channel_selections(BTreeMap::from([
+            (
+                "interface".to_string(),
+                ChannelSelection {
+                    channels: vec![3, 4, 9],
+                },
+            ),
+            ("headset".to_string(), ChannelSelection::default()),
+        ]));
+        assert_eq!(selected_channels("interface", 8), Some(vec![2, 3]));
+        assert_eq!(selected_channels("interface", 2), None);
+        assert_eq!(selected_channels("headset", 8), None);
+        assert_eq!(selected_channels("other", 8), None);
+        set_channel_selections(BTreeMap::new());
+
+        // Two 4-channel frames; channels 3 and 4 (indices 2 and 3) averaged
+        let samples = [0.0, 0.0, 0.2, 0.4, 1.0, 1.0, -0.5, 0.1];
+        let stereo = downmix_channels(&samples, 4, &[2, 3]);
+        let expected = [0.3, 0.3, -0.2, -0.2];
+        for (out, want) in stereo.iter().zip(expected) {
+            assert!((out - want).abs() < 1e-6, "{:?}", stereo);
+        }
+        assert_eq!(
+            downmix_channels(&samples, 4, &[0]),
+            vec![0.0, 0.0, 1.0, 1.0]
+        );
+    }
+
+    #[test]
+    fn test_speech_capture_needs_a_single_source() {
+        set_capture_sample_rate(Some(SPEECH_SAMPLE_RATE));
+        assert!(speech_capture(Some("mic"), None));
+        assert!(speech_capture(None, Some("mic")));
+        assert!(!speech_capture(Some("mic"), Some("monitor")));
+        set_capture_sample_rate(Some(MIXER_SAMPLE_RATE));
+        assert!(!speech_capture(Some("mic"), None));
+        set_capture_sample_rate(None);
+    }
+
+    #[test]
+    fn test_soft_clip() {
+        assert_eq!(soft_clip(0.5), 0.5);
+        assert!(soft_clip(3.0) < 1.0 && soft_clip(3.0) > 0.99);
+        assert!(soft_clip(-3.0) > -1.0 && soft_clip(-3.0) < -0.99);
+        // No jump where compression starts
+        assert!((soft_clip(0.5001) - 0.5001).abs() < 1e-4);
+        assert!((soft_clip(-0.5001) + 0.5001).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_level_meter() {
+        let mut meter = LevelMeter::default();
+        assert_eq!(meter.take(), None);
+
+        meter.add(&[0.5, -0.5, 0.5, -1.0]);
+        let level = meter.take().unwrap();
+        assert!((level.peak_db - 0.0).abs() < 1e-4);
+        assert!((level.rms_db - 20.0 * 0.4375f32.sqrt().log10()).abs() < 1e-4);
+
+        meter.add(&[0.0; 8]);
+        assert_eq!(meter.take().unwrap().rms_db, LEVEL_FLOOR_DB);
+    }
+
+    #[test]
+    fn test_resampler_ratio() {
+        let mut resampler = Resampler::new(44100, MIXER_SAMPLE_RATE);
+        let input = vec![0.25f32; 44100 * 2];
+        let output = resampler.process(&input, 2);
+        let frames = output.len() / 2;
+        assert!((47990..=48000).contains(&frames), "got {} frames", frames);
+        assert!(output.iter().all(|s| (s - 0.25).abs() < 1e-6));
+    }
+}
@@ -0,0 +1,413 @@
+//! Registration of the background service to start at login.
+//!
+//! - Linux: a systemd user unit, enabled for the default target
+//! - macOS: a LaunchAgent, which launchd loads at login
+//! - Windows: a `Run` entry in the user's registry hive, or with `--system`
+//!   a service under the Service Control Manager, started at boot
+//!
+//! Without a terminal, the service's output is only found in the rotating
+//! log files it writes to the platform data directory.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use flowstt_common::paths;
+use serde::Serialize;
+
+use crate::client::get_service_path;
+
+/// Name of the systemd user unit
+#[cfg(target_os = "linux")]
+const UNIT_NAME: &str = "flowstt.service";
+
+/// Label of the LaunchAgent
+#[cfg(any(target_os = "macos", test))]
+const AGENT_LABEL: &str = "com.flowstt.service";
+
+/// Registry key of programs started at login
+#[cfg(windows)]
+const RUN_KEY: &str = r"HKCU\Software\Microsoft\Windows\CurrentVersion\Run";
+
+/// Value name of the Run entry
+#[cfg(windows)]
+const RUN_VALUE: &str = "FlowSTT";
+
+/// Name of the Windows service, matching the service executable
+#[cfg(windows)]
+const SERVICE_NAME: &str = "FlowSTT";
+
+/// Whether and how the service is set up to start at login.
+#[derive(Debug, Serialize)]
+pub struct AutostartStatus {
+    /// Whether the service is registered
+    pub installed: bool,
+    /// Unit file, LaunchAgent plist or registry key of the registration
+    pub location: String,
+    /// Service executable started at login
+    pub service_path: String,
+    /// Directory the service writes its log files to
+    pub log_dir: String,
+    /// Whether the service is registered as a Windows system service
+    pub system_service: bool,
+}
+
+/// Register the service to start at login.
+pub fn install() -> Result<AutostartStatus, String> {
+    let service_path = service_executable()?;
+    register(&service_path)?;
+    Ok(status())
+}
+
+/// Remove the login registration. Returns whether one existed.
+pub fn uninstall() -> Result<bool, String> {
+    let installed = status().installed;
+    if installed {
+        unregister()?;
+    }
+    Ok(installed)
+}
+
+/// Current login registration.
+pub fn status() -> AutostartStatus {
+    AutostartStatus {
+        installed: is_registered(),
+        location: registration_location(),
+        service_path: get_service_path().to_string_lossy().to_string(),
+        log_dir: paths::log_dir().to_string_lossy().to_string(),
+        system_service: is_system_service(),
+    }
+}
+
+/// Register the service with the Windows Service Control Manager, to start at
+/// boot as LocalSystem. Needs an elevated prompt.
+#[cfg(windows)]
+pub fn install_system() -> Result<AutostartStatus, String> {
+    let service_path = service_executable()?;
+    let bin_path = windows_command(&service_path, &["--windows-service"]);
+    run(
+        "sc.exe",
+        &[
+            "create",
+            SERVICE_NAME,
+            "binPath=",
+            &bin_path,
+            "start=",
+            "auto",
+            "DisplayName=",
+            "FlowSTT",
+        ],
+    )?;
+    let _ = run(
+        "sc.exe",
+        &[
+            "description",
+            SERVICE_NAME,
+            "FlowSTT voice transcription service",
+        ],
+    );
+    Ok(status())
+}
+
+#[cfg(not(windows))]
+pub fn install_system() -> Result<AutostartStatus, String> {
+    Err("System services are only supported on Windows".into())
+}
+
+/// Stop and remove the Windows system service. Returns whether one existed.
+#[cfg(windows)]
+pub fn uninstall_system() -> Result<bool, String> {
+    if !is_system_service() {
+        return Ok(false);
+    }
+    // Fails if the service isn't running, which is fine
+    let _ = run("sc.exe", &["stop", SERVICE_NAME]);
+    run("sc.exe", &["delete", SERVICE_NAME])?;
+    Ok(true)
+}
+
+#[cfg(not(windows))]
+pub fn uninstall_system() -> Result<bool, String> {
+    Err("System services are only supported on Windows".into())
+}
+
+#[cfg(windows)]
+fn is_system_service() -> bool {
+    run("sc.exe", &["query", SERVICE_NAME]).is_ok()
+}
+
+#[cfg(not(windows))]
+fn is_system_service() -> bool {
+    false
+}
+
+/// Absolute path of the service executable next to the CLI.
+fn service_executable() -> Result<PathBuf, String> {
+    let path = get_service_path();
+    if !path.is_absolute() {
+        return Err(format!(
+            "{} was not found next to the flowstt executable",
+            path.display()
+        ));
+    }
+    Ok(path)
+}
+
+/// Run a command, failing with its error output if it doesn't succeed.
+fn run(program: &str, args: &[&str]) -> Result<(), String> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "{} {} failed: {}",
+            program,
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn unit_path() -> PathBuf {
+    directories::BaseDirs::new()
+        .map(|d| d.config_dir().to_path_buf())
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("systemd")
+        .join("user")
+        .join(UNIT_NAME)
+}
+
+/// Systemd user unit starting the service at `service_path`.
+#[cfg(any(target_os = "linux", test))]
+fn unit_file(service_path: &Path) -> String {
+    format!(
+        "[Unit]\n\
+         Description=FlowSTT voice transcription service\n\
+         \n\
+         [Service]\n\
+         ExecStart={}\n\
+         Restart=on-failure\n\
+         \n\
+         [Install]\n\
+         WantedBy=default.target\n",
+        systemd_quote(service_path)
+    )
+}
+
+/// Quote a path for `ExecStart=`: within quotes backslashes and quotes are
+/// escaped, and outside of them `%` starts a specifier and `$` a variable.
+#[cfg(any(target_os = "linux", test))]
+fn systemd_quote(path: &Path) -> String {
+    let escaped = path
+        .to_string_lossy()
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('%', "%%")
+        .replace('$', "$$");
+    format!("\"{}\"", escaped)
+}
+
+#[cfg(target_os = "linux")]
+fn register(service_path: &Path) -> Result<(), String> {
+    let unit = unit_file(service_path);
+    let path = unit_path();
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create directory: {}", e))?;
+    }
+    std::fs::write(&path, unit)
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+
+    run("systemctl", &["--user", "daemon-reload"])?;
+    run("systemctl", &["--user", "enable", UNIT_NAME])
+}
+
+#[cfg(target_os = "linux")]
+fn unregister() -> Result<(), String> {
+    run("systemctl", &["--user", "disable", UNIT_NAME])?;
+    let path = unit_path();
+    std::fs::remove_file(&path)
+        .map_err(|e| format!("Failed to remove {}: {}", path.display(), e))?;
+    run("systemctl", &["--user", "daemon-reload"])
+}
+
+#[cfg(target_os = "linux")]
+fn is_registered() -> bool {
+    unit_path().exists()
+        && run("systemctl", &["--user", "is-enabled", "--quiet", UNIT_NAME]).is_ok()
+}
+
+#[cfg(target_os = "linux")]
+fn registration_location() -> String {
+    unit_path().to_string_lossy().to_string()
+}
+
+#[cfg(target_os = "macos")]
+fn plist_path() -> PathBuf {
+    directories::BaseDirs::new()
+        .map(|d| d.home_dir().to_path_buf())
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("Library")
+        .join("LaunchAgents")
+        .join(format!("{}.plist", AGENT_LABEL))
+}
+
+/// LaunchAgent starting the service at `service_path`, with its error output
+/// going to `stderr_path`.
+#[cfg(any(target_os = "macos", test))]
+fn launch_agent(service_path: &Path, stderr_path: &Path) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{label}</string>
+    <key>ProgramArguments</key>
+    <array>
+        <string>{service}</string>
+    </array>
+    <key>RunAtLoad</key>
+    <true/>
+    <key>KeepAlive</key>
+    <dict>
+        <key>SuccessfulExit</key>
+        <false/>
+    </dict>
+    <key>StandardErrorPath</key>
+    <string>{log}</string>
+</dict>
+</plist>
+"#,
+        label = AGENT_LABEL,
+        service = xml_escape(service_path),
+        log = xml_escape(stderr_path)
+    )
+}
+
+/// Escape a path for the text of a plist element.
+#[cfg(any(target_os = "macos", test))]
+fn xml_escape(path: &Path) -> String {
+    path.to_string_lossy()
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(target_os = "macos")]
+fn register(service_path: &Path) -> Result<(), String> {
+    // launchd captures stderr here, which includes panic messages
+    let stderr_path = paths::log_dir().join("launchd.log");
+    std::fs::create_dir_all(paths::log_dir())
+        .map_err(|e| format!("Failed to create log directory: {}", e))?;
+    let plist = launch_agent(service_path, &stderr_path);
+    let path = plist_path();
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create directory: {}", e))?;
+    }
+    // launchd loads agents from this directory at the next login
+    std::fs::write(&path, plist).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+#[cfg(target_os = "macos")]
+fn unregister() -> Result<(), String> {
+    let path = plist_path();
+    // Not loaded unless the user logged in again since installing
+    let _ = run("launchctl", &["unload", &path.to_string_lossy()]);
+    std::fs::remove_file(&path).map_err(|e| format!("Failed to remove {}: {}", path.display(), e))
+}
+
+#[cfg(target_os = "macos")]
+fn is_registered() -> bool {
+    plist_path().exists()
+}
+
+#[cfg(target_os = "macos")]
+fn registration_location() -> String {
+    plist_path().to_string_lossy().to_string()
+}
+
+/// Command line running the executable at `path` with `args`, quoted for the
+/// registry and the Service Control Manager. Windows paths can't contain
+/// quotes, so quoting the path keeps its spaces in it.
+#[cfg(any(windows, test))]
+fn windows_command(path: &Path, args: &[&str]) -> String {
+    let mut command = format!("\"{}\"", path.display());
+    for arg in args {
+        command.push(' ');
+        command.push_str(arg);
+    }
+    command
+}
+
+#[cfg(windows)]
+fn register(service_path: &Path) -> Result<(), String> {
+    let command = windows_command(service_path, &[]);
+    run(
+        "reg",
+        &[
+            "add", RUN_KEY, "/v", RUN_VALUE, "/t", "REG_SZ", "/d", &command, "/f",
+        ],
+    )
+}
+
+#[cfg(windows)]
+fn unregister() -> Result<(), String> {
+    run("reg", &["delete", RUN_KEY, "/v", RUN_VALUE, "/f"])
+}
+
+#[cfg(windows)]
+fn is_registered() -> bool {
+    run("reg", &["query", RUN_KEY, "/v", RUN_VALUE]).is_ok()
+}
+
+#[cfg(windows)]
+fn registration_location() -> String {
+    format!(r"{}\{}", RUN_KEY, RUN_VALUE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unit_quotes_the_service_path() {
+        let unit = unit_file(Path::new("/home/ana maria/bin/flowstt-service"));
+        assert!(unit.contains("\nExecStart=\"/home/ana maria/bin/flowstt-service\"\n"));
+        assert!(unit.ends_with("WantedBy=default.target\n"));
+
+        assert_eq!(
+            systemd_quote(Path::new(r#"/opt/a "b"\c/50%$HOME/flowstt-service"#)),
+            r#""/opt/a \"b\"\\c/50%%$$HOME/flowstt-service""#
+        );
+    }
+
+    #[test]
+    fn test_launch_agent_escapes_paths() {
+        let plist = launch_agent(
+            Path::new("/Applications/Flow & STT.app/Contents/MacOS/flowstt-service"),
+            Path::new("/Users/ana maria/Library/Logs/<flowstt>/launchd.log"),
+        );
+        assert!(plist.contains(
+            "<string>/Applications/Flow &amp; STT.app/Contents/MacOS/flowstt-service</string>"
+        ));
+        assert!(plist.contains(
+            "<string>/Users/ana maria/Library/Logs/&lt;flowstt&gt;/launchd.log</string>"
+        ));
+        assert!(plist.contains(&format!("<string>{}</string>", AGENT_LABEL)));
+    }
+
+    #[test]
+    fn test_windows_command_quotes_the_path() {
+        let path = Path::new(r"C:\Program Files\FlowSTT\flowstt-service.exe");
+        assert_eq!(
+            windows_command(path, &[]),
+            r#""C:\Program Files\FlowSTT\flowstt-service.exe""#
+        );
+        assert_eq!(
+            windows_command(path, &["--windows-service"]),
+            r#""C:\Program Files\FlowSTT\flowstt-service.exe" --windows-service"#
+        );
+    }
+}
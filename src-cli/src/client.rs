@@ -1,6 +1,9 @@
 //! IPC client for communicating with the FlowSTT service.
 
-use flowstt_common::ipc::{get_socket_path, read_json, write_json, IpcError, Request, Response};
+use flowstt_common::ipc::{
+    get_socket_path, read_json, write_json, Capability, EventType, IpcError, Request, Response,
+    MIN_PROTOCOL_VERSION, PROTOCOL_VERSION,
+};
 use std::path::PathBuf;
 use std::process::{Child, Command, Stdio};
 use std::time::Duration;
@@ -11,12 +14,17 @@ pub struct Client {
     stream: Option<tokio::net::UnixStream>,
     #[cfg(windows)]
     stream: Option<tokio::net::windows::named_pipe::NamedPipeClient>,
+    /// Optional features of the connected service
+    capabilities: Vec<Capability>,
 }
 
 impl Client {
     /// Create a new client (not connected).
     pub fn new() -> Self {
-        Self { stream: None }
+        Self {
+            stream: None,
+            capabilities: Vec::new(),
+        }
     }
 
     /// Connect to the service.
@@ -40,9 +48,40 @@ impl Client {
             self.stream = Some(stream);
         }
 
+        if let Err(e) = self.handshake().await {
+            self.stream = None;
+            return Err(e);
+        }
         Ok(())
     }
 
+    /// Agree on a protocol version; the service refuses other requests until then.
+    async fn handshake(&mut self) -> Result<(), IpcError> {
+        let hello = Request::Hello {
+            protocol_version: PROTOCOL_VERSION,
+        };
+        match self.request(hello).await? {
+            Response::Welcome {
+                protocol_version,
+                capabilities,
+                ..
+            } if protocol_version >= MIN_PROTOCOL_VERSION => {
+                self.capabilities = capabilities;
+                Ok(())
+            }
+            Response::Welcome {
+                protocol_version,
+                service_version,
+                ..
+            } => Err(IpcError::ParseError(format!(
+                "Service {} speaks protocol version {}, which is no longer supported",
+                service_version, protocol_version
+            ))),
+            Response::Error { message, .. } => Err(IpcError::ParseError(message)),
+            _ => Err(IpcError::ParseError("Unexpected handshake response".into())),
+        }
+    }
+
     /// Check if the service is running.
     #[allow(dead_code)]
     pub async fn is_service_running() -> bool {
@@ -74,8 +113,20 @@ impl Client {
         ))
     }
 
+    /// Whether the connected service supports `capability`.
+    pub fn supports(&self, capability: Capability) -> bool {
+        self.capabilities.contains(&capability)
+    }
+
     /// Send a request and receive a response.
     pub async fn request(&mut self, request: Request) -> Result<Response, IpcError> {
+        // Don't send what the service wouldn't understand
+        if let Some(capability) = Capability::required_by(&request) {
+            if !self.supports(capability) {
+                return Err(IpcError::Unsupported(capability));
+            }
+        }
+
         #[cfg(unix)]
         {
             let stream = self
@@ -99,18 +150,75 @@ impl Client {
         }
     }
 
+    /// Read the next message sent by the service.
+    async fn read_response(&mut self) -> Result<Response, IpcError> {
+        #[cfg(unix)]
+        {
+            let stream = self
+                .stream
+                .as_mut()
+                .ok_or_else(|| IpcError::ParseError("Not connected".into()))?;
+            let (mut reader, _) = stream.split();
+            read_json(&mut reader).await
+        }
+
+        #[cfg(windows)]
+        {
+            let stream = self
+                .stream
+                .as_mut()
+                .ok_or_else(|| IpcError::ParseError("Not connected".into()))?;
+            let (mut reader, _) = tokio::io::split(stream);
+            read_json(&mut reader).await
+        }
+    }
+
+    /// Subscribe to service events, turning this connection into an event stream.
+    ///
+    /// The connection can't be used for requests afterwards.
+    pub async fn subscribe(mut self) -> Result<EventStream, IpcError> {
+        match self.request(Request::SubscribeEvents).await? {
+            Response::Subscribed => Ok(EventStream { client: self }),
+            Response::Error { message, .. } => Err(IpcError::ParseError(message)),
+            _ => Err(IpcError::ParseError("Failed to subscribe to events".into())),
+        }
+    }
+
     /// Ping the service.
     pub async fn ping(&mut self) -> Result<bool, IpcError> {
         match self.request(Request::Ping).await? {
             Response::Pong => Ok(true),
-            Response::Error { message } => Err(IpcError::ParseError(message)),
+            Response::Error { message, .. } => Err(IpcError::ParseError(message)),
             _ => Err(IpcError::ParseError("Unexpected response".into())),
         }
     }
 }
 
+/// Stream of events pushed by the service after subscribing.
+pub struct EventStream {
+    client: Client,
+}
+
+impl EventStream {
+    /// Wait for the next event.
+    ///
+    /// Returns `None` once the service closes the connection.
+    pub async fn next(&mut self) -> Option<Result<EventType, IpcError>> {
+        loop {
+            match self.client.read_response().await {
+                Ok(Response::Event { event }) => return Some(Ok(event)),
+                Ok(_) => {
+                    // Ignore other responses in event stream
+                }
+                Err(IpcError::ConnectionClosed) => return None,
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
 /// Get the path to the service executable.
-fn get_service_path() -> PathBuf {
+pub fn get_service_path() -> PathBuf {
     // Try to find the service binary next to the CLI binary
     if let Ok(exe_path) = std::env::current_exe() {
         if let Some(dir) = exe_path.parent() {
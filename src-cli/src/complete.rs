@@ -0,0 +1,183 @@
+//! Shell completion.
+//!
+//! Completion is answered by `flowstt` itself: the script a shell sources
+//! (`COMPLETE=bash flowstt`, or `zsh` or `fish`) calls back into the binary
+//! with the words typed so far, so subcommands and options come from the
+//! same definitions as parsing. Device and session IDs are completed from a
+//! running service; when none is running they aren't offered, and completion
+//! never starts one.
+
+use std::ffi::OsStr;
+use std::time::Duration;
+
+use clap_complete::engine::{CompletionCandidate, PathCompleter, ValueCompleter};
+use flowstt_common::ipc::{Request, Response};
+use flowstt_common::SessionFilter;
+
+use crate::client::Client;
+
+/// Longest wait for the service while completing, so a busy service doesn't
+/// stall the shell
+const SERVICE_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Most recent sessions offered when completing session IDs
+const SESSION_LIMIT: usize = 50;
+
+/// IDs of the service's audio devices, described by their names.
+pub fn device_ids(current: &OsStr) -> Vec<CompletionCandidate> {
+    let Some(prefix) = current.to_str() else {
+        return Vec::new();
+    };
+    match request(Request::ListDevices { source_type: None }) {
+        Some(Response::Devices { devices }) => devices
+            .into_iter()
+            .filter(|device| device.id.starts_with(prefix))
+            .map(|device| CompletionCandidate::new(device.id).help(Some(device.name.into())))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// IDs of recent saved transcriptions, described by their titles or text.
+pub fn session_ids(current: &OsStr) -> Vec<CompletionCandidate> {
+    let Some(prefix) = current.to_str() else {
+        return Vec::new();
+    };
+    let list = Request::ListSessions {
+        limit: Some(SESSION_LIMIT),
+        filter: SessionFilter::default(),
+    };
+    match request(list) {
+        Some(Response::Sessions { sessions }) => sessions
+            .into_iter()
+            .filter(|session| session.id.starts_with(prefix))
+            .map(|session| {
+                let help = session.title.unwrap_or(session.preview);
+                CompletionCandidate::new(session.id).help(Some(help.into()))
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Session IDs, then files, for arguments that take either.
+pub fn session_ids_or_files(current: &OsStr) -> Vec<CompletionCandidate> {
+    let mut candidates = session_ids(current);
+    candidates.extend(PathCompleter::file().complete(current));
+    candidates
+}
+
+/// Send one request to a running service, giving up after `SERVICE_TIMEOUT`.
+///
+/// Completion runs before the CLI's runtime starts, so it gets its own.
+fn request(request: Request) -> Option<Response> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .ok()?;
+    runtime.block_on(async {
+        tokio::time::timeout(SERVICE_TIMEOUT, async {
+            let mut client = Client::new();
+            client.connect().await.ok()?;
+            client.request(request).await.ok()
+        })
+        .await
+        .ok()
+        .flatten()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use clap::CommandFactory;
+    use clap_complete::env::{Bash, EnvCompleter, Fish, Zsh};
+
+    use crate::Cli;
+
+    /// Bash and zsh read the index of the word being completed from the
+    /// environment, which the tests share
+    static ENV: Mutex<()> = Mutex::new(());
+
+    /// The registration script `shell` sources, and what it is offered after
+    /// `flowstt` followed by `words`.
+    fn complete(shell: &dyn EnvCompleter, words: &[&str]) -> (String, String) {
+        let mut registration = Vec::new();
+        shell
+            .write_registration(
+                "COMPLETE",
+                "flowstt",
+                "flowstt",
+                "flowstt",
+                &mut registration,
+            )
+            .unwrap();
+
+        let mut args = vec!["flowstt".into()];
+        args.extend(words.iter().map(Into::into));
+        args.push("".into());
+        let mut candidates = Vec::new();
+        {
+            let _env = ENV.lock().unwrap();
+            std::env::set_var("_CLAP_COMPLETE_INDEX", (args.len() - 1).to_string());
+            shell
+                .write_complete(&mut Cli::command(), args, None, &mut candidates)
+                .unwrap();
+        }
+        (
+            String::from_utf8(registration).unwrap(),
+            String::from_utf8(candidates).unwrap(),
+        )
+    }
+
+    /// Check that `shell` is registered for `flowstt` and offers every
+    /// subcommand, at the top level and under `history`.
+    fn check_subcommands(shell: &dyn EnvCompleter) {
+        let (registration, _) = complete(shell, &[]);
+        assert!(registration.contains("flowstt"), "{}", registration);
+        // Calls back into flowstt, quoting the shell's name or not
+        assert!(
+            registration
+                .replace('"', "")
+                .contains(&format!("COMPLETE={}", shell.name())),
+            "{}",
+            registration
+        );
+
+        let command = Cli::command();
+        let history = command.find_subcommand("history").unwrap();
+        for (words, parent) in [(&[][..], &command), (&["history"][..], history)] {
+            let (_, offered) = complete(shell, words);
+            let offered: Vec<&str> = offered
+                .lines()
+                .map(|line| line.split(['\t', ':']).next().unwrap())
+                .collect();
+            for subcommand in parent.get_subcommands().filter(|s| !s.is_hide_set()) {
+                assert!(
+                    offered.contains(&subcommand.get_name()),
+                    "{} doesn't offer {:?} after {:?}: {:?}",
+                    shell.name(),
+                    subcommand.get_name(),
+                    words,
+                    offered
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_bash_completes_subcommands() {
+        check_subcommands(&Bash);
+    }
+
+    #[test]
+    fn test_zsh_completes_subcommands() {
+        check_subcommands(&Zsh);
+    }
+
+    #[test]
+    fn test_fish_completes_subcommands() {
+        check_subcommands(&Fish);
+    }
+}
@@ -0,0 +1,73 @@
+//! Reading the service's log files.
+//!
+//! The service writes daily rotating files to [`paths::log_dir`]; these are
+//! read directly, so they can be inspected even when the service won't start.
+
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use flowstt_common::paths;
+
+/// How often a followed log is checked for new output
+const FOLLOW_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Print the last `lines` lines of the active log, then keep printing new
+/// output if `follow` is set.
+pub async fn show(lines: usize, follow: bool) -> Result<(), String> {
+    let mut path = active_log()?;
+    let contents =
+        std::fs::read(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let text = String::from_utf8_lossy(&contents);
+    let tail: Vec<&str> = text.lines().rev().take(lines).collect();
+    for line in tail.iter().rev() {
+        println!("{}", line);
+    }
+    if !follow {
+        return Ok(());
+    }
+
+    let mut offset = contents.len() as u64;
+    loop {
+        tokio::time::sleep(FOLLOW_INTERVAL).await;
+
+        // Continue with the next day's file once the service rotates
+        if let Ok(active) = active_log() {
+            if active != path {
+                path = active;
+                offset = 0;
+            }
+        }
+        offset = print_new_output(&path, offset)?;
+    }
+}
+
+fn active_log() -> Result<PathBuf, String> {
+    paths::active_service_log().ok_or_else(|| {
+        format!(
+            "No service log found in {}",
+            paths::log_dir().to_string_lossy()
+        )
+    })
+}
+
+/// Print everything written to `path` after `offset`, returning the new end.
+fn print_new_output(path: &Path, offset: u64) -> Result<u64, String> {
+    let mut file = std::fs::File::open(path)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let len = file.metadata().map(|m| m.len()).unwrap_or(0);
+    // Start over if the file was truncated
+    let offset = if len < offset { 0 } else { offset };
+    if len == offset {
+        return Ok(offset);
+    }
+
+    let mut new_output = Vec::new();
+    file.seek(SeekFrom::Start(offset))
+        .and_then(|_| file.read_to_end(&mut new_output))
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let mut stdout = std::io::stdout();
+    let _ = stdout.write_all(&new_output);
+    let _ = stdout.flush();
+    Ok(offset + new_output.len() as u64)
+}
@@ -3,14 +3,35 @@
 //! This is the command-line interface for FlowSTT voice transcription.
 //! It communicates with the background service via IPC.
 
+mod autostart;
 mod client;
+mod complete;
+mod logs;
+mod pipe;
+mod tui;
 
-use clap::{Parser, Subcommand, ValueEnum};
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use clap_complete::{ArgValueCompleter, CompleteEnv};
 use colored::Colorize;
-use flowstt_common::ipc::{Request, Response};
-use flowstt_common::{AudioSourceType, RecordingMode};
+use flowstt_common::export::{self, ExportFormat};
+use flowstt_common::ipc::{Capability, ErrorCode, EventType, Request, Response};
+use flowstt_common::{
+    AudioFault, AudioLevel, AudioSourceType, ChannelSelection, DateRange, HookTarget, HotkeyAction,
+    HotkeyBinding, JobState, KeyCode, LatencyPreset, OutputMode, Permission, PermissionState,
+    PlaybackStatus, Profile, RecordingMode, RetranscribeJob, SearchHit, SessionFilter, SessionHook,
+    SourceLabel, SourceSegment, TranscriptionMode,
+};
 
-use client::Client;
+use client::{Client, EventStream};
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Level shown as an empty meter (dBFS)
+const METER_FLOOR_DB: f32 = -60.0;
+
+/// Width of the level meter in characters
+const METER_WIDTH: usize = 40;
 
 #[derive(Parser)]
 #[command(name = "flowstt")]
@@ -30,6 +51,10 @@ struct Cli {
     #[arg(short, long)]
     verbose: bool,
 
+    /// Take control of capture from another client (such as the GUI) first
+    #[arg(long, global = true)]
+    take_control: bool,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -48,32 +73,118 @@ enum Commands {
         /// Filter by source type
         #[arg(short, long)]
         source: Option<SourceFilter>,
+
+        /// Print only device IDs, one per line, for scripts
+        #[arg(long)]
+        ids: bool,
     },
 
     /// Start transcription
     Transcribe {
         /// Primary audio source ID (use 'list' to see available devices)
-        #[arg(short = '1', long)]
+        #[arg(short = '1', long, add = ArgValueCompleter::new(complete::device_ids))]
         source1: Option<String>,
 
         /// Secondary audio source ID for mixing or AEC
-        #[arg(short = '2', long)]
+        #[arg(short = '2', long, add = ArgValueCompleter::new(complete::device_ids))]
         source2: Option<String>,
 
+        /// Channels of the primary source's device to capture, e.g. 3, or 3+4
+        /// to downmix two; "all" captures its own channels. Remembered for
+        /// the device
+        #[arg(long, value_parser = ChannelSelection::parse)]
+        channels1: Option<ChannelSelection>,
+
+        /// Channels of the secondary source's device to capture
+        #[arg(long, value_parser = ChannelSelection::parse)]
+        channels2: Option<ChannelSelection>,
+
         /// Enable acoustic echo cancellation
         #[arg(long)]
         aec: bool,
 
-        /// Recording mode (mix or echo-cancel)
+        /// Recording mode (mixed, echo-cancel or split-channels)
         #[arg(short, long, default_value = "mixed")]
         mode: RecordingModeArg,
+
+        /// Transcription language code (e.g. "en", "de"), or "auto" to detect
+        #[arg(short, long)]
+        language: Option<String>,
+
+        /// Also translate non-English speech to English (needs a multilingual model)
+        #[arg(long)]
+        translate: bool,
+
+        /// Trade latency against accuracy; kept for later sessions
+        #[arg(long, value_enum)]
+        latency: Option<LatencyPresetArg>,
+
+        /// Type transcriptions into the focused application
+        #[arg(long = "type")]
+        type_output: bool,
+
+        /// Label transcriptions with speaker IDs (speaker_1, speaker_2, ...)
+        #[arg(long)]
+        diarize: bool,
+
+        /// Also save each source's raw capture (before echo cancellation) as separate WAV files
+        #[arg(long)]
+        record_sources: bool,
+
+        /// Save the captured audio only while speech is detected, with a little
+        /// audio before and after each utterance (see the vad_recording settings)
+        #[arg(long)]
+        vad_record: bool,
+
+        /// Transcribe audio piped to stdin (WAV, or raw 16-bit PCM) instead of
+        /// capturing, printing each transcript line to stdout
+        #[arg(long, conflicts_with_all = ["source1", "source2"])]
+        stdin: bool,
+
+        /// Sample rate of raw PCM on stdin
+        #[arg(long, default_value_t = 16000, requires = "stdin")]
+        rate: u32,
+
+        /// Channels of raw PCM on stdin
+        #[arg(long, default_value_t = 1, requires = "stdin")]
+        channels: u16,
     },
 
+    /// Open a full-screen dashboard to choose devices, watch levels, start and
+    /// stop capture and follow the transcript
+    Tui,
+
     /// Get current transcription status
     Status,
 
     /// Stop transcription
-    Stop,
+    Stop {
+        /// Discard queued speech and abort the transcription in progress
+        #[arg(long)]
+        cancel: bool,
+    },
+
+    /// Stop all capture and keep it off until unmuted
+    Mute {
+        /// Unmute if already muted, for binding to a keyboard shortcut
+        #[arg(long)]
+        toggle: bool,
+    },
+
+    /// Leave privacy mode and resume capture
+    Unmute,
+
+    /// Deliver the last transcription again with the output mode, e.g. when
+    /// the target application missed the typed text
+    Last {
+        /// Type it into the focused application
+        #[arg(long = "type", conflicts_with = "copy")]
+        type_text: bool,
+
+        /// Copy it to the clipboard
+        #[arg(long)]
+        copy: bool,
+    },
 
     /// Show Whisper model status
     Model {
@@ -81,8 +192,202 @@ enum Commands {
         action: Option<ModelAction>,
     },
 
-    /// Show GPU/CUDA acceleration status
-    Gpu,
+    /// Measure transcription speed of the current model on this machine
+    Bench {
+        /// WAV file to transcribe (defaults to a short speech sample)
+        #[arg(short, long)]
+        file: Option<PathBuf>,
+
+        /// Number of timed runs
+        #[arg(short = 'n', long, default_value = "3")]
+        iterations: u32,
+
+        /// Repeat the runs CPU-only to compare with the GPU
+        #[arg(long)]
+        compare_cpu: bool,
+    },
+
+    /// Show GPU/CUDA acceleration status, or select the GPU to use
+    Gpu {
+        #[command(subcommand)]
+        action: Option<GpuAction>,
+    },
+
+    /// List, show, or delete saved transcriptions
+    History {
+        #[command(subcommand)]
+        action: Option<HistoryAction>,
+    },
+
+    /// Search saved transcriptions for sessions containing every word of a query
+    Search {
+        /// Words to find; each matches words starting with it
+        query: String,
+
+        /// Only sessions from this day on (YYYY-MM-DD)
+        #[arg(long)]
+        from: Option<String>,
+
+        /// Only sessions up to and including this day (YYYY-MM-DD)
+        #[arg(long)]
+        to: Option<String>,
+
+        /// Maximum number of results
+        #[arg(short = 'n', long, default_value = "20")]
+        limit: usize,
+    },
+
+    /// Play a saved transcription's recording, or a WAV file
+    Play {
+        /// Session ID (see 'flowstt history list') or path to a WAV file
+        #[arg(add = ArgValueCompleter::new(complete::session_ids_or_files))]
+        target: String,
+
+        /// Start this many seconds in
+        #[arg(long, default_value = "0")]
+        from: f64,
+
+        /// Return once playback starts instead of following it to the end
+        #[arg(short, long)]
+        detach: bool,
+    },
+
+    /// Save and transcribe the last seconds of captured audio
+    CaptureLast {
+        /// Seconds to keep (default: the whole rolling buffer)
+        #[arg(short, long)]
+        secs: Option<u32>,
+
+        /// Return once the audio is saved instead of waiting for the transcript
+        #[arg(short, long)]
+        detach: bool,
+    },
+
+    /// Show what was transcribed in the last minutes of the active session
+    Recent {
+        /// Minutes to cover
+        #[arg(short, long, default_value = "5")]
+        minutes: u32,
+
+        /// Show each transcription with its time and speaker
+        #[arg(short, long)]
+        segments: bool,
+    },
+
+    /// Pause, resume, seek or stop playback
+    Playback {
+        #[command(subcommand)]
+        action: PlaybackAction,
+    },
+
+    /// Export a saved transcription as subtitles or a document
+    Export {
+        /// Session or capture session ID (see 'flowstt history list')
+        #[arg(add = ArgValueCompleter::new(complete::session_ids))]
+        id: String,
+
+        /// Output format
+        #[arg(short, long, default_value = "srt")]
+        format: ExportFormatArg,
+
+        /// Write to this file instead of standard output
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Manage folders whose new recordings are transcribed automatically
+    Watch {
+        #[command(subcommand)]
+        action: Option<WatchAction>,
+    },
+
+    /// Dictate into a running document ("new paragraph" and "new line" insert breaks)
+    Dictate {
+        #[command(subcommand)]
+        action: DictateAction,
+    },
+
+    /// Manage spoken command phrases
+    #[command(name = "commands")]
+    Voice {
+        #[command(subcommand)]
+        action: Option<CommandsAction>,
+    },
+
+    /// Show disk usage of recordings, or apply the retention limits now
+    Storage {
+        #[command(subcommand)]
+        action: Option<StorageAction>,
+    },
+
+    /// Run commands or HTTP requests on each capture session's transcript, e.g. to summarize it
+    Hooks {
+        #[command(subcommand)]
+        action: Option<HooksAction>,
+    },
+
+    /// Bind global keys to push-to-talk variants and session or mute toggles
+    /// (push-to-talk mode only)
+    Hotkeys {
+        #[command(subcommand)]
+        action: Option<HotkeysAction>,
+    },
+
+    /// Switch between saved bundles of sources, modes, model and output
+    Profile {
+        #[command(subcommand)]
+        action: Option<ProfileAction>,
+    },
+
+    /// Capture briefly from a device and show its level and format
+    Test {
+        /// Device ID to test (use 'list' to see available devices)
+        #[arg(short, long, add = ArgValueCompleter::new(complete::device_ids))]
+        source: String,
+
+        /// How long to capture, in seconds
+        #[arg(short, long, default_value = "5")]
+        duration: u64,
+    },
+
+    /// Measure ambient noise and tune the speech detector to it
+    Calibrate {
+        /// Device ID to capture from if capture isn't already running
+        #[arg(short, long, add = ArgValueCompleter::new(complete::device_ids))]
+        source: Option<String>,
+
+        /// How long to measure, in seconds (5-10)
+        #[arg(short, long, default_value = "5")]
+        duration: u32,
+    },
+
+    /// Show or tune echo cancellation
+    Aec {
+        #[command(subcommand)]
+        action: Option<AecAction>,
+    },
+
+    /// Show or change service settings
+    Config {
+        #[command(subcommand)]
+        action: Option<ConfigAction>,
+    },
+
+    /// Show uptime, throughput and transcription latency of the service
+    Metrics,
+
+    /// Show the application that has keyboard focus, as profiles see it
+    Focus,
+
+    /// Show whether the service may use the microphone and other protected
+    /// resources, or ask for access
+    Permissions {
+        #[command(subcommand)]
+        action: Option<PermissionsAction>,
+    },
+
+    /// Show whether system audio can be captured, and what to set up if not
+    SystemAudio,
 
     /// Ping the service
     Ping,
@@ -90,6 +395,33 @@ enum Commands {
     /// Stop the background service
     Shutdown,
 
+    /// Show the service log, or change how much it logs
+    Logs {
+        /// Keep printing new output as it is written
+        #[arg(short, long)]
+        follow: bool,
+        /// Number of lines to show from the end of the log
+        #[arg(short = 'n', long, default_value_t = 50)]
+        lines: usize,
+        /// Set the service's log level instead, e.g. debug or info,flowstt_service=trace
+        #[arg(long, conflicts_with = "follow")]
+        level: Option<String>,
+    },
+
+    /// Make capture misbehave to test recovery from device problems (debug
+    /// builds of the service only)
+    #[command(hide = true)]
+    Fault {
+        #[command(subcommand)]
+        action: FaultAction,
+    },
+
+    /// Start the background service at login
+    Service {
+        #[command(subcommand)]
+        action: Option<ServiceAction>,
+    },
+
     /// Show version information
     Version,
 }
@@ -98,325 +430,3189 @@ enum Commands {
 enum SourceFilter {
     Input,
     System,
+    Network,
 }
 
 #[derive(Clone, ValueEnum)]
 enum RecordingModeArg {
     Mixed,
     EchoCancel,
+    SplitChannels,
 }
 
-#[derive(Subcommand)]
-enum ModelAction {
-    /// Download the Whisper model
-    Download,
-}
-
-#[tokio::main]
-async fn main() {
-    let cli = Cli::parse();
-
-    if let Err(e) = run(cli).await {
-        eprintln!("{}: {}", "Error".red().bold(), e);
-        std::process::exit(1);
+impl From<RecordingModeArg> for RecordingMode {
+    fn from(mode: RecordingModeArg) -> Self {
+        match mode {
+            RecordingModeArg::Mixed => RecordingMode::Mixed,
+            RecordingModeArg::EchoCancel => RecordingMode::EchoCancel,
+            RecordingModeArg::SplitChannels => RecordingMode::SplitChannels,
+        }
     }
 }
 
-async fn run(cli: Cli) -> Result<(), String> {
-    let mut client = Client::new();
+#[derive(Clone, ValueEnum)]
+enum LatencyPresetArg {
+    /// Short segments, no decoding retries
+    Fast,
+    /// The defaults
+    Balanced,
+    /// Longer segments, beam search decoding
+    Accurate,
+}
 
-    // Handle version separately (doesn't need service)
-    if matches!(cli.command, Commands::Version) {
-        println!("flowstt {}", env!("CARGO_PKG_VERSION"));
-        return Ok(());
+impl From<LatencyPresetArg> for LatencyPreset {
+    fn from(preset: LatencyPresetArg) -> Self {
+        match preset {
+            LatencyPresetArg::Fast => LatencyPreset::Fast,
+            LatencyPresetArg::Balanced => LatencyPreset::Balanced,
+            LatencyPresetArg::Accurate => LatencyPreset::Accurate,
+        }
     }
+}
 
-    // Connect to service (spawn if needed)
-    client
-        .connect_or_spawn()
-        .await
-        .map_err(|e| format!("Failed to connect to service: {}", e))?;
-
-    match cli.command {
-        Commands::List { source } => {
-            let source_type = source.map(|s| match s {
-                SourceFilter::Input => AudioSourceType::Input,
-                SourceFilter::System => AudioSourceType::System,
-            });
+#[derive(Clone, ValueEnum)]
+enum OutputModeArg {
+    /// Only send transcriptions to connected clients
+    Events,
+    /// Also type them into the focused application
+    Type,
+    /// Also copy them to the clipboard
+    Clipboard,
+}
 
-            let response = client
-                .request(Request::ListDevices { source_type })
-                .await
-                .map_err(|e| e.to_string())?;
+#[derive(Clone, ValueEnum)]
+enum KeyArg {
+    RightAlt,
+    LeftAlt,
+    RightControl,
+    LeftControl,
+    RightShift,
+    LeftShift,
+    CapsLock,
+    F13,
+    F14,
+    F15,
+    F16,
+    F17,
+    F18,
+    F19,
+    F20,
+}
 
-            match response {
-                Response::Devices { devices } => {
-                    if matches!(cli.format, OutputFormat::Json) {
-                        println!("{}", serde_json::to_string_pretty(&devices).unwrap());
-                    } else if devices.is_empty() {
-                        println!("No audio devices found");
-                    } else {
-                        println!(
-                            "{} {} found:\n",
-                            devices.len().to_string().green().bold(),
-                            if devices.len() == 1 {
-                                "device"
-                            } else {
-                                "devices"
-                            }
-                        );
-                        for device in devices {
-                            let source_badge = match device.source_type {
-                                AudioSourceType::Input => "[input]".cyan(),
-                                AudioSourceType::System => "[system]".magenta(),
-                                AudioSourceType::Mixed => "[mixed]".yellow(),
-                            };
-                            println!("  {} {}", source_badge, device.name);
-                            println!("    ID: {}", device.id.dimmed());
-                        }
-                    }
-                }
-                Response::Error { message } => return Err(message),
-                _ => return Err("Unexpected response".into()),
-            }
+impl From<KeyArg> for KeyCode {
+    fn from(key: KeyArg) -> Self {
+        match key {
+            KeyArg::RightAlt => KeyCode::RightAlt,
+            KeyArg::LeftAlt => KeyCode::LeftAlt,
+            KeyArg::RightControl => KeyCode::RightControl,
+            KeyArg::LeftControl => KeyCode::LeftControl,
+            KeyArg::RightShift => KeyCode::RightShift,
+            KeyArg::LeftShift => KeyCode::LeftShift,
+            KeyArg::CapsLock => KeyCode::CapsLock,
+            KeyArg::F13 => KeyCode::F13,
+            KeyArg::F14 => KeyCode::F14,
+            KeyArg::F15 => KeyCode::F15,
+            KeyArg::F16 => KeyCode::F16,
+            KeyArg::F17 => KeyCode::F17,
+            KeyArg::F18 => KeyCode::F18,
+            KeyArg::F19 => KeyCode::F19,
+            KeyArg::F20 => KeyCode::F20,
         }
+    }
+}
 
-        Commands::Transcribe {
-            source1,
-            source2,
-            aec,
-            mode,
-        } => {
-            if source1.is_none() && source2.is_none() {
-                return Err(
-                    "At least one audio source is required. Use 'flowstt list' to see devices."
-                        .into(),
-                );
-            }
+#[derive(Clone, ValueEnum)]
+enum HotkeyActionArg {
+    /// Hold to transcribe, delivered by the output mode
+    PushToTalk,
+    /// Hold to transcribe and type the result
+    PushToTalkType,
+    /// Hold to transcribe and copy the result to the clipboard
+    PushToTalkClipboard,
+    /// Press to start or stop transcribing
+    ToggleSession,
+    /// Press to turn privacy mode on or off
+    ToggleMute,
+    /// Press to deliver the last transcription again
+    RepeatLast,
+}
 
-            let recording_mode = match mode {
-                RecordingModeArg::Mixed => RecordingMode::Mixed,
-                RecordingModeArg::EchoCancel => RecordingMode::EchoCancel,
-            };
+impl From<HotkeyActionArg> for HotkeyAction {
+    fn from(action: HotkeyActionArg) -> Self {
+        match action {
+            HotkeyActionArg::PushToTalk => HotkeyAction::PushToTalk,
+            HotkeyActionArg::PushToTalkType => HotkeyAction::PushToTalkType,
+            HotkeyActionArg::PushToTalkClipboard => HotkeyAction::PushToTalkClipboard,
+            HotkeyActionArg::ToggleSession => HotkeyAction::ToggleSession,
+            HotkeyActionArg::ToggleMute => HotkeyAction::ToggleMute,
+            HotkeyActionArg::RepeatLast => HotkeyAction::RepeatLast,
+        }
+    }
+}
 
-            // Set AEC and recording mode first
-            if aec {
-                let _ = client
-                    .request(Request::SetAecEnabled { enabled: true })
-                    .await;
-            }
-            let _ = client
-                .request(Request::SetRecordingMode {
-                    mode: recording_mode,
-                })
-                .await;
+#[derive(Clone, ValueEnum)]
+enum ExportFormatArg {
+    Srt,
+    Vtt,
+    Json,
+    Markdown,
+}
 
-            // Set sources - this starts capture automatically
-            let response = client
-                .request(Request::SetSources {
-                    source1_id: source1,
-                    source2_id: source2,
-                })
-                .await
-                .map_err(|e| e.to_string())?;
+#[derive(Subcommand)]
+enum ModelAction {
+    /// Show model status (the default)
+    Status,
+    /// List downloadable models with their size and memory requirements
+    List,
+    /// Download a model (the selected one by default)
+    Download {
+        /// Model name from `model list` (e.g. small.en-q5_1)
+        name: Option<String>,
+    },
+    /// Select the model to transcribe with
+    Use {
+        /// Model name from `model list`
+        name: String,
+    },
+    /// Use an existing ggml model file instead of downloading one
+    Import {
+        /// Path to the model file (e.g. ggml-small.en-q5_1.bin)
+        path: PathBuf,
+    },
+    /// Load the model now so the first transcription doesn't wait for it
+    Load,
+    /// Free the memory the model uses until it is needed again
+    Unload,
+}
 
-            match response {
-                Response::Ok => {
-                    if !cli.quiet {
-                        println!("{}", "Transcription started".green());
-                        println!("Press Ctrl+C to stop, or run 'flowstt stop'");
-                    }
+#[derive(Subcommand)]
+enum ServiceAction {
+    /// Show whether the service starts at login (the default)
+    Status,
+    /// Start the service at login
+    Install {
+        /// Register a Windows system service started at boot instead (needs an elevated prompt)
+        #[arg(long)]
+        system: bool,
+    },
+    /// Stop starting the service at login
+    Uninstall {
+        /// Remove the Windows system service instead
+        #[arg(long)]
+        system: bool,
+    },
+}
 
-                    // Subscribe to events and stream transcription results
-                    let subscribe_response = client
-                        .request(Request::SubscribeEvents)
-                        .await
-                        .map_err(|e| e.to_string())?;
+#[derive(Subcommand)]
+enum GpuAction {
+    /// Transcribe on the GPU with the given index
+    Use {
+        /// GPU device index (0 is the first GPU)
+        index: u32,
+    },
+    /// Force CPU-only transcription
+    Cpu,
+}
 
-                    if !matches!(subscribe_response, Response::Subscribed) {
-                        return Err("Failed to subscribe to events".into());
+#[derive(Subcommand)]
+enum HistoryAction {
+    /// List saved transcriptions, newest first
+    List {
+        /// Maximum number of sessions to show
+        #[arg(short = 'n', long, default_value = "20")]
+        limit: usize,
+        /// Only sessions with this tag
+        #[arg(long)]
+        tag: Option<String>,
+        /// Only sessions with a participant whose name contains this
+        #[arg(long)]
+        participant: Option<String>,
+        /// Only sessions whose title, notes, tags or participants contain this
+        #[arg(long)]
+        query: Option<String>,
+    },
+    /// Show a saved transcription
+    Show {
+        /// Session ID (see 'flowstt history list')
+        #[arg(add = ArgValueCompleter::new(complete::session_ids))]
+        id: String,
+    },
+    /// Show a capture session: everything transcribed between starting and
+    /// stopping capture
+    Capture {
+        /// Capture session ID (see 'flowstt history show')
+        id: String,
+    },
+    /// Show talk time, pace and each speaker's share of a saved transcription
+    /// or capture session
+    Stats {
+        /// Session or capture session ID (see 'flowstt history list')
+        #[arg(add = ArgValueCompleter::new(complete::session_ids))]
+        id: String,
+    },
+    /// Delete a saved transcription
+    Delete {
+        /// Session ID (see 'flowstt history list')
+        #[arg(add = ArgValueCompleter::new(complete::session_ids))]
+        id: String,
+    },
+    /// Set the title, tags, participants or notes of a saved transcription
+    Tag {
+        /// Session ID (see 'flowstt history list')
+        #[arg(add = ArgValueCompleter::new(complete::session_ids))]
+        id: String,
+        /// Title shown instead of the beginning of the text (empty to remove)
+        #[arg(long)]
+        title: Option<String>,
+        /// Add a tag (repeatable)
+        #[arg(short, long = "tag")]
+        tags: Vec<String>,
+        /// Remove a tag (repeatable)
+        #[arg(long = "untag")]
+        untags: Vec<String>,
+        /// Add a participant (repeatable)
+        #[arg(short, long = "participant")]
+        participants: Vec<String>,
+        /// Remove a participant (repeatable)
+        #[arg(long = "remove-participant")]
+        removed_participants: Vec<String>,
+        /// Notes about the session (empty to remove)
+        #[arg(long)]
+        notes: Option<String>,
+    },
+    /// Transcribe saved transcriptions again with the configured model,
+    /// keeping the previous transcripts as versions
+    Retranscribe {
+        /// Session IDs (see 'flowstt history list')
+        #[arg(required = true, add = ArgValueCompleter::new(complete::session_ids))]
+        ids: Vec<String>,
+        /// Return once the job is queued instead of following its progress
+        #[arg(short, long)]
+        detach: bool,
+    },
+    /// List re-transcription jobs
+    Jobs,
+    /// Cancel a re-transcription job
+    CancelJob {
+        /// Job ID (see 'flowstt history jobs')
+        id: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum DictateAction {
+    /// Start a new dictated document
+    Start,
+    /// Stop dictating and print the document
+    Stop,
+}
+
+#[derive(Subcommand)]
+enum CommandsAction {
+    /// List registered commands
+    List,
+    /// Register a phrase; saying it alone emits a command event
+    Add {
+        /// Phrase to listen for
+        phrase: String,
+        /// Don't also deliver the phrase as transcribed text
+        #[arg(long)]
+        suppress_text: bool,
+    },
+    /// Remove a registered phrase
+    Remove {
+        /// Phrase to remove
+        phrase: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum PlaybackAction {
+    /// Pause playback
+    Pause,
+    /// Resume paused playback
+    Resume,
+    /// Jump to a position
+    Seek {
+        /// Position in seconds
+        seconds: f64,
+    },
+    /// Stop playback
+    Stop,
+}
+
+#[derive(Subcommand)]
+enum PermissionsAction {
+    /// Show current access (the default)
+    Status,
+    /// Show the system prompt for a permission and wait for the answer
+    Request {
+        #[arg(value_enum)]
+        permission: PermissionArg,
+    },
+}
+
+#[derive(Clone, ValueEnum)]
+enum PermissionArg {
+    Microphone,
+    ScreenRecording,
+    Accessibility,
+}
+
+impl From<PermissionArg> for Permission {
+    fn from(permission: PermissionArg) -> Self {
+        match permission {
+            PermissionArg::Microphone => Permission::Microphone,
+            PermissionArg::ScreenRecording => Permission::ScreenRecording,
+            PermissionArg::Accessibility => Permission::Accessibility,
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum StorageAction {
+    /// Show disk usage and retention limits
+    Status,
+    /// Remove recordings beyond the retention limits
+    Prune,
+}
+
+#[derive(Subcommand)]
+enum HooksAction {
+    /// List configured hooks
+    List,
+    /// Add a hook (replaces one with the same name)
+    Add {
+        /// Hook name
+        name: String,
+        /// Shell command that reads the text on stdin; its output is the summary
+        #[arg(long, conflicts_with = "url", required_unless_present = "url")]
+        command: Option<String>,
+        /// Endpoint the session is POSTed to as JSON; the response (or its
+        /// "summary" field) is the summary
+        #[arg(long)]
+        url: Option<String>,
+        /// Don't store the hook's output as the capture session's summary
+        #[arg(long)]
+        no_summary: bool,
+    },
+    /// Remove a hook
+    Remove {
+        /// Hook name
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum HotkeysAction {
+    /// List hotkeys
+    List,
+    /// Bind a key to an action, replacing the action's previous key
+    Set {
+        /// Key to bind
+        key: KeyArg,
+        /// Action the key triggers
+        action: HotkeyActionArg,
+    },
+    /// Remove the key bound to an action
+    Remove {
+        /// Action to unbind
+        action: HotkeyActionArg,
+    },
+}
+
+#[derive(Subcommand)]
+enum ProfileAction {
+    /// List saved profiles
+    List,
+    /// Apply a profile's settings
+    Use {
+        /// Profile name
+        name: String,
+    },
+    /// Save a profile; settings left out are unchanged when it is used
+    Save {
+        /// Profile name, e.g. meetings, dictation or gaming
+        name: String,
+        /// Primary audio source ID
+        #[arg(short = '1', long, add = ArgValueCompleter::new(complete::device_ids))]
+        source1: Option<String>,
+        /// Secondary audio source ID
+        #[arg(short = '2', long, requires = "source1", add = ArgValueCompleter::new(complete::device_ids))]
+        source2: Option<String>,
+        /// Recording mode
+        #[arg(short, long)]
+        mode: Option<RecordingModeArg>,
+        /// Echo cancellation on or off
+        #[arg(long)]
+        aec: Option<bool>,
+        /// Catalog model (see 'flowstt model list')
+        #[arg(long)]
+        model: Option<String>,
+        /// Where transcriptions go
+        #[arg(short, long)]
+        output: Option<OutputModeArg>,
+        /// Switch to this profile when the application comes to the front
+        /// (bundle ID, executable or window class; see 'flowstt focus')
+        #[arg(long = "app")]
+        apps: Vec<String>,
+    },
+    /// Delete a profile
+    Delete {
+        /// Profile name
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum AecAction {
+    /// Change echo cancellation tuning
+    Set {
+        /// Delay from playback to its echo at the microphone, in milliseconds
+        #[arg(long)]
+        delay: Option<u32>,
+        /// Echo path length the filter models, in milliseconds
+        #[arg(long)]
+        filter_length: Option<u32>,
+    },
+    /// Measure the echo delay while audio plays through the speakers
+    Estimate {
+        /// How long to measure, in seconds (2-10)
+        #[arg(short, long, default_value = "5")]
+        duration: u32,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigAction {
+    /// Show settings (all of them if no key is given)
+    Get {
+        /// Setting name, e.g. recordings.dir
+        key: Option<String>,
+    },
+    /// Change a setting; an empty value restores the default
+    Set {
+        /// Setting name: recordings.dir, recordings.template, recordings.format,
+        /// recordings.trim_silence, recordings.silence_threshold_db,
+        /// recordings.normalize, recordings.target_lufs, audio.buffer_ms,
+        /// audio.capture_mode, audio.sample_rate, model.path, model.name, cues.ptt,
+        /// cues.transcription, cues.volume, osc.enabled, osc.address, osc.prefix, osc.chatbox,
+        /// output.template, post_processing.profile, retention.max_size_mb, retention.max_age_days,
+        /// retention.keep_transcripts, vad_recording.enabled, vad_recording.split,
+        /// vad_recording.pre_roll_ms, vad_recording.post_roll_ms,
+        /// latency.preset, chapters.silence_secs, chapters.interval_mins,
+        /// rolling_buffer.secs, redaction.channels, redaction.emails, redaction.phone_numbers or
+        /// redaction.card_numbers
+        key: String,
+        /// New value; templates may use {date}, {time}, {session} and {source},
+        /// formats are wav, flac or opus, trimming, normalization, cues, osc,
+        /// vad_recording and redaction switches are on or off, the silence
+        /// threshold is dBFS, the target loudness is LUFS (-23 for EBU R128),
+        /// the capture buffer is milliseconds (empty for the backend default),
+        /// the capture mode is shared, raw or exclusive (Windows only),
+        /// volume is 0 to 1, the OSC address is host:port,
+        /// retention limits are megabytes or days, the split is utterance or
+        /// session, rolls are milliseconds, the latency preset is fast,
+        /// balanced or accurate, the text profile is prose or technical, chapter splits are seconds of
+        /// silence or minutes (0 turns them off), the rolling buffer is seconds
+        /// (0 turns it off), redaction channels are a
+        /// comma-separated list of history, output, events and hooks, or all
+        #[arg(allow_hyphen_values = true)]
+        value: String,
+    },
+}
+
+#[derive(Clone, ValueEnum)]
+enum FaultSourceArg {
+    /// The first source (usually the microphone)
+    Input,
+    /// The second source (usually system audio)
+    System,
+}
+
+impl From<FaultSourceArg> for AudioSourceType {
+    fn from(source: FaultSourceArg) -> Self {
+        match source {
+            FaultSourceArg::Input => AudioSourceType::Input,
+            FaultSourceArg::System => AudioSourceType::System,
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum FaultAction {
+    /// Drop the next buffers captured from a source
+    Drop {
+        #[arg(long, value_enum, default_value = "input")]
+        source: FaultSourceArg,
+        /// Number of buffers to drop
+        #[arg(long, default_value_t = 50)]
+        count: u32,
+    },
+    /// Deliver the next buffers from a source late
+    Delay {
+        #[arg(long, value_enum, default_value = "input")]
+        source: FaultSourceArg,
+        /// Number of buffers to delay
+        #[arg(long, default_value_t = 10)]
+        count: u32,
+        /// How late each buffer arrives, in milliseconds
+        #[arg(long, default_value_t = 100)]
+        delay_ms: u32,
+    },
+    /// Make the device capturing a source vanish for a while
+    Disconnect {
+        #[arg(long, value_enum, default_value = "input")]
+        source: FaultSourceArg,
+        /// How long the device stays gone, in milliseconds
+        #[arg(long, default_value_t = 5000)]
+        duration_ms: u32,
+    },
+    /// Remove all injected faults
+    Clear,
+}
+
+#[derive(Subcommand)]
+enum WatchAction {
+    /// List watched folders
+    List,
+    /// Watch a folder; new WAV files get .txt and .srt transcripts next to them
+    Add {
+        /// Directory to watch
+        path: PathBuf,
+    },
+    /// Stop watching a folder
+    Remove {
+        /// Directory to stop watching
+        path: PathBuf,
+    },
+}
+
+fn main() {
+    // Shell completion requests are answered here and exit
+    CompleteEnv::with_factory(Cli::command).complete();
+
+    let cli = Cli::parse();
+    let runtime = tokio::runtime::Runtime::new().expect("Failed to start async runtime");
+
+    if let Err(e) = runtime.block_on(run(cli)) {
+        eprintln!("{}: {}", "Error".red().bold(), e);
+        std::process::exit(1);
+    }
+}
+
+/// A streamed service event as one line of JSON.
+///
+/// Completed transcriptions also get `start_ms` and `end_ms`, the span of
+/// their speech from when the stream started, and always carry `speaker` and
+/// the per-segment `confidence` so scripts can read them without checking
+/// for them first. The span
+/// ends when the transcription arrives, so it runs late by however long the
+/// transcription took.
+fn json_event_line(event: &EventType, elapsed: std::time::Duration) -> String {
+    let mut line = serde_json::to_value(event).unwrap();
+    if let (EventType::TranscriptionComplete(result), Some(fields)) = (event, line.as_object_mut())
+    {
+        let end_ms = elapsed.as_millis() as u64;
+        fields.insert(
+            "start_ms".into(),
+            end_ms.saturating_sub(result.duration_ms).into(),
+        );
+        fields.insert("end_ms".into(), end_ms.into());
+        fields.entry("speaker").or_insert(serde_json::Value::Null);
+        fields.entry("confidence").or_insert(serde_json::json!([]));
+    }
+    line.to_string()
+}
+
+/// Print a streamed service event in text format.
+fn print_event(event: &EventType, verbose: bool) {
+    match event {
+        EventType::TranscriptionComplete(result) => {
+            if let Some(speaker) = &result.speaker {
+                println!("{} {}", format!("[{}]", speaker).cyan(), result.text);
+            } else if result.segments.is_empty() {
+                println!("{}", result.text);
+            } else {
+                print_source_segments(&result.segments);
+            }
+            for segment in result.confidence.iter().filter(|s| s.low_confidence) {
+                println!("  {} {}", "[low confidence]".yellow(), segment.text);
+            }
+            if let Some(translation) = &result.translation {
+                println!("  {} {}", "[en]".dimmed(), translation);
+            }
+        }
+        EventType::CommandDetected { phrase, .. } => {
+            println!("{} {}", "[command]".magenta(), phrase)
+        }
+        EventType::SpeechStarted if verbose => println!("{}", "[speech started]".dimmed()),
+        EventType::SpeechEnded { duration_ms } if verbose => {
+            println!("{}", format!("[speech ended: {}ms]", duration_ms).dimmed())
+        }
+        EventType::TranscriptionCancelled { .. } => {
+            println!("{}", "[transcription cancelled]".yellow())
+        }
+        EventType::ControllerChanged {
+            controller: Some(controller),
+        } if verbose => println!("{}", format!("[controlled by {}]", controller).dimmed()),
+        EventType::ProfileChanged { name } if verbose => {
+            println!("{}", format!("[profile {}]", name).dimmed())
+        }
+        EventType::SessionSummarized { id, summary } => {
+            println!("{} {}", format!("[summary {}]", id).cyan(), summary)
+        }
+        EventType::PrivacyMode { muted: true } => println!("{}", "[muted]".yellow()),
+        EventType::PrivacyMode { muted: false } => println!("{}", "[unmuted]".yellow()),
+        EventType::SourceSwitched { previous, current } => eprintln!(
+            "{}",
+            format!("[source {} disappeared, switched to {}]", previous, current).yellow()
+        ),
+        EventType::Error { message, .. } => eprintln!("{}: {}", "Error".red().bold(), message),
+        _ => {}
+    }
+}
+
+/// Summary of the settings a profile changes.
+fn describe_profile(profile: &Profile) -> String {
+    let mut settings = Vec::new();
+    if let Some(source1) = &profile.source1_id {
+        settings.push(format!("source {}", source1));
+    }
+    if let Some(source2) = &profile.source2_id {
+        settings.push(format!("second source {}", source2));
+    }
+    if let Some(mode) = profile.recording_mode {
+        let mode = match mode {
+            RecordingMode::Mixed => "mixed",
+            RecordingMode::EchoCancel => "echo-cancel",
+            RecordingMode::SplitChannels => "split-channels",
+        };
+        settings.push(format!("{} mode", mode));
+    }
+    if let Some(aec) = profile.aec_enabled {
+        settings.push(format!(
+            "echo cancellation {}",
+            if aec { "on" } else { "off" }
+        ));
+    }
+    if let Some(model) = &profile.model_name {
+        settings.push(format!("model {}", model));
+    }
+    match profile.output_mode {
+        Some(OutputMode::Type) => settings.push("typed output".to_string()),
+        Some(OutputMode::EventsOnly) => settings.push("events only".to_string()),
+        Some(OutputMode::Clipboard) => settings.push("clipboard output".to_string()),
+        None => {}
+    }
+    if !profile.apps.is_empty() {
+        settings.push(format!("used in {}", profile.apps.join(", ")));
+    }
+    if settings.is_empty() {
+        "(no settings)".to_string()
+    } else {
+        format!("({})", settings.join(", "))
+    }
+}
+
+/// Show levels and format events from a device test until `duration` passes.
+///
+/// Returns the highest peak level seen, or `None` if no audio arrived.
+async fn run_device_test(
+    mut events: EventStream,
+    duration: Duration,
+    json: bool,
+) -> Result<Option<f32>, String> {
+    let deadline = tokio::time::Instant::now() + duration;
+    let mut max_peak: Option<f32> = None;
+
+    while let Ok(Some(event)) = tokio::time::timeout_at(deadline, events.next()).await {
+        let event = event.map_err(|e| e.to_string())?;
+        match &event {
+            EventType::SourceFormat(format) => {
+                if !json {
+                    println!(
+                        "\rFormat: {} Hz, {} channel{}",
+                        format.sample_rate,
+                        format.channels,
+                        if format.channels == 1 { "" } else { "s" }
+                    );
+                }
+            }
+            EventType::Levels(levels) => {
+                let Some(level) = levels.input.or(levels.system) else {
+                    continue;
+                };
+                max_peak = Some(max_peak.map_or(level.peak_db, |p| p.max(level.peak_db)));
+                if !json {
+                    print!("\r{}", level_meter(&level));
+                    let _ = std::io::stdout().flush();
+                }
+            }
+            EventType::CaptureStateChanged {
+                capturing: false,
+                error: Some(error),
+            } => return Err(error.clone()),
+            _ => continue,
+        }
+        if json {
+            println!("{}", serde_json::to_string(&event).unwrap());
+        }
+    }
+
+    Ok(max_peak)
+}
+
+/// Show playback progress until the recording ends. Ctrl+C stops playback
+/// through `control`.
+async fn follow_playback(
+    mut events: EventStream,
+    control: &mut Client,
+    status: &PlaybackStatus,
+    json: bool,
+) -> Result<(), String> {
+    // Wait for a position of this playback before taking a stop event as its
+    // own; the one it replaced may report stopping first
+    let mut started = false;
+    loop {
+        let event = tokio::select! {
+            event = events.next() => match event {
+                Some(event) => event.map_err(|e| e.to_string())?,
+                None => break,
+            },
+            _ = tokio::signal::ctrl_c() => {
+                let _ = control.request(Request::StopPlayback).await;
+                break;
+            }
+        };
+        let done = match &event {
+            EventType::PlaybackPosition(position) if position.path == status.path => {
+                started = true;
+                if !json {
+                    print!("\r{}", playback_progress(position));
+                    let _ = std::io::stdout().flush();
+                }
+                false
+            }
+            EventType::PlaybackStopped { session_id } => {
+                started || (session_id.is_some() && *session_id == status.session_id)
+            }
+            EventType::Shutdown => true,
+            _ => continue,
+        };
+        if json {
+            println!("{}", serde_json::to_string(&event).unwrap());
+        }
+        if done {
+            break;
+        }
+    }
+    if !json {
+        println!();
+    }
+    Ok(())
+}
+
+/// A search hit's snippet with the matched words picked out.
+fn highlight_snippet(hit: &SearchHit) -> String {
+    let mut out = String::new();
+    let mut pos = 0;
+    for highlight in &hit.highlights {
+        let (Some(before), Some(word)) = (
+            hit.snippet.get(pos..highlight.start),
+            hit.snippet.get(highlight.start..highlight.end),
+        ) else {
+            continue;
+        };
+        out.push_str(before);
+        out.push_str(&word.yellow().bold().to_string());
+        pos = highlight.end;
+    }
+    out.push_str(hit.snippet.get(pos..).unwrap_or_default());
+    out
+}
+
+/// Describe a playback position, e.g. "0:03.2 / 0:10.0".
+fn playback_progress(status: &PlaybackStatus) -> String {
+    let time = |ms: u64| format!("{}:{:04.1}", ms / 60_000, (ms % 60_000) as f64 / 1000.0);
+    let progress = format!(
+        "{} / {}",
+        time(status.position_ms),
+        time(status.duration_ms)
+    );
+    if status.paused {
+        format!("{} {}", progress, "(paused)".yellow())
+    } else {
+        progress
+    }
+}
+
+/// Format a byte count for display, e.g. "12.3 MB".
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+/// Print one line of permission status, e.g. "Microphone: granted".
+fn print_permission(name: &str, state: PermissionState) {
+    let state = match state {
+        PermissionState::Granted => "granted".green(),
+        PermissionState::Denied => "denied".red(),
+        PermissionState::NotDetermined => "not asked yet".yellow(),
+        PermissionState::Restricted => "restricted by policy".red(),
+        PermissionState::NotRequired => "not required".dimmed(),
+    };
+    println!("{}: {}", name, state);
+}
+
+/// Render a level as a terminal meter, colored by how close the peak is to clipping.
+fn level_meter(level: &AudioLevel) -> String {
+    let filled = ((level.rms_db - METER_FLOOR_DB) / -METER_FLOOR_DB * METER_WIDTH as f32)
+        .clamp(0.0, METER_WIDTH as f32) as usize;
+    let bar = format!("{}{}", "#".repeat(filled), "-".repeat(METER_WIDTH - filled));
+    let bar = if level.peak_db > -3.0 {
+        bar.red()
+    } else if level.peak_db > -12.0 {
+        bar.yellow()
+    } else {
+        bar.green()
+    };
+    format!(
+        "[{}] {:6.1} dB  peak {:6.1} dB",
+        bar, level.rms_db, level.peak_db
+    )
+}
+
+/// Print per-source transcription segments, one labeled line each.
+fn print_source_segments(segments: &[SourceSegment]) {
+    for segment in segments {
+        let label = match segment.source {
+            SourceLabel::Mic => "[mic]".green(),
+            SourceLabel::System => "[system]".blue(),
+        };
+        println!("{} {}", label, segment.text);
+    }
+}
+
+/// Print a re-transcription job's state and the sessions that failed.
+fn print_job(job: &RetranscribeJob) {
+    let state = match job.state {
+        JobState::Queued => "queued".dimmed(),
+        JobState::Running => "running".cyan(),
+        JobState::Completed if job.errors.is_empty() => "completed".green(),
+        JobState::Completed => "completed with errors".yellow(),
+        JobState::Failed => "failed".red(),
+        JobState::Cancelled => "cancelled".yellow(),
+    };
+    println!(
+        "{} {} ({}/{} sessions, {})",
+        job.id.cyan(),
+        state,
+        job.completed,
+        job.session_ids.len(),
+        job.model
+    );
+    if let Some(error) = &job.error {
+        println!("  {}", error.red());
+    }
+    for error in &job.errors {
+        println!("  {} {}", error.session_id.red(), error.message);
+    }
+}
+
+/// Take control of capture on this connection, overriding any other client.
+async fn take_control(client: &mut Client) -> Result<(), String> {
+    let request = Request::AcquireControl {
+        client_name: "flowstt CLI".into(),
+        force: true,
+    };
+    match client.request(request).await.map_err(|e| e.to_string())? {
+        Response::Ok => Ok(()),
+        Response::Error { message, .. } => Err(message),
+        _ => Err("Unexpected response".into()),
+    }
+}
+
+/// Install, remove or show the login registration of the service.
+fn run_service_action(action: Option<&ServiceAction>, cli: &Cli) -> Result<(), String> {
+    let status = match action {
+        Some(ServiceAction::Install { system: false }) => {
+            let status = autostart::install()?;
+            if !cli.quiet {
+                println!("{}", "Service will start at login".green());
+            }
+            status
+        }
+        Some(ServiceAction::Install { system: true }) => {
+            let status = autostart::install_system()?;
+            if !cli.quiet {
+                println!("{}", "Service will start at boot".green());
+            }
+            status
+        }
+        Some(ServiceAction::Uninstall { system }) => {
+            let (removed, when) = if *system {
+                (autostart::uninstall_system()?, "boot")
+            } else {
+                (autostart::uninstall()?, "login")
+            };
+            if !cli.quiet {
+                if removed {
+                    let message = format!("Service will no longer start at {}", when);
+                    println!("{}", message.green());
+                } else {
+                    let message = format!("Service was not set to start at {}", when);
+                    println!("{}", message.yellow());
+                }
+            }
+            return Ok(());
+        }
+        None | Some(ServiceAction::Status) => autostart::status(),
+    };
+
+    if cli.quiet && action.is_some_and(|a| matches!(a, ServiceAction::Install { .. })) {
+        return Ok(());
+    }
+    if matches!(cli.format, OutputFormat::Json) {
+        println!("{}", serde_json::to_string_pretty(&status).unwrap());
+    } else {
+        let installed = if status.installed {
+            "installed".green().bold()
+        } else {
+            "not installed".yellow()
+        };
+        println!("Start at login: {}", installed);
+        println!("Registration: {}", status.location.dimmed());
+        println!("Executable: {}", status.service_path.dimmed());
+        println!("Logs: {}", status.log_dir.dimmed());
+        if status.system_service {
+            println!("Windows service: {}", "installed".green().bold());
+        }
+    }
+    Ok(())
+}
+
+async fn run(cli: Cli) -> Result<(), String> {
+    let mut client = Client::new();
+
+    // Handle version separately (doesn't need service)
+    if matches!(cli.command, Commands::Version) {
+        println!("flowstt {}", env!("CARGO_PKG_VERSION"));
+        return Ok(());
+    }
+
+    // Autostart registration doesn't need the service either
+    if let Commands::Service { action } = &cli.command {
+        return run_service_action(action.as_ref(), &cli);
+    }
+
+    // Log files are read directly; only changing the level needs the service
+    if let Commands::Logs {
+        follow,
+        lines,
+        level: None,
+    } = &cli.command
+    {
+        return logs::show(*lines, *follow).await;
+    }
+
+    // Connect to service (spawn if needed)
+    client
+        .connect_or_spawn()
+        .await
+        .map_err(|e| format!("Failed to connect to service: {}", e))?;
+    if cli.take_control {
+        take_control(&mut client).await?;
+    }
+
+    match cli.command {
+        Commands::List { source, ids } => {
+            let source_type = source.map(|s| match s {
+                SourceFilter::Input => AudioSourceType::Input,
+                SourceFilter::System => AudioSourceType::System,
+                SourceFilter::Network => AudioSourceType::Network,
+            });
+
+            let response = client
+                .request(Request::ListDevices { source_type })
+                .await
+                .map_err(|e| e.to_string())?;
+
+            match response {
+                Response::Devices { devices } => {
+                    if matches!(cli.format, OutputFormat::Json) {
+                        println!("{}", serde_json::to_string_pretty(&devices).unwrap());
+                    } else if ids {
+                        for device in devices {
+                            println!("{}", device.id);
+                        }
+                    } else if devices.is_empty() {
+                        println!("No audio devices found");
+                    } else {
+                        println!(
+                            "{} {} found:\n",
+                            devices.len().to_string().green().bold(),
+                            if devices.len() == 1 {
+                                "device"
+                            } else {
+                                "devices"
+                            }
+                        );
+                        for device in devices {
+                            let source_badge = match device.source_type {
+                                AudioSourceType::Input => "[input]".cyan(),
+                                AudioSourceType::System => "[system]".magenta(),
+                                AudioSourceType::Mixed => "[mixed]".yellow(),
+                                AudioSourceType::Network => "[network]".blue(),
+                            };
+                            println!("  {} {}", source_badge, device.name);
+                            println!("    ID: {}", device.id.dimmed());
+                        }
+                    }
+                }
+                Response::Error { message, .. } => return Err(message),
+                _ => return Err("Unexpected response".into()),
+            }
+        }
+
+        Commands::Transcribe {
+            source1,
+            source2,
+            channels1,
+            channels2,
+            aec,
+            mode,
+            language,
+            translate,
+            latency,
+            type_output,
+            diarize,
+            record_sources,
+            vad_record,
+            stdin,
+            rate,
+            channels,
+        } => {
+            if !stdin && source1.is_none() && source2.is_none() {
+                return Err(
+                    "At least one audio source is required. Use 'flowstt list' to see devices."
+                        .into(),
+                );
+            }
+
+            let recording_mode = RecordingMode::from(mode);
+
+            if let Some(language) = language {
+                let response = client
+                    .request(Request::SetLanguage { language })
+                    .await
+                    .map_err(|e| e.to_string())?;
+                if let Response::Error { message, .. } = response {
+                    return Err(message);
+                }
+            }
+
+            if stdin {
+                let raw = pipe::PcmFormat {
+                    sample_rate: rate,
+                    channels,
+                };
+                return pipe::run(&mut client, raw, matches!(cli.format, OutputFormat::Json)).await;
+            }
+
+            // Leave features an older service lacks alone unless they were asked for
+            if translate || client.supports(Capability::Translation) {
+                let response = client
+                    .request(Request::SetTranslate { enabled: translate })
+                    .await
+                    .map_err(|e| e.to_string())?;
+                if let Response::Error { message, .. } = response {
+                    return Err(message);
+                }
+            }
+
+            if let Some(latency) = latency {
+                let response = client
+                    .request(Request::SetLatencyPreset {
+                        preset: latency.into(),
+                    })
+                    .await
+                    .map_err(|e| e.to_string())?;
+                if let Response::Error { message, .. } = response {
+                    return Err(message);
+                }
+            }
+
+            let output_mode = if type_output {
+                OutputMode::Type
+            } else {
+                OutputMode::EventsOnly
+            };
+            let response = client
+                .request(Request::SetOutputMode { mode: output_mode })
+                .await
+                .map_err(|e| e.to_string())?;
+            if let Response::Error { message, .. } = response {
+                return Err(message);
+            }
+
+            if diarize || client.supports(Capability::Diarization) {
+                let response = client
+                    .request(Request::SetDiarization { enabled: diarize })
+                    .await
+                    .map_err(|e| e.to_string())?;
+                if let Response::Error { message, .. } = response {
+                    return Err(message);
+                }
+            }
+
+            let response = client
+                .request(Request::SetSourceRecording {
+                    enabled: record_sources,
+                })
+                .await
+                .map_err(|e| e.to_string())?;
+            if let Response::Error { message, .. } = response {
+                return Err(message);
+            }
+
+            if vad_record || client.supports(Capability::VadRecording) {
+                let response = client
+                    .request(Request::SetVadRecording {
+                        enabled: vad_record,
+                    })
+                    .await
+                    .map_err(|e| e.to_string())?;
+                if let Response::Error { message, .. } = response {
+                    return Err(message);
+                }
+            }
+
+            // Set AEC and recording mode first
+            if aec {
+                let _ = client
+                    .request(Request::SetAecEnabled { enabled: true })
+                    .await;
+            }
+            let _ = client
+                .request(Request::SetRecordingMode {
+                    mode: recording_mode,
+                })
+                .await;
+
+            // Set sources - this starts capture automatically
+            let response = client
+                .request(Request::SetSources {
+                    source1_id: source1,
+                    source2_id: source2,
+                    source1_channels: channels1,
+                    source2_channels: channels2,
+                })
+                .await
+                .map_err(|e| e.to_string())?;
+
+            match response {
+                Response::Ok => {
+                    if !cli.quiet {
+                        println!("{}", "Transcription started".green());
+                        println!("Press Ctrl+C to stop, or run 'flowstt stop'");
+                    }
+
+                    // In PTT mode capture stops after every key release, so only an
+                    // automatic-mode capture stop ends the stream
+                    let mut mode = match client.request(Request::GetPttStatus).await {
+                        Ok(Response::PttStatus(status)) => status.mode,
+                        _ => TranscriptionMode::default(),
+                    };
+
+                    // Subscribe to events and stream transcription results
+                    let mut events = client.subscribe().await.map_err(|e| e.to_string())?;
+                    let started = std::time::Instant::now();
+
+                    // Stream events until capture stops, shutdown, or Ctrl+C
+                    while let Some(event) = events.next().await {
+                        let event = event.map_err(|e| e.to_string())?;
+
+                        if matches!(cli.format, OutputFormat::Json) {
+                            // Visualization data and levels are far too frequent for a line stream
+                            if !matches!(
+                                event,
+                                EventType::VisualizationData(_) | EventType::Levels(_)
+                            ) {
+                                println!("{}", json_event_line(&event, started.elapsed()));
+                            }
+                        } else {
+                            print_event(&event, cli.verbose);
+                        }
+
+                        match event {
+                            EventType::TranscriptionModeChanged { mode: new_mode } => {
+                                mode = new_mode;
+                            }
+                            EventType::CaptureStateChanged {
+                                capturing: false,
+                                error,
+                            } if error.is_some() || mode == TranscriptionMode::Automatic => {
+                                if let Some(error) = error {
+                                    return Err(error);
+                                }
+                                break;
+                            }
+                            EventType::Shutdown => break,
+                            _ => {}
+                        }
+                    }
+
+                    if !cli.quiet && matches!(cli.format, OutputFormat::Text) {
+                        println!("\n{}", "Transcription stopped".yellow());
+                    }
+                }
+                Response::Error { message, .. } => return Err(message),
+                _ => return Err("Unexpected response".into()),
+            }
+        }
+
+        Commands::Tui => tui::run(client).await?,
+
+        Commands::Status => {
+            let response = client
+                .request(Request::GetStatus)
+                .await
+                .map_err(|e| e.to_string())?;
+
+            match response {
+                Response::Status(status) => {
+                    if matches!(cli.format, OutputFormat::Json) {
+                        println!("{}", serde_json::to_string_pretty(&status).unwrap());
+                    } else {
+                        let capture_str = if status.muted {
+                            "muted".yellow().bold()
+                        } else if status.capturing {
+                            "capturing".green().bold()
+                        } else {
+                            "idle".dimmed()
+                        };
+                        println!("Capture: {}", capture_str);
+
+                        if let Some(error) = &status.error {
+                            println!("Error: {}", error.red());
+                        }
+                        if let Some(error) = &status.permission_error {
+                            println!("Microphone: {}", error.red());
+                        }
+                        if let Some(controller) = &status.controller {
+                            println!("Controlled by: {}", controller);
+                        }
+
+                        if status.capturing {
+                            let speech_str = if status.in_speech {
+                                "speaking".green()
+                            } else {
+                                "silent".dimmed()
+                            };
+                            println!("Speech: {}", speech_str);
+                            println!("Queue depth: {}", status.queue_depth);
+                            if let Some(aec) = &status.aec_metrics {
+                                println!(
+                                    "Echo cancellation: ERLE {:.1} dB, ERL {:.1} dB, delay {} ms",
+                                    aec.erle_db, aec.erl_db, aec.delay_ms
+                                );
+                            }
+                            if let Some(sync) = &status.sync_metrics {
+                                let drift = sync
+                                    .drift_ppm
+                                    .map_or("measuring".to_string(), |d| format!("{:+.0} ppm", d));
+                                println!(
+                                    "Stream sync: drift {}, offset {:.1} ms, {} frames dropped, {} inserted",
+                                    drift,
+                                    sync.offset_ms,
+                                    sync.dropped_frames,
+                                    sync.inserted_frames
+                                );
+                            }
+                            for latency in &status.capture_latency {
+                                let requested = latency
+                                    .requested_ms
+                                    .map_or(String::new(), |ms| format!(" ({} ms requested)", ms));
+                                println!(
+                                    "{:?} buffer: {:.1} ms{}",
+                                    latency.source_type, latency.buffer_ms, requested
+                                );
+                            }
+                        }
+                    }
+                }
+                Response::Error { message, .. } => return Err(message),
+                _ => return Err("Unexpected response".into()),
+            }
+        }
+
+        Commands::Stop { cancel } => {
+            // Clear sources to stop capture
+            let response = client
+                .request(Request::SetSources {
+                    source1_id: None,
+                    source2_id: None,
+                    source1_channels: None,
+                    source2_channels: None,
+                })
+                .await
+                .map_err(|e| e.to_string())?;
+
+            match response {
+                Response::Ok => {
+                    if !cli.quiet {
+                        println!("{}", "Capture stopped".green());
+                    }
+                }
+                Response::Error { message, .. } => return Err(message),
+                _ => return Err("Unexpected response".into()),
+            }
+
+            if cancel && !client.supports(Capability::Cancellation) {
+                eprintln!(
+                    "{}",
+                    "The running service can't cancel transcriptions; pending segments will still be transcribed"
+                        .yellow()
+                );
+            } else if cancel {
+                let response = client
+                    .request(Request::CancelTranscription)
+                    .await
+                    .map_err(|e| e.to_string())?;
+
+                match response {
+                    Response::Ok => {
+                        if !cli.quiet {
+                            println!("{}", "Pending transcription cancelled".green());
+                        }
+                    }
+                    Response::Error { message, .. } => return Err(message),
+                    _ => return Err("Unexpected response".into()),
+                }
+            }
+        }
+
+        Commands::Mute { .. } | Commands::Unmute => {
+            let muted = match cli.command {
+                Commands::Mute { toggle: true } => {
+                    match client
+                        .request(Request::GetStatus)
+                        .await
+                        .map_err(|e| e.to_string())?
+                    {
+                        Response::Status(status) => !status.muted,
+                        Response::Error { message, .. } => return Err(message),
+                        _ => return Err("Unexpected response".into()),
+                    }
+                }
+                Commands::Mute { toggle: false } => true,
+                _ => false,
+            };
+
+            let response = client
+                .request(Request::SetMuted { muted })
+                .await
+                .map_err(|e| e.to_string())?;
+
+            match response {
+                Response::Ok => {
+                    if !cli.quiet {
+                        if muted {
+                            println!("{}", "Muted: capture is off".yellow());
+                        } else {
+                            println!("{}", "Unmuted".green());
+                        }
+                    }
+                }
+                Response::Error { message, .. } => return Err(message),
+                _ => return Err("Unexpected response".into()),
+            }
+        }
+
+        Commands::Last { type_text, copy } => {
+            let mode = if type_text {
+                Some(OutputMode::Type)
+            } else if copy {
+                Some(OutputMode::Clipboard)
+            } else {
+                None
+            };
+            let response = client
+                .request(Request::RepeatLastOutput { mode })
+                .await
+                .map_err(|e| e.to_string())?;
+
+            match response {
+                Response::RepeatedOutput { text } => {
+                    if matches!(cli.format, OutputFormat::Json) {
+                        println!("{}", serde_json::json!({ "text": text }));
+                    } else if !cli.quiet {
+                        println!("{}", text);
+                    }
+                }
+                Response::Error { message, .. } => return Err(message),
+                _ => return Err("Unexpected response".into()),
+            }
+        }
+
+        Commands::Model { action } => {
+            match action {
+                Some(ModelAction::List) => {
+                    let response = client
+                        .request(Request::ListModels)
+                        .await
+                        .map_err(|e| e.to_string())?;
+
+                    match response {
+                        Response::Models { models } => {
+                            if matches!(cli.format, OutputFormat::Json) {
+                                println!("{}", serde_json::to_string_pretty(&models).unwrap());
+                            } else {
+                                println!(
+                                    "  {:<22} {:<6} {:>9} {:>10}",
+                                    "NAME", "QUANT", "SIZE", "EST. RAM"
+                                );
+                                for variant in &models {
+                                    let marker = if variant.active { "*" } else { " " };
+                                    let line = format!(
+                                        "{} {:<22} {:<6} {:>6} MB {:>7} MB",
+                                        marker,
+                                        variant.name,
+                                        variant.quantization,
+                                        variant.size_mb,
+                                        variant.memory_mb
+                                    );
+                                    if variant.downloaded {
+                                        println!("{} {}", line, "downloaded".green());
+                                    } else {
+                                        println!("{}", line);
+                                    }
+                                }
+                                println!(
+                                    "\n* selected model. Use {} to switch models.",
+                                    "'flowstt model use <name>'".cyan()
+                                );
+                            }
+                        }
+                        Response::Error { message, .. } => return Err(message),
+                        _ => return Err("Unexpected response".into()),
+                    }
+                }
+                Some(ModelAction::Download { name }) => {
+                    if !cli.quiet {
+                        println!("Downloading Whisper model...");
+                    }
+
+                    let response = client
+                        .request(Request::DownloadModel { name })
+                        .await
+                        .map_err(|e| e.to_string())?;
+
+                    match response {
+                        Response::Ok => {
+                            if !cli.quiet {
+                                println!("{}", "Model download started".green());
+                            }
+                        }
+                        Response::Error { message, .. } => {
+                            if message.contains("already downloaded") {
+                                println!("{}", "Model already downloaded".yellow());
+                            } else {
+                                return Err(message);
+                            }
+                        }
+                        _ => return Err("Unexpected response".into()),
+                    }
+                }
+                Some(ModelAction::Use { name }) => {
+                    let response = client
+                        .request(Request::SetConfigValue {
+                            key: "model.name".to_string(),
+                            value: name.clone(),
+                        })
+                        .await
+                        .map_err(|e| e.to_string())?;
+
+                    match response {
+                        Response::ConfigValues { .. } => {
+                            if !cli.quiet {
+                                println!("{} {}", "Using model".green(), name);
+                            }
+                        }
+                        Response::Error { message, .. } => return Err(message),
+                        _ => return Err("Unexpected response".into()),
+                    }
+                }
+                Some(ModelAction::Import { path }) => {
+                    // The service has its own working directory, so send an absolute path
+                    let path = path
+                        .canonicalize()
+                        .map_err(|e| format!("Invalid model file {}: {}", path.display(), e))?;
+                    let response = client
+                        .request(Request::SetConfigValue {
+                            key: "model.path".to_string(),
+                            value: path.to_string_lossy().to_string(),
+                        })
+                        .await
+                        .map_err(|e| e.to_string())?;
+
+                    match response {
+                        Response::ConfigValues { .. } => {
+                            if !cli.quiet {
+                                println!("{} {}", "Using model".green(), path.display());
+                            }
+                        }
+                        Response::Error { message, .. } => return Err(message),
+                        _ => return Err("Unexpected response".into()),
+                    }
+                }
+                Some(action @ (ModelAction::Load | ModelAction::Unload)) => {
+                    let (request, done) = match action {
+                        ModelAction::Load => (Request::LoadModel, "Model loaded"),
+                        _ => (Request::UnloadModel, "Model unloaded"),
+                    };
+                    let response = client.request(request).await.map_err(|e| e.to_string())?;
+
+                    match response {
+                        Response::Ok => {
+                            if !cli.quiet {
+                                println!("{}", done.green());
+                            }
+                        }
+                        Response::Error { message, .. } => return Err(message),
+                        _ => return Err("Unexpected response".into()),
+                    }
+                }
+                None | Some(ModelAction::Status) => {
+                    // Show model status
+                    let response = client
+                        .request(Request::GetModelStatus)
+                        .await
+                        .map_err(|e| e.to_string())?;
+
+                    match response {
+                        Response::ModelStatus(status) => {
+                            if matches!(cli.format, OutputFormat::Json) {
+                                println!("{}", serde_json::to_string_pretty(&status).unwrap());
+                            } else {
+                                let available_str = if status.available {
+                                    "available".green().bold()
+                                } else {
+                                    "not available".red()
+                                };
+                                println!("Model: {}", available_str);
+                                if status.available {
+                                    println!(
+                                        "Loaded: {}",
+                                        if status.loaded { "yes" } else { "no" }
+                                    );
+                                }
+                                if status.custom {
+                                    println!("Path: {} (custom)", status.path.dimmed());
+                                } else {
+                                    println!("Path: {}", status.path.dimmed());
+                                }
+                                if let Some(info) = &status.info {
+                                    println!(
+                                        "Type: {}{}",
+                                        info.model_type,
+                                        if info.multilingual {
+                                            ""
+                                        } else {
+                                            " (English-only)"
+                                        }
+                                    );
+                                    println!("Quantization: {}", info.quantization);
+                                    println!(
+                                        "Size: {:.1} MB",
+                                        info.size_bytes as f64 / (1024.0 * 1024.0)
+                                    );
+                                }
+
+                                if !status.available && status.custom {
+                                    println!(
+                                        "\nRun {} to switch back to the default model",
+                                        "'flowstt config set model.path \"\"'".cyan()
+                                    );
+                                } else if !status.available {
+                                    println!(
+                                        "\nRun {} to download the model, or {} to use an existing one",
+                                        "'flowstt model download'".cyan(),
+                                        "'flowstt model import <path>'".cyan()
+                                    );
+                                }
+                            }
+                        }
+                        Response::Error { message, .. } => return Err(message),
+                        _ => return Err("Unexpected response".into()),
+                    }
+                }
+            }
+        }
+
+        Commands::Bench {
+            file,
+            iterations,
+            compare_cpu,
+        } => {
+            // The service has its own working directory, so send an absolute path
+            let path = file
+                .map(|file| {
+                    file.canonicalize()
+                        .map_err(|e| format!("Invalid file {}: {}", file.display(), e))
+                })
+                .transpose()?
+                .map(|path| path.to_string_lossy().to_string());
+
+            if !cli.quiet && matches!(cli.format, OutputFormat::Text) {
+                println!(
+                    "{}",
+                    format!("Running {} transcriptions per configuration...", iterations).cyan()
+                );
+            }
+            let response = client
+                .request(Request::Benchmark {
+                    path,
+                    iterations,
+                    compare_cpu,
+                })
+                .await
+                .map_err(|e| e.to_string())?;
+
+            match response {
+                Response::Benchmark(result) => {
+                    if matches!(cli.format, OutputFormat::Json) {
+                        println!("{}", serde_json::to_string_pretty(&result).unwrap());
+                    } else {
+                        println!("Model: {}", result.model_path.dimmed());
+                        println!(
+                            "Audio: {} ({:.1}s)",
+                            result.audio_path.dimmed(),
+                            result.audio_duration_ms as f64 / 1000.0
+                        );
+                        for run in &result.runs {
+                            let device = if run.gpu { "GPU" } else { "CPU" };
+                            println!();
+                            println!("{}", device.bold());
+                            println!("  Model load:       {}ms", run.load_ms);
+                            println!(
+                                "  Transcription:    {}ms mean ({}-{}ms)",
+                                run.mean_ms, run.min_ms, run.max_ms
+                            );
+                            let rtf = format!("{:.3}", run.real_time_factor);
+                            let rtf = if run.real_time_factor < 1.0 {
+                                rtf.green()
+                            } else {
+                                rtf.red()
+                            };
+                            println!("  Real-time factor: {}", rtf);
+                            println!("  Tokens/sec:       {:.1}", run.tokens_per_sec);
+                        }
+                    }
+                }
+                Response::Error { message, .. } => return Err(message),
+                _ => return Err("Unexpected response".into()),
+            }
+        }
+
+        Commands::Gpu { action } => match action {
+            Some(GpuAction::Use { index }) => {
+                for request in [
+                    Request::SetGpuDevice { device: index },
+                    Request::SetGpuEnabled { enabled: true },
+                ] {
+                    let response = client.request(request).await.map_err(|e| e.to_string())?;
+                    if let Response::Error { message, .. } = response {
+                        return Err(message);
+                    }
+                }
+                if !cli.quiet {
+                    println!("{}", format!("Using GPU {}", index).green());
+                }
+            }
+            Some(GpuAction::Cpu) => {
+                let response = client
+                    .request(Request::SetGpuEnabled { enabled: false })
+                    .await
+                    .map_err(|e| e.to_string())?;
+                match response {
+                    Response::Ok => {
+                        if !cli.quiet {
+                            println!("{}", "Using CPU only".green());
+                        }
+                    }
+                    Response::Error { message, .. } => return Err(message),
+                    _ => return Err("Unexpected response".into()),
+                }
+            }
+            None => {
+                let response = client
+                    .request(Request::GetCudaStatus)
+                    .await
+                    .map_err(|e| e.to_string())?;
+
+                match response {
+                    Response::CudaStatus(status) => {
+                        if matches!(cli.format, OutputFormat::Json) {
+                            println!("{}", serde_json::to_string_pretty(&status).unwrap());
+                        } else {
+                            let build_str = if status.build_enabled {
+                                "enabled".green()
+                            } else {
+                                "disabled".dimmed()
+                            };
+                            let runtime_str = if status.runtime_available {
+                                "available".green().bold()
+                            } else {
+                                "not available".dimmed()
+                            };
+
+                            println!("GPU Acceleration");
+                            println!("  Build: {}", build_str);
+                            println!("  Runtime: {}", runtime_str);
+                            if status.gpu_enabled {
+                                println!("  Device: {}", status.gpu_device);
+                            } else {
+                                println!("  Device: {}", "CPU only".yellow());
+                            }
+                            println!("\nSystem Info:");
+                            println!("  {}", status.system_info.dimmed());
+                        }
+                    }
+                    Response::Error { message, .. } => return Err(message),
+                    _ => return Err("Unexpected response".into()),
+                }
+            }
+        },
+
+        Commands::History { action } => match action.unwrap_or(HistoryAction::List {
+            limit: 20,
+            tag: None,
+            participant: None,
+            query: None,
+        }) {
+            HistoryAction::List {
+                limit,
+                tag,
+                participant,
+                query,
+            } => {
+                let response = client
+                    .request(Request::ListSessions {
+                        limit: Some(limit),
+                        filter: SessionFilter {
+                            tag,
+                            participant,
+                            query,
+                        },
+                    })
+                    .await
+                    .map_err(|e| e.to_string())?;
+
+                match response {
+                    Response::Sessions { sessions } => {
+                        if matches!(cli.format, OutputFormat::Json) {
+                            println!("{}", serde_json::to_string_pretty(&sessions).unwrap());
+                        } else if sessions.is_empty() {
+                            println!("{}", "No saved transcriptions".yellow());
+                        } else {
+                            for session in sessions {
+                                let text = match &session.title {
+                                    Some(title) => title.bold().to_string(),
+                                    None => session.preview,
+                                };
+                                let tags = session
+                                    .tags
+                                    .iter()
+                                    .map(|tag| format!(" #{}", tag))
+                                    .collect::<String>();
+                                println!(
+                                    "{} {} {}{}",
+                                    session.id.cyan(),
+                                    format!("({:.1}s)", session.duration_ms as f64 / 1000.0)
+                                        .dimmed(),
+                                    text,
+                                    tags.yellow()
+                                );
+                            }
+                        }
+                    }
+                    Response::Error { message, .. } => return Err(message),
+                    _ => return Err("Unexpected response".into()),
+                }
+            }
+            HistoryAction::Show { id } => {
+                let response = client
+                    .request(Request::GetSession { id })
+                    .await
+                    .map_err(|e| e.to_string())?;
+
+                match response {
+                    Response::Session(session) => {
+                        if matches!(cli.format, OutputFormat::Json) {
+                            println!("{}", serde_json::to_string_pretty(&session).unwrap());
+                        } else {
+                            let metadata = &session.metadata;
+                            println!("ID: {}", session.id.cyan());
+                            if let Some(title) = &metadata.title {
+                                println!("Title: {}", title.bold());
+                            }
+                            println!("Date: {}", session.created_at);
+                            println!("Duration: {:.1}s", session.duration_ms as f64 / 1000.0);
+                            if let Some(language) = &session.language {
+                                println!("Language: {}", language);
+                            }
+                            if let Some(model) = &session.model {
+                                println!("Model: {}", model);
+                            }
+                            if !session.versions.is_empty() {
+                                println!("Earlier versions: {}", session.versions.len());
+                            }
+                            if let Some(translation) = &session.translation {
+                                println!("Translation: {}", translation);
+                            }
+                            if let Some(speaker) = &session.speaker {
+                                println!("Speaker: {}", speaker);
+                            }
+                            if let Some(app) = &session.app {
+                                println!("Application: {}", app.name.as_ref().unwrap_or(&app.id));
+                            }
+                            if let Some(source) = &session.source1_id {
+                                println!("Source 1: {}", source.dimmed());
+                            }
+                            if let Some(source) = &session.source2_id {
+                                println!("Source 2: {}", source.dimmed());
+                            }
+                            if let Some(path) = &session.audio_path {
+                                println!("Audio: {}", path.dimmed());
+                            }
+                            if let Some(capture) = &session.capture_id {
+                                println!("Capture: {}", capture.dimmed());
+                            }
+                            if !metadata.participants.is_empty() {
+                                println!("Participants: {}", metadata.participants.join(", "));
+                            }
+                            if !metadata.tags.is_empty() {
+                                println!("Tags: {}", metadata.tags.join(", ").yellow());
+                            }
+                            if let Some(notes) = &metadata.notes {
+                                println!("\n{}\n{}", "Notes:".bold(), notes);
+                            }
+                            if let Some(summary) = &session.summary {
+                                println!("\n{}\n{}", "Summary:".bold(), summary);
+                            }
+                            if session.segments.is_empty() {
+                                println!("\n{}", session.text);
+                            } else {
+                                println!();
+                                print_source_segments(&session.segments);
+                            }
+                        }
+                    }
+                    Response::Error { message, .. } => return Err(message),
+                    _ => return Err("Unexpected response".into()),
+                }
+            }
+            HistoryAction::Capture { id } => {
+                let response = client
+                    .request(Request::GetCapture { id })
+                    .await
+                    .map_err(|e| e.to_string())?;
+
+                match response {
+                    Response::Capture(capture) => {
+                        if matches!(cli.format, OutputFormat::Json) {
+                            println!("{}", serde_json::to_string_pretty(&capture).unwrap());
+                        } else {
+                            println!("ID: {}", capture.id.cyan());
+                            println!("Started: {}", capture.started_at);
+                            println!("Stopped: {}", capture.ended_at);
+                            println!("Duration: {:.1}s", capture.duration_ms as f64 / 1000.0);
+                            println!("Transcriptions: {}", capture.session_ids.join(", "));
+                            for chapter in &capture.chapters {
+                                println!(
+                                    "{} {}",
+                                    format!(
+                                        "[{:.1}s-{:.1}s]",
+                                        chapter.start_ms as f64 / 1000.0,
+                                        chapter.end_ms as f64 / 1000.0
+                                    )
+                                    .dimmed(),
+                                    chapter.title
+                                );
+                            }
+                            if let Some(summary) = &capture.summary {
+                                println!("\n{}\n{}", "Summary:".bold(), summary);
+                            }
+                        }
+                    }
+                    Response::Error { message, .. } => return Err(message),
+                    _ => return Err("Unexpected response".into()),
+                }
+            }
+            HistoryAction::Stats { id } => {
+                let response = client
+                    .request(Request::GetSessionStats { id })
+                    .await
+                    .map_err(|e| e.to_string())?;
+
+                match response {
+                    Response::SessionStats(stats) => {
+                        if matches!(cli.format, OutputFormat::Json) {
+                            println!("{}", serde_json::to_string_pretty(&stats).unwrap());
+                        } else {
+                            println!("Duration: {:.1}s", stats.duration_ms as f64 / 1000.0);
+                            println!(
+                                "Speech: {:.1}s ({:.0}% silence)",
+                                stats.speech_ms as f64 / 1000.0,
+                                stats.silence_ratio * 100.0
+                            );
+                            println!("Words: {}", stats.words);
+                            println!("Pace: {:.0} words/min", stats.words_per_minute);
+                            println!("Segments: {}", stats.segments);
+                            if !stats.speakers.is_empty() {
+                                println!("\n{}", "Speakers:".bold());
+                                for speaker in &stats.speakers {
+                                    println!(
+                                        "  {:<12} {:>6.1}s {:>4.0}%  {} words",
+                                        speaker.speaker.cyan(),
+                                        speaker.speech_ms as f64 / 1000.0,
+                                        speaker.talk_ratio * 100.0,
+                                        speaker.words
+                                    );
+                                }
+                            }
+                        }
+                    }
+                    Response::Error { message, .. } => return Err(message),
+                    _ => return Err("Unexpected response".into()),
+                }
+            }
+            HistoryAction::Tag {
+                id,
+                title,
+                tags,
+                untags,
+                participants,
+                removed_participants,
+                notes,
+            } => {
+                // Edit the current metadata so unmentioned fields are kept
+                let mut metadata = match client
+                    .request(Request::GetSession { id: id.clone() })
+                    .await
+                    .map_err(|e| e.to_string())?
+                {
+                    Response::Session(session) => session.metadata,
+                    Response::Error { message, .. } => return Err(message),
+                    _ => return Err("Unexpected response".into()),
+                };
+                if let Some(title) = title {
+                    metadata.title = Some(title).filter(|t| !t.trim().is_empty());
+                }
+                if let Some(notes) = notes {
+                    metadata.notes = Some(notes).filter(|n| !n.trim().is_empty());
+                }
+                metadata
+                    .tags
+                    .retain(|t| !untags.iter().any(|u| u.eq_ignore_ascii_case(t)));
+                for tag in tags {
+                    if !metadata.has_tag(&tag) {
+                        metadata.tags.push(tag);
+                    }
+                }
+                metadata
+                    .participants
+                    .retain(|p| !removed_participants.contains(p));
+                for participant in participants {
+                    if !metadata.participants.contains(&participant) {
+                        metadata.participants.push(participant);
+                    }
+                }
+
+                let response = client
+                    .request(Request::SetSessionMetadata {
+                        id,
+                        metadata: metadata.clone(),
+                    })
+                    .await
+                    .map_err(|e| e.to_string())?;
+
+                match response {
+                    Response::Ok => {
+                        if matches!(cli.format, OutputFormat::Json) {
+                            println!("{}", serde_json::to_string_pretty(&metadata).unwrap());
+                        } else if !cli.quiet {
+                            println!("{}", "Session updated".green());
+                        }
+                    }
+                    Response::Error { message, .. } => return Err(message),
+                    _ => return Err("Unexpected response".into()),
+                }
+            }
+            HistoryAction::Delete { id } => {
+                let response = client
+                    .request(Request::DeleteSession { id })
+                    .await
+                    .map_err(|e| e.to_string())?;
+
+                match response {
+                    Response::Ok => {
+                        if !cli.quiet {
+                            println!("{}", "Session deleted".green());
+                        }
+                    }
+                    Response::Error { message, .. } => return Err(message),
+                    _ => return Err("Unexpected response".into()),
+                }
+            }
+            HistoryAction::Retranscribe { ids, detach } => {
+                let json = matches!(cli.format, OutputFormat::Json);
+
+                // Listen on this connection and queue the job on another so
+                // no progress is missed
+                let mut control = Client::new();
+                control.connect().await.map_err(|e| e.to_string())?;
+                let events = if detach {
+                    None
+                } else {
+                    Some(client.subscribe().await.map_err(|e| e.to_string())?)
+                };
+
+                let response = control
+                    .request(Request::RetranscribeSessions { ids })
+                    .await
+                    .map_err(|e| e.to_string())?;
+                let job = match response {
+                    Response::Job(job) => job,
+                    Response::Error { message, .. } => return Err(message),
+                    _ => return Err("Unexpected response".into()),
+                };
+                if json {
+                    println!("{}", serde_json::to_string(&job).unwrap());
+                } else if !cli.quiet {
+                    println!("Queued {} with {}", job.id.cyan(), job.model);
+                }
+
+                if let Some(mut events) = events {
+                    loop {
+                        let event = tokio::select! {
+                            event = events.next() => match event {
+                                Some(event) => event.map_err(|e| e.to_string())?,
+                                None => break,
+                            },
+                            _ = tokio::signal::ctrl_c() => break,
+                        };
+                        let progress = match event {
+                            EventType::JobProgress(progress) if progress.id == job.id => progress,
+                            EventType::Shutdown => break,
+                            _ => continue,
+                        };
+                        if json {
+                            println!("{}", serde_json::to_string(&progress).unwrap());
+                        } else if let Some(current) = &progress.current {
+                            println!(
+                                "{} {}",
+                                format!(
+                                    "[{}/{}]",
+                                    progress.completed + 1,
+                                    progress.session_ids.len()
+                                )
+                                .dimmed(),
+                                current
+                            );
+                        }
+                        if progress.is_finished() {
+                            if !json {
+                                print_job(&progress);
+                            }
+                            if let Some(error) = progress.error {
+                                return Err(error);
+                            }
+                            break;
+                        }
+                    }
+                }
+            }
+            HistoryAction::Jobs => {
+                let response = client
+                    .request(Request::ListJobs)
+                    .await
+                    .map_err(|e| e.to_string())?;
+
+                match response {
+                    Response::Jobs { jobs } => {
+                        if matches!(cli.format, OutputFormat::Json) {
+                            println!("{}", serde_json::to_string_pretty(&jobs).unwrap());
+                        } else if jobs.is_empty() {
+                            println!("{}", "No re-transcription jobs".yellow());
+                        } else {
+                            for job in &jobs {
+                                print_job(job);
+                            }
+                        }
+                    }
+                    Response::Error { message, .. } => return Err(message),
+                    _ => return Err("Unexpected response".into()),
+                }
+            }
+            HistoryAction::CancelJob { id } => {
+                let response = client
+                    .request(Request::CancelJob { id })
+                    .await
+                    .map_err(|e| e.to_string())?;
+
+                match response {
+                    Response::Ok => {
+                        if !cli.quiet {
+                            println!("{}", "Job cancelled".green());
+                        }
+                    }
+                    Response::Error { message, .. } => return Err(message),
+                    _ => return Err("Unexpected response".into()),
+                }
+            }
+        },
+
+        Commands::Search {
+            query,
+            from,
+            to,
+            limit,
+        } => {
+            let date_range = (from.is_some() || to.is_some()).then_some(DateRange { from, to });
+            let response = client
+                .request(Request::SearchTranscripts {
+                    query,
+                    date_range,
+                    limit: Some(limit),
+                })
+                .await
+                .map_err(|e| e.to_string())?;
+
+            match response {
+                Response::SearchResults { hits } => {
+                    if matches!(cli.format, OutputFormat::Json) {
+                        println!("{}", serde_json::to_string_pretty(&hits).unwrap());
+                    } else if hits.is_empty() {
+                        println!("{}", "No matching transcriptions".yellow());
+                    } else {
+                        for hit in hits {
+                            let offset = hit
+                                .offset_ms
+                                .map(|ms| format!(" at {}:{:02}", ms / 60_000, ms % 60_000 / 1000))
+                                .unwrap_or_default();
+                            let title = hit
+                                .title
+                                .as_ref()
+                                .map(|title| format!(" {}", title.bold()))
+                                .unwrap_or_default();
+                            println!(
+                                "{} {}{}",
+                                hit.id.cyan(),
+                                format!("({}{})", hit.created_at.get(..10).unwrap_or(""), offset)
+                                    .dimmed(),
+                                title
+                            );
+                            println!("  {}", highlight_snippet(&hit));
+                        }
+                    }
+                }
+                Response::Error { message, .. } => return Err(message),
+                _ => return Err("Unexpected response".into()),
+            }
+        }
+
+        Commands::Play {
+            target,
+            from,
+            detach,
+        } => {
+            let position_ms = (from.max(0.0) * 1000.0) as u64;
+            let file = PathBuf::from(&target);
+            let request = if file.is_file() {
+                let path = if file.is_absolute() {
+                    file
+                } else {
+                    std::env::current_dir()
+                        .map_err(|e| e.to_string())?
+                        .join(file)
+                };
+                Request::PlayFile {
+                    path: path.to_string_lossy().to_string(),
+                    position_ms,
+                }
+            } else {
+                Request::PlaySession {
+                    id: target,
+                    position_ms,
+                }
+            };
+            let json = matches!(cli.format, OutputFormat::Json);
+
+            // Listen before starting so a short recording can't end unseen. The
+            // event stream takes over the connection, so playback is
+            // controlled on another.
+            let mut control = Client::new();
+            control.connect().await.map_err(|e| e.to_string())?;
+            let events = if detach {
+                None
+            } else {
+                Some(client.subscribe().await.map_err(|e| e.to_string())?)
+            };
+
+            let response = control.request(request).await.map_err(|e| e.to_string())?;
+            let status = match response {
+                Response::Playback(status) => status,
+                Response::Error { message, .. } => return Err(message),
+                _ => return Err("Unexpected response".into()),
+            };
+            if json {
+                println!("{}", serde_json::to_string(&status).unwrap());
+            } else if !cli.quiet {
+                println!("Playing {}", status.path.cyan());
+                if !detach {
+                    println!("Press Ctrl+C to stop, or run 'flowstt playback pause'");
+                }
+            }
+
+            if let Some(events) = events {
+                follow_playback(events, &mut control, &status, json).await?;
+            }
+        }
+
+        Commands::CaptureLast { secs, detach } => {
+            let json = matches!(cli.format, OutputFormat::Json);
+
+            // As with play, listen on this connection and send the request on
+            // another so a quick transcript isn't missed
+            let mut control = Client::new();
+            control.connect().await.map_err(|e| e.to_string())?;
+            let events = if detach {
+                None
+            } else {
+                Some(client.subscribe().await.map_err(|e| e.to_string())?)
+            };
+
+            let response = control
+                .request(Request::CaptureLastN { secs })
+                .await
+                .map_err(|e| e.to_string())?;
+            let (path, duration_ms) = match response {
+                Response::Captured { path, duration_ms } => (path, duration_ms),
+                Response::Error { message, .. } => return Err(message),
+                _ => return Err("Unexpected response".into()),
+            };
+            if json {
+                println!(
+                    "{}",
+                    serde_json::json!({ "path": path, "duration_ms": duration_ms })
+                );
+            } else if !cli.quiet {
+                println!(
+                    "Saved {:.1}s to {}",
+                    duration_ms as f64 / 1000.0,
+                    path.cyan()
+                );
+            }
+
+            if let Some(mut events) = events {
+                loop {
+                    let event = tokio::select! {
+                        event = events.next() => match event {
+                            Some(event) => event.map_err(|e| e.to_string())?,
+                            None => break,
+                        },
+                        _ = tokio::signal::ctrl_c() => break,
+                    };
+                    match event {
+                        EventType::TranscriptionComplete(result)
+                            if result.audio_path.as_deref() == Some(path.as_str()) =>
+                        {
+                            if json {
+                                println!("{}", serde_json::to_string(&result).unwrap());
+                            } else {
+                                println!("{}", result.text);
+                            }
+                            break;
+                        }
+                        EventType::Error { message, .. } => return Err(message),
+                        EventType::Shutdown => break,
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        Commands::Recent { minutes, segments } => {
+            let response = client
+                .request(Request::GetRecentTranscript {
+                    seconds: minutes.saturating_mul(60),
+                })
+                .await
+                .map_err(|e| e.to_string())?;
+
+            match response {
+                Response::RecentTranscript(recent) => {
+                    if matches!(cli.format, OutputFormat::Json) {
+                        println!("{}", serde_json::to_string_pretty(&recent).unwrap());
+                    } else if recent.segments.is_empty() {
+                        if !cli.quiet {
+                            println!("Nothing transcribed in the last {} minutes", minutes);
+                        }
+                    } else if segments {
+                        for segment in &recent.segments {
+                            // The time of day from the RFC 3339 timestamp
+                            let time = segment.completed_at.get(11..19).unwrap_or("");
+                            match &segment.speaker {
+                                Some(speaker) => {
+                                    println!(
+                                        "{} {}: {}",
+                                        time.dimmed(),
+                                        speaker.cyan(),
+                                        segment.text
+                                    )
+                                }
+                                None => println!("{} {}", time.dimmed(), segment.text),
+                            }
+                        }
+                    } else {
+                        println!("{}", recent.text);
+                    }
+                }
+                Response::Error { message, .. } => return Err(message),
+                _ => return Err("Unexpected response".into()),
+            }
+        }
+
+        Commands::Playback { action } => {
+            let request = match action {
+                PlaybackAction::Pause => Request::PausePlayback { paused: true },
+                PlaybackAction::Resume => Request::PausePlayback { paused: false },
+                PlaybackAction::Seek { seconds } => Request::SeekPlayback {
+                    position_ms: (seconds.max(0.0) * 1000.0) as u64,
+                },
+                PlaybackAction::Stop => Request::StopPlayback,
+            };
+
+            let response = client.request(request).await.map_err(|e| e.to_string())?;
+
+            match response {
+                Response::Playback(status) => {
+                    if matches!(cli.format, OutputFormat::Json) {
+                        println!("{}", serde_json::to_string_pretty(&status).unwrap());
+                    } else if !cli.quiet {
+                        println!("{} {}", status.path, playback_progress(&status).dimmed());
+                    }
+                }
+                Response::Ok => {
+                    if !cli.quiet {
+                        println!("{}", "Playback stopped".green());
+                    }
+                }
+                Response::Error { message, .. } => return Err(message),
+                _ => return Err("Unexpected response".into()),
+            }
+        }
+
+        Commands::Export { id, format, output } => {
+            let format = match format {
+                ExportFormatArg::Srt => ExportFormat::Srt,
+                ExportFormatArg::Vtt => ExportFormat::Vtt,
+                ExportFormatArg::Json => ExportFormat::Json,
+                ExportFormatArg::Markdown => ExportFormat::Markdown,
+            };
+
+            let response = client
+                .request(Request::GetSession { id: id.clone() })
+                .await
+                .map_err(|e| e.to_string())?;
+            // A capture session exports its combined transcript, with chapters
+            let response = match response {
+                Response::Error {
+                    code: ErrorCode::SessionNotFound { .. },
+                    ..
+                } => client
+                    .request(Request::GetCaptureTranscript { id })
+                    .await
+                    .map_err(|e| e.to_string())?,
+                response => response,
+            };
+
+            let session = match response {
+                Response::Session(session) => session,
+                Response::Error { message, .. } => return Err(message),
+                _ => return Err("Unexpected response".into()),
+            };
+            let contents = export::export_session(&session, format);
+
+            match output {
+                Some(path) => {
+                    std::fs::write(&path, contents)
+                        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+                    if !cli.quiet {
+                        println!(
+                            "{}",
+                            format!("Exported {} to {}", session.id, path.display()).green()
+                        );
+                    }
+                }
+                None => print!("{}", contents),
+            }
+        }
+
+        Commands::Watch { action } => {
+            let request = match action.unwrap_or(WatchAction::List) {
+                WatchAction::List => Request::ListWatchFolders,
+                WatchAction::Add { path } => {
+                    // The service has its own working directory, so send an absolute path
+                    let path = path
+                        .canonicalize()
+                        .map_err(|e| format!("Invalid folder {}: {}", path.display(), e))?;
+                    Request::AddWatchFolder {
+                        path: path.to_string_lossy().to_string(),
+                    }
+                }
+                WatchAction::Remove { path } => Request::RemoveWatchFolder {
+                    path: path
+                        .canonicalize()
+                        .unwrap_or(path)
+                        .to_string_lossy()
+                        .to_string(),
+                },
+            };
+
+            let response = client.request(request).await.map_err(|e| e.to_string())?;
+
+            match response {
+                Response::WatchFolders { folders } => {
+                    if matches!(cli.format, OutputFormat::Json) {
+                        println!("{}", serde_json::to_string_pretty(&folders).unwrap());
+                    } else if folders.is_empty() {
+                        println!("{}", "No watched folders".dimmed());
+                    } else {
+                        for folder in folders {
+                            println!("{}", folder);
+                        }
+                    }
+                }
+                Response::Ok => {
+                    if !cli.quiet {
+                        println!("{}", "Watch folders updated".green());
+                    }
+                }
+                Response::Error { message, .. } => return Err(message),
+                _ => return Err("Unexpected response".into()),
+            }
+        }
+
+        Commands::Dictate { action } => {
+            let request = match action {
+                DictateAction::Start => Request::StartDictation,
+                DictateAction::Stop => Request::StopDictation,
+            };
+
+            let response = client.request(request).await.map_err(|e| e.to_string())?;
+
+            match response {
+                Response::Ok => {
+                    if !cli.quiet {
+                        println!("{}", "Dictation started".green());
+                    }
+                }
+                Response::Dictation { text } => {
+                    if matches!(cli.format, OutputFormat::Json) {
+                        println!("{}", serde_json::json!({ "text": text }));
+                    } else {
+                        println!("{}", text);
+                    }
+                }
+                Response::Error { message, .. } => return Err(message),
+                _ => return Err("Unexpected response".into()),
+            }
+        }
+
+        Commands::Voice { action } => {
+            let request = match action.unwrap_or(CommandsAction::List) {
+                CommandsAction::List => Request::ListCommands,
+                CommandsAction::Add {
+                    phrase,
+                    suppress_text,
+                } => Request::RegisterCommand {
+                    phrase,
+                    suppress_text,
+                },
+                CommandsAction::Remove { phrase } => Request::UnregisterCommand { phrase },
+            };
+
+            let response = client.request(request).await.map_err(|e| e.to_string())?;
+
+            match response {
+                Response::Commands { commands } => {
+                    if matches!(cli.format, OutputFormat::Json) {
+                        println!("{}", serde_json::to_string_pretty(&commands).unwrap());
+                    } else if commands.is_empty() {
+                        println!("{}", "No voice commands".dimmed());
+                    } else {
+                        for command in commands {
+                            if command.suppress_text {
+                                println!("{} {}", command.phrase, "(text suppressed)".dimmed());
+                            } else {
+                                println!("{}", command.phrase);
+                            }
+                        }
+                    }
+                }
+                Response::Ok => {
+                    if !cli.quiet {
+                        println!("{}", "Voice commands updated".green());
+                    }
+                }
+                Response::Error { message, .. } => return Err(message),
+                _ => return Err("Unexpected response".into()),
+            }
+        }
+
+        Commands::Storage { action } => {
+            let request = match action.unwrap_or(StorageAction::Status) {
+                StorageAction::Status => Request::GetStorageStatus,
+                StorageAction::Prune => Request::PruneRecordings,
+            };
+
+            let response = client.request(request).await.map_err(|e| e.to_string())?;
+
+            match response {
+                Response::Storage(status) => {
+                    if matches!(cli.format, OutputFormat::Json) {
+                        println!("{}", serde_json::to_string_pretty(&status).unwrap());
+                        return Ok(());
+                    }
+                    println!("Recordings: {}", status.recordings_dir);
+                    println!(
+                        "  {} files, {}",
+                        status.recording_count,
+                        format_bytes(status.recordings_bytes)
+                    );
+                    println!(
+                        "  {} from saved transcriptions, {}",
+                        status.managed_count,
+                        format_bytes(status.managed_bytes)
+                    );
+                    println!(
+                        "History: {} transcriptions, {}",
+                        status.history_sessions,
+                        format_bytes(status.history_bytes)
+                    );
+
+                    let limit = |limit: Option<String>| {
+                        limit.unwrap_or_else(|| "none".dimmed().to_string())
+                    };
+                    println!(
+                        "Size limit: {}",
+                        limit(status.max_size_mb.map(|mb| format!("{} MB", mb)))
+                    );
+                    println!(
+                        "Age limit: {}",
+                        limit(status.max_age_days.map(|days| format!("{} days", days)))
+                    );
+                    println!(
+                        "Transcripts: {}",
+                        if status.keep_transcripts {
+                            "kept when recordings are removed"
+                        } else {
+                            "removed with their recordings"
+                        }
+                    );
+                }
+                Response::Pruned(result) => {
+                    if matches!(cli.format, OutputFormat::Json) {
+                        println!("{}", serde_json::to_string_pretty(&result).unwrap());
+                    } else if result.files_removed == 0 {
+                        if !cli.quiet {
+                            println!("{}", "Nothing to remove".dimmed());
+                        }
+                    } else {
+                        println!(
+                            "{}",
+                            format!(
+                                "Removed {} recordings, freed {}",
+                                result.files_removed,
+                                format_bytes(result.bytes_freed)
+                            )
+                            .green()
+                        );
+                        if result.sessions_removed > 0 {
+                            println!(
+                                "  {} transcriptions removed with them",
+                                result.sessions_removed
+                            );
+                        }
+                    }
+                }
+                Response::Error { message, .. } => return Err(message),
+                _ => return Err("Unexpected response".into()),
+            }
+        }
+
+        Commands::Hooks { action } => {
+            let request = match action.unwrap_or(HooksAction::List) {
+                HooksAction::List => Request::ListHooks,
+                HooksAction::Add {
+                    name,
+                    command,
+                    url,
+                    no_summary,
+                } => {
+                    let target = match (command, url) {
+                        (Some(command), _) => HookTarget::Command { command },
+                        (None, Some(url)) => HookTarget::Http { url },
+                        (None, None) => return Err("Either --command or --url is required".into()),
+                    };
+                    Request::AddHook {
+                        hook: SessionHook {
+                            name,
+                            target,
+                            summarize: !no_summary,
+                        },
                     }
+                }
+                HooksAction::Remove { name } => Request::RemoveHook { name },
+            };
 
-                    // Stream events until shutdown or Ctrl+C
-                    loop {
-                        // Read next event (blocking)
-                        // TODO: Implement proper event streaming
-                        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
-
-                        // Check if we should exit
-                        // For now, just print status
-                        let status_response = client
-                            .request(Request::GetStatus)
-                            .await
-                            .map_err(|e| e.to_string())?;
-
-                        if let Response::Status(status) = status_response {
-                            if !status.capturing {
-                                if !cli.quiet {
-                                    println!("\n{}", "Transcription stopped".yellow());
-                                }
-                                break;
+            let response = client.request(request).await.map_err(|e| e.to_string())?;
+
+            match response {
+                Response::Hooks { hooks } => {
+                    if matches!(cli.format, OutputFormat::Json) {
+                        println!("{}", serde_json::to_string_pretty(&hooks).unwrap());
+                    } else if hooks.is_empty() {
+                        println!("{}", "No hooks".dimmed());
+                    } else {
+                        for hook in hooks {
+                            let target = match &hook.target {
+                                HookTarget::Command { command } => format!("run {}", command),
+                                HookTarget::Http { url } => format!("POST {}", url),
+                            };
+                            if hook.summarize {
+                                println!("{} {}", hook.name, target.dimmed());
+                            } else {
+                                println!(
+                                    "{} {} {}",
+                                    hook.name,
+                                    target.dimmed(),
+                                    "(no summary)".dimmed()
+                                );
                             }
                         }
                     }
                 }
-                Response::Error { message } => return Err(message),
+                Response::Ok => {
+                    if !cli.quiet {
+                        println!("{}", "Hooks updated".green());
+                    }
+                }
+                Response::Error { message, .. } => return Err(message),
                 _ => return Err("Unexpected response".into()),
             }
         }
 
-        Commands::Status => {
+        Commands::Hotkeys { action } => {
+            let request = match action.unwrap_or(HotkeysAction::List) {
+                HotkeysAction::List => Request::ListHotkeys,
+                HotkeysAction::Set { key, action } => Request::SetHotkey {
+                    binding: HotkeyBinding {
+                        key: key.into(),
+                        action: action.into(),
+                    },
+                },
+                HotkeysAction::Remove { action } => Request::RemoveHotkey {
+                    action: action.into(),
+                },
+            };
+
+            let response = client.request(request).await.map_err(|e| e.to_string())?;
+
+            match response {
+                Response::Hotkeys { bindings } => {
+                    if matches!(cli.format, OutputFormat::Json) {
+                        println!("{}", serde_json::to_string_pretty(&bindings).unwrap());
+                    } else {
+                        for binding in bindings {
+                            println!(
+                                "{:<14} {}",
+                                binding.key.display_name(),
+                                binding.action.description().dimmed()
+                            );
+                        }
+                    }
+                }
+                Response::Ok => {
+                    if !cli.quiet {
+                        println!("{}", "Hotkeys updated".green());
+                    }
+                }
+                Response::Error { message, .. } => return Err(message),
+                _ => return Err("Unexpected response".into()),
+            }
+        }
+
+        Commands::Profile { action } => {
+            let request = match action.unwrap_or(ProfileAction::List) {
+                ProfileAction::List => Request::ListProfiles,
+                ProfileAction::Use { name } => Request::UseProfile { name },
+                ProfileAction::Save {
+                    name,
+                    source1,
+                    source2,
+                    mode,
+                    aec,
+                    model,
+                    output,
+                    apps,
+                } => Request::SaveProfile {
+                    profile: Profile {
+                        name,
+                        source1_id: source1,
+                        source2_id: source2,
+                        recording_mode: mode.map(RecordingMode::from),
+                        aec_enabled: aec,
+                        model_name: model,
+                        output_mode: output.map(|output| match output {
+                            OutputModeArg::Events => OutputMode::EventsOnly,
+                            OutputModeArg::Type => OutputMode::Type,
+                            OutputModeArg::Clipboard => OutputMode::Clipboard,
+                        }),
+                        apps,
+                    },
+                },
+                ProfileAction::Delete { name } => Request::DeleteProfile { name },
+            };
+
+            let response = client.request(request).await.map_err(|e| e.to_string())?;
+
+            match response {
+                Response::Profiles { profiles, active } => {
+                    if matches!(cli.format, OutputFormat::Json) {
+                        println!("{}", serde_json::to_string_pretty(&profiles).unwrap());
+                    } else if profiles.is_empty() {
+                        println!("{}", "No profiles".dimmed());
+                    } else {
+                        for profile in profiles {
+                            let marker = if active.as_deref() == Some(profile.name.as_str()) {
+                                "*".green().bold()
+                            } else {
+                                " ".normal()
+                            };
+                            println!(
+                                "{} {} {}",
+                                marker,
+                                profile.name,
+                                describe_profile(&profile).dimmed()
+                            );
+                        }
+                    }
+                }
+                Response::Ok => {
+                    if !cli.quiet {
+                        println!("{}", "Profiles updated".green());
+                    }
+                }
+                Response::Error { message, .. } => return Err(message),
+                _ => return Err("Unexpected response".into()),
+            }
+        }
+
+        Commands::Focus => {
             let response = client
-                .request(Request::GetStatus)
+                .request(Request::GetFocusedApp)
                 .await
                 .map_err(|e| e.to_string())?;
 
             match response {
-                Response::Status(status) => {
+                Response::FocusedApp(app) => {
                     if matches!(cli.format, OutputFormat::Json) {
-                        println!("{}", serde_json::to_string_pretty(&status).unwrap());
+                        println!("{}", serde_json::to_string_pretty(&app).unwrap());
                     } else {
-                        let capture_str = if status.capturing {
-                            "capturing".green().bold()
-                        } else {
-                            "idle".dimmed()
-                        };
-                        println!("Capture: {}", capture_str);
-
-                        if let Some(error) = &status.error {
-                            println!("Error: {}", error.red());
+                        println!("Application: {}", app.id.cyan());
+                        if let Some(name) = &app.name {
+                            println!("Name: {}", name);
                         }
+                        if let Some(class) = &app.window_class {
+                            println!("Window class: {}", class);
+                        }
+                    }
+                }
+                Response::Error { message, .. } => return Err(message),
+                _ => return Err("Unexpected response".into()),
+            }
+        }
 
-                        if status.capturing {
-                            let speech_str = if status.in_speech {
-                                "speaking".green()
-                            } else {
-                                "silent".dimmed()
-                            };
-                            println!("Speech: {}", speech_str);
-                            println!("Queue depth: {}", status.queue_depth);
+        Commands::Permissions { action } => {
+            let request = match action.unwrap_or(PermissionsAction::Status) {
+                PermissionsAction::Status => Request::GetPermissions,
+                PermissionsAction::Request { permission } => Request::RequestPermission {
+                    permission: permission.into(),
+                },
+            };
+
+            let response = client.request(request).await.map_err(|e| e.to_string())?;
+            match response {
+                Response::Permissions(permissions) => {
+                    if matches!(cli.format, OutputFormat::Json) {
+                        println!("{}", serde_json::to_string_pretty(&permissions).unwrap());
+                    } else {
+                        print_permission("Microphone", permissions.microphone);
+                        print_permission("Screen recording", permissions.screen_recording);
+                        print_permission("Accessibility", permissions.accessibility);
+                    }
+                }
+                Response::Error { message, .. } => return Err(message),
+                _ => return Err("Unexpected response".into()),
+            }
+        }
+
+        Commands::SystemAudio => {
+            let response = client
+                .request(Request::GetSystemAudioSupport)
+                .await
+                .map_err(|e| e.to_string())?;
+            match response {
+                Response::SystemAudioSupport(support) => {
+                    if matches!(cli.format, OutputFormat::Json) {
+                        println!("{}", serde_json::to_string_pretty(&support).unwrap());
+                        return Ok(());
+                    }
+                    if support.native {
+                        println!("Native capture: {}", "available".green());
+                    } else {
+                        println!("Native capture: {}", "unavailable".red());
+                        if let Some(error) = &support.native_error {
+                            println!("  {}", error.dimmed());
                         }
                     }
+                    if !support.loopback_devices.is_empty() {
+                        println!("Loopback devices:");
+                        for device in &support.loopback_devices {
+                            println!("  {}", device.name);
+                            println!("    ID: {}", device.id.dimmed());
+                        }
+                    }
+                    if let Some(setup) = &support.setup {
+                        println!("\n{}", setup);
+                    }
                 }
-                Response::Error { message } => return Err(message),
+                Response::Error { message, .. } => return Err(message),
                 _ => return Err("Unexpected response".into()),
             }
         }
 
-        Commands::Stop => {
-            // Clear sources to stop capture
+        Commands::Test { source, duration } => {
             let response = client
+                .request(Request::GetStatus)
+                .await
+                .map_err(|e| e.to_string())?;
+            if let Response::Status(status) = response {
+                if status.capturing {
+                    return Err(
+                        "Capture is already running; run 'flowstt stop' before testing a device"
+                            .into(),
+                    );
+                }
+            }
+
+            // The event stream takes over the connection, so control capture on another
+            let mut control = Client::new();
+            control.connect().await.map_err(|e| e.to_string())?;
+            if cli.take_control {
+                take_control(&mut control).await?;
+            }
+            let events = client.subscribe().await.map_err(|e| e.to_string())?;
+
+            for request in [
+                Request::AppReady,
+                Request::SetSources {
+                    source1_id: Some(source),
+                    source2_id: None,
+                    source1_channels: None,
+                    source2_channels: None,
+                },
+            ] {
+                let response = control.request(request).await.map_err(|e| e.to_string())?;
+                if let Response::Error { message, .. } = response {
+                    return Err(message);
+                }
+            }
+
+            let json = matches!(cli.format, OutputFormat::Json);
+            let result = run_device_test(events, Duration::from_secs(duration), json).await;
+
+            let _ = control
                 .request(Request::SetSources {
                     source1_id: None,
                     source2_id: None,
+                    source1_channels: None,
+                    source2_channels: None,
                 })
+                .await;
+
+            let peak_db = result?;
+            if !json {
+                println!();
+                match peak_db {
+                    None => return Err("No audio received from the device".into()),
+                    Some(peak) if peak <= METER_FLOOR_DB => {
+                        println!("{}", "Device is silent".yellow())
+                    }
+                    Some(peak) => println!("{}", format!("Peak level: {:.1} dB", peak).green()),
+                }
+            }
+        }
+
+        Commands::Calibrate { source, duration } => {
+            let response = client
+                .request(Request::GetStatus)
                 .await
                 .map_err(|e| e.to_string())?;
+            let capturing = matches!(response, Response::Status(status) if status.capturing);
 
-            match response {
-                Response::Ok => {
-                    if !cli.quiet {
-                        println!("{}", "Capture stopped".green());
+            if !capturing {
+                let source = source
+                    .ok_or("Capture isn't running; use --source to choose a device to calibrate")?;
+                for request in [
+                    Request::AppReady,
+                    Request::SetSources {
+                        source1_id: Some(source),
+                        source2_id: None,
+                        source1_channels: None,
+                        source2_channels: None,
+                    },
+                ] {
+                    let response = client.request(request).await.map_err(|e| e.to_string())?;
+                    if let Response::Error { message, .. } = response {
+                        return Err(message);
                     }
                 }
-                Response::Error { message } => return Err(message),
-                _ => return Err("Unexpected response".into()),
             }
-        }
 
-        Commands::Model { action } => {
-            match action {
-                Some(ModelAction::Download) => {
-                    if !cli.quiet {
-                        println!("Downloading Whisper model...");
+            if !cli.quiet && matches!(cli.format, OutputFormat::Text) {
+                println!(
+                    "{}",
+                    format!("Measuring ambient noise for {}s, stay quiet...", duration).cyan()
+                );
+            }
+            let response = client
+                .request(Request::CalibrateNoise {
+                    duration_secs: duration,
+                })
+                .await
+                .map_err(|e| e.to_string());
+
+            if !capturing {
+                let _ = client
+                    .request(Request::SetSources {
+                        source1_id: None,
+                        source2_id: None,
+                        source1_channels: None,
+                        source2_channels: None,
+                    })
+                    .await;
+            }
+
+            match response? {
+                Response::NoiseCalibration(calibration) => {
+                    if matches!(cli.format, OutputFormat::Json) {
+                        println!("{}", serde_json::to_string_pretty(&calibration).unwrap());
+                    } else {
+                        println!("Noise floor:        {:.1} dB", calibration.noise_floor_db);
+                        println!(
+                            "Voiced threshold:   {:.1} dB",
+                            calibration.config.voiced_threshold_db
+                        );
+                        println!(
+                            "Whisper threshold:  {:.1} dB",
+                            calibration.config.whisper_threshold_db
+                        );
+                        println!(
+                            "Lookback threshold: {:.1} dB",
+                            calibration.config.lookback_threshold_db
+                        );
+                        println!("{}", "Speech detector calibrated".green());
                     }
+                }
+                Response::Error { message, .. } => return Err(message),
+                _ => return Err("Unexpected response".into()),
+            }
+        }
 
+        Commands::Aec { action } => {
+            let request = match action {
+                None => Request::GetAecSettings,
+                Some(AecAction::Set {
+                    delay,
+                    filter_length,
+                }) => {
                     let response = client
-                        .request(Request::DownloadModel)
+                        .request(Request::GetAecSettings)
                         .await
                         .map_err(|e| e.to_string())?;
+                    let mut settings = match response {
+                        Response::AecSettings(settings) => settings,
+                        Response::Error { message, .. } => return Err(message),
+                        _ => return Err("Unexpected response".into()),
+                    };
+                    if let Some(delay) = delay {
+                        settings.initial_delay_ms = delay;
+                    }
+                    if let Some(filter_length) = filter_length {
+                        settings.filter_length_ms = filter_length;
+                    }
+                    Request::SetAecSettings { settings }
+                }
+                Some(AecAction::Estimate { duration }) => {
+                    if !cli.quiet && matches!(cli.format, OutputFormat::Text) {
+                        println!(
+                            "{}",
+                            format!(
+                                "Measuring echo delay for {}s, keep audio playing...",
+                                duration
+                            )
+                            .cyan()
+                        );
+                    }
+                    Request::EstimateAecDelay {
+                        duration_secs: duration,
+                    }
+                }
+            };
 
-                    match response {
-                        Response::Ok => {
-                            if !cli.quiet {
-                                println!("{}", "Model download started".green());
-                            }
-                        }
-                        Response::Error { message } => {
-                            if message.contains("already downloaded") {
-                                println!("{}", "Model already downloaded".yellow());
-                            } else {
-                                return Err(message);
-                            }
+            let response = client.request(request).await.map_err(|e| e.to_string())?;
+
+            match response {
+                Response::AecSettings(settings) => {
+                    if matches!(cli.format, OutputFormat::Json) {
+                        println!("{}", serde_json::to_string_pretty(&settings).unwrap());
+                    } else {
+                        println!("Initial delay: {} ms", settings.initial_delay_ms);
+                        println!("Filter length: {} ms", settings.filter_length_ms);
+                    }
+                }
+                Response::Error { message, .. } => return Err(message),
+                _ => return Err("Unexpected response".into()),
+            }
+        }
+
+        Commands::Config { action } => {
+            let (request, key) = match action.unwrap_or(ConfigAction::Get { key: None }) {
+                ConfigAction::Get { key } => (Request::GetConfig, key),
+                ConfigAction::Set { key, mut value } => {
+                    if key == "recordings.dir" && !value.is_empty() {
+                        let dir = PathBuf::from(&value);
+                        if dir.is_relative() {
+                            let cwd = std::env::current_dir().map_err(|e| e.to_string())?;
+                            value = cwd.join(dir).to_string_lossy().to_string();
                         }
-                        _ => return Err("Unexpected response".into()),
                     }
+                    (
+                        Request::SetConfigValue {
+                            key: key.clone(),
+                            value,
+                        },
+                        Some(key),
+                    )
                 }
-                None => {
-                    // Show model status
-                    let response = client
-                        .request(Request::GetModelStatus)
-                        .await
-                        .map_err(|e| e.to_string())?;
+            };
 
-                    match response {
-                        Response::ModelStatus(status) => {
-                            if matches!(cli.format, OutputFormat::Json) {
-                                println!("{}", serde_json::to_string_pretty(&status).unwrap());
-                            } else {
-                                let available_str = if status.available {
-                                    "available".green().bold()
-                                } else {
-                                    "not available".red()
-                                };
-                                println!("Model: {}", available_str);
-                                println!("Path: {}", status.path.dimmed());
+            let response = client.request(request).await.map_err(|e| e.to_string())?;
 
-                                if !status.available {
-                                    println!(
-                                        "\nRun {} to download the model",
-                                        "'flowstt model download'".cyan()
-                                    );
-                                }
-                            }
+            match response {
+                Response::ConfigValues { mut values } => {
+                    if let Some(key) = key {
+                        let value = values
+                            .remove(&key)
+                            .ok_or_else(|| format!("Unknown setting: {}", key))?;
+                        values = [(key, value)].into_iter().collect();
+                    }
+                    if matches!(cli.format, OutputFormat::Json) {
+                        println!("{}", serde_json::to_string_pretty(&values).unwrap());
+                    } else {
+                        for (key, value) in values {
+                            println!("{} = {}", key, value);
                         }
-                        Response::Error { message } => return Err(message),
-                        _ => return Err("Unexpected response".into()),
                     }
                 }
+                Response::Error { message, .. } => return Err(message),
+                _ => return Err("Unexpected response".into()),
             }
         }
 
-        Commands::Gpu => {
+        Commands::Metrics => {
             let response = client
-                .request(Request::GetCudaStatus)
+                .request(Request::GetMetrics)
                 .await
                 .map_err(|e| e.to_string())?;
 
             match response {
-                Response::CudaStatus(status) => {
+                Response::Metrics(metrics) => {
                     if matches!(cli.format, OutputFormat::Json) {
-                        println!("{}", serde_json::to_string_pretty(&status).unwrap());
+                        println!("{}", serde_json::to_string_pretty(&metrics).unwrap());
                     } else {
-                        let build_str = if status.build_enabled {
+                        let uptime = metrics.uptime_secs;
+                        println!(
+                            "Uptime: {}h {:02}m {:02}s",
+                            uptime / 3600,
+                            uptime / 60 % 60,
+                            uptime % 60
+                        );
+                        println!("Samples processed: {}", metrics.samples_processed);
+                        let dropped = metrics.dropped_frames.to_string();
+                        if metrics.dropped_frames > 0 {
+                            println!("Dropped frames: {}", dropped.red());
+                        } else {
+                            println!("Dropped frames: {}", dropped);
+                        }
+                        println!("Transcriptions: {}", metrics.transcriptions);
+                        let peak = metrics
+                            .queue_depth_history
+                            .iter()
+                            .map(|s| s.depth)
+                            .max()
+                            .unwrap_or(0);
+                        println!(
+                            "Queue depth: {} (recent peak {})",
+                            metrics.queue_depth, peak
+                        );
+                        match &metrics.latency {
+                            Some(latency) => println!(
+                                "Latency: p50 {} ms, p90 {} ms, p99 {} ms ({} segments)",
+                                latency.p50_ms, latency.p90_ms, latency.p99_ms, latency.samples
+                            ),
+                            None => println!("Latency: {}", "no transcriptions yet".dimmed()),
+                        }
+                        let aec = if metrics.aec_enabled {
                             "enabled".green()
                         } else {
                             "disabled".dimmed()
                         };
-                        let runtime_str = if status.runtime_available {
-                            "available".green().bold()
-                        } else {
-                            "not available".dimmed()
-                        };
-
-                        println!("GPU Acceleration");
-                        println!("  Build: {}", build_str);
-                        println!("  Runtime: {}", runtime_str);
-                        println!("\nSystem Info:");
-                        println!("  {}", status.system_info.dimmed());
+                        println!("Echo cancellation: {}", aec);
+                        if let Some(aec) = &metrics.aec_metrics {
+                            println!(
+                                "  ERLE {:.1} dB, ERL {:.1} dB, delay {} ms",
+                                aec.erle_db, aec.erl_db, aec.delay_ms
+                            );
+                        }
                     }
                 }
-                Response::Error { message } => return Err(message),
+                Response::Error { message, .. } => return Err(message),
                 _ => return Err("Unexpected response".into()),
             }
         }
@@ -433,6 +3629,68 @@ async fn run(cli: Cli) -> Result<(), String> {
             Err(e) => return Err(e.to_string()),
         },
 
+        Commands::Logs {
+            level: Some(level), ..
+        } => {
+            let response = client
+                .request(Request::SetLogLevel {
+                    level: level.clone(),
+                })
+                .await
+                .map_err(|e| e.to_string())?;
+
+            match response {
+                Response::Ok => {
+                    if !cli.quiet {
+                        println!("{} {}", "Log level set to".green(), level);
+                    }
+                }
+                Response::Error { message, .. } => return Err(message),
+                _ => return Err("Unexpected response".into()),
+            }
+        }
+
+        Commands::Fault { action } => {
+            let fault = match action {
+                FaultAction::Drop { source, count } => AudioFault::DropBuffers {
+                    source: source.into(),
+                    count,
+                },
+                FaultAction::Delay {
+                    source,
+                    count,
+                    delay_ms,
+                } => AudioFault::DelayBuffers {
+                    source: source.into(),
+                    count,
+                    delay_ms,
+                },
+                FaultAction::Disconnect {
+                    source,
+                    duration_ms,
+                } => AudioFault::Disconnect {
+                    source: source.into(),
+                    duration_ms,
+                },
+                FaultAction::Clear => AudioFault::Clear,
+            };
+
+            let response = client
+                .request(Request::InjectFault { fault })
+                .await
+                .map_err(|e| e.to_string())?;
+
+            match response {
+                Response::Ok => {
+                    if !cli.quiet {
+                        println!("{} {:?}", "Injected".yellow(), fault);
+                    }
+                }
+                Response::Error { message, .. } => return Err(message),
+                _ => return Err("Unexpected response".into()),
+            }
+        }
+
         Commands::Shutdown => {
             let response = client
                 .request(Request::Shutdown)
@@ -445,12 +3703,12 @@ async fn run(cli: Cli) -> Result<(), String> {
                         println!("{}", "Service shutdown initiated".green());
                     }
                 }
-                Response::Error { message } => return Err(message),
+                Response::Error { message, .. } => return Err(message),
                 _ => return Err("Unexpected response".into()),
             }
         }
 
-        Commands::Version => {
+        Commands::Version | Commands::Service { .. } | Commands::Logs { level: None, .. } => {
             // Already handled above
             unreachable!()
         }
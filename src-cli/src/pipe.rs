@@ -0,0 +1,445 @@
+//! Transcription of audio piped to stdin (`flowstt transcribe --stdin`).
+//!
+//! Audio is read as it arrives, either as a WAV stream or as raw signed
+//! 16-bit little-endian PCM, and cut into chunks of up to `MAX_CHUNK_SECS`
+//! at the quietest moment near their end so words aren't split. Each chunk is
+//! written to a temporary WAV file for the service to transcribe, and its
+//! text is printed as soon as it is ready: a line of text, or a JSON object
+//! per line with `--format json`. Nothing is saved to history or typed.
+
+use std::path::{Path, PathBuf};
+
+use flowstt_common::ipc::{Request, Response};
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use crate::client::Client;
+
+/// Longest chunk sent for transcription, Whisper's window
+const MAX_CHUNK_SECS: u32 = 30;
+
+/// Chunks are cut at the quietest moment after this many seconds
+const MIN_CHUNK_SECS: u32 = 20;
+
+/// Length of the windows compared when looking for a quiet moment
+const QUIET_WINDOW_MS: u32 = 100;
+
+/// Bytes read from stdin at a time
+const READ_SIZE: usize = 64 * 1024;
+
+/// Format of raw PCM on stdin; WAV input carries its own.
+#[derive(Debug, Clone, Copy)]
+pub struct PcmFormat {
+    pub sample_rate: u32,
+    pub channels: u16,
+}
+
+/// Sample encoding of the audio stream
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Encoding {
+    Int16,
+    Float32,
+}
+
+impl Encoding {
+    fn sample_size(self) -> usize {
+        match self {
+            Encoding::Int16 => 2,
+            Encoding::Float32 => 4,
+        }
+    }
+}
+
+/// Transcribe stdin until it ends.
+pub async fn run(client: &mut Client, raw: PcmFormat, json: bool) -> Result<(), String> {
+    let mut stdin = tokio::io::stdin();
+
+    let mut magic = [0u8; 4];
+    let mut pending = Vec::new();
+    let read = read_full(&mut stdin, &mut magic).await?;
+    let (format, encoding, mut remaining) = if read == 4 && &magic == b"RIFF" {
+        read_wav_header(&mut stdin).await?
+    } else {
+        pending.extend_from_slice(&magic[..read]);
+        (raw, Encoding::Int16, None)
+    };
+    if format.sample_rate == 0 || format.channels == 0 {
+        return Err("Audio on stdin has no sample rate or channels".into());
+    }
+
+    let channels = format.channels as usize;
+    let rate = format.sample_rate as usize;
+    let mut samples: Vec<i16> = Vec::new();
+    let mut chunk_start_frame: u64 = 0;
+    let mut chunk_index = 0;
+    let mut buffer = vec![0u8; READ_SIZE];
+    loop {
+        let limit = remaining.map_or(READ_SIZE, |r: u64| r.min(READ_SIZE as u64) as usize);
+        let n = if limit == 0 {
+            0
+        } else {
+            stdin
+                .read(&mut buffer[..limit])
+                .await
+                .map_err(|e| format!("Failed to read stdin: {}", e))?
+        };
+        if let Some(remaining) = remaining.as_mut() {
+            *remaining -= n as u64;
+        }
+        pending.extend_from_slice(&buffer[..n]);
+        decode_pending(&mut pending, encoding, &mut samples);
+
+        let ended = n == 0;
+        while samples.len() / channels >= MAX_CHUNK_SECS as usize * rate
+            || (ended && samples.len() >= channels)
+        {
+            let frames = if ended && samples.len() / channels < MAX_CHUNK_SECS as usize * rate {
+                samples.len() / channels
+            } else {
+                quiet_cut(&samples, format)
+            };
+            let chunk: Vec<i16> = samples.drain(..frames * channels).collect();
+            let start_ms = chunk_start_frame * 1000 / rate as u64;
+            chunk_start_frame += frames as u64;
+            let end_ms = chunk_start_frame * 1000 / rate as u64;
+            transcribe_chunk(client, &chunk, format, chunk_index, start_ms, end_ms, json).await?;
+            chunk_index += 1;
+        }
+        if ended {
+            return Ok(());
+        }
+    }
+}
+
+/// Have the service transcribe a chunk and print its text.
+async fn transcribe_chunk(
+    client: &mut Client,
+    samples: &[i16],
+    format: PcmFormat,
+    index: usize,
+    start_ms: u64,
+    end_ms: u64,
+    json: bool,
+) -> Result<(), String> {
+    let path = chunk_path(index);
+    write_wav(&path, samples, format)?;
+    let response = client
+        .request(Request::TranscribeFile {
+            path: path.to_string_lossy().to_string(),
+        })
+        .await;
+    let _ = std::fs::remove_file(&path);
+
+    let result = match response.map_err(|e| e.to_string())? {
+        Response::Transcribed(result) => result,
+        Response::Error { message, .. } => return Err(message),
+        _ => return Err("Unexpected response".into()),
+    };
+    let text = result.text.trim();
+    if text.is_empty() {
+        return Ok(());
+    }
+    if json {
+        println!(
+            "{}",
+            serde_json::json!({
+                "start_ms": start_ms,
+                "end_ms": end_ms,
+                "text": text,
+                "language": result.language,
+            })
+        );
+    } else {
+        println!("{}", text);
+    }
+    Ok(())
+}
+
+/// Temporary file a chunk is handed to the service in
+fn chunk_path(index: usize) -> PathBuf {
+    std::env::temp_dir().join(format!(
+        "flowstt-stdin-{}-{}.wav",
+        std::process::id(),
+        index
+    ))
+}
+
+fn write_wav(path: &Path, samples: &[i16], format: PcmFormat) -> Result<(), String> {
+    let spec = hound::WavSpec {
+        channels: format.channels,
+        sample_rate: format.sample_rate,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let write = || -> Result<(), hound::Error> {
+        let mut writer = hound::WavWriter::create(path, spec)?;
+        for &sample in samples {
+            writer.write_sample(sample)?;
+        }
+        writer.finalize()
+    };
+    write().map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Frames to cut a full chunk after: the middle of its quietest window
+/// between `MIN_CHUNK_SECS` and `MAX_CHUNK_SECS`.
+fn quiet_cut(samples: &[i16], format: PcmFormat) -> usize {
+    let channels = format.channels as usize;
+    let rate = format.sample_rate as usize;
+    let window = (rate * QUIET_WINDOW_MS as usize / 1000).max(1);
+    let max = MAX_CHUNK_SECS as usize * rate;
+    let mut best = (max, f64::MAX);
+    let mut start = MIN_CHUNK_SECS as usize * rate;
+    while start + window <= max {
+        let energy: f64 = samples[start * channels..(start + window) * channels]
+            .iter()
+            .map(|&s| (s as f64) * (s as f64))
+            .sum();
+        if energy < best.1 {
+            best = (start + window / 2, energy);
+        }
+        start += window;
+    }
+    best.0
+}
+
+/// Decode the whole samples in `pending` into `samples`, keeping a sample
+/// split across reads for the next one.
+fn decode_pending(pending: &mut Vec<u8>, encoding: Encoding, samples: &mut Vec<i16>) {
+    let usable = pending.len() - pending.len() % encoding.sample_size();
+    decode(&pending[..usable], encoding, samples);
+    pending.drain(..usable);
+}
+
+/// Append decoded samples, as 16-bit integers, to `samples`.
+fn decode(bytes: &[u8], encoding: Encoding, samples: &mut Vec<i16>) {
+    match encoding {
+        Encoding::Int16 => samples.extend(
+            bytes
+                .chunks_exact(2)
+                .map(|b| i16::from_le_bytes([b[0], b[1]])),
+        ),
+        Encoding::Float32 => samples.extend(bytes.chunks_exact(4).map(|b| {
+            let sample = f32::from_le_bytes([b[0], b[1], b[2], b[3]]);
+            (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16
+        })),
+    }
+}
+
+/// Read the rest of a WAV header after "RIFF", up to the start of the audio.
+///
+/// Returns the format and the length of the audio, if the header gives one.
+/// Streamed WAV (from ffmpeg, for instance) can't know its length up front
+/// and leaves it unset, so the audio then runs to the end of the input.
+async fn read_wav_header(
+    reader: &mut (impl AsyncRead + Unpin),
+) -> Result<(PcmFormat, Encoding, Option<u64>), String> {
+    let mut riff = [0u8; 8];
+    if read_full(reader, &mut riff).await? < 8 || &riff[4..] != b"WAVE" {
+        return Err("stdin is not a WAV file".into());
+    }
+
+    let mut format = None;
+    loop {
+        let mut header = [0u8; 8];
+        if read_full(reader, &mut header).await? < 8 {
+            return Err("WAV input has no audio".into());
+        }
+        let size = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+        // Chunks are padded to an even length
+        let padded = size as usize + (size as usize & 1);
+        match &header[..4] {
+            b"data" => {
+                let (format, encoding) =
+                    format.ok_or("WAV input has no format before its audio")?;
+                let length = match size {
+                    0 | u32::MAX => None,
+                    size => Some(size as u64),
+                };
+                return Ok((format, encoding, length));
+            }
+            b"fmt " => {
+                let mut body = vec![0u8; padded];
+                if read_full(reader, &mut body).await? < padded || padded < 16 {
+                    return Err("WAV input has a truncated format".into());
+                }
+                let mut tag = u16::from_le_bytes([body[0], body[1]]);
+                // WAVE_FORMAT_EXTENSIBLE keeps the real tag in its sub-format
+                if tag == 0xFFFE && body.len() >= 26 {
+                    tag = u16::from_le_bytes([body[24], body[25]]);
+                }
+                let bits = u16::from_le_bytes([body[14], body[15]]);
+                let encoding = match (tag, bits) {
+                    (1, 16) => Encoding::Int16,
+                    (3, 32) => Encoding::Float32,
+                    _ => {
+                        return Err(format!(
+                            "Unsupported WAV encoding (format {}, {} bits); use 16-bit PCM or 32-bit float",
+                            tag, bits
+                        ))
+                    }
+                };
+                format = Some((
+                    PcmFormat {
+                        channels: u16::from_le_bytes([body[2], body[3]]),
+                        sample_rate: u32::from_le_bytes([body[4], body[5], body[6], body[7]]),
+                    },
+                    encoding,
+                ));
+            }
+            _ => {
+                let mut body = vec![0u8; padded];
+                read_full(reader, &mut body).await?;
+            }
+        }
+    }
+}
+
+/// Fill `buf` unless the input ends first; returns the bytes read.
+async fn read_full(reader: &mut (impl AsyncRead + Unpin), buf: &mut [u8]) -> Result<usize, String> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        let n = reader
+            .read(&mut buf[filled..])
+            .await
+            .map_err(|e| format!("Failed to read stdin: {}", e))?;
+        if n == 0 {
+            break;
+        }
+        filled += n;
+    }
+    Ok(filled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A RIFF chunk with its header, padded to an even length
+    fn chunk(id: &[u8; 4], body: &[u8]) -> Vec<u8> {
+        let mut bytes = id.to_vec();
+        bytes.extend_from_slice(&(body.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(body);
+        if body.len() % 2 == 1 {
+            bytes.push(0);
+        }
+        bytes
+    }
+
+    fn fmt(tag: u16, channels: u16, sample_rate: u32, bits: u16) -> Vec<u8> {
+        let mut body = tag.to_le_bytes().to_vec();
+        body.extend_from_slice(&channels.to_le_bytes());
+        body.extend_from_slice(&sample_rate.to_le_bytes());
+        body.extend_from_slice(&[0; 6]);
+        body.extend_from_slice(&bits.to_le_bytes());
+        body
+    }
+
+    /// A WAV stream after its "RIFF" magic: size, "WAVE", then `chunks`
+    fn wav(chunks: &[Vec<u8>]) -> Vec<u8> {
+        let mut bytes = 0u32.to_le_bytes().to_vec();
+        bytes.extend_from_slice(b"WAVE");
+        for chunk in chunks {
+            bytes.extend_from_slice(chunk);
+        }
+        bytes
+    }
+
+    #[tokio::test]
+    async fn test_wav_header_skips_padded_chunks() {
+        let audio = [1, 0, 2, 0, 3, 0, 4, 0];
+        let input = wav(&[
+            chunk(b"LIST", b"odd"),
+            chunk(b"fmt ", &fmt(1, 2, 16000, 16)),
+            chunk(b"data", &audio),
+        ]);
+        let mut reader = input.as_slice();
+        let (format, encoding, length) = read_wav_header(&mut reader).await.unwrap();
+        assert_eq!((format.sample_rate, format.channels), (16000, 2));
+        assert_eq!(encoding, Encoding::Int16);
+        assert_eq!(length, Some(8));
+        // The reader is left at the start of the audio
+        assert_eq!(reader, audio);
+    }
+
+    #[tokio::test]
+    async fn test_wav_header_reads_extensible_float() {
+        let mut body = fmt(0xFFFE, 1, 48000, 32);
+        body.extend_from_slice(&22u16.to_le_bytes());
+        body.extend_from_slice(&[0; 6]);
+        body.extend_from_slice(&3u16.to_le_bytes());
+        body.extend_from_slice(&[0; 14]);
+        for streamed in [0, u32::MAX] {
+            let mut data = b"data".to_vec();
+            data.extend_from_slice(&streamed.to_le_bytes());
+            let input = wav(&[chunk(b"fmt ", &body), data]);
+            let (format, encoding, length) = read_wav_header(&mut input.as_slice()).await.unwrap();
+            assert_eq!((format.sample_rate, format.channels), (48000, 1));
+            assert_eq!(encoding, Encoding::Float32);
+            // Streamed WAV runs to the end of the input
+            assert_eq!(length, None);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_wav_header_rejects_bad_input() {
+        let mut not_wave = 0u32.to_le_bytes().to_vec();
+        not_wave.extend_from_slice(b"AVI ");
+        let no_format = wav(&[chunk(b"data", &[0; 4])]);
+        let unsupported = wav(&[chunk(b"fmt ", &fmt(1, 1, 16000, 24))]);
+        let truncated = wav(&[chunk(b"fmt ", &[1, 0, 1, 0])]);
+        let no_audio = wav(&[chunk(b"fmt ", &fmt(1, 1, 16000, 16))]);
+        for input in [not_wave, no_format, unsupported, truncated, no_audio] {
+            assert!(read_wav_header(&mut input.as_slice()).await.is_err());
+        }
+    }
+
+    #[test]
+    fn test_decode_converts_float_to_int16() {
+        let bytes: Vec<u8> = [1.0f32, -1.0, 2.0, 0.0]
+            .iter()
+            .flat_map(|s| s.to_le_bytes())
+            .collect();
+        let mut samples = Vec::new();
+        decode(&bytes, Encoding::Float32, &mut samples);
+        assert_eq!(samples, [i16::MAX, -i16::MAX, i16::MAX, 0]);
+    }
+
+    #[test]
+    fn test_samples_split_across_reads() {
+        let mut pending = vec![0x01, 0x00, 0xFF, 0x7F, 0x00];
+        let mut samples = Vec::new();
+        decode_pending(&mut pending, Encoding::Int16, &mut samples);
+        assert_eq!(samples, [1, i16::MAX]);
+        assert_eq!(pending, [0x00]);
+
+        // The next read completes the split sample
+        pending.extend_from_slice(&[0x80, 0x02]);
+        decode_pending(&mut pending, Encoding::Int16, &mut samples);
+        assert_eq!(samples, [1, i16::MAX, i16::MIN]);
+        assert_eq!(pending, [0x02]);
+
+        // A stream ending mid-sample leaves the partial sample undecoded
+        let mut pending = 0.5f32.to_le_bytes().to_vec();
+        pending.extend_from_slice(&[0, 0]);
+        let mut samples = Vec::new();
+        decode_pending(&mut pending, Encoding::Float32, &mut samples);
+        assert_eq!(samples, [(0.5 * i16::MAX as f32) as i16]);
+        assert_eq!(pending.len(), 2);
+    }
+
+    #[test]
+    fn test_quiet_cut_picks_the_silent_window() {
+        // At 1kHz a window is 100 frames
+        for channels in [1u16, 2] {
+            let format = PcmFormat {
+                sample_rate: 1000,
+                channels,
+            };
+            let frames = MAX_CHUNK_SECS as usize * 1000;
+            let mut samples = vec![1000i16; frames * channels as usize];
+            let silent = 25_000 * channels as usize..25_100 * channels as usize;
+            samples[silent].fill(0);
+            assert_eq!(quiet_cut(&samples, format), 25_050);
+        }
+    }
+}
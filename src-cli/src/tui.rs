@@ -0,0 +1,519 @@
+//! Full-screen dashboard (`flowstt tui`).
+//!
+//! Shows the service's devices, live input levels, whether speech is being
+//! heard and a scrolling transcript, with keys to choose the sources and start
+//! or stop capture. Events arrive on a second connection subscribed to the
+//! service while requests go over the first; key presses are read on a
+//! thread of their own, so neither waits on the other.
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use flowstt_common::ipc::{EventType, Request, Response};
+use flowstt_common::{AudioDevice, AudioLevel, AudioSourceType, SourceLabel, SourceLevels};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Gauge, List, ListItem, ListState, Paragraph};
+use ratatui::Frame;
+use tokio::sync::mpsc;
+
+use crate::client::Client;
+use crate::METER_FLOOR_DB;
+
+/// Transcriptions kept for scrolling back
+const TRANSCRIPT_LIMIT: usize = 500;
+
+/// How long the key reader waits for input before checking whether the
+/// dashboard has closed
+const KEY_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A completed transcription, labeled with its speaker or source
+struct TranscriptEntry {
+    label: Option<String>,
+    text: String,
+}
+
+/// What the dashboard shows
+struct App {
+    devices: Vec<AudioDevice>,
+    cursor: ListState,
+    source1: Option<String>,
+    source2: Option<String>,
+    capturing: bool,
+    in_speech: bool,
+    muted: bool,
+    levels: SourceLevels,
+    transcript: VecDeque<TranscriptEntry>,
+    /// Interim text of the segment being spoken or decoded
+    partial: Option<String>,
+    /// Outcome of the last action, or the last error from the service
+    message: Option<String>,
+}
+
+impl App {
+    fn new() -> Self {
+        Self {
+            devices: Vec::new(),
+            cursor: ListState::default(),
+            source1: None,
+            source2: None,
+            capturing: false,
+            in_speech: false,
+            muted: false,
+            levels: SourceLevels::default(),
+            transcript: VecDeque::new(),
+            partial: None,
+            message: None,
+        }
+    }
+
+    /// Update from a service event. Returns whether anything shown changed.
+    fn apply(&mut self, event: EventType) -> bool {
+        match event {
+            EventType::Levels(levels) => self.levels = levels,
+            EventType::SpeechStarted => self.in_speech = true,
+            EventType::SpeechEnded { .. } => self.in_speech = false,
+            EventType::CaptureStateChanged { capturing, error } => {
+                self.capturing = capturing;
+                if !capturing {
+                    self.in_speech = false;
+                    self.levels = SourceLevels::default();
+                }
+                if error.is_some() {
+                    self.message = error;
+                }
+            }
+            EventType::PrivacyMode { muted } => self.muted = muted,
+            EventType::TranscriptionPartial(result) => self.partial = Some(result.text),
+            EventType::TranscriptionToken { text } => self.partial = Some(text),
+            EventType::TranscriptionComplete(result) => {
+                self.partial = None;
+                if result.segments.is_empty() {
+                    self.push_transcript(result.speaker, result.text);
+                } else {
+                    for segment in result.segments {
+                        let label = match segment.source {
+                            SourceLabel::Mic => "mic",
+                            SourceLabel::System => "system",
+                        };
+                        self.push_transcript(Some(label.to_string()), segment.text);
+                    }
+                }
+            }
+            EventType::TranscriptionCancelled { .. } => self.partial = None,
+            EventType::SourceSwitched { previous, current } => {
+                for source in [&mut self.source1, &mut self.source2] {
+                    if source.as_deref() == Some(previous.as_str()) {
+                        *source = Some(current.clone());
+                    }
+                }
+                self.message = Some("Capture switched to another device".to_string());
+            }
+            EventType::Error { message, .. } => self.message = Some(message),
+            _ => return false,
+        }
+        true
+    }
+
+    fn push_transcript(&mut self, label: Option<String>, text: String) {
+        let text = text.trim().to_string();
+        if text.is_empty() {
+            return;
+        }
+        if self.transcript.len() == TRANSCRIPT_LIMIT {
+            self.transcript.pop_front();
+        }
+        self.transcript.push_back(TranscriptEntry { label, text });
+    }
+
+    fn selected_device(&self) -> Option<&AudioDevice> {
+        self.cursor.selected().and_then(|i| self.devices.get(i))
+    }
+
+    fn move_cursor(&mut self, down: bool) {
+        if self.devices.is_empty() {
+            return;
+        }
+        let last = self.devices.len() - 1;
+        let selected = match (self.cursor.selected(), down) {
+            (Some(i), true) => (i + 1).min(last),
+            (Some(i), false) => i.saturating_sub(1),
+            (None, _) => 0,
+        };
+        self.cursor.select(Some(selected));
+    }
+}
+
+/// Run the dashboard until the user quits or the service shuts down.
+///
+/// Capture is left as it is on quitting, so a session started here keeps
+/// running; stop it first, or afterwards with `flowstt stop`.
+pub async fn run(mut client: Client) -> Result<(), String> {
+    let mut app = App::new();
+    refresh_devices(&mut client, &mut app).await?;
+    match client
+        .request(Request::GetStatus)
+        .await
+        .map_err(|e| e.to_string())?
+    {
+        Response::Status(status) => {
+            app.capturing = status.capturing;
+            app.in_speech = status.in_speech;
+            app.muted = status.muted;
+            app.message = status.error;
+        }
+        Response::Error { message, .. } => return Err(message),
+        _ => return Err("Unexpected response".into()),
+    }
+
+    let mut subscriber = Client::new();
+    subscriber.connect().await.map_err(|e| e.to_string())?;
+    let mut stream = subscriber.subscribe().await.map_err(|e| e.to_string())?;
+    let (event_tx, mut events) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        while let Some(event) = stream.next().await {
+            if event_tx.send(event).is_err() {
+                break;
+            }
+        }
+    });
+    let mut keys = read_keys();
+
+    let mut terminal = ratatui::init();
+    let result = async {
+        loop {
+            terminal
+                .draw(|frame| draw(frame, &mut app))
+                .map_err(|e| format!("Failed to draw: {}", e))?;
+
+            // Wait until something shown changes
+            loop {
+                tokio::select! {
+                    key = keys.recv() => {
+                        let Some(key) = key else { return Ok(()) };
+                        if !handle_key(key, &mut client, &mut app).await? {
+                            return Ok(());
+                        }
+                        break;
+                    }
+                    event = events.recv() => match event {
+                        Some(Ok(EventType::Shutdown)) | None => {
+                            return Err("The service shut down".to_string())
+                        }
+                        Some(Ok(event)) => {
+                            if app.apply(event) {
+                                break;
+                            }
+                        }
+                        Some(Err(e)) => return Err(e.to_string()),
+                    },
+                }
+            }
+        }
+    }
+    .await;
+    ratatui::restore();
+    result
+}
+
+/// Read key presses on a thread, until the receiver is dropped.
+fn read_keys() -> mpsc::UnboundedReceiver<KeyEvent> {
+    let (tx, rx) = mpsc::unbounded_channel();
+    std::thread::spawn(move || {
+        while !tx.is_closed() {
+            match event::poll(KEY_POLL_INTERVAL) {
+                Ok(true) => {
+                    if let Ok(Event::Key(key)) = event::read() {
+                        if key.kind == KeyEventKind::Press && tx.send(key).is_err() {
+                            break;
+                        }
+                    }
+                }
+                Ok(false) => {}
+                Err(_) => break,
+            }
+        }
+    });
+    rx
+}
+
+/// Act on a key press. Returns false when the dashboard should close.
+async fn handle_key(key: KeyEvent, client: &mut Client, app: &mut App) -> Result<bool, String> {
+    match key.code {
+        KeyCode::Char('q') | KeyCode::Esc => return Ok(false),
+        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return Ok(false),
+        KeyCode::Up | KeyCode::Char('k') => app.move_cursor(false),
+        KeyCode::Down | KeyCode::Char('j') => app.move_cursor(true),
+        KeyCode::Char('r') => {
+            refresh_devices(client, app).await?;
+            app.message = Some(format!("{} devices", app.devices.len()));
+        }
+        KeyCode::Char(slot @ ('1' | '2')) => {
+            let Some(id) = app.selected_device().map(|device| device.id.clone()) else {
+                return Ok(true);
+            };
+            let source = if slot == '1' {
+                &mut app.source1
+            } else {
+                &mut app.source2
+            };
+            // Choosing the same device again clears the slot
+            *source = match source.as_deref() {
+                Some(current) if current == id => None,
+                _ => Some(id),
+            };
+            if app.source1.is_none() {
+                app.source1 = app.source2.take();
+            }
+            if app.source1 == app.source2 {
+                app.source2 = None;
+            }
+            // Sources take effect at once while capturing; clearing both stops it
+            if app.capturing {
+                let (source1, source2) = (app.source1.clone(), app.source2.clone());
+                set_sources(client, app, source1, source2).await;
+            }
+        }
+        KeyCode::Enter | KeyCode::Char(' ') => {
+            if app.capturing {
+                set_sources(client, app, None, None).await;
+            } else if app.source1.is_none() {
+                app.message = Some("Choose a device with 1 first".to_string());
+            } else {
+                let (source1, source2) = (app.source1.clone(), app.source2.clone());
+                set_sources(client, app, source1, source2).await;
+            }
+        }
+        _ => return Ok(true),
+    }
+    Ok(true)
+}
+
+/// Capture from the given sources, or stop capture when there are none.
+/// Capture state itself is updated from the events that follow.
+async fn set_sources(
+    client: &mut Client,
+    app: &mut App,
+    source1_id: Option<String>,
+    source2_id: Option<String>,
+) {
+    let request = Request::SetSources {
+        source1_id,
+        source2_id,
+        source1_channels: None,
+        source2_channels: None,
+    };
+    app.message = match client.request(request).await {
+        Ok(Response::Ok) => None,
+        Ok(Response::Error { message, .. }) => Some(message),
+        Ok(_) => Some("Unexpected response".to_string()),
+        Err(e) => Some(e.to_string()),
+    };
+}
+
+async fn refresh_devices(client: &mut Client, app: &mut App) -> Result<(), String> {
+    match client
+        .request(Request::ListDevices { source_type: None })
+        .await
+        .map_err(|e| e.to_string())?
+    {
+        Response::Devices { devices } => {
+            app.devices = devices;
+            let selected = app.cursor.selected().unwrap_or(0);
+            app.cursor.select(match app.devices.len() {
+                0 => None,
+                len => Some(selected.min(len - 1)),
+            });
+            Ok(())
+        }
+        Response::Error { message, .. } => Err(message),
+        _ => Err("Unexpected response".into()),
+    }
+}
+
+fn draw(frame: &mut Frame, app: &mut App) {
+    let [header, body, footer] = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Min(0),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+    let [devices, right] =
+        Layout::horizontal([Constraint::Percentage(40), Constraint::Percentage(60)]).areas(body);
+    let [levels, transcript] =
+        Layout::vertical([Constraint::Length(4), Constraint::Min(0)]).areas(right);
+
+    draw_header(frame, app, header);
+    draw_devices(frame, app, devices);
+    draw_levels(frame, app, levels);
+    draw_transcript(frame, app, transcript);
+
+    let help = "↑/↓ select  1/2 set source  enter start/stop  r refresh  q quit";
+    frame.render_widget(Paragraph::new(help.dim()), footer);
+}
+
+fn draw_header(frame: &mut Frame, app: &App, area: Rect) {
+    let capture = if app.muted {
+        "muted".yellow().bold()
+    } else if app.capturing {
+        "capturing".green().bold()
+    } else {
+        "idle".dim()
+    };
+    let mut spans = vec!["FlowSTT ".bold(), capture];
+    if app.capturing && app.in_speech {
+        spans.push("  ● speaking".red());
+    }
+    if let Some(message) = &app.message {
+        spans.push(Span::raw("  "));
+        spans.push(message.clone().yellow());
+    }
+    frame.render_widget(Paragraph::new(Line::from(spans)), area);
+}
+
+fn draw_devices(frame: &mut Frame, app: &mut App, area: Rect) {
+    let items: Vec<ListItem> = app
+        .devices
+        .iter()
+        .map(|device| {
+            let slot = if app.source1.as_deref() == Some(device.id.as_str()) {
+                "1 ".green().bold()
+            } else if app.source2.as_deref() == Some(device.id.as_str()) {
+                "2 ".blue().bold()
+            } else {
+                Span::raw("  ")
+            };
+            let kind = match device.source_type {
+                AudioSourceType::Input => " input".cyan(),
+                AudioSourceType::System => " system".magenta(),
+                AudioSourceType::Mixed => " mixed".yellow(),
+                AudioSourceType::Network => " network".blue(),
+            };
+            ListItem::new(Line::from(vec![slot, Span::raw(device.name.clone()), kind]))
+        })
+        .collect();
+    let list = List::new(items)
+        .block(Block::bordered().title(" Devices "))
+        .highlight_style(Style::new().reversed());
+    frame.render_stateful_widget(list, area, &mut app.cursor);
+}
+
+fn draw_levels(frame: &mut Frame, app: &App, area: Rect) {
+    let [input, system] =
+        Layout::vertical([Constraint::Length(2), Constraint::Length(2)]).areas(area);
+    frame.render_widget(level_gauge(" Input ", app.levels.input.as_ref()), input);
+    frame.render_widget(level_gauge(" System ", app.levels.system.as_ref()), system);
+}
+
+/// A gauge filled from `METER_FLOOR_DB` to 0 dBFS, colored like the CLI's
+/// level meter by how close the peaks come to clipping.
+fn level_gauge<'a>(title: &'a str, level: Option<&AudioLevel>) -> Gauge<'a> {
+    let gauge = Gauge::default().block(Block::new().title(title));
+    let Some(level) = level else {
+        return gauge
+            .ratio(0.0)
+            .label("no signal")
+            .gauge_style(Color::DarkGray);
+    };
+    let ratio = ((level.rms_db - METER_FLOOR_DB) / -METER_FLOOR_DB).clamp(0.0, 1.0);
+    let color = if level.peak_db > -3.0 {
+        Color::Red
+    } else if level.peak_db > -12.0 {
+        Color::Yellow
+    } else {
+        Color::Green
+    };
+    gauge
+        .ratio(ratio as f64)
+        .label(format!("{:.1} dB", level.rms_db))
+        .gauge_style(color)
+}
+
+fn draw_transcript(frame: &mut Frame, app: &App, area: Rect) {
+    let block = Block::bordered().title(" Transcript ");
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+    let width = inner.width as usize;
+    if width == 0 {
+        return;
+    }
+
+    // Wrap every entry to the width, then show the newest lines that fit
+    let mut lines: Vec<Line> = Vec::new();
+    for entry in &app.transcript {
+        let label = entry
+            .label
+            .as_ref()
+            .map(|label| format!("[{}] ", label))
+            .unwrap_or_default();
+        for (i, row) in wrap(&format!("{}{}", label, entry.text), width)
+            .into_iter()
+            .enumerate()
+        {
+            match row.strip_prefix(label.as_str()) {
+                Some(text) if i == 0 && !label.is_empty() => lines.push(Line::from(vec![
+                    label.clone().cyan(),
+                    Span::raw(text.to_string()),
+                ])),
+                _ => lines.push(Line::from(row)),
+            }
+        }
+    }
+    if let Some(partial) = &app.partial {
+        lines.extend(
+            wrap(partial, width)
+                .into_iter()
+                .map(|row| Line::from(row.dim().italic())),
+        );
+    }
+    let skip = lines.len().saturating_sub(inner.height as usize);
+    let visible: Vec<Line> = lines.into_iter().skip(skip).collect();
+    frame.render_widget(Paragraph::new(visible), inner);
+}
+
+/// Break text into rows of at most `width` characters, at spaces where
+/// possible.
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut rows = Vec::new();
+    let mut row = String::new();
+    for word in text.split_whitespace() {
+        let mut word = word.to_string();
+        loop {
+            let row_len = row.chars().count();
+            let word_len = word.chars().count();
+            let needed = if row.is_empty() {
+                word_len
+            } else {
+                row_len + 1 + word_len
+            };
+            if needed <= width {
+                if !row.is_empty() {
+                    row.push(' ');
+                }
+                row.push_str(&word);
+                break;
+            }
+            if !row.is_empty() {
+                rows.push(std::mem::take(&mut row));
+                continue;
+            }
+            // A word longer than a row is split across rows
+            let split = word
+                .char_indices()
+                .nth(width)
+                .map_or(word.len(), |(i, _)| i);
+            let rest = word.split_off(split);
+            rows.push(word);
+            word = rest;
+            if word.is_empty() {
+                break;
+            }
+        }
+    }
+    if !row.is_empty() {
+        rows.push(row);
+    }
+    rows
+}
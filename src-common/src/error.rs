@@ -0,0 +1,126 @@
+//! Errors shared by the service's subsystems.
+//!
+//! Audio backends and transcription report a [`FlowSttError`] rather than a
+//! bare message, so callers can tell a missing device from a denied
+//! permission and the IPC layer can send the matching [`ErrorCode`].
+
+use std::path::PathBuf;
+
+use thiserror::Error;
+
+use crate::ipc::{ErrorCode, Response};
+
+/// What went wrong in the service.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum FlowSttError {
+    /// No audio device has this ID
+    #[error("Audio device not found: {0}")]
+    DeviceNotFound(String),
+    /// The OS refused access; the message says to what and where to allow it
+    #[error("{0}")]
+    PermissionDenied(String),
+    /// The audio backend or a library it needs could not be started
+    #[error("Audio backend unavailable: {0}")]
+    BackendUnavailable(String),
+    /// The transcription model file doesn't exist
+    #[error(
+        "Model not found at {}; download one with `flowstt model download`",
+        .0.display()
+    )]
+    ModelMissing(PathBuf),
+    /// Capture failed to start, stop or run
+    #[error("{0}")]
+    Capture(String),
+    /// Loading the model or transcribing failed
+    #[error("{0}")]
+    Transcription(String),
+    /// Capture is muted for privacy
+    #[error("Capture is muted; unmute to start listening")]
+    Muted,
+    /// The app hasn't finished starting up
+    #[error("App not ready")]
+    NotReady,
+    /// No primary audio source is configured
+    #[error("No primary audio source configured")]
+    NoSource,
+    /// Hotkey monitoring could not be started
+    #[error("{0}")]
+    Hotkey(String),
+    /// This build or platform can't do what was asked
+    #[error("{0}")]
+    Unsupported(String),
+    /// Anything else; the message describes it
+    #[error("{0}")]
+    Other(String),
+}
+
+impl FlowSttError {
+    /// The IPC error code for this error.
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            FlowSttError::DeviceNotFound(id) => ErrorCode::DeviceNotFound { id: id.clone() },
+            FlowSttError::PermissionDenied(_) => ErrorCode::PermissionDenied,
+            FlowSttError::BackendUnavailable(_) => ErrorCode::BackendUnavailable,
+            FlowSttError::ModelMissing(_) => ErrorCode::ModelMissing,
+            FlowSttError::Capture(_) => ErrorCode::CaptureFailed,
+            FlowSttError::Transcription(_) => ErrorCode::TranscriptionFailed,
+            FlowSttError::Muted => ErrorCode::Muted,
+            FlowSttError::NotReady => ErrorCode::NotReady,
+            FlowSttError::NoSource => ErrorCode::NoAudioDevice,
+            FlowSttError::Hotkey(_) => ErrorCode::HotkeyFailed,
+            FlowSttError::Unsupported(_) => ErrorCode::Unsupported,
+            FlowSttError::Other(_) => ErrorCode::Other,
+        }
+    }
+}
+
+impl From<FlowSttError> for String {
+    fn from(error: FlowSttError) -> Self {
+        error.to_string()
+    }
+}
+
+impl From<FlowSttError> for Response {
+    fn from(error: FlowSttError) -> Self {
+        Response::error_with_code(error.code(), error.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_response_carries_code_and_message() {
+        let error = FlowSttError::DeviceNotFound("hw:1".to_string());
+        match Response::from(error) {
+            Response::Error { code, message } => {
+                assert_eq!(
+                    code,
+                    ErrorCode::DeviceNotFound {
+                        id: "hw:1".to_string()
+                    }
+                );
+                assert_eq!(message, "Audio device not found: hw:1");
+            }
+            other => panic!("unexpected response {:?}", other),
+        }
+
+        let error = FlowSttError::ModelMissing(PathBuf::from("/models/base.bin"));
+        assert!(error
+            .to_string()
+            .starts_with("Model not found at /models/base.bin;"));
+        assert_eq!(error.code(), ErrorCode::ModelMissing);
+    }
+
+    #[test]
+    fn test_capture_refusals_have_their_own_codes() {
+        assert_eq!(FlowSttError::Muted.code(), ErrorCode::Muted);
+        assert_eq!(FlowSttError::NotReady.code(), ErrorCode::NotReady);
+        assert_eq!(FlowSttError::NoSource.code(), ErrorCode::NoAudioDevice);
+        assert_eq!(
+            FlowSttError::Muted.to_string(),
+            "Capture is muted; unmute to start listening"
+        );
+    }
+}
@@ -0,0 +1,426 @@
+//! Transcript export to subtitle and document formats.
+//!
+//! Sessions are converted on the client side, so the CLI and GUI can export
+//! any saved transcription without extra service support.
+
+use serde::{Deserialize, Serialize};
+
+use crate::types::{Chapter, SourceLabel, TranscriptSession, WordTiming};
+
+/// Longest subtitle cue built from word timings, in milliseconds
+const MAX_CUE_MS: u64 = 5000;
+
+/// Longest subtitle cue built from word timings, in characters
+const MAX_CUE_CHARS: usize = 80;
+
+/// Output format for exported transcripts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    /// SubRip subtitles (.srt)
+    Srt,
+    /// WebVTT subtitles (.vtt)
+    Vtt,
+    /// The full session as JSON
+    Json,
+    /// Markdown document
+    Markdown,
+}
+
+impl ExportFormat {
+    /// Conventional file extension for this format.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Srt => "srt",
+            ExportFormat::Vtt => "vtt",
+            ExportFormat::Json => "json",
+            ExportFormat::Markdown => "md",
+        }
+    }
+}
+
+/// A timed piece of text in a subtitle file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cue {
+    /// Start offset in milliseconds
+    pub start_ms: u64,
+    /// End offset in milliseconds
+    pub end_ms: u64,
+    /// Cue text
+    pub text: String,
+    /// Who is speaking, if known
+    pub speaker: Option<String>,
+}
+
+/// Build subtitle cues from word timings.
+///
+/// Cues break at sentence ends or when they get too long. Without word
+/// timings the whole text becomes a single cue spanning `duration_ms`.
+pub fn cues_from_words(text: &str, words: &[WordTiming], duration_ms: u64) -> Vec<Cue> {
+    if words.is_empty() {
+        if text.is_empty() {
+            return Vec::new();
+        }
+        return vec![Cue {
+            start_ms: 0,
+            end_ms: duration_ms,
+            text: text.to_string(),
+            speaker: None,
+        }];
+    }
+
+    let mut cues = Vec::new();
+    let mut current: Option<Cue> = None;
+    for word in words {
+        let cue = current.get_or_insert_with(|| Cue {
+            start_ms: word.start_ms,
+            end_ms: word.end_ms,
+            text: String::new(),
+            speaker: None,
+        });
+        if !cue.text.is_empty() {
+            cue.text.push(' ');
+        }
+        cue.text.push_str(&word.word);
+        cue.end_ms = word.end_ms;
+
+        let sentence_end = word.word.ends_with(['.', '?', '!']);
+        if sentence_end
+            || cue.end_ms - cue.start_ms >= MAX_CUE_MS
+            || cue.text.len() >= MAX_CUE_CHARS
+        {
+            cues.extend(current.take());
+        }
+    }
+    cues.extend(current);
+    cues
+}
+
+/// Build subtitle cues for a saved session.
+///
+/// Per-source segments are used when present; otherwise cues come from the
+/// word timings and carry the session's speaker label.
+pub fn session_cues(session: &TranscriptSession) -> Vec<Cue> {
+    if !session.segments.is_empty() {
+        return session
+            .segments
+            .iter()
+            .map(|s| Cue {
+                start_ms: s.start_ms,
+                end_ms: s.end_ms,
+                text: s.text.clone(),
+                speaker: Some(source_name(s.source).to_string()),
+            })
+            .collect();
+    }
+
+    let mut cues = cues_from_words(&session.text, &session.words, session.duration_ms);
+    for cue in &mut cues {
+        cue.speaker = session.speaker.clone();
+    }
+    cues
+}
+
+/// Convert a saved session to the given format.
+pub fn export_session(session: &TranscriptSession, format: ExportFormat) -> String {
+    match format {
+        ExportFormat::Srt => to_srt(&session_cues(session), &session.chapters),
+        ExportFormat::Vtt => to_vtt(&session_cues(session), &session.chapters),
+        ExportFormat::Json => serde_json::to_string_pretty(session).unwrap_or_default(),
+        ExportFormat::Markdown => to_markdown(session),
+    }
+}
+
+/// Format cues as SubRip subtitles.
+///
+/// Each chapter is announced by a `NOTE` block before its first cue, which
+/// players skip like any other block without a timing line.
+pub fn to_srt(cues: &[Cue], chapters: &[Chapter]) -> String {
+    let starts = chapter_starts(cues, chapters);
+    cues.iter()
+        .enumerate()
+        .map(|(i, cue)| {
+            let text = match &cue.speaker {
+                Some(speaker) => format!("[{}] {}", speaker, cue.text),
+                None => cue.text.clone(),
+            };
+            let note = match starts[i] {
+                Some(chapter) => format!("NOTE {}\n\n", chapter.title),
+                None => String::new(),
+            };
+            format!(
+                "{}{}\n{} --> {}\n{}\n",
+                note,
+                i + 1,
+                timestamp(cue.start_ms, ','),
+                timestamp(cue.end_ms, ','),
+                text
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Format cues as WebVTT subtitles, with a `NOTE` before each chapter.
+pub fn to_vtt(cues: &[Cue], chapters: &[Chapter]) -> String {
+    let mut out = String::from("WEBVTT\n");
+    for (cue, chapter) in cues.iter().zip(chapter_starts(cues, chapters)) {
+        if let Some(chapter) = chapter {
+            out.push_str(&format!("\nNOTE {}\n", chapter.title));
+        }
+        out.push_str(&format!(
+            "\n{} --> {}\n",
+            timestamp(cue.start_ms, '.'),
+            timestamp(cue.end_ms, '.')
+        ));
+        match &cue.speaker {
+            Some(speaker) => out.push_str(&format!("<v {}>{}\n", speaker, cue.text)),
+            None => out.push_str(&format!("{}\n", cue.text)),
+        }
+    }
+    out
+}
+
+/// Format a session as a Markdown document.
+pub fn to_markdown(session: &TranscriptSession) -> String {
+    let metadata = &session.metadata;
+    let title = match &metadata.title {
+        Some(title) => title.clone(),
+        None => format!("Transcript {}", session.id),
+    };
+    let mut out = format!(
+        "# {}\n\n- **Date:** {}\n- **Duration:** {:.1}s\n",
+        title,
+        session.created_at,
+        session.duration_ms as f64 / 1000.0
+    );
+    if let Some(language) = &session.language {
+        out.push_str(&format!("- **Language:** {}\n", language));
+    }
+    if !metadata.participants.is_empty() {
+        out.push_str(&format!(
+            "- **Participants:** {}\n",
+            metadata.participants.join(", ")
+        ));
+    }
+    if !metadata.tags.is_empty() {
+        out.push_str(&format!("- **Tags:** {}\n", metadata.tags.join(", ")));
+    }
+    out.push('\n');
+    if let Some(notes) = &metadata.notes {
+        out.push_str(&format!("> {}\n\n", notes.trim().replace('\n', "\n> ")));
+    }
+    // Chapter headings go under the transcript heading when there is a summary
+    let mut heading = "##";
+    if let Some(summary) = &session.summary {
+        out.push_str(&format!(
+            "## Summary\n\n{}\n\n## Transcript\n\n",
+            summary.trim()
+        ));
+        heading = "###";
+    }
+
+    let cues = session_cues(session);
+    let speakers = cues.iter().any(|c| c.speaker.is_some());
+    if !speakers && (session.chapters.is_empty() || session.words.is_empty()) {
+        out.push_str(&format!("{}\n", session.text));
+        return out;
+    }
+
+    if speakers {
+        for (cue, start) in cues.iter().zip(chapter_starts(&cues, &session.chapters)) {
+            if let Some(start) = start {
+                out.push_str(&chapter_heading(heading, start));
+            }
+            let speaker = cue.speaker.as_deref().unwrap_or("unknown");
+            out.push_str(&format!(
+                "**[{}] {}:** {}\n\n",
+                clock(cue.start_ms),
+                speaker,
+                cue.text
+            ));
+        }
+    } else {
+        let mut paragraphs: Vec<(usize, Vec<&str>)> = Vec::new();
+        for word in &session.words {
+            let index = chapter_index(&session.chapters, word.start_ms);
+            match paragraphs.last_mut() {
+                Some((i, words)) if *i == index => words.push(&word.word),
+                _ => paragraphs.push((index, vec![&word.word])),
+            }
+        }
+        for (index, words) in paragraphs {
+            out.push_str(&chapter_heading(heading, &session.chapters[index]));
+            out.push_str(&format!("{}\n\n", words.join(" ")));
+        }
+    }
+    out
+}
+
+/// Markdown heading for a chapter, with its start time.
+fn chapter_heading(level: &str, chapter: &Chapter) -> String {
+    format!(
+        "{} {} ({})\n\n",
+        level,
+        chapter.title,
+        clock(chapter.start_ms)
+    )
+}
+
+/// Index of the chapter containing `ms`.
+fn chapter_index(chapters: &[Chapter], ms: u64) -> usize {
+    chapters.iter().rposition(|c| c.start_ms <= ms).unwrap_or(0)
+}
+
+/// For each cue, the chapter that starts with it, if any.
+fn chapter_starts<'a>(cues: &[Cue], chapters: &'a [Chapter]) -> Vec<Option<&'a Chapter>> {
+    let mut current = None;
+    cues.iter()
+        .map(|cue| {
+            if chapters.is_empty() {
+                return None;
+            }
+            let index = chapter_index(chapters, cue.start_ms);
+            if current == Some(index) {
+                return None;
+            }
+            current = Some(index);
+            Some(&chapters[index])
+        })
+        .collect()
+}
+
+/// Name used for a source in exported files.
+fn source_name(source: SourceLabel) -> &'static str {
+    match source {
+        SourceLabel::Mic => "mic",
+        SourceLabel::System => "system",
+    }
+}
+
+/// Format milliseconds as `HH:MM:SS` followed by `separator` and milliseconds.
+fn timestamp(ms: u64, separator: char) -> String {
+    format!("{}{}{:03}", clock(ms), separator, ms % 1000)
+}
+
+/// Format milliseconds as `HH:MM:SS`.
+fn clock(ms: u64) -> String {
+    format!(
+        "{:02}:{:02}:{:02}",
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::SessionMetadata;
+
+    fn word(word: &str, start_ms: u64, end_ms: u64) -> WordTiming {
+        WordTiming {
+            word: word.to_string(),
+            start_ms,
+            end_ms,
+            probability: 0.9,
+        }
+    }
+
+    fn session() -> TranscriptSession {
+        TranscriptSession {
+            id: "20260101-120000-000".to_string(),
+            created_at: "2026-01-01T12:00:00+00:00".to_string(),
+            text: "Hello there. How are you?".to_string(),
+            language: Some("en".to_string()),
+            words: vec![
+                word("Hello", 0, 400),
+                word("there.", 400, 900),
+                word("How", 1500, 1700),
+                word("are", 1700, 1850),
+                word("you?", 1850, 2300),
+            ],
+            duration_ms: 2500,
+            ..Default::default()
+        }
+    }
+
+    fn chapters() -> Vec<Chapter> {
+        vec![
+            Chapter {
+                title: "Chapter 1".to_string(),
+                start_ms: 0,
+                end_ms: 1500,
+            },
+            Chapter {
+                title: "Chapter 2".to_string(),
+                start_ms: 1500,
+                end_ms: 2500,
+            },
+        ]
+    }
+
+    #[test]
+    fn test_export_srt() {
+        assert_eq!(
+            export_session(&session(), ExportFormat::Srt),
+            "1\n00:00:00,000 --> 00:00:00,900\nHello there.\n\n\
+             2\n00:00:01,500 --> 00:00:02,300\nHow are you?\n"
+        );
+    }
+
+    #[test]
+    fn test_export_vtt_with_speaker() {
+        let mut session = session();
+        session.speaker = Some("speaker_1".to_string());
+        assert_eq!(
+            export_session(&session, ExportFormat::Vtt),
+            "WEBVTT\n\n00:00:00.000 --> 00:00:00.900\n<v speaker_1>Hello there.\n\
+             \n00:00:01.500 --> 00:00:02.300\n<v speaker_1>How are you?\n"
+        );
+    }
+
+    #[test]
+    fn test_export_chapters() {
+        let mut session = session();
+        session.chapters = chapters();
+        assert_eq!(
+            export_session(&session, ExportFormat::Srt),
+            "NOTE Chapter 1\n\n1\n00:00:00,000 --> 00:00:00,900\nHello there.\n\n\
+             NOTE Chapter 2\n\n2\n00:00:01,500 --> 00:00:02,300\nHow are you?\n"
+        );
+        assert!(export_session(&session, ExportFormat::Markdown).ends_with(
+            "## Chapter 1 (00:00:00)\n\nHello there.\n\n\
+             ## Chapter 2 (00:00:01)\n\nHow are you?\n\n"
+        ));
+    }
+
+    #[test]
+    fn test_markdown_metadata() {
+        let mut session = session();
+        session.metadata = SessionMetadata {
+            title: Some("Standup".to_string()),
+            tags: vec!["work".to_string(), "daily".to_string()],
+            participants: vec!["Ana".to_string(), "Ben".to_string()],
+            notes: Some("Short one".to_string()),
+        };
+        let markdown = export_session(&session, ExportFormat::Markdown);
+        assert!(markdown.starts_with("# Standup\n\n"));
+        assert!(markdown.contains("- **Participants:** Ana, Ben\n- **Tags:** work, daily\n\n"));
+        assert!(markdown.contains("\n> Short one\n\nHello there. How are you?\n"));
+    }
+
+    #[test]
+    fn test_cues_without_word_timings() {
+        let cues = cues_from_words("Hello", &[], 1200);
+        assert_eq!(cues.len(), 1);
+        assert_eq!(cues[0].end_ms, 1200);
+        assert!(cues_from_words("", &[], 1200).is_empty());
+    }
+
+    #[test]
+    fn test_timestamp_formats() {
+        assert_eq!(timestamp(3_723_045, ','), "01:02:03,045");
+        assert_eq!(timestamp(3_723_045, '.'), "01:02:03.045");
+    }
+}
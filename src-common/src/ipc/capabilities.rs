@@ -0,0 +1,297 @@
+//! Optional service features announced in the handshake.
+//!
+//! A client may be newer than the service it talks to. The service lists what
+//! it supports in its `welcome` response, and clients check a request's
+//! [`Capability::required_by`] before sending it, so a missing feature is
+//! reported as [`IpcError::Unsupported`](super::IpcError::Unsupported) (or
+//! skipped) rather than failing on an unknown request.
+
+use serde::{Deserialize, Serialize};
+
+use super::Request;
+use crate::types::{HotkeyAction, OutputMode};
+
+/// A feature that not every service version supports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Capability {
+    /// Pushed events after `subscribe_events`
+    StreamingEvents,
+    /// `transcription_token` events while a segment decodes
+    TokenStreaming,
+    /// Speaker labels
+    Diarization,
+    /// English translation alongside transcripts
+    Translation,
+    /// Transcribing files added to watched folders
+    FileTranscription,
+    /// Dictation documents
+    Dictation,
+    /// Spoken command phrases
+    VoiceCommands,
+    /// Saved transcription sessions
+    SessionHistory,
+    /// Aborting queued and running transcriptions
+    Cancellation,
+    /// Ownership of capture among clients
+    CaptureControl,
+    /// Monitoring counters
+    Metrics,
+    /// Muting all capture
+    PrivacyMode,
+    /// Named bundles of settings
+    Profiles,
+    /// Reporting the focused application
+    FocusedApp,
+    /// Running commands or HTTP requests on saved transcriptions
+    SessionHooks,
+    /// Playing saved recordings and correcting their transcripts
+    Playback,
+    /// Disk usage reporting and recording retention
+    Storage,
+    /// Loading and unloading the model on request
+    ModelLoading,
+    /// Reporting and requesting microphone and screen recording access
+    Permissions,
+    /// Recording captured audio only while speech is detected
+    VadRecording,
+    /// Latency and accuracy presets
+    LatencyPresets,
+    /// Hotkeys for actions besides push-to-talk, and clipboard output
+    Hotkeys,
+    /// Saving and transcribing recently captured audio
+    RollingBuffer,
+    /// Configurable spectrogram FFT size, window and update rate
+    SpectrogramSettings,
+    /// Titles, tags, participants and notes on saved transcriptions
+    SessionMetadata,
+    /// Full-text search of saved transcriptions
+    TranscriptSearch,
+    /// Transcribing saved transcriptions again in background jobs
+    Retranscription,
+    /// Delivering the last transcription again
+    RepeatOutput,
+    /// Reporting how system audio can be captured
+    SystemAudioSupport,
+    /// Capturing chosen channels of multichannel devices
+    ChannelSelection,
+    /// Talk time and pace of saved transcriptions
+    SessionStats,
+    /// Reading the active session's recent transcript on demand
+    RecentTranscript,
+    /// Transcribing an audio file on request
+    TranscribeFile,
+    /// Capture sessions tying together the transcriptions between starting
+    /// and stopping capture
+    CaptureSessions,
+    /// Injecting capture faults; only debug builds of the service announce
+    /// it, so it is not in [`Capability::ALL`]
+    FaultInjection,
+    /// A capability of a newer service that this build doesn't know
+    #[serde(other)]
+    Unknown,
+}
+
+impl Capability {
+    /// Capabilities of this build.
+    pub const ALL: &'static [Capability] = &[
+        Capability::StreamingEvents,
+        Capability::TokenStreaming,
+        Capability::Diarization,
+        Capability::Translation,
+        Capability::FileTranscription,
+        Capability::Dictation,
+        Capability::VoiceCommands,
+        Capability::SessionHistory,
+        Capability::Cancellation,
+        Capability::CaptureControl,
+        Capability::Metrics,
+        Capability::PrivacyMode,
+        Capability::Profiles,
+        Capability::FocusedApp,
+        Capability::SessionHooks,
+        Capability::Playback,
+        Capability::Storage,
+        Capability::ModelLoading,
+        Capability::Permissions,
+        Capability::VadRecording,
+        Capability::LatencyPresets,
+        Capability::Hotkeys,
+        Capability::RollingBuffer,
+        Capability::SpectrogramSettings,
+        Capability::SessionMetadata,
+        Capability::TranscriptSearch,
+        Capability::Retranscription,
+        Capability::RepeatOutput,
+        Capability::SystemAudioSupport,
+        Capability::ChannelSelection,
+        Capability::SessionStats,
+        Capability::RecentTranscript,
+        Capability::TranscribeFile,
+        Capability::CaptureSessions,
+    ];
+
+    /// Capability the service needs for `request`, or None for requests of
+    /// the base protocol.
+    pub fn required_by(request: &Request) -> Option<Capability> {
+        let capability = match request {
+            Request::SubscribeEvents => Capability::StreamingEvents,
+            Request::SetDiarization { .. } => Capability::Diarization,
+            Request::SetTranslate { .. } => Capability::Translation,
+            Request::AddWatchFolder { .. }
+            | Request::RemoveWatchFolder { .. }
+            | Request::ListWatchFolders => Capability::FileTranscription,
+            Request::StartDictation | Request::StopDictation => Capability::Dictation,
+            Request::RegisterCommand { .. }
+            | Request::UnregisterCommand { .. }
+            | Request::ListCommands => Capability::VoiceCommands,
+            Request::SetSessionMetadata { .. } => Capability::SessionMetadata,
+            Request::SearchTranscripts { .. } => Capability::TranscriptSearch,
+            Request::RetranscribeSessions { .. }
+            | Request::ListJobs
+            | Request::CancelJob { .. } => Capability::Retranscription,
+            Request::ListSessions { filter, .. } if !filter.is_empty() => {
+                Capability::SessionMetadata
+            }
+            Request::ListSessions { .. }
+            | Request::GetSession { .. }
+            | Request::DeleteSession { .. } => Capability::SessionHistory,
+            Request::CancelTranscription => Capability::Cancellation,
+            Request::AcquireControl { .. } | Request::ReleaseControl => Capability::CaptureControl,
+            Request::GetMetrics => Capability::Metrics,
+            Request::SetMuted { .. } => Capability::PrivacyMode,
+            Request::ListProfiles
+            | Request::SaveProfile { .. }
+            | Request::DeleteProfile { .. }
+            | Request::UseProfile { .. } => Capability::Profiles,
+            Request::GetFocusedApp => Capability::FocusedApp,
+            Request::UpdateSession { .. }
+            | Request::PlaySession { .. }
+            | Request::PlayFile { .. }
+            | Request::PausePlayback { .. }
+            | Request::SeekPlayback { .. }
+            | Request::StopPlayback => Capability::Playback,
+            Request::GetStorageStatus | Request::PruneRecordings => Capability::Storage,
+            Request::LoadModel | Request::UnloadModel => Capability::ModelLoading,
+            Request::GetPermissions | Request::RequestPermission { .. } => Capability::Permissions,
+            Request::SetVadRecording { .. } => Capability::VadRecording,
+            Request::SetLatencyPreset { .. } => Capability::LatencyPresets,
+            Request::RepeatLastOutput { .. } => Capability::RepeatOutput,
+            Request::GetSystemAudioSupport => Capability::SystemAudioSupport,
+            Request::GetSessionStats { .. } => Capability::SessionStats,
+            Request::GetRecentTranscript { .. } => Capability::RecentTranscript,
+            Request::TranscribeFile { .. } => Capability::TranscribeFile,
+            Request::GetCapture { .. } | Request::GetCaptureTranscript { .. } => {
+                Capability::CaptureSessions
+            }
+            Request::SetSources {
+                source1_channels,
+                source2_channels,
+                ..
+            } if source1_channels.is_some() || source2_channels.is_some() => {
+                Capability::ChannelSelection
+            }
+            Request::SetHotkey { binding } if binding.action == HotkeyAction::RepeatLast => {
+                Capability::RepeatOutput
+            }
+            Request::SetHotkey { .. } | Request::RemoveHotkey { .. } | Request::ListHotkeys => {
+                Capability::Hotkeys
+            }
+            Request::CaptureLastN { .. } => Capability::RollingBuffer,
+            Request::SetSpectrogramSettings { .. } | Request::GetSpectrogramSettings => {
+                Capability::SpectrogramSettings
+            }
+            Request::InjectFault { .. } => Capability::FaultInjection,
+            Request::SetOutputMode {
+                mode: OutputMode::Clipboard,
+            } => Capability::Hotkeys,
+            Request::AddHook { .. } | Request::RemoveHook { .. } | Request::ListHooks => {
+                Capability::SessionHooks
+            }
+            _ => return None,
+        };
+        Some(capability)
+    }
+}
+
+impl std::fmt::Display for Capability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Capability::StreamingEvents => "event streaming",
+            Capability::TokenStreaming => "token streaming",
+            Capability::Diarization => "speaker labels",
+            Capability::Translation => "translation",
+            Capability::FileTranscription => "watch folders",
+            Capability::Dictation => "dictation",
+            Capability::VoiceCommands => "voice commands",
+            Capability::SessionHistory => "transcription history",
+            Capability::Cancellation => "cancelling transcriptions",
+            Capability::CaptureControl => "capture control",
+            Capability::Metrics => "metrics",
+            Capability::PrivacyMode => "privacy mode",
+            Capability::Profiles => "profiles",
+            Capability::FocusedApp => "focused application detection",
+            Capability::SessionHooks => "session hooks",
+            Capability::Playback => "playback",
+            Capability::Storage => "storage management",
+            Capability::ModelLoading => "model loading",
+            Capability::Permissions => "permission checks",
+            Capability::VadRecording => "voice-activated recording",
+            Capability::LatencyPresets => "latency presets",
+            Capability::Hotkeys => "hotkey actions",
+            Capability::RollingBuffer => "retroactive capture",
+            Capability::SpectrogramSettings => "spectrogram settings",
+            Capability::SessionMetadata => "session metadata",
+            Capability::TranscriptSearch => "transcript search",
+            Capability::Retranscription => "re-transcription",
+            Capability::RepeatOutput => "repeating output",
+            Capability::SystemAudioSupport => "system audio support",
+            Capability::ChannelSelection => "channel selection",
+            Capability::SessionStats => "session statistics",
+            Capability::RecentTranscript => "recent transcript",
+            Capability::TranscribeFile => "file transcription on request",
+            Capability::CaptureSessions => "capture sessions",
+            Capability::FaultInjection => "fault injection",
+            Capability::Unknown => "an unknown feature",
+        };
+        f.write_str(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unknown_capabilities_deserialize() {
+        let parsed: Vec<Capability> =
+            serde_json::from_str(r#"["diarization", "teleportation"]"#).unwrap();
+        assert_eq!(parsed, vec![Capability::Diarization, Capability::Unknown]);
+    }
+
+    #[test]
+    fn channel_selection_needs_capability() {
+        let request = |channels: Option<&str>| Request::SetSources {
+            source1_id: Some("mic".to_string()),
+            source2_id: None,
+            source1_channels: channels.map(|c| crate::ChannelSelection::parse(c).unwrap()),
+            source2_channels: None,
+        };
+        assert_eq!(Capability::required_by(&request(None)), None);
+        assert_eq!(
+            Capability::required_by(&request(Some("3+4"))),
+            Some(Capability::ChannelSelection)
+        );
+
+        assert_eq!(
+            crate::ChannelSelection::parse(" 3 + 4 ")
+                .unwrap()
+                .to_string(),
+            "3+4"
+        );
+        assert!(crate::ChannelSelection::parse("all").unwrap().is_all());
+        assert!(crate::ChannelSelection::parse("0").is_err());
+        assert!(crate::ChannelSelection::parse("3+3").is_err());
+        assert!(crate::ChannelSelection::parse("left").is_err());
+    }
+}
@@ -0,0 +1,145 @@
+//! Machine-readable error codes.
+//!
+//! Error responses and error events carry an [`ErrorCode`] alongside their
+//! English message, so clients can show their own, localized text. Codes that
+//! are about something (a session, a setting, a named item) carry it, so the
+//! localized text can name it too.
+
+use serde::{Deserialize, Serialize};
+
+/// Kinds of named items a request can refer to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemKind {
+    /// A directory on the service's machine
+    Folder,
+    /// A folder watched for new recordings
+    WatchedFolder,
+    /// A registered voice command
+    Command,
+    /// A session hook
+    Hook,
+    /// A settings profile
+    Profile,
+    /// A hotkey binding
+    Hotkey,
+    /// A transcription model
+    Model,
+    /// A background job
+    Job,
+    /// A capture session
+    Capture,
+}
+
+/// What went wrong.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ErrorCode {
+    /// No more specific code applies; only the message describes the error
+    #[default]
+    Other,
+    /// The request is malformed or has invalid parameters
+    InvalidRequest,
+    /// The client spoke before completing the handshake
+    HandshakeRequired,
+    /// The client's protocol version is too old
+    UnsupportedProtocol { version: u32 },
+    /// Another client controls capture
+    CaptureControlled { controller: String },
+    /// The request needs capture to be running
+    NotCapturing,
+    /// No audio device to capture from
+    NoAudioDevice,
+    /// No audio device has this ID
+    DeviceNotFound { id: String },
+    /// The OS refused access to a device or feature
+    PermissionDenied,
+    /// The audio backend could not be started
+    BackendUnavailable,
+    /// Capture failed to start, stop or run
+    CaptureFailed,
+    /// Capture is muted for privacy
+    Muted,
+    /// No saved transcription has this ID
+    SessionNotFound { id: String },
+    /// The saved transcription has no recording
+    NoRecording { id: String },
+    /// Nothing of this kind has this name
+    NotFound { kind: ItemKind, name: String },
+    /// There is no setting with this key
+    UnknownSetting { key: String },
+    /// The value given for a setting is not valid for it
+    InvalidSetting { key: String },
+    /// The model doesn't know the language
+    UnsupportedLanguage { language: String },
+    /// The model is already downloaded
+    ModelAlreadyDownloaded,
+    /// The transcription model file doesn't exist
+    ModelMissing,
+    /// There is no transcription to deliver again
+    NothingToRepeat,
+    /// Dictation was started while already running
+    DictationActive,
+    /// Dictation was stopped while not running
+    DictationInactive,
+    /// Saved data could not be written
+    StorageFailed,
+    /// Transcribing a segment failed
+    TranscriptionFailed,
+    /// The transcription queue has no room for more audio
+    QueueFull,
+    /// There is nothing to work on yet, e.g. no audio has been captured
+    NotReady,
+    /// The request needs playback to be running
+    NotPlaying,
+    /// A recording could not be played
+    PlaybackFailed,
+    /// Text could not be typed or copied
+    OutputFailed,
+    /// Hotkey monitoring could not be started with the new keys
+    HotkeyFailed,
+    /// Noise calibration didn't get enough audio to measure
+    CalibrationFailed,
+    /// The transcription benchmark could not run
+    BenchmarkFailed,
+    /// This platform or desktop session can't do what was asked
+    Unsupported,
+    /// A task handling the request failed unexpectedly
+    Internal,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_code_serialization() {
+        let code = ErrorCode::NotFound {
+            kind: ItemKind::Profile,
+            name: "meetings".to_string(),
+        };
+        let json = serde_json::to_string(&code).unwrap();
+        assert_eq!(
+            json,
+            r#"{"type":"not_found","kind":"profile","name":"meetings"}"#
+        );
+        assert_eq!(serde_json::from_str::<ErrorCode>(&json).unwrap(), code);
+        assert_eq!(
+            serde_json::to_string(&ErrorCode::NotCapturing).unwrap(),
+            r#"{"type":"not_capturing"}"#
+        );
+    }
+
+    #[test]
+    fn test_error_without_code_from_older_service() {
+        let response: crate::ipc::Response =
+            serde_json::from_str(r#"{"type":"error","message":"Session not found: x"}"#).unwrap();
+        match response {
+            crate::ipc::Response::Error { code, message } => {
+                assert_eq!(code, ErrorCode::Other);
+                assert_eq!(message, "Session not found: x");
+            }
+            other => panic!("unexpected response {:?}", other),
+        }
+    }
+}
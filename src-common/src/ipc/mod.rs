@@ -1,9 +1,13 @@
 //! IPC protocol for client-service communication.
 
+mod capabilities;
+mod errors;
 mod protocol;
 mod requests;
 mod responses;
 
+pub use capabilities::*;
+pub use errors::*;
 pub use protocol::*;
 pub use requests::*;
 pub use responses::*;
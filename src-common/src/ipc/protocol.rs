@@ -3,9 +3,31 @@
 use std::path::PathBuf;
 use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 
+use super::Capability;
+
 /// Maximum IPC message size (64 KB)
 pub const MAX_MESSAGE_SIZE: usize = 65536;
 
+/// Version of the request/response protocol, exchanged in the `hello`
+/// handshake. Bump when messages change incompatibly.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Oldest protocol version still spoken
+pub const MIN_PROTOCOL_VERSION: u32 = 1;
+
+/// Pick the protocol version to use with a peer that speaks up to
+/// `peer_version`: the newest version both sides support.
+pub fn negotiate_version(peer_version: u32) -> Result<u32, String> {
+    let version = peer_version.min(PROTOCOL_VERSION);
+    if version < MIN_PROTOCOL_VERSION {
+        return Err(format!(
+            "Protocol version {} is not supported (expected {} to {}); update the client",
+            peer_version, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION
+        ));
+    }
+    Ok(version)
+}
+
 /// Error type for IPC operations.
 #[derive(Debug)]
 pub enum IpcError {
@@ -17,6 +39,8 @@ pub enum IpcError {
     ParseError(String),
     /// Connection closed
     ConnectionClosed,
+    /// The service doesn't support a request
+    Unsupported(Capability),
 }
 
 impl std::fmt::Display for IpcError {
@@ -28,6 +52,11 @@ impl std::fmt::Display for IpcError {
             }
             IpcError::ParseError(e) => write!(f, "Parse error: {}", e),
             IpcError::ConnectionClosed => write!(f, "Connection closed"),
+            IpcError::Unsupported(capability) => write!(
+                f,
+                "The running service doesn't support {}; restart it after updating",
+                capability
+            ),
         }
     }
 }
@@ -157,6 +186,16 @@ mod tests {
         assert_eq!(read, original);
     }
 
+    #[test]
+    fn test_negotiate_version() {
+        assert_eq!(negotiate_version(PROTOCOL_VERSION), Ok(PROTOCOL_VERSION));
+        assert_eq!(
+            negotiate_version(PROTOCOL_VERSION + 1),
+            Ok(PROTOCOL_VERSION)
+        );
+        assert!(negotiate_version(MIN_PROTOCOL_VERSION - 1).is_err());
+    }
+
     #[tokio::test]
     async fn test_message_too_large() {
         let oversized = vec![0u8; MAX_MESSAGE_SIZE + 1];
@@ -2,12 +2,67 @@
 
 use serde::{Deserialize, Serialize};
 
-use crate::types::{AudioSourceType, KeyCode, RecordingMode, TranscriptionMode};
+use crate::types::{
+    AecSettings, AudioFault, AudioSourceType, ChannelSelection, DateRange, HookTarget,
+    HotkeyAction, HotkeyBinding, KeyCode, LatencyPreset, OutputMode, Permission, Profile,
+    RecordingFormat, RecordingMode, SessionFilter, SessionHook, SessionMetadata,
+    SpectrogramSettings, SpeechDetectorConfig, TranscriptionMode, WordTiming,
+    MAX_BENCHMARK_ITERATIONS, MAX_CALIBRATION_SECS, MAX_RECENT_TRANSCRIPT_SECS,
+    MAX_ROLLING_BUFFER_SECS, MIN_CALIBRATION_SECS,
+};
+
+/// Setting keys accepted by `SetConfigValue`.
+pub const CONFIG_KEYS: &[&str] = &[
+    "recordings.dir",
+    "recordings.template",
+    "recordings.format",
+    "recordings.trim_silence",
+    "recordings.silence_threshold_db",
+    "recordings.normalize",
+    "recordings.target_lufs",
+    "audio.buffer_ms",
+    "audio.capture_mode",
+    "audio.sample_rate",
+    "model.path",
+    "model.name",
+    "cues.ptt",
+    "cues.transcription",
+    "cues.volume",
+    "osc.enabled",
+    "osc.address",
+    "osc.prefix",
+    "osc.chatbox",
+    "output.template",
+    "post_processing.profile",
+    "retention.max_size_mb",
+    "retention.max_age_days",
+    "retention.keep_transcripts",
+    "vad_recording.enabled",
+    "vad_recording.split",
+    "vad_recording.pre_roll_ms",
+    "vad_recording.post_roll_ms",
+    "latency.preset",
+    "chapters.silence_secs",
+    "chapters.interval_mins",
+    "rolling_buffer.secs",
+    "redaction.channels",
+    "redaction.emails",
+    "redaction.phone_numbers",
+    "redaction.card_numbers",
+];
 
 /// IPC request from client to service.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum Request {
+    // === Handshake ===
+    /// First request on every connection; the service answers with `welcome`
+    /// and refuses anything else until then
+    Hello {
+        /// Newest protocol version the client speaks
+        protocol_version: u32,
+    },
+
     // === Device Enumeration ===
     /// List all audio devices
     ListDevices {
@@ -15,6 +70,9 @@ pub enum Request {
         #[serde(skip_serializing_if = "Option::is_none")]
         source_type: Option<AudioSourceType>,
     },
+    /// Get how system audio can be captured, with setup guidance when the
+    /// platform's own capture is unavailable
+    GetSystemAudioSupport,
 
     // === Audio Source Configuration ===
     /// Configure audio sources - capture starts automatically when valid sources are set
@@ -25,13 +83,211 @@ pub enum Request {
         /// Secondary audio source ID (system audio for mixing/AEC)
         #[serde(skip_serializing_if = "Option::is_none")]
         source2_id: Option<String>,
+        /// Channels to capture from the primary source's device, remembered
+        /// for that device; None keeps its saved selection
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        source1_channels: Option<ChannelSelection>,
+        /// Channels to capture from the secondary source's device
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        source2_channels: Option<ChannelSelection>,
     },
+    /// Enter or leave privacy mode. Muting tears down all capture streams and
+    /// capture refuses to start until unmuted; unmuting resumes capture from
+    /// the configured sources.
+    SetMuted { muted: bool },
 
     // === Audio Settings ===
     /// Set acoustic echo cancellation enabled
     SetAecEnabled { enabled: bool },
     /// Set recording mode (mixed or echo-cancel)
     SetRecordingMode { mode: RecordingMode },
+    /// Also save each source's raw, pre-AEC capture to its own file
+    SetSourceRecording { enabled: bool },
+    /// Save captured audio to the recordings directory while speech is detected
+    SetVadRecording { enabled: bool },
+    /// Trade transcription latency against accuracy; also sets the speech
+    /// detector's hold time
+    SetLatencyPreset { preset: LatencyPreset },
+    /// Set speech detector thresholds; the running detector picks them up immediately
+    SetVadConfig { config: SpeechDetectorConfig },
+    /// Get the speech detector thresholds
+    GetVadConfig,
+    /// Set echo cancellation tuning; a running AEC restarts with it
+    SetAecSettings { settings: AecSettings },
+    /// Get echo cancellation tuning
+    GetAecSettings,
+    /// Set the spectrogram FFT size, window and visualization rate; running
+    /// visualization picks them up immediately
+    SetSpectrogramSettings { settings: SpectrogramSettings },
+    /// Get the spectrogram settings
+    GetSpectrogramSettings,
+    /// Measure the echo delay by cross-correlating system audio with the
+    /// microphone while audio plays, and seed the AEC with it
+    EstimateAecDelay { duration_secs: u32 },
+    /// Measure ambient noise for a few seconds of capture and adjust the
+    /// speech detector thresholds to it
+    CalibrateNoise { duration_secs: u32 },
+
+    // === Transcription Settings ===
+    /// Set the spoken language ("en", "de", ...) or "auto" to detect it per segment
+    SetLanguage { language: String },
+    /// Also translate completed non-English transcriptions to English
+    SetTranslate { enabled: bool },
+    /// Set where completed transcriptions are delivered
+    SetOutputMode { mode: OutputMode },
+    /// Deliver the last transcription again with `mode`, or the output mode
+    /// when not given, e.g. when the target application missed the keystrokes
+    RepeatLastOutput {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        mode: Option<OutputMode>,
+    },
+    /// Enable or disable speaker labels on completed transcriptions
+    SetDiarization { enabled: bool },
+
+    // === Dictation ===
+    /// Start accumulating transcriptions into a dictated document
+    StartDictation,
+    /// Stop dictation and return the document
+    StopDictation,
+
+    // === Voice Commands ===
+    /// Register a spoken command phrase (replaces an existing one with the same phrase)
+    RegisterCommand {
+        phrase: String,
+        /// Withhold the matching text instead of also delivering it
+        #[serde(default)]
+        suppress_text: bool,
+    },
+    /// Remove a registered command phrase
+    UnregisterCommand { phrase: String },
+    /// List registered command phrases
+    ListCommands,
+
+    // === Profiles ===
+    /// List saved profiles and the one last used
+    ListProfiles,
+    /// Save a profile (replaces an existing one with the same name)
+    SaveProfile { profile: Profile },
+    /// Delete a saved profile
+    DeleteProfile { name: String },
+    /// Apply a saved profile's settings, restarting capture if it sets sources
+    UseProfile { name: String },
+    /// Get the application that currently has keyboard focus
+    GetFocusedApp,
+
+    // === Permissions ===
+    /// Get the access the service has to the microphone and other protected resources
+    GetPermissions,
+    /// Ask the user for access, showing the system prompt if they haven't
+    /// been asked yet; answers with the resulting permissions
+    RequestPermission { permission: Permission },
+
+    // === Session Hooks ===
+    /// Add a hook run on every saved transcription (replaces one with the same name)
+    AddHook { hook: SessionHook },
+    /// Remove a hook by name
+    RemoveHook { name: String },
+    /// List configured hooks
+    ListHooks,
+
+    // === Transcript History ===
+    /// List saved transcriptions, newest first
+    ListSessions {
+        /// Maximum number of sessions to return
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        limit: Option<usize>,
+        /// Only sessions whose metadata matches
+        #[serde(default, skip_serializing_if = "SessionFilter::is_empty")]
+        filter: SessionFilter,
+    },
+    /// Get a saved transcription by ID
+    GetSession { id: String },
+    /// Get the talk time and pace of a saved transcription or capture session
+    GetSessionStats { id: String },
+    /// Get a capture session by ID
+    GetCapture { id: String },
+    /// Get the combined transcript of a capture session, with its chapters
+    GetCaptureTranscript { id: String },
+    /// Delete a saved transcription by ID
+    DeleteSession { id: String },
+    /// Replace a saved transcription's text, and optionally its word timings,
+    /// with a corrected version
+    UpdateSession {
+        id: String,
+        text: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        words: Option<Vec<WordTiming>>,
+    },
+    /// Search the text, title, notes and summaries of saved transcriptions
+    /// for sessions containing every word of `query` (or words starting with
+    /// it), best matches first
+    SearchTranscripts {
+        query: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        date_range: Option<DateRange>,
+        /// Maximum number of hits to return
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        limit: Option<usize>,
+    },
+    /// Replace a saved transcription's title, tags, participants and notes
+    SetSessionMetadata {
+        id: String,
+        metadata: SessionMetadata,
+    },
+    /// Transcribe saved transcriptions again from their recordings with the
+    /// configured model, in the background. The previous transcripts are kept
+    /// as versions; progress arrives as `job_progress` events
+    RetranscribeSessions { ids: Vec<String> },
+    /// List re-transcription jobs, oldest first
+    ListJobs,
+    /// Cancel a queued or running job; sessions already done keep their new
+    /// transcripts
+    CancelJob { id: String },
+
+    // === Playback ===
+    /// Play a saved transcription's recording through the default output
+    /// device, replacing anything already playing
+    PlaySession {
+        id: String,
+        /// Where to start, in milliseconds
+        #[serde(default)]
+        position_ms: u64,
+    },
+    /// Play a WAV file through the default output device, replacing anything
+    /// already playing
+    PlayFile {
+        /// Absolute path of the file
+        path: String,
+        /// Where to start, in milliseconds
+        #[serde(default)]
+        position_ms: u64,
+    },
+    /// Pause or resume playback
+    PausePlayback { paused: bool },
+    /// Jump to a position in the recording being played
+    SeekPlayback { position_ms: u64 },
+    /// Stop playback
+    StopPlayback,
+
+    // === Storage ===
+    /// Get disk usage of recordings and history
+    GetStorageStatus,
+    /// Remove recordings beyond the retention limits now
+    PruneRecordings,
+
+    // === Watch Folders ===
+    /// Watch a directory and transcribe audio files added to it
+    AddWatchFolder { path: String },
+    /// Stop watching a directory
+    RemoveWatchFolder { path: String },
+    /// List watched directories
+    ListWatchFolders,
+
+    // === Settings ===
+    /// Get the settings listed in `CONFIG_KEYS`
+    GetConfig,
+    /// Change a setting by key; an empty value restores the default
+    SetConfigValue { key: String, value: String },
 
     // === State Queries ===
     /// Get current transcription status
@@ -42,10 +298,49 @@ pub enum Request {
     // === Model Management ===
     /// Get Whisper model status
     GetModelStatus,
-    /// Download the Whisper model
-    DownloadModel,
+    /// Discard queued segments and abort the transcription in progress
+    CancelTranscription,
+    /// Save the last `secs` seconds of captured audio (the whole rolling buffer
+    /// when None) as a recording and queue it for transcription
+    CaptureLastN { secs: Option<u32> },
+    /// Get the text transcribed in the last `seconds` of the active capture
+    /// session
+    GetRecentTranscript { seconds: u32 },
+    /// Transcribe a WAV file with the current model and return the transcript
+    /// without saving it to history or delivering it as output
+    TranscribeFile {
+        /// Absolute path of the file
+        path: String,
+    },
+    /// List the downloadable models
+    ListModels,
+    /// Download a catalog model, or the selected one when no name is given
+    DownloadModel {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        name: Option<String>,
+    },
+    /// Load the selected model now instead of on the first transcription
+    LoadModel,
+    /// Free the memory held by the loaded model; it is loaded again when needed
+    UnloadModel,
     /// Get CUDA/GPU acceleration status
     GetCudaStatus,
+    /// Enable GPU acceleration, or force CPU-only transcription
+    SetGpuEnabled { enabled: bool },
+    /// Select the GPU used for transcription by index
+    SetGpuDevice { device: u32 },
+    /// Time transcription of an audio file with the current model, optionally
+    /// repeating the runs CPU-only for comparison
+    Benchmark {
+        /// WAV file to transcribe (the whisper.cpp JFK sample when unset)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        path: Option<String>,
+        /// Number of timed runs per configuration
+        iterations: u32,
+        /// Also run CPU-only when GPU acceleration is enabled
+        #[serde(default)]
+        compare_cpu: bool,
+    },
 
     // === Transcription Mode Control ===
     /// Set the transcription mode (Automatic or PushToTalk)
@@ -60,8 +355,27 @@ pub enum Request {
     },
     /// Get the current PTT status
     GetPttStatus,
+    /// Bind a key to an action, replacing the action's previous key. Binding
+    /// push-to-talk changes the push-to-talk key. Hotkeys work in push-to-talk mode.
+    SetHotkey { binding: HotkeyBinding },
+    /// Remove the key bound to an action (push-to-talk always has a key)
+    RemoveHotkey { action: HotkeyAction },
+    /// List hotkeys, push-to-talk first
+    ListHotkeys,
 
     // === Session Control ===
+    /// Take control of capture. Only the controlling client may start, stop or
+    /// otherwise drive capture; others can still query and subscribe to events.
+    /// Control is also taken implicitly by starting capture while nobody holds it.
+    AcquireControl {
+        /// Name shown to other clients, e.g. in `controller_changed` events
+        client_name: String,
+        /// Take control even if another client holds it
+        #[serde(default)]
+        force: bool,
+    },
+    /// Give up control of capture. Stopping capture or disconnecting also does.
+    ReleaseControl,
     /// Signal that GUI is ready - enables capture when sources are configured
     AppReady,
     /// Signal that GUI is disconnecting - stops capture for security
@@ -70,17 +384,44 @@ pub enum Request {
     // === Service Control ===
     /// Ping for health check
     Ping,
+    /// Get monitoring counters: uptime, throughput, latency and queue history
+    GetMetrics,
+    /// Change the service's log filter, e.g. `debug` or `info,flowstt_service=trace`.
+    /// Lasts until the service restarts.
+    SetLogLevel { level: String },
+    /// Make capture misbehave to test recovery: drop or delay buffers, or
+    /// disconnect a device. Only debug builds of the service accept it.
+    InjectFault { fault: AudioFault },
     /// Request service shutdown
     Shutdown,
 }
 
 impl Request {
+    /// Whether this request drives capture and is therefore reserved to the
+    /// controlling client while one holds control.
+    pub fn controls_capture(&self) -> bool {
+        matches!(
+            self,
+            Request::SetSources { .. }
+                | Request::SetTranscriptionMode { .. }
+                | Request::CancelTranscription
+                | Request::CalibrateNoise { .. }
+                | Request::EstimateAecDelay { .. }
+                | Request::StartDictation
+                | Request::StopDictation
+                | Request::UseProfile { .. }
+                | Request::AppDisconnect
+        )
+    }
+
     /// Validate all parameters in this request.
     pub fn validate(&self) -> Result<(), String> {
         match self {
             Request::SetSources {
                 source1_id,
                 source2_id,
+                source1_channels,
+                source2_channels,
             } => {
                 // Validate source ID format (basic check)
                 if let Some(id) = source1_id {
@@ -93,6 +434,198 @@ impl Request {
                         return Err("source2_id cannot be empty".to_string());
                     }
                 }
+                for selection in [source1_channels, source2_channels].into_iter().flatten() {
+                    selection.validate()?;
+                }
+                Ok(())
+            }
+            Request::SetLanguage { language } => {
+                // Whisper language codes are short lowercase ASCII identifiers
+                if language.is_empty()
+                    || language.len() > 8
+                    || !language.chars().all(|c| c.is_ascii_lowercase())
+                {
+                    return Err(format!("Invalid language code: {:?}", language));
+                }
+                Ok(())
+            }
+            Request::SearchTranscripts {
+                query, date_range, ..
+            } => {
+                if !query.chars().any(|c| c.is_alphanumeric()) {
+                    return Err(format!("Nothing to search for in {:?}", query));
+                }
+                match date_range {
+                    Some(range) => range.validate(),
+                    None => Ok(()),
+                }
+            }
+            Request::RetranscribeSessions { ids } => {
+                if ids.is_empty() {
+                    return Err("No sessions to transcribe".to_string());
+                }
+                if ids.iter().any(|id| id.is_empty()) {
+                    return Err("Session id cannot be empty".to_string());
+                }
+                Ok(())
+            }
+            Request::CancelJob { id } => {
+                if id.is_empty() {
+                    return Err("Job id cannot be empty".to_string());
+                }
+                Ok(())
+            }
+            Request::SetSessionMetadata { id, metadata } => {
+                if id.is_empty() {
+                    return Err("Session id cannot be empty".to_string());
+                }
+                metadata.validate()
+            }
+            Request::GetSession { id }
+            | Request::GetCapture { id }
+            | Request::GetCaptureTranscript { id }
+            | Request::DeleteSession { id }
+            | Request::PlaySession { id, .. } => {
+                if id.is_empty() {
+                    return Err("Session id cannot be empty".to_string());
+                }
+                Ok(())
+            }
+            Request::UpdateSession { id, words, .. } => {
+                if id.is_empty() {
+                    return Err("Session id cannot be empty".to_string());
+                }
+                if let Some(words) = words {
+                    if words.iter().any(|w| w.end_ms < w.start_ms) {
+                        return Err("Word timings cannot end before they start".to_string());
+                    }
+                }
+                Ok(())
+            }
+            Request::AddWatchFolder { path } | Request::RemoveWatchFolder { path } => {
+                if path.is_empty() {
+                    return Err("Folder path cannot be empty".to_string());
+                }
+                Ok(())
+            }
+            Request::PlayFile { path, .. } | Request::TranscribeFile { path } => {
+                if !std::path::Path::new(path).is_absolute() {
+                    return Err(format!("File path must be absolute: {:?}", path));
+                }
+                Ok(())
+            }
+            Request::SetVadConfig { config } => config.validate(),
+            Request::SetAecSettings { settings } => settings.validate(),
+            Request::SetSpectrogramSettings { settings } => settings.validate(),
+            Request::InjectFault { fault } => fault.validate(),
+            Request::EstimateAecDelay { duration_secs } => {
+                if !(2..=10).contains(duration_secs) {
+                    return Err("Delay estimation must last 2 to 10 seconds".to_string());
+                }
+                Ok(())
+            }
+            Request::CalibrateNoise { duration_secs } => {
+                if !(MIN_CALIBRATION_SECS..=MAX_CALIBRATION_SECS).contains(duration_secs) {
+                    return Err(format!(
+                        "Calibration must last {} to {} seconds",
+                        MIN_CALIBRATION_SECS, MAX_CALIBRATION_SECS
+                    ));
+                }
+                Ok(())
+            }
+            Request::CaptureLastN { secs: Some(secs) } => {
+                if !(1..=MAX_ROLLING_BUFFER_SECS).contains(secs) {
+                    return Err(format!(
+                        "Capture must cover 1 to {} seconds",
+                        MAX_ROLLING_BUFFER_SECS
+                    ));
+                }
+                Ok(())
+            }
+            Request::GetRecentTranscript { seconds } => {
+                if !(1..=MAX_RECENT_TRANSCRIPT_SECS).contains(seconds) {
+                    return Err(format!(
+                        "Recent transcript must cover 1 to {} seconds",
+                        MAX_RECENT_TRANSCRIPT_SECS
+                    ));
+                }
+                Ok(())
+            }
+            Request::Benchmark { iterations, .. } => {
+                if !(1..=MAX_BENCHMARK_ITERATIONS).contains(iterations) {
+                    return Err(format!(
+                        "Benchmark iterations must be 1 to {}",
+                        MAX_BENCHMARK_ITERATIONS
+                    ));
+                }
+                Ok(())
+            }
+            Request::RegisterCommand { phrase, .. } | Request::UnregisterCommand { phrase } => {
+                if !phrase.chars().any(|c| c.is_alphanumeric()) {
+                    return Err(format!("Invalid command phrase: {:?}", phrase));
+                }
+                Ok(())
+            }
+            Request::SaveProfile { profile } => {
+                if profile.name.trim().is_empty() {
+                    return Err("Profile name cannot be empty".to_string());
+                }
+                if profile.source2_id.is_some() && profile.source1_id.is_none() {
+                    return Err("A profile's secondary source needs a primary source".to_string());
+                }
+                Ok(())
+            }
+            Request::AddHook { hook } => {
+                if hook.name.trim().is_empty() {
+                    return Err("Hook name cannot be empty".to_string());
+                }
+                match &hook.target {
+                    HookTarget::Command { command } if command.trim().is_empty() => {
+                        Err("Hook command cannot be empty".to_string())
+                    }
+                    HookTarget::Http { url }
+                        if !url.starts_with("http://") && !url.starts_with("https://") =>
+                    {
+                        Err(format!(
+                            "Hook URL must start with http:// or https://: {:?}",
+                            url
+                        ))
+                    }
+                    _ => Ok(()),
+                }
+            }
+            Request::AcquireControl { client_name, .. } => {
+                if client_name.trim().is_empty() {
+                    return Err("Client name cannot be empty".to_string());
+                }
+                Ok(())
+            }
+            Request::SetLogLevel { level } => {
+                if level.trim().is_empty() {
+                    return Err("Log level cannot be empty".to_string());
+                }
+                Ok(())
+            }
+            Request::SetConfigValue { key, value } => {
+                if !CONFIG_KEYS.contains(&key.as_str()) {
+                    return Err(format!(
+                        "Unknown setting {:?} (expected one of: {})",
+                        key,
+                        CONFIG_KEYS.join(", ")
+                    ));
+                }
+                if key == "recordings.template" && value.contains(['/', '\\']) {
+                    return Err("Filename template cannot contain path separators".to_string());
+                }
+                if key == "recordings.format"
+                    && !value.is_empty()
+                    && RecordingFormat::from_name(value).is_none()
+                {
+                    return Err(format!(
+                        "Unknown recording format {:?} (expected wav, flac or opus)",
+                        value
+                    ));
+                }
                 Ok(())
             }
             // Other requests have no parameters to validate
@@ -1,32 +1,147 @@
 //! IPC response types.
 
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
+use super::{Capability, ErrorCode};
 use crate::types::{
-    AudioDevice, CudaStatus, ModelStatus, PttStatus, TranscribeStatus, TranscriptionResult,
-    VisualizationData,
+    AecMetrics, AecSettings, AudioDevice, BenchmarkResult, CaptureSession, CudaStatus, FocusedApp,
+    HotkeyBinding, ModelStatus, ModelVariant, NoiseCalibration, Permissions, PlaybackStatus,
+    Profile, PruneResult, PttStatus, RecentTranscript, RetranscribeJob, SearchHit, ServiceMetrics,
+    SessionHook, SessionStats, SessionSummary, SourceFormat, SourceLevels, SpectrogramSettings,
+    SpeechDetectorConfig, StorageStatus, SystemAudioSupport, TranscribeStatus, TranscriptSession,
+    TranscriptionResult, VisualizationData, VoiceCommand,
 };
 
 /// IPC response from service to client.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum Response {
+    // === Handshake ===
+    /// Answer to `hello`
+    Welcome {
+        /// Protocol version used for the rest of the connection
+        protocol_version: u32,
+        /// Version of the service
+        service_version: String,
+        /// Optional features the service supports
+        capabilities: Vec<Capability>,
+    },
+
     // === Success Responses ===
     /// List of audio devices
     Devices { devices: Vec<AudioDevice> },
 
+    /// How system audio can be captured
+    SystemAudioSupport(SystemAudioSupport),
+
     /// Current transcription status
     Status(TranscribeStatus),
 
     /// Whisper model status
     ModelStatus(ModelStatus),
 
+    /// Downloadable models
+    Models { models: Vec<ModelVariant> },
+
     /// CUDA/GPU status
     CudaStatus(CudaStatus),
 
+    /// Transcription benchmark timings
+    Benchmark(BenchmarkResult),
+
     /// Push-to-talk status
     PttStatus(PttStatus),
 
+    /// Monitoring counters
+    Metrics(ServiceMetrics),
+
+    /// Speech detector thresholds
+    VadConfig(SpeechDetectorConfig),
+
+    /// Echo cancellation tuning
+    AecSettings(AecSettings),
+
+    /// Spectrogram resolution and visualization rate
+    SpectrogramSettings(SpectrogramSettings),
+
+    /// Ambient noise measurement and the thresholds derived from it
+    NoiseCalibration(NoiseCalibration),
+
+    /// Saved transcriptions, newest first
+    Sessions { sessions: Vec<SessionSummary> },
+
+    /// A single saved transcription
+    Session(Box<TranscriptSession>),
+
+    /// Talk time and pace of a saved transcription
+    SessionStats(SessionStats),
+
+    /// A capture session and what was made of its transcriptions
+    Capture(Box<CaptureSession>),
+
+    /// Saved transcriptions matching a search, best first
+    SearchResults { hits: Vec<SearchHit> },
+
+    /// A re-transcription job was queued
+    Job(RetranscribeJob),
+
+    /// Re-transcription jobs, oldest first
+    Jobs { jobs: Vec<RetranscribeJob> },
+
+    /// Playback started
+    Playback(PlaybackStatus),
+
+    /// Disk usage of recordings and history
+    Storage(StorageStatus),
+
+    /// Recordings removed by the retention limits
+    Pruned(PruneResult),
+
+    /// Watched directories
+    WatchFolders { folders: Vec<String> },
+
+    /// A dictated document
+    Dictation { text: String },
+
+    /// Current settings by key
+    ConfigValues { values: BTreeMap<String, String> },
+
+    /// Registered voice commands
+    Commands { commands: Vec<VoiceCommand> },
+
+    /// The application that has keyboard focus
+    FocusedApp(FocusedApp),
+
+    /// Access the service has to protected resources
+    Permissions(Permissions),
+
+    /// Configured session hooks
+    Hooks { hooks: Vec<SessionHook> },
+
+    /// Audio saved from the rolling buffer and queued for transcription
+    Captured { path: String, duration_ms: u64 },
+
+    /// Text transcribed recently in the active capture session
+    RecentTranscript(RecentTranscript),
+
+    /// Transcript of a file; its text is empty when no speech was heard
+    Transcribed(TranscriptionResult),
+
+    /// Configured hotkeys, push-to-talk first
+    Hotkeys { bindings: Vec<HotkeyBinding> },
+
+    /// The last transcription was delivered again
+    RepeatedOutput { text: String },
+
+    /// Saved profiles
+    Profiles {
+        profiles: Vec<Profile>,
+        /// Name of the profile last used, if any
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        active: Option<String>,
+    },
+
     /// Subscribed to events
     Subscribed,
 
@@ -38,7 +153,13 @@ pub enum Response {
 
     // === Error Response ===
     /// Error occurred
-    Error { message: String },
+    Error {
+        /// What went wrong, for clients that show their own text
+        #[serde(default)]
+        code: ErrorCode,
+        /// Description of the error in English
+        message: String,
+    },
 
     // === Event Responses (after Subscribe) ===
     /// Real-time event
@@ -52,9 +173,49 @@ pub enum EventType {
     /// Visualization data update
     VisualizationData(VisualizationData),
 
+    /// Per-source input levels, sent about ten times a second during capture
+    Levels(SourceLevels),
+
+    /// A capture source's native format became known or changed
+    SourceFormat(SourceFormat),
+
+    /// Echo cancellation quality, sent about once a second while it runs
+    AecMetrics(AecMetrics),
+
     /// Transcription result for a segment
     TranscriptionComplete(TranscriptionResult),
 
+    /// Interim transcription of the speech segment still being captured.
+    /// Superseded by later partials and by the final `TranscriptionComplete`.
+    TranscriptionPartial(TranscriptionResult),
+
+    /// Text decoded so far for a segment still being transcribed, sent as
+    /// each token is decoded. Superseded by the final `TranscriptionComplete`.
+    TranscriptionToken {
+        /// Provisional segment text
+        text: String,
+    },
+
+    /// Queued and in-progress transcriptions were cancelled
+    TranscriptionCancelled {
+        /// Number of queued segments that were discarded
+        discarded: usize,
+    },
+
+    /// The dictated document changed
+    DictationUpdated {
+        /// Full document text so far
+        text: String,
+    },
+
+    /// A transcribed segment matched a registered voice command
+    CommandDetected {
+        /// The registered phrase that matched
+        phrase: String,
+        /// The transcribed text
+        text: String,
+    },
+
     /// Speech started (segment recording began)
     SpeechStarted,
 
@@ -70,32 +231,87 @@ pub enum EventType {
         error: Option<String>,
     },
 
+    /// A client took or gave up control of capture
+    ControllerChanged {
+        /// Name of the controlling client, or None if nobody holds control
+        #[serde(skip_serializing_if = "Option::is_none")]
+        controller: Option<String>,
+    },
+
+    /// A profile was applied
+    ProfileChanged { name: String },
+
+    /// Position of the recording being played, sent a few times per second
+    PlaybackPosition(PlaybackStatus),
+
+    /// Playback reached the end of the recording or was stopped
+    PlaybackStopped {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        session_id: Option<String>,
+    },
+
+    /// A session hook stored a summary with a capture session
+    SessionSummarized { id: String, summary: String },
+
+    /// Privacy mode was entered or left
+    PrivacyMode { muted: bool },
+
+    /// Capture switched to another device because the selected one disappeared
+    SourceSwitched {
+        /// ID of the device that was removed
+        previous: String,
+        /// ID of the device now being captured
+        current: String,
+    },
+
     /// Model download progress
     ModelDownloadProgress { percent: u8 },
 
     /// Model download complete
     ModelDownloadComplete { success: bool },
 
+    /// A re-transcription job started, finished a session or stopped
+    JobProgress(RetranscribeJob),
+
     /// Push-to-talk key pressed
     PttPressed,
 
     /// Push-to-talk key released
     PttReleased,
 
+    /// Push-to-talk engaged or released, or a transcription completed; sent
+    /// whether or not the service plays an audible cue for it
+    FeedbackCue { cue: crate::types::FeedbackCue },
+
     /// Transcription mode changed (Auto vs PTT)
     TranscriptionModeChanged {
         /// The new transcription mode
         mode: crate::types::TranscriptionMode,
     },
 
+    /// An error occurred in the background (e.g. a transcription failed)
+    Error {
+        /// What went wrong, for clients that show their own text
+        #[serde(default)]
+        code: ErrorCode,
+        /// Description of the error in English
+        message: String,
+    },
+
     /// Service is shutting down
     Shutdown,
 }
 
 impl Response {
-    /// Create an error response.
+    /// Create an error response without a specific code.
     pub fn error(message: impl Into<String>) -> Self {
+        Self::error_with_code(ErrorCode::Other, message)
+    }
+
+    /// Create an error response with a code clients can show their own text for.
+    pub fn error_with_code(code: ErrorCode, message: impl Into<String>) -> Self {
         Response::Error {
+            code,
             message: message.into(),
         }
     }
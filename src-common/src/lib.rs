@@ -3,8 +3,12 @@
 //! Shared types and IPC protocol for communication between the FlowSTT CLI,
 //! service, and GUI components.
 
+pub mod error;
+pub mod export;
 pub mod ipc;
+pub mod paths;
 pub mod security;
 pub mod types;
 
+pub use error::FlowSttError;
 pub use types::*;
@@ -0,0 +1,44 @@
+//! Well-known file locations shared by the service and its clients.
+
+use std::path::PathBuf;
+
+use directories::BaseDirs;
+
+/// Prefix of the service's daily log files in [`log_dir`]
+pub const SERVICE_LOG_PREFIX: &str = "service";
+
+/// Extension of the service's log files
+pub const SERVICE_LOG_SUFFIX: &str = "log";
+
+/// Directory service logs are written to.
+///
+/// Returns platform-specific path:
+/// - Linux: ~/.local/share/flowstt/logs
+/// - macOS: ~/Library/Application Support/flowstt/logs
+/// - Windows: %APPDATA%\flowstt\logs
+pub fn log_dir() -> PathBuf {
+    BaseDirs::new()
+        .map(|d| d.data_dir().to_path_buf())
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("flowstt")
+        .join("logs")
+}
+
+/// Log file the service is currently writing to, if any.
+///
+/// Files are named `service.<date>.log`, so the newest one sorts last.
+pub fn active_service_log() -> Option<PathBuf> {
+    std::fs::read_dir(log_dir())
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| {
+                    name.starts_with(&format!("{}.", SERVICE_LOG_PREFIX))
+                        && name.ends_with(&format!(".{}", SERVICE_LOG_SUFFIX))
+                })
+        })
+        .max()
+}
@@ -13,6 +13,8 @@ pub enum AudioSourceType {
     System,
     /// Mixed input and system audio
     Mixed,
+    /// Audio received over the network (RTP/UDP)
+    Network,
 }
 
 /// Recording mode - determines how multiple audio sources are combined.
@@ -24,6 +26,8 @@ pub enum RecordingMode {
     Mixed,
     /// Echo cancellation mode - output only echo-cancelled primary source
     EchoCancel,
+    /// Keep sources separate - primary source on the left channel, secondary on the right
+    SplitChannels,
 }
 
 /// Transcription mode - determines how speech segment boundaries are identified.
@@ -37,6 +41,312 @@ pub enum TranscriptionMode {
     PushToTalk,
 }
 
+/// Trade-off between how soon transcriptions appear and how accurate they are.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LatencyPreset {
+    /// Short segments and no decoding retries
+    Fast,
+    /// The long-standing defaults
+    #[default]
+    Balanced,
+    /// Longer segments and beam search decoding
+    Accurate,
+}
+
+/// Settings bundled by a [`LatencyPreset`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LatencySettings {
+    /// Segment duration after which a word break is sought, in milliseconds
+    pub max_segment_ms: u64,
+    /// Silence that ends speech, in milliseconds
+    pub hold_ms: u32,
+    /// Beam search width; 1 decodes greedily
+    pub beam_size: u32,
+    /// Temperature increase for each decoding retry; 0 disables retries
+    pub temperature_inc: f32,
+}
+
+impl LatencyPreset {
+    pub const ALL: [LatencyPreset; 3] = [
+        LatencyPreset::Fast,
+        LatencyPreset::Balanced,
+        LatencyPreset::Accurate,
+    ];
+
+    /// Name used in settings ("fast", "balanced" or "accurate").
+    pub fn name(&self) -> &'static str {
+        match self {
+            LatencyPreset::Fast => "fast",
+            LatencyPreset::Balanced => "balanced",
+            LatencyPreset::Accurate => "accurate",
+        }
+    }
+
+    /// Parse a preset name as used in settings.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|p| p.name().eq_ignore_ascii_case(name))
+    }
+
+    /// The settings this preset stands for.
+    pub fn settings(&self) -> LatencySettings {
+        match self {
+            LatencyPreset::Fast => LatencySettings {
+                max_segment_ms: 2500,
+                hold_ms: 200,
+                beam_size: 1,
+                temperature_inc: 0.0,
+            },
+            LatencyPreset::Balanced => LatencySettings {
+                max_segment_ms: 4000,
+                hold_ms: 300,
+                beam_size: 1,
+                temperature_inc: 0.2,
+            },
+            LatencyPreset::Accurate => LatencySettings {
+                max_segment_ms: 8000,
+                hold_ms: 500,
+                beam_size: 5,
+                temperature_inc: 0.2,
+            },
+        }
+    }
+}
+
+/// Output mode - determines where completed transcriptions are delivered.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputMode {
+    /// Deliver transcriptions only as events to connected clients
+    #[default]
+    EventsOnly,
+    /// Also type transcriptions into the focused application as keystrokes
+    Type,
+    /// Also copy each transcription to the clipboard
+    Clipboard,
+}
+
+/// File format for saved recordings.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordingFormat {
+    /// Uncompressed 32-bit float WAV
+    #[default]
+    Wav,
+    /// Lossless FLAC (16-bit)
+    Flac,
+    /// Lossy Ogg Opus, much smaller for speech
+    Opus,
+}
+
+impl RecordingFormat {
+    /// File extension for this format.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            RecordingFormat::Wav => "wav",
+            RecordingFormat::Flac => "flac",
+            RecordingFormat::Opus => "opus",
+        }
+    }
+
+    /// Parse a format name as used in the config ("wav", "flac" or "opus").
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "wav" => Some(RecordingFormat::Wav),
+            "flac" => Some(RecordingFormat::Flac),
+            "opus" => Some(RecordingFormat::Opus),
+            _ => None,
+        }
+    }
+}
+
+/// Longest echo delay the AEC can be seeded with, in milliseconds.
+pub const MAX_AEC_DELAY_MS: u32 = 500;
+
+/// Echo cancellation tuning.
+///
+/// Bluetooth headsets can delay system audio by a few hundred milliseconds;
+/// seeding the AEC with that delay lets it converge instead of searching.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AecSettings {
+    /// Delay from system audio playback to its echo at the microphone, in milliseconds
+    pub initial_delay_ms: u32,
+    /// Length of echo path the adaptive filter models, in milliseconds
+    pub filter_length_ms: u32,
+}
+
+impl Default for AecSettings {
+    fn default() -> Self {
+        Self {
+            initial_delay_ms: 0,
+            filter_length_ms: 52,
+        }
+    }
+}
+
+impl AecSettings {
+    /// Check that all values are in range.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.initial_delay_ms > MAX_AEC_DELAY_MS {
+            return Err(format!(
+                "initial_delay_ms must be at most {} ms",
+                MAX_AEC_DELAY_MS
+            ));
+        }
+        if !(20..=500).contains(&self.filter_length_ms) {
+            return Err("filter_length_ms must be between 20 and 500 ms".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Longest speech onset time, limited by the detector's 200ms lookback buffer.
+pub const MAX_VAD_ONSET_MS: u32 = 200;
+
+/// Voice activity detection backend.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VadBackend {
+    /// Built-in energy, zero-crossing and spectral heuristics
+    #[default]
+    Energy,
+    /// Silero VAD neural network, run with ONNX Runtime
+    Silero,
+}
+
+/// Speech detector thresholds.
+///
+/// Voiced mode catches normal speech, whisper mode soft or breathy speech.
+/// Lower thresholds pick up quieter voices; higher ones ignore more
+/// background noise. The threshold and zero-crossing settings apply to the
+/// energy backend only; the Silero backend uses `speech_probability` instead.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SpeechDetectorConfig {
+    /// Which detector to run
+    pub backend: VadBackend,
+    /// Minimum level for voiced speech, in dBFS
+    pub voiced_threshold_db: f32,
+    /// Zero-crossing rate range (min, max) for voiced speech
+    pub voiced_zcr_range: (f32, f32),
+    /// How long voiced speech must last before it counts, in milliseconds
+    pub voiced_onset_ms: u32,
+    /// Minimum level for whispered speech, in dBFS
+    pub whisper_threshold_db: f32,
+    /// Zero-crossing rate range (min, max) for whispered speech
+    pub whisper_zcr_range: (f32, f32),
+    /// How long whispered speech must last before it counts, in milliseconds
+    pub whisper_onset_ms: u32,
+    /// Silence before speech is considered ended, in milliseconds
+    pub hold_ms: u32,
+    /// Level above which audio before a confirmed onset is kept as part of the speech, in dBFS
+    pub lookback_threshold_db: f32,
+    /// Silero speech probability (0 to 1) above which a window counts as speech
+    pub speech_probability: f32,
+}
+
+impl Default for SpeechDetectorConfig {
+    fn default() -> Self {
+        Self {
+            backend: VadBackend::Energy,
+            voiced_threshold_db: -42.0,
+            voiced_zcr_range: (0.01, 0.30),
+            voiced_onset_ms: 80,
+            whisper_threshold_db: -52.0,
+            whisper_zcr_range: (0.08, 0.45),
+            whisper_onset_ms: 120,
+            hold_ms: 300,
+            lookback_threshold_db: -55.0,
+            speech_probability: 0.5,
+        }
+    }
+}
+
+impl SpeechDetectorConfig {
+    /// Check that all values are in range.
+    pub fn validate(&self) -> Result<(), String> {
+        for (name, db) in [
+            ("voiced_threshold_db", self.voiced_threshold_db),
+            ("whisper_threshold_db", self.whisper_threshold_db),
+            ("lookback_threshold_db", self.lookback_threshold_db),
+        ] {
+            if !(-100.0..0.0).contains(&db) {
+                return Err(format!("{} must be between -100 and 0 dB", name));
+            }
+        }
+        for (name, (min, max)) in [
+            ("voiced_zcr_range", self.voiced_zcr_range),
+            ("whisper_zcr_range", self.whisper_zcr_range),
+        ] {
+            if !(0.0 <= min && min < max && max <= 1.0) {
+                return Err(format!("{} must satisfy 0 <= min < max <= 1", name));
+            }
+        }
+        for (name, ms) in [
+            ("voiced_onset_ms", self.voiced_onset_ms),
+            ("whisper_onset_ms", self.whisper_onset_ms),
+        ] {
+            if ms == 0 || ms > MAX_VAD_ONSET_MS {
+                return Err(format!(
+                    "{} must be between 1 and {} ms",
+                    name, MAX_VAD_ONSET_MS
+                ));
+            }
+        }
+        if !(50..=5000).contains(&self.hold_ms) {
+            return Err("hold_ms must be between 50 and 5000 ms".to_string());
+        }
+        if !(0.05..=0.95).contains(&self.speech_probability) {
+            return Err("speech_probability must be between 0.05 and 0.95".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Shortest and longest ambient noise calibration, in seconds.
+pub const MIN_CALIBRATION_SECS: u32 = 5;
+pub const MAX_CALIBRATION_SECS: u32 = 10;
+
+/// Longest rolling buffer kept for retroactive capture, in seconds.
+pub const MAX_ROLLING_BUFFER_SECS: u32 = 600;
+
+/// Longest stretch of the active session's transcript that can be requested,
+/// in seconds.
+pub const MAX_RECENT_TRANSCRIPT_SECS: u32 = 3600;
+
+/// Shortest capture buffer duration that can be requested, in milliseconds.
+pub const MIN_CAPTURE_BUFFER_MS: u32 = 2;
+
+/// Longest capture buffer duration that can be requested, in milliseconds.
+pub const MAX_CAPTURE_BUFFER_MS: u32 = 500;
+
+/// Sample rates capture can be set to, in Hz. At 16 kHz a single source is
+/// captured as 16 kHz mono, the format transcription uses, where the backend
+/// can convert to it.
+pub const CAPTURE_SAMPLE_RATES: [u32; 2] = [16000, 48000];
+
+/// Result of measuring the ambient noise floor.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NoiseCalibration {
+    /// Level the ambient noise stays below 90% of the time, in dBFS
+    pub noise_floor_db: f32,
+    /// Median zero-crossing rate of the noise
+    pub noise_zcr: f32,
+    /// Median spectral centroid of the noise, in Hz (0 if too quiet to measure)
+    pub noise_centroid_hz: f32,
+    /// Speech detector settings derived from the measurement, now in effect
+    pub config: SpeechDetectorConfig,
+}
+
+/// Language code that requests automatic spoken-language detection.
+pub const AUTO_DETECT_LANGUAGE: &str = "auto";
+
+/// Default transcription language (matches the bundled English model).
+pub const DEFAULT_LANGUAGE: &str = "en";
+
 /// Platform-independent key codes for push-to-talk hotkey configuration.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -95,6 +405,88 @@ impl KeyCode {
     }
 }
 
+/// What a global hotkey does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HotkeyAction {
+    /// Hold to transcribe; the result is delivered by the output mode
+    PushToTalk,
+    /// Hold to transcribe and type the result into the focused application
+    PushToTalkType,
+    /// Hold to transcribe and copy the result to the clipboard
+    PushToTalkClipboard,
+    /// Press to start transcribing and press again to stop
+    ToggleSession,
+    /// Press to turn privacy mode on or off
+    ToggleMute,
+    /// Press to deliver the last transcription again with the output mode
+    RepeatLast,
+}
+
+impl HotkeyAction {
+    pub const ALL: [HotkeyAction; 6] = [
+        HotkeyAction::PushToTalk,
+        HotkeyAction::PushToTalkType,
+        HotkeyAction::PushToTalkClipboard,
+        HotkeyAction::ToggleSession,
+        HotkeyAction::ToggleMute,
+        HotkeyAction::RepeatLast,
+    ];
+
+    /// Name used in settings and IPC.
+    pub fn name(&self) -> &'static str {
+        match self {
+            HotkeyAction::PushToTalk => "push_to_talk",
+            HotkeyAction::PushToTalkType => "push_to_talk_type",
+            HotkeyAction::PushToTalkClipboard => "push_to_talk_clipboard",
+            HotkeyAction::ToggleSession => "toggle_session",
+            HotkeyAction::ToggleMute => "toggle_mute",
+            HotkeyAction::RepeatLast => "repeat_last",
+        }
+    }
+
+    /// Short description, e.g. for the desktop's shortcut settings.
+    pub fn description(&self) -> &'static str {
+        match self {
+            HotkeyAction::PushToTalk => "Push-to-talk (hold to transcribe)",
+            HotkeyAction::PushToTalkType => "Hold to transcribe and type",
+            HotkeyAction::PushToTalkClipboard => "Hold to transcribe to the clipboard",
+            HotkeyAction::ToggleSession => "Start or stop transcribing",
+            HotkeyAction::ToggleMute => "Turn privacy mode on or off",
+            HotkeyAction::RepeatLast => "Insert the last transcription again",
+        }
+    }
+
+    /// Whether the action records while the key is held.
+    pub fn is_push_to_talk(&self) -> bool {
+        matches!(
+            self,
+            HotkeyAction::PushToTalk
+                | HotkeyAction::PushToTalkType
+                | HotkeyAction::PushToTalkClipboard
+        )
+    }
+}
+
+/// A global hotkey and the action it triggers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HotkeyBinding {
+    pub key: KeyCode,
+    pub action: HotkeyAction,
+}
+
+/// Short feedback cue for a capture milestone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FeedbackCue {
+    /// Push-to-talk engaged and recording started
+    PttStart,
+    /// Push-to-talk released and recording stopped
+    PttStop,
+    /// A transcription completed
+    TranscriptionDone,
+}
+
 /// Push-to-talk status information.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PttStatus {
@@ -135,6 +527,88 @@ pub struct AudioDevice {
     pub source_type: AudioSourceType,
 }
 
+/// Channels of a multichannel device to capture, numbered from 1. A single
+/// channel is captured as mono and several are downmixed to mono; an empty
+/// selection captures the device's own channels.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ChannelSelection {
+    pub channels: Vec<u16>,
+}
+
+impl ChannelSelection {
+    /// Highest channel number that can be selected
+    pub const MAX_CHANNEL: u16 = 64;
+
+    /// Whether the device's own channels are captured.
+    pub fn is_all(&self) -> bool {
+        self.channels.is_empty()
+    }
+
+    /// Parse a selection such as `3`, `3+4` or `all`.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let text = text.trim();
+        if text.eq_ignore_ascii_case("all") {
+            return Ok(Self::default());
+        }
+        let channels =
+            text.split(['+', ','])
+                .map(|channel| {
+                    channel.trim().parse().map_err(|_| {
+                        format!("Invalid channel {:?} (expected e.g. 3 or 3+4)", channel)
+                    })
+                })
+                .collect::<Result<Vec<u16>, String>>()?;
+        let selection = Self { channels };
+        selection.validate()?;
+        Ok(selection)
+    }
+
+    /// Check that channels are in range and not repeated.
+    pub fn validate(&self) -> Result<(), String> {
+        for (i, &channel) in self.channels.iter().enumerate() {
+            if !(1..=Self::MAX_CHANNEL).contains(&channel) {
+                return Err(format!(
+                    "Channel {} is out of range (1 to {})",
+                    channel,
+                    Self::MAX_CHANNEL
+                ));
+            }
+            if self.channels[..i].contains(&channel) {
+                return Err(format!("Channel {} is selected twice", channel));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for ChannelSelection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_all() {
+            return f.write_str("all");
+        }
+        let channels: Vec<String> = self.channels.iter().map(u16::to_string).collect();
+        f.write_str(&channels.join("+"))
+    }
+}
+
+/// How system audio can be captured on this machine.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SystemAudioSupport {
+    /// Whether the operating system's own system audio capture can be used
+    pub native: bool,
+    /// Why native capture can't be used, when it can't
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub native_error: Option<String>,
+    /// Installed virtual loopback devices (such as BlackHole), listed as
+    /// system sources
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub loopback_devices: Vec<AudioDevice>,
+    /// What to set up to capture system audio, when native capture can't be used
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub setup: Option<String>,
+}
+
 /// Status of the transcription system.
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct TranscribeStatus {
@@ -147,6 +621,70 @@ pub struct TranscribeStatus {
     /// Error message if capture failed (e.g., invalid source)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
+    /// Echo cancellation quality, while echo cancellation is running
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aec_metrics: Option<AecMetrics>,
+    /// Alignment of the microphone and system audio, while both are mixed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sync_metrics: Option<SyncMetrics>,
+    /// Buffer latency each capture source ended up with
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub capture_latency: Vec<CaptureLatency>,
+    /// Name of the client controlling capture, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub controller: Option<String>,
+    /// Whether privacy mode is on, keeping capture stopped
+    #[serde(default)]
+    pub muted: bool,
+    /// Why input devices can't be captured, with where to allow access, when
+    /// the operating system blocks the microphone
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub permission_error: Option<String>,
+}
+
+/// Queue depth at a point in time.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct QueueDepthSample {
+    /// Seconds since the service started
+    pub uptime_secs: f64,
+    /// Segments waiting to be transcribed
+    pub depth: usize,
+}
+
+/// Transcription latency percentiles over recent segments, in milliseconds.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct LatencyPercentiles {
+    pub p50_ms: u64,
+    pub p90_ms: u64,
+    pub p99_ms: u64,
+    /// Number of segments the percentiles are computed from
+    pub samples: usize,
+}
+
+/// Counters and recent history for monitoring the service.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceMetrics {
+    /// Seconds since the service started
+    pub uptime_secs: u64,
+    /// Mono samples run through speech detection since startup
+    pub samples_processed: u64,
+    /// Audio frames discarded because the transcription queue was full
+    pub dropped_frames: u64,
+    /// Live segments transcribed since startup
+    pub transcriptions: u64,
+    /// Segments currently waiting to be transcribed
+    pub queue_depth: usize,
+    /// Recent queue depth changes, oldest first
+    pub queue_depth_history: Vec<QueueDepthSample>,
+    /// Time from starting to finishing a live segment's transcription,
+    /// if any were transcribed recently
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency: Option<LatencyPercentiles>,
+    /// Whether echo cancellation is enabled
+    pub aec_enabled: bool,
+    /// Last echo cancellation quality measurement
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aec_metrics: Option<AecMetrics>,
 }
 
 /// Status of the Whisper model.
@@ -156,6 +694,84 @@ pub struct ModelStatus {
     pub available: bool,
     /// Path to the model file
     pub path: String,
+    /// Whether the path was configured rather than the default download location
+    #[serde(default)]
+    pub custom: bool,
+    /// Whether the model is loaded in memory
+    #[serde(default)]
+    pub loaded: bool,
+    /// Details read from the model file, when it exists and is valid
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub info: Option<ModelInfo>,
+}
+
+/// Details of a whisper model file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelInfo {
+    /// File size in bytes
+    pub size_bytes: u64,
+    /// Model size ("tiny", "base", "small", "medium", "large", ...)
+    pub model_type: String,
+    /// Weight format ("f16", "q5_1", ...)
+    pub quantization: String,
+    /// Whether the model supports languages other than English
+    pub multilingual: bool,
+}
+
+/// A downloadable model from the catalog.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelVariant {
+    /// Catalog name ("base.en", "small-q5_1", ...)
+    pub name: String,
+    /// Weight format ("f16", "q5_1", "q8_0", ...)
+    pub quantization: String,
+    /// Whether the model supports languages other than English
+    pub multilingual: bool,
+    /// Approximate download size in MB
+    pub size_mb: u32,
+    /// Estimated memory needed to run the model in MB
+    pub memory_mb: u32,
+    /// Whether the model has been downloaded
+    pub downloaded: bool,
+    /// Whether this is the model used for transcription
+    pub active: bool,
+}
+
+/// Largest number of timed runs a benchmark may request.
+pub const MAX_BENCHMARK_ITERATIONS: u32 = 20;
+
+/// Transcription throughput measured by a benchmark.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchmarkResult {
+    /// Path to the model that was benchmarked
+    pub model_path: String,
+    /// Audio file that was transcribed
+    pub audio_path: String,
+    /// Duration of the audio in milliseconds
+    pub audio_duration_ms: u64,
+    /// Number of timed runs per configuration
+    pub iterations: u32,
+    /// One entry per configuration tested (current settings first, then CPU-only)
+    pub runs: Vec<BenchmarkRun>,
+}
+
+/// Timings for one GPU/CPU configuration of a benchmark.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchmarkRun {
+    /// Whether GPU acceleration was enabled
+    pub gpu: bool,
+    /// Time to load the model in milliseconds (near zero when it was already loaded)
+    pub load_ms: u64,
+    /// Mean transcription time in milliseconds
+    pub mean_ms: u64,
+    /// Fastest transcription time in milliseconds
+    pub min_ms: u64,
+    /// Slowest transcription time in milliseconds
+    pub max_ms: u64,
+    /// Mean transcription time divided by the audio duration (below 1 is faster than real time)
+    pub real_time_factor: f32,
+    /// Text tokens decoded per second
+    pub tokens_per_sec: f32,
 }
 
 /// CUDA/GPU acceleration status.
@@ -167,6 +783,10 @@ pub struct CudaStatus {
     pub runtime_available: bool,
     /// System info string from whisper.cpp
     pub system_info: String,
+    /// Whether GPU acceleration is enabled (false forces CPU mode)
+    pub gpu_enabled: bool,
+    /// Index of the GPU used for transcription
+    pub gpu_device: u32,
 }
 
 /// A single column of spectrogram data ready for rendering.
@@ -187,6 +807,205 @@ pub struct VisualizationData {
     /// Speech detection metrics
     #[serde(skip_serializing_if = "Option::is_none")]
     pub speech_metrics: Option<SpeechMetrics>,
+    /// Waveform of each source, when microphone and system audio are both
+    /// captured; `waveform` is their mix
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub source_waveforms: Vec<SourceWaveform>,
+}
+
+/// Waveform of one audio source, drawn as its own trace.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SourceWaveform {
+    /// Source the samples came from (`input` or `system`)
+    pub source: AudioSourceType,
+    /// Amplitude values, aligned with `VisualizationData::waveform`
+    pub waveform: Vec<f32>,
+}
+
+/// FFT sizes the spectrogram can use.
+pub const SPECTROGRAM_FFT_SIZES: &[u32] = &[512, 1024, 2048];
+
+/// Fastest visualization update rate, in events per second.
+pub const MAX_VISUALIZATION_RATE_HZ: u32 = 120;
+
+/// Window applied to each block of samples before the spectrogram FFT.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SpectrogramWindow {
+    #[default]
+    Hann,
+    Hamming,
+    /// Lowest sidelobes, at the cost of wider peaks
+    Blackman,
+    /// No window: sharpest peaks, most leakage
+    Rectangular,
+}
+
+/// Spectrogram resolution and visualization update rate.
+///
+/// Larger FFTs resolve frequencies more finely but cover more time per
+/// column. With an emit rate set, each column is computed from the latest
+/// `fft_size` samples, so columns overlap and keep scrolling at that rate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SpectrogramSettings {
+    /// Samples per FFT, one of `SPECTROGRAM_FFT_SIZES`
+    pub fft_size: u32,
+    /// Window function applied before the FFT
+    pub window: SpectrogramWindow,
+    /// Visualization events per second; 0 sends one per captured buffer,
+    /// with a column each time `fft_size` new samples have arrived
+    pub emit_rate_hz: u32,
+}
+
+impl Default for SpectrogramSettings {
+    fn default() -> Self {
+        Self {
+            fft_size: 512,
+            window: SpectrogramWindow::default(),
+            emit_rate_hz: 0,
+        }
+    }
+}
+
+impl SpectrogramSettings {
+    /// Check that all values are in range.
+    pub fn validate(&self) -> Result<(), String> {
+        if !SPECTROGRAM_FFT_SIZES.contains(&self.fft_size) {
+            return Err(format!(
+                "fft_size must be one of {:?}",
+                SPECTROGRAM_FFT_SIZES
+            ));
+        }
+        if self.emit_rate_hz > MAX_VISUALIZATION_RATE_HZ {
+            return Err(format!(
+                "emit_rate_hz must be at most {}",
+                MAX_VISUALIZATION_RATE_HZ
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Longest delay or disconnect an injected fault can cause, in milliseconds.
+pub const MAX_FAULT_DURATION_MS: u32 = 60_000;
+
+/// Capture fault injected to exercise the service's recovery from misbehaving
+/// devices. Only debug builds of the service accept these.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AudioFault {
+    /// Drop the next `count` buffers captured from a source before mixing
+    DropBuffers { source: AudioSourceType, count: u32 },
+    /// Deliver the next `count` buffers from a source `delay_ms` late
+    DelayBuffers {
+        source: AudioSourceType,
+        count: u32,
+        delay_ms: u32,
+    },
+    /// Make the device capturing a source vanish for `duration_ms`: its audio
+    /// stops and it is no longer listed until the time is up
+    Disconnect {
+        source: AudioSourceType,
+        duration_ms: u32,
+    },
+    /// Remove all injected faults
+    Clear,
+}
+
+impl AudioFault {
+    /// Check that the source is a capture source and all values are in range.
+    pub fn validate(&self) -> Result<(), String> {
+        let (source, ms) = match *self {
+            AudioFault::DropBuffers { source, .. } => (source, 0),
+            AudioFault::DelayBuffers {
+                source, delay_ms, ..
+            } => (source, delay_ms),
+            AudioFault::Disconnect {
+                source,
+                duration_ms,
+            } => (source, duration_ms),
+            AudioFault::Clear => return Ok(()),
+        };
+        if !matches!(source, AudioSourceType::Input | AudioSourceType::System) {
+            return Err("Faults can only be injected into input or system sources".to_string());
+        }
+        if ms > MAX_FAULT_DURATION_MS {
+            return Err(format!(
+                "Fault durations must be at most {}ms",
+                MAX_FAULT_DURATION_MS
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Level of one audio source over a short window.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AudioLevel {
+    /// RMS level in dBFS
+    pub rms_db: f32,
+    /// Peak level in dBFS
+    pub peak_db: f32,
+}
+
+/// Per-source levels measured before mixing.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SourceLevels {
+    /// Microphone/input level, if the source delivered audio
+    pub input: Option<AudioLevel>,
+    /// System audio level, if the source delivered audio
+    pub system: Option<AudioLevel>,
+}
+
+/// Echo cancellation quality averaged over about a second.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AecMetrics {
+    /// Echo return loss: how much quieter the echo is than the system audio, in dB
+    pub erl_db: f32,
+    /// Echo return loss enhancement: how much of the echo the AEC removes, in dB.
+    /// A value that climbs and holds above about 10 dB means it has converged.
+    pub erle_db: f32,
+    /// Echo delay the AEC is tracking, in milliseconds
+    pub delay_ms: i32,
+}
+
+/// Clock synchronization of the microphone and system audio streams.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SyncMetrics {
+    /// How much faster the system audio clock runs than the microphone's, in
+    /// parts per million, once about ten seconds of audio have been measured
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub drift_ppm: Option<f32>,
+    /// Remaining offset of the system audio from the microphone, in milliseconds
+    pub offset_ms: f32,
+    /// Frames of system audio dropped to keep pace with the microphone
+    pub dropped_frames: u64,
+    /// Frames of system audio repeated or padded to wait for the microphone
+    pub inserted_frames: u64,
+}
+
+/// Capture buffer latency of a source, as negotiated with the device.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CaptureLatency {
+    /// Whether this is the input or system audio source
+    pub source_type: AudioSourceType,
+    /// Buffer duration requested by `audio.buffer_ms`, if set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub requested_ms: Option<u32>,
+    /// Buffer duration the backend actually uses, in milliseconds
+    pub buffer_ms: f32,
+}
+
+/// Native format of a capture source, before conversion for the mixer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceFormat {
+    /// Whether this is the input or system audio source
+    pub source_type: AudioSourceType,
+    /// Sample rate in Hz
+    pub sample_rate: u32,
+    /// Number of channels
+    pub channels: u16,
 }
 
 /// Speech detection metrics for visualization.
@@ -212,6 +1031,63 @@ pub struct SpeechMetrics {
     pub is_word_break: bool,
 }
 
+/// Timing of a single word within a transcribed segment.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WordTiming {
+    /// Word text (without surrounding whitespace)
+    pub word: String,
+    /// Start offset from the beginning of the segment audio in milliseconds
+    pub start_ms: u64,
+    /// End offset from the beginning of the segment audio in milliseconds
+    pub end_ms: u64,
+    /// Mean token probability (0.0-1.0)
+    pub probability: f32,
+}
+
+/// Audio source a piece of transcribed speech came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SourceLabel {
+    /// Primary source (microphone)
+    Mic,
+    /// Secondary source (system audio)
+    System,
+}
+
+/// Text transcribed from a single audio source.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SourceSegment {
+    /// Source the speech was captured from
+    pub source: SourceLabel,
+    /// Transcribed text
+    pub text: String,
+    /// Start offset from the beginning of the segment audio in milliseconds
+    pub start_ms: u64,
+    /// End offset from the beginning of the segment audio in milliseconds
+    pub end_ms: u64,
+}
+
+/// Decoder confidence for one whisper segment of a transcription.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SegmentConfidence {
+    /// Text of the whisper segment
+    pub text: String,
+    /// Source the speech came from (only when sources are transcribed separately)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<SourceLabel>,
+    /// Start offset from the beginning of the segment audio in milliseconds
+    pub start_ms: u64,
+    /// End offset from the beginning of the segment audio in milliseconds
+    pub end_ms: u64,
+    /// Mean log-probability of the segment's text tokens
+    pub avg_logprob: f32,
+    /// Probability that the segment contains no speech (0.0-1.0)
+    pub no_speech_prob: f32,
+    /// Whether the segment fell below the service's confidence thresholds
+    #[serde(default)]
+    pub low_confidence: bool,
+}
+
 /// Transcription result for a speech segment.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TranscriptionResult {
@@ -220,4 +1096,680 @@ pub struct TranscriptionResult {
     /// Path to the saved audio file (if saved)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub audio_path: Option<String>,
+    /// Language code of the transcribed speech (detected when auto-detect is enabled)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// Word-level timestamps, relative to the start of the segment
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub words: Vec<WordTiming>,
+    /// Per-source text in time order (only when sources are transcribed separately)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub segments: Vec<SourceSegment>,
+    /// Per-segment decoder confidence in time order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub confidence: Vec<SegmentConfidence>,
+    /// English translation when translation is enabled and the speech isn't English
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub translation: Option<String>,
+    /// Whether post-processing (number normalization, profanity masking,
+    /// custom replacements) changed the transcribed text
+    #[serde(default)]
+    pub modified: bool,
+    /// Speaker label ("speaker_1", "speaker_2", ...) when diarization is enabled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub speaker: Option<String>,
+    /// Application that had focus when the transcription completed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub app: Option<FocusedApp>,
+    /// History session the transcription was saved as
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// Duration of the transcribed audio in milliseconds
+    #[serde(default)]
+    pub duration_ms: u64,
+}
+
+/// The application that had keyboard focus.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FocusedApp {
+    /// Identifier: bundle ID on macOS, executable name on Windows and Linux
+    pub id: String,
+    /// Display name, where the platform reports one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Class of the focused window (Windows and X11)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub window_class: Option<String>,
+}
+
+impl FocusedApp {
+    /// Whether `pattern` names this application: its ID, its display name or
+    /// its window class, ignoring case. Executable names also match without
+    /// their ".exe" extension.
+    pub fn matches(&self, pattern: &str) -> bool {
+        let pattern = pattern.trim();
+        let id = self.id.strip_suffix(".exe").unwrap_or(&self.id);
+        [
+            Some(self.id.as_str()),
+            Some(id),
+            self.name.as_deref(),
+            self.window_class.as_deref(),
+        ]
+        .into_iter()
+        .flatten()
+        .any(|candidate| candidate.eq_ignore_ascii_case(pattern))
+    }
+}
+
+/// Whether the operating system lets the service use a protected resource.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PermissionState {
+    /// Access is allowed
+    Granted,
+    /// The user refused access; it can only be allowed in the system settings
+    Denied,
+    /// The user hasn't been asked yet
+    NotDetermined,
+    /// Access is blocked by a policy the user can't change
+    Restricted,
+    /// This platform doesn't restrict the resource
+    NotRequired,
+}
+
+/// A protected resource the service uses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Permission {
+    /// Capturing from input devices
+    Microphone,
+    /// Capturing system and application audio (Screen Recording on macOS)
+    ScreenRecording,
+    /// Typing transcriptions and detecting the push-to-talk key
+    Accessibility,
+}
+
+/// Access the service has to protected resources.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Permissions {
+    pub microphone: PermissionState,
+    pub screen_recording: PermissionState,
+    pub accessibility: PermissionState,
+}
+
+/// A section of a long transcription, split at a long silence or a regular interval.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Chapter {
+    /// Chapter heading
+    pub title: String,
+    /// Start offset from the beginning of the audio in milliseconds
+    pub start_ms: u64,
+    /// End offset from the beginning of the audio in milliseconds
+    pub end_ms: u64,
+}
+
+/// A completed transcription saved in the service history.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TranscriptSession {
+    /// Unique session ID
+    pub id: String,
+    /// When the transcription completed (RFC 3339)
+    pub created_at: String,
+    /// Transcribed text
+    pub text: String,
+    /// Language code of the transcribed speech
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// Word-level timestamps, relative to the start of the segment
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub words: Vec<WordTiming>,
+    /// Per-source text in time order (only when sources are transcribed separately)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub segments: Vec<SourceSegment>,
+    /// English translation of the text, if it was translated
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub translation: Option<String>,
+    /// Speaker label when diarization was enabled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub speaker: Option<String>,
+    /// Duration of the transcribed audio in milliseconds
+    pub duration_ms: u64,
+    /// Primary audio source ID used for capture
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source1_id: Option<String>,
+    /// Secondary audio source ID used for capture
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source2_id: Option<String>,
+    /// Path to the saved audio file (if saved)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audio_path: Option<String>,
+    /// Application that had focus when the transcription completed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub app: Option<FocusedApp>,
+    /// Summary returned by a session hook
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    /// Chapters of a long transcription, in time order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub chapters: Vec<Chapter>,
+    /// Title, tags, participants and notes added by the user
+    #[serde(default, skip_serializing_if = "SessionMetadata::is_empty")]
+    pub metadata: SessionMetadata,
+    /// Model that produced the text, if known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Transcripts replaced by re-transcription, oldest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub versions: Vec<TranscriptVersion>,
+    /// Talk time and pace, computed when the transcript was saved
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stats: Option<SessionStats>,
+    /// Capture session the transcription was made in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub capture_id: Option<String>,
+}
+
+/// Everything transcribed between starting and stopping capture. Its
+/// transcriptions are saved one utterance at a time; this ties them together
+/// once capture stops.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaptureSession {
+    /// Unique capture session ID
+    pub id: String,
+    /// When capture started (RFC 3339)
+    pub started_at: String,
+    /// When capture stopped (RFC 3339)
+    pub ended_at: String,
+    /// Time from starting to stopping capture in milliseconds
+    pub duration_ms: u64,
+    /// Saved transcriptions made during the session, in order; some may have
+    /// been deleted since
+    pub session_ids: Vec<String>,
+    /// Summary returned by a session hook
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    /// Where the conversation moved on, found in the pauses within and
+    /// between transcriptions
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub chapters: Vec<Chapter>,
+    /// Speaking statistics over all of the session's transcriptions
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stats: Option<SessionStats>,
+}
+
+/// Speaking statistics of a saved transcription.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SessionStats {
+    /// Duration of the transcribed audio in milliseconds
+    pub duration_ms: u64,
+    /// Time spent speaking in milliseconds; pauses between words shorter than
+    /// a breath count as speech
+    pub speech_ms: u64,
+    /// Share of the audio without speech (0.0-1.0)
+    pub silence_ratio: f32,
+    /// Number of words spoken
+    pub words: usize,
+    /// Words per minute of speech
+    pub words_per_minute: f32,
+    /// Number of stretches of speech between pauses
+    pub segments: usize,
+    /// Talk time of each speaker or source, most talkative first; empty when
+    /// the speakers aren't known
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub speakers: Vec<SpeakerStats>,
+}
+
+/// Talk time of one speaker in a saved transcription.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpeakerStats {
+    /// Speaker label, or the source (`mic` or `system`) when sources were
+    /// transcribed separately
+    pub speaker: String,
+    /// Time spent speaking in milliseconds
+    pub speech_ms: u64,
+    /// Share of the session's speech (0.0-1.0)
+    pub talk_ratio: f32,
+    /// Number of words spoken
+    pub words: usize,
+}
+
+/// Transcribed text from the last stretch of the active capture session.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RecentTranscript {
+    /// The segments' text joined in order
+    pub text: String,
+    /// Transcriptions completed within the requested stretch, oldest first
+    pub segments: Vec<RecentSegment>,
+}
+
+/// A transcription completed during the active capture session.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecentSegment {
+    /// History session the transcription was saved as
+    pub id: String,
+    /// When the transcription completed (RFC 3339)
+    pub completed_at: String,
+    pub text: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub speaker: Option<String>,
+}
+
+/// An earlier transcript of a saved transcription, kept when it was
+/// transcribed again.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranscriptVersion {
+    /// When this transcript was replaced (RFC 3339)
+    pub replaced_at: String,
+    /// Model that produced it, if known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Transcribed text
+    pub text: String,
+    /// Language code of the transcribed speech
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// Word-level timestamps
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub words: Vec<WordTiming>,
+    /// Per-source text in time order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub segments: Vec<SourceSegment>,
+    /// English translation of the text
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub translation: Option<String>,
+}
+
+/// Longest title, tag, participant or notes text of a saved transcription.
+pub const MAX_METADATA_LENGTH: usize = 4096;
+
+/// User-supplied description of a saved transcription.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionMetadata {
+    /// Title, shown instead of the beginning of the text
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// Labels such as "meeting" or "project-x", compared case-insensitively
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// People in the conversation
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub participants: Vec<String>,
+    /// Free-form notes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+}
+
+impl SessionMetadata {
+    /// Whether nothing has been set.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Check that tags and participants are not blank and nothing is too long.
+    pub fn validate(&self) -> Result<(), String> {
+        let texts = self
+            .title
+            .iter()
+            .chain(&self.tags)
+            .chain(&self.participants);
+        if texts
+            .chain(&self.notes)
+            .any(|text| text.len() > MAX_METADATA_LENGTH)
+        {
+            return Err(format!(
+                "Session metadata is limited to {} bytes per field",
+                MAX_METADATA_LENGTH
+            ));
+        }
+        if self.tags.iter().any(|tag| tag.trim().is_empty()) {
+            return Err("Tags cannot be empty".to_string());
+        }
+        if self.participants.iter().any(|p| p.trim().is_empty()) {
+            return Err("Participant names cannot be empty".to_string());
+        }
+        Ok(())
+    }
+
+    /// Whether the session has `tag`, ignoring case.
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))
+    }
+}
+
+/// Days to search, as inclusive `YYYY-MM-DD` dates in local time; either end
+/// may be left open.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DateRange {
+    /// First day to include
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+    /// Last day to include
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to: Option<String>,
+}
+
+impl DateRange {
+    /// Check that both ends are `YYYY-MM-DD` dates, in order.
+    pub fn validate(&self) -> Result<(), String> {
+        for date in self.from.iter().chain(&self.to) {
+            let bytes = date.as_bytes();
+            let valid = bytes.len() == 10
+                && bytes.iter().enumerate().all(|(i, b)| match i {
+                    4 | 7 => *b == b'-',
+                    _ => b.is_ascii_digit(),
+                });
+            if !valid {
+                return Err(format!("Invalid date {:?} (expected YYYY-MM-DD)", date));
+            }
+        }
+        if let (Some(from), Some(to)) = (&self.from, &self.to) {
+            if from > to {
+                return Err(format!(
+                    "Date range starts after it ends: {} to {}",
+                    from, to
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether an RFC 3339 timestamp falls on one of the days.
+    pub fn contains(&self, timestamp: &str) -> bool {
+        let day = timestamp.get(..10).unwrap_or(timestamp);
+        self.from.as_deref().is_none_or(|from| day >= from)
+            && self.to.as_deref().is_none_or(|to| day <= to)
+    }
+}
+
+/// A matched word in a search snippet, as a byte range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Highlight {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// A saved transcription matching a full-text search.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SearchHit {
+    /// Session ID
+    pub id: String,
+    /// When the transcription completed (RFC 3339)
+    pub created_at: String,
+    /// Title given by the user
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// Text around the first match
+    pub snippet: String,
+    /// Matched words within `snippet`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub highlights: Vec<Highlight>,
+    /// Where the first match is spoken in the recording, when word timings
+    /// are available
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub offset_ms: Option<u64>,
+    /// Relevance; hits are ordered by it, best first
+    pub score: u32,
+}
+
+/// Which saved transcriptions to list; every criterion given must match.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionFilter {
+    /// Only sessions with this tag (case-insensitive)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    /// Only sessions with a participant whose name contains this (case-insensitive)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub participant: Option<String>,
+    /// Only sessions whose title, notes, tags or participants contain this
+    /// (case-insensitive)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub query: Option<String>,
+}
+
+impl SessionFilter {
+    /// Whether no criteria are set, so every session matches.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Whether `session` meets all criteria.
+    pub fn matches(&self, session: &TranscriptSession) -> bool {
+        let metadata = &session.metadata;
+        let contains = |text: &String, needle: &str| text.to_lowercase().contains(needle);
+
+        if let Some(tag) = &self.tag {
+            if !metadata.has_tag(tag) {
+                return false;
+            }
+        }
+        if let Some(participant) = &self.participant {
+            let participant = participant.to_lowercase();
+            if !metadata
+                .participants
+                .iter()
+                .any(|p| contains(p, &participant))
+            {
+                return false;
+            }
+        }
+        if let Some(query) = &self.query {
+            let query = query.to_lowercase();
+            let mut texts = metadata
+                .title
+                .iter()
+                .chain(&metadata.notes)
+                .chain(&metadata.tags)
+                .chain(&metadata.participants);
+            if !texts.any(|text| contains(text, &query)) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Where a background job is in its work.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    /// Waiting for an earlier job to finish
+    Queued,
+    /// Being worked on
+    Running,
+    /// Finished; some sessions may have failed, see `errors`
+    Completed,
+    /// Could not run at all, e.g. because the model is missing
+    Failed,
+    /// Cancelled before it finished
+    Cancelled,
+}
+
+/// A job re-transcribing saved transcriptions from their recordings.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RetranscribeJob {
+    /// Job ID
+    pub id: String,
+    /// Sessions to transcribe again, in order
+    pub session_ids: Vec<String>,
+    /// Number of sessions done, including failed ones
+    pub completed: usize,
+    /// Session being transcribed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub current: Option<String>,
+    /// Where the job is
+    pub state: JobState,
+    /// Model the sessions are transcribed with
+    pub model: String,
+    /// Sessions that could not be transcribed, with the reason
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<JobError>,
+    /// Why the job failed, when it is [`JobState::Failed`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl RetranscribeJob {
+    /// Whether the job has stopped, successfully or not.
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self.state,
+            JobState::Completed | JobState::Failed | JobState::Cancelled
+        )
+    }
+}
+
+/// A session a job could not process.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JobError {
+    /// Session ID
+    pub session_id: String,
+    /// What went wrong
+    pub message: String,
+}
+
+/// Progress of a recording being played back.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlaybackStatus {
+    /// Saved transcription whose recording is playing, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    /// Path of the file being played
+    pub path: String,
+    /// Current position in milliseconds
+    pub position_ms: u64,
+    /// Length of the recording in milliseconds
+    pub duration_ms: u64,
+    /// Index into the session's words of the word being spoken, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub word_index: Option<usize>,
+    /// Whether playback is paused
+    #[serde(default)]
+    pub paused: bool,
+}
+
+/// Disk space used by recordings and the transcription history.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StorageStatus {
+    /// Directory recordings are saved to
+    pub recordings_dir: String,
+    /// Audio files in the recordings directory
+    pub recording_count: usize,
+    /// Total size of those files in bytes
+    pub recordings_bytes: u64,
+    /// Recordings of saved transcriptions, which retention limits apply to
+    pub managed_count: usize,
+    /// Total size of those recordings in bytes
+    pub managed_bytes: u64,
+    /// Saved transcriptions
+    pub history_sessions: usize,
+    /// Size of the history file in bytes
+    pub history_bytes: u64,
+    /// Size limit for recordings in megabytes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_size_mb: Option<u64>,
+    /// Age limit for recordings in days
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_age_days: Option<u32>,
+    /// Whether transcripts are kept when their recordings are removed
+    pub keep_transcripts: bool,
+}
+
+/// What enforcing the retention limits removed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PruneResult {
+    /// Recordings deleted
+    pub files_removed: usize,
+    /// Space freed in bytes
+    pub bytes_freed: u64,
+    /// Transcriptions removed from the history along with their recordings
+    pub sessions_removed: usize,
+}
+
+/// Summary of a saved transcription, used for history listings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionSummary {
+    /// Unique session ID
+    pub id: String,
+    /// When the transcription completed (RFC 3339)
+    pub created_at: String,
+    /// Duration of the transcribed audio in milliseconds
+    pub duration_ms: u64,
+    /// Beginning of the transcribed text
+    pub preview: String,
+    /// Title given by the user
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// Tags given by the user
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+/// A spoken phrase that triggers a `CommandDetected` event when a transcribed
+/// segment consists of it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VoiceCommand {
+    /// Phrase to listen for (matched ignoring case and punctuation)
+    pub phrase: String,
+    /// Whether the matching text is withheld instead of delivered as a transcription
+    #[serde(default)]
+    pub suppress_text: bool,
+}
+
+/// A named bundle of capture and output settings switched in one step, such as
+/// "meetings" or "dictation". Settings a profile leaves unset keep their
+/// current values when it is used.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Profile {
+    /// Name the profile is used by
+    pub name: String,
+    /// Primary audio source ID
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source1_id: Option<String>,
+    /// Secondary audio source ID
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source2_id: Option<String>,
+    /// How the two sources are combined
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recording_mode: Option<RecordingMode>,
+    /// Whether echo cancellation is enabled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aec_enabled: Option<bool>,
+    /// Catalog model to transcribe with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_name: Option<String>,
+    /// Where transcriptions are delivered
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_mode: Option<OutputMode>,
+    /// Applications that switch to this profile when they come to the front
+    /// (see [`FocusedApp::matches`])
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub apps: Vec<String>,
+}
+
+/// Where a session hook sends the transcript of a capture session.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum HookTarget {
+    /// Shell command that receives the text on stdin; its stdout is the result
+    Command { command: String },
+    /// HTTP endpoint that receives the session as JSON in a POST; the response
+    /// body (or its `summary` field, when it is JSON) is the result
+    Http { url: String },
+}
+
+/// An action run on the transcript of every capture session once capture
+/// stops, for example piping it to a local LLM for summarization.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionHook {
+    /// Name the hook is managed by
+    pub name: String,
+    /// What the hook runs
+    pub target: HookTarget,
+    /// Store the hook's output as the capture session's summary
+    #[serde(default = "default_summarize")]
+    pub summarize: bool,
+}
+
+fn default_summarize() -> bool {
+    true
 }
@@ -3,7 +3,12 @@
 //! This module contains audio-related types and utilities shared across
 //! the service, including format conversion and recording.
 
-use std::path::PathBuf;
+use chrono::{DateTime, Utc};
+use flowstt_common::RecordingFormat;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::config::RecordingProcessingConfig;
 
 /// Raw recorded audio data before processing
 pub struct RawRecordedAudio {
@@ -30,24 +35,53 @@ pub fn process_recorded_audio(raw: RawRecordedAudio) -> Result<Vec<f32>, String>
         raw.samples
     };
 
-    // Resample to 16kHz for Whisper
+    // Resample to 16kHz for Whisper, unless speech capture already delivered it
+    if raw.sample_rate == 16000 {
+        return Ok(mono_samples);
+    }
     resample_to_16khz(&mono_samples, raw.sample_rate)
 }
 
+/// Process split-channel audio (primary source left, secondary right) into
+/// separate mono 16kHz streams, one per source
+pub fn process_split_sources(raw: RawRecordedAudio) -> Result<(Vec<f32>, Vec<f32>), String> {
+    if raw.channels != 2 {
+        return Err(format!(
+            "Split-channel audio must be stereo, got {} channels",
+            raw.channels
+        ));
+    }
+
+    let (primary, secondary): (Vec<f32>, Vec<f32>) = raw
+        .samples
+        .chunks_exact(2)
+        .map(|frame| (frame[0], frame[1]))
+        .unzip();
+
+    Ok((
+        resample_to_16khz(&primary, raw.sample_rate)?,
+        resample_to_16khz(&secondary, raw.sample_rate)?,
+    ))
+}
+
 /// Resample audio to 16kHz using linear interpolation
 /// This is a simple resampler suitable for speech-to-text
 fn resample_to_16khz(samples: &[f32], source_rate: u32) -> Result<Vec<f32>, String> {
-    const TARGET_RATE: u32 = 16000;
+    Ok(resample(samples, source_rate, 16000))
+}
 
-    if source_rate == TARGET_RATE {
-        return Ok(samples.to_vec());
+/// Resample mono audio from `source_rate` to `target_rate` using linear
+/// interpolation
+pub fn resample(samples: &[f32], source_rate: u32, target_rate: u32) -> Vec<f32> {
+    if source_rate == target_rate {
+        return samples.to_vec();
     }
 
     if samples.is_empty() {
-        return Ok(Vec::new());
+        return Vec::new();
     }
 
-    let ratio = source_rate as f64 / TARGET_RATE as f64;
+    let ratio = source_rate as f64 / target_rate as f64;
     let output_len = (samples.len() as f64 / ratio).ceil() as usize;
     let mut output = Vec::with_capacity(output_len);
 
@@ -68,44 +102,415 @@ fn resample_to_16khz(samples: &[f32], source_rate: u32) -> Result<Vec<f32>, Stri
         output.push(sample);
     }
 
-    Ok(output)
+    output
 }
 
-/// Save raw audio samples to a WAV file
-pub fn save_to_wav(
+/// Read a WAV file into raw audio samples
+pub fn read_wav(path: &Path) -> Result<RawRecordedAudio, String> {
+    use hound::{SampleFormat, WavReader};
+
+    let reader = WavReader::open(path).map_err(|e| format!("Failed to open WAV file: {}", e))?;
+    let spec = reader.spec();
+
+    let samples = match spec.sample_format {
+        SampleFormat::Float => reader.into_samples::<f32>().collect::<Result<Vec<_>, _>>(),
+        SampleFormat::Int => {
+            let scale = (1i64 << (spec.bits_per_sample - 1)) as f32;
+            reader
+                .into_samples::<i32>()
+                .map(|s| s.map(|s| s as f32 / scale))
+                .collect::<Result<Vec<_>, _>>()
+        }
+    }
+    .map_err(|e| format!("Failed to read WAV samples: {}", e))?;
+
+    Ok(RawRecordedAudio {
+        samples,
+        sample_rate: spec.sample_rate,
+        channels: spec.channels,
+    })
+}
+
+/// Audio kept around trimmed speech so word onsets aren't clipped
+const TRIM_PADDING_MS: u32 = 100;
+
+/// Window the level is measured over when trimming silence
+const TRIM_WINDOW_MS: u32 = 10;
+
+/// Highest sample peak normalization raises audio to (-1 dBFS)
+const NORMALIZE_PEAK_CEILING: f32 = 0.891;
+
+/// Loudness measurement blocks (EBU R128 momentary loudness)
+const LOUDNESS_BLOCK_MS: u32 = 400;
+
+/// Step between loudness blocks, for the 75% overlap BS.1770 specifies
+const LOUDNESS_STEP_MS: u32 = 100;
+
+/// Blocks quieter than this don't count towards the loudness
+const LOUDNESS_ABSOLUTE_GATE: f64 = -70.0;
+
+/// Blocks this far below the ungated loudness don't count either
+const LOUDNESS_RELATIVE_GATE: f64 = -10.0;
+
+static PROCESSING: Mutex<Option<RecordingProcessingConfig>> = Mutex::new(None);
+
+/// Set the processing applied to speech segments before they are saved.
+pub fn set_recording_processing(config: RecordingProcessingConfig) {
+    *PROCESSING.lock().unwrap() = Some(config);
+}
+
+/// Trim and normalize a speech segment as configured.
+pub fn preprocess_recording(mut samples: Vec<f32>, sample_rate: u32, channels: u16) -> Vec<f32> {
+    let config = PROCESSING.lock().unwrap().unwrap_or_default();
+    if config.trim_silence {
+        let range = trim_silence(&samples, sample_rate, channels, config.silence_threshold_db);
+        if range.len() < samples.len() {
+            samples.truncate(range.end);
+            samples.drain(..range.start);
+        }
+    }
+    if config.normalize {
+        normalize_loudness(&mut samples, sample_rate, channels, config.target_lufs);
+    }
+    samples
+}
+
+/// Range of interleaved `samples` left after cutting leading and trailing
+/// audio below `threshold_db`, with a little padding kept around the rest.
+/// Audio that is silent throughout is left whole.
+pub fn trim_silence(
     samples: &[f32],
     sample_rate: u32,
     channels: u16,
-    output_path: &PathBuf,
-) -> Result<(), String> {
-    use hound::{SampleFormat, WavSpec, WavWriter};
-
-    let spec = WavSpec {
-        channels,
-        sample_rate,
-        bits_per_sample: 32,
-        sample_format: SampleFormat::Float,
+    threshold_db: f32,
+) -> std::ops::Range<usize> {
+    let channels = channels.max(1) as usize;
+    let window = (sample_rate * TRIM_WINDOW_MS / 1000).max(1) as usize * channels;
+    let threshold = 10f32.powf(threshold_db / 20.0);
+    let loud = |chunk: &[f32]| {
+        (chunk.iter().map(|s| s * s).sum::<f32>() / chunk.len() as f32).sqrt() >= threshold
+    };
+
+    let windows: Vec<&[f32]> = samples.chunks(window).collect();
+    let (Some(first), Some(last)) = (
+        windows.iter().position(|w| loud(w)),
+        windows.iter().rposition(|w| loud(w)),
+    ) else {
+        return 0..samples.len();
+    };
+
+    let padding = (sample_rate * TRIM_PADDING_MS / 1000) as usize * channels;
+    let start = (first * window).saturating_sub(padding);
+    let end = ((last + 1) * window + padding).min(samples.len());
+    start..end
+}
+
+/// Scale `samples` to the integrated loudness `target_lufs`, lowering the
+/// gain where needed to keep peaks below -1 dBFS. Segments too short to
+/// measure are left alone.
+fn normalize_loudness(samples: &mut [f32], sample_rate: u32, channels: u16, target_lufs: f32) {
+    let Some(loudness) = integrated_loudness(samples, sample_rate, channels) else {
+        return;
     };
+    let peak = samples.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+    if peak <= 0.0 {
+        return;
+    }
+    let gain = 10f32
+        .powf((target_lufs - loudness as f32) / 20.0)
+        .min(NORMALIZE_PEAK_CEILING / peak);
+    for sample in samples.iter_mut() {
+        *sample *= gain;
+    }
+}
+
+/// Integrated loudness of interleaved `samples` in LUFS, as specified by
+/// ITU-R BS.1770 and EBU R128: K-weighted mean power over gated 400ms
+/// blocks. None when there is less than one block or every block is gated.
+pub fn integrated_loudness(samples: &[f32], sample_rate: u32, channels: u16) -> Option<f64> {
+    let channels = channels.max(1) as usize;
+    let frames = samples.len() / channels;
+    let block = (sample_rate * LOUDNESS_BLOCK_MS / 1000) as usize;
+    let step = (sample_rate * LOUDNESS_STEP_MS / 1000) as usize;
+    if block == 0 || frames < block {
+        return None;
+    }
+
+    // K-weighted squared samples, summed over channels (all weighted 1.0)
+    let mut power = vec![0.0f64; frames];
+    for channel in 0..channels {
+        let mut shelf = Biquad::k_weighting_shelf(sample_rate);
+        let mut high_pass = Biquad::k_weighting_high_pass(sample_rate);
+        for (frame, power) in power.iter_mut().enumerate() {
+            let x = samples[frame * channels + channel] as f64;
+            let y = high_pass.process(shelf.process(x));
+            *power += y * y;
+        }
+    }
+
+    let blocks: Vec<f64> = (0..=(frames - block) / step)
+        .map(|i| power[i * step..i * step + block].iter().sum::<f64>() / block as f64)
+        .collect();
+    let loudness = |power: f64| -0.691 + 10.0 * power.log10();
+    let gated_mean = |threshold: f64| {
+        let gated: Vec<f64> = blocks
+            .iter()
+            .copied()
+            .filter(|&p| p > 0.0 && loudness(p) > threshold)
+            .collect();
+        (!gated.is_empty()).then(|| gated.iter().sum::<f64>() / gated.len() as f64)
+    };
+
+    let ungated = gated_mean(LOUDNESS_ABSOLUTE_GATE)?;
+    let relative = loudness(ungated) + LOUDNESS_RELATIVE_GATE;
+    gated_mean(relative.max(LOUDNESS_ABSOLUTE_GATE)).map(loudness)
+}
+
+/// Second-order IIR filter section (direct form I).
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+    x: [f64; 2],
+    y: [f64; 2],
+}
+
+impl Biquad {
+    /// The high-shelf stage of the K-weighting filter, modelling the
+    /// acoustic effect of the head.
+    fn k_weighting_shelf(sample_rate: u32) -> Self {
+        let gain_db = 3.999843853973347;
+        let q = 0.7071752369554196;
+        let k = (std::f64::consts::PI * 1681.974450955533 / sample_rate as f64).tan();
+        let vh = 10f64.powf(gain_db / 20.0);
+        let vb = vh.powf(0.4996667741545416);
+        let a0 = 1.0 + k / q + k * k;
+        Self::new(
+            [
+                (vh + vb * k / q + k * k) / a0,
+                2.0 * (k * k - vh) / a0,
+                (vh - vb * k / q + k * k) / a0,
+            ],
+            [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        )
+    }
+
+    /// The high-pass stage of the K-weighting filter (revised low-frequency
+    /// B-curve).
+    fn k_weighting_high_pass(sample_rate: u32) -> Self {
+        let q = 0.5003270373238773;
+        let k = (std::f64::consts::PI * 38.13547087602444 / sample_rate as f64).tan();
+        let a0 = 1.0 + k / q + k * k;
+        Self::new(
+            [1.0, -2.0, 1.0],
+            [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        )
+    }
 
-    let mut writer = WavWriter::create(output_path, spec)
-        .map_err(|e| format!("Failed to create WAV file: {}", e))?;
+    fn new(b: [f64; 3], a: [f64; 2]) -> Self {
+        Self {
+            b,
+            a,
+            x: [0.0; 2],
+            y: [0.0; 2],
+        }
+    }
 
-    for &sample in samples {
-        writer
-            .write_sample(sample)
-            .map_err(|e| format!("Failed to write sample: {}", e))?;
+    fn process(&mut self, x: f64) -> f64 {
+        let y = self.b[0] * x + self.b[1] * self.x[0] + self.b[2] * self.x[1]
+            - self.a[0] * self.y[0]
+            - self.a[1] * self.y[1];
+        self.x = [x, self.x[0]];
+        self.y = [y, self.y[0]];
+        y
     }
+}
+
+/// Default filename template for recordings (without extension)
+pub const DEFAULT_FILENAME_TEMPLATE: &str = "flowstt-{date}-{time}";
+
+/// Where recordings are saved and how they are named
+struct RecordingNames {
+    /// Configured directory, or `None` for the default
+    dir: Option<PathBuf>,
+    /// Filename template, see `render_filename_template`
+    template: String,
+    /// File format recordings are encoded in
+    format: RecordingFormat,
+    /// ID of the current capture session
+    session: String,
+    /// Names of the sources being captured
+    source: String,
+}
 
-    writer
-        .finalize()
-        .map_err(|e| format!("Failed to finalize WAV file: {}", e))?;
+static RECORDING_NAMES: Mutex<Option<RecordingNames>> = Mutex::new(None);
 
-    Ok(())
+fn with_recording_names<T>(f: impl FnOnce(&mut RecordingNames) -> T) -> T {
+    let mut names = RECORDING_NAMES.lock().unwrap();
+    f(names.get_or_insert_with(|| RecordingNames {
+        dir: None,
+        template: DEFAULT_FILENAME_TEMPLATE.to_string(),
+        format: RecordingFormat::default(),
+        session: Utc::now().format("%Y%m%d-%H%M%S").to_string(),
+        source: String::new(),
+    }))
 }
 
-/// Generate a timestamped filename for recording
+/// Set the recordings directory (`None` for the default) and filename template.
+pub fn set_recordings_location(dir: Option<PathBuf>, template: String) {
+    with_recording_names(|names| {
+        names.dir = dir;
+        names.template = template;
+    });
+}
+
+/// Set the file format new recordings are encoded in.
+pub fn set_recording_format(format: RecordingFormat) {
+    with_recording_names(|names| names.format = format);
+}
+
+/// File format new recordings are encoded in
+pub fn recording_format() -> RecordingFormat {
+    with_recording_names(|names| names.format)
+}
+
+/// Start a new capture session for the `{session}` and `{source}` placeholders.
+pub fn begin_recording_session(source_names: &[String]) {
+    with_recording_names(|names| {
+        names.session = Utc::now().format("%Y%m%d-%H%M%S").to_string();
+        names.source = source_names.join("+");
+    });
+}
+
+/// Default directory for recordings (~/Documents/Recordings)
+pub fn default_recordings_dir() -> PathBuf {
+    directories::BaseDirs::new()
+        .map(|d| d.home_dir().join("Documents").join("Recordings"))
+        .unwrap_or_else(|| PathBuf::from(".").join("Recordings"))
+}
+
+/// Directory segment recordings are saved to
+pub fn recordings_dir() -> PathBuf {
+    with_recording_names(|names| names.dir.clone()).unwrap_or_else(default_recordings_dir)
+}
+
+/// Generate a filename for a recording from the configured template.
+///
+/// A numeric suffix is added if a file with that name already exists.
 pub fn generate_recording_filename() -> String {
-    use chrono::Utc;
-    let now = Utc::now();
-    format!("flowstt-{}.wav", now.format("%Y%m%d-%H%M%S"))
+    let (dir, stem, extension) = with_recording_names(|names| {
+        (
+            names.dir.clone().unwrap_or_else(default_recordings_dir),
+            render_filename_template(&names.template, Utc::now(), &names.session, &names.source),
+            names.format.extension(),
+        )
+    });
+
+    let mut filename = format!("{}.{}", stem, extension);
+    let mut n = 2;
+    while dir.join(&filename).exists() {
+        filename = format!("{}-{}.{}", stem, n, extension);
+        n += 1;
+    }
+    filename
+}
+
+/// Expand a recording filename template.
+///
+/// Placeholders: `{date}` (YYYYMMDD), `{time}` (HHMMSS), `{session}` (capture
+/// session ID) and `{source}` (names of the captured sources). Characters that
+/// aren't safe in filenames are replaced with `_`.
+pub fn render_filename_template(
+    template: &str,
+    now: DateTime<Utc>,
+    session: &str,
+    source: &str,
+) -> String {
+    let rendered = template
+        .replace("{date}", &now.format("%Y%m%d").to_string())
+        .replace("{time}", &now.format("%H%M%S").to_string())
+        .replace("{session}", session)
+        .replace("{source}", source);
+
+    let sanitized: String = rendered
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, '-' | '_' | '.' | '+' | ' ') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let trimmed = sanitized.trim().trim_matches('.');
+    if trimmed.is_empty() {
+        "recording".to_string()
+    } else {
+        trimmed.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_render_filename_template() {
+        let now = Utc.with_ymd_and_hms(2026, 3, 4, 5, 6, 7).unwrap();
+        assert_eq!(
+            render_filename_template(DEFAULT_FILENAME_TEMPLATE, now, "s1", "Mic"),
+            "flowstt-20260304-050607"
+        );
+        assert_eq!(
+            render_filename_template("{session}/{source}", now, "20260304-050000", "USB Mic: 1"),
+            "20260304-050000_USB Mic_ 1"
+        );
+        assert_eq!(render_filename_template("..", now, "", ""), "recording");
+    }
+
+    fn sine(sample_rate: u32, secs: f32, amplitude: f32) -> Vec<f32> {
+        (0..(sample_rate as f32 * secs) as usize)
+            .map(|i| {
+                amplitude
+                    * (2.0 * std::f32::consts::PI * 1000.0 * i as f32 / sample_rate as f32).sin()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_integrated_loudness() {
+        // A full-scale 1kHz sine measures -3.01 LUFS per channel (BS.1770 calibration)
+        let tone = sine(48000, 2.0, 1.0);
+        let loudness = integrated_loudness(&tone, 48000, 1).unwrap();
+        assert!((loudness + 3.01).abs() < 0.05, "{}", loudness);
+
+        let quiet = sine(16000, 2.0, 0.1);
+        let loudness = integrated_loudness(&quiet, 16000, 1).unwrap();
+        assert!((loudness + 23.01).abs() < 0.1, "{}", loudness);
+
+        assert_eq!(integrated_loudness(&tone[..1000], 48000, 1), None);
+        assert_eq!(integrated_loudness(&[0.0; 48000], 48000, 1), None);
+    }
+
+    #[test]
+    fn test_preprocess_recording() {
+        let mut samples = vec![0.0; 8000];
+        samples.extend(sine(16000, 1.0, 0.1));
+        samples.extend(vec![0.001; 8000]);
+
+        let range = trim_silence(&samples, 16000, 1, -50.0);
+        assert_eq!(range, 6400..25600);
+        assert_eq!(trim_silence(&samples[..8000], 16000, 1, -50.0), 0..8000);
+
+        set_recording_processing(RecordingProcessingConfig {
+            trim_silence: true,
+            normalize: true,
+            target_lufs: -16.0,
+            ..Default::default()
+        });
+        let processed = preprocess_recording(samples, 16000, 1);
+        assert_eq!(processed.len(), 19200);
+        let loudness = integrated_loudness(&processed, 16000, 1).unwrap();
+        assert!((loudness + 16.0).abs() < 0.5, "{}", loudness);
+    }
 }
@@ -4,23 +4,74 @@
 //! and transcription systems. In Automatic mode, uses VAD to trigger transcription.
 //! In PTT mode, the PTT controller manages transcription triggers.
 
+use std::borrow::Cow;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use flowstt_common::ipc::{EventType, Response};
-use flowstt_common::{TranscriptionResult, VisualizationData};
-use tracing::{debug, error, info};
+use flowstt_common::{
+    AudioSourceType, FeedbackCue, FlowSttError, SpeechDetectorConfig, TranscriptionResult,
+    VisualizationData,
+};
+use tracing::{debug, error, info, warn};
 
+use crate::config::RedactionChannel;
 use crate::ipc::broadcast_event;
+use crate::output::Segment;
 use crate::platform;
 use crate::processor::{
-    SpeechDetector, SpeechEventCallback, SpeechEventPayload, SpeechStateChange,
-    VisualizationCallback, VisualizationPayload, VisualizationProcessor, WordBreakEvent,
-    WordBreakPayload,
+    create_speech_detector, NoiseCalibrator, SpeechEventCallback, SpeechEventPayload,
+    SpeechStateChange, VisualizationCallback, VisualizationPayload, VisualizationProcessor,
+    WordBreakEvent, WordBreakPayload,
+};
+use crate::state::get_service_state;
+use crate::transcription::{
+    TranscribeState, Transcript, TranscriptionCallback, TranscriptionQueue, NO_SPEECH_TEXT,
 };
-use crate::transcription::{TranscribeState, TranscriptionCallback, TranscriptionQueue};
+use crate::vad_recording;
+
+/// Longest the loop waits for audio before checking for stop requests and
+/// config changes
+const RECV_TIMEOUT: Duration = Duration::from_millis(50);
+
+/// How long capture may deliver no audio before the sources are checked
+const STREAM_STALL_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Speech detector thresholds for the audio loop
+static VAD_CONFIG: Mutex<Option<SpeechDetectorConfig>> = Mutex::new(None);
+
+/// Set when `VAD_CONFIG` changes so the running loop reconfigures its detector
+static VAD_CONFIG_CHANGED: AtomicBool = AtomicBool::new(false);
+
+/// Set the speech detector thresholds, applying them to a running loop.
+pub fn set_vad_config(config: SpeechDetectorConfig) {
+    *VAD_CONFIG.lock().unwrap() = Some(config);
+    VAD_CONFIG_CHANGED.store(true, Ordering::SeqCst);
+}
+
+/// Make a running loop re-read the VAD config, e.g. once a model is available.
+pub fn refresh_vad() {
+    VAD_CONFIG_CHANGED.store(true, Ordering::SeqCst);
+}
+
+fn vad_config() -> SpeechDetectorConfig {
+    VAD_CONFIG.lock().unwrap().clone().unwrap_or_default()
+}
+
+/// Ambient noise measurement in progress
+static CALIBRATION: Mutex<Option<NoiseCalibrator>> = Mutex::new(None);
+
+/// Start measuring ambient noise from the running loop.
+pub fn start_calibration(sample_rate: u32) {
+    *CALIBRATION.lock().unwrap() = Some(NoiseCalibrator::new(sample_rate));
+}
+
+/// Stop measuring ambient noise and return the measurement.
+pub fn finish_calibration() -> Option<NoiseCalibrator> {
+    CALIBRATION.lock().unwrap().take()
+}
 
 /// Global audio processing thread control
 static AUDIO_LOOP_ACTIVE: std::sync::OnceLock<Arc<AtomicBool>> = std::sync::OnceLock::new();
@@ -40,16 +91,18 @@ pub fn is_audio_loop_active() -> bool {
 pub fn start_audio_loop(
     _transcription_queue: Arc<TranscriptionQueue>,
     transcribe_state: Arc<std::sync::Mutex<TranscribeState>>,
-) -> Result<(), String> {
+) -> Result<(), FlowSttError> {
     if is_audio_loop_active() {
-        return Err("Audio loop already running".into());
+        return Err(FlowSttError::Capture(
+            "Audio loop already running".to_string(),
+        ));
     }
 
     let loop_active = get_loop_active();
     loop_active.store(true, Ordering::SeqCst);
 
     // Get sample rate from backend
-    let sample_rate = platform::get_backend()
+    let mut sample_rate = platform::get_backend()
         .map(|b| b.sample_rate())
         .unwrap_or(48000);
 
@@ -57,14 +110,17 @@ pub fn start_audio_loop(
         tracing::info!("[AudioLoop] Starting audio processing loop");
 
         // Create speech detector
-        let mut speech_detector = SpeechDetector::new(sample_rate);
+        let mut speech_detector = create_speech_detector(sample_rate, &vad_config());
         speech_detector.set_callback(Arc::new(SpeechEventBroadcaster));
 
         // Create visualization processor
         let mut viz_processor = VisualizationProcessor::new(sample_rate, 256);
         viz_processor.set_callback(Arc::new(VisualizationBroadcaster));
 
+        let mut vad_recorder = vad_recording::recorder();
+
         let loop_active = get_loop_active();
+        let mut last_audio = Instant::now();
 
         loop {
             // Check if we should stop
@@ -77,12 +133,44 @@ pub fn start_audio_loop(
                 break;
             }
 
-            // Try to receive audio from backend
-            let audio_data = platform::get_backend().and_then(|b| b.try_recv());
+            // Pick up speech detector changes
+            if VAD_CONFIG_CHANGED.swap(false, Ordering::SeqCst) {
+                let config = vad_config();
+                if config.backend == speech_detector.backend() {
+                    speech_detector.set_config(&config);
+                } else {
+                    speech_detector = create_speech_detector(sample_rate, &config);
+                    speech_detector.set_callback(Arc::new(SpeechEventBroadcaster));
+                }
+            }
+
+            if vad_recording::config_changed() {
+                vad_recorder = vad_recording::recorder();
+            }
+
+            // Wait for audio from the backend
+            let audio_data = platform::recv_audio(RECV_TIMEOUT);
 
             if let Some(data) = audio_data {
+                last_audio = Instant::now();
+
+                // Follow the rate capture delivers, which is 16kHz with
+                // speech capture
+                if data.sample_rate != sample_rate {
+                    sample_rate = data.sample_rate;
+                    speech_detector = create_speech_detector(sample_rate, &vad_config());
+                    speech_detector.set_callback(Arc::new(SpeechEventBroadcaster));
+                    viz_processor = VisualizationProcessor::new(sample_rate, 256);
+                    viz_processor.set_callback(Arc::new(VisualizationBroadcaster));
+                }
+
                 // Convert to mono for processing
                 let mono_samples = convert_to_mono(&data.samples, data.channels as usize);
+                crate::metrics::add_samples_processed(mono_samples.len());
+
+                if let Some(calibrator) = CALIBRATION.lock().unwrap().as_mut() {
+                    calibrator.process(&mono_samples);
+                }
 
                 // Process through speech detector (always run for visualization)
                 speech_detector.process(&mono_samples);
@@ -91,13 +179,52 @@ pub fn start_audio_loop(
                 let speech_metrics = speech_detector.get_metrics();
                 viz_processor.set_speech_metrics(speech_metrics);
 
-                // Process visualization
+                // Process visualization, with separate traces for each source
+                if let Some(sources) = data.sources.as_ref() {
+                    let channels = sources.channels as usize;
+                    viz_processor.push_source_samples(
+                        AudioSourceType::Input,
+                        &convert_to_mono(&sources.input, channels),
+                    );
+                    viz_processor.push_source_samples(
+                        AudioSourceType::System,
+                        &convert_to_mono(&sources.system, channels),
+                    );
+                }
                 viz_processor.process(&mono_samples);
 
+                // Forward per-source formats, meter levels and AEC metrics
+                for format in platform::take_source_formats() {
+                    broadcast_event(Response::Event {
+                        event: EventType::SourceFormat(format),
+                    });
+                }
+                if let Some(levels) = platform::take_source_levels() {
+                    broadcast_event(Response::Event {
+                        event: EventType::Levels(levels),
+                    });
+                }
+                if let Some(metrics) = platform::take_aec_metrics() {
+                    broadcast_event(Response::Event {
+                        event: EventType::AecMetrics(metrics),
+                    });
+                }
+
                 // Handle speech state changes for transcribe mode
                 let state_change = speech_detector.take_state_change();
                 let word_break = speech_detector.take_word_break_event();
 
+                crate::rolling_buffer::push(&data.samples, data.sample_rate, data.channels);
+
+                if let Some(recorder) = vad_recorder.as_mut() {
+                    recorder.process(
+                        &data.samples,
+                        data.sample_rate,
+                        data.channels,
+                        &state_change,
+                    );
+                }
+
                 // Update transcribe state if active
                 // Note: In Automatic mode, VAD triggers segments
                 // In PTT mode, PTT controller triggers segments (not audio_loop)
@@ -137,9 +264,10 @@ pub fn start_audio_loop(
                         }
                     }
                 }
-            } else {
-                // No data available, sleep briefly
-                thread::sleep(Duration::from_millis(1));
+            } else if last_audio.elapsed() >= STREAM_STALL_TIMEOUT {
+                // Capture has stalled, possibly because the device was removed
+                fall_back_to_default_source();
+                last_audio = Instant::now();
             }
         }
 
@@ -154,10 +282,65 @@ pub fn stop_audio_loop() {
     get_loop_active().store(false, Ordering::SeqCst);
 }
 
-/// Convert multi-channel audio to mono
-fn convert_to_mono(samples: &[f32], channels: usize) -> Vec<f32> {
+/// Switch capture to the default input device if the primary source is gone.
+///
+/// Called when capture stops delivering audio. Does nothing if capture isn't
+/// active or the configured device still exists.
+fn fall_back_to_default_source() {
+    let Some(backend) = platform::get_backend() else {
+        return;
+    };
+
+    let state_arc = get_service_state();
+    let mut state = state_arc.blocking_lock();
+    if !state.transcribe_status.capturing {
+        return;
+    }
+    let Some(previous) = state.source1_id.clone() else {
+        return;
+    };
+
+    let inputs = backend.list_input_devices();
+    let present = inputs
+        .iter()
+        .chain(backend.list_system_devices().iter())
+        .any(|d| d.id == previous);
+    if present {
+        return;
+    }
+
+    let Some(default) = inputs.into_iter().next() else {
+        warn!(
+            "[AudioLoop] Source {} disappeared and no input device is available",
+            previous
+        );
+        return;
+    };
+
+    info!(
+        "[AudioLoop] Source {} disappeared, switching to {} ({})",
+        previous, default.name, default.id
+    );
+    if let Err(e) =
+        backend.start_capture_sources(Some(default.id.clone()), state.source2_id.clone())
+    {
+        error!("[AudioLoop] Failed to switch to default input: {}", e);
+        return;
+    }
+    state.source1_id = Some(default.id.clone());
+
+    broadcast_event(Response::Event {
+        event: EventType::SourceSwitched {
+            previous,
+            current: default.id,
+        },
+    });
+}
+
+/// Convert multi-channel audio to mono; mono audio is borrowed as it is
+fn convert_to_mono(samples: &[f32], channels: usize) -> Cow<'_, [f32]> {
     if channels <= 1 {
-        return samples.to_vec();
+        return Cow::Borrowed(samples);
     }
     samples
         .chunks(channels)
@@ -206,6 +389,7 @@ impl VisualizationCallback for VisualizationBroadcaster {
                     is_lookback_speech: m.is_lookback_speech,
                     is_word_break: m.is_word_break,
                 }),
+            source_waveforms: payload.source_waveforms,
         };
         broadcast_event(Response::Event {
             event: EventType::VisualizationData(data),
@@ -221,18 +405,114 @@ impl TranscriptionCallback for TranscriptionEventBroadcaster {
         debug!("[Transcription] Started");
     }
 
-    fn on_transcription_complete(&self, text: String) {
-        info!("[Transcription] Complete: {}", text);
+    fn on_transcription_complete(&self, mut transcript: Transcript) {
+        info!("[Transcription] Complete: {}", transcript.text);
+        let app = crate::focus::current();
+        let mut saved_id = None;
+        if transcript.text != NO_SPEECH_TEXT {
+            if let Some(command) = crate::commands::match_text(&transcript.text) {
+                info!("[Command] Detected: {:?}", command.phrase);
+                broadcast_event(Response::Event {
+                    event: EventType::CommandDetected {
+                        phrase: command.phrase,
+                        text: crate::redact::text(RedactionChannel::Events, &transcript.text),
+                    },
+                });
+                if command.suppress_text {
+                    return;
+                }
+            }
+            let mut saved = transcript.clone();
+            crate::redact::transcript(RedactionChannel::History, &mut saved);
+            let id = crate::history::record_transcript(&saved, app.clone());
+            crate::recent::push(
+                &id,
+                crate::redact::text(RedactionChannel::Events, &transcript.text),
+                transcript.speaker.clone(),
+            );
+            let output = crate::redact::text(RedactionChannel::Output, &transcript.text);
+            crate::output::deliver(
+                transcript.output,
+                Segment {
+                    text: output.clone(),
+                    speaker: transcript.speaker.clone(),
+                    language: transcript.language.clone(),
+                    app: app
+                        .as_ref()
+                        .map(|app| app.name.clone().unwrap_or_else(|| app.id.clone())),
+                    duration_ms: transcript.duration_ms,
+                    id: Some(id.clone()),
+                    completed: chrono::Local::now(),
+                },
+            );
+            crate::cues::emit(FeedbackCue::TranscriptionDone);
+            if let Some(text) = crate::dictation::append(&output) {
+                broadcast_event(Response::Event {
+                    event: EventType::DictationUpdated { text },
+                });
+            }
+            saved_id = Some(id);
+        }
+        crate::redact::transcript(RedactionChannel::Events, &mut transcript);
         broadcast_event(Response::Event {
             event: EventType::TranscriptionComplete(TranscriptionResult {
-                text,
+                text: transcript.text,
+                audio_path: transcript
+                    .audio_path
+                    .map(|p| p.to_string_lossy().to_string()),
+                language: transcript.language,
+                words: transcript.words,
+                segments: transcript.segments,
+                confidence: transcript.confidence,
+                translation: transcript.translation,
+                modified: transcript.modified,
+                speaker: transcript.speaker,
+                app,
+                id: saved_id,
+                duration_ms: transcript.duration_ms,
+            }),
+        });
+    }
+
+    fn on_transcription_partial(&self, mut transcript: Transcript) {
+        debug!("[Transcription] Partial: {}", transcript.text);
+        crate::redact::transcript(RedactionChannel::Events, &mut transcript);
+        broadcast_event(Response::Event {
+            event: EventType::TranscriptionPartial(TranscriptionResult {
+                text: transcript.text,
                 audio_path: None,
+                language: transcript.language,
+                words: transcript.words,
+                segments: transcript.segments,
+                confidence: transcript.confidence,
+                translation: transcript.translation,
+                modified: transcript.modified,
+                speaker: transcript.speaker,
+                app: None,
+                id: None,
+                duration_ms: transcript.duration_ms,
             }),
         });
     }
 
-    fn on_transcription_error(&self, error: String) {
+    fn on_transcription_token(&self, text: String) {
+        // A token can't be redacted without the text around it
+        if crate::redact::is_enabled(RedactionChannel::Events) {
+            return;
+        }
+        broadcast_event(Response::Event {
+            event: EventType::TranscriptionToken { text },
+        });
+    }
+
+    fn on_transcription_error(&self, error: FlowSttError) {
         error!("[Transcription] Error: {}", error);
+        broadcast_event(Response::Event {
+            event: EventType::Error {
+                code: error.code(),
+                message: error.to_string(),
+            },
+        });
     }
 
     fn on_transcription_finished(&self) {
@@ -241,5 +521,6 @@ impl TranscriptionCallback for TranscriptionEventBroadcaster {
 
     fn on_queue_update(&self, depth: usize) {
         debug!("[Transcription] Queue depth: {}", depth);
+        crate::metrics::record_queue_depth(depth);
     }
 }
@@ -0,0 +1,793 @@
+//! Naming of saved transcriptions after calendar meetings.
+//!
+//! With a calendar configured, a transcription saved to history is named after
+//! the meeting it overlaps most: the event's summary becomes the session title,
+//! its categories the tags and its organizer and attendees the participants.
+//! The calendar is an iCalendar file, an iCalendar feed or a CalDAV collection,
+//! re-read every `refresh_mins`.
+//!
+//! Daily and weekly recurrences in files and feeds are expanded here, with
+//! excluded and moved occurrences; CalDAV servers expand recurrences for us.
+//! Times are compared as instants. A meeting repeats at the same wall-clock
+//! time in the zone of its `TZID`, so an invite from another time zone matches
+//! the hours it really takes place in; times without one, and zones that
+//! aren't in the time zone database, are in the local time zone.
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+use chrono::{DateTime, Datelike, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use chrono::{Days, Weekday};
+use chrono_tz::Tz;
+use flowstt_common::SessionMetadata;
+use regex::Regex;
+use tracing::{debug, info, warn};
+
+use crate::config::{CalendarConfig, CalendarSource};
+
+/// Longest a calendar download may take
+const FETCH_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Days before and after now fetched from CalDAV servers
+const CALDAV_WINDOW_DAYS: u64 = 2;
+
+static CONFIG: Mutex<Option<CalendarConfig>> = Mutex::new(None);
+
+/// Meetings from the last read of the calendar
+static EVENTS: Mutex<Vec<Event>> = Mutex::new(Vec::new());
+
+/// Replace the calendar settings; takes effect with the next refresh.
+pub fn set_config(config: CalendarConfig) {
+    *CONFIG.lock().unwrap() = Some(config);
+}
+
+/// Re-read the configured calendar periodically until shutdown.
+pub async fn run() {
+    while !crate::is_shutdown_requested() {
+        let Some(config) = CONFIG.lock().unwrap().clone() else {
+            return;
+        };
+        if let Some(source) = config.source {
+            match tokio::task::spawn_blocking(move || load(&source)).await {
+                Ok(Ok(events)) => {
+                    debug!("Read {} calendar events", events.len());
+                    *EVENTS.lock().unwrap() = events;
+                }
+                Ok(Err(e)) => warn!("Failed to read calendar: {}", e),
+                Err(e) => warn!("Calendar refresh failed: {}", e),
+            }
+        }
+        let mins = config.refresh_mins.max(1) as u64;
+        tokio::time::sleep(Duration::from_secs(mins * 60)).await;
+    }
+}
+
+/// Title, tags and participants of the meeting overlapping `start..end` the
+/// most, if any.
+pub fn meeting_metadata(start: DateTime<Local>, end: DateTime<Local>) -> Option<SessionMetadata> {
+    let start = start.with_timezone(&Utc);
+    let end = end
+        .with_timezone(&Utc)
+        .max(start + chrono::Duration::seconds(1));
+    let events = EVENTS.lock().unwrap();
+    let event = best_match(&events, start, end)?;
+    info!("Naming session after meeting {:?}", event.summary);
+    Some(event.metadata())
+}
+
+/// Read and parse the events of `source`.
+fn load(source: &CalendarSource) -> Result<Vec<Event>, String> {
+    match source {
+        CalendarSource::File { path } => {
+            let ics = std::fs::read_to_string(path)
+                .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+            Ok(parse(&ics))
+        }
+        CalendarSource::Url { url } => {
+            let url = match url.strip_prefix("webcal://") {
+                Some(rest) => format!("https://{}", rest),
+                None => url.clone(),
+            };
+            let ics = client()?
+                .get(&url)
+                .send()
+                .and_then(|r| r.error_for_status())
+                .and_then(|r| r.text())
+                .map_err(|e| format!("Failed to fetch {}: {}", url, e))?;
+            Ok(parse(&ics))
+        }
+        CalendarSource::Caldav {
+            url,
+            username,
+            password,
+        } => {
+            let now = Utc::now();
+            let window = Days::new(CALDAV_WINDOW_DAYS);
+            let body = caldav_query(now - window, now + window);
+            let method = reqwest::Method::from_bytes(b"REPORT").expect("valid method");
+            let mut request = client()?
+                .request(method, url)
+                .header("Depth", "1")
+                .header(
+                    reqwest::header::CONTENT_TYPE,
+                    "application/xml; charset=utf-8",
+                )
+                .body(body);
+            if let Some(username) = username {
+                request = request.basic_auth(username, password.as_ref());
+            }
+            let xml = request
+                .send()
+                .and_then(|r| r.error_for_status())
+                .and_then(|r| r.text())
+                .map_err(|e| format!("CalDAV query to {} failed: {}", url, e))?;
+            Ok(calendar_data(&xml)
+                .iter()
+                .flat_map(|ics| parse(ics))
+                .collect())
+        }
+    }
+}
+
+fn client() -> Result<reqwest::blocking::Client, String> {
+    reqwest::blocking::Client::builder()
+        .timeout(FETCH_TIMEOUT)
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))
+}
+
+/// A CalDAV `calendar-query` for the events between `from` and `to`, with
+/// recurrences expanded by the server.
+fn caldav_query(from: DateTime<Utc>, to: DateTime<Utc>) -> String {
+    let from = from.format("%Y%m%dT%H%M%SZ");
+    let to = to.format("%Y%m%dT%H%M%SZ");
+    format!(
+        r#"<?xml version="1.0" encoding="utf-8"?>
+<C:calendar-query xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">
+  <D:prop>
+    <C:calendar-data><C:expand start="{from}" end="{to}"/></C:calendar-data>
+  </D:prop>
+  <C:filter>
+    <C:comp-filter name="VCALENDAR">
+      <C:comp-filter name="VEVENT"><C:time-range start="{from}" end="{to}"/></C:comp-filter>
+    </C:comp-filter>
+  </C:filter>
+</C:calendar-query>"#
+    )
+}
+
+/// The iCalendar objects in a CalDAV multistatus response.
+fn calendar_data(xml: &str) -> Vec<String> {
+    let pattern =
+        Regex::new(r"(?s)<(?:[\w-]+:)?calendar-data[^>]*>(.*?)</(?:[\w-]+:)?calendar-data>")
+            .expect("valid regex");
+    pattern
+        .captures_iter(xml)
+        .map(|captures| {
+            let data = captures[1].trim();
+            match data
+                .strip_prefix("<![CDATA[")
+                .and_then(|d| d.strip_suffix("]]>"))
+            {
+                Some(cdata) => cdata.to_string(),
+                None => data
+                    .replace("&lt;", "<")
+                    .replace("&gt;", ">")
+                    .replace("&quot;", "\"")
+                    .replace("&apos;", "'")
+                    .replace("&#13;", "\r")
+                    .replace("&amp;", "&"),
+            }
+        })
+        .collect()
+}
+
+/// A meeting, or the first occurrence of a recurring one
+#[derive(Debug, Clone, Default)]
+struct Event {
+    uid: String,
+    summary: String,
+    categories: Vec<String>,
+    participants: Vec<String>,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    /// Zone the meeting is scheduled in; the local time zone when `None`
+    zone: Option<Tz>,
+    recurrence: Option<Recurrence>,
+    /// Starts of occurrences that were removed or moved
+    exceptions: Vec<DateTime<Utc>>,
+    /// Start of the occurrence of a recurring meeting this one replaces
+    recurrence_id: Option<DateTime<Utc>>,
+    cancelled: bool,
+}
+
+/// How a meeting repeats
+#[derive(Debug, Clone, PartialEq)]
+struct Recurrence {
+    weekly: bool,
+    interval: u32,
+    until: Option<DateTime<Utc>>,
+    count: Option<u32>,
+    /// Days of a weekly meeting; the day it starts on when empty
+    days: Vec<Weekday>,
+}
+
+impl Event {
+    fn metadata(&self) -> SessionMetadata {
+        SessionMetadata {
+            title: Some(self.summary.clone()).filter(|s| !s.is_empty()),
+            tags: self.categories.clone(),
+            participants: self.participants.clone(),
+            notes: None,
+        }
+    }
+
+    /// Occurrences overlapping `from..to`, as (start, end).
+    fn occurrences(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+        let length = self.end - self.start;
+        let overlaps = |start: DateTime<Utc>| start < to && start + length > from;
+        let Some(rule) = &self.recurrence else {
+            return if overlaps(self.start) {
+                vec![(self.start, self.end)]
+            } else {
+                Vec::new()
+            };
+        };
+
+        // Occurrences keep the wall-clock time of the first in its zone
+        let first = wall_clock(self.start, self.zone);
+        let first_day = first.date();
+        let time = first.time();
+        let mut days = rule.days.clone();
+        if days.is_empty() {
+            days.push(first_day.weekday());
+        }
+        days.sort_by_key(|day| day.num_days_from_monday());
+        let week_start = first_day - Days::new(first_day.weekday().num_days_from_monday() as u64);
+
+        let mut found = Vec::new();
+        let mut count = 0;
+        for period in 0u64.. {
+            let step = period * rule.interval as u64;
+            let dates: Vec<NaiveDate> = if rule.weekly {
+                let week = week_start + Days::new(7 * step);
+                days.iter()
+                    .map(|day| week + Days::new(day.num_days_from_monday() as u64))
+                    .filter(|date| *date >= first_day)
+                    .collect()
+            } else {
+                vec![first_day + Days::new(step)]
+            };
+            for date in dates {
+                let Some(start) = instant(date.and_time(time), self.zone) else {
+                    continue;
+                };
+                if start >= to
+                    || rule.until.is_some_and(|until| start > until)
+                    || rule.count.is_some_and(|limit| count >= limit)
+                {
+                    return found;
+                }
+                count += 1;
+                if overlaps(start) && !self.exceptions.contains(&start) {
+                    found.push((start, start + length));
+                }
+            }
+        }
+        found
+    }
+}
+
+/// The event with the occurrence overlapping `from..to` the most.
+fn best_match(events: &[Event], from: DateTime<Utc>, to: DateTime<Utc>) -> Option<&Event> {
+    events
+        .iter()
+        .filter_map(|event| {
+            let overlap = event
+                .occurrences(from, to)
+                .into_iter()
+                .map(|(start, end)| end.min(to) - start.max(from))
+                .max()?;
+            Some((overlap, event))
+        })
+        .max_by_key(|(overlap, _)| *overlap)
+        .map(|(_, event)| event)
+}
+
+/// Parse the timed, non-cancelled events of an iCalendar object.
+fn parse(ics: &str) -> Vec<Event> {
+    let mut events: Vec<Event> = Vec::new();
+    let mut current: Option<Event> = None;
+    let mut duration = None;
+    // Depth of components nested in the event, like alarms
+    let mut nested = 0;
+
+    for line in unfold(ics) {
+        let Some((name, params, value)) = property(&line) else {
+            continue;
+        };
+        let Some(event) = current.as_mut() else {
+            if name == "BEGIN" && value == "VEVENT" {
+                current = Some(Event::default());
+                duration = None;
+                nested = 0;
+            }
+            continue;
+        };
+        let param = |key: &str| {
+            params
+                .iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case(key))
+                .map(|(_, v)| v.as_str())
+        };
+        let time = |value: &str| date_time(value, param("VALUE"), param("TZID"));
+        match name.as_str() {
+            "BEGIN" => nested += 1,
+            "END" if nested > 0 => nested -= 1,
+            "END" => {
+                if let Some(duration) = duration {
+                    event.end = event.start + duration;
+                }
+                event.end = event.end.max(event.start);
+                // All-day and malformed events have no start time
+                if let Some(event) = current
+                    .take()
+                    .filter(|e| e.start != DateTime::<Utc>::default())
+                {
+                    events.push(event);
+                }
+            }
+            _ if nested > 0 => {}
+            "UID" => event.uid = value,
+            "SUMMARY" => event.summary = unescape(&value),
+            "STATUS" => event.cancelled = value.eq_ignore_ascii_case("CANCELLED"),
+            "DTSTART" => {
+                let (start, zone) = time(&value).unwrap_or_default();
+                event.start = start;
+                event.zone = zone;
+            }
+            "DTEND" => event.end = time(&value).unwrap_or_default().0,
+            "DURATION" => duration = parse_duration(&value),
+            "RRULE" => event.recurrence = parse_rule(&value),
+            "EXDATE" => event.exceptions.extend(
+                value
+                    .split(',')
+                    .filter_map(|date| time(date).map(|(start, _)| start)),
+            ),
+            "RECURRENCE-ID" => event.recurrence_id = time(&value).map(|(start, _)| start),
+            "CATEGORIES" => event.categories.extend(
+                split_list(&value)
+                    .into_iter()
+                    .filter(|category| !category.is_empty()),
+            ),
+            "ORGANIZER" | "ATTENDEE" => {
+                let name = match param("CN") {
+                    Some(cn) => cn.to_string(),
+                    None => strip_mailto(&value).to_string(),
+                };
+                if !name.is_empty() && !event.participants.contains(&name) {
+                    event.participants.push(name);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Moved and cancelled occurrences replace theirs in the series
+    let replaced: Vec<(String, DateTime<Utc>)> = events
+        .iter()
+        .filter_map(|event| Some((event.uid.clone(), event.recurrence_id?)))
+        .collect();
+    for (uid, start) in replaced {
+        if let Some(series) = events
+            .iter_mut()
+            .find(|event| event.uid == uid && event.recurrence.is_some())
+        {
+            series.exceptions.push(start);
+        }
+    }
+    events.retain(|event| !event.cancelled);
+    events
+}
+
+/// Join folded lines: a line starting with a space or tab continues the
+/// previous one.
+fn unfold(ics: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in ics.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+/// Property parameters, as (name, value) pairs
+type Params = Vec<(String, String)>;
+
+/// Split a content line into its upper-case name, parameters and value.
+fn property(line: &str) -> Option<(String, Params, String)> {
+    let mut in_quotes = false;
+    let colon = line.char_indices().find_map(|(i, c)| {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            ':' if !in_quotes => return Some(i),
+            _ => {}
+        }
+        None
+    })?;
+    let (head, value) = (&line[..colon], &line[colon + 1..]);
+
+    let mut parts = Vec::new();
+    let mut part = String::new();
+    in_quotes = false;
+    for c in head.chars() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            ';' if !in_quotes => parts.push(std::mem::take(&mut part)),
+            _ => part.push(c),
+        }
+    }
+    parts.push(part);
+
+    let name = parts.remove(0).to_ascii_uppercase();
+    let params = parts
+        .into_iter()
+        .filter_map(|param| {
+            let (key, value) = param.split_once('=')?;
+            Some((key.to_string(), value.to_string()))
+        })
+        .collect();
+    Some((name, params, value.to_string()))
+}
+
+/// Undo iCalendar text escaping.
+fn unescape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => out.push('\n'),
+            Some(other) => out.push(other),
+            None => {}
+        }
+    }
+    out.trim().to_string()
+}
+
+/// Split a comma-separated text list, keeping escaped commas.
+fn split_list(value: &str) -> Vec<String> {
+    let mut items = Vec::new();
+    let mut item = String::new();
+    let mut escaped = false;
+    for c in value.chars() {
+        match c {
+            ',' if !escaped => items.push(unescape(&std::mem::take(&mut item))),
+            _ => item.push(c),
+        }
+        escaped = c == '\\' && !escaped;
+    }
+    items.push(unescape(&item));
+    items
+}
+
+fn strip_mailto(value: &str) -> &str {
+    match value.get(..7) {
+        Some(scheme) if scheme.eq_ignore_ascii_case("mailto:") => &value[7..],
+        _ => value,
+    }
+}
+
+/// Parse a DATE-TIME, in UTC or the zone named by `tzid`, into the instant
+/// it stands for and the zone it was given in. All-day DATE values give
+/// `None`, since they would name every session of the day.
+fn date_time(
+    value: &str,
+    value_type: Option<&str>,
+    tzid: Option<&str>,
+) -> Option<(DateTime<Utc>, Option<Tz>)> {
+    if value_type.is_some_and(|t| t.eq_ignore_ascii_case("DATE")) {
+        return None;
+    }
+    let value = value.trim();
+    let (value, zone) = match value.strip_suffix(['Z', 'z']) {
+        Some(utc) => (utc, Some(Tz::UTC)),
+        None => (value, tzid.and_then(time_zone)),
+    };
+    let time = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?;
+    Some((instant(time, zone)?, zone))
+}
+
+/// The zone a `TZID` names, if it's in the time zone database.
+fn time_zone(tzid: &str) -> Option<Tz> {
+    let tzid = tzid.trim().trim_matches('"');
+    let zone = tzid.parse().ok();
+    if zone.is_none() {
+        debug!("Unknown calendar time zone {:?}, using local time", tzid);
+    }
+    zone
+}
+
+/// The instant a wall-clock time in `zone`, or the local time zone, stands
+/// for: the first of two when clocks go back, and an hour later when they
+/// skip it going forward.
+fn instant(time: NaiveDateTime, zone: Option<Tz>) -> Option<DateTime<Utc>> {
+    fn resolve<Z: TimeZone>(zone: &Z, time: NaiveDateTime) -> Option<DateTime<Utc>> {
+        zone.from_local_datetime(&time)
+            .earliest()
+            .or_else(|| {
+                zone.from_local_datetime(&(time + chrono::Duration::hours(1)))
+                    .earliest()
+            })
+            .map(|time| time.with_timezone(&Utc))
+    }
+    match zone {
+        Some(zone) => resolve(&zone, time),
+        None => resolve(&Local, time),
+    }
+}
+
+/// Wall-clock time of `instant` in `zone`, or the local time zone.
+fn wall_clock(instant: DateTime<Utc>, zone: Option<Tz>) -> NaiveDateTime {
+    match zone {
+        Some(zone) => instant.with_timezone(&zone).naive_local(),
+        None => instant.with_timezone(&Local).naive_local(),
+    }
+}
+
+/// Parse a DURATION such as `PT1H30M` or `P1D`.
+fn parse_duration(value: &str) -> Option<chrono::Duration> {
+    let value = value.trim().trim_start_matches('+').strip_prefix('P')?;
+    let mut total = chrono::Duration::zero();
+    let mut number = String::new();
+    for c in value.chars() {
+        match c {
+            'T' => {}
+            '0'..='9' => number.push(c),
+            unit => {
+                let n: i64 = std::mem::take(&mut number).parse().ok()?;
+                total += match unit {
+                    'W' => chrono::Duration::weeks(n),
+                    'D' => chrono::Duration::days(n),
+                    'H' => chrono::Duration::hours(n),
+                    'M' => chrono::Duration::minutes(n),
+                    'S' => chrono::Duration::seconds(n),
+                    _ => return None,
+                };
+            }
+        }
+    }
+    Some(total)
+}
+
+/// Parse an RRULE. Only daily and weekly rules are expanded; meetings that
+/// repeat otherwise keep just their first occurrence.
+fn parse_rule(value: &str) -> Option<Recurrence> {
+    let mut rule = Recurrence {
+        weekly: false,
+        interval: 1,
+        until: None,
+        count: None,
+        days: Vec::new(),
+    };
+    for part in value.split(';') {
+        let (key, value) = part.split_once('=')?;
+        match key.to_ascii_uppercase().as_str() {
+            "FREQ" => match value.to_ascii_uppercase().as_str() {
+                "DAILY" => rule.weekly = false,
+                "WEEKLY" => rule.weekly = true,
+                other => {
+                    debug!("Not expanding {} calendar recurrence", other);
+                    return None;
+                }
+            },
+            "INTERVAL" => rule.interval = value.parse().ok().filter(|&n| n > 0)?,
+            "COUNT" => rule.count = value.parse().ok(),
+            "UNTIL" => {
+                // UNTIL is in UTC unless the meeting's times are local
+                rule.until = date_time(value, None, None)
+                    .map(|(until, _)| until)
+                    .or_else(|| {
+                        let date = NaiveDate::parse_from_str(value, "%Y%m%d").ok()?;
+                        instant(date.and_time(NaiveTime::from_hms_opt(23, 59, 59)?), None)
+                    })
+            }
+            "BYDAY" => {
+                rule.days = value
+                    .split(',')
+                    .filter_map(|day| day.get(day.len().saturating_sub(2)..).and_then(weekday))
+                    .collect()
+            }
+            _ => {}
+        }
+    }
+    Some(rule)
+}
+
+fn weekday(code: &str) -> Option<Weekday> {
+    Some(match code.to_ascii_uppercase().as_str() {
+        "MO" => Weekday::Mon,
+        "TU" => Weekday::Tue,
+        "WE" => Weekday::Wed,
+        "TH" => Weekday::Thu,
+        "FR" => Weekday::Fri,
+        "SA" => Weekday::Sat,
+        "SU" => Weekday::Sun,
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Instant of a wall-clock time in Berlin.
+    fn at(value: &str) -> DateTime<Utc> {
+        in_zone(value, Tz::Europe__Berlin)
+    }
+
+    fn in_zone(value: &str, zone: Tz) -> DateTime<Utc> {
+        let time = NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M").unwrap();
+        instant(time, Some(zone)).unwrap()
+    }
+
+    const STANDUP: &str = "BEGIN:VCALENDAR\r
+VERSION:2.0\r
+BEGIN:VEVENT\r
+UID:standup\r
+SUMMARY:Team standup\r
+DTSTART;TZID=Europe/Berlin:20260302T093000\r
+DURATION:PT15M\r
+RRULE:FREQ=WEEKLY;BYDAY=MO,WE;COUNT=6\r
+EXDATE;TZID=Europe/Berlin:20260304T093000\r
+CATEGORIES:team,daily\r
+ORGANIZER;CN=Ana:mailto:ana@example.com\r
+ATTENDEE;CN=\"Li, Wei\";ROLE=REQ-PARTICIPANT:mailto:wei@example.com\r
+ATTENDEE:mailto:sam@example.com\r
+BEGIN:VALARM\r
+DESCRIPTION:Reminder\r
+SUMMARY:Alarm\r
+END:VALARM\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+UID:standup\r
+RECURRENCE-ID;TZID=Europe/Berlin:20260309T093000\r
+SUMMARY:Team standup (moved)\r
+DTSTART;TZID=Europe/Berlin:20260309T140000\r
+DTEND;TZID=Europe/Berlin:20260309T141500\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+UID:offsite\r
+SUMMARY:Offsite\r
+DTSTART;VALUE=DATE:20260302\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+UID:review\r
+SUMMARY:Budget review\\, Q1\r
+DTSTART;TZID=Europe/Berlin:20260302T100000\r
+DTEND;TZID=Europe/Berlin:20260302T110000\r
+DESCRIPTION:Long description that is folded over\r
+  two lines\r
+END:VEVENT\r
+END:VCALENDAR\r
+";
+
+    #[test]
+    fn test_parse_events() {
+        let events = parse(STANDUP);
+        let summaries: Vec<&str> = events.iter().map(|e| e.summary.as_str()).collect();
+        assert_eq!(
+            summaries,
+            ["Team standup", "Team standup (moved)", "Budget review, Q1"]
+        );
+
+        let standup = &events[0];
+        assert_eq!(standup.end - standup.start, chrono::Duration::minutes(15));
+        assert_eq!(standup.categories, ["team", "daily"]);
+        assert_eq!(standup.participants, ["Ana", "Li, Wei", "sam@example.com"]);
+        assert_eq!(
+            standup.exceptions,
+            [at("2026-03-04 09:30"), at("2026-03-09 09:30")]
+        );
+    }
+
+    #[test]
+    fn test_recurring_meetings_name_sessions() {
+        let events = parse(STANDUP);
+        let title = |from: &str, to: &str| {
+            best_match(&events, at(from), at(to)).map(|event| event.metadata().title.unwrap())
+        };
+
+        assert_eq!(
+            title("2026-03-02 09:35", "2026-03-02 09:40").as_deref(),
+            Some("Team standup")
+        );
+        // Excluded occurrence
+        assert_eq!(title("2026-03-04 09:35", "2026-03-04 09:40"), None);
+        // Moved occurrence
+        assert_eq!(title("2026-03-09 09:35", "2026-03-09 09:40"), None);
+        assert_eq!(
+            title("2026-03-09 14:05", "2026-03-09 14:10").as_deref(),
+            Some("Team standup (moved)")
+        );
+        assert_eq!(
+            title("2026-03-16 09:35", "2026-03-16 09:40").as_deref(),
+            Some("Team standup")
+        );
+        // The sixth occurrence is the last
+        assert_eq!(
+            title("2026-03-18 09:35", "2026-03-18 09:40").as_deref(),
+            Some("Team standup")
+        );
+        assert_eq!(title("2026-03-23 09:35", "2026-03-23 09:40"), None);
+        // The session spends longer in the review than in the standup
+        assert_eq!(
+            title("2026-03-02 09:40", "2026-03-02 10:30").as_deref(),
+            Some("Budget review, Q1")
+        );
+    }
+
+    #[test]
+    fn test_meetings_keep_their_time_zone() {
+        let events = parse(
+            "BEGIN:VEVENT\r
+UID:sync\r
+SUMMARY:New York sync\r
+DTSTART;TZID=\"America/New_York\":20260302T090000\r
+DURATION:PT30M\r
+RRULE:FREQ=WEEKLY\r
+END:VEVENT\r
+",
+        );
+        let new_york = |value: &str| in_zone(value, Tz::America__New_York);
+        let matches = |from: DateTime<Utc>| {
+            best_match(&events, from, from + chrono::Duration::minutes(5)).is_some()
+        };
+
+        assert_eq!(events[0].zone, Some(Tz::America__New_York));
+        assert!(matches(new_york("2026-03-02 09:05")));
+        assert!(!matches(at("2026-03-02 09:05")));
+        // Still at nine in New York after its clocks go forward
+        assert!(matches(new_york("2026-03-09 09:05")));
+        assert!(!matches(new_york("2026-03-09 10:05")));
+    }
+
+    #[test]
+    fn test_caldav_calendar_data() {
+        let xml = r#"<d:multistatus xmlns:d="DAV:" xmlns:cal="urn:ietf:params:xml:ns:caldav">
+<d:response><d:propstat><d:prop>
+<cal:calendar-data>BEGIN:VCALENDAR
+BEGIN:VEVENT
+SUMMARY:R&amp;D sync
+DTSTART:20260302T100000Z
+DTEND:20260302T103000Z
+END:VEVENT
+END:VCALENDAR
+</cal:calendar-data>
+</d:prop></d:propstat></d:response>
+</d:multistatus>"#;
+        let data = calendar_data(xml);
+        assert_eq!(data.len(), 1);
+        let events = parse(&data[0]);
+        assert_eq!(events[0].summary, "R&D sync");
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(
+            parse_duration("PT1H30M"),
+            Some(chrono::Duration::minutes(90))
+        );
+        assert_eq!(parse_duration("P1W"), Some(chrono::Duration::days(7)));
+        assert_eq!(parse_duration("1H"), None);
+    }
+}
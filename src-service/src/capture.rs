@@ -0,0 +1,382 @@
+//! Capture sessions.
+//!
+//! A capture session runs from starting capture to stopping it. Its
+//! transcriptions are saved to history one utterance at a time as they
+//! complete; once capture stops and the last of them is saved, a single
+//! worker records the capture session, notifies webhooks and runs the session
+//! hooks on its combined transcript. Finished sessions queue up on the
+//! worker, so hook runs never overlap.
+
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Local};
+use flowstt_common::{CaptureSession, TranscriptSession};
+use tracing::{info, warn};
+
+use crate::history::get_history;
+use crate::ipc::handlers::get_transcription_queue;
+
+/// Longest to wait for segments queued before capture stopped to be
+/// transcribed
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How often the transcription queue is checked while draining
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A capture session that has started.
+struct Started {
+    id: String,
+    started_at: DateTime<Local>,
+}
+
+/// A capture session waiting for the worker.
+struct Finished {
+    id: String,
+    started_at: DateTime<Local>,
+    ended_at: DateTime<Local>,
+}
+
+/// The capture session in progress, if any
+static ACTIVE: Mutex<Option<Started>> = Mutex::new(None);
+
+/// Queue of the worker that finishes capture sessions
+static WORKER: OnceLock<Mutex<Sender<Finished>>> = OnceLock::new();
+
+/// Start a capture session, ending any that is still open, and return its ID.
+pub fn begin() -> String {
+    end();
+
+    let started_at = Local::now();
+    let id = started_at.format("%Y%m%d-%H%M%S-%3f").to_string();
+    get_history().lock().unwrap().set_capture(Some(id.clone()));
+    *ACTIVE.lock().unwrap() = Some(Started {
+        id: id.clone(),
+        started_at,
+    });
+    info!("Capture session {} started", id);
+    crate::webhooks::session_started(&id, started_at);
+    id
+}
+
+/// End the capture session in progress, if any, and queue it for the worker.
+pub fn end() {
+    let Some(started) = ACTIVE.lock().unwrap().take() else {
+        return;
+    };
+    info!("Capture session {} ended", started.id);
+    let finished = Finished {
+        id: started.id,
+        started_at: started.started_at,
+        ended_at: Local::now(),
+    };
+    if worker().lock().unwrap().send(finished).is_err() {
+        warn!("Capture session worker is gone");
+    }
+}
+
+/// Get the worker's queue, starting the worker on first use.
+fn worker() -> &'static Mutex<Sender<Finished>> {
+    WORKER.get_or_init(|| {
+        let (tx, rx) = mpsc::channel::<Finished>();
+        thread::spawn(move || {
+            for finished in rx {
+                finish(finished);
+            }
+        });
+        Mutex::new(tx)
+    })
+}
+
+/// Record a finished capture session with the chapters and statistics of its
+/// combined transcript, tell webhooks it ended and run the hooks on it.
+/// Sessions in which nothing was transcribed aren't recorded or passed to
+/// hooks.
+fn finish(finished: Finished) {
+    wait_for_transcriptions();
+
+    let (capture, session) = {
+        let mut history = get_history().lock().unwrap();
+        let entries: Vec<TranscriptSession> = history
+            .capture_sessions(&finished.id)
+            .into_iter()
+            .cloned()
+            .collect();
+        let mut session = combine(&finished, &entries);
+        session.chapters = crate::chapters::detect(&session);
+        session.stats = Some(crate::stats::compute(&session));
+        let capture = CaptureSession {
+            id: finished.id.clone(),
+            started_at: finished.started_at.to_rfc3339(),
+            ended_at: finished.ended_at.to_rfc3339(),
+            duration_ms: session.duration_ms,
+            session_ids: entries.into_iter().map(|s| s.id).collect(),
+            summary: None,
+            chapters: session.chapters.clone(),
+            stats: session.stats.clone(),
+        };
+        if capture.session_ids.is_empty() {
+            info!("Capture session {} had no transcriptions", finished.id);
+        } else {
+            history.add_capture(capture.clone());
+            if let Err(e) = history.save() {
+                warn!("Failed to save history: {}", e);
+            }
+        }
+        (capture, session)
+    };
+
+    crate::webhooks::session_ended(&capture, &session);
+    if !capture.session_ids.is_empty() {
+        crate::hooks::run(session);
+    }
+}
+
+/// Combined transcript of a recorded capture session, with its chapters and
+/// statistics. `None` if its times can't be read.
+pub fn transcript(
+    capture: &CaptureSession,
+    entries: &[TranscriptSession],
+) -> Option<TranscriptSession> {
+    let time = |t: &str| {
+        DateTime::parse_from_rfc3339(t)
+            .ok()
+            .map(|t| t.with_timezone(&Local))
+    };
+    let finished = Finished {
+        id: capture.id.clone(),
+        started_at: time(&capture.started_at)?,
+        ended_at: time(&capture.ended_at)?,
+    };
+    let mut session = combine(&finished, entries);
+    session.summary = capture.summary.clone();
+    session.chapters = capture.chapters.clone();
+    session.stats = capture.stats.clone();
+    Some(session)
+}
+
+/// Wait until every segment queued so far has been transcribed and saved.
+fn wait_for_transcriptions() {
+    let queue = get_transcription_queue();
+    let deadline = Instant::now() + DRAIN_TIMEOUT;
+    // Give the audio loop a moment to queue the segment in progress at stop
+    thread::sleep(POLL_INTERVAL);
+    while !queue.is_idle() {
+        if Instant::now() >= deadline {
+            warn!("Gave up waiting for transcriptions after capture stopped");
+            return;
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// Join the transcriptions of a capture session into one transcript, with
+/// word and segment times relative to the start of capture.
+///
+/// Each transcription is placed where it was spoken: it ended when it was
+/// saved, at the latest, and starts no earlier than the previous one ended.
+fn combine(finished: &Finished, entries: &[TranscriptSession]) -> TranscriptSession {
+    let first = entries.first();
+    let mut session = TranscriptSession {
+        id: finished.id.clone(),
+        created_at: finished.ended_at.to_rfc3339(),
+        text: String::new(),
+        language: first.and_then(|s| s.language.clone()),
+        words: Vec::new(),
+        segments: Vec::new(),
+        translation: None,
+        speaker: None,
+        duration_ms: (finished.ended_at - finished.started_at)
+            .num_milliseconds()
+            .max(0) as u64,
+        source1_id: first.and_then(|s| s.source1_id.clone()),
+        source2_id: first.and_then(|s| s.source2_id.clone()),
+        audio_path: None,
+        app: first.and_then(|s| s.app.clone()),
+        summary: None,
+        chapters: Vec::new(),
+        metadata: crate::calendar::meeting_metadata(finished.started_at, finished.ended_at)
+            .unwrap_or_default(),
+        model: first.and_then(|s| s.model.clone()),
+        versions: Vec::new(),
+        stats: None,
+        capture_id: Some(finished.id.clone()),
+    };
+
+    let mut translations = Vec::new();
+    let mut previous_end = 0;
+    for entry in entries {
+        let saved_at = DateTime::parse_from_rfc3339(&entry.created_at)
+            .map(|t| (t.with_timezone(&Local) - finished.started_at).num_milliseconds())
+            .unwrap_or(0)
+            .max(0) as u64;
+        let offset = saved_at.saturating_sub(entry.duration_ms).max(previous_end);
+        previous_end = offset + entry.duration_ms;
+
+        if !session.text.is_empty() {
+            session.text.push(' ');
+        }
+        session.text.push_str(&entry.text);
+        if let Some(translation) = &entry.translation {
+            translations.push(translation.as_str());
+        }
+        for word in &entry.words {
+            let mut word = word.clone();
+            word.start_ms += offset;
+            word.end_ms += offset;
+            session.words.push(word);
+        }
+        for segment in &entry.segments {
+            let mut segment = segment.clone();
+            segment.start_ms += offset;
+            segment.end_ms += offset;
+            session.segments.push(segment);
+        }
+    }
+    if !translations.is_empty() {
+        session.translation = Some(translations.join(" "));
+    }
+    session.duration_ms = session.duration_ms.max(previous_end);
+    session
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use flowstt_common::WordTiming;
+
+    fn entry(
+        id: &str,
+        saved_at: DateTime<Local>,
+        text: &str,
+        duration_ms: u64,
+    ) -> TranscriptSession {
+        TranscriptSession {
+            id: id.to_string(),
+            created_at: saved_at.to_rfc3339(),
+            text: text.to_string(),
+            language: Some("en".to_string()),
+            words: vec![WordTiming {
+                word: text.to_string(),
+                start_ms: 100,
+                end_ms: duration_ms - 100,
+                probability: 0.9,
+            }],
+            duration_ms,
+            capture_id: Some("capture".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_combine_places_transcriptions_in_capture_time() {
+        let started_at = Local.with_ymd_and_hms(2026, 3, 2, 10, 0, 0).unwrap();
+        let finished = Finished {
+            id: "capture".to_string(),
+            started_at,
+            ended_at: started_at + chrono::Duration::seconds(60),
+        };
+        let second = |s: i64| started_at + chrono::Duration::seconds(s);
+        let entries = [
+            entry("a", second(10), "hello", 4000),
+            // Saved late: starts no earlier than the previous one ended
+            entry("b", second(12), "there", 3000),
+            entry("c", second(40), "again", 5000),
+        ];
+
+        let session = combine(&finished, &entries);
+        assert_eq!(session.id, "capture");
+        assert_eq!(session.text, "hello there again");
+        assert_eq!(session.duration_ms, 60_000);
+        let starts: Vec<u64> = session.words.iter().map(|w| w.start_ms).collect();
+        assert_eq!(starts, [6_100, 10_100, 35_100]);
+    }
+
+    #[test]
+    fn test_capture_transcript_exports_chapters() {
+        use flowstt_common::export::{export_session, ExportFormat};
+        use flowstt_common::Chapter;
+
+        let started_at = Local.with_ymd_and_hms(2026, 3, 2, 10, 0, 0).unwrap();
+        let second = |s: i64| started_at + chrono::Duration::seconds(s);
+        let chapter = |title: &str, start_ms, end_ms| Chapter {
+            title: title.to_string(),
+            start_ms,
+            end_ms,
+        };
+        let capture = CaptureSession {
+            id: "capture".to_string(),
+            started_at: started_at.to_rfc3339(),
+            ended_at: second(120).to_rfc3339(),
+            duration_ms: 120_000,
+            session_ids: vec!["a".to_string(), "b".to_string()],
+            summary: None,
+            chapters: vec![
+                chapter("Opening", 0, 75_100),
+                chapter("Later", 75_100, 120_000),
+            ],
+            stats: None,
+        };
+        let entries = [
+            entry("a", second(10), "Hello.", 5000),
+            entry("b", second(80), "Again.", 5000),
+        ];
+
+        let session = transcript(&capture, &entries).unwrap();
+        let srt = export_session(&session, ExportFormat::Srt);
+        assert!(srt.starts_with("NOTE Opening\n"));
+        assert!(srt.contains("NOTE Later\n"));
+        let vtt = export_session(&session, ExportFormat::Vtt);
+        assert!(vtt.contains("NOTE Opening\n") && vtt.contains("NOTE Later\n"));
+        let markdown = export_session(&session, ExportFormat::Markdown);
+        assert!(markdown.contains("Opening (00:00:00)"));
+        assert!(markdown.contains("Later (00:01:15)"));
+    }
+
+    #[test]
+    fn test_stats_cover_the_whole_capture() {
+        let started_at = Local.with_ymd_and_hms(2026, 3, 2, 10, 0, 0).unwrap();
+        let finished = Finished {
+            id: "capture".to_string(),
+            started_at,
+            ended_at: started_at + chrono::Duration::seconds(60),
+        };
+        let second = |s: i64| started_at + chrono::Duration::seconds(s);
+        // The first two utterances run into each other; the third stands apart
+        let entries = [
+            entry("a", second(10), "hello", 4000),
+            entry("b", second(13), "there", 3000),
+            entry("c", second(40), "again", 5000),
+        ];
+
+        let stats = crate::stats::compute(&combine(&finished, &entries));
+        assert_eq!(stats.duration_ms, 60_000);
+        assert_eq!(stats.words, 3);
+        assert_eq!(stats.segments, 2);
+        assert_eq!(stats.speech_ms, 11_600);
+    }
+
+    #[test]
+    fn test_chapters_split_between_transcriptions() {
+        let started_at = Local.with_ymd_and_hms(2026, 3, 2, 10, 0, 0).unwrap();
+        let finished = Finished {
+            id: "capture".to_string(),
+            started_at,
+            ended_at: started_at + chrono::Duration::seconds(120),
+        };
+        let second = |s: i64| started_at + chrono::Duration::seconds(s);
+        // Each utterance is too short to split; the minute between them isn't
+        let entries = [
+            entry("a", second(10), "hello", 5000),
+            entry("b", second(80), "again", 5000),
+        ];
+
+        let session = combine(&finished, &entries);
+        let chapters = crate::chapters::detect(&session);
+        let bounds: Vec<(u64, u64)> = chapters.iter().map(|c| (c.start_ms, c.end_ms)).collect();
+        assert_eq!(bounds, [(0, 75_100), (75_100, 120_000)]);
+    }
+}
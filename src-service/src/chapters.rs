@@ -0,0 +1,109 @@
+//! Chapter markers for capture sessions.
+//!
+//! The combined transcript of a capture session is split where the speech
+//! pauses for longer than the silence threshold, including the gaps between
+//! utterances, or once a chapter has run for the chapter interval. Chapters
+//! start at the first word after the split, so no word is cut in two.
+//! Sessions that don't split get no chapters.
+
+use std::sync::Mutex;
+
+use flowstt_common::{Chapter, TranscriptSession};
+
+use crate::config::ChapterConfig;
+
+static CONFIG: Mutex<Option<ChapterConfig>> = Mutex::new(None);
+
+/// Replace the chapter settings used for subsequent capture sessions.
+pub fn set_config(config: ChapterConfig) {
+    *CONFIG.lock().unwrap() = Some(config);
+}
+
+fn config() -> ChapterConfig {
+    CONFIG.lock().unwrap().unwrap_or_default()
+}
+
+/// Chapters of a capture session's combined transcript, from its per-source
+/// segments if it has any and its word timings otherwise.
+pub fn detect(transcript: &TranscriptSession) -> Vec<Chapter> {
+    let spans: Vec<(u64, u64)> = if transcript.segments.is_empty() {
+        transcript
+            .words
+            .iter()
+            .map(|w| (w.start_ms, w.end_ms))
+            .collect()
+    } else {
+        transcript
+            .segments
+            .iter()
+            .map(|s| (s.start_ms, s.end_ms))
+            .collect()
+    };
+    split(&spans, transcript.duration_ms, &config())
+}
+
+/// Split timed spans of speech into chapters covering `duration_ms`.
+fn split(spans: &[(u64, u64)], duration_ms: u64, config: &ChapterConfig) -> Vec<Chapter> {
+    let silence_ms = config.silence_secs as u64 * 1000;
+    let interval_ms = config.interval_mins as u64 * 60_000;
+
+    let mut starts = vec![0];
+    let mut previous_end = None;
+    for &(start_ms, end_ms) in spans {
+        let chapter_start = *starts.last().unwrap();
+        let long_pause = silence_ms > 0
+            && previous_end.is_some_and(|end: u64| start_ms.saturating_sub(end) >= silence_ms);
+        let long_chapter = interval_ms > 0 && start_ms.saturating_sub(chapter_start) >= interval_ms;
+        if start_ms > chapter_start && (long_pause || long_chapter) {
+            starts.push(start_ms);
+        }
+        previous_end = Some(previous_end.unwrap_or(0).max(end_ms));
+    }
+    if starts.len() < 2 {
+        return Vec::new();
+    }
+
+    let ends = starts.iter().skip(1).copied().chain([duration_ms]);
+    starts
+        .iter()
+        .zip(ends)
+        .enumerate()
+        .map(|(i, (&start_ms, end_ms))| Chapter {
+            title: format!("Chapter {}", i + 1),
+            start_ms,
+            end_ms: end_ms.max(start_ms),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(silence_secs: u32, interval_mins: u32) -> ChapterConfig {
+        ChapterConfig {
+            silence_secs,
+            interval_mins,
+        }
+    }
+
+    #[test]
+    fn test_split_at_long_silence() {
+        let spans = [(0, 1000), (1500, 2000), (40_000, 41_000), (41_200, 42_000)];
+        let chapters = split(&spans, 45_000, &config(30, 0));
+        assert_eq!(chapters.len(), 2);
+        assert_eq!((chapters[0].start_ms, chapters[0].end_ms), (0, 40_000));
+        assert_eq!((chapters[1].start_ms, chapters[1].end_ms), (40_000, 45_000));
+        assert_eq!(chapters[1].title, "Chapter 2");
+
+        assert!(split(&spans, 45_000, &config(60, 0)).is_empty());
+    }
+
+    #[test]
+    fn test_split_at_interval() {
+        let spans: Vec<(u64, u64)> = (0..25).map(|i| (i * 10_000, i * 10_000 + 9000)).collect();
+        let chapters = split(&spans, 250_000, &config(0, 1));
+        let starts: Vec<u64> = chapters.iter().map(|c| c.start_ms).collect();
+        assert_eq!(starts, [0, 60_000, 120_000, 180_000, 240_000]);
+    }
+}
@@ -0,0 +1,72 @@
+//! Voice command matching.
+//!
+//! Clients register phrases such as "stop recording". A completed
+//! transcription whose text is exactly one of the phrases, ignoring case and
+//! punctuation, raises a `CommandDetected` event. Matching whole segments
+//! only keeps ordinary speech that happens to contain a phrase from
+//! triggering it.
+
+use flowstt_common::VoiceCommand;
+use std::sync::Mutex;
+
+/// Registered commands
+static COMMANDS: Mutex<Vec<VoiceCommand>> = Mutex::new(Vec::new());
+
+/// Replace the set of registered commands.
+pub fn set_commands(commands: &[VoiceCommand]) {
+    *COMMANDS.lock().unwrap() = commands.to_vec();
+}
+
+/// Find the registered command that a transcribed segment matches.
+pub fn match_text(text: &str) -> Option<VoiceCommand> {
+    let text = normalize(text);
+    if text.is_empty() {
+        return None;
+    }
+    COMMANDS
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|command| normalize(&command.phrase) == text)
+        .cloned()
+}
+
+/// Normalize a phrase for comparison: lowercase words without punctuation.
+pub fn normalize(text: &str) -> String {
+    text.split_whitespace()
+        .map(|word| {
+            word.chars()
+                .filter(|c| c.is_alphanumeric() || *c == '\'')
+                .flat_map(char::to_lowercase)
+                .collect::<String>()
+        })
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize("  Stop recording! "), "stop recording");
+        assert_eq!(normalize("Don't -- stop."), "don't stop");
+        assert_eq!(normalize("..."), "");
+    }
+
+    #[test]
+    fn test_match_whole_segment_only() {
+        set_commands(&[VoiceCommand {
+            phrase: "stop recording".to_string(),
+            suppress_text: true,
+        }]);
+        assert_eq!(
+            match_text("Stop recording.").map(|c| c.phrase),
+            Some("stop recording".to_string())
+        );
+        assert!(match_text("Please don't stop recording yet").is_none());
+        set_commands(&[]);
+    }
+}
@@ -4,13 +4,20 @@
 //! in the user's configuration directory.
 
 use directories::BaseDirs;
-use flowstt_common::{KeyCode, TranscriptionMode};
+use flowstt_common::{
+    AecSettings, ChannelSelection, HotkeyBinding, KeyCode, LatencyPreset, OutputMode, Profile,
+    RecordingFormat, SessionHook, SpectrogramSettings, SpeechDetectorConfig, TranscriptionMode,
+    VoiceCommand, DEFAULT_LANGUAGE,
+};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
 use std::io;
 use std::path::PathBuf;
 use tracing::{info, warn};
 
+use crate::audio::DEFAULT_FILENAME_TEMPLATE;
+
 /// Service configuration that persists across restarts.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
@@ -20,6 +27,870 @@ pub struct Config {
     /// Configured push-to-talk hotkey
     #[serde(default)]
     pub ptt_key: KeyCode,
+    /// Hotkeys for actions other than push-to-talk
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hotkeys: Vec<HotkeyBinding>,
+    /// Transcription language code ("auto" for detection)
+    #[serde(default = "default_language")]
+    pub language: String,
+    /// Also translate non-English transcriptions to English
+    #[serde(default)]
+    pub translate: bool,
+    /// Where completed transcriptions are delivered
+    #[serde(default)]
+    pub output_mode: OutputMode,
+    /// Template delivered text is formatted with (see `output::template`);
+    /// the bare text when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_template: Option<String>,
+    /// Trade-off between transcription latency and accuracy
+    #[serde(default)]
+    pub latency_preset: LatencyPreset,
+    /// Whether GPU acceleration is used for transcription
+    #[serde(default = "default_use_gpu")]
+    pub use_gpu: bool,
+    /// Index of the GPU used for transcription
+    #[serde(default)]
+    pub gpu_device: u32,
+    /// Directories watched for new recordings to transcribe
+    #[serde(default)]
+    pub watch_folders: Vec<PathBuf>,
+    /// Registered voice command phrases
+    #[serde(default)]
+    pub voice_commands: Vec<VoiceCommand>,
+    /// Directory recordings are saved to; ~/Documents/Recordings when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recordings_dir: Option<PathBuf>,
+    /// Whisper model file to use instead of the downloaded default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_path: Option<PathBuf>,
+    /// Catalog model to download and use; base.en when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_name: Option<String>,
+    /// Filename template for recordings (see `audio::render_filename_template`)
+    #[serde(default = "default_filename_template")]
+    pub recording_filename_template: String,
+    /// File format recordings are saved in
+    #[serde(default)]
+    pub recording_format: RecordingFormat,
+    /// Silence trimming and loudness normalization of segment recordings
+    #[serde(default)]
+    pub recording_processing: RecordingProcessingConfig,
+    /// Speech detector thresholds
+    #[serde(default)]
+    pub vad: SpeechDetectorConfig,
+    /// Echo cancellation tuning
+    #[serde(default)]
+    pub aec: AecSettings,
+    /// Spectrogram resolution and visualization rate
+    #[serde(default)]
+    pub spectrogram: SpectrogramSettings,
+    /// Address for the HTTP API (e.g. "127.0.0.1:7878"); disabled when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub http_address: Option<String>,
+    /// Token the HTTP API requires as `Authorization: Bearer <token>`; needed
+    /// to serve it on anything but a loopback address
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub http_token: Option<String>,
+    /// Base URL webhook receivers reach the HTTP API at (e.g.
+    /// "https://stt.example.com"); webhooks carry no links when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub http_public_url: Option<String>,
+    /// Audio capture settings
+    #[serde(default)]
+    pub audio: AudioConfig,
+    /// Text post-processing applied to transcriptions
+    #[serde(default)]
+    pub post_processing: PostProcessConfig,
+    /// Thresholds for flagging low-confidence segments
+    #[serde(default)]
+    pub confidence: ConfidenceConfig,
+    /// Audible feedback cues
+    #[serde(default)]
+    pub cues: CueConfig,
+    /// Open Sound Control output
+    #[serde(default)]
+    pub osc: OscConfig,
+    /// Limits on kept recordings
+    #[serde(default)]
+    pub retention: RetentionConfig,
+    /// Recording of captured audio while speech is detected
+    #[serde(default)]
+    pub vad_recording: VadRecordingConfig,
+    /// Splitting of capture sessions into chapters
+    #[serde(default)]
+    pub chapters: ChapterConfig,
+    /// Recent audio kept for retroactive capture
+    #[serde(default)]
+    pub rolling_buffer: RollingBufferConfig,
+    /// Masking of sensitive data in transcripts
+    #[serde(default)]
+    pub redaction: RedactionConfig,
+    /// Privacy mode; kept across restarts so capture stays off until unmuted
+    #[serde(default)]
+    pub muted: bool,
+    /// Saved settings profiles
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub profiles: Vec<Profile>,
+    /// Name of the profile last used
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active_profile: Option<String>,
+    /// Hooks run on the transcript of every capture session
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hooks: Vec<SessionHook>,
+    /// Endpoints notified of session lifecycle events
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub webhooks: Vec<WebhookConfig>,
+    /// Naming of sessions after calendar meetings
+    #[serde(default)]
+    pub calendar: CalendarConfig,
+}
+
+/// Thresholds below which transcribed segments are flagged as low confidence.
+///
+/// The defaults match the thresholds whisper's reference implementation uses to
+/// decide a segment is unreliable.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ConfidenceConfig {
+    /// Segments whose mean token log-probability is below this are flagged
+    #[serde(default = "default_min_avg_logprob")]
+    pub min_avg_logprob: f32,
+    /// Segments whose no-speech probability is above this are flagged
+    #[serde(default = "default_max_no_speech_prob")]
+    pub max_no_speech_prob: f32,
+    /// Drop flagged segments from the text instead of only flagging them
+    #[serde(default)]
+    pub suppress: bool,
+}
+
+impl ConfidenceConfig {
+    /// Check whether a segment's scores fall outside the thresholds.
+    pub fn is_low(&self, avg_logprob: f32, no_speech_prob: f32) -> bool {
+        avg_logprob < self.min_avg_logprob || no_speech_prob > self.max_no_speech_prob
+    }
+}
+
+impl Default for ConfidenceConfig {
+    fn default() -> Self {
+        Self {
+            min_avg_logprob: default_min_avg_logprob(),
+            max_no_speech_prob: default_max_no_speech_prob(),
+            suppress: false,
+        }
+    }
+}
+
+fn default_min_avg_logprob() -> f32 {
+    -1.0
+}
+
+fn default_max_no_speech_prob() -> f32 {
+    0.6
+}
+
+/// Short tones played through the default output device.
+///
+/// Both cue types are off by default. The `feedback_cue` event is sent to
+/// clients regardless, so the GUI can show a visual indicator instead.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CueConfig {
+    /// Beep when push-to-talk engages (rising) and disengages (falling)
+    #[serde(default)]
+    pub ptt: bool,
+    /// Blip when a transcription completes
+    #[serde(default)]
+    pub transcription: bool,
+    /// Cue loudness from 0.0 to 1.0
+    #[serde(default = "default_cue_volume")]
+    pub volume: f32,
+}
+
+impl Default for CueConfig {
+    fn default() -> Self {
+        Self {
+            ptt: false,
+            transcription: false,
+            volume: default_cue_volume(),
+        }
+    }
+}
+
+fn default_cue_volume() -> f32 {
+    0.3
+}
+
+/// Limits on how much recorded audio is kept.
+///
+/// Only recordings of transcriptions in the history are removed, oldest
+/// first, so other files in the recordings directory are never touched. No
+/// limit is set by default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetentionConfig {
+    /// Largest total size of recordings, in megabytes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_size_mb: Option<u64>,
+    /// Recordings older than this many days are removed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_age_days: Option<u32>,
+    /// Keep the transcript in the history when its recording is removed
+    #[serde(default = "default_keep_transcripts")]
+    pub keep_transcripts: bool,
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        Self {
+            max_size_mb: None,
+            max_age_days: None,
+            keep_transcripts: default_keep_transcripts(),
+        }
+    }
+}
+
+fn default_keep_transcripts() -> bool {
+    true
+}
+
+/// Processing applied to speech segments before they are saved and transcribed.
+///
+/// Both steps are off by default. Trimming happens before transcription so
+/// word timings stay in sync with the saved file.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RecordingProcessingConfig {
+    /// Cut leading and trailing audio quieter than `silence_threshold_db`
+    #[serde(default)]
+    pub trim_silence: bool,
+    /// Level below which audio counts as silence, in dBFS
+    #[serde(default = "default_silence_threshold_db")]
+    pub silence_threshold_db: f32,
+    /// Adjust the gain so each segment has the loudness `target_lufs`
+    #[serde(default)]
+    pub normalize: bool,
+    /// Integrated loudness normalized segments get, in LUFS (EBU R128 uses -23)
+    #[serde(default = "default_target_lufs")]
+    pub target_lufs: f32,
+}
+
+impl Default for RecordingProcessingConfig {
+    fn default() -> Self {
+        Self {
+            trim_silence: false,
+            silence_threshold_db: default_silence_threshold_db(),
+            normalize: false,
+            target_lufs: default_target_lufs(),
+        }
+    }
+}
+
+fn default_silence_threshold_db() -> f32 {
+    -50.0
+}
+
+fn default_target_lufs() -> f32 {
+    -23.0
+}
+
+/// When capture sessions are split into chapters.
+///
+/// A new chapter starts after a pause of `silence_secs`, and after
+/// `interval_mins` of the current chapter. Zero turns either split off; only
+/// the silence split is on by default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChapterConfig {
+    /// Length of pause that starts a new chapter, in seconds
+    #[serde(default = "default_chapter_silence_secs")]
+    pub silence_secs: u32,
+    /// Longest chapter, in minutes
+    #[serde(default)]
+    pub interval_mins: u32,
+}
+
+impl Default for ChapterConfig {
+    fn default() -> Self {
+        Self {
+            silence_secs: default_chapter_silence_secs(),
+            interval_mins: 0,
+        }
+    }
+}
+
+fn default_chapter_silence_secs() -> u32 {
+    30
+}
+
+/// How much recent capture audio is kept for retroactive capture.
+///
+/// Zero turns the buffer off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RollingBufferConfig {
+    /// Audio kept, in seconds
+    #[serde(default = "default_rolling_buffer_secs")]
+    pub secs: u32,
+}
+
+impl Default for RollingBufferConfig {
+    fn default() -> Self {
+        Self {
+            secs: default_rolling_buffer_secs(),
+        }
+    }
+}
+
+fn default_rolling_buffer_secs() -> u32 {
+    60
+}
+
+/// Recording of the captured audio only while speech is detected.
+///
+/// Each utterance is kept with `pre_roll_ms` of audio from before speech was
+/// detected and `post_roll_ms` after it ended. Off by default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VadRecordingConfig {
+    /// Whether recordings are written
+    #[serde(default)]
+    pub enabled: bool,
+    /// Whether utterances get their own files
+    #[serde(default)]
+    pub split: VadRecordingSplit,
+    /// Audio kept from before speech was detected, in milliseconds
+    #[serde(default = "default_pre_roll_ms")]
+    pub pre_roll_ms: u32,
+    /// Audio kept after speech ends, in milliseconds
+    #[serde(default = "default_post_roll_ms")]
+    pub post_roll_ms: u32,
+}
+
+impl Default for VadRecordingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            split: VadRecordingSplit::default(),
+            pre_roll_ms: default_pre_roll_ms(),
+            post_roll_ms: default_post_roll_ms(),
+        }
+    }
+}
+
+fn default_pre_roll_ms() -> u32 {
+    500
+}
+
+fn default_post_roll_ms() -> u32 {
+    1000
+}
+
+/// How voice-activated recordings are split into files.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VadRecordingSplit {
+    /// One file per utterance
+    #[default]
+    Utterance,
+    /// One file per capture session, with the silence between utterances left out
+    Session,
+}
+
+impl VadRecordingSplit {
+    pub const ALL: [VadRecordingSplit; 2] =
+        [VadRecordingSplit::Utterance, VadRecordingSplit::Session];
+
+    /// Name used in settings.
+    pub fn name(&self) -> &'static str {
+        match self {
+            VadRecordingSplit::Utterance => "utterance",
+            VadRecordingSplit::Session => "session",
+        }
+    }
+
+    /// Parse a split name as used in settings.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|split| split.name().eq_ignore_ascii_case(name))
+    }
+}
+
+/// Open Sound Control output for driving avatars and overlays from speech.
+///
+/// Speech start/end and transcripts are sent as UDP OSC messages under
+/// `prefix`; see the `osc` module for the message layout.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OscConfig {
+    /// Whether messages are sent
+    #[serde(default)]
+    pub enabled: bool,
+    /// Host and UDP port messages are sent to (VRChat listens on 9000)
+    #[serde(default = "default_osc_address")]
+    pub address: String,
+    /// Address prefix of FlowSTT's own messages
+    #[serde(default = "default_osc_prefix")]
+    pub prefix: String,
+    /// Also send transcripts to VRChat's chatbox
+    #[serde(default)]
+    pub chatbox: bool,
+}
+
+impl Default for OscConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            address: default_osc_address(),
+            prefix: default_osc_prefix(),
+            chatbox: false,
+        }
+    }
+}
+
+fn default_osc_address() -> String {
+    "127.0.0.1:9000".to_string()
+}
+
+fn default_osc_prefix() -> String {
+    "/flowstt".to_string()
+}
+
+/// A session lifecycle event sent to webhooks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEvent {
+    /// Capture started
+    SessionStarted,
+    /// Capture stopped and everything said before was transcribed
+    SessionEnded,
+    /// Capture or transcription failed
+    Error,
+}
+
+impl WebhookEvent {
+    /// Name used in settings and payloads.
+    pub fn name(&self) -> &'static str {
+        match self {
+            WebhookEvent::SessionStarted => "session_started",
+            WebhookEvent::SessionEnded => "session_ended",
+            WebhookEvent::Error => "error",
+        }
+    }
+}
+
+/// An HTTP endpoint notified of session lifecycle events; see the `webhooks`
+/// module for the payloads and signing.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WebhookConfig {
+    /// URL events are POSTed to
+    pub url: String,
+    /// Events to send (all of them when empty)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub events: Vec<WebhookEvent>,
+    /// Key the body is signed with; unsigned when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+    /// Times a failed delivery is retried before the event is dropped
+    #[serde(default = "default_webhook_retries")]
+    pub max_retries: u32,
+}
+
+impl WebhookConfig {
+    /// Whether `event` is sent to this webhook.
+    pub fn subscribes(&self, event: WebhookEvent) -> bool {
+        self.events.is_empty() || self.events.contains(&event)
+    }
+}
+
+fn default_webhook_retries() -> u32 {
+    5
+}
+
+/// Where the meetings sessions are named after are read from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum CalendarSource {
+    /// An iCalendar (.ics) file
+    File { path: PathBuf },
+    /// An iCalendar feed, such as a calendar's secret address; `webcal://`
+    /// URLs are fetched over HTTPS
+    Url { url: String },
+    /// A CalDAV calendar collection
+    Caldav {
+        url: String,
+        /// User name for basic authentication
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        username: Option<String>,
+        /// Password or app password for basic authentication
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        password: Option<String>,
+    },
+}
+
+/// Naming of saved transcriptions after the calendar meeting they overlap.
+///
+/// The meeting's summary, categories and attendees become the session's title,
+/// tags and participants; see the `calendar` module. Off unless a source is set.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CalendarConfig {
+    /// Calendar to read
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<CalendarSource>,
+    /// Minutes between re-reads of the calendar
+    #[serde(default = "default_calendar_refresh_mins")]
+    pub refresh_mins: u32,
+}
+
+impl Default for CalendarConfig {
+    fn default() -> Self {
+        Self {
+            source: None,
+            refresh_mins: default_calendar_refresh_mins(),
+        }
+    }
+}
+
+fn default_calendar_refresh_mins() -> u32 {
+    15
+}
+
+/// Masking of sensitive data in transcripts.
+///
+/// Detected emails, phone numbers, card numbers and matches of `patterns` are
+/// replaced with a placeholder such as `[email]` in the channels listed in
+/// `channels`; the rest see the text as transcribed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RedactionConfig {
+    /// Where redacted text is used (none by default, which turns redaction off)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub channels: Vec<RedactionChannel>,
+    /// Mask email addresses, written or spoken ("jane at example dot com")
+    #[serde(default = "default_detector_enabled")]
+    pub emails: bool,
+    /// Mask phone numbers
+    #[serde(default = "default_detector_enabled")]
+    pub phone_numbers: bool,
+    /// Mask credit card numbers (digit runs that pass the Luhn check)
+    #[serde(default = "default_detector_enabled")]
+    pub card_numbers: bool,
+    /// Additional regular expressions whose matches are masked
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub patterns: Vec<String>,
+}
+
+impl Default for RedactionConfig {
+    fn default() -> Self {
+        Self {
+            channels: Vec::new(),
+            emails: default_detector_enabled(),
+            phone_numbers: default_detector_enabled(),
+            card_numbers: default_detector_enabled(),
+            patterns: Vec::new(),
+        }
+    }
+}
+
+fn default_detector_enabled() -> bool {
+    true
+}
+
+/// A destination for transcribed text that redaction can apply to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RedactionChannel {
+    /// Saved transcription history and watch-folder sidecar files
+    History,
+    /// Typed or copied output and the dictation document
+    Output,
+    /// Events sent to clients, including OSC and the HTTP API
+    Events,
+    /// Session hooks and webhooks
+    Hooks,
+}
+
+impl RedactionChannel {
+    pub const ALL: [RedactionChannel; 4] = [
+        RedactionChannel::History,
+        RedactionChannel::Output,
+        RedactionChannel::Events,
+        RedactionChannel::Hooks,
+    ];
+
+    /// Name used in settings.
+    pub fn name(&self) -> &'static str {
+        match self {
+            RedactionChannel::History => "history",
+            RedactionChannel::Output => "output",
+            RedactionChannel::Events => "events",
+            RedactionChannel::Hooks => "hooks",
+        }
+    }
+
+    /// Parse a channel name as used in settings.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|channel| channel.name().eq_ignore_ascii_case(name))
+    }
+}
+
+/// Text post-processing steps, read at startup; the profile can also be
+/// changed at runtime.
+///
+/// Steps run in field order: numbers are normalized, profanity is masked,
+/// punctuation is restored and the custom replacements are applied last so they
+/// can override the built-in steps. The technical profile takes the place of
+/// number normalization and punctuation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PostProcessConfig {
+    /// How dictated text is read: as prose, or as code and commands
+    #[serde(default)]
+    pub profile: TextProfile,
+    /// Convert spelled-out numbers to digits ("twenty three" -> "23")
+    #[serde(default)]
+    pub normalize_numbers: bool,
+    /// Mask profane words, keeping their first letter ("d***")
+    #[serde(default)]
+    pub mask_profanity: bool,
+    /// Additional words to mask alongside the built-in list
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub profanity_words: Vec<String>,
+    /// Output modes in which missing capitalization and sentence punctuation are
+    /// restored (dictation by default, where short push-to-talk segments often
+    /// come back unpunctuated)
+    #[serde(default = "default_punctuation_modes")]
+    pub restore_punctuation: Vec<OutputMode>,
+    /// Regex replacements applied in order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub replacements: Vec<TextReplacement>,
+}
+
+impl Default for PostProcessConfig {
+    fn default() -> Self {
+        Self {
+            profile: TextProfile::default(),
+            normalize_numbers: false,
+            mask_profanity: false,
+            profanity_words: Vec::new(),
+            restore_punctuation: default_punctuation_modes(),
+            replacements: Vec::new(),
+        }
+    }
+}
+
+fn default_punctuation_modes() -> Vec<OutputMode> {
+    vec![OutputMode::Type]
+}
+
+/// Post-processing profile selecting how dictated text is read.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TextProfile {
+    /// Ordinary speech
+    #[default]
+    Prose,
+    /// Code and terminal input: spoken symbols ("open paren", "underscore")
+    /// become characters, numbers become digits, and punctuation is never
+    /// added; "literal" keeps the next word as spoken
+    Technical,
+}
+
+impl TextProfile {
+    pub const ALL: [TextProfile; 2] = [TextProfile::Prose, TextProfile::Technical];
+
+    /// Name used in settings.
+    pub fn name(&self) -> &'static str {
+        match self {
+            TextProfile::Prose => "prose",
+            TextProfile::Technical => "technical",
+        }
+    }
+
+    /// Parse a profile name as used in settings.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|profile| profile.name().eq_ignore_ascii_case(name))
+    }
+}
+
+/// A regex replacement applied to transcribed text.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TextReplacement {
+    /// Regular expression to search for
+    pub pattern: String,
+    /// Replacement text; `$1` etc. refer to capture groups
+    pub replacement: String,
+}
+
+/// Audio capture settings. The backend and network sources are read once at
+/// startup; the buffer duration applies when capture next starts.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AudioConfig {
+    /// Capture backend to use
+    #[serde(default)]
+    pub backend: AudioBackendKind,
+    /// Requested capture buffer duration in milliseconds; `None` keeps each
+    /// backend's default. Shorter buffers lower latency at the cost of CPU.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub buffer_ms: Option<u32>,
+    /// How input devices are opened on Windows; exclusive and raw modes fall
+    /// back to shared mode when the device refuses them
+    #[serde(default)]
+    pub capture_mode: CaptureMode,
+    /// Sample rate to capture at; `None` captures at 48 kHz. At 16 kHz a
+    /// single source is delivered as 16 kHz mono by backends that can convert
+    /// to it (PipeWire and WASAPI), skipping the resampling and stereo buffers
+    /// in between; other captures stay at 48 kHz.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sample_rate: Option<u32>,
+    /// Channels captured from multichannel devices, by device ID; other
+    /// devices are captured with their own channels
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub channels: BTreeMap<String, ChannelSelection>,
+    /// Network streams listed as additional input devices
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub network_sources: Vec<NetworkSource>,
+    /// WAV playback for the mock backend
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mock: Option<MockAudioConfig>,
+}
+
+/// How WASAPI opens input devices.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CaptureMode {
+    /// Through the audio engine, with the device's effects
+    #[default]
+    Shared,
+    /// Through the audio engine, bypassing the device's effects (APOs) such
+    /// as OEM noise suppression that interfere with echo cancellation
+    Raw,
+    /// Directly from the device, bypassing the audio engine for the lowest
+    /// latency; other applications can't use the microphone meanwhile
+    Exclusive,
+}
+
+impl CaptureMode {
+    pub const ALL: [CaptureMode; 3] = [
+        CaptureMode::Shared,
+        CaptureMode::Raw,
+        CaptureMode::Exclusive,
+    ];
+
+    /// Name used in settings.
+    pub fn name(&self) -> &'static str {
+        match self {
+            CaptureMode::Shared => "shared",
+            CaptureMode::Raw => "raw",
+            CaptureMode::Exclusive => "exclusive",
+        }
+    }
+
+    /// Parse a mode name as used in settings.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|mode| mode.name().eq_ignore_ascii_case(name))
+    }
+}
+
+/// Mock backend settings: WAV files played back as capture devices, for
+/// testing without audio hardware. `FLOWSTT_MOCK_AUDIO` and
+/// `FLOWSTT_MOCK_AUDIO_SPEED` override the directory and speed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MockAudioConfig {
+    /// Directory of WAV files, each listed as an input and a system device
+    pub fixtures_dir: PathBuf,
+    /// Playback speed as a multiple of real time
+    pub speed: u32,
+    /// Start files over when they end instead of continuing with silence
+    pub looped: bool,
+}
+
+impl Default for MockAudioConfig {
+    fn default() -> Self {
+        Self {
+            fixtures_dir: PathBuf::new(),
+            speed: 1,
+            looped: false,
+        }
+    }
+}
+
+/// A network audio stream received on a local UDP port.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NetworkSource {
+    /// Display name
+    pub name: String,
+    /// Local address to listen on (e.g. "0.0.0.0:5004")
+    pub address: String,
+    /// Packet framing
+    #[serde(default)]
+    pub protocol: NetworkProtocol,
+    /// Payload encoding
+    #[serde(default)]
+    pub codec: NetworkCodec,
+    /// Sample rate of PCM payloads (Opus is always decoded at 48kHz)
+    #[serde(default = "default_network_sample_rate")]
+    pub sample_rate: u32,
+    /// Channel count (1 or 2)
+    #[serde(default = "default_network_channels")]
+    pub channels: u16,
+}
+
+/// Framing of network audio packets.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NetworkProtocol {
+    /// RTP packets; PCM payloads are big-endian L16 as in RFC 3551
+    #[default]
+    Rtp,
+    /// Bare UDP datagrams; PCM payloads are little-endian s16
+    Udp,
+}
+
+/// Encoding of network audio payloads.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NetworkCodec {
+    /// 16-bit linear PCM
+    #[default]
+    Pcm,
+    /// Opus, one packet per datagram
+    Opus,
+}
+
+fn default_network_sample_rate() -> u32 {
+    48000
+}
+
+fn default_network_channels() -> u16 {
+    1
+}
+
+/// Audio capture backend selection.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AudioBackendKind {
+    /// Use the platform's preferred backend
+    #[default]
+    Auto,
+    /// PipeWire (Linux)
+    PipeWire,
+    /// PulseAudio (Linux)
+    Pulse,
+    /// ALSA, input devices only (Linux)
+    Alsa,
+    /// WAV files from `audio.mock`, on any platform
+    Mock,
+}
+
+fn default_language() -> String {
+    DEFAULT_LANGUAGE.to_string()
+}
+
+fn default_filename_template() -> String {
+    DEFAULT_FILENAME_TEMPLATE.to_string()
+}
+
+fn default_use_gpu() -> bool {
+    true
 }
 
 impl Default for Config {
@@ -27,6 +898,44 @@ impl Default for Config {
         Self {
             transcription_mode: TranscriptionMode::default(),
             ptt_key: KeyCode::default(),
+            hotkeys: Vec::new(),
+            language: default_language(),
+            translate: false,
+            output_mode: OutputMode::default(),
+            output_template: None,
+            latency_preset: LatencyPreset::default(),
+            use_gpu: default_use_gpu(),
+            gpu_device: 0,
+            watch_folders: Vec::new(),
+            voice_commands: Vec::new(),
+            recordings_dir: None,
+            model_path: None,
+            model_name: None,
+            recording_filename_template: default_filename_template(),
+            recording_format: RecordingFormat::default(),
+            recording_processing: RecordingProcessingConfig::default(),
+            vad: SpeechDetectorConfig::default(),
+            aec: AecSettings::default(),
+            spectrogram: SpectrogramSettings::default(),
+            http_address: None,
+            http_token: None,
+            http_public_url: None,
+            audio: AudioConfig::default(),
+            post_processing: PostProcessConfig::default(),
+            confidence: ConfidenceConfig::default(),
+            cues: CueConfig::default(),
+            osc: OscConfig::default(),
+            retention: RetentionConfig::default(),
+            vad_recording: VadRecordingConfig::default(),
+            chapters: ChapterConfig::default(),
+            rolling_buffer: RollingBufferConfig::default(),
+            redaction: RedactionConfig::default(),
+            muted: false,
+            profiles: Vec::new(),
+            active_profile: None,
+            hooks: Vec::new(),
+            webhooks: Vec::new(),
+            calendar: CalendarConfig::default(),
         }
     }
 }
@@ -104,12 +1013,14 @@ impl Config {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use flowstt_common::{HookTarget, HotkeyAction, SpectrogramWindow};
 
     #[test]
     fn test_default_config() {
         let config = Config::default();
         assert_eq!(config.transcription_mode, TranscriptionMode::default());
         assert_eq!(config.ptt_key, KeyCode::default());
+        assert_eq!(config.language, DEFAULT_LANGUAGE);
     }
 
     #[test]
@@ -117,6 +1028,144 @@ mod tests {
         let config = Config {
             transcription_mode: TranscriptionMode::Automatic,
             ptt_key: KeyCode::F13,
+            hotkeys: vec![HotkeyBinding {
+                key: KeyCode::F14,
+                action: HotkeyAction::PushToTalkClipboard,
+            }],
+            language: "auto".to_string(),
+            translate: true,
+            output_mode: OutputMode::Type,
+            output_template: Some("[{time}] {text}\\n".to_string()),
+            latency_preset: LatencyPreset::Accurate,
+            use_gpu: false,
+            gpu_device: 1,
+            watch_folders: vec![PathBuf::from("/tmp/recordings")],
+            voice_commands: vec![VoiceCommand {
+                phrase: "stop recording".to_string(),
+                suppress_text: true,
+            }],
+            recordings_dir: Some(PathBuf::from("/tmp/recordings")),
+            model_path: Some(PathBuf::from("/models/ggml-small-q5_1.bin")),
+            model_name: Some("small.en-q8_0".to_string()),
+            recording_filename_template: "{session}-{time}".to_string(),
+            recording_format: RecordingFormat::Flac,
+            recording_processing: RecordingProcessingConfig {
+                trim_silence: true,
+                silence_threshold_db: -45.0,
+                normalize: true,
+                target_lufs: -16.0,
+            },
+            vad: SpeechDetectorConfig {
+                voiced_threshold_db: -48.0,
+                hold_ms: 500,
+                ..Default::default()
+            },
+            aec: AecSettings {
+                initial_delay_ms: 180,
+                filter_length_ms: 100,
+            },
+            spectrogram: SpectrogramSettings {
+                fft_size: 2048,
+                window: SpectrogramWindow::Blackman,
+                emit_rate_hz: 60,
+            },
+            http_address: Some("127.0.0.1:7878".to_string()),
+            http_token: Some("t0ken".to_string()),
+            http_public_url: Some("http://192.168.1.20:7878".to_string()),
+            audio: AudioConfig {
+                backend: AudioBackendKind::Alsa,
+                buffer_ms: Some(20),
+                capture_mode: CaptureMode::Raw,
+                sample_rate: Some(16000),
+                channels: BTreeMap::from([(
+                    "alsa_input.usb-interface".to_string(),
+                    ChannelSelection {
+                        channels: vec![3, 4],
+                    },
+                )]),
+                network_sources: Vec::new(),
+                mock: None,
+            },
+            post_processing: PostProcessConfig {
+                profile: TextProfile::Technical,
+                normalize_numbers: true,
+                mask_profanity: true,
+                profanity_words: vec!["heck".to_string()],
+                restore_punctuation: vec![OutputMode::EventsOnly, OutputMode::Type],
+                replacements: vec![TextReplacement {
+                    pattern: r"\bflow stt\b".to_string(),
+                    replacement: "FlowSTT".to_string(),
+                }],
+            },
+            confidence: ConfidenceConfig {
+                min_avg_logprob: -0.8,
+                max_no_speech_prob: 0.5,
+                suppress: true,
+            },
+            cues: CueConfig {
+                ptt: true,
+                transcription: false,
+                volume: 0.5,
+            },
+            osc: OscConfig {
+                enabled: true,
+                address: "192.168.1.20:9000".to_string(),
+                prefix: "/stt".to_string(),
+                chatbox: true,
+            },
+            retention: RetentionConfig {
+                max_size_mb: Some(2048),
+                max_age_days: Some(30),
+                keep_transcripts: false,
+            },
+            vad_recording: VadRecordingConfig {
+                enabled: true,
+                split: VadRecordingSplit::Session,
+                pre_roll_ms: 250,
+                post_roll_ms: 2000,
+            },
+            chapters: ChapterConfig {
+                silence_secs: 0,
+                interval_mins: 15,
+            },
+            rolling_buffer: RollingBufferConfig { secs: 120 },
+            redaction: RedactionConfig {
+                channels: vec![RedactionChannel::History, RedactionChannel::Hooks],
+                emails: true,
+                phone_numbers: false,
+                card_numbers: true,
+                patterns: vec![r"\bACCT-\d+\b".to_string()],
+            },
+            muted: true,
+            profiles: vec![Profile {
+                name: "meetings".to_string(),
+                source1_id: Some("mic".to_string()),
+                aec_enabled: Some(true),
+                output_mode: Some(OutputMode::EventsOnly),
+                ..Default::default()
+            }],
+            active_profile: Some("meetings".to_string()),
+            hooks: vec![SessionHook {
+                name: "summarize".to_string(),
+                target: HookTarget::Http {
+                    url: "http://127.0.0.1:8080/summarize".to_string(),
+                },
+                summarize: true,
+            }],
+            webhooks: vec![WebhookConfig {
+                url: "https://hooks.example.com/flowstt".to_string(),
+                events: vec![WebhookEvent::SessionEnded],
+                secret: Some("s3cret".to_string()),
+                max_retries: 3,
+            }],
+            calendar: CalendarConfig {
+                source: Some(CalendarSource::Caldav {
+                    url: "https://dav.example.com/calendars/ana/work/".to_string(),
+                    username: Some("ana".to_string()),
+                    password: Some("app-password".to_string()),
+                }),
+                refresh_mins: 5,
+            },
         };
 
         let json = serde_json::to_string(&config).unwrap();
@@ -124,5 +1173,154 @@ mod tests {
 
         assert_eq!(parsed.transcription_mode, TranscriptionMode::Automatic);
         assert_eq!(parsed.ptt_key, KeyCode::F13);
+        assert_eq!(parsed.hotkeys, config.hotkeys);
+        assert_eq!(parsed.language, "auto");
+        assert!(parsed.translate);
+        assert_eq!(parsed.output_mode, OutputMode::Type);
+        assert_eq!(parsed.output_template, config.output_template);
+        assert_eq!(parsed.latency_preset, LatencyPreset::Accurate);
+        assert!(!parsed.use_gpu);
+        assert_eq!(parsed.gpu_device, 1);
+        assert_eq!(parsed.watch_folders, vec![PathBuf::from("/tmp/recordings")]);
+        assert_eq!(parsed.voice_commands, config.voice_commands);
+        assert_eq!(
+            parsed.recordings_dir,
+            Some(PathBuf::from("/tmp/recordings"))
+        );
+        assert_eq!(
+            parsed.model_path,
+            Some(PathBuf::from("/models/ggml-small-q5_1.bin"))
+        );
+        assert_eq!(parsed.model_name.as_deref(), Some("small.en-q8_0"));
+        assert_eq!(parsed.recording_filename_template, "{session}-{time}");
+        assert_eq!(parsed.recording_format, RecordingFormat::Flac);
+        assert_eq!(parsed.recording_processing, config.recording_processing);
+        assert_eq!(parsed.vad, config.vad);
+        assert_eq!(parsed.aec, config.aec);
+        assert_eq!(parsed.spectrogram, config.spectrogram);
+        assert_eq!(parsed.http_address.as_deref(), Some("127.0.0.1:7878"));
+        assert_eq!(parsed.http_token.as_deref(), Some("t0ken"));
+        assert_eq!(
+            parsed.http_public_url.as_deref(),
+            Some("http://192.168.1.20:7878")
+        );
+        assert_eq!(parsed.audio, config.audio);
+        assert_eq!(parsed.post_processing, config.post_processing);
+        assert_eq!(parsed.confidence, config.confidence);
+        assert_eq!(parsed.cues, config.cues);
+        assert_eq!(parsed.osc, config.osc);
+        assert_eq!(parsed.retention, config.retention);
+        assert_eq!(parsed.vad_recording, config.vad_recording);
+        assert_eq!(parsed.chapters, config.chapters);
+        assert_eq!(parsed.rolling_buffer, config.rolling_buffer);
+        assert_eq!(parsed.redaction, config.redaction);
+        assert!(parsed.muted);
+        assert_eq!(parsed.profiles, config.profiles);
+        assert_eq!(parsed.active_profile.as_deref(), Some("meetings"));
+        assert_eq!(parsed.hooks, config.hooks);
+        assert_eq!(parsed.webhooks, config.webhooks);
+        assert_eq!(parsed.calendar, config.calendar);
+    }
+
+    #[test]
+    fn test_config_missing_fields_use_defaults() {
+        let parsed: Config = serde_json::from_str(r#"{"ptt_key":"f14"}"#).unwrap();
+        assert_eq!(parsed.ptt_key, KeyCode::F14);
+        assert!(parsed.hotkeys.is_empty());
+        assert_eq!(parsed.language, DEFAULT_LANGUAGE);
+        assert!(!parsed.translate);
+        assert_eq!(parsed.latency_preset, LatencyPreset::Balanced);
+        assert!(parsed.use_gpu);
+        assert_eq!(
+            parsed.recording_filename_template,
+            DEFAULT_FILENAME_TEMPLATE
+        );
+        assert_eq!(parsed.recording_format, RecordingFormat::Wav);
+        assert_eq!(
+            parsed.recording_processing,
+            RecordingProcessingConfig::default()
+        );
+        assert_eq!(parsed.vad, SpeechDetectorConfig::default());
+        assert_eq!(parsed.aec, AecSettings::default());
+        assert_eq!(parsed.spectrogram, SpectrogramSettings::default());
+        assert_eq!(parsed.audio.backend, AudioBackendKind::Auto);
+        assert_eq!(parsed.post_processing, PostProcessConfig::default());
+        assert_eq!(
+            parsed.post_processing.restore_punctuation,
+            vec![OutputMode::Type]
+        );
+        assert_eq!(parsed.post_processing.profile, TextProfile::Prose);
+        assert_eq!(parsed.confidence, ConfidenceConfig::default());
+        assert!(parsed.confidence.is_low(-1.2, 0.1));
+        assert!(parsed.confidence.is_low(-0.3, 0.9));
+        assert!(!parsed.confidence.is_low(-0.3, 0.1));
+        assert_eq!(parsed.cues, CueConfig::default());
+        assert_eq!(parsed.osc, OscConfig::default());
+        assert_eq!(parsed.output_template, None);
+        assert!(parsed.retention.keep_transcripts);
+        assert_eq!(parsed.retention.max_size_mb, None);
+        assert_eq!(parsed.vad_recording, VadRecordingConfig::default());
+        assert_eq!(parsed.chapters.silence_secs, 30);
+        assert_eq!(parsed.rolling_buffer.secs, 60);
+        assert_eq!(parsed.redaction, RedactionConfig::default());
+        assert!(parsed.redaction.phone_numbers);
+        assert!(!parsed.muted);
+        assert!(parsed.profiles.is_empty());
+        assert!(parsed.hooks.is_empty());
+        assert!(parsed.webhooks.is_empty());
+        assert_eq!(parsed.calendar, CalendarConfig::default());
+
+        let parsed: Config = serde_json::from_str(
+            r#"{"calendar":{"source":{"type":"file","path":"/home/ana/work.ics"}}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            parsed.calendar.source,
+            Some(CalendarSource::File {
+                path: PathBuf::from("/home/ana/work.ics")
+            })
+        );
+        assert_eq!(parsed.calendar.refresh_mins, 15);
+
+        // A webhook without an event list gets every event
+        let parsed: Config =
+            serde_json::from_str(r#"{"webhooks":[{"url":"https://example.com/hook"}]}"#).unwrap();
+        let webhook = &parsed.webhooks[0];
+        assert_eq!(webhook.max_retries, 5);
+        assert!(webhook.subscribes(WebhookEvent::SessionStarted));
+        assert!(webhook.subscribes(WebhookEvent::Error));
+
+        // Thresholds left out of a partial VAD section keep their defaults
+        let parsed: Config = serde_json::from_str(r#"{"vad":{"hold_ms":800}}"#).unwrap();
+        assert_eq!(parsed.vad.hold_ms, 800);
+        assert_eq!(parsed.vad.voiced_threshold_db, -42.0);
+
+        let parsed: Config = serde_json::from_str(r#"{"audio":{"backend":"alsa"}}"#).unwrap();
+        assert_eq!(parsed.audio.backend, AudioBackendKind::Alsa);
+        assert_eq!(parsed.audio.buffer_ms, None);
+        assert_eq!(parsed.audio.capture_mode, CaptureMode::Shared);
+        assert_eq!(parsed.audio.sample_rate, None);
+        assert!(parsed.audio.channels.is_empty());
+        assert!(parsed.audio.network_sources.is_empty());
+
+        let parsed: Config = serde_json::from_str(
+            r#"{"audio":{"backend":"mock","mock":{"fixtures_dir":"/tmp/fixtures"}}}"#,
+        )
+        .unwrap();
+        assert_eq!(parsed.audio.backend, AudioBackendKind::Mock);
+        let mock = parsed.audio.mock.unwrap();
+        assert_eq!(mock.fixtures_dir, PathBuf::from("/tmp/fixtures"));
+        assert_eq!(mock.speed, 1);
+        assert!(!mock.looped);
+
+        let parsed: Config = serde_json::from_str(
+            r#"{"audio":{"network_sources":[{"name":"Pi","address":"0.0.0.0:5004"}]}}"#,
+        )
+        .unwrap();
+        let source = &parsed.audio.network_sources[0];
+        assert_eq!(source.protocol, NetworkProtocol::Rtp);
+        assert_eq!(source.codec, NetworkCodec::Pcm);
+        assert_eq!(source.sample_rate, 48000);
+        assert_eq!(source.channels, 1);
     }
 }
@@ -0,0 +1,151 @@
+//! Audible feedback cues.
+//!
+//! Short synthesized tones played through the default output device when
+//! push-to-talk engages or disengages and when a transcription completes.
+//! Every cue is also broadcast as a `feedback_cue` event, whether or not it is
+//! audible, so clients can flash an indicator.
+
+use std::f32::consts::TAU;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{FromSample, Sample, SampleFormat, SizedSample, StreamConfig};
+use flowstt_common::ipc::{EventType, Response};
+use flowstt_common::FeedbackCue;
+use tracing::{debug, warn};
+
+use crate::config::CueConfig;
+use crate::ipc::broadcast_event;
+
+/// Fade applied to both ends of a tone to avoid clicks
+const FADE_SECS: f32 = 0.005;
+
+/// Extra time the stream stays open so the device buffer drains
+const DRAIN_TIME: Duration = Duration::from_millis(100);
+
+static CONFIG: Mutex<Option<CueConfig>> = Mutex::new(None);
+
+/// Replace the cue settings.
+pub fn set_config(config: CueConfig) {
+    *CONFIG.lock().unwrap() = Some(config);
+}
+
+/// Announce `cue` to clients and play it if that kind of cue is enabled.
+pub fn emit(cue: FeedbackCue) {
+    broadcast_event(Response::Event {
+        event: EventType::FeedbackCue { cue },
+    });
+
+    let config = CONFIG.lock().unwrap().unwrap_or_default();
+    let enabled = match cue {
+        FeedbackCue::PttStart | FeedbackCue::PttStop => config.ptt,
+        FeedbackCue::TranscriptionDone => config.transcription,
+    };
+    if !enabled || config.volume <= 0.0 {
+        return;
+    }
+
+    // Playback blocks until the tone ends, so it gets its own thread
+    thread::spawn(move || {
+        if let Err(e) = play(cue, config.volume.min(1.0)) {
+            warn!("Failed to play feedback cue: {}", e);
+        }
+    });
+}
+
+/// Frequency sweep (start Hz, end Hz) and length of each cue.
+fn tone(cue: FeedbackCue) -> (f32, f32, f32) {
+    match cue {
+        FeedbackCue::PttStart => (660.0, 990.0, 0.09),
+        FeedbackCue::PttStop => (990.0, 660.0, 0.09),
+        FeedbackCue::TranscriptionDone => (1320.0, 1320.0, 0.05),
+    }
+}
+
+/// Mono samples of `cue` at `sample_rate`, peaking at `volume`.
+fn render(cue: FeedbackCue, sample_rate: u32, volume: f32) -> Vec<f32> {
+    let (start_hz, end_hz, secs) = tone(cue);
+    let rate = sample_rate as f32;
+    let len = (secs * rate) as usize;
+    let fade = ((FADE_SECS * rate) as usize).max(1);
+
+    let mut phase = 0.0f32;
+    (0..len)
+        .map(|i| {
+            let progress = i as f32 / len as f32;
+            let hz = start_hz + (end_hz - start_hz) * progress;
+            phase = (phase + TAU * hz / rate) % TAU;
+            let envelope = (i.min(len - 1 - i) as f32 / fade as f32).min(1.0);
+            phase.sin() * envelope * volume
+        })
+        .collect()
+}
+
+fn play(cue: FeedbackCue, volume: f32) -> Result<(), String> {
+    let device = cpal::default_host()
+        .default_output_device()
+        .ok_or("No default output device")?;
+    let supported = device
+        .default_output_config()
+        .map_err(|e| format!("Failed to query output device: {}", e))?;
+    let sample_rate = supported.sample_rate().0;
+    let samples = render(cue, sample_rate, volume);
+    let duration = Duration::from_secs_f64(samples.len() as f64 / sample_rate as f64);
+
+    let config: StreamConfig = supported.config();
+    let stream = match supported.sample_format() {
+        SampleFormat::F32 => build_stream::<f32>(&device, &config, samples),
+        SampleFormat::I16 => build_stream::<i16>(&device, &config, samples),
+        SampleFormat::U16 => build_stream::<u16>(&device, &config, samples),
+        other => return Err(format!("Unsupported output sample format: {}", other)),
+    }?;
+    stream
+        .play()
+        .map_err(|e| format!("Failed to start output stream: {}", e))?;
+    debug!("Playing {:?} cue", cue);
+    thread::sleep(duration + DRAIN_TIME);
+    Ok(())
+}
+
+/// Output stream that writes `samples` to every channel, then silence.
+fn build_stream<T>(
+    device: &cpal::Device,
+    config: &StreamConfig,
+    samples: Vec<f32>,
+) -> Result<cpal::Stream, String>
+where
+    T: SizedSample + FromSample<f32>,
+{
+    let channels = config.channels as usize;
+    let mut samples = samples.into_iter();
+    device
+        .build_output_stream(
+            config,
+            move |data: &mut [T], _| {
+                for frame in data.chunks_mut(channels) {
+                    frame.fill(T::from_sample(samples.next().unwrap_or(0.0)));
+                }
+            },
+            |e| warn!("Feedback cue stream error: {}", e),
+            None,
+        )
+        .map_err(|e| format!("Failed to open output stream: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rendered_tones_fade_in_and_out() {
+        let samples = render(FeedbackCue::PttStart, 48000, 0.5);
+        assert_eq!(samples.len(), 4320);
+        assert_eq!(samples[0], 0.0);
+        assert_eq!(*samples.last().unwrap(), 0.0);
+
+        let peak = samples.iter().fold(0.0f32, |max, s| max.max(s.abs()));
+        assert!(peak > 0.45 && peak <= 0.5);
+    }
+}
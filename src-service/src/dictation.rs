@@ -0,0 +1,177 @@
+//! Continuous dictation into a running document.
+//!
+//! While dictation is active, every completed transcription is appended to a
+//! document. Text is joined across segments so sentences that whisper split
+//! in the middle read naturally, a new sentence is capitalized after `.`, `?`
+//! or `!`, and the spoken commands "new line" and "new paragraph" insert line
+//! breaks instead of being typed.
+
+use std::sync::Mutex;
+
+/// Characters that end a sentence
+const SENTENCE_TERMINATORS: &[char] = &['.', '?', '!'];
+
+/// Trailing punctuation replaced by a full stop when a break is inserted
+const CLAUSE_PUNCTUATION: &[char] = &[',', ';', ':'];
+
+/// Spoken commands and the text they insert
+const COMMANDS: &[(&[&str], &str)] = &[(&["new", "paragraph"], "\n\n"), (&["new", "line"], "\n")];
+
+/// A document built up from dictated speech.
+#[derive(Debug, Default)]
+pub struct DictationDocument {
+    text: String,
+}
+
+impl DictationDocument {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The document text so far.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Append a transcribed segment, applying spoken commands.
+    pub fn push(&mut self, segment: &str) {
+        let words: Vec<&str> = segment.split_whitespace().collect();
+        let mut i = 0;
+        while i < words.len() {
+            match match_command(&words[i..]) {
+                Some((len, insert)) => {
+                    self.insert_break(insert);
+                    i += len;
+                }
+                None => {
+                    self.push_word(words[i]);
+                    i += 1;
+                }
+            }
+        }
+    }
+
+    /// Append a word, spacing and capitalizing it from the preceding text.
+    fn push_word(&mut self, word: &str) {
+        let new_sentence = match self.text.chars().last() {
+            None | Some('\n') => true,
+            Some(c) => {
+                self.text.push(' ');
+                SENTENCE_TERMINATORS.contains(&c)
+            }
+        };
+        if new_sentence {
+            self.text.push_str(&capitalize(word));
+        } else {
+            self.text.push_str(word);
+        }
+    }
+
+    /// End the current sentence and insert a line or paragraph break.
+    ///
+    /// Consecutive breaks collapse into the larger one.
+    fn insert_break(&mut self, insert: &str) {
+        let content = self.text.trim_end_matches('\n');
+        let existing = self.text.len() - content.len();
+        let trimmed = content.trim_end_matches(CLAUSE_PUNCTUATION).len();
+        self.text.truncate(trimmed);
+        if self.text.is_empty() {
+            return;
+        }
+        if !self.text.ends_with(SENTENCE_TERMINATORS) {
+            self.text.push('.');
+        }
+        self.text.push_str(&"\n".repeat(existing.max(insert.len())));
+    }
+}
+
+/// Match a spoken command at the start of `words`.
+///
+/// Returns the number of words consumed and the text to insert.
+fn match_command(words: &[&str]) -> Option<(usize, &'static str)> {
+    COMMANDS.iter().find_map(|(phrase, insert)| {
+        let matches = words.len() >= phrase.len()
+            && phrase.iter().zip(words).all(|(expected, word)| {
+                word.trim_matches(|c: char| !c.is_alphanumeric())
+                    .eq_ignore_ascii_case(expected)
+            });
+        matches.then_some((phrase.len(), *insert))
+    })
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// The active dictation document, if dictation is running
+static DICTATION: Mutex<Option<DictationDocument>> = Mutex::new(None);
+
+/// Start a new dictation document. Returns false if dictation was already active.
+pub fn start() -> bool {
+    let mut dictation = DICTATION.lock().unwrap();
+    if dictation.is_some() {
+        return false;
+    }
+    *dictation = Some(DictationDocument::new());
+    true
+}
+
+/// Stop dictation and return the finished document.
+pub fn stop() -> Option<String> {
+    DICTATION.lock().unwrap().take().map(|doc| doc.text)
+}
+
+/// Append transcribed text if dictation is active, returning the updated document.
+pub fn append(segment: &str) -> Option<String> {
+    let mut dictation = DICTATION.lock().unwrap();
+    let doc = dictation.as_mut()?;
+    doc.push(segment);
+    Some(doc.text().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dictate(segments: &[&str]) -> String {
+        let mut doc = DictationDocument::new();
+        for segment in segments {
+            doc.push(segment);
+        }
+        doc.text().to_string()
+    }
+
+    #[test]
+    fn test_joins_segments_into_sentences() {
+        assert_eq!(
+            dictate(&["Dear team, the build", "is green.", "we can ship today"]),
+            "Dear team, the build is green. We can ship today"
+        );
+    }
+
+    #[test]
+    fn test_paragraph_and_line_commands() {
+        assert_eq!(
+            dictate(&[
+                "first point, new paragraph.",
+                "Second point",
+                "New line",
+                "third"
+            ]),
+            "First point.\n\nSecond point.\nThird"
+        );
+    }
+
+    #[test]
+    fn test_repeated_breaks_collapse() {
+        assert_eq!(
+            dictate(&["Hello. New paragraph", "new paragraph. New line world"]),
+            "Hello.\n\nWorld"
+        );
+        assert_eq!(dictate(&["new paragraph hello"]), "Hello");
+    }
+}
@@ -0,0 +1,478 @@
+//! Encoders for saved recordings.
+//!
+//! Recordings can be written as WAV, FLAC or Ogg Opus. Each encoder takes
+//! interleaved f32 samples in chunks and writes them out as it goes, so memory
+//! use does not grow with the length of a recording. Files are completed by
+//! `AudioEncoder::finish`, or when the encoder is dropped.
+
+use flacenc::bitsink::ByteSink;
+use flacenc::component::{BitRepr, StreamInfo};
+use flacenc::error::Verified;
+use flacenc::source::FrameBuf;
+use flowstt_common::RecordingFormat;
+use hound::{SampleFormat, WavSpec, WavWriter};
+use ogg::writing::{PacketWriteEndInfo, PacketWriter};
+use std::fs::File;
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::path::Path;
+
+/// FLAC recordings store 16-bit samples
+const FLAC_BITS_PER_SAMPLE: u32 = 16;
+
+/// Size of the `fLaC` marker plus the STREAMINFO block
+const FLAC_HEADER_LEN: usize = 42;
+
+/// Opus frame duration in milliseconds
+const OPUS_FRAME_MS: u32 = 20;
+
+/// Opus granule positions always count samples at 48kHz
+const OPUS_GRANULE_RATE: u32 = 48000;
+
+/// Largest Opus packet requested from the encoder
+const OPUS_MAX_PACKET: usize = 4000;
+
+/// Ogg stream serial number (each file holds a single stream)
+const OGG_SERIAL: u32 = 0x466c_6f77;
+
+/// A recording being written in one of the supported formats.
+pub enum AudioEncoder {
+    Wav(WavWriter<BufWriter<File>>),
+    Flac(FlacEncoder),
+    Opus(OpusEncoder),
+}
+
+impl AudioEncoder {
+    /// Create `path` for audio with the given sample rate and channel count.
+    pub fn create(
+        path: &Path,
+        format: RecordingFormat,
+        sample_rate: u32,
+        channels: u16,
+    ) -> Result<Self, String> {
+        let file = File::create(path).map_err(|e| format!("Failed to create {:?}: {}", path, e))?;
+        let writer = BufWriter::new(file);
+
+        match format {
+            RecordingFormat::Wav => {
+                let spec = WavSpec {
+                    channels,
+                    sample_rate,
+                    bits_per_sample: 32,
+                    sample_format: SampleFormat::Float,
+                };
+                WavWriter::new(writer, spec)
+                    .map(AudioEncoder::Wav)
+                    .map_err(|e| format!("Failed to create WAV file: {}", e))
+            }
+            RecordingFormat::Flac => {
+                FlacEncoder::new(writer, sample_rate, channels).map(AudioEncoder::Flac)
+            }
+            RecordingFormat::Opus => {
+                OpusEncoder::new(writer, sample_rate, channels).map(AudioEncoder::Opus)
+            }
+        }
+    }
+
+    /// Encode interleaved samples.
+    pub fn write(&mut self, samples: &[f32]) -> Result<(), String> {
+        match self {
+            AudioEncoder::Wav(writer) => {
+                for &sample in samples {
+                    writer
+                        .write_sample(sample)
+                        .map_err(|e| format!("Failed to write sample: {}", e))?;
+                }
+                Ok(())
+            }
+            AudioEncoder::Flac(encoder) => encoder.write(samples),
+            AudioEncoder::Opus(encoder) => encoder.write(samples),
+        }
+    }
+
+    /// Flush buffered audio and complete the file headers.
+    pub fn finish(self) -> Result<(), String> {
+        match self {
+            AudioEncoder::Wav(writer) => writer
+                .finalize()
+                .map_err(|e| format!("Failed to finalize WAV file: {}", e)),
+            AudioEncoder::Flac(mut encoder) => encoder.finish(),
+            AudioEncoder::Opus(mut encoder) => encoder.finish(),
+        }
+    }
+}
+
+/// Encode a complete recording to `path`.
+pub fn save(
+    samples: &[f32],
+    sample_rate: u32,
+    channels: u16,
+    path: &Path,
+    format: RecordingFormat,
+) -> Result<(), String> {
+    let mut encoder = AudioEncoder::create(path, format, sample_rate, channels)?;
+    encoder.write(samples)?;
+    encoder.finish()
+}
+
+/// Streaming FLAC encoder.
+///
+/// Samples are encoded a block at a time. The STREAMINFO header is written
+/// up front and rewritten with the final sample count and frame sizes when
+/// the encoder finishes.
+pub struct FlacEncoder {
+    writer: BufWriter<File>,
+    config: Verified<flacenc::config::Encoder>,
+    stream_info: StreamInfo,
+    sample_rate: u32,
+    channels: usize,
+    /// Interleaved samples waiting for a full block
+    pending: Vec<i32>,
+    frame_number: usize,
+    total_samples: u64,
+    min_frame_size: usize,
+    max_frame_size: usize,
+    finished: bool,
+}
+
+impl FlacEncoder {
+    fn new(mut writer: BufWriter<File>, sample_rate: u32, channels: u16) -> Result<Self, String> {
+        let config = flacenc::config::Encoder::default()
+            .into_verified()
+            .map_err(|(_, e)| format!("Invalid FLAC encoder config: {:?}", e))?;
+        let stream_info = StreamInfo::new(
+            sample_rate as usize,
+            channels as usize,
+            FLAC_BITS_PER_SAMPLE as usize,
+        )
+        .map_err(|e| format!("Unsupported FLAC stream: {:?}", e))?;
+
+        // Placeholder header, rewritten with the totals in `finish`
+        writer
+            .write_all(&flac_header(
+                config.block_size as u16,
+                0,
+                0,
+                sample_rate,
+                channels as u8,
+                0,
+            ))
+            .map_err(|e| format!("Failed to write FLAC header: {}", e))?;
+
+        Ok(Self {
+            writer,
+            config,
+            stream_info,
+            sample_rate,
+            channels: channels as usize,
+            pending: Vec::new(),
+            frame_number: 0,
+            total_samples: 0,
+            min_frame_size: 0,
+            max_frame_size: 0,
+            finished: false,
+        })
+    }
+
+    fn write(&mut self, samples: &[f32]) -> Result<(), String> {
+        let scale = ((1 << (FLAC_BITS_PER_SAMPLE - 1)) - 1) as f32;
+        self.pending.extend(
+            samples
+                .iter()
+                .map(|s| (s.clamp(-1.0, 1.0) * scale).round() as i32),
+        );
+
+        let block_len = self.config.block_size * self.channels;
+        while self.pending.len() >= block_len {
+            let block: Vec<i32> = self.pending.drain(..block_len).collect();
+            self.encode_block(&block)?;
+        }
+        Ok(())
+    }
+
+    fn encode_block(&mut self, block: &[i32]) -> Result<(), String> {
+        let mut framebuf = FrameBuf::with_size(self.channels, block.len() / self.channels)
+            .map_err(|e| format!("Failed to allocate FLAC frame: {:?}", e))?;
+        framebuf
+            .fill_interleaved(block)
+            .map_err(|e| format!("Failed to fill FLAC frame: {:?}", e))?;
+        let frame = flacenc::encode_fixed_size_frame(
+            &self.config,
+            &framebuf,
+            self.frame_number,
+            &self.stream_info,
+        )
+        .map_err(|e| format!("Failed to encode FLAC frame: {:?}", e))?;
+
+        let mut sink = ByteSink::new();
+        frame
+            .write(&mut sink)
+            .map_err(|e| format!("Failed to encode FLAC frame: {:?}", e))?;
+        let bytes = sink.as_slice();
+        self.writer
+            .write_all(bytes)
+            .map_err(|e| format!("Failed to write FLAC frame: {}", e))?;
+
+        self.frame_number += 1;
+        self.total_samples += (block.len() / self.channels) as u64;
+        self.min_frame_size = if self.min_frame_size == 0 {
+            bytes.len()
+        } else {
+            self.min_frame_size.min(bytes.len())
+        };
+        self.max_frame_size = self.max_frame_size.max(bytes.len());
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), String> {
+        if self.finished {
+            return Ok(());
+        }
+        self.finished = true;
+
+        if !self.pending.is_empty() {
+            let block = std::mem::take(&mut self.pending);
+            self.encode_block(&block)?;
+        }
+
+        let header = flac_header(
+            self.config.block_size as u16,
+            self.min_frame_size as u32,
+            self.max_frame_size as u32,
+            self.sample_rate,
+            self.channels as u8,
+            self.total_samples,
+        );
+        self.writer
+            .seek(SeekFrom::Start(0))
+            .and_then(|_| self.writer.write_all(&header))
+            .and_then(|_| self.writer.flush())
+            .map_err(|e| format!("Failed to finalize FLAC file: {}", e))
+    }
+}
+
+impl Drop for FlacEncoder {
+    fn drop(&mut self) {
+        if let Err(e) = self.finish() {
+            tracing::error!("{}", e);
+        }
+    }
+}
+
+/// Build the `fLaC` marker and STREAMINFO metadata block.
+///
+/// Frame sizes of 0 mean unknown; the MD5 signature is left unset.
+fn flac_header(
+    block_size: u16,
+    min_frame_size: u32,
+    max_frame_size: u32,
+    sample_rate: u32,
+    channels: u8,
+    total_samples: u64,
+) -> [u8; FLAC_HEADER_LEN] {
+    let mut header = [0u8; FLAC_HEADER_LEN];
+    header[..4].copy_from_slice(b"fLaC");
+    // Last metadata block, type 0 (STREAMINFO), 34 bytes long
+    header[4..8].copy_from_slice(&[0x80, 0, 0, 34]);
+    header[8..10].copy_from_slice(&block_size.to_be_bytes());
+    header[10..12].copy_from_slice(&block_size.to_be_bytes());
+    header[12..15].copy_from_slice(&min_frame_size.to_be_bytes()[1..]);
+    header[15..18].copy_from_slice(&max_frame_size.to_be_bytes()[1..]);
+    let packed = (u64::from(sample_rate) << 44)
+        | (u64::from(channels - 1) << 41)
+        | (u64::from(FLAC_BITS_PER_SAMPLE - 1) << 36)
+        | (total_samples & 0xF_FFFF_FFFF);
+    header[18..26].copy_from_slice(&packed.to_be_bytes());
+    header
+}
+
+/// Streaming Ogg Opus encoder.
+///
+/// Audio is encoded in 20ms frames. The final packet is held back until the
+/// encoder finishes so it can be marked as the end of the stream.
+pub struct OpusEncoder {
+    packets: PacketWriter<'static, BufWriter<File>>,
+    encoder: opus::Encoder,
+    channels: usize,
+    /// Samples per channel in one frame at the input rate
+    frame_samples: usize,
+    /// Input samples per 48kHz granule sample
+    granule_scale: u64,
+    /// Encoder delay in 48kHz samples, skipped by decoders
+    pre_skip: u64,
+    /// Interleaved samples waiting for a full frame
+    pending: Vec<f32>,
+    /// Samples per channel encoded so far, excluding padding
+    total_samples: u64,
+    /// Encoded packet not yet written, with its granule position
+    held_packet: Option<(Vec<u8>, u64)>,
+    finished: bool,
+}
+
+impl OpusEncoder {
+    fn new(writer: BufWriter<File>, sample_rate: u32, channels: u16) -> Result<Self, String> {
+        if OPUS_GRANULE_RATE % sample_rate != 0 || sample_rate < 8000 {
+            return Err(format!(
+                "Opus does not support {} Hz audio (use 8, 12, 16, 24 or 48 kHz)",
+                sample_rate
+            ));
+        }
+        let opus_channels = match channels {
+            1 => opus::Channels::Mono,
+            2 => opus::Channels::Stereo,
+            _ => return Err(format!("Opus does not support {} channels", channels)),
+        };
+        let encoder = opus::Encoder::new(sample_rate, opus_channels, opus::Application::Audio)
+            .map_err(|e| format!("Failed to create Opus encoder: {}", e))?;
+        let granule_scale = u64::from(OPUS_GRANULE_RATE / sample_rate);
+        let pre_skip = encoder
+            .get_lookahead()
+            .map_err(|e| format!("Failed to query Opus encoder: {}", e))?
+            as u64
+            * granule_scale;
+
+        let mut packets = PacketWriter::new(writer);
+        packets
+            .write_packet(
+                opus_head(channels as u8, pre_skip as u16, sample_rate).to_vec(),
+                OGG_SERIAL,
+                PacketWriteEndInfo::EndPage,
+                0,
+            )
+            .and_then(|_| {
+                packets.write_packet(opus_tags(), OGG_SERIAL, PacketWriteEndInfo::EndPage, 0)
+            })
+            .map_err(|e| format!("Failed to write Opus header: {}", e))?;
+
+        Ok(Self {
+            packets,
+            encoder,
+            channels: channels as usize,
+            frame_samples: (sample_rate * OPUS_FRAME_MS / 1000) as usize,
+            granule_scale,
+            pre_skip,
+            pending: Vec::new(),
+            total_samples: 0,
+            held_packet: None,
+            finished: false,
+        })
+    }
+
+    fn write(&mut self, samples: &[f32]) -> Result<(), String> {
+        self.pending.extend_from_slice(samples);
+        let frame_len = self.frame_samples * self.channels;
+        while self.pending.len() >= frame_len {
+            let frame: Vec<f32> = self.pending.drain(..frame_len).collect();
+            self.total_samples += self.frame_samples as u64;
+            self.encode_frame(&frame)?;
+        }
+        Ok(())
+    }
+
+    fn encode_frame(&mut self, frame: &[f32]) -> Result<(), String> {
+        let mut packet = vec![0u8; OPUS_MAX_PACKET];
+        let len = self
+            .encoder
+            .encode_float(frame, &mut packet)
+            .map_err(|e| format!("Failed to encode Opus frame: {}", e))?;
+        packet.truncate(len);
+
+        let granule = self.pre_skip + self.total_samples * self.granule_scale;
+        if let Some((held, held_granule)) = self.held_packet.replace((packet, granule)) {
+            self.packets
+                .write_packet(
+                    held,
+                    OGG_SERIAL,
+                    PacketWriteEndInfo::NormalPacket,
+                    held_granule,
+                )
+                .map_err(|e| format!("Failed to write Opus packet: {}", e))?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), String> {
+        if self.finished {
+            return Ok(());
+        }
+        self.finished = true;
+
+        // Pad the last frame with silence; the end granule position tells
+        // decoders to drop the padding
+        if !self.pending.is_empty() || self.held_packet.is_none() {
+            let mut frame = std::mem::take(&mut self.pending);
+            self.total_samples += (frame.len() / self.channels) as u64;
+            frame.resize(self.frame_samples * self.channels, 0.0);
+            self.encode_frame(&frame)?;
+        }
+
+        if let Some((packet, _)) = self.held_packet.take() {
+            let granule = self.pre_skip + self.total_samples * self.granule_scale;
+            self.packets
+                .write_packet(packet, OGG_SERIAL, PacketWriteEndInfo::EndStream, granule)
+                .map_err(|e| format!("Failed to write Opus packet: {}", e))?;
+        }
+        self.packets
+            .inner_mut()
+            .flush()
+            .map_err(|e| format!("Failed to finalize Opus file: {}", e))
+    }
+}
+
+impl Drop for OpusEncoder {
+    fn drop(&mut self) {
+        if let Err(e) = self.finish() {
+            tracing::error!("{}", e);
+        }
+    }
+}
+
+/// Build the Ogg Opus identification header (RFC 7845, section 5.1).
+fn opus_head(channels: u8, pre_skip: u16, input_sample_rate: u32) -> [u8; 19] {
+    let mut head = [0u8; 19];
+    head[..8].copy_from_slice(b"OpusHead");
+    head[8] = 1;
+    head[9] = channels;
+    head[10..12].copy_from_slice(&pre_skip.to_le_bytes());
+    head[12..16].copy_from_slice(&input_sample_rate.to_le_bytes());
+    // Output gain 0 dB, channel mapping family 0
+    head
+}
+
+/// Build the Ogg Opus comment header (RFC 7845, section 5.2).
+fn opus_tags() -> Vec<u8> {
+    let vendor = concat!("flowstt ", env!("CARGO_PKG_VERSION"));
+    let mut tags = b"OpusTags".to_vec();
+    tags.extend_from_slice(&(vendor.len() as u32).to_le_bytes());
+    tags.extend_from_slice(vendor.as_bytes());
+    tags.extend_from_slice(&0u32.to_le_bytes());
+    tags
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flac_header() {
+        let header = flac_header(4096, 10, 2000, 48000, 2, 96000);
+        assert_eq!(&header[..8], b"fLaC\x80\x00\x00\x22");
+        assert_eq!(&header[8..12], &[0x10, 0x00, 0x10, 0x00]);
+        assert_eq!(&header[12..18], &[0, 0, 10, 0, 0x07, 0xd0]);
+        // 48000 Hz, 2 channels, 16 bits, 96000 samples
+        assert_eq!(
+            &header[18..26],
+            &[0x0b, 0xb8, 0x02, 0xf0, 0x00, 0x01, 0x77, 0x00]
+        );
+        assert!(header[26..].iter().all(|&b| b == 0));
+    }
+
+    #[test]
+    fn test_opus_head() {
+        let head = opus_head(2, 312, 48000);
+        assert_eq!(&head[..8], b"OpusHead");
+        assert_eq!(head[8..10], [1, 2]);
+        assert_eq!(head[10..12], 312u16.to_le_bytes());
+        assert_eq!(head[12..16], 48000u32.to_le_bytes());
+        assert_eq!(head[16..], [0, 0, 0]);
+    }
+}
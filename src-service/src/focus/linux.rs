@@ -0,0 +1,57 @@
+//! Linux focused application detection using xdotool.
+//!
+//! xdotool is already needed for typing output on X11. Wayland compositors
+//! don't tell other clients which window is active, so detection fails there.
+
+use std::process::{Command, Stdio};
+
+use flowstt_common::FocusedApp;
+
+/// Check whether the session is running under Wayland
+fn is_wayland_session() -> bool {
+    std::env::var("XDG_SESSION_TYPE").is_ok_and(|t| t == "wayland")
+        || std::env::var_os("WAYLAND_DISPLAY").is_some()
+}
+
+/// The application owning the active X11 window.
+pub fn focused_app() -> Result<FocusedApp, String> {
+    if is_wayland_session() {
+        return Err("Wayland doesn't expose the focused application".to_string());
+    }
+
+    let window = xdotool(&["getactivewindow"])?;
+    let window_class = xdotool(&["getwindowclassname", &window]).ok();
+    let executable = xdotool(&["getwindowpid", &window])
+        .ok()
+        .and_then(|pid| std::fs::read_link(format!("/proc/{}/exe", pid)).ok())
+        .and_then(|exe| Some(exe.file_name()?.to_string_lossy().to_string()));
+
+    let id = executable
+        .or_else(|| window_class.as_ref().map(|class| class.to_lowercase()))
+        .ok_or("Active window has no process or class")?;
+    Ok(FocusedApp {
+        id,
+        name: None,
+        window_class,
+    })
+}
+
+/// Run xdotool and return its trimmed output.
+fn xdotool(args: &[&str]) -> Result<String, String> {
+    let output = Command::new("xdotool")
+        .args(args)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .map_err(|e| {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                "xdotool not found; install it to detect the focused application".to_string()
+            } else {
+                format!("Failed to run xdotool: {}", e)
+            }
+        })?;
+    if !output.status.success() {
+        return Err(format!("xdotool {} failed", args[0]));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
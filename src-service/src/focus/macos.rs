@@ -0,0 +1,25 @@
+//! macOS focused application detection using NSWorkspace.
+
+use flowstt_common::FocusedApp;
+use objc2_app_kit::NSWorkspace;
+
+/// The frontmost application.
+pub fn focused_app() -> Result<FocusedApp, String> {
+    unsafe {
+        let app = NSWorkspace::sharedWorkspace()
+            .frontmostApplication()
+            .ok_or("No application is frontmost")?;
+        let name = app.localizedName().map(|name| name.to_string());
+        let id = app
+            .bundleIdentifier()
+            .map(|id| id.to_string())
+            .or_else(|| name.clone())
+            .ok_or("Frontmost application has no bundle identifier")?;
+
+        Ok(FocusedApp {
+            id,
+            name,
+            window_class: None,
+        })
+    }
+}
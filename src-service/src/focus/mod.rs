@@ -0,0 +1,53 @@
+//! Detection of the application that has keyboard focus.
+//!
+//! Transcripts are tagged with the application they were dictated into, and
+//! profiles can switch automatically when a listed application comes to the
+//! front:
+//! - macOS: NSWorkspace's frontmost application (bundle identifier)
+//! - Windows: the foreground window's process and window class
+//! - Linux: the active X11 window via xdotool (Wayland doesn't expose it)
+
+#[cfg(target_os = "macos")]
+mod macos;
+
+#[cfg(target_os = "windows")]
+mod windows;
+
+#[cfg(target_os = "linux")]
+mod linux;
+
+use flowstt_common::FocusedApp;
+
+/// The application that currently has keyboard focus.
+pub fn focused_app() -> Result<FocusedApp, String> {
+    #[cfg(target_os = "macos")]
+    {
+        macos::focused_app()
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        windows::focused_app()
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        linux::focused_app()
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+    {
+        Err("Focused application detection is not supported on this platform".to_string())
+    }
+}
+
+/// The focused application, or None if it can't be determined.
+pub fn current() -> Option<FocusedApp> {
+    match focused_app() {
+        Ok(app) => Some(app),
+        Err(e) => {
+            tracing::debug!("Focused application unknown: {}", e);
+            None
+        }
+    }
+}
@@ -0,0 +1,62 @@
+//! Windows focused application detection.
+//!
+//! Reports the executable of the process owning the foreground window. A
+//! service running in session 0 has no foreground window, so this only works
+//! when the service runs in the user's session.
+
+use std::path::Path;
+
+use flowstt_common::FocusedApp;
+use windows::core::PWSTR;
+use windows::Win32::Foundation::CloseHandle;
+use windows::Win32::System::Threading::{
+    OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_WIN32, PROCESS_QUERY_LIMITED_INFORMATION,
+};
+use windows::Win32::UI::WindowsAndMessaging::{
+    GetClassNameW, GetForegroundWindow, GetWindowThreadProcessId,
+};
+
+/// The application owning the foreground window.
+pub fn focused_app() -> Result<FocusedApp, String> {
+    unsafe {
+        let window = GetForegroundWindow();
+        if window.0.is_null() {
+            return Err("No window has focus".to_string());
+        }
+
+        let mut class = [0u16; 256];
+        let len = GetClassNameW(window, &mut class);
+        let window_class = (len > 0).then(|| String::from_utf16_lossy(&class[..len as usize]));
+
+        let mut pid = 0u32;
+        GetWindowThreadProcessId(window, Some(&mut pid));
+        let image = process_image(pid)?;
+        let id = Path::new(&image)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or(image);
+
+        Ok(FocusedApp {
+            id,
+            name: None,
+            window_class,
+        })
+    }
+}
+
+/// Full path of a process's executable.
+unsafe fn process_image(pid: u32) -> Result<String, String> {
+    let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid)
+        .map_err(|e| format!("Failed to open process {}: {}", pid, e))?;
+    let mut buffer = [0u16; 1024];
+    let mut len = buffer.len() as u32;
+    let result = QueryFullProcessImageNameW(
+        process,
+        PROCESS_NAME_WIN32,
+        PWSTR(buffer.as_mut_ptr()),
+        &mut len,
+    );
+    let _ = CloseHandle(process);
+    result.map_err(|e| format!("Failed to query process {}: {}", pid, e))?;
+    Ok(String::from_utf16_lossy(&buffer[..len as usize]))
+}
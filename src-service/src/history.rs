@@ -0,0 +1,522 @@
+//! Transcript history persistence for FlowSTT service.
+//!
+//! Every completed transcription is saved to a JSON file in the user's data
+//! directory, so transcripts are still available after their events were emitted.
+//! Capture sessions, which group the transcriptions made between starting and
+//! stopping capture, are saved next to it.
+
+use directories::BaseDirs;
+use flowstt_common::{
+    CaptureSession, FocusedApp, SessionFilter, SessionMetadata, SessionStats, SessionSummary,
+    TranscriptSession, TranscriptVersion, WordTiming,
+};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use tracing::{info, warn};
+
+use crate::transcription::{model, Transcript};
+
+/// Number of characters of text shown in session summaries
+const PREVIEW_LENGTH: usize = 80;
+
+/// Saved transcriptions, oldest first.
+pub struct History {
+    sessions: Vec<TranscriptSession>,
+    path: PathBuf,
+    /// Audio sources of the current capture, recorded with each session
+    source1_id: Option<String>,
+    source2_id: Option<String>,
+    /// Finished capture sessions, oldest first
+    captures: Vec<CaptureSession>,
+    /// Capture session recorded with each new session
+    capture_id: Option<String>,
+}
+
+impl History {
+    /// Get the path to the history file.
+    ///
+    /// Returns platform-specific path:
+    /// - Linux: ~/.local/share/flowstt/history.json
+    /// - macOS: ~/Library/Application Support/flowstt/history.json
+    /// - Windows: %APPDATA%\flowstt\history.json
+    pub fn history_path() -> PathBuf {
+        BaseDirs::new()
+            .map(|d| d.data_dir().to_path_buf())
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("flowstt")
+            .join("history.json")
+    }
+
+    /// Load history from the given file, and capture sessions from the file
+    /// next to it.
+    ///
+    /// A missing or unreadable file results in an empty history. Errors are
+    /// logged but don't fail the load.
+    pub fn load(path: PathBuf) -> Self {
+        let sessions: Vec<TranscriptSession> = read_list(&path);
+        let captures = read_list(&captures_path(&path));
+
+        info!("Loaded {} history sessions from {:?}", sessions.len(), path);
+
+        Self {
+            sessions,
+            path,
+            source1_id: None,
+            source2_id: None,
+            captures,
+            capture_id: None,
+        }
+    }
+
+    /// Save history to disk, creating the parent directory if needed.
+    pub fn save(&self) -> io::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let contents = serde_json::to_string_pretty(&self.sessions)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        fs::write(&self.path, contents)?;
+
+        let contents = serde_json::to_string_pretty(&self.captures)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        fs::write(captures_path(&self.path), contents)
+    }
+
+    /// Set the audio sources recorded with subsequent sessions.
+    pub fn set_sources(&mut self, source1_id: Option<String>, source2_id: Option<String>) {
+        self.source1_id = source1_id;
+        self.source2_id = source2_id;
+    }
+
+    /// Set the capture session recorded with subsequent sessions.
+    ///
+    /// It stays set after capture stops, so segments still being transcribed
+    /// then are recorded with the session they were spoken in.
+    pub fn set_capture(&mut self, capture_id: Option<String>) {
+        self.capture_id = capture_id;
+    }
+
+    /// Add a completed transcription, dictated into `app` if known and named
+    /// after the calendar meeting it overlaps, and return the new session ID.
+    pub fn add(&mut self, transcript: &Transcript, app: Option<FocusedApp>) -> String {
+        let now = chrono::Local::now();
+        let started = now - chrono::Duration::milliseconds(transcript.duration_ms as i64);
+
+        // IDs are timestamps; disambiguate transcriptions completing in the same millisecond
+        let base_id = now.format("%Y%m%d-%H%M%S-%3f").to_string();
+        let mut id = base_id.clone();
+        let mut suffix = 1;
+        while self.sessions.iter().any(|s| s.id == id) {
+            id = format!("{}-{}", base_id, suffix);
+            suffix += 1;
+        }
+
+        let mut session = TranscriptSession {
+            id: id.clone(),
+            created_at: now.to_rfc3339(),
+            text: transcript.text.clone(),
+            language: transcript.language.clone(),
+            words: transcript.words.clone(),
+            segments: transcript.segments.clone(),
+            translation: transcript.translation.clone(),
+            speaker: transcript.speaker.clone(),
+            duration_ms: transcript.duration_ms,
+            source1_id: self.source1_id.clone(),
+            source2_id: self.source2_id.clone(),
+            audio_path: transcript
+                .audio_path
+                .as_ref()
+                .map(|p| p.to_string_lossy().to_string()),
+            app,
+            summary: None,
+            chapters: Vec::new(),
+            metadata: crate::calendar::meeting_metadata(started, now).unwrap_or_default(),
+            model: Some(model::model_label()),
+            versions: Vec::new(),
+            stats: None,
+            capture_id: self.capture_id.clone(),
+        };
+        session.stats = Some(crate::stats::compute(&session));
+        self.sessions.push(session);
+
+        id
+    }
+
+    /// Sessions recorded during capture session `capture_id`, oldest first.
+    pub fn capture_sessions(&self, capture_id: &str) -> Vec<&TranscriptSession> {
+        self.sessions
+            .iter()
+            .filter(|s| s.capture_id.as_deref() == Some(capture_id))
+            .collect()
+    }
+
+    /// Add a finished capture session.
+    pub fn add_capture(&mut self, capture: CaptureSession) {
+        self.captures.push(capture);
+    }
+
+    /// Get a capture session by ID.
+    pub fn capture(&self, id: &str) -> Option<&CaptureSession> {
+        self.captures.iter().find(|c| c.id == id)
+    }
+
+    /// Store a summary with a capture session. Returns false if there is no
+    /// such capture session.
+    pub fn set_capture_summary(&mut self, id: &str, summary: String) -> bool {
+        match self.captures.iter_mut().find(|c| c.id == id) {
+            Some(capture) => {
+                capture.summary = Some(summary);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// List summaries of the sessions matching `filter`, newest first.
+    pub fn list(&self, limit: Option<usize>, filter: &SessionFilter) -> Vec<SessionSummary> {
+        self.sessions
+            .iter()
+            .rev()
+            .filter(|s| filter.matches(s))
+            .take(limit.unwrap_or(usize::MAX))
+            .map(|s| SessionSummary {
+                id: s.id.clone(),
+                created_at: s.created_at.clone(),
+                duration_ms: s.duration_ms,
+                preview: preview(&s.text),
+                title: s.metadata.title.clone(),
+                tags: s.metadata.tags.clone(),
+            })
+            .collect()
+    }
+
+    /// All sessions, oldest first.
+    pub fn sessions(&self) -> &[TranscriptSession] {
+        &self.sessions
+    }
+
+    /// Speaking statistics of a session, computed now for sessions saved
+    /// before statistics were, or of a whole capture session.
+    pub fn stats(&self, id: &str) -> Option<SessionStats> {
+        match self.get(id) {
+            Some(session) => Some(
+                session
+                    .stats
+                    .clone()
+                    .unwrap_or_else(|| crate::stats::compute(session)),
+            ),
+            None => self.capture(id)?.stats.clone(),
+        }
+    }
+
+    /// Forget a session's recording, keeping its transcript.
+    pub fn clear_audio(&mut self, id: &str) {
+        if let Some(session) = self.sessions.iter_mut().find(|s| s.id == id) {
+            session.audio_path = None;
+        }
+    }
+
+    /// Get a session by ID.
+    pub fn get(&self, id: &str) -> Option<&TranscriptSession> {
+        self.sessions.iter().find(|s| s.id == id)
+    }
+
+    /// Get the session whose recording was saved at `path`.
+    pub fn find_by_audio_path(&self, path: &str) -> Option<&TranscriptSession> {
+        self.sessions
+            .iter()
+            .find(|s| s.audio_path.as_deref() == Some(path))
+    }
+
+    /// Replace a session's text with a corrected version, and its word timings
+    /// too if given. Returns false if there is no such session.
+    pub fn update(&mut self, id: &str, text: String, words: Option<Vec<WordTiming>>) -> bool {
+        let Some(session) = self.sessions.iter_mut().find(|s| s.id == id) else {
+            return false;
+        };
+        session.text = text;
+        if let Some(words) = words {
+            session.words = words;
+        }
+        session.stats = Some(crate::stats::compute(session));
+        true
+    }
+
+    /// Replace a session's transcript with one from `model`, keeping the
+    /// previous transcript as a version. Returns false if there is no such
+    /// session.
+    pub fn replace_transcript(&mut self, id: &str, transcript: &Transcript, model: String) -> bool {
+        let Some(session) = self.sessions.iter_mut().find(|s| s.id == id) else {
+            return false;
+        };
+        session.versions.push(TranscriptVersion {
+            replaced_at: chrono::Local::now().to_rfc3339(),
+            model: session.model.take(),
+            text: std::mem::replace(&mut session.text, transcript.text.clone()),
+            language: std::mem::replace(&mut session.language, transcript.language.clone()),
+            words: std::mem::replace(&mut session.words, transcript.words.clone()),
+            segments: std::mem::replace(&mut session.segments, transcript.segments.clone()),
+            translation: std::mem::replace(
+                &mut session.translation,
+                transcript.translation.clone(),
+            ),
+        });
+        session.model = Some(model);
+        session.stats = Some(crate::stats::compute(session));
+        true
+    }
+
+    /// Replace a session's title, tags, participants and notes. Returns false
+    /// if there is no such session.
+    pub fn set_metadata(&mut self, id: &str, metadata: SessionMetadata) -> bool {
+        match self.sessions.iter_mut().find(|s| s.id == id) {
+            Some(session) => {
+                session.metadata = metadata;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Remove a session by ID. The saved recording is left on disk.
+    pub fn remove(&mut self, id: &str) -> Option<TranscriptSession> {
+        let index = self.sessions.iter().position(|s| s.id == id)?;
+        Some(self.sessions.remove(index))
+    }
+}
+
+/// Path of the capture sessions file kept next to the history file at `path`.
+fn captures_path(path: &Path) -> PathBuf {
+    path.with_extension("captures.json")
+}
+
+/// Read a JSON list from `path`, treating a missing or unreadable file as empty.
+fn read_list<T: serde::de::DeserializeOwned>(path: &Path) -> Vec<T> {
+    if !path.exists() {
+        return Vec::new();
+    }
+    match fs::read_to_string(path) {
+        Ok(contents) => match serde_json::from_str(&contents) {
+            Ok(list) => list,
+            Err(e) => {
+                warn!("Failed to parse history file {:?}: {}", path, e);
+                Vec::new()
+            }
+        },
+        Err(e) => {
+            warn!("Failed to read history file {:?}: {}", path, e);
+            Vec::new()
+        }
+    }
+}
+
+/// Shorten text to at most `PREVIEW_LENGTH` characters.
+fn preview(text: &str) -> String {
+    if text.chars().count() <= PREVIEW_LENGTH {
+        return text.to_string();
+    }
+    let mut preview: String = text.chars().take(PREVIEW_LENGTH - 1).collect();
+    preview.push('…');
+    preview
+}
+
+/// Global history store
+static HISTORY: OnceLock<Mutex<History>> = OnceLock::new();
+
+/// Get the global history store, loading it from disk on first use.
+pub fn get_history() -> &'static Mutex<History> {
+    HISTORY.get_or_init(|| Mutex::new(History::load(History::history_path())))
+}
+
+/// Save a completed transcription to the history store and return its ID.
+pub fn record_transcript(transcript: &Transcript, app: Option<FocusedApp>) -> String {
+    let mut history = get_history().lock().unwrap();
+    let id = history.add(transcript, app);
+    if let Err(e) = history.save() {
+        warn!("Failed to save history: {}", e);
+    }
+    tracing::debug!("Saved transcription to history as {}", id);
+    id
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transcript(text: &str) -> Transcript {
+        Transcript {
+            text: text.to_string(),
+            language: Some("en".to_string()),
+            words: Vec::new(),
+            segments: Vec::new(),
+            confidence: Vec::new(),
+            translation: None,
+            speaker: None,
+            modified: false,
+            duration_ms: 1500,
+            audio_path: None,
+            output: None,
+        }
+    }
+
+    #[test]
+    fn test_history_add_list_remove() {
+        let path = std::env::temp_dir().join("flowstt-history-test.json");
+        let mut history = History::load(path);
+        history.sessions.clear();
+        history.set_sources(Some("mic".to_string()), None);
+
+        let first = history.add(&transcript("first"), None);
+        let second = history.add(&transcript("second"), None);
+        assert_ne!(first, second);
+
+        let all = SessionFilter::default();
+        let listed = history.list(None, &all);
+        assert_eq!(listed.len(), 2);
+        assert_eq!(listed[0].id, second);
+        assert_eq!(history.list(Some(1), &all).len(), 1);
+
+        let session = history.get(&first).unwrap();
+        assert_eq!(session.text, "first");
+        assert_eq!(session.source1_id.as_deref(), Some("mic"));
+        assert_eq!(session.stats.as_ref().map(|s| s.words), Some(1));
+        assert!(history.update(&first, "First of all".to_string(), None));
+        assert_eq!(history.get(&first).unwrap().text, "First of all");
+        assert_eq!(history.stats(&first).map(|s| s.words), Some(3));
+
+        assert!(history.remove(&first).is_some());
+        assert!(history.get(&first).is_none());
+        assert!(history.remove(&first).is_none());
+    }
+
+    #[test]
+    fn test_history_metadata_filter() {
+        let path = std::env::temp_dir().join("flowstt-history-metadata-test.json");
+        let mut history = History::load(path);
+        history.sessions.clear();
+
+        let standup = history.add(&transcript("standup"), None);
+        let review = history.add(&transcript("review"), None);
+        assert!(history.set_metadata(
+            &standup,
+            SessionMetadata {
+                title: Some("Daily standup".to_string()),
+                tags: vec!["Work".to_string()],
+                participants: vec!["Ana Silva".to_string()],
+                notes: None,
+            }
+        ));
+        assert!(!history.set_metadata("missing", SessionMetadata::default()));
+
+        let listed = history.list(None, &SessionFilter::default());
+        assert_eq!(listed.len(), 2);
+        assert_eq!(listed[1].title.as_deref(), Some("Daily standup"));
+
+        let by_tag = SessionFilter {
+            tag: Some("work".to_string()),
+            ..Default::default()
+        };
+        let by_participant = SessionFilter {
+            participant: Some("silva".to_string()),
+            ..Default::default()
+        };
+        let by_query = SessionFilter {
+            query: Some("STANDUP".to_string()),
+            ..Default::default()
+        };
+        for filter in [by_tag, by_participant, by_query] {
+            let listed = history.list(None, &filter);
+            assert_eq!(listed.len(), 1);
+            assert_eq!(listed[0].id, standup);
+        }
+
+        let no_match = SessionFilter {
+            tag: Some("work".to_string()),
+            query: Some("review".to_string()),
+            ..Default::default()
+        };
+        assert!(history.list(None, &no_match).is_empty());
+        assert!(history.get(&review).unwrap().metadata.is_empty());
+    }
+
+    #[test]
+    fn test_history_replace_transcript_keeps_version() {
+        let path = std::env::temp_dir().join("flowstt-history-versions-test.json");
+        let mut history = History::load(path);
+        history.sessions.clear();
+
+        let id = history.add(&transcript("wreck a nice beach"), None);
+        let previous_model = history.get(&id).unwrap().model.clone();
+        assert!(history.replace_transcript(
+            &id,
+            &transcript("recognize speech"),
+            "large-v3".to_string()
+        ));
+        assert!(!history.replace_transcript(
+            "missing",
+            &transcript("nothing"),
+            "large-v3".to_string()
+        ));
+
+        let session = history.get(&id).unwrap();
+        assert_eq!(session.text, "recognize speech");
+        assert_eq!(session.model.as_deref(), Some("large-v3"));
+        assert_eq!(session.versions.len(), 1);
+        assert_eq!(session.versions[0].text, "wreck a nice beach");
+        assert_eq!(session.versions[0].model, previous_model);
+        assert_eq!(session.versions[0].language.as_deref(), Some("en"));
+    }
+
+    #[test]
+    fn test_history_capture_sessions() {
+        let path = std::env::temp_dir().join("flowstt-history-captures-test.json");
+        let mut history = History::load(path.clone());
+        history.sessions.clear();
+        history.captures.clear();
+
+        let before = history.add(&transcript("before"), None);
+        history.set_capture(Some("capture-1".to_string()));
+        let first = history.add(&transcript("first"), None);
+        let second = history.add(&transcript("second"), None);
+        let ids: Vec<&str> = history
+            .capture_sessions("capture-1")
+            .iter()
+            .map(|s| s.id.as_str())
+            .collect();
+        assert_eq!(ids, [first.as_str(), second.as_str()]);
+        assert!(history.get(&before).unwrap().capture_id.is_none());
+
+        history.add_capture(CaptureSession {
+            id: "capture-1".to_string(),
+            started_at: "2026-03-02T10:00:00+00:00".to_string(),
+            ended_at: "2026-03-02T10:05:00+00:00".to_string(),
+            duration_ms: 300_000,
+            session_ids: vec![first, second],
+            summary: None,
+            chapters: Vec::new(),
+            stats: Some(SessionStats {
+                words: 2,
+                ..Default::default()
+            }),
+        });
+        assert_eq!(history.stats("capture-1").map(|s| s.words), Some(2));
+        assert!(history.set_capture_summary("capture-1", "A summary".to_string()));
+        assert!(!history.set_capture_summary("missing", "Gone".to_string()));
+        history.save().unwrap();
+
+        let loaded = History::load(path);
+        let capture = loaded.capture("capture-1").unwrap();
+        assert_eq!(capture.session_ids.len(), 2);
+        assert_eq!(capture.summary.as_deref(), Some("A summary"));
+    }
+
+    #[test]
+    fn test_preview_truncates_long_text() {
+        let long = "a".repeat(200);
+        assert_eq!(preview(&long).chars().count(), PREVIEW_LENGTH);
+        assert_eq!(preview("short"), "short");
+    }
+}
@@ -0,0 +1,199 @@
+//! Session hooks.
+//!
+//! Hooks run once capture stops, on everything transcribed since it started.
+//! A command hook gets the text on stdin and the capture session ID in
+//! `FLOWSTT_SESSION_ID`; an HTTP hook gets the combined transcript POSTed as
+//! JSON, which suits a local LLM server. The output of a summarizing hook is
+//! stored with the capture session as its summary.
+
+use std::io::{Read, Write};
+use std::process::{Child, Command, Stdio};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use flowstt_common::ipc::{EventType, Response};
+use flowstt_common::{HookTarget, SessionHook, TranscriptSession};
+use tracing::{debug, info, warn};
+
+use crate::config::RedactionChannel;
+use crate::history::get_history;
+use crate::ipc::broadcast_event;
+use crate::redact;
+
+/// Longest a hook may run before it is abandoned
+const HOOK_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// How often a running command is checked for completion
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Configured hooks
+static HOOKS: Mutex<Vec<SessionHook>> = Mutex::new(Vec::new());
+
+/// Replace the set of configured hooks.
+pub fn set_hooks(hooks: &[SessionHook]) {
+    *HOOKS.lock().unwrap() = hooks.to_vec();
+}
+
+/// Run the configured hooks on the combined transcript of a finished capture
+/// session, whose ID `session` carries.
+///
+/// Blocks until every hook is done; the capture session worker calls this, so
+/// hook runs never overlap. Hooks run one after another in the order they were
+/// added, so when several summarize, the last one to produce output wins.
+pub fn run(mut session: TranscriptSession) {
+    let hooks = HOOKS.lock().unwrap().clone();
+    if hooks.is_empty() {
+        return;
+    }
+    redact::session(RedactionChannel::Hooks, &mut session);
+
+    for hook in hooks {
+        let output = match &hook.target {
+            HookTarget::Command { command } => run_command(command, &session),
+            HookTarget::Http { url } => post_session(url, &session),
+        };
+        match output {
+            Ok(output) if hook.summarize && !output.is_empty() => {
+                store_summary(&session.id, output);
+            }
+            Ok(_) => debug!("Hook {} finished for {}", hook.name, session.id),
+            Err(e) => warn!("Hook {} failed for {}: {}", hook.name, session.id, e),
+        }
+    }
+}
+
+/// Save `summary` with the capture session and tell clients about it.
+fn store_summary(id: &str, summary: String) {
+    {
+        let mut history = get_history().lock().unwrap();
+        if !history.set_capture_summary(id, summary.clone()) {
+            return;
+        }
+        if let Err(e) = history.save() {
+            warn!("Failed to save history: {}", e);
+        }
+    }
+    info!("Stored summary for {}", id);
+    broadcast_event(Response::Event {
+        event: EventType::SessionSummarized {
+            id: id.to_string(),
+            summary,
+        },
+    });
+}
+
+/// Run `command` through the shell with the session text on stdin and return
+/// its trimmed stdout.
+fn run_command(command: &str, session: &TranscriptSession) -> Result<String, String> {
+    let mut child = shell(command)
+        .env("FLOWSTT_SESSION_ID", &session.id)
+        .env(
+            "FLOWSTT_AUDIO_PATH",
+            session.audio_path.as_deref().unwrap_or(""),
+        )
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Failed to start {:?}: {}", command, e))?;
+
+    // Feed stdin and drain stdout on their own threads so a command that
+    // writes before reading everything can't deadlock against us
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let text = session.text.clone();
+    thread::spawn(move || stdin.write_all(text.as_bytes()));
+    let mut stdout = child.stdout.take().expect("stdout is piped");
+    let reader = thread::spawn(move || {
+        let mut output = String::new();
+        stdout.read_to_string(&mut output).map(|_| output)
+    });
+
+    let status = wait_timeout(&mut child, HOOK_TIMEOUT)?;
+    if !status.success() {
+        return Err(format!("Command exited with {}", status));
+    }
+    let output = reader
+        .join()
+        .map_err(|_| "Output reader panicked".to_string())?
+        .map_err(|e| format!("Failed to read command output: {}", e))?;
+    Ok(output.trim().to_string())
+}
+
+#[cfg(windows)]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("cmd");
+    shell.args(["/C", command]);
+    shell
+}
+
+#[cfg(not(windows))]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("sh");
+    shell.args(["-c", command]);
+    shell
+}
+
+/// Wait for `child` to exit, killing it after `timeout`.
+fn wait_timeout(child: &mut Child, timeout: Duration) -> Result<std::process::ExitStatus, String> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) => return Ok(status),
+            Ok(None) if Instant::now() >= deadline => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!("Timed out after {}s", timeout.as_secs()));
+            }
+            Ok(None) => thread::sleep(POLL_INTERVAL),
+            Err(e) => return Err(format!("Failed to wait for command: {}", e)),
+        }
+    }
+}
+
+/// POST the session as JSON to `url` and return the summary in the response.
+fn post_session(url: &str, session: &TranscriptSession) -> Result<String, String> {
+    let body = serde_json::to_string(session).map_err(|e| e.to_string())?;
+    let client = reqwest::blocking::Client::builder()
+        .timeout(HOOK_TIMEOUT)
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+    let response = client
+        .post(url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(body)
+        .send()
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Request to {} failed: {}", url, e))?;
+    let text = response
+        .text()
+        .map_err(|e| format!("Failed to read response from {}: {}", url, e))?;
+    Ok(summary_from_response(&text))
+}
+
+/// The `summary` field of a JSON response, or the whole body otherwise.
+fn summary_from_response(body: &str) -> String {
+    let summary = serde_json::from_str::<serde_json::Value>(body)
+        .ok()
+        .and_then(|value| value.get("summary")?.as_str().map(str::to_string));
+    summary.as_deref().unwrap_or(body).trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary_from_response() {
+        assert_eq!(
+            summary_from_response(r#"{"summary": " Agreed to ship Friday. ", "tokens": 12}"#),
+            "Agreed to ship Friday."
+        );
+        assert_eq!(
+            summary_from_response("Agreed to ship Friday.\n"),
+            "Agreed to ship Friday."
+        );
+        // JSON without a summary field is returned as is
+        assert_eq!(summary_from_response(r#"{"ok":true}"#), r#"{"ok":true}"#);
+    }
+}
@@ -1,29 +1,29 @@
 //! Platform-agnostic hotkey backend trait.
 
-use flowstt_common::KeyCode;
+use flowstt_common::{HotkeyBinding, KeyCode};
 
 /// Event emitted when hotkey state changes.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum HotkeyEvent {
-    /// The hotkey was pressed
-    Pressed,
-    /// The hotkey was released
-    Released,
+    /// A bound key was pressed
+    Pressed(KeyCode),
+    /// A bound key was released
+    Released(KeyCode),
 }
 
 /// Platform-agnostic hotkey backend interface.
 ///
-/// Implementations capture global keyboard events and filter for the configured
-/// push-to-talk hotkey. The backend runs on a separate thread and delivers
-/// events via a channel.
+/// Implementations capture global keyboard events and filter for the bound
+/// keys. The backend runs on a separate thread and delivers events via a
+/// channel; acting on them is up to the caller.
 pub trait HotkeyBackend: Send {
-    /// Start monitoring for the specified hotkey.
+    /// Start monitoring for the keys of `bindings`.
     ///
     /// Returns an error if:
     /// - The platform doesn't support global hotkeys
     /// - Required permissions are not granted (e.g., Accessibility on macOS)
     /// - The backend is already running
-    fn start(&mut self, key: KeyCode) -> Result<(), String>;
+    fn start(&mut self, bindings: &[HotkeyBinding]) -> Result<(), String>;
 
     /// Stop monitoring for hotkey events.
     fn stop(&mut self);
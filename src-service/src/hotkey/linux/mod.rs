@@ -0,0 +1,157 @@
+//! Linux hotkey backend.
+//!
+//! Uses XGrabKey on X11 sessions and the XDG GlobalShortcuts portal on
+//! Wayland. XWayland grabs only see keys while an X11 window is focused, so
+//! Wayland sessions never fall back to X11.
+
+mod portal;
+mod x11;
+
+use super::backend::{HotkeyBackend, HotkeyEvent};
+use flowstt_common::HotkeyBinding;
+use std::sync::mpsc::{self, Receiver};
+use tracing::{info, warn};
+
+/// Mechanism used to capture the hotkey
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum HotkeyMethod {
+    X11,
+    Portal,
+}
+
+/// Active key capture for the selected method
+enum ActiveGrab {
+    X11(x11::X11Grab),
+    Portal(portal::PortalGrab),
+}
+
+/// Check whether the session is running under Wayland
+fn is_wayland_session() -> bool {
+    std::env::var("XDG_SESSION_TYPE").is_ok_and(|t| t == "wayland")
+        || std::env::var_os("WAYLAND_DISPLAY").is_some()
+}
+
+/// Pick the capture method for this session, or explain why none is usable
+fn detect_method() -> Result<HotkeyMethod, String> {
+    if is_wayland_session() {
+        return portal::probe().map(|()| HotkeyMethod::Portal).map_err(|e| {
+            format!(
+                "Push-to-talk on Wayland requires a desktop with GlobalShortcuts portal support \
+                 (e.g. KDE Plasma 5.27+ or GNOME 48+): {}",
+                e
+            )
+        });
+    }
+
+    if std::env::var_os("DISPLAY").is_some() {
+        return x11::probe()
+            .map(|()| HotkeyMethod::X11)
+            .map_err(|e| format!("Push-to-talk is unavailable: {}", e));
+    }
+
+    Err(
+        "Push-to-talk requires a graphical session (neither DISPLAY nor WAYLAND_DISPLAY is set)"
+            .to_string(),
+    )
+}
+
+/// Linux hotkey backend using X11 key grabs or the GlobalShortcuts portal
+pub struct LinuxHotkeyBackend {
+    /// Capture method for this session (None if unavailable)
+    method: Option<HotkeyMethod>,
+    /// Channel for receiving hotkey events
+    receiver: Option<Receiver<HotkeyEvent>>,
+    /// Active key capture
+    grab: Option<ActiveGrab>,
+    /// Why hotkeys are unavailable (detection or last start failure)
+    unavailable_reason: Option<String>,
+}
+
+impl LinuxHotkeyBackend {
+    pub fn new() -> Self {
+        let (method, unavailable_reason) = match detect_method() {
+            Ok(method) => {
+                info!("[Hotkey] Using {:?} hotkey capture", method);
+                (Some(method), None)
+            }
+            Err(reason) => {
+                warn!("[Hotkey] {}", reason);
+                (None, Some(reason))
+            }
+        };
+
+        Self {
+            method,
+            receiver: None,
+            grab: None,
+            unavailable_reason,
+        }
+    }
+}
+
+impl HotkeyBackend for LinuxHotkeyBackend {
+    fn start(&mut self, bindings: &[HotkeyBinding]) -> Result<(), String> {
+        if self.grab.is_some() {
+            return Err("Hotkey backend already running".to_string());
+        }
+
+        let method = self.method.ok_or_else(|| {
+            self.unavailable_reason
+                .clone()
+                .unwrap_or_else(|| "Push-to-talk is unavailable".to_string())
+        })?;
+
+        let (sender, receiver) = mpsc::channel();
+        let grab = match method {
+            HotkeyMethod::X11 => x11::X11Grab::start(bindings, sender).map(ActiveGrab::X11),
+            HotkeyMethod::Portal => {
+                portal::PortalGrab::start(bindings, sender).map(ActiveGrab::Portal)
+            }
+        };
+
+        match grab {
+            Ok(grab) => {
+                self.grab = Some(grab);
+                self.receiver = Some(receiver);
+                self.unavailable_reason = None;
+                Ok(())
+            }
+            Err(e) => {
+                self.unavailable_reason = Some(e.clone());
+                Err(e)
+            }
+        }
+    }
+
+    fn stop(&mut self) {
+        match self.grab.take() {
+            Some(ActiveGrab::X11(grab)) => grab.stop(),
+            Some(ActiveGrab::Portal(grab)) => grab.stop(),
+            None => return,
+        }
+        info!("[Hotkey] Stopped hotkey backend");
+        self.receiver = None;
+    }
+
+    fn try_recv(&self) -> Option<HotkeyEvent> {
+        self.receiver.as_ref()?.try_recv().ok()
+    }
+
+    fn is_running(&self) -> bool {
+        self.grab.is_some()
+    }
+
+    fn is_available(&self) -> bool {
+        self.method.is_some()
+    }
+
+    fn unavailable_reason(&self) -> Option<String> {
+        self.unavailable_reason.clone()
+    }
+}
+
+impl Drop for LinuxHotkeyBackend {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
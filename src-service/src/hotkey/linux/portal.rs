@@ -0,0 +1,203 @@
+//! Wayland hotkey capture using the XDG GlobalShortcuts portal.
+//!
+//! Wayland doesn't let clients grab keys globally, so each binding is
+//! registered as a shortcut with the desktop through xdg-desktop-portal. The
+//! desktop may ask the user to confirm or change the bindings; the configured
+//! keys are only passed as preferred triggers.
+
+use super::super::backend::HotkeyEvent;
+use ashpd::desktop::global_shortcuts::{GlobalShortcuts, NewShortcut};
+use flowstt_common::{HotkeyAction, HotkeyBinding, KeyCode};
+use futures::StreamExt;
+use std::sync::mpsc::{self, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use tokio::sync::oneshot;
+use tracing::{error, info};
+
+/// Portal shortcut identifier for push-to-talk
+const PTT_SHORTCUT_ID: &str = "flowstt-ptt";
+
+/// Portal shortcut identifier for a binding.
+///
+/// Push-to-talk keeps its original ID so desktops remember the user's choice.
+fn shortcut_id(action: HotkeyAction) -> String {
+    match action {
+        HotkeyAction::PushToTalk => PTT_SHORTCUT_ID.to_string(),
+        other => format!("flowstt-{}", other.name()),
+    }
+}
+
+/// How long to wait for the portal to answer the availability probe
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// How long to wait for the shortcut to be bound (the user may be prompted)
+const BIND_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Preferred trigger in the XDG shortcuts format (xkb keysym names)
+fn preferred_trigger(key: KeyCode) -> &'static str {
+    match key {
+        KeyCode::RightAlt => "Alt_R",
+        KeyCode::LeftAlt => "Alt_L",
+        KeyCode::RightControl => "Control_R",
+        KeyCode::LeftControl => "Control_L",
+        KeyCode::RightShift => "Shift_R",
+        KeyCode::LeftShift => "Shift_L",
+        KeyCode::CapsLock => "Caps_Lock",
+        KeyCode::F13 => "F13",
+        KeyCode::F14 => "F14",
+        KeyCode::F15 => "F15",
+        KeyCode::F16 => "F16",
+        KeyCode::F17 => "F17",
+        KeyCode::F18 => "F18",
+        KeyCode::F19 => "F19",
+        KeyCode::F20 => "F20",
+    }
+}
+
+/// Build a single-threaded runtime for portal D-Bus traffic.
+fn build_runtime() -> Result<tokio::runtime::Runtime, String> {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(|e| format!("Failed to create portal runtime: {}", e))
+}
+
+/// Check whether the GlobalShortcuts portal is available.
+///
+/// Runs on its own thread so it can be called from inside the service runtime.
+pub fn probe() -> Result<(), String> {
+    thread::spawn(|| {
+        build_runtime()?.block_on(async {
+            match tokio::time::timeout(PROBE_TIMEOUT, GlobalShortcuts::new()).await {
+                Ok(Ok(_)) => Ok(()),
+                Ok(Err(e)) => Err(format!("GlobalShortcuts portal not available: {}", e)),
+                Err(_) => Err("xdg-desktop-portal did not respond".to_string()),
+            }
+        })
+    })
+    .join()
+    .unwrap_or_else(|_| Err("GlobalShortcuts portal probe panicked".to_string()))
+}
+
+/// An active portal shortcut session running on its own thread.
+pub struct PortalGrab {
+    stop_sender: Option<oneshot::Sender<()>>,
+    thread_handle: Option<JoinHandle<()>>,
+}
+
+impl PortalGrab {
+    /// Bind the shortcuts and start delivering events.
+    ///
+    /// Returns once the desktop has accepted the bindings.
+    pub fn start(bindings: &[HotkeyBinding], sender: Sender<HotkeyEvent>) -> Result<Self, String> {
+        let (ready_sender, ready_receiver) = mpsc::channel();
+        let (stop_sender, stop_receiver) = oneshot::channel();
+
+        let bindings = bindings.to_vec();
+        let handle = thread::spawn(move || {
+            let result = build_runtime().and_then(|runtime| {
+                runtime.block_on(run_session(&bindings, sender, &ready_sender, stop_receiver))
+            });
+            if let Err(e) = result {
+                let _ = ready_sender.send(Err(e.clone()));
+                error!("[Hotkey] Portal session error: {}", e);
+            }
+            info!("[Hotkey] Portal session thread exiting");
+        });
+
+        match ready_receiver.recv_timeout(BIND_TIMEOUT) {
+            Ok(Ok(())) => Ok(Self {
+                stop_sender: Some(stop_sender),
+                thread_handle: Some(handle),
+            }),
+            Ok(Err(e)) => {
+                let _ = handle.join();
+                Err(e)
+            }
+            Err(_) => {
+                // Dropping the stop sender ends the session once the portal answers
+                Err("Timed out waiting for the shortcut to be bound".to_string())
+            }
+        }
+    }
+
+    /// Close the portal session and stop the event thread.
+    pub fn stop(mut self) {
+        if let Some(stop) = self.stop_sender.take() {
+            let _ = stop.send(());
+        }
+        if let Some(handle) = self.thread_handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Bind the shortcuts and forward activation signals until stopped.
+async fn run_session(
+    bindings: &[HotkeyBinding],
+    sender: Sender<HotkeyEvent>,
+    ready: &Sender<Result<(), String>>,
+    mut stop: oneshot::Receiver<()>,
+) -> Result<(), String> {
+    let proxy = GlobalShortcuts::new()
+        .await
+        .map_err(|e| format!("GlobalShortcuts portal not available: {}", e))?;
+    let session = proxy
+        .create_session()
+        .await
+        .map_err(|e| format!("Failed to create portal session: {}", e))?;
+
+    let ids: Vec<(String, KeyCode)> = bindings
+        .iter()
+        .map(|binding| (shortcut_id(binding.action), binding.key))
+        .collect();
+    let shortcuts: Vec<NewShortcut> = bindings
+        .iter()
+        .zip(&ids)
+        .map(|(binding, (id, _))| {
+            NewShortcut::new(id.as_str(), binding.action.description())
+                .preferred_trigger(preferred_trigger(binding.key))
+        })
+        .collect();
+    proxy
+        .bind_shortcuts(&session, &shortcuts, None)
+        .await
+        .and_then(|request| request.response())
+        .map_err(|e| format!("Failed to bind hotkey shortcuts: {}", e))?;
+    let key_for = |id: &str| ids.iter().find(|(i, _)| i == id).map(|&(_, key)| key);
+
+    let mut activated = proxy
+        .receive_activated()
+        .await
+        .map_err(|e| format!("Failed to listen for shortcut activation: {}", e))?;
+    let mut deactivated = proxy
+        .receive_deactivated()
+        .await
+        .map_err(|e| format!("Failed to listen for shortcut deactivation: {}", e))?;
+
+    info!("[Hotkey] {} portal shortcuts bound", shortcuts.len());
+    let _ = ready.send(Ok(()));
+
+    loop {
+        tokio::select! {
+            _ = &mut stop => break,
+            Some(event) = activated.next() => {
+                if let Some(key) = key_for(event.shortcut_id()) {
+                    info!("[Hotkey] {} DOWN", event.shortcut_id());
+                    let _ = sender.send(HotkeyEvent::Pressed(key));
+                }
+            }
+            Some(event) = deactivated.next() => {
+                if let Some(key) = key_for(event.shortcut_id()) {
+                    info!("[Hotkey] {} UP", event.shortcut_id());
+                    let _ = sender.send(HotkeyEvent::Released(key));
+                }
+            }
+            else => break,
+        }
+    }
+
+    let _ = session.close().await;
+    Ok(())
+}
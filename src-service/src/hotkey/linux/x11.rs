@@ -0,0 +1,373 @@
+//! X11 hotkey capture using XGrabKey.
+//!
+//! libX11 is loaded at runtime so the service still starts on systems without
+//! X11. Each key is grabbed on the root window with `AnyModifier`, so it fires
+//! regardless of other held modifiers. Detectable auto-repeat is enabled so
+//! holding the key doesn't produce synthetic release/press pairs.
+
+use super::super::backend::HotkeyEvent;
+use flowstt_common::{HotkeyBinding, KeyCode};
+use libloading::Library;
+use std::ffi::{c_char, c_int, c_long, c_uint, c_ulong};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use tracing::{debug, error, info};
+
+/// Opaque Xlib display
+type Display = std::ffi::c_void;
+type Window = c_ulong;
+type KeySym = c_ulong;
+type Bool = c_int;
+
+const KEY_PRESS: c_int = 2;
+const KEY_RELEASE: c_int = 3;
+const GRAB_MODE_ASYNC: c_int = 1;
+const ANY_MODIFIER: c_uint = 1 << 15;
+
+/// X11 keysyms for PTT keys
+mod keysym {
+    use super::KeySym;
+
+    pub const ALT_R: KeySym = 0xffea;
+    pub const ALT_L: KeySym = 0xffe9;
+    pub const CONTROL_R: KeySym = 0xffe4;
+    pub const CONTROL_L: KeySym = 0xffe3;
+    pub const SHIFT_R: KeySym = 0xffe2;
+    pub const SHIFT_L: KeySym = 0xffe1;
+    pub const CAPS_LOCK: KeySym = 0xffe5;
+    /// Right Alt on layouts where it acts as AltGr
+    pub const ISO_LEVEL3_SHIFT: KeySym = 0xfe03;
+    pub const F13: KeySym = 0xffca;
+    pub const F14: KeySym = 0xffcb;
+    pub const F15: KeySym = 0xffcc;
+    pub const F16: KeySym = 0xffcd;
+    pub const F17: KeySym = 0xffce;
+    pub const F18: KeySym = 0xffcf;
+    pub const F19: KeySym = 0xffd0;
+    pub const F20: KeySym = 0xffd1;
+}
+
+/// Convert KeyCode to candidate X11 keysyms, in order of preference
+fn keycode_to_keysyms(key: KeyCode) -> &'static [KeySym] {
+    match key {
+        KeyCode::RightAlt => &[keysym::ALT_R, keysym::ISO_LEVEL3_SHIFT],
+        KeyCode::LeftAlt => &[keysym::ALT_L],
+        KeyCode::RightControl => &[keysym::CONTROL_R],
+        KeyCode::LeftControl => &[keysym::CONTROL_L],
+        KeyCode::RightShift => &[keysym::SHIFT_R],
+        KeyCode::LeftShift => &[keysym::SHIFT_L],
+        KeyCode::CapsLock => &[keysym::CAPS_LOCK],
+        KeyCode::F13 => &[keysym::F13],
+        KeyCode::F14 => &[keysym::F14],
+        KeyCode::F15 => &[keysym::F15],
+        KeyCode::F16 => &[keysym::F16],
+        KeyCode::F17 => &[keysym::F17],
+        KeyCode::F18 => &[keysym::F18],
+        KeyCode::F19 => &[keysym::F19],
+        KeyCode::F20 => &[keysym::F20],
+    }
+}
+
+/// XKeyEvent matching the C struct layout from Xlib.h
+#[repr(C)]
+#[derive(Clone, Copy)]
+#[allow(dead_code)]
+struct XKeyEvent {
+    type_: c_int,
+    serial: c_ulong,
+    send_event: Bool,
+    display: *mut Display,
+    window: Window,
+    root: Window,
+    subwindow: Window,
+    time: c_ulong,
+    x: c_int,
+    y: c_int,
+    x_root: c_int,
+    y_root: c_int,
+    state: c_uint,
+    keycode: c_uint,
+    same_screen: Bool,
+}
+
+/// XEvent union (only the key event member is needed)
+#[repr(C)]
+#[allow(dead_code)]
+union XEvent {
+    type_: c_int,
+    key: XKeyEvent,
+    pad: [c_long; 24],
+}
+
+type XErrorHandler = Option<unsafe extern "C" fn(*mut Display, *mut std::ffi::c_void) -> c_int>;
+
+/// Wrapper around the loaded libX11
+struct Xlib {
+    _lib: Library,
+    open_display: unsafe extern "C" fn(*const c_char) -> *mut Display,
+    close_display: unsafe extern "C" fn(*mut Display) -> c_int,
+    default_root_window: unsafe extern "C" fn(*mut Display) -> Window,
+    keysym_to_keycode: unsafe extern "C" fn(*mut Display, KeySym) -> u8,
+    grab_key:
+        unsafe extern "C" fn(*mut Display, c_int, c_uint, Window, Bool, c_int, c_int) -> c_int,
+    ungrab_key: unsafe extern "C" fn(*mut Display, c_int, c_uint, Window) -> c_int,
+    pending: unsafe extern "C" fn(*mut Display) -> c_int,
+    next_event: unsafe extern "C" fn(*mut Display, *mut XEvent) -> c_int,
+    connection_number: unsafe extern "C" fn(*mut Display) -> c_int,
+    sync: unsafe extern "C" fn(*mut Display, Bool) -> c_int,
+    set_error_handler: unsafe extern "C" fn(XErrorHandler) -> XErrorHandler,
+    xkb_set_detectable_auto_repeat: unsafe extern "C" fn(*mut Display, Bool, *mut Bool) -> Bool,
+}
+
+impl Xlib {
+    /// Load libX11 and resolve the required symbols
+    fn load() -> Result<Self, String> {
+        unsafe {
+            let lib = Library::new("libX11.so.6")
+                .or_else(|_| Library::new("libX11.so"))
+                .map_err(|e| format!("libX11 not found: {}", e))?;
+
+            macro_rules! sym {
+                ($name:literal) => {
+                    *lib.get(concat!($name, "\0").as_bytes())
+                        .map_err(|e| format!("Failed to load {}: {}", $name, e))?
+                };
+            }
+
+            Ok(Self {
+                open_display: sym!("XOpenDisplay"),
+                close_display: sym!("XCloseDisplay"),
+                default_root_window: sym!("XDefaultRootWindow"),
+                keysym_to_keycode: sym!("XKeysymToKeycode"),
+                grab_key: sym!("XGrabKey"),
+                ungrab_key: sym!("XUngrabKey"),
+                pending: sym!("XPending"),
+                next_event: sym!("XNextEvent"),
+                connection_number: sym!("XConnectionNumber"),
+                sync: sym!("XSync"),
+                set_error_handler: sym!("XSetErrorHandler"),
+                xkb_set_detectable_auto_repeat: sym!("XkbSetDetectableAutoRepeat"),
+                _lib: lib,
+            })
+        }
+    }
+
+    /// Open the display named by $DISPLAY
+    fn open(&self) -> Result<*mut Display, String> {
+        let display = unsafe { (self.open_display)(std::ptr::null()) };
+        if display.is_null() {
+            return Err(format!(
+                "Cannot open X display {:?}",
+                std::env::var("DISPLAY").unwrap_or_default()
+            ));
+        }
+        Ok(display)
+    }
+}
+
+/// Set by the error handler when an X request fails (e.g. BadAccess on XGrabKey)
+static X_ERROR: AtomicBool = AtomicBool::new(false);
+
+unsafe extern "C" fn record_x_error(
+    _display: *mut Display,
+    _event: *mut std::ffi::c_void,
+) -> c_int {
+    X_ERROR.store(true, Ordering::SeqCst);
+    0
+}
+
+/// Check whether X11 hotkey capture can be used.
+pub fn probe() -> Result<(), String> {
+    let xlib = Xlib::load()?;
+    let display = xlib.open()?;
+    unsafe { (xlib.close_display)(display) };
+    Ok(())
+}
+
+/// An active X11 key grab running on its own thread.
+pub struct X11Grab {
+    running: Arc<AtomicBool>,
+    thread_handle: Option<JoinHandle<()>>,
+}
+
+impl X11Grab {
+    /// Grab the bound keys and start delivering events.
+    ///
+    /// Returns once the grabs are in place, or with an error if any failed
+    /// (e.g. another application already grabbed the key).
+    pub fn start(bindings: &[HotkeyBinding], sender: Sender<HotkeyEvent>) -> Result<Self, String> {
+        let running = Arc::new(AtomicBool::new(true));
+        let (ready_sender, ready_receiver) = mpsc::channel();
+
+        let keys: Vec<KeyCode> = bindings.iter().map(|b| b.key).collect();
+        let thread_running = running.clone();
+        let handle = thread::spawn(move || {
+            if let Err(e) = run_event_loop(&keys, sender, ready_sender, &thread_running) {
+                error!("[Hotkey] X11 event loop error: {}", e);
+            }
+            thread_running.store(false, Ordering::SeqCst);
+            info!("[Hotkey] X11 event loop thread exiting");
+        });
+
+        match ready_receiver.recv_timeout(std::time::Duration::from_secs(5)) {
+            Ok(Ok(())) => Ok(Self {
+                running,
+                thread_handle: Some(handle),
+            }),
+            Ok(Err(e)) => {
+                let _ = handle.join();
+                Err(e)
+            }
+            Err(_) => {
+                running.store(false, Ordering::SeqCst);
+                Err("Timed out waiting for X11 key grab".to_string())
+            }
+        }
+    }
+
+    /// Release the grab and stop the event thread.
+    pub fn stop(mut self) {
+        self.running.store(false, Ordering::SeqCst);
+        if let Some(handle) = self.thread_handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// A grabbed key and its pressed state
+struct GrabbedKey {
+    key: KeyCode,
+    keycode: c_int,
+    down: bool,
+}
+
+/// Grab the keys and process X events until `running` is cleared.
+fn run_event_loop(
+    keys: &[KeyCode],
+    sender: Sender<HotkeyEvent>,
+    ready: Sender<Result<(), String>>,
+    running: &AtomicBool,
+) -> Result<(), String> {
+    let setup = || -> Result<(Xlib, *mut Display, Window, Vec<GrabbedKey>), String> {
+        let xlib = Xlib::load()?;
+        let display = xlib.open()?;
+
+        unsafe {
+            let root = (xlib.default_root_window)(display);
+
+            // Suppress synthetic release events while a key auto-repeats
+            (xlib.xkb_set_detectable_auto_repeat)(display, 1, std::ptr::null_mut());
+
+            let mut grabbed = Vec::with_capacity(keys.len());
+            for &key in keys {
+                let keycode = keycode_to_keysyms(key)
+                    .iter()
+                    .map(|&sym| (xlib.keysym_to_keycode)(display, sym))
+                    .find(|&code| code != 0)
+                    .map(c_int::from);
+                let Some(keycode) = keycode else {
+                    // Closing the display releases any grabs already made
+                    (xlib.close_display)(display);
+                    return Err(format!(
+                        "{:?} is not mapped on the current keyboard layout",
+                        key
+                    ));
+                };
+
+                // XGrabKey reports failure asynchronously; sync to collect errors
+                X_ERROR.store(false, Ordering::SeqCst);
+                let previous_handler = (xlib.set_error_handler)(Some(record_x_error));
+                (xlib.grab_key)(
+                    display,
+                    keycode,
+                    ANY_MODIFIER,
+                    root,
+                    0,
+                    GRAB_MODE_ASYNC,
+                    GRAB_MODE_ASYNC,
+                );
+                (xlib.sync)(display, 0);
+                (xlib.set_error_handler)(previous_handler);
+
+                if X_ERROR.load(Ordering::SeqCst) {
+                    (xlib.close_display)(display);
+                    return Err(format!(
+                        "{:?} is already grabbed by another application",
+                        key
+                    ));
+                }
+
+                info!(
+                    "[Hotkey] X11 grab active for {:?} (keycode {})",
+                    key, keycode
+                );
+                grabbed.push(GrabbedKey {
+                    key,
+                    keycode,
+                    down: false,
+                });
+            }
+
+            Ok((xlib, display, root, grabbed))
+        }
+    };
+
+    let (xlib, display, root, mut grabbed) = match setup() {
+        Ok(grab) => grab,
+        Err(e) => {
+            let _ = ready.send(Err(e.clone()));
+            return Err(e);
+        }
+    };
+
+    let _ = ready.send(Ok(()));
+
+    let fd = unsafe { (xlib.connection_number)(display) };
+
+    while running.load(Ordering::SeqCst) {
+        unsafe {
+            while (xlib.pending)(display) > 0 {
+                let mut event: XEvent = std::mem::zeroed();
+                (xlib.next_event)(display, &mut event);
+
+                if event.type_ != KEY_PRESS && event.type_ != KEY_RELEASE {
+                    continue;
+                }
+                let keycode = event.key.keycode as c_int;
+                let Some(target) = grabbed.iter_mut().find(|g| g.keycode == keycode) else {
+                    continue;
+                };
+
+                if event.type_ == KEY_PRESS && !target.down {
+                    target.down = true;
+                    info!("[Hotkey] {:?} DOWN", target.key);
+                    let _ = sender.send(HotkeyEvent::Pressed(target.key));
+                } else if event.type_ == KEY_RELEASE && target.down {
+                    target.down = false;
+                    info!("[Hotkey] {:?} UP", target.key);
+                    let _ = sender.send(HotkeyEvent::Released(target.key));
+                }
+            }
+
+            // Wait for more events, waking periodically to check for stop
+            let mut pfd = libc::pollfd {
+                fd,
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            libc::poll(&mut pfd, 1, 100);
+        }
+    }
+
+    unsafe {
+        for target in &grabbed {
+            (xlib.ungrab_key)(display, target.keycode, ANY_MODIFIER, root);
+        }
+        (xlib.close_display)(display);
+    }
+
+    debug!("[Hotkey] X11 grab released");
+    Ok(())
+}
@@ -4,7 +4,7 @@
 //! global keyboard events. It requires Accessibility permission to function.
 
 use super::backend::{HotkeyBackend, HotkeyEvent};
-use flowstt_common::KeyCode;
+use flowstt_common::{HotkeyBinding, KeyCode};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::{self, Receiver, Sender};
 use std::sync::Arc;
@@ -109,7 +109,7 @@ impl MacOSHotkeyBackend {
 }
 
 impl HotkeyBackend for MacOSHotkeyBackend {
-    fn start(&mut self, key: KeyCode) -> Result<(), String> {
+    fn start(&mut self, bindings: &[HotkeyBinding]) -> Result<(), String> {
         if self.running.load(Ordering::SeqCst) {
             return Err("Hotkey backend already running".to_string());
         }
@@ -132,16 +132,23 @@ impl HotkeyBackend for MacOSHotkeyBackend {
         let running = self.running.clone();
         running.store(true, Ordering::SeqCst);
 
-        let target_keycode = keycode_to_macos(key);
+        let targets: Vec<TargetKey> = bindings
+            .iter()
+            .map(|binding| TargetKey {
+                key: binding.key,
+                keycode: keycode_to_macos(binding.key),
+                down: AtomicBool::new(false),
+            })
+            .collect();
 
         // Spawn the event tap thread
         let handle = thread::spawn(move || {
             info!(
-                "[Hotkey] Starting macOS event tap for key code {}",
-                target_keycode
+                "[Hotkey] Starting macOS event tap for {} keys",
+                targets.len()
             );
 
-            if let Err(e) = run_event_tap(running.clone(), sender, target_keycode) {
+            if let Err(e) = run_event_tap(running.clone(), sender, targets) {
                 error!("[Hotkey] Event tap error: {}", e);
             }
 
@@ -200,7 +207,7 @@ impl Drop for MacOSHotkeyBackend {
 fn run_event_tap(
     running: Arc<AtomicBool>,
     sender: Sender<HotkeyEvent>,
-    target_keycode: u16,
+    targets: Vec<TargetKey>,
 ) -> Result<(), String> {
     unsafe {
         // Create a mach port for the event tap
@@ -209,11 +216,7 @@ fn run_event_tap(
             | (1 << macos_ffi::kCGEventFlagsChanged);
 
         // Store context for the callback
-        let context = Box::new(EventTapContext {
-            sender,
-            target_keycode,
-            key_down: AtomicBool::new(false),
-        });
+        let context = Box::new(EventTapContext { sender, targets });
         let context_ptr = Box::into_raw(context);
 
         let tap = macos_ffi::CGEventTapCreate(
@@ -282,8 +285,14 @@ fn run_event_tap(
 /// Context passed to the event tap callback
 struct EventTapContext {
     sender: Sender<HotkeyEvent>,
-    target_keycode: u16,
-    key_down: AtomicBool,
+    targets: Vec<TargetKey>,
+}
+
+/// A bound key and its pressed state
+struct TargetKey {
+    key: KeyCode,
+    keycode: u16,
+    down: AtomicBool,
 }
 
 /// CGEventTap callback function
@@ -301,10 +310,10 @@ extern "C" fn event_tap_callback(
             macos_ffi::CGEventGetIntegerValueField(event, macos_ffi::kCGKeyboardEventKeycode)
         } as u16;
 
-        if keycode == context.target_keycode {
+        if let Some(target) = context.targets.iter().find(|t| t.keycode == keycode) {
             // Check if the modifier is pressed by looking at the flags
             let flags = unsafe { macos_ffi::CGEventGetFlags(event) };
-            let is_pressed = match context.target_keycode {
+            let is_pressed = match target.keycode {
                 keycode::RIGHT_OPTION | keycode::LEFT_OPTION => {
                     (flags & macos_ffi::kCGEventFlagMaskAlternate) != 0
                 }
@@ -318,16 +327,16 @@ extern "C" fn event_tap_callback(
                 _ => false,
             };
 
-            let was_down = context.key_down.load(Ordering::SeqCst);
+            let was_down = target.down.load(Ordering::SeqCst);
 
             if is_pressed && !was_down {
-                context.key_down.store(true, Ordering::SeqCst);
-                debug!("[Hotkey] PTT key pressed (flags changed)");
-                let _ = context.sender.send(HotkeyEvent::Pressed);
+                target.down.store(true, Ordering::SeqCst);
+                debug!("[Hotkey] {:?} pressed (flags changed)", target.key);
+                let _ = context.sender.send(HotkeyEvent::Pressed(target.key));
             } else if !is_pressed && was_down {
-                context.key_down.store(false, Ordering::SeqCst);
-                debug!("[Hotkey] PTT key released (flags changed)");
-                let _ = context.sender.send(HotkeyEvent::Released);
+                target.down.store(false, Ordering::SeqCst);
+                debug!("[Hotkey] {:?} released (flags changed)", target.key);
+                let _ = context.sender.send(HotkeyEvent::Released(target.key));
             }
         }
     }
@@ -337,17 +346,17 @@ extern "C" fn event_tap_callback(
             macos_ffi::CGEventGetIntegerValueField(event, macos_ffi::kCGKeyboardEventKeycode)
         } as u16;
 
-        if keycode == context.target_keycode {
+        if let Some(target) = context.targets.iter().find(|t| t.keycode == keycode) {
             if event_type == macos_ffi::kCGEventKeyDown {
-                let was_down = context.key_down.swap(true, Ordering::SeqCst);
+                let was_down = target.down.swap(true, Ordering::SeqCst);
                 if !was_down {
-                    debug!("[Hotkey] PTT key pressed (key down)");
-                    let _ = context.sender.send(HotkeyEvent::Pressed);
+                    debug!("[Hotkey] {:?} pressed (key down)", target.key);
+                    let _ = context.sender.send(HotkeyEvent::Pressed(target.key));
                 }
             } else {
-                context.key_down.store(false, Ordering::SeqCst);
-                debug!("[Hotkey] PTT key released (key up)");
-                let _ = context.sender.send(HotkeyEvent::Released);
+                target.down.store(false, Ordering::SeqCst);
+                debug!("[Hotkey] {:?} released (key up)", target.key);
+                let _ = context.sender.send(HotkeyEvent::Released(target.key));
             }
         }
     }
@@ -1,9 +1,9 @@
-//! Global hotkey capture for push-to-talk functionality.
+//! Global hotkey capture for push-to-talk and other hotkey actions.
 //!
 //! This module provides platform-specific global hotkey capture:
 //! - macOS: CGEventTap API (requires Accessibility permission)
-//! - Windows: Stub (not yet implemented)
-//! - Linux: Stub (not yet implemented)
+//! - Windows: Raw Input API (no special permissions needed)
+//! - Linux: XGrabKey on X11, GlobalShortcuts portal on Wayland
 
 mod backend;
 
@@ -18,7 +18,7 @@ mod linux;
 
 pub use backend::{HotkeyBackend, HotkeyEvent};
 
-use flowstt_common::KeyCode;
+use flowstt_common::HotkeyBinding;
 use std::sync::{Arc, Mutex, OnceLock};
 
 /// Global hotkey backend singleton.
@@ -68,11 +68,12 @@ pub fn get_hotkey_backend() -> Option<Arc<Mutex<Box<dyn HotkeyBackend>>>> {
     HOTKEY_BACKEND.get().cloned()
 }
 
-/// Start hotkey monitoring with the specified key.
-pub fn start_hotkey(key: KeyCode) -> Result<(), String> {
+/// Start hotkey monitoring for the given bindings, replacing any running monitor.
+pub fn start_hotkeys(bindings: &[HotkeyBinding]) -> Result<(), String> {
     let backend = get_hotkey_backend().ok_or("Hotkey backend not available")?;
     let mut backend = backend.lock().map_err(|e| format!("Lock error: {}", e))?;
-    backend.start(key)
+    backend.stop();
+    backend.start(bindings)
 }
 
 /// Stop hotkey monitoring.
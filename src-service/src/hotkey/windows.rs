@@ -5,21 +5,24 @@
 //! message-only window to receive WM_INPUT messages.
 
 use super::backend::{HotkeyBackend, HotkeyEvent};
-use flowstt_common::KeyCode;
+use flowstt_common::{HotkeyBinding, KeyCode};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::{self, Receiver, Sender};
 use std::sync::Arc;
 use std::thread::{self, JoinHandle};
 use tracing::{debug, error, info};
-use windows::Win32::Foundation::{HWND, LPARAM, LRESULT, WPARAM};
+use windows::Win32::Foundation::{
+    GetLastError, ERROR_CLASS_ALREADY_EXISTS, HWND, LPARAM, LRESULT, WPARAM,
+};
+use windows::Win32::System::Threading::GetCurrentThreadId;
 use windows::Win32::UI::Input::{
     GetRawInputData, RegisterRawInputDevices, HRAWINPUT, RAWINPUT, RAWINPUTDEVICE, RAWINPUTHEADER,
     RIDEV_INPUTSINK, RID_INPUT, RIM_TYPEKEYBOARD,
 };
 use windows::Win32::UI::WindowsAndMessaging::{
-    CreateWindowExW, DefWindowProcW, DestroyWindow, DispatchMessageW, PeekMessageW,
-    PostThreadMessageW, RegisterClassW, TranslateMessage, HWND_MESSAGE, MSG, PM_REMOVE, WM_INPUT,
-    WM_QUIT, WNDCLASSW, WS_OVERLAPPED,
+    CreateWindowExW, DefWindowProcW, DestroyWindow, DispatchMessageW, GetMessageW,
+    PostThreadMessageW, RegisterClassW, TranslateMessage, HWND_MESSAGE, MSG, WM_INPUT, WM_QUIT,
+    WNDCLASSW, WS_OVERLAPPED,
 };
 
 /// Raw input keyboard flags
@@ -104,7 +107,7 @@ impl WindowsHotkeyBackend {
 }
 
 impl HotkeyBackend for WindowsHotkeyBackend {
-    fn start(&mut self, key: KeyCode) -> Result<(), String> {
+    fn start(&mut self, bindings: &[HotkeyBinding]) -> Result<(), String> {
         if self.running.load(Ordering::SeqCst) {
             return Err("Hotkey backend already running".to_string());
         }
@@ -115,35 +118,48 @@ impl HotkeyBackend for WindowsHotkeyBackend {
         let running = self.running.clone();
         running.store(true, Ordering::SeqCst);
 
-        let (target_vk, target_requires_e0) = keycode_to_raw_input(key);
+        let targets: Vec<TargetKey> = bindings
+            .iter()
+            .map(|binding| {
+                let (vk, requires_e0) = keycode_to_raw_input(binding.key);
+                TargetKey {
+                    key: binding.key,
+                    vk,
+                    requires_e0,
+                    down: false,
+                }
+            })
+            .collect();
 
-        // Channel to receive thread ID from the spawned thread
-        let (tid_sender, tid_receiver) = mpsc::channel();
+        // Channel to receive the thread ID (or a setup error) from the spawned thread
+        let (ready_sender, ready_receiver) = mpsc::channel();
 
         // Spawn the message loop thread
         let handle = thread::spawn(move || {
-            // Get and send our thread ID
-            let thread_id = unsafe { windows::Win32::System::Threading::GetCurrentThreadId() };
-            let _ = tid_sender.send(thread_id);
-
             info!(
-                "[Hotkey] Starting Windows Raw Input message loop for VK {} (E0={})",
-                target_vk, target_requires_e0
+                "[Hotkey] Starting Windows Raw Input message loop for {} keys",
+                targets.len()
             );
 
-            if let Err(e) = run_message_loop(running.clone(), sender, target_vk, target_requires_e0)
-            {
+            if let Err(e) = run_message_loop(sender, ready_sender, targets) {
                 error!("[Hotkey] Message loop error: {}", e);
             }
 
+            running.store(false, Ordering::SeqCst);
             info!("[Hotkey] Message loop thread exiting");
         });
 
-        // Wait for thread ID
-        match tid_receiver.recv_timeout(std::time::Duration::from_secs(5)) {
-            Ok(tid) => {
+        // Wait until raw input is registered so setup errors reach the caller
+        match ready_receiver.recv_timeout(std::time::Duration::from_secs(5)) {
+            Ok(Ok(tid)) => {
                 self.thread_id = Some(tid);
             }
+            Ok(Err(e)) => {
+                let _ = handle.join();
+                self.receiver = None;
+                self.unavailable_reason = Some(e.clone());
+                return Err(e);
+            }
             Err(_) => {
                 self.running.store(false, Ordering::SeqCst);
                 return Err("Failed to get message loop thread ID".to_string());
@@ -157,7 +173,8 @@ impl HotkeyBackend for WindowsHotkeyBackend {
     }
 
     fn stop(&mut self) {
-        if !self.running.load(Ordering::SeqCst) {
+        // The thread may have exited on its own; still join it
+        if !self.running.load(Ordering::SeqCst) && self.thread_handle.is_none() {
             return;
         }
 
@@ -211,92 +228,106 @@ thread_local! {
 /// Context for hotkey event handling
 struct HotkeyContext {
     sender: Sender<HotkeyEvent>,
-    target_vk: u16,
-    target_requires_e0: bool,
-    key_down: bool,
+    targets: Vec<TargetKey>,
 }
 
-/// Run the Windows message loop on this thread
+/// A bound key as Raw Input reports it
+struct TargetKey {
+    key: KeyCode,
+    vk: u16,
+    requires_e0: bool,
+    down: bool,
+}
+
+/// Create the message-only window and register it for raw keyboard input.
+unsafe fn create_input_window() -> Result<HWND, String> {
+    // Register window class (it survives from a previous start, which is fine)
+    let class_name = windows::core::w!("FlowSTT_HotkeyClass");
+    let wc = WNDCLASSW {
+        lpfnWndProc: Some(window_proc),
+        lpszClassName: class_name,
+        ..Default::default()
+    };
+
+    if RegisterClassW(&wc) == 0 && GetLastError() != ERROR_CLASS_ALREADY_EXISTS {
+        return Err("Failed to register window class".to_string());
+    }
+
+    // Create a message-only window (invisible, just for receiving messages)
+    let hwnd = CreateWindowExW(
+        Default::default(),
+        class_name,
+        windows::core::w!("FlowSTT Hotkey"),
+        WS_OVERLAPPED,
+        0,
+        0,
+        0,
+        0,
+        HWND_MESSAGE, // Message-only window
+        None,
+        None,
+        None,
+    )
+    .map_err(|e| format!("Failed to create message window: {}", e))?;
+
+    // Register for raw keyboard input with RIDEV_INPUTSINK to receive input even when not focused
+    let rid = RAWINPUTDEVICE {
+        usUsagePage: 0x01, // Generic Desktop Controls
+        usUsage: 0x06,     // Keyboard
+        dwFlags: RIDEV_INPUTSINK,
+        hwndTarget: hwnd,
+    };
+
+    RegisterRawInputDevices(&[rid], size_of::<RAWINPUTDEVICE>() as u32).map_err(|e| {
+        let _ = DestroyWindow(hwnd);
+        format!("Failed to register raw input device: {}", e)
+    })?;
+
+    Ok(hwnd)
+}
+
+/// Run the Windows message loop on this thread until WM_QUIT is posted.
+///
+/// Sends this thread's ID on `ready` once raw input is registered (the thread
+/// has a message queue by then, so WM_QUIT can't be lost), or the setup error.
 fn run_message_loop(
-    running: Arc<AtomicBool>,
     sender: Sender<HotkeyEvent>,
-    target_vk: u16,
-    target_requires_e0: bool,
+    ready: Sender<Result<u32, String>>,
+    targets: Vec<TargetKey>,
 ) -> Result<(), String> {
     unsafe {
-        // Register window class
-        let class_name = windows::core::w!("FlowSTT_HotkeyClass");
-        let wc = WNDCLASSW {
-            lpfnWndProc: Some(window_proc),
-            lpszClassName: class_name,
-            ..Default::default()
-        };
-
-        let atom = RegisterClassW(&wc);
-        if atom == 0 {
-            return Err("Failed to register window class".to_string());
-        }
-
-        // Create a message-only window (invisible, just for receiving messages)
-        let hwnd = CreateWindowExW(
-            Default::default(),
-            class_name,
-            windows::core::w!("FlowSTT Hotkey"),
-            WS_OVERLAPPED,
-            0,
-            0,
-            0,
-            0,
-            HWND_MESSAGE, // Message-only window
-            None,
-            None,
-            None,
-        )
-        .map_err(|e| format!("Failed to create message window: {}", e))?;
-
-        // Register for raw keyboard input with RIDEV_INPUTSINK to receive input even when not focused
-        let rid = RAWINPUTDEVICE {
-            usUsagePage: 0x01, // Generic Desktop Controls
-            usUsage: 0x06,     // Keyboard
-            dwFlags: RIDEV_INPUTSINK,
-            hwndTarget: hwnd,
+        let hwnd = match create_input_window() {
+            Ok(hwnd) => hwnd,
+            Err(e) => {
+                let _ = ready.send(Err(e.clone()));
+                return Err(e);
+            }
         };
 
-        RegisterRawInputDevices(&[rid], size_of::<RAWINPUTDEVICE>() as u32).map_err(|e| {
-            let _ = DestroyWindow(hwnd);
-            format!("Failed to register raw input device: {}", e)
-        })?;
-
         info!("[Hotkey] Raw input registered, message loop ready");
 
         // Set up thread-local context
         HOTKEY_CONTEXT.with(|ctx| {
-            *ctx.borrow_mut() = Some(HotkeyContext {
-                sender,
-                target_vk,
-                target_requires_e0,
-                key_down: false,
-            });
+            *ctx.borrow_mut() = Some(HotkeyContext { sender, targets });
         });
 
-        // Message loop using PeekMessageW for non-blocking operation
-        let mut msg = MSG::default();
-
-        while running.load(Ordering::SeqCst) {
-            // Non-blocking message check
-            while PeekMessageW(&mut msg, None, 0, 0, PM_REMOVE).as_bool() {
-                if msg.message == WM_QUIT {
-                    debug!("[Hotkey] Received WM_QUIT, exiting loop");
-                    running.store(false, Ordering::SeqCst);
-                    break;
-                }
+        let _ = ready.send(Ok(GetCurrentThreadId()));
 
-                let _ = TranslateMessage(&msg);
-                DispatchMessageW(&msg);
+        // Block until messages arrive; GetMessageW returns 0 on WM_QUIT and -1 on error
+        let mut msg = MSG::default();
+        loop {
+            let result = GetMessageW(&mut msg, None, 0, 0);
+            if result.0 == 0 {
+                debug!("[Hotkey] Received WM_QUIT, exiting loop");
+                break;
+            }
+            if result.0 == -1 {
+                error!("[Hotkey] GetMessageW failed");
+                break;
             }
 
-            // Sleep briefly to avoid busy-waiting
-            std::thread::sleep(std::time::Duration::from_millis(10));
+            let _ = TranslateMessage(&msg);
+            DispatchMessageW(&msg);
         }
 
         // Cleanup
@@ -372,19 +403,22 @@ unsafe fn handle_raw_input(hrawinput: HRAWINPUT) {
 
     HOTKEY_CONTEXT.with(|ctx| {
         if let Some(ref mut context) = *ctx.borrow_mut() {
-            // Check if this is our target key
             // For modifier keys, we need to match both VK code AND the E0 flag
-            let is_target_key = vk_code == context.target_vk && is_e0 == context.target_requires_e0;
-
-            if is_target_key {
-                if is_key_up && context.key_down {
-                    context.key_down = false;
-                    info!("[PTT] Key UP");
-                    let _ = context.sender.send(HotkeyEvent::Released);
-                } else if !is_key_up && !context.key_down {
-                    context.key_down = true;
-                    info!("[PTT] Key DOWN");
-                    let _ = context.sender.send(HotkeyEvent::Pressed);
+            let sender = &context.sender;
+            let target = context
+                .targets
+                .iter_mut()
+                .find(|t| t.vk == vk_code && t.requires_e0 == is_e0);
+
+            if let Some(target) = target {
+                if is_key_up && target.down {
+                    target.down = false;
+                    info!("[Hotkey] {:?} UP", target.key);
+                    let _ = sender.send(HotkeyEvent::Released(target.key));
+                } else if !is_key_up && !target.down {
+                    target.down = true;
+                    info!("[Hotkey] {:?} DOWN", target.key);
+                    let _ = sender.send(HotkeyEvent::Pressed(target.key));
                 }
                 // Note: We don't log repeated key-down events (auto-repeat while held)
             }
@@ -0,0 +1,421 @@
+//! Embedded HTTP API for status and control.
+//!
+//! Exposes a small REST interface for home-automation setups and remote
+//! control without the CLI. Each endpoint maps to an IPC request and returns
+//! the IPC response as JSON, except `/metrics`, which serves the Prometheus
+//! text format for monitoring. The server is disabled unless `http_address`
+//! is set in the config.
+//!
+//! Web pages the user visits can send requests to it too, so every request
+//! is checked before it is handled:
+//! - With `http_token` set, requests must carry it as a bearer token.
+//! - Without one, the Host header must name the address the server is bound
+//!   to, so a page can't reach it through DNS rebinding. That only keeps out
+//!   web pages, not other machines, so binding to anything but a loopback
+//!   address needs a token.
+//! - POST bodies must be declared as JSON, which a page can only do with
+//!   CORS, and the API doesn't allow it.
+
+use axum::extract::{Path, Query, State};
+use axum::http::{header, HeaderMap, Method, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::IntoResponse;
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use flowstt_common::ipc::{ErrorCode, Request, Response};
+use flowstt_common::{AudioSourceType, SessionFilter};
+use serde::Deserialize;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::info;
+
+use crate::ipc::authorize;
+use crate::ipc::handlers::handle_request;
+use crate::platform;
+
+type HttpResponse = (StatusCode, Json<Response>);
+
+#[derive(Deserialize)]
+struct RecentQuery {
+    seconds: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct DevicesQuery {
+    source_type: Option<AudioSourceType>,
+}
+
+#[derive(Deserialize, Default)]
+struct StartBody {
+    source1_id: Option<String>,
+    source2_id: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TranscriptsQuery {
+    limit: Option<usize>,
+    tag: Option<String>,
+    participant: Option<String>,
+    q: Option<String>,
+}
+
+/// Who may use the API: the Host headers it answers to and the token it
+/// requires, if any.
+struct Access {
+    hosts: Vec<String>,
+    token: Option<String>,
+}
+
+impl Access {
+    /// Access to a server bound to `address`.
+    fn new(address: SocketAddr, token: Option<String>) -> Result<Self, String> {
+        // Any client on the network can send a Host header that matches
+        if token.is_none() && !address.ip().is_loopback() {
+            return Err(format!(
+                "HTTP API on {} requires http_token to be set; bind it to a loopback address \
+                 to run it without one",
+                address
+            ));
+        }
+        let port = address.port();
+        let mut hosts = vec![address.to_string()];
+        if address.ip().is_loopback() {
+            hosts.push(format!("localhost:{}", port));
+        }
+        // Browsers leave the default port out of the Host header
+        if port == 80 {
+            let bare: Vec<String> = hosts
+                .iter()
+                .filter_map(|host| host.strip_suffix(":80").map(str::to_string))
+                .collect();
+            hosts.extend(bare);
+        }
+        Ok(Self { hosts, token })
+    }
+
+    /// Why a request must be refused, if it must.
+    fn refusal(&self, method: &Method, headers: &HeaderMap) -> Option<(StatusCode, &'static str)> {
+        let header =
+            |name: header::HeaderName| headers.get(name).and_then(|value| value.to_str().ok());
+        match &self.token {
+            Some(token) => {
+                let bearer = header(header::AUTHORIZATION)
+                    .and_then(|value| value.strip_prefix("Bearer "))
+                    .unwrap_or_default();
+                if !constant_time_eq(bearer.as_bytes(), token.as_bytes()) {
+                    return Some((StatusCode::UNAUTHORIZED, "Missing or wrong bearer token"));
+                }
+            }
+            None => {
+                let host = header(header::HOST).unwrap_or_default();
+                if !self.hosts.iter().any(|h| h.eq_ignore_ascii_case(host)) {
+                    return Some((StatusCode::FORBIDDEN, "Host not allowed"));
+                }
+            }
+        }
+        if *method == Method::POST {
+            let json = header(header::CONTENT_TYPE)
+                .and_then(|value| value.split(';').next())
+                .is_some_and(|mime| mime.trim().eq_ignore_ascii_case("application/json"));
+            if !json {
+                return Some((
+                    StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                    "POST requests must have Content-Type: application/json",
+                ));
+            }
+        }
+        None
+    }
+}
+
+/// Compare without stopping at the first difference, so response times don't
+/// reveal how much of a guessed token is right.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// Refuse requests `Access` doesn't allow before they reach a handler.
+async fn check_access(
+    State(access): State<Arc<Access>>,
+    request: axum::extract::Request,
+    next: Next,
+) -> axum::response::Response {
+    match access.refusal(request.method(), request.headers()) {
+        Some((status, message)) => (status, Json(Response::error(message))).into_response(),
+        None => next.run(request).await,
+    }
+}
+
+/// Run the HTTP server on `address` until shutdown, requiring `token` if set.
+pub async fn run_server(address: String, token: Option<String>) -> Result<(), String> {
+    let listener = tokio::net::TcpListener::bind(&address)
+        .await
+        .map_err(|e| format!("Failed to bind HTTP API to {}: {}", address, e))?;
+    let bound = listener
+        .local_addr()
+        .map_err(|e| format!("Failed to bind HTTP API to {}: {}", address, e))?;
+    let access = Arc::new(Access::new(bound, token)?);
+
+    let app = Router::new()
+        .route("/health", get(health))
+        .route("/metrics", get(metrics))
+        .route("/status", get(status))
+        .route("/devices", get(devices))
+        .route("/start", post(start))
+        .route("/stop", post(stop))
+        .route("/mute", post(mute))
+        .route("/transcripts", get(transcripts))
+        .route("/transcripts/:id", get(transcript))
+        .route("/captures/:id", get(capture))
+        .route("/recent", get(recent))
+        .layer(middleware::from_fn_with_state(access, check_access));
+
+    info!("HTTP API listening on {}", address);
+
+    axum::serve(listener, app)
+        .with_graceful_shutdown(async {
+            while !crate::is_shutdown_requested() {
+                tokio::time::sleep(Duration::from_secs(1)).await;
+            }
+        })
+        .await
+        .map_err(|e| format!("HTTP API error: {}", e))
+}
+
+/// Handle an IPC request and map the response to an HTTP status.
+///
+/// The HTTP API can't hold control of capture, so requests that drive capture
+/// are refused while an IPC client holds it.
+async fn respond(request: Request) -> HttpResponse {
+    if let Err(response) = authorize(None, &request) {
+        return (http_status(&response), Json(response));
+    }
+    let response = handle_request(request).await;
+    (http_status(&response), Json(response))
+}
+
+/// HTTP status for an IPC response, from its error code if it is an error.
+fn http_status(response: &Response) -> StatusCode {
+    match response {
+        Response::Error { code, .. } => match code {
+            ErrorCode::SessionNotFound { .. }
+            | ErrorCode::NoRecording { .. }
+            | ErrorCode::NotFound { .. }
+            | ErrorCode::DeviceNotFound { .. } => StatusCode::NOT_FOUND,
+            ErrorCode::CaptureControlled { .. } => StatusCode::CONFLICT,
+            ErrorCode::PermissionDenied => StatusCode::FORBIDDEN,
+            ErrorCode::BackendUnavailable => StatusCode::SERVICE_UNAVAILABLE,
+            ErrorCode::StorageFailed | ErrorCode::TranscriptionFailed => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+            _ => StatusCode::BAD_REQUEST,
+        },
+        _ => StatusCode::OK,
+    }
+}
+
+async fn health() -> HttpResponse {
+    respond(Request::Ping).await
+}
+
+async fn metrics() -> impl IntoResponse {
+    match handle_request(Request::GetMetrics).await {
+        Response::Metrics(metrics) => (
+            StatusCode::OK,
+            [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+            crate::metrics::to_prometheus(&metrics),
+        ),
+        _ => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            [(header::CONTENT_TYPE, "text/plain")],
+            "Metrics unavailable\n".to_string(),
+        ),
+    }
+}
+
+async fn status() -> HttpResponse {
+    respond(Request::GetStatus).await
+}
+
+async fn devices(Query(query): Query<DevicesQuery>) -> HttpResponse {
+    respond(Request::ListDevices {
+        source_type: query.source_type,
+    })
+    .await
+}
+
+/// Start capture, using the default input device if no source is given.
+async fn start(body: Option<Json<StartBody>>) -> HttpResponse {
+    let StartBody {
+        source1_id,
+        source2_id,
+    } = body.map(|Json(body)| body).unwrap_or_default();
+
+    let source1_id = source1_id.or_else(|| {
+        platform::get_backend()
+            .and_then(|b| b.list_input_devices().into_iter().next())
+            .map(|d| d.id)
+    });
+    if source1_id.is_none() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(Response::error_with_code(
+                ErrorCode::NoAudioDevice,
+                "No audio input devices found",
+            )),
+        );
+    }
+
+    let request = Request::SetSources {
+        source1_id,
+        source2_id,
+        source1_channels: None,
+        source2_channels: None,
+    };
+    if let Err(response) = authorize(None, &request) {
+        return (StatusCode::CONFLICT, Json(response));
+    }
+
+    let ready = handle_request(Request::AppReady).await;
+    if let Response::Error { .. } = ready {
+        return (StatusCode::BAD_REQUEST, Json(ready));
+    }
+    respond(request).await
+}
+
+async fn stop() -> HttpResponse {
+    respond(Request::SetSources {
+        source1_id: None,
+        source2_id: None,
+        source1_channels: None,
+        source2_channels: None,
+    })
+    .await
+}
+
+/// Enter privacy mode. There is deliberately no unmute endpoint: listening
+/// only resumes from a local client.
+async fn mute() -> HttpResponse {
+    respond(Request::SetMuted { muted: true }).await
+}
+
+async fn transcripts(Query(query): Query<TranscriptsQuery>) -> HttpResponse {
+    respond(Request::ListSessions {
+        limit: query.limit,
+        filter: SessionFilter {
+            tag: query.tag,
+            participant: query.participant,
+            query: query.q,
+        },
+    })
+    .await
+}
+
+async fn transcript(Path(id): Path<String>) -> HttpResponse {
+    respond(Request::GetSession { id }).await
+}
+
+async fn capture(Path(id): Path<String>) -> HttpResponse {
+    respond(Request::GetCapture { id }).await
+}
+
+/// Text transcribed in the active session, the last five minutes by default.
+async fn recent(Query(query): Query<RecentQuery>) -> HttpResponse {
+    respond(Request::GetRecentTranscript {
+        seconds: query.seconds.unwrap_or(300),
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn headers(pairs: &[(header::HeaderName, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.insert(name.clone(), HeaderValue::from_str(value).unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn test_host_must_name_bound_address() {
+        let access = Access::new("127.0.0.1:7878".parse().unwrap(), None).unwrap();
+        for host in ["127.0.0.1:7878", "localhost:7878", "LOCALHOST:7878"] {
+            let request = headers(&[(header::HOST, host)]);
+            assert_eq!(access.refusal(&Method::GET, &request), None, "{}", host);
+        }
+        for host in ["attacker.example:7878", "localhost:80", ""] {
+            let request = headers(&[(header::HOST, host)]);
+            assert_eq!(
+                access.refusal(&Method::GET, &request).map(|r| r.0),
+                Some(StatusCode::FORBIDDEN),
+                "{}",
+                host
+            );
+        }
+    }
+
+    #[test]
+    fn test_post_must_be_json() {
+        let access = Access::new("127.0.0.1:7878".parse().unwrap(), None).unwrap();
+        let plain = headers(&[
+            (header::HOST, "127.0.0.1:7878"),
+            (header::CONTENT_TYPE, "text/plain"),
+        ]);
+        assert_eq!(
+            access.refusal(&Method::POST, &plain).map(|r| r.0),
+            Some(StatusCode::UNSUPPORTED_MEDIA_TYPE)
+        );
+        let missing = headers(&[(header::HOST, "127.0.0.1:7878")]);
+        assert!(access.refusal(&Method::POST, &missing).is_some());
+        let json = headers(&[
+            (header::HOST, "127.0.0.1:7878"),
+            (header::CONTENT_TYPE, "application/json; charset=utf-8"),
+        ]);
+        assert_eq!(access.refusal(&Method::POST, &json), None);
+    }
+
+    #[test]
+    fn test_token_required_off_loopback() {
+        for address in ["0.0.0.0:7878", "192.168.1.10:7878", "[::]:7878"] {
+            assert!(
+                Access::new(address.parse().unwrap(), None).is_err(),
+                "{}",
+                address
+            );
+        }
+        assert!(Access::new("[::1]:7878".parse().unwrap(), None).is_ok());
+
+        let access =
+            Access::new("192.168.1.10:7878".parse().unwrap(), Some("s3cret".into())).unwrap();
+        let request = headers(&[(header::HOST, "192.168.1.10:7878")]);
+        assert_eq!(
+            access.refusal(&Method::GET, &request).map(|r| r.0),
+            Some(StatusCode::UNAUTHORIZED)
+        );
+    }
+
+    #[test]
+    fn test_token_required_when_set() {
+        let access = Access::new("0.0.0.0:7878".parse().unwrap(), Some("s3cret".into())).unwrap();
+
+        let good = headers(&[
+            (header::HOST, "flowstt.lan:7878"),
+            (header::AUTHORIZATION, "Bearer s3cret"),
+        ]);
+        assert_eq!(access.refusal(&Method::GET, &good), None);
+        for auth in ["Bearer wrong", "s3cret", ""] {
+            let request = headers(&[(header::AUTHORIZATION, auth)]);
+            assert_eq!(
+                access.refusal(&Method::GET, &request).map(|r| r.0),
+                Some(StatusCode::UNAUTHORIZED),
+                "{}",
+                auth
+            );
+        }
+    }
+}
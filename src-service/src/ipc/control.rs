@@ -0,0 +1,122 @@
+//! Ownership of capture among connected clients.
+//!
+//! Any number of clients can query the service and subscribe to events, but
+//! only one at a time controls capture. A client takes control with
+//! `AcquireControl`, or implicitly by starting capture while nobody holds
+//! it, and keeps it until it stops capture, sends `ReleaseControl` or
+//! disconnects. Requests that drive capture from other clients are refused.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use flowstt_common::ipc::{ErrorCode, EventType, Request, Response};
+use tracing::info;
+
+use super::broadcast_event;
+
+/// Identifies a client connection for the lifetime of the service.
+pub type ClientId = u64;
+
+static NEXT_CLIENT_ID: AtomicU64 = AtomicU64::new(1);
+
+/// The controlling client, if any
+static CONTROLLER: Mutex<Option<(ClientId, String)>> = Mutex::new(None);
+
+/// Allocate an ID for a new connection.
+pub fn next_client_id() -> ClientId {
+    NEXT_CLIENT_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Name of the controlling client, if any.
+pub fn controller_name() -> Option<String> {
+    CONTROLLER
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|(_, name)| name.clone())
+}
+
+/// Take control for `client`.
+pub fn acquire(client: ClientId, name: &str, force: bool) -> Result<(), Response> {
+    let mut controller = CONTROLLER.lock().unwrap();
+    match controller.as_ref() {
+        Some((id, current)) if *id != client && !force => {
+            return Err(not_controller_error(current));
+        }
+        Some((id, current)) if *id == client && current == name => return Ok(()),
+        _ => {}
+    }
+    *controller = Some((client, name.to_string()));
+    drop(controller);
+
+    info!("Capture is now controlled by {}", name);
+    broadcast_controller(Some(name.to_string()));
+    Ok(())
+}
+
+/// Give up control if `client` holds it.
+pub fn release(client: ClientId) {
+    let mut controller = CONTROLLER.lock().unwrap();
+    if controller.as_ref().is_some_and(|(id, _)| *id == client) {
+        *controller = None;
+        drop(controller);
+
+        info!("Capture control released");
+        broadcast_controller(None);
+    }
+}
+
+/// Check that `client` may make `request`, taking control if the request
+/// starts capture while nobody holds it. `client` is None for callers that
+/// have no connection, such as the HTTP API; they can never hold control.
+pub fn authorize(client: Option<ClientId>, request: &Request) -> Result<(), Response> {
+    if !request.controls_capture() {
+        return Ok(());
+    }
+
+    let starts_capture = matches!(
+        request,
+        Request::SetSources {
+            source1_id: Some(_),
+            ..
+        }
+    );
+    let current = CONTROLLER.lock().unwrap().clone();
+    match (current, client) {
+        (Some((id, _)), Some(client)) if id == client => Ok(()),
+        (Some((_, name)), _) => Err(not_controller_error(&name)),
+        (None, Some(client)) if starts_capture => {
+            acquire(client, &format!("client {}", client), false)
+        }
+        (None, _) => Ok(()),
+    }
+}
+
+/// Whether `request` stops capture, which gives up control once it succeeds.
+pub fn stops_capture(request: &Request) -> bool {
+    matches!(
+        request,
+        Request::SetSources {
+            source1_id: None,
+            ..
+        } | Request::AppDisconnect
+    )
+}
+
+fn not_controller_error(controller: &str) -> Response {
+    Response::error_with_code(
+        ErrorCode::CaptureControlled {
+            controller: controller.to_string(),
+        },
+        format!(
+            "Capture is controlled by {}; take control with force to override",
+            controller
+        ),
+    )
+}
+
+fn broadcast_controller(controller: Option<String>) {
+    broadcast_event(Response::Event {
+        event: EventType::ControllerChanged { controller },
+    });
+}
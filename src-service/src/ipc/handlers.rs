@@ -1,18 +1,62 @@
 //! IPC request handlers.
 
-use flowstt_common::ipc::{EventType, Request, Response};
-use flowstt_common::{CudaStatus, ModelStatus, PttStatus, TranscriptionMode};
+use flowstt_common::ipc::{ErrorCode, EventType, ItemKind, Request, Response};
+use flowstt_common::{
+    AudioFault, AudioSourceType, CudaStatus, FlowSttError, HotkeyAction, HotkeyBinding, KeyCode,
+    LatencyPreset, ModelStatus, Permission, PttStatus, RecordingFormat, RecordingMode,
+    TranscriptSession, TranscriptionMode, TranscriptionResult, VoiceCommand, CAPTURE_SAMPLE_RATES,
+    MAX_CAPTURE_BUFFER_MS, MAX_ROLLING_BUFFER_SECS, MIN_CAPTURE_BUFFER_MS,
+};
+use std::future::Future;
+use std::pin::Pin;
 use std::sync::Arc;
 use tracing::info;
 
 use super::broadcast_event;
+use super::control;
+use crate::audio;
+use crate::capture;
+use crate::chapters;
+use crate::commands;
+use crate::config::{
+    CaptureMode, ChapterConfig, CueConfig, OscConfig, RecordingProcessingConfig, RedactionChannel,
+    RollingBufferConfig, TextProfile, VadRecordingConfig, VadRecordingSplit,
+};
+use crate::cues;
+use crate::dictation;
+use crate::focus;
+use crate::history;
+use crate::hooks;
 use crate::hotkey;
+use crate::logging;
+use crate::metrics;
+use crate::osc;
+use crate::output;
+use crate::permissions;
 use crate::platform;
+use crate::playback;
+use crate::postprocess;
+use crate::processor;
+use crate::profiles;
 use crate::ptt_controller;
-use crate::state::get_service_state;
-use crate::transcription::{download_model, TranscribeState, Transcriber, TranscriptionQueue};
+use crate::recent;
+use crate::redact;
+use crate::retention;
+use crate::retranscribe;
+use crate::rolling_buffer;
+use crate::search;
+use crate::silero_vad;
+use crate::state::{get_service_state, ServiceState};
+use crate::transcription::queue::QueuedSegment;
+use crate::transcription::{
+    download_model, model, pool, GpuSettings, TranscribeState, Transcriber, Transcript,
+    TranscriptionQueue, NO_SPEECH_TEXT,
+};
+use crate::vad_recording::{self, MAX_ROLL_MS};
+use crate::watch;
 use crate::{
-    is_audio_loop_active, start_audio_loop, stop_audio_loop, TranscriptionEventBroadcaster,
+    finish_calibration, is_audio_loop_active, set_vad_config, start_audio_loop, start_calibration,
+    stop_audio_loop, TranscriptionEventBroadcaster,
 };
 
 /// Global transcription queue
@@ -49,25 +93,397 @@ pub fn init_transcription_system() {
     queue.set_callback(Arc::new(TranscriptionEventBroadcaster));
 
     // Start transcription worker
-    let transcriber = Transcriber::new();
-    let model_path = transcriber.get_model_path().clone();
-    queue.start_worker(model_path);
+    queue.start_worker();
 
     info!("Transcription system initialized");
 }
 
+/// Current values of the settings exposed through `GetConfig`.
+fn config_values(state: &ServiceState) -> std::collections::BTreeMap<String, String> {
+    let recordings_dir = state
+        .recordings_dir
+        .clone()
+        .unwrap_or_else(audio::default_recordings_dir);
+    [
+        (
+            "recordings.dir",
+            recordings_dir.to_string_lossy().to_string(),
+        ),
+        (
+            "recordings.template",
+            state.recording_filename_template.clone(),
+        ),
+        (
+            "recordings.format",
+            state.recording_format.extension().to_string(),
+        ),
+        (
+            "recordings.trim_silence",
+            state.recording_processing.trim_silence.to_string(),
+        ),
+        (
+            "recordings.silence_threshold_db",
+            state.recording_processing.silence_threshold_db.to_string(),
+        ),
+        (
+            "recordings.normalize",
+            state.recording_processing.normalize.to_string(),
+        ),
+        (
+            "recordings.target_lufs",
+            state.recording_processing.target_lufs.to_string(),
+        ),
+        (
+            "audio.buffer_ms",
+            state
+                .audio_config
+                .buffer_ms
+                .map(|ms| ms.to_string())
+                .unwrap_or_default(),
+        ),
+        (
+            "audio.capture_mode",
+            state.audio_config.capture_mode.name().to_string(),
+        ),
+        (
+            "audio.sample_rate",
+            state
+                .audio_config
+                .sample_rate
+                .map(|rate| rate.to_string())
+                .unwrap_or_default(),
+        ),
+        (
+            "model.path",
+            model::model_path().to_string_lossy().to_string(),
+        ),
+        ("model.name", model::model_name()),
+        ("cues.ptt", state.cues.ptt.to_string()),
+        ("cues.transcription", state.cues.transcription.to_string()),
+        ("cues.volume", state.cues.volume.to_string()),
+        ("osc.enabled", state.osc.enabled.to_string()),
+        ("osc.address", state.osc.address.clone()),
+        ("osc.prefix", state.osc.prefix.clone()),
+        ("osc.chatbox", state.osc.chatbox.to_string()),
+        (
+            "output.template",
+            state.output_template.clone().unwrap_or_default(),
+        ),
+        (
+            "post_processing.profile",
+            state.post_processing.profile.name().to_string(),
+        ),
+        (
+            "retention.max_size_mb",
+            state
+                .retention
+                .max_size_mb
+                .map(|mb| mb.to_string())
+                .unwrap_or_default(),
+        ),
+        (
+            "retention.max_age_days",
+            state
+                .retention
+                .max_age_days
+                .map(|days| days.to_string())
+                .unwrap_or_default(),
+        ),
+        (
+            "retention.keep_transcripts",
+            state.retention.keep_transcripts.to_string(),
+        ),
+        (
+            "vad_recording.enabled",
+            state.vad_recording.enabled.to_string(),
+        ),
+        (
+            "vad_recording.split",
+            state.vad_recording.split.name().to_string(),
+        ),
+        (
+            "vad_recording.pre_roll_ms",
+            state.vad_recording.pre_roll_ms.to_string(),
+        ),
+        (
+            "vad_recording.post_roll_ms",
+            state.vad_recording.post_roll_ms.to_string(),
+        ),
+        ("latency.preset", state.latency_preset.name().to_string()),
+        (
+            "chapters.silence_secs",
+            state.chapters.silence_secs.to_string(),
+        ),
+        (
+            "chapters.interval_mins",
+            state.chapters.interval_mins.to_string(),
+        ),
+        ("rolling_buffer.secs", state.rolling_buffer.secs.to_string()),
+        (
+            "redaction.channels",
+            state
+                .redaction
+                .channels
+                .iter()
+                .map(RedactionChannel::name)
+                .collect::<Vec<_>>()
+                .join(","),
+        ),
+        ("redaction.emails", state.redaction.emails.to_string()),
+        (
+            "redaction.phone_numbers",
+            state.redaction.phone_numbers.to_string(),
+        ),
+        (
+            "redaction.card_numbers",
+            state.redaction.card_numbers.to_string(),
+        ),
+    ]
+    .into_iter()
+    .map(|(k, v)| (k.to_string(), v))
+    .collect()
+}
+
+/// Persist the user-facing settings from the service state to disk.
+fn save_config(state: &ServiceState) {
+    let config = crate::config::Config {
+        transcription_mode: state.transcription_mode,
+        ptt_key: state.ptt_key,
+        hotkeys: state.hotkeys.clone(),
+        language: state.language.clone(),
+        translate: state.translate,
+        output_mode: state.output_mode,
+        output_template: state.output_template.clone(),
+        latency_preset: state.latency_preset,
+        use_gpu: state.use_gpu,
+        gpu_device: state.gpu_device,
+        watch_folders: state.watch_folders.clone(),
+        voice_commands: state.voice_commands.clone(),
+        recordings_dir: state.recordings_dir.clone(),
+        model_path: state.model_path.clone(),
+        model_name: state.model_name.clone(),
+        recording_filename_template: state.recording_filename_template.clone(),
+        recording_format: state.recording_format,
+        recording_processing: state.recording_processing,
+        vad: state.vad_config.clone(),
+        aec: state.aec_settings,
+        spectrogram: state.spectrogram_settings,
+        http_address: state.http_address.clone(),
+        http_token: state.http_token.clone(),
+        http_public_url: state.http_public_url.clone(),
+        audio: state.audio_config.clone(),
+        post_processing: state.post_processing.clone(),
+        confidence: state.confidence,
+        cues: state.cues,
+        osc: state.osc.clone(),
+        retention: state.retention,
+        vad_recording: state.vad_recording,
+        chapters: state.chapters,
+        rolling_buffer: state.rolling_buffer,
+        redaction: state.redaction.clone(),
+        muted: state.muted,
+        profiles: state.profiles.clone(),
+        active_profile: state.active_profile.clone(),
+        hooks: state.hooks.clone(),
+        webhooks: state.webhooks.clone(),
+        calendar: state.calendar.clone(),
+    };
+    if let Err(e) = config.save() {
+        tracing::warn!("Failed to save config: {}", e);
+    }
+}
+
+/// Parse an on/off setting value; an empty value means off.
+fn parse_switch(value: &str) -> Result<bool, String> {
+    match value.to_ascii_lowercase().as_str() {
+        "" | "false" | "off" | "no" | "0" => Ok(false),
+        "true" | "on" | "yes" | "1" => Ok(true),
+        _ => Err(format!("Expected on or off: {}", value)),
+    }
+}
+
+/// Error response for an invalid value of the setting `key`.
+fn invalid_setting(key: &str, message: impl Into<String>) -> Response {
+    Response::error_with_code(
+        ErrorCode::InvalidSetting {
+            key: key.to_string(),
+        },
+        message,
+    )
+}
+
+/// Error response for a missing item of `kind` named `name`.
+fn not_found(kind: ItemKind, name: impl Into<String>, message: impl Into<String>) -> Response {
+    Response::error_with_code(
+        ErrorCode::NotFound {
+            kind,
+            name: name.into(),
+        },
+        message,
+    )
+}
+
+/// Error response for a saved transcription that doesn't exist.
+fn session_not_found(id: &str) -> Response {
+    Response::error_with_code(
+        ErrorCode::SessionNotFound { id: id.to_string() },
+        format!("Session not found: {}", id),
+    )
+}
+
+/// Error response for a blocking task that panicked or was cancelled.
+fn task_failed(what: &str, e: tokio::task::JoinError) -> Response {
+    Response::error_with_code(ErrorCode::Internal, format!("{} failed: {}", what, e))
+}
+
+/// Error response for history that couldn't be saved.
+fn storage_failed(e: impl std::fmt::Display) -> Response {
+    Response::error_with_code(
+        ErrorCode::StorageFailed,
+        format!("Failed to save history: {}", e),
+    )
+}
+
+/// Apply the GPU settings from the service state to the transcription queue.
+fn apply_gpu_settings(state: &ServiceState) {
+    get_transcription_queue().set_gpu(GpuSettings {
+        enabled: state.use_gpu,
+        device: state.gpu_device,
+    });
+}
+
+/// Apply a latency preset's segment length and decoding settings.
+pub fn apply_latency_preset(preset: LatencyPreset) {
+    let settings = preset.settings();
+    get_transcription_queue().set_latency(settings);
+    get_transcribe_state()
+        .lock()
+        .unwrap()
+        .set_max_segment_duration_ms(settings.max_segment_ms);
+}
+
+/// Switch to a latency preset, including the speech detector hold time it
+/// bundles. The hold time can still be tuned separately afterwards.
+fn select_latency_preset(state: &mut ServiceState, preset: LatencyPreset) {
+    state.latency_preset = preset;
+    state.vad_config.hold_ms = preset.settings().hold_ms;
+    set_vad_config(state.vad_config.clone());
+    apply_latency_preset(preset);
+}
+
+/// Replace the hotkey bindings and save them, restarting hotkey monitoring if
+/// it is running. The previous bindings are kept if the new keys can't be
+/// monitored.
+async fn set_hotkeys(ptt_key: KeyCode, hotkeys: Vec<HotkeyBinding>) -> Result<(), Response> {
+    let mut keys = vec![ptt_key];
+    for binding in &hotkeys {
+        if keys.contains(&binding.key) {
+            return Err(Response::error_with_code(
+                ErrorCode::InvalidRequest,
+                format!("{:?} is already bound to another action", binding.key),
+            ));
+        }
+        keys.push(binding.key);
+    }
+
+    let state_arc = get_service_state();
+    let (old_key, old_hotkeys, bindings) = {
+        let mut state = state_arc.lock().await;
+        let old_key = std::mem::replace(&mut state.ptt_key, ptt_key);
+        let old_hotkeys = std::mem::replace(&mut state.hotkeys, hotkeys);
+        (old_key, old_hotkeys, state.hotkey_bindings())
+    };
+
+    if ptt_controller::is_ptt_controller_running() {
+        if let Err(e) = hotkey::start_hotkeys(&bindings) {
+            // Revert on failure
+            let mut state = state_arc.lock().await;
+            state.ptt_key = old_key;
+            state.hotkeys = old_hotkeys;
+            let _ = hotkey::start_hotkeys(&state.hotkey_bindings());
+            return Err(Response::error_with_code(
+                ErrorCode::HotkeyFailed,
+                format!("Failed to set hotkey: {}", e),
+            ));
+        }
+    }
+
+    save_config(&*state_arc.lock().await);
+    Ok(())
+}
+
+/// Keep watching hotkeys while muted when one is bound to toggle mute, so it
+/// can turn privacy mode off again.
+async fn keep_mute_hotkey() {
+    let bindings = {
+        let state_arc = get_service_state();
+        let state = state_arc.lock().await;
+        let has_mute_key = state
+            .hotkeys
+            .iter()
+            .any(|b| b.action == HotkeyAction::ToggleMute);
+        if !has_mute_key
+            || !state.should_capture()
+            || state.transcription_mode != TranscriptionMode::PushToTalk
+        {
+            return;
+        }
+        state.hotkey_bindings()
+    };
+
+    let result =
+        hotkey::start_hotkeys(&bindings).and_then(|()| ptt_controller::start_ptt_controller());
+    if let Err(e) = result {
+        tracing::warn!("Failed to keep the mute hotkey active: {}", e);
+        hotkey::stop_hotkey();
+    }
+}
+
+/// Transcribe sources separately when they are captured on separate channels.
+fn apply_split_sources(state: &ServiceState) {
+    get_transcription_queue().set_split_sources(
+        state.recording_mode == RecordingMode::SplitChannels && state.source2_id.is_some(),
+    );
+}
+
+/// Display names of the configured sources, falling back to their IDs.
+fn source_names(ids: &[&Option<String>]) -> Vec<String> {
+    let devices: Vec<_> = platform::get_backend()
+        .map(|b| {
+            let mut devices = b.list_input_devices();
+            devices.extend(b.list_system_devices());
+            devices
+        })
+        .unwrap_or_default();
+
+    ids.iter()
+        .filter_map(|id| id.as_ref())
+        .map(|id| {
+            devices
+                .iter()
+                .find(|d| &d.id == id)
+                .map(|d| d.name.clone())
+                .unwrap_or_else(|| id.clone())
+        })
+        .collect()
+}
+
 /// Start audio capture with current source configuration.
 /// Returns Ok if capture started, Err with message if it failed.
-async fn start_capture() -> Result<(), String> {
+async fn start_capture() -> Result<(), FlowSttError> {
     let state_arc = get_service_state();
     let state = state_arc.lock().await;
 
+    if state.muted {
+        return Err(FlowSttError::Muted);
+    }
+
     if !state.app_ready {
-        return Err("App not ready".to_string());
+        return Err(FlowSttError::NotReady);
     }
 
     if !state.has_primary_source() {
-        return Err("No primary audio source configured".to_string());
+        return Err(FlowSttError::NoSource);
     }
 
     let source1_id = state.source1_id.clone();
@@ -75,27 +491,44 @@ async fn start_capture() -> Result<(), String> {
     let aec_enabled = state.aec_enabled;
     let recording_mode = state.recording_mode;
     let transcription_mode = state.transcription_mode;
-    let ptt_key = state.ptt_key;
+    let hotkey_bindings = state.hotkey_bindings();
+    apply_split_sources(&state);
 
     // Drop the lock before doing expensive operations
     drop(state);
 
+    history::get_history()
+        .lock()
+        .unwrap()
+        .set_sources(source1_id.clone(), source2_id.clone());
+    audio::begin_recording_session(&source_names(&[&source1_id, &source2_id]));
+    recent::clear();
+
     if transcription_mode == TranscriptionMode::PushToTalk {
         // PTT mode: Don't start audio capture yet, just start the PTT controller
         // Audio will be started/stopped when the hotkey is pressed/released
 
         // Start hotkey backend
-        if let Err(e) = hotkey::start_hotkey(ptt_key) {
-            return Err(format!("Failed to start PTT hotkey monitoring: {}", e));
+        if let Err(e) = hotkey::start_hotkeys(&hotkey_bindings) {
+            return Err(FlowSttError::Hotkey(format!(
+                "Failed to start PTT hotkey monitoring: {}",
+                e
+            )));
         }
-        info!("PTT hotkey monitoring started for {:?}", ptt_key);
+        info!("PTT hotkey monitoring started for {:?}", hotkey_bindings);
 
         // Start PTT controller
         if let Err(e) = ptt_controller::start_ptt_controller() {
             hotkey::stop_hotkey();
-            return Err(format!("Failed to start PTT controller: {}", e));
+            return Err(FlowSttError::Capture(format!(
+                "Failed to start PTT controller: {}",
+                e
+            )));
         }
 
+        // The capture session starts once nothing more can fail
+        capture::begin();
+
         // Update state - not capturing yet, but ready
         let state_arc = get_service_state();
         let mut state = state_arc.lock().await;
@@ -116,16 +549,15 @@ async fn start_capture() -> Result<(), String> {
     } else {
         // Automatic mode: Start continuous audio capture with VAD
 
-        // Get sample rate from backend
-        let sample_rate = platform::get_backend()
-            .map(|b| b.sample_rate())
-            .unwrap_or(48000);
+        // Get the format capture will deliver
+        let (sample_rate, channels) =
+            platform::capture_format(source1_id.as_deref(), source2_id.as_deref());
 
         // Initialize transcribe state
         {
             let transcribe_state = get_transcribe_state();
             let mut transcribe = transcribe_state.lock().unwrap();
-            transcribe.init_for_capture(sample_rate, 2);
+            transcribe.init_for_capture(sample_rate, channels);
             transcribe.activate();
         }
 
@@ -134,22 +566,23 @@ async fn start_capture() -> Result<(), String> {
             backend.set_aec_enabled(aec_enabled);
             backend.set_recording_mode(recording_mode);
 
-            if let Err(e) = backend.start_capture_sources(source1_id, source2_id) {
-                return Err(e);
-            }
+            backend.start_capture_sources(source1_id, source2_id)?;
         } else {
-            return Err("Audio backend not available".to_string());
+            return Err(FlowSttError::BackendUnavailable(
+                "no audio backend was initialized".to_string(),
+            ));
         }
 
         // Start audio processing loop
         if !is_audio_loop_active() {
             let queue = get_transcription_queue();
             let transcribe_state = get_transcribe_state();
-            if let Err(e) = start_audio_loop(queue, transcribe_state) {
-                return Err(e);
-            }
+            start_audio_loop(queue, transcribe_state)?;
         }
 
+        // The capture session starts once nothing more can fail
+        capture::begin();
+
         // Update state
         let state_arc = get_service_state();
         let mut state = state_arc.lock().await;
@@ -194,6 +627,9 @@ async fn stop_capture() {
         let _ = backend.stop_capture();
     }
 
+    // Hooks run once the rest of the session is transcribed
+    capture::end();
+
     // Update state
     let state_arc = get_service_state();
     let mut state = state_arc.lock().await;
@@ -207,12 +643,64 @@ async fn stop_capture() {
 pub async fn handle_request(request: Request) -> Response {
     // Validate request
     if let Err(e) = request.validate() {
-        return Response::error(e);
+        return Response::error_with_code(ErrorCode::InvalidRequest, e);
     }
 
     match request {
         Request::Ping => Response::Pong,
 
+        // These belong to a connection, so the IPC server handles them
+        Request::Hello { .. } | Request::AcquireControl { .. } | Request::ReleaseControl => {
+            Response::error_with_code(
+                ErrorCode::InvalidRequest,
+                "Only available on an IPC connection",
+            )
+        }
+
+        Request::GetMetrics => {
+            let aec_enabled = get_service_state().lock().await.aec_enabled;
+            Response::Metrics(metrics::snapshot(
+                get_transcription_queue().queue_depth(),
+                aec_enabled,
+                platform::aec_metrics(),
+            ))
+        }
+
+        Request::SetLogLevel { level } => match logging::set_level(&level) {
+            Ok(()) => {
+                info!("Log level set to {}", level);
+                Response::Ok
+            }
+            Err(e) => Response::error_with_code(ErrorCode::InvalidRequest, e),
+        },
+
+        Request::InjectFault { fault } => {
+            // A disconnect applies to the device capturing that source
+            let device_id = match fault {
+                AudioFault::Disconnect { source, .. } => {
+                    let state_arc = get_service_state();
+                    let state = state_arc.lock().await;
+                    let id = if source == AudioSourceType::System {
+                        state.source2_id.clone()
+                    } else {
+                        state.source1_id.clone()
+                    };
+                    if id.is_none() {
+                        return Response::error_with_code(
+                            ErrorCode::NotCapturing,
+                            "No device is capturing that source",
+                        );
+                    }
+                    id
+                }
+                _ => None,
+            };
+            match platform::inject_fault(fault, device_id) {
+                Ok(()) => Response::Ok,
+                Err(e) => Response::from(e),
+            }
+        }
+
         Request::ListDevices { source_type } => {
             let mut devices = Vec::new();
 
@@ -238,14 +726,34 @@ pub async fn handle_request(request: Request) -> Response {
                 {
                     devices.extend(backend.list_system_devices());
                 }
+
+                // Network sources are listed among the input devices
+                if source_type == Some(flowstt_common::AudioSourceType::Network) {
+                    devices.extend(
+                        backend
+                            .list_input_devices()
+                            .into_iter()
+                            .filter(|d| d.source_type == flowstt_common::AudioSourceType::Network),
+                    );
+                }
             }
 
             Response::Devices { devices }
         }
 
+        Request::GetSystemAudioSupport => {
+            // Checks the OS version and lists devices, which can block
+            match tokio::task::spawn_blocking(platform::system_audio_support).await {
+                Ok(support) => Response::SystemAudioSupport(support),
+                Err(e) => task_failed("System audio check", e),
+            }
+        }
+
         Request::SetSources {
             source1_id,
             source2_id,
+            source1_channels,
+            source2_channels,
         } => {
             let state_arc = get_service_state();
 
@@ -253,6 +761,30 @@ pub async fn handle_request(request: Request) -> Response {
             let (was_capturing, should_capture) = {
                 let mut state = state_arc.lock().await;
                 let was = state.transcribe_status.capturing;
+
+                // Remember channel selections for the sources' devices
+                let selections = [
+                    (&source1_id, source1_channels),
+                    (&source2_id, source2_channels),
+                ];
+                let mut selections_changed = false;
+                for (id, selection) in selections {
+                    let (Some(id), Some(selection)) = (id, selection) else {
+                        continue;
+                    };
+                    let current = (!selection.is_all()).then_some(selection);
+                    let channels = &mut state.audio_config.channels;
+                    let previous = match &current {
+                        Some(selection) => channels.insert(id.clone(), selection.clone()),
+                        None => channels.remove(id),
+                    };
+                    selections_changed |= previous != current;
+                }
+                if selections_changed {
+                    platform::set_channel_selections(state.audio_config.channels.clone());
+                    save_config(&state);
+                }
+
                 state.source1_id = source1_id;
                 state.source2_id = source2_id;
                 (was, state.should_capture())
@@ -270,17 +802,17 @@ pub async fn handle_request(request: Request) -> Response {
                     Err(e) => {
                         // Update error state
                         let mut state = state_arc.lock().await;
-                        state.transcribe_status.error = Some(e.clone());
+                        state.transcribe_status.error = Some(e.to_string());
 
                         // Broadcast error
                         broadcast_event(Response::Event {
                             event: EventType::CaptureStateChanged {
                                 capturing: false,
-                                error: Some(e.clone()),
+                                error: Some(e.to_string()),
                             },
                         });
 
-                        Response::error(e)
+                        Response::from(e)
                     }
                 }
             } else {
@@ -295,6 +827,54 @@ pub async fn handle_request(request: Request) -> Response {
             }
         }
 
+        Request::SetMuted { muted } => {
+            let state_arc = get_service_state();
+            let (was_muted, should_capture) = {
+                let mut state = state_arc.lock().await;
+                let was = state.muted;
+                // Set first so nothing can start capture while it is torn down
+                state.muted = muted;
+                state.transcribe_status.error = None;
+                save_config(&state);
+                (was, state.should_capture())
+            };
+            if was_muted == muted {
+                return Response::Ok;
+            }
+
+            if muted {
+                // Tear down every stream, including an idle push-to-talk setup
+                stop_capture().await;
+                keep_mute_hotkey().await;
+                broadcast_event(Response::Event {
+                    event: EventType::CaptureStateChanged {
+                        capturing: false,
+                        error: None,
+                    },
+                });
+                info!("Privacy mode on: capture stopped");
+            } else {
+                info!("Privacy mode off");
+            }
+            broadcast_event(Response::Event {
+                event: EventType::PrivacyMode { muted },
+            });
+
+            if !muted && should_capture {
+                if let Err(e) = start_capture().await {
+                    state_arc.lock().await.transcribe_status.error = Some(e.to_string());
+                    broadcast_event(Response::Event {
+                        event: EventType::CaptureStateChanged {
+                            capturing: false,
+                            error: Some(e.to_string()),
+                        },
+                    });
+                    return Response::from(e);
+                }
+            }
+            Response::Ok
+        }
+
         Request::SetAecEnabled { enabled } => {
             let state_arc = get_service_state();
             let mut state = state_arc.lock().await;
@@ -311,10 +891,111 @@ pub async fn handle_request(request: Request) -> Response {
             Response::Ok
         }
 
+        Request::GetAecSettings => {
+            let state_arc = get_service_state();
+            let state = state_arc.lock().await;
+            Response::AecSettings(state.aec_settings)
+        }
+
+        Request::GetSpectrogramSettings => {
+            let state_arc = get_service_state();
+            let state = state_arc.lock().await;
+            Response::SpectrogramSettings(state.spectrogram_settings)
+        }
+
+        Request::SetSpectrogramSettings { settings } => {
+            let state_arc = get_service_state();
+            let mut state = state_arc.lock().await;
+            state.spectrogram_settings = settings;
+            processor::set_spectrogram_settings(settings);
+            save_config(&state);
+
+            info!(
+                "Spectrogram settings: {}-point FFT, {:?} window, {} Hz",
+                settings.fft_size, settings.window, settings.emit_rate_hz
+            );
+            Response::SpectrogramSettings(settings)
+        }
+
+        Request::SetAecSettings { settings } => {
+            let state_arc = get_service_state();
+            let mut state = state_arc.lock().await;
+            state.aec_settings = settings;
+            platform::set_aec_settings(settings);
+            save_config(&state);
+
+            info!(
+                "AEC settings: initial delay {}ms, filter length {}ms",
+                settings.initial_delay_ms, settings.filter_length_ms
+            );
+            Response::AecSettings(settings)
+        }
+
+        Request::EstimateAecDelay { duration_secs } => {
+            {
+                let state_arc = get_service_state();
+                let state = state_arc.lock().await;
+                if !state.transcribe_status.capturing
+                    || state.source1_id.is_none()
+                    || state.source2_id.is_none()
+                {
+                    return Response::error_with_code(
+                        ErrorCode::NotCapturing,
+                        "Delay estimation needs capture running from a microphone and system audio",
+                    );
+                }
+            }
+
+            info!("Estimating echo delay for {}s", duration_secs);
+            platform::start_delay_estimation();
+            tokio::time::sleep(std::time::Duration::from_secs(duration_secs as u64)).await;
+            let delay_ms = match platform::finish_delay_estimation() {
+                Ok(delay_ms) => delay_ms,
+                Err(e) => return Response::from(e),
+            };
+
+            let state_arc = get_service_state();
+            let mut state = state_arc.lock().await;
+            state.aec_settings.initial_delay_ms = delay_ms;
+            platform::set_aec_settings(state.aec_settings);
+            save_config(&state);
+
+            info!("Estimated echo delay: {}ms", delay_ms);
+            Response::AecSettings(state.aec_settings)
+        }
+
+        Request::SetSourceRecording { enabled } => {
+            platform::set_source_recording(enabled.then(audio::recordings_dir));
+            info!("Raw source recording: {}", enabled);
+            Response::Ok
+        }
+
+        Request::SetVadRecording { enabled } => {
+            let state_arc = get_service_state();
+            let mut state = state_arc.lock().await;
+            state.vad_recording.enabled = enabled;
+            vad_recording::set_config(state.vad_recording);
+            save_config(&state);
+
+            info!("Voice-activated recording: {}", enabled);
+            Response::Ok
+        }
+
+        Request::SetLatencyPreset { preset } => {
+            let state_arc = get_service_state();
+            let mut state = state_arc.lock().await;
+            select_latency_preset(&mut state, preset);
+            save_config(&state);
+
+            info!("Latency preset: {}", preset.name());
+            Response::Ok
+        }
+
         Request::SetRecordingMode { mode } => {
             let state_arc = get_service_state();
             let mut state = state_arc.lock().await;
             state.recording_mode = mode;
+            apply_split_sources(&state);
 
             // Apply to backend if capturing
             if state.transcribe_status.capturing {
@@ -327,154 +1008,1477 @@ pub async fn handle_request(request: Request) -> Response {
             Response::Ok
         }
 
-        Request::GetStatus => {
-            let state_arc = get_service_state();
-            let state = state_arc.lock().await;
-
-            // Update in_speech and queue_depth from transcribe state
-            let mut status = state.transcribe_status.clone();
-            if status.capturing {
-                if let Ok(transcribe) = get_transcribe_state().try_lock() {
-                    status.in_speech = transcribe.in_speech;
+        Request::SetLanguage { language } => {
+            // Reject codes whisper doesn't know (when the library is loaded)
+            if language != flowstt_common::AUTO_DETECT_LANGUAGE {
+                if let Ok(false) = crate::transcription::whisper_ffi::is_valid_language(&language) {
+                    return Response::error_with_code(
+                        ErrorCode::UnsupportedLanguage {
+                            language: language.clone(),
+                        },
+                        format!("Unsupported language: {}", language),
+                    );
                 }
-                status.queue_depth = get_transcription_queue().queue_depth();
             }
 
-            Response::Status(status)
-        }
+            get_transcription_queue().set_language(language.clone());
 
-        Request::SubscribeEvents => {
-            // Actual subscription is handled in the server
-            Response::Subscribed
-        }
+            let state_arc = get_service_state();
+            let mut state = state_arc.lock().await;
+            state.language = language;
+            save_config(&state);
 
-        Request::GetModelStatus => {
-            let transcriber = Transcriber::new();
-            Response::ModelStatus(ModelStatus {
-                available: transcriber.is_model_available(),
-                path: transcriber.get_model_path().to_string_lossy().to_string(),
-            })
+            info!("Transcription language: {}", state.language);
+            Response::Ok
         }
 
-        Request::DownloadModel => {
-            let transcriber = Transcriber::new();
-            let model_path = transcriber.get_model_path().clone();
-
-            if model_path.exists() {
-                return Response::error("Model already downloaded");
-            }
-
-            // Download in background
-            let path_clone = model_path.clone();
-            tokio::task::spawn_blocking(move || {
-                // Broadcast progress (simplified - just start/end)
-                broadcast_event(Response::Event {
-                    event: EventType::ModelDownloadProgress { percent: 0 },
-                });
+        Request::SetTranslate { enabled } => {
+            get_transcription_queue().set_translate(enabled);
 
-                let result = download_model(&path_clone);
-
-                match result {
-                    Ok(()) => {
-                        broadcast_event(Response::Event {
-                            event: EventType::ModelDownloadProgress { percent: 100 },
-                        });
-                        broadcast_event(Response::Event {
-                            event: EventType::ModelDownloadComplete { success: true },
-                        });
-                    }
-                    Err(e) => {
-                        tracing::error!("Model download failed: {}", e);
-                        broadcast_event(Response::Event {
-                            event: EventType::ModelDownloadComplete { success: false },
-                        });
-                    }
-                }
-            });
+            let state_arc = get_service_state();
+            let mut state = state_arc.lock().await;
+            state.translate = enabled;
+            save_config(&state);
 
+            info!("Translation to English: {}", enabled);
             Response::Ok
         }
 
-        Request::SetTranscriptionMode { mode } => {
-            let state_arc = get_service_state();
+        Request::SetOutputMode { mode } => {
+            output::set_output_mode(mode);
 
-            let (old_mode, is_ready, ptt_key) = {
-                let mut state = state_arc.lock().await;
-                let old_mode = state.transcription_mode;
-                state.transcription_mode = mode;
-                (
-                    old_mode,
-                    state.app_ready && state.has_primary_source(),
-                    state.ptt_key,
-                )
-            };
+            let state_arc = get_service_state();
+            let mut state = state_arc.lock().await;
+            state.output_mode = mode;
+            save_config(&state);
 
-            // If mode changed and system is ready, restart capture with new mode
-            if old_mode != mode && is_ready {
-                // Stop current capture
-                stop_capture().await;
+            info!("Output mode set to {:?}", mode);
+            Response::Ok
+        }
 
-                // Restart with new mode
-                if let Err(e) = start_capture().await {
-                    tracing::warn!("Failed to restart capture after mode change: {}", e);
+        Request::RepeatLastOutput { mode } => {
+            // Typing runs a helper process on some platforms
+            match tokio::task::spawn_blocking(move || output::repeat_last(mode)).await {
+                Ok(Ok(Some(text))) => {
+                    info!("Delivered the last transcription again");
+                    Response::RepeatedOutput { text }
                 }
+                Ok(Ok(None)) => Response::error_with_code(
+                    ErrorCode::NothingToRepeat,
+                    "Nothing has been transcribed yet",
+                ),
+                Ok(Err(e)) => Response::error_with_code(ErrorCode::OutputFailed, e),
+                Err(e) => task_failed("Repeating output", e),
             }
+        }
 
-            // Save configuration to disk
-            let config = crate::config::Config {
-                transcription_mode: mode,
-                ptt_key,
-            };
-            if let Err(e) = config.save() {
-                tracing::warn!("Failed to save config: {}", e);
-            }
-
-            info!("Transcription mode set to {:?}", mode);
-
-            // Broadcast mode change event
-            broadcast_event(Response::Event {
-                event: EventType::TranscriptionModeChanged { mode },
-            });
+        Request::SetDiarization { enabled } => {
+            get_transcription_queue().set_diarization(enabled);
+            info!("Speaker diarization: {}", enabled);
+            Response::Ok
+        }
 
+        Request::StartDictation => {
+            if !dictation::start() {
+                return Response::error_with_code(
+                    ErrorCode::DictationActive,
+                    "Dictation is already active",
+                );
+            }
+            get_transcribe_state()
+                .lock()
+                .unwrap()
+                .set_dictation_mode(true);
+            info!("Dictation started");
             Response::Ok
         }
 
-        Request::SetPushToTalkKey { key } => {
-            let state_arc = get_service_state();
-            let (old_key, transcription_mode, is_capturing_ptt) = {
-                let mut state = state_arc.lock().await;
-                let old_key = state.ptt_key;
-                state.ptt_key = key;
-                let is_capturing_ptt = state.transcribe_status.capturing
-                    && state.transcription_mode == TranscriptionMode::PushToTalk;
-                (old_key, state.transcription_mode, is_capturing_ptt)
+        Request::StopDictation => {
+            let Some(text) = dictation::stop() else {
+                return Response::error_with_code(
+                    ErrorCode::DictationInactive,
+                    "Dictation is not active",
+                );
             };
+            get_transcribe_state()
+                .lock()
+                .unwrap()
+                .set_dictation_mode(false);
+            info!("Dictation stopped ({} chars)", text.len());
+            Response::Dictation { text }
+        }
 
-            // If capturing in PTT mode, restart hotkey with new key
-            if is_capturing_ptt {
-                hotkey::stop_hotkey();
-                if let Err(e) = hotkey::start_hotkey(key) {
-                    // Revert on failure
-                    let mut state = state_arc.lock().await;
-                    state.ptt_key = old_key;
-                    let _ = hotkey::start_hotkey(old_key);
-                    return Response::error(format!("Failed to set hotkey: {}", e));
-                }
+        Request::ListSessions { limit, filter } => {
+            let sessions = history::get_history().lock().unwrap().list(limit, &filter);
+            Response::Sessions { sessions }
+        }
+
+        Request::GetSession { id } => match history::get_history().lock().unwrap().get(&id) {
+            Some(session) => Response::Session(Box::new(session.clone())),
+            None => session_not_found(&id),
+        },
+
+        Request::GetCapture { id } => match history::get_history().lock().unwrap().capture(&id) {
+            Some(capture) => Response::Capture(Box::new(capture.clone())),
+            None => Response::error_with_code(
+                ErrorCode::NotFound {
+                    kind: ItemKind::Capture,
+                    name: id.clone(),
+                },
+                format!("Capture session not found: {}", id),
+            ),
+        },
+
+        Request::GetCaptureTranscript { id } => {
+            let history = history::get_history().lock().unwrap();
+            let transcript = history.capture(&id).and_then(|found| {
+                let entries: Vec<TranscriptSession> =
+                    history.capture_sessions(&id).into_iter().cloned().collect();
+                capture::transcript(found, &entries)
+            });
+            match transcript {
+                Some(session) => Response::Session(Box::new(session)),
+                None => Response::error_with_code(
+                    ErrorCode::NotFound {
+                        kind: ItemKind::Capture,
+                        name: id.clone(),
+                    },
+                    format!("Capture session not found: {}", id),
+                ),
             }
+        }
 
-            // Save configuration to disk
-            let config = crate::config::Config {
-                transcription_mode,
-                ptt_key: key,
-            };
-            if let Err(e) = config.save() {
-                tracing::warn!("Failed to save config: {}", e);
+        Request::GetSessionStats { id } => {
+            match history::get_history().lock().unwrap().stats(&id) {
+                Some(stats) => Response::SessionStats(stats),
+                None => session_not_found(&id),
             }
+        }
 
-            info!("PTT key set to {:?}", key);
+        Request::DeleteSession { id } => {
+            let mut history = history::get_history().lock().unwrap();
+            if history.remove(&id).is_none() {
+                return session_not_found(&id);
+            }
+            if let Err(e) = history.save() {
+                return storage_failed(e);
+            }
+            info!("Deleted history session {}", id);
             Response::Ok
         }
 
+        Request::UpdateSession { id, text, words } => {
+            let mut history = history::get_history().lock().unwrap();
+            if !history.update(&id, text, words) {
+                return session_not_found(&id);
+            }
+            if let Err(e) = history.save() {
+                return storage_failed(e);
+            }
+            info!("Updated history session {}", id);
+            Response::Ok
+        }
+
+        Request::SearchTranscripts {
+            query,
+            date_range,
+            limit,
+        } => {
+            let history = history::get_history().lock().unwrap();
+            let hits = search::search(history.sessions(), &query, date_range.as_ref(), limit);
+            Response::SearchResults { hits }
+        }
+
+        Request::SetSessionMetadata { id, metadata } => {
+            let mut history = history::get_history().lock().unwrap();
+            if !history.set_metadata(&id, metadata) {
+                return session_not_found(&id);
+            }
+            if let Err(e) = history.save() {
+                return storage_failed(e);
+            }
+            info!("Updated metadata of history session {}", id);
+            Response::Ok
+        }
+
+        Request::RetranscribeSessions { ids } => {
+            {
+                let history = history::get_history().lock().unwrap();
+                for id in &ids {
+                    match history.get(id) {
+                        Some(session) if session.audio_path.is_some() => {}
+                        Some(_) => {
+                            return Response::error_with_code(
+                                ErrorCode::NoRecording { id: id.clone() },
+                                format!("Session {} has no saved recording", id),
+                            )
+                        }
+                        None => return session_not_found(id),
+                    }
+                }
+            }
+
+            let settings = {
+                let state_arc = get_service_state();
+                let state = state_arc.lock().await;
+                retranscribe::JobSettings {
+                    gpu: GpuSettings {
+                        enabled: state.use_gpu,
+                        device: state.gpu_device,
+                    },
+                    language: state.language.clone(),
+                }
+            };
+            Response::Job(retranscribe::submit(ids, settings))
+        }
+
+        Request::ListJobs => Response::Jobs {
+            jobs: retranscribe::list(),
+        },
+
+        Request::CancelJob { id } => match retranscribe::cancel(&id) {
+            Some(_) => Response::Ok,
+            None => not_found(ItemKind::Job, &id, format!("Job not found: {}", id)),
+        },
+
+        Request::PlaySession { id, position_ms } => {
+            let session = match history::get_history().lock().unwrap().get(&id) {
+                Some(session) => session.clone(),
+                None => return session_not_found(&id),
+            };
+            let Some(path) = session.audio_path else {
+                return Response::error_with_code(
+                    ErrorCode::NoRecording { id: id.clone() },
+                    format!("Session {} has no saved recording", id),
+                );
+            };
+            let play = move || {
+                playback::play(
+                    std::path::Path::new(&path),
+                    Some(session.id),
+                    session.words,
+                    position_ms,
+                )
+            };
+            match tokio::task::spawn_blocking(play).await {
+                Ok(Ok(status)) => Response::Playback(status),
+                Ok(Err(e)) => Response::error_with_code(ErrorCode::PlaybackFailed, e),
+                Err(e) => task_failed("Playback", e),
+            }
+        }
+
+        Request::PlayFile { path, position_ms } => {
+            // Highlight words when the file is a saved session's recording
+            let session = history::get_history()
+                .lock()
+                .unwrap()
+                .find_by_audio_path(&path)
+                .cloned();
+            let (session_id, words) = match session {
+                Some(session) => (Some(session.id), session.words),
+                None => (None, Vec::new()),
+            };
+            let play =
+                move || playback::play(std::path::Path::new(&path), session_id, words, position_ms);
+            match tokio::task::spawn_blocking(play).await {
+                Ok(Ok(status)) => Response::Playback(status),
+                Ok(Err(e)) => Response::error_with_code(ErrorCode::PlaybackFailed, e),
+                Err(e) => task_failed("Playback", e),
+            }
+        }
+
+        Request::PausePlayback { paused } => match playback::set_paused(paused) {
+            Ok(status) => Response::Playback(status),
+            Err(e) => Response::error_with_code(ErrorCode::NotPlaying, e),
+        },
+
+        Request::SeekPlayback { position_ms } => match playback::seek(position_ms) {
+            Ok(status) => Response::Playback(status),
+            Err(e) => Response::error_with_code(ErrorCode::NotPlaying, e),
+        },
+
+        Request::StopPlayback => {
+            playback::stop();
+            Response::Ok
+        }
+
+        Request::GetStorageStatus => match tokio::task::spawn_blocking(retention::status).await {
+            Ok(status) => Response::Storage(status),
+            Err(e) => task_failed("Reading storage status", e),
+        },
+
+        Request::PruneRecordings => match tokio::task::spawn_blocking(retention::prune).await {
+            Ok(result) => Response::Pruned(result),
+            Err(e) => task_failed("Pruning", e),
+        },
+
+        Request::AddWatchFolder { path } => {
+            let path = std::path::PathBuf::from(path);
+            if !path.is_dir() {
+                return not_found(
+                    ItemKind::Folder,
+                    path.display().to_string(),
+                    format!("Not a directory: {}", path.display()),
+                );
+            }
+
+            let state_arc = get_service_state();
+            let mut state = state_arc.lock().await;
+            if !state.watch_folders.contains(&path) {
+                state.watch_folders.push(path.clone());
+                watch::set_folders(&state.watch_folders);
+                save_config(&state);
+                info!("Watching folder: {}", path.display());
+            }
+            Response::Ok
+        }
+
+        Request::RemoveWatchFolder { path } => {
+            let path = std::path::PathBuf::from(path);
+
+            let state_arc = get_service_state();
+            let mut state = state_arc.lock().await;
+            let Some(index) = state.watch_folders.iter().position(|p| p == &path) else {
+                return not_found(
+                    ItemKind::WatchedFolder,
+                    path.display().to_string(),
+                    format!("Folder is not watched: {}", path.display()),
+                );
+            };
+            state.watch_folders.remove(index);
+            watch::set_folders(&state.watch_folders);
+            save_config(&state);
+
+            info!("Stopped watching folder: {}", path.display());
+            Response::Ok
+        }
+
+        Request::ListWatchFolders => {
+            let state_arc = get_service_state();
+            let state = state_arc.lock().await;
+            Response::WatchFolders {
+                folders: state
+                    .watch_folders
+                    .iter()
+                    .map(|p| p.to_string_lossy().to_string())
+                    .collect(),
+            }
+        }
+
+        Request::RegisterCommand {
+            phrase,
+            suppress_text,
+        } => {
+            let state_arc = get_service_state();
+            let mut state = state_arc.lock().await;
+            let key = commands::normalize(&phrase);
+            state
+                .voice_commands
+                .retain(|c| commands::normalize(&c.phrase) != key);
+            state.voice_commands.push(VoiceCommand {
+                phrase: phrase.clone(),
+                suppress_text,
+            });
+            commands::set_commands(&state.voice_commands);
+            save_config(&state);
+
+            info!("Registered voice command: {:?}", phrase);
+            Response::Ok
+        }
+
+        Request::UnregisterCommand { phrase } => {
+            let state_arc = get_service_state();
+            let mut state = state_arc.lock().await;
+            let key = commands::normalize(&phrase);
+            let count = state.voice_commands.len();
+            state
+                .voice_commands
+                .retain(|c| commands::normalize(&c.phrase) != key);
+            if state.voice_commands.len() == count {
+                return not_found(
+                    ItemKind::Command,
+                    phrase.clone(),
+                    format!("Command is not registered: {:?}", phrase),
+                );
+            }
+            commands::set_commands(&state.voice_commands);
+            save_config(&state);
+
+            info!("Unregistered voice command: {:?}", phrase);
+            Response::Ok
+        }
+
+        Request::ListCommands => {
+            let state_arc = get_service_state();
+            let state = state_arc.lock().await;
+            Response::Commands {
+                commands: state.voice_commands.clone(),
+            }
+        }
+
+        Request::AddHook { mut hook } => {
+            hook.name = hook.name.trim().to_string();
+            let state_arc = get_service_state();
+            let mut state = state_arc.lock().await;
+            state
+                .hooks
+                .retain(|h| !h.name.eq_ignore_ascii_case(&hook.name));
+            info!("Added session hook: {}", hook.name);
+            state.hooks.push(hook);
+            hooks::set_hooks(&state.hooks);
+            save_config(&state);
+            Response::Ok
+        }
+
+        Request::RemoveHook { name } => {
+            let state_arc = get_service_state();
+            let mut state = state_arc.lock().await;
+            let count = state.hooks.len();
+            state
+                .hooks
+                .retain(|h| !h.name.eq_ignore_ascii_case(name.trim()));
+            if state.hooks.len() == count {
+                return not_found(
+                    ItemKind::Hook,
+                    name.clone(),
+                    format!("No hook named {:?}", name),
+                );
+            }
+            hooks::set_hooks(&state.hooks);
+            save_config(&state);
+
+            info!("Removed session hook: {}", name);
+            Response::Ok
+        }
+
+        Request::ListHooks => {
+            let state_arc = get_service_state();
+            let state = state_arc.lock().await;
+            Response::Hooks {
+                hooks: state.hooks.clone(),
+            }
+        }
+
+        Request::ListProfiles => {
+            let state_arc = get_service_state();
+            let state = state_arc.lock().await;
+            Response::Profiles {
+                profiles: state.profiles.clone(),
+                active: state.active_profile.clone(),
+            }
+        }
+
+        Request::SaveProfile { mut profile } => {
+            profile.name = profile.name.trim().to_string();
+            let state_arc = get_service_state();
+            let mut state = state_arc.lock().await;
+            state
+                .profiles
+                .retain(|p| !p.name.eq_ignore_ascii_case(&profile.name));
+            info!("Saved profile: {}", profile.name);
+            state.profiles.push(profile);
+            save_config(&state);
+            Response::Ok
+        }
+
+        Request::DeleteProfile { name } => {
+            let state_arc = get_service_state();
+            let mut state = state_arc.lock().await;
+            let Some(name) = profiles::find(&state.profiles, &name).map(|p| p.name.clone()) else {
+                return not_found(
+                    ItemKind::Profile,
+                    name.clone(),
+                    format!("No profile named {:?}", name),
+                );
+            };
+            state.profiles.retain(|p| p.name != name);
+            if state.active_profile.as_deref() == Some(name.as_str()) {
+                state.active_profile = None;
+            }
+            save_config(&state);
+
+            info!("Deleted profile: {}", name);
+            Response::Ok
+        }
+
+        Request::UseProfile { name } => {
+            let profile = {
+                let state_arc = get_service_state();
+                let state = state_arc.lock().await;
+                match profiles::find(&state.profiles, &name) {
+                    Some(profile) => profile.clone(),
+                    None => {
+                        return not_found(
+                            ItemKind::Profile,
+                            name.clone(),
+                            format!("No profile named {:?}", name),
+                        )
+                    }
+                }
+            };
+
+            for request in profiles::requests(&profile) {
+                // Boxed because handle_request is recursive here
+                let response: Pin<Box<dyn Future<Output = Response> + Send>> =
+                    Box::pin(handle_request(request));
+                if let Response::Error { code, message } = response.await {
+                    return Response::error_with_code(
+                        code,
+                        format!("Failed to apply profile {}: {}", profile.name, message),
+                    );
+                }
+            }
+
+            let state_arc = get_service_state();
+            let mut state = state_arc.lock().await;
+            state.active_profile = Some(profile.name.clone());
+            save_config(&state);
+
+            info!("Using profile: {}", profile.name);
+            broadcast_event(Response::Event {
+                event: EventType::ProfileChanged { name: profile.name },
+            });
+            Response::Ok
+        }
+
+        Request::GetFocusedApp => match tokio::task::spawn_blocking(focus::focused_app).await {
+            Ok(Ok(app)) => Response::FocusedApp(app),
+            Ok(Err(e)) => Response::error_with_code(ErrorCode::Unsupported, e),
+            Err(e) => task_failed("Focused application detection", e),
+        },
+
+        Request::GetPermissions => match tokio::task::spawn_blocking(permissions::check).await {
+            Ok(permissions) => Response::Permissions(permissions),
+            Err(e) => task_failed("Permission check", e),
+        },
+
+        Request::RequestPermission { permission } => {
+            // Blocks until the user answers the system prompt
+            match tokio::task::spawn_blocking(move || {
+                permissions::request(permission);
+                permissions::check()
+            })
+            .await
+            {
+                Ok(permissions) => Response::Permissions(permissions),
+                Err(e) => task_failed("Permission request", e),
+            }
+        }
+
+        Request::GetVadConfig => {
+            let state_arc = get_service_state();
+            let state = state_arc.lock().await;
+            Response::VadConfig(state.vad_config.clone())
+        }
+
+        Request::SetVadConfig { config } => {
+            let state_arc = get_service_state();
+            let mut state = state_arc.lock().await;
+            state.vad_config = config.clone();
+            silero_vad::ensure_model(&config);
+            set_vad_config(config);
+            save_config(&state);
+
+            info!("Speech detector settings updated");
+            Response::Ok
+        }
+
+        Request::CalibrateNoise { duration_secs } => {
+            let (sample_rate, _) = {
+                let state_arc = get_service_state();
+                let state = state_arc.lock().await;
+                if !state.transcribe_status.capturing || !is_audio_loop_active() {
+                    return Response::error_with_code(
+                        ErrorCode::NotCapturing,
+                        "Start capture before calibrating",
+                    );
+                }
+                platform::capture_format(state.source1_id.as_deref(), state.source2_id.as_deref())
+            };
+
+            info!("Calibrating noise floor for {}s", duration_secs);
+            start_calibration(sample_rate);
+            tokio::time::sleep(std::time::Duration::from_secs(duration_secs as u64)).await;
+            let Some(calibrator) = finish_calibration() else {
+                return Response::error_with_code(
+                    ErrorCode::NotCapturing,
+                    "Calibration was interrupted",
+                );
+            };
+
+            let state_arc = get_service_state();
+            let mut state = state_arc.lock().await;
+            match calibrator.finish(&state.vad_config) {
+                Ok(calibration) => {
+                    state.vad_config = calibration.config.clone();
+                    set_vad_config(calibration.config.clone());
+                    save_config(&state);
+
+                    info!(
+                        "Noise floor {:.1} dB; voiced threshold {:.1} dB, whisper threshold {:.1} dB",
+                        calibration.noise_floor_db,
+                        calibration.config.voiced_threshold_db,
+                        calibration.config.whisper_threshold_db
+                    );
+                    Response::NoiseCalibration(calibration)
+                }
+                Err(e) => Response::error_with_code(ErrorCode::CalibrationFailed, e),
+            }
+        }
+
+        Request::GetConfig => {
+            let state_arc = get_service_state();
+            let state = state_arc.lock().await;
+            Response::ConfigValues {
+                values: config_values(&state),
+            }
+        }
+
+        Request::SetConfigValue { key, value } => {
+            let state_arc = get_service_state();
+            let mut state = state_arc.lock().await;
+            match key.as_str() {
+                "recordings.dir" => {
+                    if value.is_empty() {
+                        state.recordings_dir = None;
+                    } else {
+                        let dir = std::path::PathBuf::from(&value);
+                        if !dir.is_absolute() {
+                            return invalid_setting(
+                                &key,
+                                format!("Recordings directory must be an absolute path: {}", value),
+                            );
+                        }
+                        state.recordings_dir = Some(dir);
+                    }
+                }
+                "recordings.template" => {
+                    state.recording_filename_template = if value.is_empty() {
+                        audio::DEFAULT_FILENAME_TEMPLATE.to_string()
+                    } else {
+                        value
+                    };
+                }
+                "recordings.format" => {
+                    state.recording_format = if value.is_empty() {
+                        RecordingFormat::default()
+                    } else {
+                        match RecordingFormat::from_name(&value) {
+                            Some(format) => format,
+                            None => {
+                                return invalid_setting(
+                                    &key,
+                                    format!("Unknown recording format: {}", value),
+                                )
+                            }
+                        }
+                    };
+                }
+                "recordings.trim_silence" | "recordings.normalize" => {
+                    let enabled = match parse_switch(&value) {
+                        Ok(enabled) => enabled,
+                        Err(e) => return invalid_setting(&key, e),
+                    };
+                    if key == "recordings.trim_silence" {
+                        state.recording_processing.trim_silence = enabled;
+                    } else {
+                        state.recording_processing.normalize = enabled;
+                    }
+                }
+                "recordings.silence_threshold_db" => {
+                    state.recording_processing.silence_threshold_db = if value.is_empty() {
+                        RecordingProcessingConfig::default().silence_threshold_db
+                    } else {
+                        match value.parse::<f32>() {
+                            Ok(db) if (-100.0..=0.0).contains(&db) => db,
+                            _ => {
+                                return invalid_setting(
+                                    &key,
+                                    format!(
+                                        "Silence threshold must be between -100 and 0 dBFS: {}",
+                                        value
+                                    ),
+                                )
+                            }
+                        }
+                    };
+                }
+                "recordings.target_lufs" => {
+                    state.recording_processing.target_lufs = if value.is_empty() {
+                        RecordingProcessingConfig::default().target_lufs
+                    } else {
+                        match value.parse::<f32>() {
+                            Ok(lufs) if (-70.0..=0.0).contains(&lufs) => lufs,
+                            _ => {
+                                return invalid_setting(
+                                    &key,
+                                    format!(
+                                        "Target loudness must be between -70 and 0 LUFS: {}",
+                                        value
+                                    ),
+                                )
+                            }
+                        }
+                    };
+                }
+                "model.path" => {
+                    if value.is_empty() {
+                        state.model_path = None;
+                    } else {
+                        let path = std::path::PathBuf::from(&value);
+                        if !path.is_absolute() {
+                            return invalid_setting(
+                                &key,
+                                format!("Model path must be an absolute path: {}", value),
+                            );
+                        }
+                        if let Err(e) = model::inspect(&path) {
+                            return invalid_setting(&key, e);
+                        }
+                        state.model_path = Some(path);
+                    }
+                    // The transcription worker reloads before its next segment
+                    model::set_model_path(state.model_path.clone());
+                }
+                "model.name" => {
+                    if value.is_empty() {
+                        state.model_name = None;
+                    } else if model::is_known_model(&value) {
+                        state.model_name = Some(value);
+                    } else {
+                        return invalid_setting(
+                            &key,
+                            format!("Unknown model: {} (see `flowstt model list`)", value),
+                        );
+                    }
+                    model::set_model_name(state.model_name.clone());
+                }
+                "cues.ptt" | "cues.transcription" => {
+                    let enabled = match parse_switch(&value) {
+                        Ok(enabled) => enabled,
+                        Err(e) => return invalid_setting(&key, e),
+                    };
+                    if key == "cues.ptt" {
+                        state.cues.ptt = enabled;
+                    } else {
+                        state.cues.transcription = enabled;
+                    }
+                    cues::set_config(state.cues);
+                }
+                "cues.volume" => {
+                    state.cues.volume = if value.is_empty() {
+                        CueConfig::default().volume
+                    } else {
+                        match value.parse::<f32>() {
+                            Ok(volume) if (0.0..=1.0).contains(&volume) => volume,
+                            _ => {
+                                return invalid_setting(
+                                    &key,
+                                    format!("Cue volume must be between 0 and 1: {}", value),
+                                )
+                            }
+                        }
+                    };
+                    cues::set_config(state.cues);
+                }
+                "osc.enabled" | "osc.chatbox" => {
+                    let enabled = match parse_switch(&value) {
+                        Ok(enabled) => enabled,
+                        Err(e) => return invalid_setting(&key, e),
+                    };
+                    if key == "osc.enabled" {
+                        state.osc.enabled = enabled;
+                    } else {
+                        state.osc.chatbox = enabled;
+                    }
+                    osc::set_config(state.osc.clone());
+                }
+                "osc.address" => {
+                    state.osc.address = if value.is_empty() {
+                        OscConfig::default().address
+                    } else if value
+                        .rsplit_once(':')
+                        .is_some_and(|(host, port)| !host.is_empty() && port.parse::<u16>().is_ok())
+                    {
+                        value
+                    } else {
+                        return invalid_setting(
+                            &key,
+                            format!("OSC address must be host:port: {}", value),
+                        );
+                    };
+                    osc::set_config(state.osc.clone());
+                }
+                "osc.prefix" => {
+                    state.osc.prefix = if value.is_empty() {
+                        OscConfig::default().prefix
+                    } else if value.starts_with('/') && !value.contains(char::is_whitespace) {
+                        value.trim_end_matches('/').to_string()
+                    } else {
+                        return invalid_setting(
+                            &key,
+                            format!(
+                                "OSC prefix must start with / and contain no spaces: {}",
+                                value
+                            ),
+                        );
+                    };
+                    osc::set_config(state.osc.clone());
+                }
+                "retention.max_size_mb" => {
+                    state.retention.max_size_mb = match value.parse::<u64>() {
+                        _ if value.is_empty() => None,
+                        Ok(mb) if mb > 0 => Some(mb),
+                        _ => {
+                            return invalid_setting(
+                                &key,
+                                format!(
+                                    "Size limit must be a positive number of megabytes: {}",
+                                    value
+                                ),
+                            )
+                        }
+                    };
+                    retention::set_config(state.retention);
+                }
+                "retention.max_age_days" => {
+                    state.retention.max_age_days = match value.parse::<u32>() {
+                        _ if value.is_empty() => None,
+                        Ok(days) if days > 0 => Some(days),
+                        _ => {
+                            return invalid_setting(
+                                &key,
+                                format!("Age limit must be a positive number of days: {}", value),
+                            )
+                        }
+                    };
+                    retention::set_config(state.retention);
+                }
+                "retention.keep_transcripts" => {
+                    state.retention.keep_transcripts = if value.is_empty() {
+                        true
+                    } else {
+                        match parse_switch(&value) {
+                            Ok(keep) => keep,
+                            Err(e) => return invalid_setting(&key, e),
+                        }
+                    };
+                    retention::set_config(state.retention);
+                }
+                "vad_recording.enabled" => {
+                    state.vad_recording.enabled = match parse_switch(&value) {
+                        Ok(enabled) => enabled,
+                        Err(e) => return invalid_setting(&key, e),
+                    };
+                    vad_recording::set_config(state.vad_recording);
+                }
+                "vad_recording.split" => {
+                    state.vad_recording.split = if value.is_empty() {
+                        VadRecordingSplit::default()
+                    } else {
+                        match VadRecordingSplit::from_name(&value) {
+                            Some(split) => split,
+                            None => {
+                                return invalid_setting(
+                                    &key,
+                                    format!(
+                                        "Unknown split: {} (expected utterance or session)",
+                                        value
+                                    ),
+                                )
+                            }
+                        }
+                    };
+                    vad_recording::set_config(state.vad_recording);
+                }
+                "vad_recording.pre_roll_ms" | "vad_recording.post_roll_ms" => {
+                    let defaults = VadRecordingConfig::default();
+                    let ms = match value.parse::<u32>() {
+                        _ if value.is_empty() && key == "vad_recording.pre_roll_ms" => {
+                            defaults.pre_roll_ms
+                        }
+                        _ if value.is_empty() => defaults.post_roll_ms,
+                        Ok(ms) if ms <= MAX_ROLL_MS => ms,
+                        _ => {
+                            return invalid_setting(
+                                &key,
+                                format!(
+                                    "Roll must be 0 to {} milliseconds: {}",
+                                    MAX_ROLL_MS, value
+                                ),
+                            )
+                        }
+                    };
+                    if key == "vad_recording.pre_roll_ms" {
+                        state.vad_recording.pre_roll_ms = ms;
+                    } else {
+                        state.vad_recording.post_roll_ms = ms;
+                    }
+                    vad_recording::set_config(state.vad_recording);
+                }
+                "output.template" => {
+                    let template = Some(value).filter(|value| !value.is_empty());
+                    if let Err(e) = output::set_template(template.as_deref()) {
+                        return invalid_setting(&key, e);
+                    }
+                    state.output_template = template;
+                }
+                "post_processing.profile" => {
+                    state.post_processing.profile = if value.is_empty() {
+                        TextProfile::default()
+                    } else {
+                        match TextProfile::from_name(&value) {
+                            Some(profile) => profile,
+                            None => {
+                                return invalid_setting(
+                                    &key,
+                                    format!(
+                                        "Unknown profile: {} (expected prose or technical)",
+                                        value
+                                    ),
+                                )
+                            }
+                        }
+                    };
+                    postprocess::set_config(&state.post_processing);
+                }
+                "latency.preset" => {
+                    let preset = if value.is_empty() {
+                        LatencyPreset::default()
+                    } else {
+                        match LatencyPreset::from_name(&value) {
+                            Some(preset) => preset,
+                            None => {
+                                return invalid_setting(
+                                    &key,
+                                    format!(
+                                        "Unknown preset: {} (expected fast, balanced or accurate)",
+                                        value
+                                    ),
+                                )
+                            }
+                        }
+                    };
+                    select_latency_preset(&mut state, preset);
+                }
+                "chapters.silence_secs" => {
+                    state.chapters.silence_secs = match value.parse::<u32>() {
+                        _ if value.is_empty() => ChapterConfig::default().silence_secs,
+                        Ok(secs) => secs,
+                        Err(_) => {
+                            return invalid_setting(
+                                &key,
+                                format!(
+                                "Chapter silence must be a number of seconds (0 to disable): {}",
+                                value
+                            ),
+                            )
+                        }
+                    };
+                    chapters::set_config(state.chapters);
+                }
+                "chapters.interval_mins" => {
+                    state.chapters.interval_mins = match value.parse::<u32>() {
+                        _ if value.is_empty() => 0,
+                        Ok(mins) => mins,
+                        Err(_) => {
+                            return invalid_setting(
+                                &key,
+                                format!(
+                                "Chapter interval must be a number of minutes (0 to disable): {}",
+                                value
+                            ),
+                            )
+                        }
+                    };
+                    chapters::set_config(state.chapters);
+                }
+                "audio.buffer_ms" => {
+                    state.audio_config.buffer_ms = match value.parse::<u32>() {
+                        _ if value.is_empty() => None,
+                        Ok(ms) if (MIN_CAPTURE_BUFFER_MS..=MAX_CAPTURE_BUFFER_MS).contains(&ms) => {
+                            Some(ms)
+                        }
+                        _ => {
+                            return invalid_setting(
+                                &key,
+                                format!(
+                                    "Capture buffer must be {} to {} milliseconds: {}",
+                                    MIN_CAPTURE_BUFFER_MS, MAX_CAPTURE_BUFFER_MS, value
+                                ),
+                            )
+                        }
+                    };
+                    platform::set_capture_buffer_ms(state.audio_config.buffer_ms);
+                }
+                "audio.capture_mode" => {
+                    state.audio_config.capture_mode = if value.is_empty() {
+                        CaptureMode::default()
+                    } else {
+                        match CaptureMode::from_name(&value) {
+                            Some(mode) => mode,
+                            None => {
+                                return invalid_setting(
+                                    &key,
+                                    format!(
+                                    "Unknown capture mode: {} (expected shared, raw or exclusive)",
+                                    value
+                                ),
+                                )
+                            }
+                        }
+                    };
+                    platform::set_capture_mode(state.audio_config.capture_mode);
+                }
+                "audio.sample_rate" => {
+                    state.audio_config.sample_rate = match value.parse::<u32>() {
+                        _ if value.is_empty() => None,
+                        Ok(rate) if CAPTURE_SAMPLE_RATES.contains(&rate) => Some(rate),
+                        _ => {
+                            return invalid_setting(
+                                &key,
+                                format!("Capture sample rate must be 16000 or 48000: {}", value),
+                            )
+                        }
+                    };
+                    platform::set_capture_sample_rate(state.audio_config.sample_rate);
+                }
+                "rolling_buffer.secs" => {
+                    state.rolling_buffer.secs = match value.parse::<u32>() {
+                        _ if value.is_empty() => RollingBufferConfig::default().secs,
+                        Ok(secs) if secs <= MAX_ROLLING_BUFFER_SECS => secs,
+                        _ => {
+                            return invalid_setting(
+                                &key,
+                                format!(
+                                    "Rolling buffer must be 0 to {} seconds (0 to disable): {}",
+                                    MAX_ROLLING_BUFFER_SECS, value
+                                ),
+                            )
+                        }
+                    };
+                    rolling_buffer::set_config(state.rolling_buffer);
+                }
+                "redaction.channels" => {
+                    let mut channels = Vec::new();
+                    for name in value.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+                        if name.eq_ignore_ascii_case("all") {
+                            channels = RedactionChannel::ALL.to_vec();
+                            break;
+                        }
+                        match RedactionChannel::from_name(name) {
+                            Some(channel) if !channels.contains(&channel) => channels.push(channel),
+                            Some(_) => {}
+                            None => {
+                                return invalid_setting(&key, format!(
+                                    "Unknown redaction channel: {} (expected history, output, events, hooks or all)",
+                                    name
+                                ))
+                            }
+                        }
+                    }
+                    state.redaction.channels = channels;
+                    redact::set_config(&state.redaction);
+                }
+                "redaction.emails" | "redaction.phone_numbers" | "redaction.card_numbers" => {
+                    let enabled = if value.is_empty() {
+                        true
+                    } else {
+                        match parse_switch(&value) {
+                            Ok(enabled) => enabled,
+                            Err(e) => return invalid_setting(&key, e),
+                        }
+                    };
+                    match key.as_str() {
+                        "redaction.emails" => state.redaction.emails = enabled,
+                        "redaction.phone_numbers" => state.redaction.phone_numbers = enabled,
+                        _ => state.redaction.card_numbers = enabled,
+                    }
+                    redact::set_config(&state.redaction);
+                }
+                _ => {
+                    return Response::error_with_code(
+                        ErrorCode::UnknownSetting { key: key.clone() },
+                        format!("Unknown setting: {}", key),
+                    )
+                }
+            }
+            audio::set_recordings_location(
+                state.recordings_dir.clone(),
+                state.recording_filename_template.clone(),
+            );
+            audio::set_recording_format(state.recording_format);
+            audio::set_recording_processing(state.recording_processing);
+            save_config(&state);
+
+            info!("Setting {} changed", key);
+            Response::ConfigValues {
+                values: config_values(&state),
+            }
+        }
+
+        Request::GetStatus => {
+            let state_arc = get_service_state();
+            let state = state_arc.lock().await;
+
+            // Update in_speech and queue_depth from transcribe state
+            let mut status = state.transcribe_status.clone();
+            if status.capturing {
+                if let Ok(transcribe) = get_transcribe_state().try_lock() {
+                    status.in_speech = transcribe.in_speech;
+                }
+                status.queue_depth = get_transcription_queue().queue_depth();
+                status.aec_metrics = platform::aec_metrics();
+                status.sync_metrics = platform::sync_metrics();
+                status.capture_latency = platform::capture_latency();
+            }
+            status.controller = control::controller_name();
+            status.muted = state.muted;
+            status.permission_error = permissions::require(Permission::Microphone)
+                .err()
+                .map(|e| e.to_string());
+
+            Response::Status(status)
+        }
+
+        Request::SubscribeEvents => {
+            // Actual subscription is handled in the server
+            Response::Subscribed
+        }
+
+        Request::GetModelStatus => {
+            let path = model::model_path();
+            let info = if path.exists() {
+                match model::inspect(&path) {
+                    Ok(info) => Some(info),
+                    Err(e) => {
+                        tracing::warn!("Model file is not valid: {}", e);
+                        None
+                    }
+                }
+            } else {
+                None
+            };
+            Response::ModelStatus(ModelStatus {
+                available: info.is_some(),
+                path: path.to_string_lossy().to_string(),
+                custom: model::is_custom_path(),
+                loaded: pool::is_loaded(&path),
+                info,
+            })
+        }
+
+        Request::LoadModel => {
+            let gpu = {
+                let state_arc = get_service_state();
+                let state = state_arc.lock().await;
+                GpuSettings {
+                    enabled: state.use_gpu,
+                    device: state.gpu_device,
+                }
+            };
+            let load = move || {
+                let mut transcriber = Transcriber::new();
+                transcriber.set_gpu(gpu);
+                // Dropping the transcriber leaves the model loaded in the pool
+                transcriber.load_model()
+            };
+            match tokio::task::spawn_blocking(load).await {
+                Ok(Ok(())) => Response::Ok,
+                Ok(Err(e)) => Response::from(e),
+                Err(e) => task_failed("Model loading", e),
+            }
+        }
+
+        Request::UnloadModel => {
+            pool::unload();
+            Response::Ok
+        }
+
+        Request::CancelTranscription => {
+            let discarded = get_transcription_queue().cancel();
+            info!(
+                "Transcription cancelled ({} queued segments discarded)",
+                discarded
+            );
+            broadcast_event(Response::Event {
+                event: EventType::TranscriptionCancelled { discarded },
+            });
+            Response::Ok
+        }
+
+        Request::CaptureLastN { secs } => {
+            let audio = match rolling_buffer::last(secs) {
+                Some(audio) => audio,
+                None => {
+                    return Response::error_with_code(
+                        ErrorCode::NotReady,
+                        "Nothing captured yet; the rolling buffer fills while capture runs",
+                    )
+                }
+            };
+            let frames = audio.samples.len() / audio.channels.max(1) as usize;
+            let duration_ms = frames as u64 * 1000 / audio.sample_rate.max(1) as u64;
+
+            let (path, audio) =
+                match tokio::task::spawn_blocking(move || (rolling_buffer::save(&audio), audio))
+                    .await
+                {
+                    Ok((Ok(path), audio)) => (path, audio),
+                    Ok((Err(e), _)) => {
+                        return Response::error_with_code(ErrorCode::StorageFailed, e)
+                    }
+                    Err(e) => return task_failed("Capture task", e),
+                };
+            let display_path = path.to_string_lossy().to_string();
+
+            let queued = get_transcription_queue().enqueue(QueuedSegment {
+                samples: audio.samples,
+                sample_rate: audio.sample_rate,
+                channels: audio.channels,
+                wav_path: Some(path),
+                is_partial: false,
+                source_file: None,
+                output: None,
+            });
+            if !queued {
+                return Response::error_with_code(
+                    ErrorCode::QueueFull,
+                    format!(
+                        "Transcription queue is full; audio saved to {}",
+                        display_path
+                    ),
+                );
+            }
+
+            info!(
+                "Captured last {} ms of audio to {}",
+                duration_ms, display_path
+            );
+            Response::Captured {
+                path: display_path,
+                duration_ms,
+            }
+        }
+
+        Request::GetRecentTranscript { seconds } => {
+            Response::RecentTranscript(recent::last(seconds))
+        }
+
+        Request::TranscribeFile { path } => {
+            let (gpu, language) = {
+                let state_arc = get_service_state();
+                let state = state_arc.lock().await;
+                (
+                    GpuSettings {
+                        enabled: state.use_gpu,
+                        device: state.gpu_device,
+                    },
+                    state.language.clone(),
+                )
+            };
+
+            let transcribe = move || -> Result<Transcript, FlowSttError> {
+                let audio = audio::read_wav(std::path::Path::new(&path))
+                    .and_then(audio::process_recorded_audio)
+                    .map_err(FlowSttError::Transcription)?;
+                let mut transcriber = Transcriber::new();
+                transcriber.set_gpu(gpu);
+                let transcript = transcriber.transcribe(&audio, &language);
+                // Let live transcription have the model back
+                transcriber.release();
+                transcript
+            };
+            match tokio::task::spawn_blocking(transcribe).await {
+                Ok(Ok(mut transcript)) => {
+                    postprocess::apply(&mut transcript);
+                    redact::transcript(RedactionChannel::Output, &mut transcript);
+                    if transcript.text == NO_SPEECH_TEXT {
+                        transcript.text.clear();
+                    }
+                    Response::Transcribed(TranscriptionResult {
+                        text: transcript.text,
+                        audio_path: None,
+                        language: transcript.language,
+                        words: transcript.words,
+                        segments: transcript.segments,
+                        confidence: transcript.confidence,
+                        translation: transcript.translation,
+                        modified: transcript.modified,
+                        speaker: transcript.speaker,
+                        app: None,
+                        id: None,
+                        duration_ms: transcript.duration_ms,
+                    })
+                }
+                Ok(Err(e)) => Response::from(e),
+                Err(e) => task_failed("Transcription task", e),
+            }
+        }
+
+        Request::ListModels => Response::Models {
+            models: model::list_variants(),
+        },
+
+        Request::DownloadModel { name } => {
+            let name = match name {
+                Some(name) if !model::is_known_model(&name) => {
+                    return not_found(
+                        ItemKind::Model,
+                        name.clone(),
+                        format!("Unknown model: {}", name),
+                    );
+                }
+                Some(name) => name,
+                None if model::is_custom_path() => {
+                    return Response::error_with_code(
+                        ErrorCode::InvalidRequest,
+                        "A custom model path is configured; clear model.path to download the default model",
+                    );
+                }
+                None => model::model_name(),
+            };
+            let model_path = model::variant_path(&name);
+
+            if model_path.exists() {
+                return Response::error_with_code(
+                    ErrorCode::ModelAlreadyDownloaded,
+                    "Model already downloaded",
+                );
+            }
+
+            // Download in background
+            let path_clone = model_path.clone();
+            tokio::task::spawn_blocking(move || {
+                // Broadcast progress (simplified - just start/end)
+                broadcast_event(Response::Event {
+                    event: EventType::ModelDownloadProgress { percent: 0 },
+                });
+
+                let result = download_model(&name, &path_clone);
+
+                match result {
+                    Ok(()) => {
+                        broadcast_event(Response::Event {
+                            event: EventType::ModelDownloadProgress { percent: 100 },
+                        });
+                        broadcast_event(Response::Event {
+                            event: EventType::ModelDownloadComplete { success: true },
+                        });
+                    }
+                    Err(e) => {
+                        tracing::error!("Model download failed: {}", e);
+                        broadcast_event(Response::Event {
+                            event: EventType::ModelDownloadComplete { success: false },
+                        });
+                    }
+                }
+            });
+
+            Response::Ok
+        }
+
+        Request::SetTranscriptionMode { mode } => {
+            let state_arc = get_service_state();
+
+            let (old_mode, is_ready) = {
+                let mut state = state_arc.lock().await;
+                let old_mode = state.transcription_mode;
+                state.transcription_mode = mode;
+                (old_mode, state.app_ready && state.has_primary_source())
+            };
+
+            // If mode changed and system is ready, restart capture with new mode
+            if old_mode != mode && is_ready {
+                // Stop current capture
+                stop_capture().await;
+
+                // Restart with new mode
+                if let Err(e) = start_capture().await {
+                    tracing::warn!("Failed to restart capture after mode change: {}", e);
+                }
+            }
+
+            // Save configuration to disk
+            save_config(&*state_arc.lock().await);
+
+            info!("Transcription mode set to {:?}", mode);
+
+            // Broadcast mode change event
+            broadcast_event(Response::Event {
+                event: EventType::TranscriptionModeChanged { mode },
+            });
+
+            Response::Ok
+        }
+
+        Request::SetPushToTalkKey { key } => {
+            let hotkeys = get_service_state().lock().await.hotkeys.clone();
+            if let Err(response) = set_hotkeys(key, hotkeys).await {
+                return response;
+            }
+
+            info!("PTT key set to {:?}", key);
+            Response::Ok
+        }
+
+        Request::SetHotkey { binding } => {
+            let (mut ptt_key, mut hotkeys) = {
+                let state_arc = get_service_state();
+                let state = state_arc.lock().await;
+                (state.ptt_key, state.hotkeys.clone())
+            };
+            if binding.action == HotkeyAction::PushToTalk {
+                ptt_key = binding.key;
+            } else {
+                hotkeys.retain(|b| b.action != binding.action);
+                hotkeys.push(binding);
+            }
+            if let Err(response) = set_hotkeys(ptt_key, hotkeys).await {
+                return response;
+            }
+
+            info!(
+                "Hotkey {:?} bound to {}",
+                binding.key,
+                binding.action.name()
+            );
+            Response::Ok
+        }
+
+        Request::RemoveHotkey { action } => {
+            if action == HotkeyAction::PushToTalk {
+                return Response::error_with_code(
+                    ErrorCode::InvalidRequest,
+                    "The push-to-talk key can't be removed",
+                );
+            }
+            let (ptt_key, mut hotkeys) = {
+                let state_arc = get_service_state();
+                let state = state_arc.lock().await;
+                (state.ptt_key, state.hotkeys.clone())
+            };
+            let count = hotkeys.len();
+            hotkeys.retain(|b| b.action != action);
+            if hotkeys.len() == count {
+                return not_found(
+                    ItemKind::Hotkey,
+                    action.name(),
+                    format!("No hotkey is bound to {}", action.name()),
+                );
+            }
+            if let Err(response) = set_hotkeys(ptt_key, hotkeys).await {
+                return response;
+            }
+
+            info!("Hotkey for {} removed", action.name());
+            Response::Ok
+        }
+
+        Request::ListHotkeys => Response::Hotkeys {
+            bindings: get_service_state().lock().await.hotkey_bindings(),
+        },
+
         Request::GetPttStatus => {
             let state_arc = get_service_state();
             let state = state_arc.lock().await;
@@ -495,6 +2499,40 @@ pub async fn handle_request(request: Request) -> Response {
             })
         }
 
+        Request::Benchmark {
+            path,
+            iterations,
+            compare_cpu,
+        } => {
+            let (gpu, language) = {
+                let state_arc = get_service_state();
+                let state = state_arc.lock().await;
+                (
+                    GpuSettings {
+                        enabled: state.use_gpu,
+                        device: state.gpu_device,
+                    },
+                    state.language.clone(),
+                )
+            };
+
+            let result = tokio::task::spawn_blocking(move || {
+                crate::transcription::benchmark::run(
+                    path.map(std::path::PathBuf::from),
+                    &language,
+                    iterations,
+                    gpu,
+                    compare_cpu,
+                )
+            })
+            .await;
+            match result {
+                Ok(Ok(result)) => Response::Benchmark(result),
+                Ok(Err(e)) => Response::error_with_code(ErrorCode::BenchmarkFailed, e),
+                Err(e) => task_failed("Benchmark", e),
+            }
+        }
+
         Request::GetCudaStatus => {
             // Check build-time CUDA support
             #[cfg(all(any(target_os = "linux", target_os = "windows"), feature = "cuda"))]
@@ -514,13 +2552,40 @@ pub async fn handle_request(request: Request) -> Response {
                     Err(e) => (false, format!("Error: {}", e)),
                 };
 
+            let state_arc = get_service_state();
+            let state = state_arc.lock().await;
+
             Response::CudaStatus(CudaStatus {
                 build_enabled,
                 runtime_available,
                 system_info,
+                gpu_enabled: state.use_gpu,
+                gpu_device: state.gpu_device,
             })
         }
 
+        Request::SetGpuEnabled { enabled } => {
+            let state_arc = get_service_state();
+            let mut state = state_arc.lock().await;
+            state.use_gpu = enabled;
+            apply_gpu_settings(&state);
+            save_config(&state);
+
+            info!("GPU acceleration enabled: {}", enabled);
+            Response::Ok
+        }
+
+        Request::SetGpuDevice { device } => {
+            let state_arc = get_service_state();
+            let mut state = state_arc.lock().await;
+            state.gpu_device = device;
+            apply_gpu_settings(&state);
+            save_config(&state);
+
+            info!("GPU device: {}", device);
+            Response::Ok
+        }
+
         Request::AppReady => {
             let state_arc = get_service_state();
 
@@ -542,12 +2607,12 @@ pub async fn handle_request(request: Request) -> Response {
             if should_capture {
                 if let Err(e) = start_capture().await {
                     let mut state = state_arc.lock().await;
-                    state.transcribe_status.error = Some(e.clone());
+                    state.transcribe_status.error = Some(e.to_string());
 
                     broadcast_event(Response::Event {
                         event: EventType::CaptureStateChanged {
                             capturing: false,
-                            error: Some(e),
+                            error: Some(e.to_string()),
                         },
                     });
                 }
@@ -602,3 +2667,47 @@ pub async fn handle_request(request: Request) -> Response {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn error_code(request: Request) -> ErrorCode {
+        match handle_request(request).await {
+            Response::Error { code, .. } => code,
+            other => panic!("expected an error, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failures_carry_specific_codes() {
+        assert_eq!(
+            error_code(Request::RemoveHotkey {
+                action: HotkeyAction::PushToTalk
+            })
+            .await,
+            ErrorCode::InvalidRequest
+        );
+        assert_eq!(
+            error_code(Request::PausePlayback { paused: true }).await,
+            ErrorCode::NotPlaying
+        );
+        assert_eq!(
+            error_code(Request::SeekPlayback { position_ms: 1000 }).await,
+            ErrorCode::NotPlaying
+        );
+        assert_eq!(
+            error_code(Request::CaptureLastN { secs: Some(5) }).await,
+            ErrorCode::NotReady
+        );
+        assert_eq!(
+            error_code(Request::Benchmark {
+                path: Some("/nonexistent/flowstt-benchmark.wav".to_string()),
+                iterations: 1,
+                compare_cpu: false,
+            })
+            .await,
+            ErrorCode::BenchmarkFailed
+        );
+    }
+}
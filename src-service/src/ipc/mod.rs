@@ -1,6 +1,10 @@
 //! IPC server for client communication.
 
+mod control;
 pub mod handlers;
+#[cfg(windows)]
+mod pipe_security;
 mod server;
 
-pub use server::{broadcast_event, run_server};
+pub use control::authorize;
+pub use server::{broadcast_event, get_event_sender, run_server};
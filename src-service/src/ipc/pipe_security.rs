@@ -0,0 +1,108 @@
+//! Access control for the Windows named pipe.
+//!
+//! A pipe created with default security can be opened for reading by
+//! everyone. The service's pipe instead gets a protected DACL that grants
+//! access to the user running the service and LocalSystem only. When running
+//! as a Windows service (as LocalSystem), administrators are allowed too, so
+//! an elevated CLI or GUI can reach it. Other users are never let in: the
+//! service runs hooks and writes its configuration on clients' behalf.
+
+use std::ffi::c_void;
+
+use windows::core::{PCWSTR, PWSTR};
+use windows::Win32::Foundation::{CloseHandle, LocalFree, HANDLE, HLOCAL};
+use windows::Win32::Security::Authorization::{
+    ConvertSidToStringSidW, ConvertStringSecurityDescriptorToSecurityDescriptorW, SDDL_REVISION_1,
+};
+use windows::Win32::Security::{
+    GetTokenInformation, TokenUser, PSECURITY_DESCRIPTOR, SECURITY_ATTRIBUTES, TOKEN_QUERY,
+    TOKEN_USER,
+};
+use windows::Win32::System::Threading::{GetCurrentProcess, OpenProcessToken};
+
+/// Security attributes to create pipe instances with.
+pub struct PipeSecurity {
+    descriptor: PSECURITY_DESCRIPTOR,
+    attributes: SECURITY_ATTRIBUTES,
+}
+
+// The descriptor is an immutable LocalAlloc'd buffer owned by this struct
+unsafe impl Send for PipeSecurity {}
+
+impl PipeSecurity {
+    /// Restrict the pipe to the current user, and also to administrators if
+    /// `allow_administrators` is set.
+    pub fn new(allow_administrators: bool) -> Result<Self, String> {
+        let sid = current_user_sid()?;
+        let mut sddl = format!("D:P(A;;GA;;;{})(A;;GA;;;SY)", sid);
+        if allow_administrators {
+            sddl.push_str("(A;;GA;;;BA)");
+        }
+        let wide: Vec<u16> = sddl.encode_utf16().chain(Some(0)).collect();
+
+        let mut descriptor = PSECURITY_DESCRIPTOR::default();
+        unsafe {
+            ConvertStringSecurityDescriptorToSecurityDescriptorW(
+                PCWSTR(wide.as_ptr()),
+                SDDL_REVISION_1,
+                &mut descriptor,
+                None,
+            )
+        }
+        .map_err(|e| format!("Failed to build pipe security descriptor: {}", e))?;
+
+        Ok(Self {
+            descriptor,
+            attributes: SECURITY_ATTRIBUTES {
+                nLength: std::mem::size_of::<SECURITY_ATTRIBUTES>() as u32,
+                lpSecurityDescriptor: descriptor.0,
+                bInheritHandle: false.into(),
+            },
+        })
+    }
+
+    /// Pointer for `ServerOptions::create_with_security_attributes_raw`.
+    pub fn as_ptr(&mut self) -> *mut c_void {
+        &mut self.attributes as *mut SECURITY_ATTRIBUTES as *mut c_void
+    }
+}
+
+impl Drop for PipeSecurity {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = LocalFree(HLOCAL(self.descriptor.0));
+        }
+    }
+}
+
+/// SID of the user the service runs as, in string form.
+fn current_user_sid() -> Result<String, String> {
+    unsafe {
+        let mut token = HANDLE::default();
+        OpenProcessToken(GetCurrentProcess(), TOKEN_QUERY, &mut token)
+            .map_err(|e| format!("Failed to open process token: {}", e))?;
+
+        // The first call only reports the size needed
+        let mut len = 0u32;
+        let _ = GetTokenInformation(token, TokenUser, None, 0, &mut len);
+        // u64 elements keep the buffer aligned for TOKEN_USER
+        let mut buffer = vec![0u64; (len as usize).div_ceil(8)];
+        let result = GetTokenInformation(
+            token,
+            TokenUser,
+            Some(buffer.as_mut_ptr() as *mut c_void),
+            len,
+            &mut len,
+        );
+        let _ = CloseHandle(token);
+        result.map_err(|e| format!("Failed to query process user: {}", e))?;
+
+        let user = &*(buffer.as_ptr() as *const TOKEN_USER);
+        let mut sid = PWSTR::null();
+        ConvertSidToStringSidW(user.User.Sid, &mut sid)
+            .map_err(|e| format!("Failed to format user SID: {}", e))?;
+        let result = sid.to_string().map_err(|e| e.to_string());
+        let _ = LocalFree(HLOCAL(sid.0 as *mut c_void));
+        result
+    }
+}
@@ -4,11 +4,14 @@
 //! and routes requests to handlers. It supports both Unix sockets (Linux/macOS)
 //! and named pipes (Windows).
 
-use flowstt_common::ipc::{get_socket_path, read_json, write_json, IpcError, Request, Response};
-use std::sync::Arc;
-use tokio::sync::{broadcast, Mutex};
+use flowstt_common::ipc::{
+    get_socket_path, negotiate_version, read_json, write_json, Capability, ErrorCode, EventType,
+    IpcError, Request, Response,
+};
+use tokio::sync::{broadcast, mpsc};
 use tracing::{error, info, warn};
 
+use super::control::{self, ClientId};
 use super::handlers::handle_request;
 use crate::is_shutdown_requested;
 
@@ -35,6 +38,15 @@ pub fn broadcast_event(event: Response) {
     let _ = sender.send(event);
 }
 
+/// Capabilities announced to clients, with fault injection in debug builds.
+fn capabilities() -> Vec<Capability> {
+    let mut capabilities = Capability::ALL.to_vec();
+    if cfg!(debug_assertions) {
+        capabilities.push(Capability::FaultInjection);
+    }
+    capabilities
+}
+
 /// Run the IPC server until shutdown.
 #[cfg(unix)]
 pub async fn run_server() -> Result<(), IpcError> {
@@ -104,54 +116,72 @@ async fn handle_unix_client(stream: tokio::net::UnixStream) -> Result<(), IpcErr
 /// Run the IPC server on Windows using named pipes.
 #[cfg(windows)]
 pub async fn run_server() -> Result<(), IpcError> {
+    use super::pipe_security::PipeSecurity;
     use tokio::net::windows::named_pipe::{PipeMode, ServerOptions};
 
     let pipe_name = get_socket_path();
     let pipe_name_str = pipe_name.to_string_lossy();
+    let mut security = PipeSecurity::new(crate::scm::is_service_mode())
+        .map_err(|e| IpcError::Io(std::io::Error::other(e)))?;
+
+    // Create a pipe instance, accessible to the current user only
+    let create = |first: bool, security: &mut PipeSecurity| unsafe {
+        ServerOptions::new()
+            .first_pipe_instance(first)
+            .reject_remote_clients(true)
+            .pipe_mode(PipeMode::Byte)
+            .create_with_security_attributes_raw(&pipe_name, security.as_ptr())
+    };
+
+    // The first instance must be ours; failing means another process holds the name
+    let mut server = create(true, &mut security).map_err(|e| {
+        error!("Failed to create pipe {}: {}", pipe_name_str, e);
+        IpcError::Io(e)
+    })?;
     info!("IPC server listening on {}", pipe_name_str);
 
+    // One instance is always left waiting, so the name is never free for
+    // another process to take between clients
+    let mut shutdown_check = tokio::time::interval(std::time::Duration::from_millis(100));
     loop {
-        if is_shutdown_requested() {
-            info!("Shutdown requested, stopping IPC server");
-            break;
-        }
-
-        // Create a new pipe instance
-        let server = match ServerOptions::new()
-            .first_pipe_instance(false)
-            .pipe_mode(PipeMode::Byte)
-            .create(&pipe_name)
-        {
-            Ok(s) => s,
-            Err(e) => {
-                error!("Failed to create pipe: {}", e);
-                tokio::time::sleep(std::time::Duration::from_secs(1)).await;
-                continue;
-            }
-        };
-
-        // Wait for client with timeout
-        let connect_result =
-            tokio::time::timeout(std::time::Duration::from_secs(1), server.connect()).await;
-
-        match connect_result {
-            Ok(Ok(())) => {
-                info!("Client connected");
-                tokio::spawn(async move {
-                    if let Err(e) = handle_windows_client(server).await {
-                        if !matches!(e, IpcError::ConnectionClosed) {
-                            error!("Client error: {}", e);
+        tokio::select! {
+            connected = server.connect() => {
+                // Create the next instance before handing this one over
+                let next = loop {
+                    match create(false, &mut security) {
+                        Ok(next) => break next,
+                        Err(e) => {
+                            error!("Failed to create pipe: {}", e);
+                            if is_shutdown_requested() {
+                                return Ok(());
+                            }
+                            tokio::time::sleep(std::time::Duration::from_secs(1)).await;
                         }
                     }
-                    info!("Client disconnected");
-                });
-            }
-            Ok(Err(e)) => {
-                error!("Pipe connect error: {}", e);
+                };
+                let client = std::mem::replace(&mut server, next);
+                match connected {
+                    Ok(()) => {
+                        info!("Client connected");
+                        tokio::spawn(async move {
+                            if let Err(e) = handle_windows_client(client).await {
+                                if !matches!(e, IpcError::ConnectionClosed) {
+                                    error!("Client error: {}", e);
+                                }
+                            }
+                            info!("Client disconnected");
+                        });
+                    }
+                    Err(e) => {
+                        error!("Pipe connect error: {}", e);
+                    }
+                }
             }
-            Err(_) => {
-                // Timeout, check shutdown flag again
-                continue;
+            _ = shutdown_check.tick() => {
+                if is_shutdown_requested() {
+                    info!("Shutdown requested, stopping IPC server");
+                    break;
+                }
             }
         }
     }
@@ -169,115 +199,176 @@ async fn handle_windows_client(
 }
 
 /// Handle a client connection (platform-agnostic).
+///
+/// The client must open with a `hello` handshake. Requests are then answered
+/// in order. Once the client subscribes, events from the broadcast channel are
+/// pushed on the same connection between responses. Control of capture held
+/// by the client ends with the connection.
 async fn handle_client_connection<R, W>(reader: R, writer: W) -> Result<(), IpcError>
 where
     R: tokio::io::AsyncRead + Unpin + Send + 'static,
     W: tokio::io::AsyncWrite + Unpin + Send + 'static,
 {
-    let reader = Arc::new(Mutex::new(reader));
-    let writer = Arc::new(Mutex::new(writer));
-    let mut event_receiver: Option<broadcast::Receiver<Response>> = None;
-    let mut subscribed = false;
+    let client_id = control::next_client_id();
+    let result = serve_client(client_id, reader, writer).await;
+    control::release(client_id);
+    result
+}
 
-    loop {
-        if is_shutdown_requested() {
-            // Notify client of shutdown if subscribed
-            if subscribed {
-                let mut w = writer.lock().await;
-                let _ = write_json(
-                    &mut *w,
-                    &Response::Event {
-                        event: flowstt_common::ipc::EventType::Shutdown,
-                    },
-                )
-                .await;
+async fn serve_client<R, W>(
+    client_id: ClientId,
+    mut reader: R,
+    mut writer: W,
+) -> Result<(), IpcError>
+where
+    R: tokio::io::AsyncRead + Unpin + Send + 'static,
+    W: tokio::io::AsyncWrite + Unpin + Send + 'static,
+{
+    // Read requests on their own task: read_json is not cancel-safe, so it must
+    // not be raced against events in select! (a partial read would be lost)
+    let (request_tx, mut request_rx) = mpsc::channel::<Result<Request, IpcError>>(8);
+    let reader_task = tokio::spawn(async move {
+        loop {
+            let result = read_json(&mut reader).await;
+            let failed = result.is_err();
+            if request_tx.send(result).await.is_err() || failed {
+                break;
             }
-            break;
         }
+    });
 
-        // If subscribed to events, use select! to handle both events and requests efficiently
-        if subscribed {
-            if let Some(ref mut rx) = event_receiver {
-                let mut r = reader.lock().await;
-
-                tokio::select! {
-                    // Wait for event from broadcast channel
-                    event_result = rx.recv() => {
-                        drop(r); // Release reader lock before writing
-                        match event_result {
-                            Ok(event) => {
-                                let mut w = writer.lock().await;
-                                write_json(&mut *w, &event).await?;
-                            }
-                            Err(broadcast::error::RecvError::Lagged(n)) => {
-                                warn!("Client lagged {} events", n);
-                            }
-                            Err(broadcast::error::RecvError::Closed) => {
-                                // Channel closed, unsubscribe
-                                subscribed = false;
-                                event_receiver = None;
-                            }
-                        }
-                        continue;
-                    }
-                    // Wait for request from client (with longer timeout since events are prioritized)
-                    read_result = tokio::time::timeout(std::time::Duration::from_secs(1), read_json(&mut *r)) => {
-                        drop(r); // Release reader lock
-                        match read_result {
-                            Ok(Ok(request)) => {
-                                info!("Received request: {:?}", request);
-                                let response = handle_request(request).await;
-                                info!("Sending response: {:?}", response);
-                                let mut w = writer.lock().await;
-                                write_json(&mut *w, &response).await?;
-                            }
-                            Ok(Err(e)) => {
-                                return Err(e);
-                            }
-                            Err(_) => {
-                                // Timeout, continue loop to check for events/shutdown
+    let mut protocol_version: Option<u32> = None;
+    let mut event_receiver: Option<broadcast::Receiver<Response>> = None;
+    let mut shutdown_check = tokio::time::interval(std::time::Duration::from_millis(100));
+
+    let result = loop {
+        tokio::select! {
+            request = request_rx.recv() => {
+                let request = match request {
+                    Some(Ok(request)) => request,
+                    Some(Err(e)) => break Err(e),
+                    None => break Err(IpcError::ConnectionClosed),
+                };
+                info!("Received request: {:?}", request);
+
+                if let Request::Hello { protocol_version: client_version } = request {
+                    let response = match negotiate_version(client_version) {
+                        Ok(version) => {
+                            protocol_version = Some(version);
+                            Response::Welcome {
+                                protocol_version: version,
+                                service_version: env!("CARGO_PKG_VERSION").to_string(),
+                                capabilities: capabilities(),
                             }
                         }
-                        continue;
+                        Err(e) => Response::error_with_code(
+                            ErrorCode::UnsupportedProtocol {
+                                version: client_version,
+                            },
+                            e,
+                        ),
+                    };
+                    if let Err(e) = write_json(&mut writer, &response).await {
+                        break Err(e);
                     }
+                    continue;
+                }
+                if protocol_version.is_none() {
+                    warn!("Client sent {:?} before the handshake", request);
+                    let response = Response::error_with_code(
+                        ErrorCode::HandshakeRequired,
+                        "Handshake required: send hello first",
+                    );
+                    let _ = write_json(&mut writer, &response).await;
+                    break Ok(());
                 }
-            }
-        }
-
-        // Not subscribed - just handle requests with timeout
-        let read_result = {
-            let mut r = reader.lock().await;
-            tokio::time::timeout(std::time::Duration::from_millis(100), read_json(&mut *r)).await
-        };
-
-        match read_result {
-            Ok(Ok(request)) => {
-                info!("Received request: {:?}", request);
 
-                // Check if this is a subscribe request
-                if matches!(request, Request::SubscribeEvents) {
-                    subscribed = true;
+                // Start forwarding events before the response so none are missed
+                if matches!(request, Request::SubscribeEvents) && event_receiver.is_none() {
                     event_receiver = Some(get_event_sender().subscribe());
                 }
 
-                // Handle request
-                let response = handle_request(request).await;
+                let response = handle_client_request(client_id, request).await;
                 info!("Sending response: {:?}", response);
-
-                // Send response
-                let mut w = writer.lock().await;
-                write_json(&mut *w, &response).await?;
+                if let Err(e) = write_json(&mut writer, &response).await {
+                    break Err(e);
+                }
             }
-            Ok(Err(e)) => {
-                // Read error
-                return Err(e);
+            event = recv_event(&mut event_receiver) => {
+                match event {
+                    Ok(event) => {
+                        if let Err(e) = write_json(&mut writer, &event).await {
+                            break Err(e);
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(n)) => {
+                        warn!("Client lagged {} events", n);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => {
+                        // Channel closed, unsubscribe
+                        event_receiver = None;
+                    }
+                }
             }
-            Err(_) => {
-                // Timeout, continue loop
-                continue;
+            _ = shutdown_check.tick() => {
+                if is_shutdown_requested() {
+                    // Notify client of shutdown if subscribed
+                    if event_receiver.is_some() {
+                        let _ = write_json(
+                            &mut writer,
+                            &Response::Event {
+                                event: EventType::Shutdown,
+                            },
+                        )
+                        .await;
+                    }
+                    break Ok(());
+                }
             }
         }
+    };
+
+    reader_task.abort();
+    result
+}
+
+/// Handle a request, enforcing and updating control of capture.
+async fn handle_client_request(client_id: ClientId, request: Request) -> Response {
+    match &request {
+        Request::AcquireControl { client_name, force } => {
+            let result = request
+                .validate()
+                .map_err(|e| Response::error_with_code(ErrorCode::InvalidRequest, e))
+                .and_then(|()| control::acquire(client_id, client_name.trim(), *force));
+            return match result {
+                Ok(()) => Response::Ok,
+                Err(response) => response,
+            };
+        }
+        Request::ReleaseControl => {
+            control::release(client_id);
+            return Response::Ok;
+        }
+        _ => {}
     }
 
-    Ok(())
+    if let Err(response) = control::authorize(Some(client_id), &request) {
+        return response;
+    }
+    let stops_capture = control::stops_capture(&request);
+    let response = handle_request(request).await;
+    if stops_capture && matches!(response, Response::Ok) {
+        control::release(client_id);
+    }
+    response
+}
+
+/// Wait for the next event, or forever if the client has not subscribed.
+async fn recv_event(
+    receiver: &mut Option<broadcast::Receiver<Response>>,
+) -> Result<Response, broadcast::error::RecvError> {
+    match receiver {
+        Some(rx) => rx.recv().await,
+        None => std::future::pending().await,
+    }
 }
@@ -0,0 +1,83 @@
+//! Logging setup.
+//!
+//! Log records go to stderr and to daily rotating files in
+//! [`paths::log_dir`], so a service started without a terminal still leaves
+//! something to look at. Both outputs are plain text unless the service is
+//! started with `--log-json`, which writes one JSON object per record.
+//!
+//! The level filter starts from `RUST_LOG` (default `info`) and can be
+//! changed while running with [`set_level`].
+
+use std::sync::OnceLock;
+
+use flowstt_common::paths;
+use tracing::Subscriber;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, reload, EnvFilter, Layer, Registry};
+
+/// Number of daily log files kept before the oldest is deleted
+const MAX_LOG_FILES: usize = 7;
+
+/// Handle to swap the level filter of the installed subscriber
+static FILTER_HANDLE: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+
+/// Install the global subscriber.
+pub fn init() {
+    let json = std::env::args().any(|arg| arg == "--log-json");
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    let (filter, handle) = reload::Layer::new(filter);
+
+    let file_layer = match open_log_file() {
+        Ok(appender) => Some(output_layer(appender, json, false)),
+        Err(e) => {
+            eprintln!("File logging disabled: {}", e);
+            None
+        }
+    };
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(output_layer(std::io::stderr, json, true))
+        .with(file_layer)
+        .init();
+    let _ = FILTER_HANDLE.set(handle);
+}
+
+/// Replace the level filter, e.g. `debug` or `info,flowstt_service=trace`.
+pub fn set_level(directives: &str) -> Result<(), String> {
+    let filter = EnvFilter::try_new(directives)
+        .map_err(|e| format!("Invalid log level {:?}: {}", directives, e))?;
+    FILTER_HANDLE
+        .get()
+        .ok_or_else(|| "Logging is not initialized".to_string())?
+        .reload(filter)
+        .map_err(|e| format!("Failed to change log level: {}", e))
+}
+
+fn open_log_file() -> Result<RollingFileAppender, String> {
+    RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix(paths::SERVICE_LOG_PREFIX)
+        .filename_suffix(paths::SERVICE_LOG_SUFFIX)
+        .max_log_files(MAX_LOG_FILES)
+        .build(paths::log_dir())
+        .map_err(|e| e.to_string())
+}
+
+/// Text or JSON formatting layer writing to `writer`.
+fn output_layer<S, W>(writer: W, json: bool, ansi: bool) -> Box<dyn Layer<S> + Send + Sync>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    let layer = fmt::layer().with_writer(writer);
+    if json {
+        layer.json().boxed()
+    } else {
+        layer.with_ansi(ansi).boxed()
+    }
+}
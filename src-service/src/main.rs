@@ -5,23 +5,57 @@
 
 mod audio;
 mod audio_loop;
+mod calendar;
+mod capture;
+mod chapters;
+mod commands;
 pub mod config;
+mod cues;
+mod dictation;
+mod encoder;
+mod focus;
+mod history;
+mod hooks;
 mod hotkey;
+mod http;
 mod ipc;
+mod logging;
+mod metrics;
+mod osc;
+mod output;
+mod permissions;
+#[cfg(test)]
+mod pipeline_tests;
 mod platform;
+mod playback;
+mod postprocess;
 mod processor;
+mod profiles;
 mod ptt_controller;
+mod recent;
+mod redact;
+mod retention;
+mod retranscribe;
+mod rolling_buffer;
+#[cfg(windows)]
+mod scm;
+mod search;
+mod silero_vad;
 mod state;
+mod stats;
 mod transcription;
+mod vad_recording;
+mod watch;
+mod webhooks;
 
 pub use audio_loop::{
-    is_audio_loop_active, start_audio_loop, stop_audio_loop, TranscriptionEventBroadcaster,
+    finish_calibration, is_audio_loop_active, refresh_vad, set_vad_config, start_audio_loop,
+    start_calibration, stop_audio_loop, TranscriptionEventBroadcaster,
 };
 
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use tracing::{error, info, warn};
-use tracing_subscriber::EnvFilter;
 
 /// Global shutdown flag
 static SHUTDOWN_FLAG: std::sync::OnceLock<Arc<AtomicBool>> = std::sync::OnceLock::new();
@@ -49,11 +83,18 @@ fn main() {
     let check_gpu = std::env::args().any(|arg| arg == "--check-gpu");
 
     // Initialize logging with RUST_LOG env var support
-    tracing_subscriber::fmt()
-        .with_env_filter(
-            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")),
-        )
-        .init();
+    logging::init();
+    metrics::init();
+
+    // Started by the Windows service control manager, which runs the service
+    #[cfg(windows)]
+    if scm::is_service_mode() {
+        if let Err(e) = scm::run() {
+            error!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
 
     // If --check-gpu, just initialize whisper and print GPU status, then exit
     if check_gpu {
@@ -100,20 +141,133 @@ fn main() {
         return;
     }
 
+    run();
+}
+
+/// Load the configuration and serve clients until shutdown is requested.
+pub fn run() {
     info!("FlowSTT Service starting (pid: {})...", std::process::id());
 
+    if std::env::var_os(transcription::WHISPER_STUB_ENV).is_some() {
+        warn!(
+            "{} is set: transcription returns stub text instead of running whisper",
+            transcription::WHISPER_STUB_ENV
+        );
+        transcription::set_whisper_stub(true);
+    }
+
     // Load configuration from disk and apply to service state
-    let loaded_config = config::Config::load();
+    let mut loaded_config = config::Config::load();
+    if let Err(e) = loaded_config.vad.validate() {
+        warn!("Ignoring speech detector config: {}", e);
+        loaded_config.vad = Default::default();
+    }
+    if let Err(e) = loaded_config.aec.validate() {
+        warn!("Ignoring echo cancellation settings: {}", e);
+        loaded_config.aec = Default::default();
+    }
+    if let Err(e) = output::set_template(loaded_config.output_template.as_deref()) {
+        warn!("Ignoring output template: {}", e);
+        loaded_config.output_template = None;
+    }
     {
         let state = state::get_service_state();
         let mut state = state.blocking_lock();
         state.transcription_mode = loaded_config.transcription_mode;
         state.ptt_key = loaded_config.ptt_key;
+        state.hotkeys = loaded_config.hotkeys.clone();
+        state.language = loaded_config.language.clone();
+        state.translate = loaded_config.translate;
+        state.output_mode = loaded_config.output_mode;
+        state.output_template = loaded_config.output_template.clone();
+        state.latency_preset = loaded_config.latency_preset;
+        state.use_gpu = loaded_config.use_gpu;
+        state.gpu_device = loaded_config.gpu_device;
+        state.watch_folders = loaded_config.watch_folders.clone();
+        state.voice_commands = loaded_config.voice_commands.clone();
+        state.http_address = loaded_config.http_address.clone();
+        state.http_token = loaded_config.http_token.clone();
+        state.http_public_url = loaded_config.http_public_url.clone();
+        state.audio_config = loaded_config.audio.clone();
+        state.post_processing = loaded_config.post_processing.clone();
+        state.confidence = loaded_config.confidence;
+        state.cues = loaded_config.cues;
+        state.osc = loaded_config.osc.clone();
+        state.retention = loaded_config.retention;
+        state.vad_recording = loaded_config.vad_recording;
+        state.chapters = loaded_config.chapters;
+        state.rolling_buffer = loaded_config.rolling_buffer;
+        state.redaction = loaded_config.redaction.clone();
+        state.muted = loaded_config.muted;
+        state.profiles = loaded_config.profiles.clone();
+        state.active_profile = loaded_config.active_profile.clone();
+        state.hooks = loaded_config.hooks.clone();
+        state.webhooks = loaded_config.webhooks.clone();
+        state.calendar = loaded_config.calendar.clone();
+        state.recordings_dir = loaded_config.recordings_dir.clone();
+        state.model_path = loaded_config.model_path.clone();
+        state.model_name = loaded_config.model_name.clone();
+        state.recording_filename_template = loaded_config.recording_filename_template.clone();
+        state.recording_format = loaded_config.recording_format;
+        state.recording_processing = loaded_config.recording_processing;
+        state.vad_config = loaded_config.vad.clone();
+        state.aec_settings = loaded_config.aec;
+        state.spectrogram_settings = loaded_config.spectrogram;
         info!(
-            "Applied config: transcription_mode={:?}, ptt_key={:?}",
-            state.transcription_mode, state.ptt_key
+            "Applied config: transcription_mode={:?}, ptt_key={:?}, language={}, output_mode={:?}, use_gpu={}, gpu_device={}",
+            state.transcription_mode,
+            state.ptt_key,
+            state.language,
+            state.output_mode,
+            state.use_gpu,
+            state.gpu_device
         );
     }
+    output::set_output_mode(loaded_config.output_mode);
+    let queue = ipc::handlers::get_transcription_queue();
+    queue.set_language(loaded_config.language);
+    queue.set_translate(loaded_config.translate);
+    queue.set_confidence(loaded_config.confidence);
+    ipc::handlers::apply_latency_preset(loaded_config.latency_preset);
+    queue.set_gpu(transcription::GpuSettings {
+        enabled: loaded_config.use_gpu,
+        device: loaded_config.gpu_device,
+    });
+    watch::set_folders(&loaded_config.watch_folders);
+    commands::set_commands(&loaded_config.voice_commands);
+    hooks::set_hooks(&loaded_config.hooks);
+    webhooks::set_config(
+        &loaded_config.webhooks,
+        loaded_config.http_public_url.as_deref(),
+    );
+    transcription::model::set_model_path(loaded_config.model_path.clone());
+    transcription::model::set_model_name(loaded_config.model_name.clone());
+    postprocess::set_config(&loaded_config.post_processing);
+    cues::set_config(loaded_config.cues);
+    osc::set_config(loaded_config.osc.clone());
+    calendar::set_config(loaded_config.calendar.clone());
+    retention::set_config(loaded_config.retention);
+    vad_recording::set_config(loaded_config.vad_recording);
+    chapters::set_config(loaded_config.chapters);
+    rolling_buffer::set_config(loaded_config.rolling_buffer);
+    redact::set_config(&loaded_config.redaction);
+    audio::set_recordings_location(
+        loaded_config.recordings_dir.clone(),
+        loaded_config.recording_filename_template.clone(),
+    );
+    audio::set_recording_format(loaded_config.recording_format);
+    audio::set_recording_processing(loaded_config.recording_processing);
+    silero_vad::ensure_model(&loaded_config.vad);
+    set_vad_config(loaded_config.vad.clone());
+    processor::set_spectrogram_settings(loaded_config.spectrogram);
+    platform::set_aec_settings(loaded_config.aec);
+    platform::set_capture_buffer_ms(loaded_config.audio.buffer_ms);
+    platform::set_capture_mode(loaded_config.audio.capture_mode);
+    platform::set_capture_sample_rate(loaded_config.audio.sample_rate);
+    platform::set_channel_selections(loaded_config.audio.channels.clone());
+    let http_address = loaded_config.http_address.clone();
+    let http_token = loaded_config.http_token.clone();
+    let audio_config = loaded_config.audio.clone();
 
     // Set up signal handlers for graceful shutdown
     setup_signal_handlers();
@@ -127,7 +281,7 @@ fn main() {
     runtime.block_on(async {
         // Initialize platform-specific audio backends
         info!("Initializing audio backends...");
-        if let Err(e) = platform::init_audio_backend() {
+        if let Err(e) = platform::init_audio_backend(&audio_config) {
             error!("Failed to initialize audio backend: {}", e);
         }
 
@@ -140,6 +294,36 @@ fn main() {
         // Initialize transcription system (worker ready to process segments)
         ipc::handlers::init_transcription_system();
 
+        // Watch folders for new recordings
+        watch::start_watcher();
+
+        // Run re-transcription jobs as they are queued
+        retranscribe::start_worker();
+
+        // Forward speech and transcripts to OSC receivers while enabled
+        tokio::spawn(osc::run());
+
+        // Notify webhooks of sessions starting and of errors
+        tokio::spawn(webhooks::run());
+
+        // Keep the meetings sessions are named after up to date
+        tokio::spawn(calendar::run());
+
+        // Remove recordings beyond the retention limits
+        tokio::spawn(retention::run());
+
+        // Switch profiles as their applications come to the front
+        tokio::spawn(profiles::run_auto_switch());
+
+        // Serve the HTTP API if configured
+        if let Some(address) = http_address {
+            tokio::spawn(async move {
+                if let Err(e) = http::run_server(address, http_token).await {
+                    error!("{}", e);
+                }
+            });
+        }
+
         // In PTT mode, configure default audio source and start monitoring
         {
             let state_arc = state::get_service_state();
@@ -166,13 +350,13 @@ fn main() {
                     }
 
                     // Start hotkey monitoring
-                    let ptt_key = {
+                    let (ptt_key, bindings) = {
                         let state = state_arc.lock().await;
-                        state.ptt_key
+                        (state.ptt_key, state.hotkey_bindings())
                     };
 
-                    info!("PTT mode: Starting hotkey monitoring for {:?}", ptt_key);
-                    match hotkey::start_hotkey(ptt_key) {
+                    info!("PTT mode: Starting hotkey monitoring for {:?}", bindings);
+                    match hotkey::start_hotkeys(&bindings) {
                         Ok(()) => {
                             // Start PTT controller to handle key events
                             if let Err(e) = ptt_controller::start_ptt_controller() {
@@ -237,8 +421,9 @@ fn setup_signal_handlers() {
         });
     }
 
+    // Under the service control manager, stop requests come from the SCM instead
     #[cfg(windows)]
-    {
+    if !scm::is_service_mode() {
         // Windows uses Ctrl+C handler
         ctrlc::set_handler(|| {
             info!("Received Ctrl+C");
@@ -0,0 +1,214 @@
+//! Monitoring counters.
+//!
+//! Cheap counters updated from the audio loop and transcription worker, read
+//! through the `GetMetrics` request and the HTTP API's `/metrics` endpoint.
+
+use std::collections::VecDeque;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use flowstt_common::{AecMetrics, LatencyPercentiles, QueueDepthSample, ServiceMetrics};
+
+/// Number of queue depth changes kept
+const QUEUE_HISTORY_LEN: usize = 120;
+
+/// Number of transcription latencies percentiles are computed from
+const LATENCY_HISTORY_LEN: usize = 200;
+
+static STARTED: OnceLock<Instant> = OnceLock::new();
+static SAMPLES_PROCESSED: AtomicU64 = AtomicU64::new(0);
+static DROPPED_FRAMES: AtomicU64 = AtomicU64::new(0);
+static TRANSCRIPTIONS: AtomicU64 = AtomicU64::new(0);
+static QUEUE_HISTORY: Mutex<VecDeque<QueueDepthSample>> = Mutex::new(VecDeque::new());
+static LATENCIES_MS: Mutex<VecDeque<u64>> = Mutex::new(VecDeque::new());
+
+/// Start the uptime clock. Called once at startup.
+pub fn init() {
+    STARTED.get_or_init(Instant::now);
+}
+
+fn uptime() -> Duration {
+    STARTED.get_or_init(Instant::now).elapsed()
+}
+
+/// Count samples run through speech detection.
+pub fn add_samples_processed(count: usize) {
+    SAMPLES_PROCESSED.fetch_add(count as u64, Ordering::Relaxed);
+}
+
+/// Count audio frames lost to a full transcription queue.
+pub fn add_dropped_frames(count: usize) {
+    DROPPED_FRAMES.fetch_add(count as u64, Ordering::Relaxed);
+}
+
+/// Record a change in the transcription queue's depth.
+pub fn record_queue_depth(depth: usize) {
+    let mut history = QUEUE_HISTORY.lock().unwrap();
+    if history.back().is_some_and(|last| last.depth == depth) {
+        return;
+    }
+    if history.len() == QUEUE_HISTORY_LEN {
+        history.pop_front();
+    }
+    history.push_back(QueueDepthSample {
+        uptime_secs: uptime().as_secs_f64(),
+        depth,
+    });
+}
+
+/// Record how long a live segment took to transcribe.
+pub fn record_transcription(latency: Duration) {
+    TRANSCRIPTIONS.fetch_add(1, Ordering::Relaxed);
+    let mut latencies = LATENCIES_MS.lock().unwrap();
+    if latencies.len() == LATENCY_HISTORY_LEN {
+        latencies.pop_front();
+    }
+    latencies.push_back(latency.as_millis() as u64);
+}
+
+/// Current metrics. Queue and echo cancellation state come from the caller,
+/// which owns that state.
+pub fn snapshot(
+    queue_depth: usize,
+    aec_enabled: bool,
+    aec_metrics: Option<AecMetrics>,
+) -> ServiceMetrics {
+    let latencies: Vec<u64> = LATENCIES_MS.lock().unwrap().iter().copied().collect();
+    ServiceMetrics {
+        uptime_secs: uptime().as_secs(),
+        samples_processed: SAMPLES_PROCESSED.load(Ordering::Relaxed),
+        dropped_frames: DROPPED_FRAMES.load(Ordering::Relaxed),
+        transcriptions: TRANSCRIPTIONS.load(Ordering::Relaxed),
+        queue_depth,
+        queue_depth_history: QUEUE_HISTORY.lock().unwrap().iter().copied().collect(),
+        latency: percentiles(latencies),
+        aec_enabled,
+        aec_metrics,
+    }
+}
+
+/// Nearest-rank percentiles of `latencies`, or None if there are none.
+fn percentiles(mut latencies: Vec<u64>) -> Option<LatencyPercentiles> {
+    if latencies.is_empty() {
+        return None;
+    }
+    latencies.sort_unstable();
+    let rank = |p: f64| {
+        let index = (p * latencies.len() as f64).ceil() as usize;
+        latencies[index.clamp(1, latencies.len()) - 1]
+    };
+    Some(LatencyPercentiles {
+        p50_ms: rank(0.5),
+        p90_ms: rank(0.9),
+        p99_ms: rank(0.99),
+        samples: latencies.len(),
+    })
+}
+
+/// Render metrics in the Prometheus text exposition format.
+pub fn to_prometheus(metrics: &ServiceMetrics) -> String {
+    let mut out = String::new();
+    let mut metric = |name: &str, kind: &str, help: &str, value: String| {
+        let _ = writeln!(out, "# HELP flowstt_{} {}", name, help);
+        let _ = writeln!(out, "# TYPE flowstt_{} {}", name, kind);
+        let _ = writeln!(out, "flowstt_{} {}", name, value);
+    };
+
+    metric(
+        "uptime_seconds",
+        "gauge",
+        "Seconds since the service started.",
+        metrics.uptime_secs.to_string(),
+    );
+    metric(
+        "samples_processed_total",
+        "counter",
+        "Mono samples run through speech detection.",
+        metrics.samples_processed.to_string(),
+    );
+    metric(
+        "dropped_frames_total",
+        "counter",
+        "Audio frames discarded because the transcription queue was full.",
+        metrics.dropped_frames.to_string(),
+    );
+    metric(
+        "transcriptions_total",
+        "counter",
+        "Live segments transcribed.",
+        metrics.transcriptions.to_string(),
+    );
+    metric(
+        "queue_depth",
+        "gauge",
+        "Segments waiting to be transcribed.",
+        metrics.queue_depth.to_string(),
+    );
+    metric(
+        "aec_enabled",
+        "gauge",
+        "Whether echo cancellation is enabled.",
+        u8::from(metrics.aec_enabled).to_string(),
+    );
+    if let Some(aec) = &metrics.aec_metrics {
+        metric(
+            "aec_erle_db",
+            "gauge",
+            "Echo return loss enhancement in dB.",
+            aec.erle_db.to_string(),
+        );
+        metric(
+            "aec_delay_ms",
+            "gauge",
+            "Echo delay tracked by echo cancellation in milliseconds.",
+            aec.delay_ms.to_string(),
+        );
+    }
+
+    if let Some(latency) = &metrics.latency {
+        let _ = writeln!(
+            out,
+            "# HELP flowstt_transcription_latency_seconds Time to transcribe recent live segments."
+        );
+        let _ = writeln!(out, "# TYPE flowstt_transcription_latency_seconds summary");
+        for (quantile, ms) in [
+            ("0.5", latency.p50_ms),
+            ("0.9", latency.p90_ms),
+            ("0.99", latency.p99_ms),
+        ] {
+            let _ = writeln!(
+                out,
+                "flowstt_transcription_latency_seconds{{quantile=\"{}\"}} {}",
+                quantile,
+                ms as f64 / 1000.0
+            );
+        }
+        let _ = writeln!(
+            out,
+            "flowstt_transcription_latency_seconds_count {}",
+            latency.samples
+        );
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percentiles_use_nearest_rank() {
+        assert!(percentiles(Vec::new()).is_none());
+
+        let latency = percentiles((1..=100).rev().collect()).unwrap();
+        assert_eq!(latency.p50_ms, 50);
+        assert_eq!(latency.p90_ms, 90);
+        assert_eq!(latency.p99_ms, 99);
+        assert_eq!(latency.samples, 100);
+
+        let single = percentiles(vec![42]).unwrap();
+        assert_eq!((single.p50_ms, single.p99_ms), (42, 42));
+    }
+}
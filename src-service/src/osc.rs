@@ -0,0 +1,179 @@
+//! Open Sound Control output.
+//!
+//! Forwards speech activity and transcripts as UDP OSC messages so VRChat,
+//! OBS or TouchDesigner setups can react to speech. With the default prefix
+//! the messages are:
+//!
+//! - `/flowstt/speaking` with int 1 when speech starts and 0 when it ends
+//! - `/flowstt/transcript` with the transcribed text as a string
+//!
+//! With `chatbox` set, transcripts also go to VRChat's `/chatbox/input` and
+//! speech activity to `/chatbox/typing`.
+
+use std::sync::Mutex;
+
+use flowstt_common::ipc::{EventType, Response};
+use tokio::net::UdpSocket;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{debug, warn};
+
+use crate::config::OscConfig;
+use crate::ipc::get_event_sender;
+use crate::transcription::NO_SPEECH_TEXT;
+
+/// Longest message VRChat's chatbox displays, in characters
+const CHATBOX_MAX_CHARS: usize = 144;
+
+static CONFIG: Mutex<Option<OscConfig>> = Mutex::new(None);
+
+/// Replace the OSC settings; takes effect with the next event.
+pub fn set_config(config: OscConfig) {
+    *CONFIG.lock().unwrap() = Some(config);
+}
+
+/// An OSC message argument.
+#[derive(Debug, Clone, PartialEq)]
+enum Arg {
+    Int(i32),
+    Str(String),
+    Bool(bool),
+}
+
+/// Forward events to the configured OSC receiver until shutdown.
+pub async fn run() {
+    let socket = match UdpSocket::bind("0.0.0.0:0").await {
+        Ok(socket) => socket,
+        Err(e) => {
+            warn!("OSC output unavailable: {}", e);
+            return;
+        }
+    };
+    let mut events = get_event_sender().subscribe();
+
+    while !crate::is_shutdown_requested() {
+        let event = match events.recv().await {
+            Ok(Response::Event { event }) => event,
+            Ok(_) => continue,
+            // Visualization data can outpace us; missing some of it is fine
+            Err(RecvError::Lagged(_)) => continue,
+            Err(RecvError::Closed) => break,
+        };
+        let Some(config) = CONFIG.lock().unwrap().clone().filter(|c| c.enabled) else {
+            continue;
+        };
+
+        for (address, args) in messages(&config, &event) {
+            let packet = encode(&address, &args);
+            if let Err(e) = socket.send_to(&packet, config.address.as_str()).await {
+                warn!("Failed to send OSC message to {}: {}", config.address, e);
+                break;
+            }
+            debug!("Sent OSC {} to {}", address, config.address);
+        }
+    }
+}
+
+/// OSC messages for `event`, as (address, arguments) pairs.
+fn messages(config: &OscConfig, event: &EventType) -> Vec<(String, Vec<Arg>)> {
+    let speaking = format!("{}/speaking", config.prefix);
+    let mut messages = Vec::new();
+    match event {
+        EventType::SpeechStarted => {
+            messages.push((speaking, vec![Arg::Int(1)]));
+            if config.chatbox {
+                messages.push(("/chatbox/typing".to_string(), vec![Arg::Bool(true)]));
+            }
+        }
+        EventType::SpeechEnded { .. } => {
+            messages.push((speaking, vec![Arg::Int(0)]));
+            if config.chatbox {
+                messages.push(("/chatbox/typing".to_string(), vec![Arg::Bool(false)]));
+            }
+        }
+        EventType::TranscriptionComplete(result) if result.text != NO_SPEECH_TEXT => {
+            messages.push((
+                format!("{}/transcript", config.prefix),
+                vec![Arg::Str(result.text.clone())],
+            ));
+            if config.chatbox {
+                // Send immediately, without the notification sound
+                let text = result.text.chars().take(CHATBOX_MAX_CHARS).collect();
+                messages.push((
+                    "/chatbox/input".to_string(),
+                    vec![Arg::Str(text), Arg::Bool(true), Arg::Bool(false)],
+                ));
+            }
+        }
+        _ => {}
+    }
+    messages
+}
+
+/// Encode an OSC 1.0 message.
+fn encode(address: &str, args: &[Arg]) -> Vec<u8> {
+    let mut packet = Vec::new();
+    push_string(&mut packet, address);
+
+    let mut tags = String::from(",");
+    for arg in args {
+        tags.push(match arg {
+            Arg::Int(_) => 'i',
+            Arg::Str(_) => 's',
+            Arg::Bool(true) => 'T',
+            Arg::Bool(false) => 'F',
+        });
+    }
+    push_string(&mut packet, &tags);
+
+    for arg in args {
+        match arg {
+            Arg::Int(value) => packet.extend_from_slice(&value.to_be_bytes()),
+            Arg::Str(value) => push_string(&mut packet, value),
+            // Booleans are carried entirely by their type tag
+            Arg::Bool(_) => {}
+        }
+    }
+    packet
+}
+
+/// Append a null-terminated string padded to a multiple of four bytes.
+fn push_string(packet: &mut Vec<u8>, value: &str) {
+    packet.extend_from_slice(value.as_bytes());
+    let padding = 4 - value.len() % 4;
+    packet.resize(packet.len() + padding, 0);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_message() {
+        assert_eq!(
+            encode("/a", &[Arg::Int(1)]),
+            b"/a\0\0,i\0\0\0\0\0\x01".to_vec()
+        );
+        // A string that fills its four bytes still gets a null terminator
+        assert_eq!(
+            encode("/abc", &[Arg::Str("hi".to_string()), Arg::Bool(true)]),
+            b"/abc\0\0\0\0,sT\0hi\0\0".to_vec()
+        );
+    }
+
+    #[test]
+    fn test_chatbox_messages_are_optional() {
+        let mut config = OscConfig::default();
+        let addresses = |config: &OscConfig| -> Vec<String> {
+            messages(config, &EventType::SpeechStarted)
+                .into_iter()
+                .map(|(address, _)| address)
+                .collect()
+        };
+        assert_eq!(addresses(&config), vec!["/flowstt/speaking"]);
+        config.chatbox = true;
+        assert_eq!(
+            addresses(&config),
+            vec!["/flowstt/speaking", "/chatbox/typing"]
+        );
+    }
+}
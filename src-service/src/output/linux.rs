@@ -0,0 +1,90 @@
+//! Linux keystroke output using xdotool (X11) or wtype (Wayland), and
+//! clipboard output using xclip (X11) or wl-copy (Wayland).
+//!
+//! Wayland has no portable API for synthetic input from unprivileged clients,
+//! so both backends shell out to the standard helper tools.
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Check whether the session is running under Wayland
+fn is_wayland_session() -> bool {
+    std::env::var("XDG_SESSION_TYPE").is_ok_and(|t| t == "wayland")
+        || std::env::var_os("WAYLAND_DISPLAY").is_some()
+}
+
+/// Type text into the focused application.
+pub fn type_text(text: &str) -> Result<(), String> {
+    let (program, args): (&str, &[&str]) = if is_wayland_session() {
+        ("wtype", &["--"])
+    } else {
+        // Release held modifiers (e.g. the PTT key) so they don't alter the text
+        (
+            "xdotool",
+            &["type", "--clearmodifiers", "--delay", "0", "--"],
+        )
+    };
+
+    let status = Command::new(program)
+        .args(args)
+        .arg(text)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map_err(|e| {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                format!("{} not found; install it to use typing output", program)
+            } else {
+                format!("Failed to run {}: {}", program, e)
+            }
+        })?;
+
+    if !status.success() {
+        return Err(format!("{} exited with {}", program, status));
+    }
+
+    Ok(())
+}
+
+/// Replace the clipboard contents with text.
+pub fn copy_text(text: &str) -> Result<(), String> {
+    let (program, args): (&str, &[&str]) = if is_wayland_session() {
+        ("wl-copy", &[])
+    } else {
+        ("xclip", &["-selection", "clipboard"])
+    };
+
+    // Both tools fork to serve the selection once they have read the text
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                format!("{} not found; install it to use clipboard output", program)
+            } else {
+                format!("Failed to run {}: {}", program, e)
+            }
+        })?;
+
+    let mut stdin = child
+        .stdin
+        .take()
+        .ok_or("Failed to open clipboard tool input")?;
+    stdin
+        .write_all(text.as_bytes())
+        .map_err(|e| format!("Failed to write to {}: {}", program, e))?;
+    drop(stdin);
+
+    let status = child
+        .wait()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+    if !status.success() {
+        return Err(format!("{} exited with {}", program, status));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,89 @@
+//! macOS keystroke output using CGEventPost, and clipboard output using pbcopy.
+//!
+//! Text is attached to keyboard events as a Unicode string, so it doesn't
+//! depend on the keyboard layout. Posting events requires Accessibility
+//! permission (the same permission push-to-talk needs).
+
+use std::ffi::c_void;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Maximum UTF-16 units attached to a single keyboard event
+const MAX_CHUNK_LEN: usize = 20;
+
+type CGEventRef = *mut c_void;
+type CGEventSourceRef = *mut c_void;
+
+/// kCGHIDEventTap - post at the HID level, as if typed on the keyboard
+const CG_HID_EVENT_TAP: u32 = 0;
+
+#[link(name = "CoreGraphics", kind = "framework")]
+extern "C" {
+    fn CGEventCreateKeyboardEvent(
+        source: CGEventSourceRef,
+        virtual_key: u16,
+        key_down: bool,
+    ) -> CGEventRef;
+    fn CGEventKeyboardSetUnicodeString(event: CGEventRef, length: usize, string: *const u16);
+    fn CGEventPost(tap: u32, event: CGEventRef);
+}
+
+#[link(name = "CoreFoundation", kind = "framework")]
+extern "C" {
+    fn CFRelease(cf: *const c_void);
+}
+
+#[link(name = "ApplicationServices", kind = "framework")]
+extern "C" {
+    fn AXIsProcessTrusted() -> bool;
+}
+
+/// Type text into the focused application.
+pub fn type_text(text: &str) -> Result<(), String> {
+    if !unsafe { AXIsProcessTrusted() } {
+        return Err("Accessibility permission is required to type transcriptions".to_string());
+    }
+
+    let units: Vec<u16> = text.encode_utf16().collect();
+
+    for chunk in units.chunks(MAX_CHUNK_LEN) {
+        for key_down in [true, false] {
+            unsafe {
+                let event = CGEventCreateKeyboardEvent(std::ptr::null_mut(), 0, key_down);
+                if event.is_null() {
+                    return Err("Failed to create keyboard event".to_string());
+                }
+                CGEventKeyboardSetUnicodeString(event, chunk.len(), chunk.as_ptr());
+                CGEventPost(CG_HID_EVENT_TAP, event);
+                CFRelease(event);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Replace the clipboard contents with text.
+pub fn copy_text(text: &str) -> Result<(), String> {
+    let mut child = Command::new("pbcopy")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Failed to run pbcopy: {}", e))?;
+
+    let mut stdin = child.stdin.take().ok_or("Failed to open pbcopy input")?;
+    stdin
+        .write_all(text.as_bytes())
+        .map_err(|e| format!("Failed to write to pbcopy: {}", e))?;
+    drop(stdin);
+
+    let status = child
+        .wait()
+        .map_err(|e| format!("Failed to run pbcopy: {}", e))?;
+    if !status.success() {
+        return Err(format!("pbcopy exited with {}", status));
+    }
+
+    Ok(())
+}
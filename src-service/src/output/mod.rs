@@ -0,0 +1,163 @@
+//! Delivery of completed transcriptions outside of IPC events.
+//!
+//! In `OutputMode::Type` each transcription is typed into the currently
+//! focused application as synthetic keystrokes, so FlowSTT works as a
+//! system-wide dictation tool:
+//! - macOS: CGEventPost (requires Accessibility permission)
+//! - Windows: SendInput with Unicode key events
+//! - Linux: xdotool (X11) or wtype (Wayland)
+//!
+//! In `OutputMode::Clipboard` each transcription replaces the clipboard
+//! contents (pbcopy on macOS, the Win32 clipboard on Windows, xclip or wl-copy
+//! on Linux).
+//!
+//! With an output template set (see [`template`]), the text typed or copied is
+//! the template expanded for the transcription instead of the bare text.
+//!
+//! The last transcription is kept so it can be delivered again with
+//! [`repeat_last`] when the target application missed it.
+
+#[cfg(target_os = "macos")]
+mod macos;
+
+#[cfg(target_os = "windows")]
+mod windows;
+
+#[cfg(target_os = "linux")]
+mod linux;
+
+pub mod template;
+
+use flowstt_common::OutputMode;
+use std::sync::Mutex;
+use tracing::{debug, warn};
+
+pub use template::{Segment, Template};
+
+/// Current output mode
+static OUTPUT_MODE: Mutex<OutputMode> = Mutex::new(OutputMode::EventsOnly);
+
+/// Template delivered text is formatted with, if any
+static TEMPLATE: Mutex<Option<Template>> = Mutex::new(None);
+
+/// Last transcription delivered, for [`repeat_last`]
+static LAST: Mutex<Option<Segment>> = Mutex::new(None);
+
+/// Set where completed transcriptions are delivered.
+pub fn set_output_mode(mode: OutputMode) {
+    *OUTPUT_MODE.lock().unwrap() = mode;
+}
+
+/// Get the current output mode.
+pub fn get_output_mode() -> OutputMode {
+    *OUTPUT_MODE.lock().unwrap()
+}
+
+/// Set the template delivered text is formatted with, or `None` for the bare
+/// text. An invalid template is rejected and the current one kept.
+pub fn set_template(template: Option<&str>) -> Result<(), String> {
+    let template = template.map(Template::parse).transpose()?;
+    *TEMPLATE.lock().unwrap() = template;
+    Ok(())
+}
+
+/// Format a transcription with the output template, or `None` when no
+/// template is set.
+pub fn format(segment: &Segment) -> Option<String> {
+    TEMPLATE
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|template| template.render(segment))
+}
+
+/// Deliver a completed transcription with `mode`, or the current output mode
+/// when the transcription has no override.
+pub fn deliver(mode: Option<OutputMode>, segment: Segment) {
+    if let Err(e) = emit(mode.unwrap_or_else(get_output_mode), &segment) {
+        warn!("[Output] {}", e);
+    }
+    *LAST.lock().unwrap() = Some(segment);
+}
+
+/// Deliver the last transcription again with `mode`, or the current output
+/// mode. Returns its text, or None if nothing has been transcribed yet.
+pub fn repeat_last(mode: Option<OutputMode>) -> Result<Option<String>, String> {
+    let Some(segment) = LAST.lock().unwrap().clone() else {
+        return Ok(None);
+    };
+    let mode = mode.unwrap_or_else(get_output_mode);
+    if mode == OutputMode::EventsOnly {
+        return Err("Output goes to events only; type or copy the transcription instead".into());
+    }
+    emit(mode, &segment)?;
+    Ok(Some(segment.text))
+}
+
+/// Type or copy a transcription, formatted with the template if one is set.
+fn emit(mode: OutputMode, segment: &Segment) -> Result<(), String> {
+    let formatted = format(segment);
+    match mode {
+        OutputMode::EventsOnly => {}
+        OutputMode::Type => {
+            // Trailing space separates consecutive segments in the target app
+            let text = formatted.unwrap_or_else(|| format!("{} ", segment.text.trim()));
+            type_text(&text).map_err(|e| format!("Failed to type transcription: {}", e))?;
+            debug!("[Output] Typed {} characters", text.chars().count());
+        }
+        OutputMode::Clipboard => {
+            let text = formatted.unwrap_or_else(|| segment.text.trim().to_string());
+            copy_text(&text).map_err(|e| format!("Failed to copy transcription: {}", e))?;
+            debug!("[Output] Copied {} characters", text.chars().count());
+        }
+    }
+    Ok(())
+}
+
+/// Type text into the focused application.
+fn type_text(text: &str) -> Result<(), String> {
+    #[cfg(target_os = "macos")]
+    {
+        macos::type_text(text)
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        windows::type_text(text)
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        linux::type_text(text)
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+    {
+        let _ = text;
+        Err("Typing output is not supported on this platform".to_string())
+    }
+}
+
+/// Replace the clipboard contents with text.
+fn copy_text(text: &str) -> Result<(), String> {
+    #[cfg(target_os = "macos")]
+    {
+        macos::copy_text(text)
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        windows::copy_text(text)
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        linux::copy_text(text)
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+    {
+        let _ = text;
+        Err("Clipboard output is not supported on this platform".to_string())
+    }
+}
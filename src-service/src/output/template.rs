@@ -0,0 +1,241 @@
+//! Templates formatting delivered transcriptions.
+//!
+//! A template such as `[{time}] {speaker}: {text}\n` is expanded for every
+//! transcription before it is typed, copied or sent to webhooks. Placeholders:
+//!
+//! - `{text}`: the transcribed text
+//! - `{time}`: local time the transcription completed (HH:MM:SS)
+//! - `{date}`: local date the transcription completed (YYYY-MM-DD)
+//! - `{speaker}`: speaker label, empty without diarization
+//! - `{language}`: language code of the speech
+//! - `{app}`: application that had focus
+//! - `{duration}`: length of the speech in seconds, e.g. `4.2`
+//! - `{id}`: ID of the saved session
+//!
+//! `{{` and `}}` stand for literal braces, and `\n` and `\t` for a newline and
+//! a tab so templates can be set from a shell.
+
+use chrono::{DateTime, Local};
+use flowstt_common::TranscriptSession;
+
+/// Placeholder names, for error messages
+const PLACEHOLDERS: &[&str] = &[
+    "text", "time", "date", "speaker", "language", "app", "duration", "id",
+];
+
+/// A value substituted into a template.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Text,
+    Time,
+    Date,
+    Speaker,
+    Language,
+    App,
+    Duration,
+    Id,
+}
+
+impl Field {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "text" => Field::Text,
+            "time" => Field::Time,
+            "date" => Field::Date,
+            "speaker" => Field::Speaker,
+            "language" => Field::Language,
+            "app" => Field::App,
+            "duration" => Field::Duration,
+            "id" => Field::Id,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Literal(String),
+    Field(Field),
+}
+
+/// A parsed output template.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+/// What a template is expanded with.
+#[derive(Debug, Clone)]
+pub struct Segment {
+    /// Transcribed text
+    pub text: String,
+    /// Speaker label when diarization is enabled
+    pub speaker: Option<String>,
+    /// Language code of the speech
+    pub language: Option<String>,
+    /// Name of the application that had focus
+    pub app: Option<String>,
+    /// Duration of the speech in milliseconds
+    pub duration_ms: u64,
+    /// Saved session ID
+    pub id: Option<String>,
+    /// When the transcription completed
+    pub completed: DateTime<Local>,
+}
+
+impl Segment {
+    /// The fields of a saved session.
+    pub fn from_session(session: &TranscriptSession) -> Self {
+        Self {
+            text: session.text.clone(),
+            speaker: session.speaker.clone(),
+            language: session.language.clone(),
+            app: session
+                .app
+                .as_ref()
+                .map(|app| app.name.clone().unwrap_or_else(|| app.id.clone())),
+            duration_ms: session.duration_ms,
+            id: Some(session.id.clone()),
+            completed: DateTime::parse_from_rfc3339(&session.created_at)
+                .map(|time| time.with_timezone(&Local))
+                .unwrap_or_else(|_| Local::now()),
+        }
+    }
+}
+
+impl Template {
+    /// Parse a template, rejecting unknown placeholders and unmatched braces.
+    pub fn parse(template: &str) -> Result<Self, String> {
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut chars = template.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => name.push(c),
+                            None => return Err(format!("Unclosed placeholder {{{}", name)),
+                        }
+                    }
+                    let field = Field::from_name(name.trim()).ok_or_else(|| {
+                        format!(
+                            "Unknown placeholder {{{}}} (expected one of: {})",
+                            name,
+                            PLACEHOLDERS.join(", ")
+                        )
+                    })?;
+                    if !literal.is_empty() {
+                        parts.push(Part::Literal(std::mem::take(&mut literal)));
+                    }
+                    parts.push(Part::Field(field));
+                }
+                '}' => return Err("Unmatched } in template (use }} for a brace)".to_string()),
+                '\\' => match chars.peek() {
+                    Some('n') => {
+                        chars.next();
+                        literal.push('\n');
+                    }
+                    Some('t') => {
+                        chars.next();
+                        literal.push('\t');
+                    }
+                    _ => literal.push('\\'),
+                },
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            parts.push(Part::Literal(literal));
+        }
+
+        if !parts.contains(&Part::Field(Field::Text)) {
+            return Err("Template must contain {text}".to_string());
+        }
+        Ok(Self { parts })
+    }
+
+    /// Expand the template for a transcription.
+    pub fn render(&self, segment: &Segment) -> String {
+        let mut output = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Literal(text) => output.push_str(text),
+                Part::Field(Field::Text) => output.push_str(segment.text.trim()),
+                Part::Field(Field::Time) => {
+                    output.push_str(&segment.completed.format("%H:%M:%S").to_string())
+                }
+                Part::Field(Field::Date) => {
+                    output.push_str(&segment.completed.format("%Y-%m-%d").to_string())
+                }
+                Part::Field(Field::Speaker) => {
+                    output.push_str(segment.speaker.as_deref().unwrap_or(""))
+                }
+                Part::Field(Field::Language) => {
+                    output.push_str(segment.language.as_deref().unwrap_or(""))
+                }
+                Part::Field(Field::App) => output.push_str(segment.app.as_deref().unwrap_or("")),
+                Part::Field(Field::Duration) => {
+                    output.push_str(&format!("{:.1}", segment.duration_ms as f64 / 1000.0))
+                }
+                Part::Field(Field::Id) => output.push_str(segment.id.as_deref().unwrap_or("")),
+            }
+        }
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn segment() -> Segment {
+        Segment {
+            text: " Ship it. ".to_string(),
+            speaker: Some("Speaker 2".to_string()),
+            language: Some("en".to_string()),
+            app: None,
+            duration_ms: 4200,
+            id: Some("20260304-050607-000".to_string()),
+            completed: Local.with_ymd_and_hms(2026, 3, 4, 5, 6, 7).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_render_placeholders() {
+        let template =
+            Template::parse(r"[{date} {time}] {speaker}: {text} ({duration}s)\n").unwrap();
+        assert_eq!(
+            template.render(&segment()),
+            "[2026-03-04 05:06:07] Speaker 2: Ship it. (4.2s)\n"
+        );
+
+        let template = Template::parse("{{{id}}}\t{app}{language}: {text}").unwrap();
+        assert_eq!(
+            template.render(&segment()),
+            "{20260304-050607-000}\ten: Ship it."
+        );
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_templates() {
+        assert!(Template::parse("{time} {txt}")
+            .unwrap_err()
+            .starts_with("Unknown placeholder {txt}"));
+        assert!(Template::parse("{text").is_err());
+        assert!(Template::parse("{text} }").is_err());
+        assert!(Template::parse("[{time}]").is_err());
+    }
+}
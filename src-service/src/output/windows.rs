@@ -0,0 +1,88 @@
+//! Windows keystroke output using SendInput, and clipboard output.
+//!
+//! Text is sent as Unicode key events (KEYEVENTF_UNICODE), which bypasses the
+//! keyboard layout and works for any character.
+
+use windows::Win32::Foundation::{GlobalFree, HANDLE, HWND};
+use windows::Win32::System::DataExchange::{
+    CloseClipboard, EmptyClipboard, OpenClipboard, SetClipboardData,
+};
+use windows::Win32::System::Memory::{GlobalAlloc, GlobalLock, GlobalUnlock, GMEM_MOVEABLE};
+use windows::Win32::System::Ole::CF_UNICODETEXT;
+use windows::Win32::UI::Input::KeyboardAndMouse::{
+    SendInput, INPUT, INPUT_0, INPUT_KEYBOARD, KEYBDINPUT, KEYEVENTF_KEYUP, KEYEVENTF_UNICODE,
+    VIRTUAL_KEY,
+};
+
+/// Type text into the focused application.
+pub fn type_text(text: &str) -> Result<(), String> {
+    // One key-down and one key-up event per UTF-16 code unit
+    let inputs: Vec<INPUT> = text
+        .encode_utf16()
+        .flat_map(|unit| {
+            [KEYEVENTF_UNICODE, KEYEVENTF_UNICODE | KEYEVENTF_KEYUP].map(|flags| INPUT {
+                r#type: INPUT_KEYBOARD,
+                Anonymous: INPUT_0 {
+                    ki: KEYBDINPUT {
+                        wVk: VIRTUAL_KEY(0),
+                        wScan: unit,
+                        dwFlags: flags,
+                        time: 0,
+                        dwExtraInfo: 0,
+                    },
+                },
+            })
+        })
+        .collect();
+
+    if inputs.is_empty() {
+        return Ok(());
+    }
+
+    let sent = unsafe { SendInput(&inputs, size_of::<INPUT>() as i32) };
+    if sent as usize != inputs.len() {
+        // SendInput is blocked by UIPI when the focused window runs elevated
+        return Err(format!(
+            "SendInput injected {} of {} events (is the target application elevated?)",
+            sent,
+            inputs.len()
+        ));
+    }
+
+    Ok(())
+}
+
+/// Replace the clipboard contents with text.
+pub fn copy_text(text: &str) -> Result<(), String> {
+    let units: Vec<u16> = text.encode_utf16().chain(std::iter::once(0)).collect();
+
+    unsafe {
+        OpenClipboard(HWND::default()).map_err(|e| format!("Failed to open clipboard: {}", e))?;
+        let result = set_clipboard_text(&units);
+        let _ = CloseClipboard();
+        result
+    }
+}
+
+/// Put NUL-terminated UTF-16 text on the open clipboard.
+unsafe fn set_clipboard_text(units: &[u16]) -> Result<(), String> {
+    EmptyClipboard().map_err(|e| format!("Failed to clear clipboard: {}", e))?;
+
+    let memory = GlobalAlloc(GMEM_MOVEABLE, std::mem::size_of_val(units))
+        .map_err(|e| format!("Failed to allocate clipboard memory: {}", e))?;
+    let target = GlobalLock(memory) as *mut u16;
+    if target.is_null() {
+        let _ = GlobalFree(memory);
+        return Err("Failed to lock clipboard memory".to_string());
+    }
+    std::ptr::copy_nonoverlapping(units.as_ptr(), target, units.len());
+    let _ = GlobalUnlock(memory);
+
+    // The clipboard owns the memory once SetClipboardData succeeds
+    if let Err(e) = SetClipboardData(CF_UNICODETEXT.0 as u32, HANDLE(memory.0)) {
+        let _ = GlobalFree(memory);
+        return Err(format!("Failed to set clipboard data: {}", e));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,121 @@
+//! macOS privacy checks using AVFoundation, CoreGraphics and the
+//! Accessibility API.
+//!
+//! Only the microphone reports whether the user has been asked yet; screen
+//! recording and accessibility report untrusted processes as denied.
+
+use std::sync::mpsc;
+use std::time::Duration;
+
+use block2::RcBlock;
+use core_foundation::base::TCFType;
+use core_foundation::boolean::CFBoolean;
+use core_foundation::dictionary::{CFDictionary, CFDictionaryRef};
+use core_foundation::string::CFString;
+use flowstt_common::{Permission, PermissionState};
+use objc2::runtime::Bool;
+use objc2::{class, msg_send};
+use objc2_foundation::NSString;
+
+use crate::platform::macos::screencapturekit;
+
+/// How long to wait for the user to answer the microphone prompt
+const PROMPT_TIMEOUT: Duration = Duration::from_secs(120);
+
+// AVAuthorizationStatus values
+const AV_NOT_DETERMINED: isize = 0;
+const AV_RESTRICTED: isize = 1;
+const AV_DENIED: isize = 2;
+const AV_AUTHORIZED: isize = 3;
+
+#[link(name = "AVFoundation", kind = "framework")]
+extern "C" {
+    static AVMediaTypeAudio: &'static NSString;
+}
+
+#[link(name = "ApplicationServices", kind = "framework")]
+extern "C" {
+    fn AXIsProcessTrusted() -> bool;
+    fn AXIsProcessTrustedWithOptions(options: CFDictionaryRef) -> bool;
+}
+
+pub fn state(permission: Permission) -> PermissionState {
+    match permission {
+        Permission::Microphone => microphone_state(),
+        Permission::ScreenRecording => granted_or_denied(screencapturekit::check_permission()),
+        Permission::Accessibility => granted_or_denied(unsafe { AXIsProcessTrusted() }),
+    }
+}
+
+pub fn request(permission: Permission) -> PermissionState {
+    match permission {
+        Permission::Microphone => request_microphone(),
+        Permission::ScreenRecording => {
+            // Only prompts once; afterwards the user has to change it in settings
+            screencapturekit::request_permission();
+            state(permission)
+        }
+        Permission::Accessibility => {
+            let options = CFDictionary::from_CFType_pairs(&[(
+                CFString::new("AXTrustedCheckOptionPrompt"),
+                CFBoolean::true_value(),
+            )]);
+            granted_or_denied(unsafe {
+                AXIsProcessTrustedWithOptions(options.as_concrete_TypeRef())
+            })
+        }
+    }
+}
+
+fn granted_or_denied(granted: bool) -> PermissionState {
+    if granted {
+        PermissionState::Granted
+    } else {
+        PermissionState::Denied
+    }
+}
+
+fn microphone_state() -> PermissionState {
+    let status: isize = unsafe {
+        msg_send![
+            class!(AVCaptureDevice),
+            authorizationStatusForMediaType: AVMediaTypeAudio
+        ]
+    };
+    match status {
+        AV_AUTHORIZED => PermissionState::Granted,
+        AV_DENIED => PermissionState::Denied,
+        AV_RESTRICTED => PermissionState::Restricted,
+        AV_NOT_DETERMINED => PermissionState::NotDetermined,
+        other => {
+            tracing::warn!("Unknown microphone authorization status: {}", other);
+            PermissionState::NotDetermined
+        }
+    }
+}
+
+fn request_microphone() -> PermissionState {
+    if microphone_state() != PermissionState::NotDetermined {
+        return microphone_state();
+    }
+
+    let (tx, rx) = mpsc::channel();
+    let handler = RcBlock::new(move |granted: Bool| {
+        let _ = tx.send(granted.as_bool());
+    });
+    unsafe {
+        let _: () = msg_send![
+            class!(AVCaptureDevice),
+            requestAccessForMediaType: AVMediaTypeAudio,
+            completionHandler: &*handler
+        ];
+    }
+
+    match rx.recv_timeout(PROMPT_TIMEOUT) {
+        Ok(granted) => granted_or_denied(granted),
+        Err(_) => {
+            tracing::warn!("No answer to the microphone permission prompt");
+            microphone_state()
+        }
+    }
+}
@@ -0,0 +1,116 @@
+//! Access to protected resources.
+//!
+//! macOS asks the user before an application may record the microphone,
+//! capture other applications' audio (Screen Recording) or post keystrokes
+//! (Accessibility), and Windows lets the user turn off microphone access for
+//! desktop applications. Without access, capture silently delivers nothing, so
+//! the service checks up front and reports what is missing instead. Resources
+//! a platform doesn't restrict are reported as not required.
+
+#[cfg(target_os = "macos")]
+mod macos;
+
+#[cfg(target_os = "windows")]
+mod windows;
+
+use flowstt_common::{FlowSttError, Permission, PermissionState, Permissions};
+
+/// Current access to every protected resource.
+pub fn check() -> Permissions {
+    Permissions {
+        microphone: state(Permission::Microphone),
+        screen_recording: state(Permission::ScreenRecording),
+        accessibility: state(Permission::Accessibility),
+    }
+}
+
+/// Current access to `permission`.
+pub fn state(permission: Permission) -> PermissionState {
+    #[cfg(target_os = "macos")]
+    {
+        macos::state(permission)
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        windows::state(permission)
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    {
+        let _ = permission;
+        PermissionState::NotRequired
+    }
+}
+
+/// Ask the user for `permission`, blocking until they answer when the system
+/// shows a prompt. On Windows, which has no prompt, this opens the privacy
+/// settings instead. Returns the resulting access.
+pub fn request(permission: Permission) -> PermissionState {
+    #[cfg(target_os = "macos")]
+    {
+        macos::request(permission)
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        windows::request(permission)
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    {
+        state(permission)
+    }
+}
+
+/// Fail with an explanation when `permission` is known to be unavailable.
+pub fn require(permission: Permission) -> Result<(), FlowSttError> {
+    match state(permission) {
+        PermissionState::Denied => Err(FlowSttError::PermissionDenied(format!(
+            "{} access is turned off; allow it in {}",
+            name(permission),
+            settings_location(permission)
+        ))),
+        PermissionState::Restricted => Err(FlowSttError::PermissionDenied(format!(
+            "{} access is turned off for this device by a system policy or administrator ({})",
+            name(permission),
+            settings_location(permission)
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// Where the user grants `permission`, as a settings link they can open.
+fn settings_location(permission: Permission) -> String {
+    #[cfg(target_os = "windows")]
+    {
+        let _ = permission;
+        format!(
+            "Settings > Privacy & security > Microphone, including \"Let desktop apps access \
+             your microphone\" ({})",
+            windows::MICROPHONE_SETTINGS
+        )
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        let pane = match permission {
+            Permission::Microphone => "Privacy_Microphone",
+            Permission::ScreenRecording => "Privacy_ScreenCapture",
+            Permission::Accessibility => "Privacy_Accessibility",
+        };
+        format!(
+            "System Settings > Privacy & Security \
+             (x-apple.systempreferences:com.apple.preference.security?{})",
+            pane
+        )
+    }
+}
+
+fn name(permission: Permission) -> &'static str {
+    match permission {
+        Permission::Microphone => "Microphone",
+        Permission::ScreenRecording => "Screen recording",
+        Permission::Accessibility => "Accessibility",
+    }
+}
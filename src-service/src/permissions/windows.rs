@@ -0,0 +1,114 @@
+//! Windows microphone privacy settings.
+//!
+//! Windows has no consent prompt for desktop applications: when "Microphone
+//! access" or "Let desktop apps access your microphone" is off, capture
+//! devices activate normally and deliver silence. The settings are read from
+//! the capability consent store in the registry instead. A service running
+//! under another account sees that account's settings, not the signed-in
+//! user's.
+
+use flowstt_common::{Permission, PermissionState};
+use windows::core::{w, PCWSTR};
+use windows::Win32::Foundation::ERROR_SUCCESS;
+use windows::Win32::System::Registry::{
+    RegGetValueW, HKEY, HKEY_CURRENT_USER, HKEY_LOCAL_MACHINE, RRF_RT_REG_DWORD, RRF_RT_REG_SZ,
+};
+
+/// Settings page for microphone privacy
+pub const MICROPHONE_SETTINGS: &str = "ms-settings:privacy-microphone";
+
+/// `LetAppsAccessMicrophone` policy value that forces access off
+const POLICY_FORCE_DENY: u32 = 2;
+
+/// `LetAppsAccessMicrophone` policy value that forces access on
+const POLICY_FORCE_ALLOW: u32 = 1;
+
+pub fn state(permission: Permission) -> PermissionState {
+    match permission {
+        Permission::Microphone => microphone_state(),
+        // Loopback capture and keystroke injection aren't privacy controlled
+        Permission::ScreenRecording | Permission::Accessibility => PermissionState::NotRequired,
+    }
+}
+
+/// Windows can't prompt desktop applications, so open the settings page
+/// where the user can allow access.
+pub fn request(permission: Permission) -> PermissionState {
+    let state = state(permission);
+    if permission == Permission::Microphone && state == PermissionState::Denied {
+        if let Err(e) = std::process::Command::new("explorer.exe")
+            .arg(MICROPHONE_SETTINGS)
+            .spawn()
+        {
+            tracing::warn!("Failed to open microphone privacy settings: {}", e);
+        }
+    }
+    state
+}
+
+fn microphone_state() -> PermissionState {
+    let store = w!(
+        "Software\\Microsoft\\Windows\\CurrentVersion\\CapabilityAccessManager\\ConsentStore\\microphone"
+    );
+    let desktop_apps = w!(
+        "Software\\Microsoft\\Windows\\CurrentVersion\\CapabilityAccessManager\\ConsentStore\\microphone\\NonPackaged"
+    );
+    let policy = read_dword(
+        HKEY_LOCAL_MACHINE,
+        w!("SOFTWARE\\Policies\\Microsoft\\Windows\\AppPrivacy"),
+        w!("LetAppsAccessMicrophone"),
+    );
+    let denied =
+        |root: HKEY, key: PCWSTR| read_string(root, key, w!("Value")).as_deref() == Some("Deny");
+
+    match policy {
+        Some(POLICY_FORCE_DENY) => PermissionState::Restricted,
+        Some(POLICY_FORCE_ALLOW) => PermissionState::Granted,
+        // "Microphone access" for the whole device needs an administrator to change
+        _ if denied(HKEY_LOCAL_MACHINE, store) => PermissionState::Restricted,
+        _ if denied(HKEY_CURRENT_USER, store) || denied(HKEY_CURRENT_USER, desktop_apps) => {
+            PermissionState::Denied
+        }
+        // Missing keys mean the setting was never turned off
+        _ => PermissionState::Granted,
+    }
+}
+
+fn read_string(root: HKEY, key: PCWSTR, value: PCWSTR) -> Option<String> {
+    let mut buf = [0u16; 64];
+    let mut size = std::mem::size_of_val(&buf) as u32;
+    let status = unsafe {
+        RegGetValueW(
+            root,
+            key,
+            value,
+            RRF_RT_REG_SZ,
+            None,
+            Some(buf.as_mut_ptr().cast()),
+            Some(&mut size),
+        )
+    };
+    if status != ERROR_SUCCESS {
+        return None;
+    }
+    // The size includes the terminating null
+    let len = (size as usize / 2).saturating_sub(1);
+    Some(String::from_utf16_lossy(&buf[..len]))
+}
+
+fn read_dword(root: HKEY, key: PCWSTR, value: PCWSTR) -> Option<u32> {
+    let mut data = 0u32;
+    let mut size = std::mem::size_of::<u32>() as u32;
+    let status = unsafe {
+        RegGetValueW(
+            root,
+            key,
+            value,
+            RRF_RT_REG_DWORD,
+            None,
+            Some((&mut data as *mut u32).cast()),
+            Some(&mut size),
+        )
+    };
+    (status == ERROR_SUCCESS).then_some(data)
+}
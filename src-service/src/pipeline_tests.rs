@@ -0,0 +1,163 @@
+//! End-to-end test of the capture pipeline without audio hardware or a model.
+//!
+//! A WAV fixture is played through the mock audio backend into the mixer,
+//! audio loop, speech detector, segmenter and transcription queue, with the
+//! whisper stub standing in for the model. The test checks what a client would
+//! see: speech events on the IPC broadcast and a transcript for the segment.
+
+use std::path::Path;
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::Duration;
+
+use flowstt_common::ipc::{EventType, Response};
+use flowstt_common::FlowSttError;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::audio::{set_recordings_location, DEFAULT_FILENAME_TEMPLATE};
+use crate::audio_loop::{start_audio_loop, stop_audio_loop};
+use crate::config::{AudioBackendKind, AudioConfig, MockAudioConfig};
+use crate::transcription::{
+    set_whisper_stub, TranscribeState, Transcript, TranscriptionCallback, TranscriptionQueue,
+};
+
+/// Sample rate of the fixture, resampled to 48kHz by the mock backend
+const FIXTURE_SAMPLE_RATE: u32 = 16000;
+
+/// Sends completed transcripts to the test
+struct TranscriptCollector(Mutex<mpsc::Sender<Transcript>>);
+
+impl TranscriptionCallback for TranscriptCollector {
+    fn on_transcription_started(&self) {}
+
+    fn on_transcription_complete(&self, transcript: Transcript) {
+        let _ = self.0.lock().unwrap().send(transcript);
+    }
+
+    fn on_transcription_partial(&self, _transcript: Transcript) {}
+
+    fn on_transcription_token(&self, _text: String) {}
+
+    fn on_transcription_error(&self, error: FlowSttError) {
+        panic!("Transcription failed: {}", error);
+    }
+
+    fn on_transcription_finished(&self) {}
+
+    fn on_queue_update(&self, _depth: usize) {}
+}
+
+/// Write 0.5s of silence, 1.5s of a voiced harmonic signal and 1.5s of silence.
+fn write_speech_fixture(path: &Path) {
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate: FIXTURE_SAMPLE_RATE,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let mut writer = hound::WavWriter::create(path, spec).unwrap();
+    let rate = FIXTURE_SAMPLE_RATE as f32;
+    let silence = |secs: f32| std::iter::repeat(0.0).take((secs * rate) as usize);
+    // A 300Hz fundamental with decaying harmonics, like a sustained vowel
+    let voice = (0..(1.5 * rate) as usize).map(|i| {
+        let t = i as f32 / rate;
+        (1..=6)
+            .map(|k| (2.0 * std::f32::consts::PI * 300.0 * k as f32 * t).sin() / k as f32)
+            .sum::<f32>()
+            * 0.2
+    });
+    for sample in silence(0.5).chain(voice).chain(silence(1.5)) {
+        writer
+            .write_sample((sample * i16::MAX as f32) as i16)
+            .unwrap();
+    }
+    writer.finalize().unwrap();
+}
+
+#[test]
+fn test_mock_capture_to_transcript() {
+    let dir = std::env::temp_dir().join(format!("flowstt-pipeline-test-{}", std::process::id()));
+    let fixtures_dir = dir.join("fixtures");
+    std::fs::create_dir_all(&fixtures_dir).unwrap();
+    write_speech_fixture(&fixtures_dir.join("speech.wav"));
+
+    set_recordings_location(
+        Some(dir.join("recordings")),
+        DEFAULT_FILENAME_TEMPLATE.to_string(),
+    );
+    set_whisper_stub(true);
+    crate::platform::init_audio_backend(&AudioConfig {
+        backend: AudioBackendKind::Mock,
+        mock: Some(MockAudioConfig {
+            fixtures_dir: fixtures_dir.clone(),
+            speed: 4,
+            looped: false,
+        }),
+        ..Default::default()
+    })
+    .unwrap();
+    let backend = crate::platform::get_backend().unwrap();
+    assert!(backend
+        .list_input_devices()
+        .iter()
+        .any(|device| device.id == "speech.wav" && device.name == "speech"));
+
+    let (transcript_tx, transcript_rx) = mpsc::channel();
+    let queue = Arc::new(TranscriptionQueue::new());
+    queue.set_callback(Arc::new(TranscriptCollector(Mutex::new(transcript_tx))));
+    queue.start_worker();
+
+    let transcribe_state = Arc::new(std::sync::Mutex::new(TranscribeState::new(Arc::clone(
+        &queue,
+    ))));
+    {
+        let mut state = transcribe_state.lock().unwrap();
+        state.init_for_capture(backend.sample_rate(), 2);
+        state.activate();
+    }
+
+    // Collect speech events as an IPC client would receive them
+    let mut events = crate::ipc::get_event_sender().subscribe();
+    let (event_tx, event_rx) = mpsc::channel();
+    std::thread::spawn(move || loop {
+        match events.blocking_recv() {
+            Ok(Response::Event {
+                event: event @ (EventType::SpeechStarted | EventType::SpeechEnded { .. }),
+            }) => {
+                if event_tx.send(event).is_err() {
+                    break;
+                }
+            }
+            Ok(_) | Err(RecvError::Lagged(_)) => {}
+            Err(RecvError::Closed) => break,
+        }
+    });
+
+    start_audio_loop(Arc::clone(&queue), Arc::clone(&transcribe_state)).unwrap();
+    backend
+        .start_capture_sources(Some("speech.wav".to_string()), None)
+        .unwrap();
+
+    let transcript = transcript_rx.recv_timeout(Duration::from_secs(10));
+
+    stop_audio_loop();
+    backend.stop_capture().unwrap();
+    queue.stop_worker();
+    set_whisper_stub(false);
+
+    let transcript = transcript.expect("no transcript within 10s");
+    assert!(transcript.text.starts_with("Stub transcript of"));
+    assert!(
+        (1000..=3000).contains(&transcript.duration_ms),
+        "segment of {}ms for 1.5s of speech",
+        transcript.duration_ms
+    );
+    assert!(transcript.audio_path.is_some_and(|path| path.exists()));
+
+    let events: Vec<EventType> = event_rx.try_iter().collect();
+    assert!(matches!(events.first(), Some(EventType::SpeechStarted)));
+    assert!(events
+        .iter()
+        .any(|event| matches!(event, EventType::SpeechEnded { .. })));
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -1,6 +1,10 @@
 //! Platform-agnostic audio backend trait.
 
-use flowstt_common::{AudioDevice, RecordingMode};
+use std::time::Duration;
+
+use flowstt_common::{AudioDevice, FlowSttError, RecordingMode};
+
+pub use flowstt_audio::{SourceSamples, SPEECH_SAMPLE_RATE};
 
 /// Audio data received from capture
 pub struct AudioData {
@@ -9,8 +13,9 @@ pub struct AudioData {
     /// Number of channels
     pub channels: u16,
     /// Sample rate in Hz
-    #[allow(dead_code)]
     pub sample_rate: u32,
+    /// Each source's samples before they were combined, when capturing two
+    pub sources: Option<SourceSamples>,
 }
 
 /// Platform-agnostic audio backend interface.
@@ -18,6 +23,13 @@ pub trait AudioBackend: Send + Sync {
     /// Get the sample rate for this backend.
     fn sample_rate(&self) -> u32;
 
+    /// Whether capturing these sources delivers 16kHz mono rather than the
+    /// backend's usual format. Only backends that can convert to it while
+    /// capturing override this.
+    fn captures_speech_format(&self, _source1_id: Option<&str>, _source2_id: Option<&str>) -> bool {
+        false
+    }
+
     /// List available input devices (microphones).
     fn list_input_devices(&self) -> Vec<AudioDevice>;
 
@@ -29,13 +41,14 @@ pub trait AudioBackend: Send + Sync {
         &self,
         source1_id: Option<String>,
         source2_id: Option<String>,
-    ) -> Result<(), String>;
+    ) -> Result<(), FlowSttError>;
 
     /// Stop audio capture.
-    fn stop_capture(&self) -> Result<(), String>;
+    fn stop_capture(&self) -> Result<(), FlowSttError>;
 
-    /// Try to receive audio data (non-blocking).
-    fn try_recv(&self) -> Option<AudioData>;
+    /// Receive audio data, waiting up to `timeout` for capture to deliver
+    /// some. Returns `None` if none arrived in time.
+    fn recv_timeout(&self, timeout: Duration) -> Option<AudioData>;
 
     /// Set whether AEC is enabled.
     fn set_aec_enabled(&self, enabled: bool);
@@ -0,0 +1,66 @@
+//! Capture timestamps on the host's monotonic clock.
+//!
+//! Backends stamp each buffer they hand to the mixer with the time its first
+//! frame was captured, in nanoseconds. Both streams of a capture session must
+//! use the same clock, so device timestamps are converted to the clock `now`
+//! reads: QPC on Windows, mach host time on macOS and `CLOCK_MONOTONIC` on
+//! Linux (the clock PipeWire and PulseAudio schedule against). Backends that
+//! have no device timestamp stamp buffers on arrival with `before_now`.
+
+pub use flowstt_audio::frames_to_ns;
+
+/// Current host clock time in nanoseconds.
+#[cfg(target_os = "linux")]
+pub fn now() -> u64 {
+    let mut ts = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut ts) };
+    ts.tv_sec as u64 * 1_000_000_000 + ts.tv_nsec as u64
+}
+
+/// Current host clock time in nanoseconds.
+#[cfg(target_os = "windows")]
+pub fn now() -> u64 {
+    use windows::Win32::System::Performance::{QueryPerformanceCounter, QueryPerformanceFrequency};
+
+    let mut counter = 0i64;
+    let mut frequency = 0i64;
+    unsafe {
+        let _ = QueryPerformanceCounter(&mut counter);
+        let _ = QueryPerformanceFrequency(&mut frequency);
+    }
+    if frequency <= 0 {
+        return 0;
+    }
+    (counter as u128 * 1_000_000_000 / frequency as u128) as u64
+}
+
+/// Current host clock time in nanoseconds.
+#[cfg(target_os = "macos")]
+pub fn now() -> u64 {
+    use coreaudio::sys::AudioGetCurrentHostTime;
+
+    host_time_to_ns(unsafe { AudioGetCurrentHostTime() })
+}
+
+/// Convert a WASAPI QPC position, in 100ns units, to nanoseconds.
+#[cfg(target_os = "windows")]
+pub fn from_qpc_position(position: u64) -> u64 {
+    position * 100
+}
+
+/// Convert a CoreAudio host time (mach absolute time) to nanoseconds.
+#[cfg(target_os = "macos")]
+pub fn host_time_to_ns(host_time: u64) -> u64 {
+    use coreaudio::sys::AudioConvertHostTimeToNanos;
+
+    unsafe { AudioConvertHostTimeToNanos(host_time) }
+}
+
+/// Timestamp for `frames` frames at `sample_rate` that have just arrived:
+/// the first of them was captured that long ago.
+pub fn before_now(frames: usize, sample_rate: u32) -> u64 {
+    now().saturating_sub(frames_to_ns(frames, sample_rate))
+}
@@ -0,0 +1,347 @@
+//! Capture fault injection for resilience testing (debug builds only).
+//!
+//! `InjectFault` requests make capture misbehave the way real devices do, so
+//! the stall fallback, device switching and the mixer's handling of missing
+//! and late buffers can be exercised on demand. Once a fault is injected, the
+//! mixer asks [`deliver`] about every buffer before using it, which drops or
+//! delays it. A
+//! disconnected device stops delivering audio and, through [`backend`], is
+//! hidden from the device lists and can't be opened until the fault expires,
+//! while capture from other devices carries on.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use flowstt_common::{AudioDevice, AudioFault, AudioSourceType, FlowSttError, RecordingMode};
+
+use super::backend::{AudioBackend, AudioData};
+
+/// Injected faults, checked by the mixer for every buffer
+static FAULTS: Mutex<Faults> = Mutex::new(Faults::new());
+
+/// Whether any fault is injected, so the audio path skips the lock otherwise
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// Backend wrapper hiding disconnected devices
+static BACKEND: FaultyBackend = FaultyBackend;
+
+/// Inject a fault; `device_id` is the device a disconnect applies to.
+pub fn inject(fault: AudioFault, device_id: Option<String>) {
+    tracing::warn!("Injecting audio fault: {:?}", fault);
+    flowstt_audio::mixer::set_buffer_filter(deliver);
+    let mut faults = FAULTS.lock().unwrap();
+    faults.inject(fault, device_id, Instant::now());
+    ACTIVE.store(faults.is_active(), Ordering::SeqCst);
+}
+
+/// Whether any fault is injected.
+pub fn is_active() -> bool {
+    ACTIVE.load(Ordering::Relaxed)
+}
+
+/// The current backend with disconnected devices hidden.
+pub fn backend() -> &'static dyn AudioBackend {
+    &BACKEND
+}
+
+/// Decide whether the mixer gets a buffer from a source, sleeping first if it
+/// should arrive late. Called on the capture thread.
+pub fn deliver(is_render: bool) -> bool {
+    if !is_active() {
+        return true;
+    }
+    let verdict = {
+        let mut faults = FAULTS.lock().unwrap();
+        let verdict = faults.next_buffer(is_render, Instant::now());
+        ACTIVE.store(faults.is_active(), Ordering::SeqCst);
+        verdict
+    };
+    match verdict {
+        Verdict::Deliver => true,
+        Verdict::Delay(delay) => {
+            std::thread::sleep(delay);
+            true
+        }
+        Verdict::Drop => false,
+    }
+}
+
+/// What happens to a captured buffer
+#[derive(Debug, PartialEq)]
+enum Verdict {
+    Deliver,
+    Delay(Duration),
+    Drop,
+}
+
+/// A device made to vanish
+struct Disconnect {
+    device_id: Option<String>,
+    is_render: bool,
+    until: Instant,
+    /// Whether its audio is still being dropped; cleared when capture
+    /// restarts, since that can no longer include this device
+    silenced: bool,
+}
+
+/// Injected faults, with per-source counters indexed by `is_render`
+struct Faults {
+    drops: [u32; 2],
+    delays: [(u32, Duration); 2],
+    disconnects: Vec<Disconnect>,
+}
+
+impl Faults {
+    const fn new() -> Self {
+        Self {
+            drops: [0; 2],
+            delays: [(0, Duration::ZERO); 2],
+            disconnects: Vec::new(),
+        }
+    }
+
+    fn inject(&mut self, fault: AudioFault, device_id: Option<String>, now: Instant) {
+        match fault {
+            AudioFault::DropBuffers { source, count } => {
+                self.drops[is_render(source) as usize] += count;
+            }
+            AudioFault::DelayBuffers {
+                source,
+                count,
+                delay_ms,
+            } => {
+                self.delays[is_render(source) as usize] =
+                    (count, Duration::from_millis(delay_ms as u64));
+            }
+            AudioFault::Disconnect {
+                source,
+                duration_ms,
+            } => self.disconnects.push(Disconnect {
+                device_id,
+                is_render: is_render(source),
+                until: now + Duration::from_millis(duration_ms as u64),
+                silenced: true,
+            }),
+            AudioFault::Clear => *self = Self::new(),
+        }
+    }
+
+    fn is_active(&self) -> bool {
+        self.drops.iter().any(|&count| count > 0)
+            || self.delays.iter().any(|&(count, _)| count > 0)
+            || !self.disconnects.is_empty()
+    }
+
+    /// Expire finished disconnects
+    fn expire(&mut self, now: Instant) {
+        self.disconnects.retain(|disconnect| {
+            let expired = now >= disconnect.until;
+            if expired {
+                tracing::info!("Disconnected device {:?} is back", disconnect.device_id);
+            }
+            !expired
+        });
+    }
+
+    fn next_buffer(&mut self, is_render: bool, now: Instant) -> Verdict {
+        self.expire(now);
+        let side = is_render as usize;
+        if self
+            .disconnects
+            .iter()
+            .any(|disconnect| disconnect.silenced && disconnect.is_render == is_render)
+        {
+            return Verdict::Drop;
+        }
+        if self.drops[side] > 0 {
+            self.drops[side] -= 1;
+            return Verdict::Drop;
+        }
+        let (count, delay) = &mut self.delays[side];
+        if *count > 0 {
+            *count -= 1;
+            return Verdict::Delay(*delay);
+        }
+        Verdict::Deliver
+    }
+
+    fn is_disconnected(&self, device_id: &str) -> bool {
+        self.disconnects
+            .iter()
+            .any(|disconnect| disconnect.device_id.as_deref() == Some(device_id))
+    }
+}
+
+/// Faults name sources by type; the second source is the mixer's render side
+fn is_render(source: AudioSourceType) -> bool {
+    source == AudioSourceType::System
+}
+
+/// The current backend, less the disconnected devices
+struct FaultyBackend;
+
+impl FaultyBackend {
+    fn inner(&self) -> Result<&'static dyn AudioBackend, FlowSttError> {
+        super::backend_without_faults().ok_or_else(|| {
+            FlowSttError::BackendUnavailable("no audio backend was initialized".to_string())
+        })
+    }
+
+    fn connected(&self, mut devices: Vec<AudioDevice>) -> Vec<AudioDevice> {
+        let mut faults = FAULTS.lock().unwrap();
+        faults.expire(Instant::now());
+        devices.retain(|device| !faults.is_disconnected(&device.id));
+        devices
+    }
+}
+
+impl AudioBackend for FaultyBackend {
+    fn sample_rate(&self) -> u32 {
+        self.inner().map(|b| b.sample_rate()).unwrap_or(48000)
+    }
+
+    fn captures_speech_format(&self, source1_id: Option<&str>, source2_id: Option<&str>) -> bool {
+        self.inner()
+            .is_ok_and(|b| b.captures_speech_format(source1_id, source2_id))
+    }
+
+    fn list_input_devices(&self) -> Vec<AudioDevice> {
+        let devices = self
+            .inner()
+            .map(|b| b.list_input_devices())
+            .unwrap_or_default();
+        self.connected(devices)
+    }
+
+    fn list_system_devices(&self) -> Vec<AudioDevice> {
+        let devices = self
+            .inner()
+            .map(|b| b.list_system_devices())
+            .unwrap_or_default();
+        self.connected(devices)
+    }
+
+    fn start_capture_sources(
+        &self,
+        source1_id: Option<String>,
+        source2_id: Option<String>,
+    ) -> Result<(), FlowSttError> {
+        {
+            let mut faults = FAULTS.lock().unwrap();
+            faults.expire(Instant::now());
+            for id in source1_id.iter().chain(source2_id.iter()) {
+                if faults.is_disconnected(id) {
+                    return Err(FlowSttError::DeviceNotFound(id.clone()));
+                }
+            }
+            for disconnect in faults.disconnects.iter_mut() {
+                disconnect.silenced = false;
+            }
+        }
+        self.inner()?.start_capture_sources(source1_id, source2_id)
+    }
+
+    fn stop_capture(&self) -> Result<(), FlowSttError> {
+        self.inner()?.stop_capture()
+    }
+
+    fn recv_timeout(&self, timeout: Duration) -> Option<AudioData> {
+        match self.inner() {
+            Ok(backend) => backend.recv_timeout(timeout),
+            Err(_) => {
+                std::thread::sleep(timeout);
+                None
+            }
+        }
+    }
+
+    fn set_aec_enabled(&self, enabled: bool) {
+        if let Ok(backend) = self.inner() {
+            backend.set_aec_enabled(enabled);
+        }
+    }
+
+    fn set_recording_mode(&self, mode: RecordingMode) {
+        if let Ok(backend) = self.inner() {
+            backend.set_recording_mode(mode);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_drop_and_delay_count_down_per_source() {
+        let now = Instant::now();
+        let mut faults = Faults::new();
+        faults.inject(
+            AudioFault::DropBuffers {
+                source: AudioSourceType::Input,
+                count: 2,
+            },
+            None,
+            now,
+        );
+        faults.inject(
+            AudioFault::DelayBuffers {
+                source: AudioSourceType::System,
+                count: 1,
+                delay_ms: 30,
+            },
+            None,
+            now,
+        );
+
+        assert_eq!(
+            faults.next_buffer(true, now),
+            Verdict::Delay(Duration::from_millis(30))
+        );
+        assert_eq!(faults.next_buffer(true, now), Verdict::Deliver);
+        assert_eq!(faults.next_buffer(false, now), Verdict::Drop);
+        assert_eq!(faults.next_buffer(false, now), Verdict::Drop);
+        assert_eq!(faults.next_buffer(false, now), Verdict::Deliver);
+        assert!(!faults.is_active());
+    }
+
+    #[test]
+    fn test_disconnect_silences_until_restart_and_hides_until_expiry() {
+        let now = Instant::now();
+        let mut faults = Faults::new();
+        faults.inject(
+            AudioFault::Disconnect {
+                source: AudioSourceType::Input,
+                duration_ms: 1000,
+            },
+            Some("mic".to_string()),
+            now,
+        );
+
+        assert_eq!(faults.next_buffer(false, now), Verdict::Drop);
+        assert_eq!(faults.next_buffer(true, now), Verdict::Deliver);
+        assert!(faults.is_disconnected("mic"));
+
+        // Capture restarted on another device
+        faults.disconnects[0].silenced = false;
+        assert_eq!(faults.next_buffer(false, now), Verdict::Deliver);
+        assert!(faults.is_disconnected("mic"));
+
+        let later = now + Duration::from_millis(1000);
+        assert_eq!(faults.next_buffer(false, later), Verdict::Deliver);
+        assert!(!faults.is_disconnected("mic"));
+        assert!(!faults.is_active());
+
+        faults.inject(
+            AudioFault::DropBuffers {
+                source: AudioSourceType::Input,
+                count: 5,
+            },
+            None,
+            later,
+        );
+        faults.inject(AudioFault::Clear, None, later);
+        assert!(!faults.is_active());
+    }
+}
@@ -0,0 +1,477 @@
+//! ALSA capture backend for Linux
+//!
+//! Intended for servers and embedded devices that run no sound server. Only input
+//! devices are supported; there is no equivalent of a sink monitor, so system audio
+//! capture is unavailable. Each source is read on its own thread, converted to
+//! 48kHz stereo and fed to the common mixer.
+
+use alsa::device_name::HintIter;
+use alsa::pcm::{Access, Format, HwParams, PCM};
+use alsa::{Direction, ValueOr};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::platform::backend::{AudioBackend, AudioData};
+use crate::platform::clock;
+use crate::platform::mixer::{
+    capture_buffer_frames, channel_selection, downmix_channels, mixed_queue, mono_to_stereo,
+    report_capture_latency, report_source_format, selected_channels, AudioMixer, MixedReceiver,
+    MixedSender, MixerControls, Resampler, MIXER_SAMPLE_RATE,
+};
+use flowstt_common::{AudioDevice, AudioSourceType, FlowSttError, RecordingMode};
+
+/// Channels requested from the device (falls back to mono)
+const CAPTURE_CHANNELS: u32 = 2;
+
+/// Period size requested from the device unless a buffer duration is
+/// configured (10ms at 48kHz)
+const PERIOD_FRAMES: usize = 480;
+
+/// Commands sent to the capture thread
+enum CaptureCommand {
+    StartSources {
+        source1_id: Option<String>,
+        source2_id: Option<String>,
+        result_tx: mpsc::Sender<Result<(), FlowSttError>>,
+    },
+    Stop,
+    Shutdown,
+}
+
+/// ALSA audio backend for Linux
+pub struct AlsaBackend {
+    /// Channel to send commands to capture thread
+    cmd_tx: mpsc::Sender<CaptureCommand>,
+    /// Queue of mixed audio from the capture thread (in a Mutex for Sync;
+    /// only the processing thread locks it, so it is never contended)
+    audio_rx: Mutex<MixedReceiver>,
+    /// Cached input devices
+    input_devices: Mutex<Vec<AudioDevice>>,
+    /// Capture thread handle
+    _thread_handle: JoinHandle<()>,
+    /// AEC flag and recording mode (shared with mixer)
+    controls: Arc<MixerControls>,
+}
+
+impl AlsaBackend {
+    /// Create a new ALSA backend
+    pub fn new(controls: Arc<MixerControls>) -> Result<Self, FlowSttError> {
+        let input_devices = enumerate_input_devices()?;
+
+        let (cmd_tx, cmd_rx) = mpsc::channel();
+        let (audio_tx, audio_rx) = mixed_queue();
+
+        let controls_clone = Arc::clone(&controls);
+
+        let thread_handle = thread::spawn(move || {
+            run_capture_thread(cmd_rx, audio_tx, controls_clone);
+        });
+
+        Ok(Self {
+            cmd_tx,
+            audio_rx: Mutex::new(audio_rx),
+            input_devices: Mutex::new(input_devices),
+            _thread_handle: thread_handle,
+            controls,
+        })
+    }
+}
+
+impl Drop for AlsaBackend {
+    fn drop(&mut self) {
+        let _ = self.cmd_tx.send(CaptureCommand::Shutdown);
+    }
+}
+
+impl AudioBackend for AlsaBackend {
+    fn list_input_devices(&self) -> Vec<AudioDevice> {
+        let mut devices = self.input_devices.lock().unwrap();
+        match enumerate_input_devices() {
+            Ok(fresh) => *devices = fresh,
+            Err(e) => tracing::warn!("ALSA: Failed to refresh devices: {}", e),
+        }
+        devices.clone()
+    }
+
+    fn list_system_devices(&self) -> Vec<AudioDevice> {
+        Vec::new()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        MIXER_SAMPLE_RATE
+    }
+
+    fn start_capture_sources(
+        &self,
+        source1_id: Option<String>,
+        source2_id: Option<String>,
+    ) -> Result<(), FlowSttError> {
+        let (result_tx, result_rx) = mpsc::channel();
+
+        self.cmd_tx
+            .send(CaptureCommand::StartSources {
+                source1_id,
+                source2_id,
+                result_tx,
+            })
+            .map_err(|e| FlowSttError::Capture(format!("Failed to send start command: {}", e)))?;
+
+        match result_rx.recv_timeout(std::time::Duration::from_secs(5)) {
+            Ok(result) => result,
+            Err(mpsc::RecvTimeoutError::Timeout) => Err(FlowSttError::Capture(
+                "Timeout waiting for audio capture to start".to_string(),
+            )),
+            Err(mpsc::RecvTimeoutError::Disconnected) => Err(FlowSttError::Capture(
+                "Capture thread disconnected".to_string(),
+            )),
+        }
+    }
+
+    fn stop_capture(&self) -> Result<(), FlowSttError> {
+        self.cmd_tx
+            .send(CaptureCommand::Stop)
+            .map_err(|e| FlowSttError::Capture(format!("Failed to send stop command: {}", e)))
+    }
+
+    fn recv_timeout(&self, timeout: Duration) -> Option<AudioData> {
+        self.audio_rx
+            .lock()
+            .unwrap()
+            .recv_timeout(timeout)
+            .map(|samples| AudioData {
+                samples: samples.samples,
+                channels: samples.channels,
+                sample_rate: MIXER_SAMPLE_RATE,
+                sources: samples.sources,
+            })
+    }
+
+    fn set_aec_enabled(&self, enabled: bool) {
+        self.controls.set_aec_enabled(enabled);
+    }
+
+    fn set_recording_mode(&self, mode: RecordingMode) {
+        self.controls.set_recording_mode(mode);
+    }
+}
+
+/// Create a Linux audio backend using ALSA
+pub fn create_backend(controls: Arc<MixerControls>) -> Result<Box<dyn AudioBackend>, FlowSttError> {
+    let backend = AlsaBackend::new(controls)?;
+    Ok(Box::new(backend))
+}
+
+/// Enumerate PCM devices that support capture
+fn enumerate_input_devices() -> Result<Vec<AudioDevice>, FlowSttError> {
+    let hints = HintIter::new_str(None, "pcm").map_err(|e| {
+        FlowSttError::BackendUnavailable(format!("cannot enumerate ALSA devices: {}", e))
+    })?;
+
+    Ok(hints
+        .filter(|hint| hint.direction != Some(Direction::Playback))
+        .filter_map(|hint| {
+            let id = hint.name?;
+            if id == "null" {
+                return None;
+            }
+            // Descriptions span two lines: card name, then device name
+            let name = hint
+                .desc
+                .map(|desc| desc.lines().collect::<Vec<_>>().join(", "))
+                .unwrap_or_else(|| id.clone());
+            Some(AudioDevice {
+                id,
+                name,
+                source_type: AudioSourceType::Input,
+            })
+        })
+        .collect())
+}
+
+/// Run the capture thread, which owns the mixer and the stream threads
+fn run_capture_thread(
+    cmd_rx: mpsc::Receiver<CaptureCommand>,
+    audio_tx: MixedSender,
+    controls: Arc<MixerControls>,
+) {
+    tracing::info!("ALSA: Capture thread started");
+
+    let mut mixer = AudioMixer::new("ALSA", audio_tx, controls);
+    let (stream_tx, stream_rx) = mpsc::channel::<(Vec<f32>, u64)>();
+    let mut streams: Vec<CaptureStream> = Vec::new();
+
+    loop {
+        // Process any samples from stream threads first
+        while let Ok((samples, timestamp)) = stream_rx.try_recv() {
+            mixer.push_samples(&samples, false, timestamp);
+        }
+
+        let timeout = if streams.is_empty() {
+            std::time::Duration::from_secs(1)
+        } else {
+            std::time::Duration::from_millis(1)
+        };
+
+        match cmd_rx.recv_timeout(timeout) {
+            Ok(CaptureCommand::StartSources {
+                source1_id,
+                source2_id,
+                result_tx,
+            }) => {
+                // Stop any existing capture
+                streams.clear();
+
+                let sources: Vec<String> = [source1_id, source2_id].into_iter().flatten().collect();
+                mixer.set_num_streams(sources.len());
+
+                let result: Result<Vec<CaptureStream>, FlowSttError> = sources
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, id)| CaptureStream::start(id, i + 1, stream_tx.clone()))
+                    .collect();
+
+                match result {
+                    Ok(started) => {
+                        tracing::info!("ALSA: Started capture with {} sources", started.len());
+                        streams = started;
+                        let _ = result_tx.send(Ok(()));
+                    }
+                    Err(e) => {
+                        tracing::error!("ALSA: Failed to start capture: {}", e);
+                        mixer.set_num_streams(0);
+                        let _ = result_tx.send(Err(e));
+                    }
+                }
+            }
+            Ok(CaptureCommand::Stop) => {
+                if !streams.is_empty() {
+                    tracing::info!("ALSA: Stopping capture");
+                    streams.clear();
+                }
+                mixer.set_num_streams(0);
+            }
+            Ok(CaptureCommand::Shutdown) | Err(mpsc::RecvTimeoutError::Disconnected) => {
+                streams.clear();
+                break;
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                // Continue processing samples
+            }
+        }
+    }
+}
+
+/// A capture stream reading from one PCM device on its own thread
+struct CaptureStream {
+    stop_flag: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl CaptureStream {
+    /// Spawn the stream thread and wait until the device has been opened
+    fn start(
+        device_id: String,
+        stream_index: usize,
+        stream_tx: mpsc::Sender<(Vec<f32>, u64)>,
+    ) -> Result<Self, FlowSttError> {
+        let stop_flag = Arc::new(AtomicBool::new(false));
+        let stop_flag_clone = Arc::clone(&stop_flag);
+        let (ready_tx, ready_rx) = mpsc::channel();
+
+        let handle = thread::spawn(move || {
+            run_stream_capture(
+                device_id,
+                stream_index,
+                stream_tx,
+                stop_flag_clone,
+                ready_tx,
+            );
+        });
+
+        match ready_rx.recv() {
+            Ok(Ok(())) => Ok(Self {
+                stop_flag,
+                handle: Some(handle),
+            }),
+            Ok(Err(e)) => {
+                let _ = handle.join();
+                Err(e)
+            }
+            Err(_) => Err(FlowSttError::Capture(format!(
+                "Stream {} capture thread exited",
+                stream_index
+            ))),
+        }
+    }
+}
+
+impl Drop for CaptureStream {
+    fn drop(&mut self) {
+        self.stop_flag.store(true, Ordering::SeqCst);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Format negotiated with the device
+struct CaptureFormat {
+    sample_rate: u32,
+    channels: u32,
+    is_float: bool,
+    /// Period size the device accepted
+    period_frames: usize,
+}
+
+/// Open a PCM device for capture, preferring float stereo at the mixer rate
+fn open_pcm(device_id: &str) -> Result<(PCM, CaptureFormat), FlowSttError> {
+    let pcm = PCM::new(device_id, Direction::Capture, false).map_err(|e| match e.errno() {
+        libc::ENOENT | libc::ENODEV => FlowSttError::DeviceNotFound(device_id.to_string()),
+        libc::EACCES | libc::EPERM => FlowSttError::PermissionDenied(format!(
+            "Access to ALSA device {} was denied; add your user to the audio group",
+            device_id
+        )),
+        _ => FlowSttError::Capture(format!("Failed to open ALSA device {}: {}", device_id, e)),
+    })?;
+
+    let format = {
+        let hwp = HwParams::any(&pcm)
+            .map_err(|e| FlowSttError::Capture(format!("Failed to get hw params: {}", e)))?;
+        hwp.set_access(Access::RWInterleaved)
+            .map_err(|e| FlowSttError::Capture(format!("Failed to set access mode: {}", e)))?;
+
+        let is_float = hwp.set_format(Format::float()).is_ok();
+        if !is_float {
+            hwp.set_format(Format::s16()).map_err(|e| {
+                FlowSttError::Capture(format!("Device supports neither f32 nor s16: {}", e))
+            })?;
+        }
+
+        // Open enough channels for a channel selection, if the device has them
+        let highest = channel_selection(device_id)
+            .and_then(|selection| selection.channels.iter().max().copied())
+            .map_or(0, u32::from);
+        let channels = if highest > CAPTURE_CHANNELS && hwp.set_channels(highest).is_ok() {
+            highest
+        } else if hwp.set_channels(CAPTURE_CHANNELS).is_ok() {
+            CAPTURE_CHANNELS
+        } else {
+            hwp.set_channels(1).map_err(|e| {
+                FlowSttError::Capture(format!("Failed to set channel count: {}", e))
+            })?;
+            1
+        };
+
+        let sample_rate = hwp
+            .set_rate_near(MIXER_SAMPLE_RATE, ValueOr::Nearest)
+            .map_err(|e| FlowSttError::Capture(format!("Failed to set sample rate: {}", e)))?;
+        let period_frames = capture_buffer_frames(sample_rate).unwrap_or(PERIOD_FRAMES);
+        let period_frames = hwp
+            .set_period_size_near(period_frames as alsa::pcm::Frames, ValueOr::Nearest)
+            .map_err(|e| FlowSttError::Capture(format!("Failed to set period size: {}", e)))?;
+
+        pcm.hw_params(&hwp)
+            .map_err(|e| FlowSttError::Capture(format!("Failed to apply hw params: {}", e)))?;
+
+        CaptureFormat {
+            sample_rate,
+            channels,
+            is_float,
+            period_frames: period_frames as usize,
+        }
+    };
+
+    Ok((pcm, format))
+}
+
+/// Read from a single device until stopped
+fn run_stream_capture(
+    device_id: String,
+    stream_index: usize,
+    stream_tx: mpsc::Sender<(Vec<f32>, u64)>,
+    stop_flag: Arc<AtomicBool>,
+    ready_tx: mpsc::Sender<Result<(), FlowSttError>>,
+) {
+    let (pcm, format) = match open_pcm(&device_id) {
+        Ok(opened) => {
+            let _ = ready_tx.send(Ok(()));
+            opened
+        }
+        Err(e) => {
+            let _ = ready_tx.send(Err(e));
+            return;
+        }
+    };
+
+    tracing::info!(
+        "ALSA: Stream {} capture started (device={}, {}Hz, {} channels, float={})",
+        stream_index,
+        device_id,
+        format.sample_rate,
+        format.channels,
+        format.is_float
+    );
+    report_source_format(false, format.sample_rate, format.channels as u16);
+    report_capture_latency(false, format.period_frames, format.sample_rate);
+
+    let mut resampler = if format.sample_rate != MIXER_SAMPLE_RATE {
+        Some(Resampler::new(format.sample_rate, MIXER_SAMPLE_RATE))
+    } else {
+        None
+    };
+
+    let selected = selected_channels(&device_id, format.channels as usize);
+    let buffer_len = format.period_frames * format.channels as usize;
+    let mut float_buffer = vec![0.0f32; buffer_len];
+    let mut int_buffer = vec![0i16; buffer_len];
+
+    while !stop_flag.load(Ordering::SeqCst) {
+        let frames = if format.is_float {
+            pcm.io_f32().and_then(|io| io.readi(&mut float_buffer))
+        } else {
+            pcm.io_i16()
+                .and_then(|io| io.readi(&mut int_buffer))
+                .map(|frames| {
+                    for (dst, src) in float_buffer.iter_mut().zip(&int_buffer) {
+                        *dst = *src as f32 / 32768.0;
+                    }
+                    frames
+                })
+        };
+
+        let frames = match frames {
+            Ok(frames) => frames,
+            Err(e) => {
+                // Recover from overruns; give up on anything else
+                if let Err(e) = pcm.try_recover(e, true) {
+                    tracing::error!("ALSA: Stream {} read error: {}", stream_index, e);
+                    break;
+                }
+                continue;
+            }
+        };
+
+        let timestamp = clock::before_now(frames, format.sample_rate);
+        let samples = &float_buffer[..frames * format.channels as usize];
+        let stereo = if let Some(selected) = &selected {
+            downmix_channels(samples, format.channels as usize, selected)
+        } else if format.channels == 1 {
+            mono_to_stereo(samples)
+        } else {
+            samples.to_vec()
+        };
+        let output = match resampler.as_mut() {
+            Some(resampler) => resampler.process(&stereo, 2),
+            None => stereo,
+        };
+
+        if !output.is_empty() && stream_tx.send((output, timestamp)).is_err() {
+            break;
+        }
+    }
+
+    tracing::info!("ALSA: Stream {} capture stopped", stream_index);
+}
@@ -1,29 +1,59 @@
-//! Linux audio backend using PipeWire.
+//! Linux audio backends: PipeWire, PulseAudio and ALSA.
 
+mod alsa;
 mod pipewire;
+mod pulse;
 
+use super::mixer::MixerControls;
 use super::AudioBackend;
-use flowstt_common::RecordingMode;
-use std::sync::{Arc, Mutex, OnceLock};
+use crate::config::AudioBackendKind;
+use flowstt_common::FlowSttError;
+use std::sync::{Arc, OnceLock};
 
 /// Global backend instance
 static BACKEND: OnceLock<Box<dyn AudioBackend>> = OnceLock::new();
 
 /// Initialize the Linux audio backend.
-pub fn init() -> Result<(), String> {
-    tracing::info!("Initializing Linux PipeWire audio backend");
-
+///
+/// With `AudioBackendKind::Auto`, PipeWire is preferred and plain PulseAudio is used
+/// when no PipeWire daemon is running. ALSA is only used when selected explicitly.
+pub fn init(kind: AudioBackendKind) -> Result<(), FlowSttError> {
     // Create shared state for AEC and recording mode
-    let aec_enabled = Arc::new(Mutex::new(false));
-    let recording_mode = Arc::new(Mutex::new(RecordingMode::default()));
-
-    let backend = pipewire::create_backend(aec_enabled, recording_mode)?;
+    let controls = Arc::new(MixerControls::default());
+
+    let backend = match kind {
+        AudioBackendKind::Auto if pipewire::is_available() => {
+            tracing::info!("Initializing Linux PipeWire audio backend");
+            pipewire::create_backend(controls)
+        }
+        AudioBackendKind::Auto => {
+            tracing::info!("PipeWire unavailable, initializing Linux PulseAudio audio backend");
+            pulse::create_backend(controls)
+        }
+        AudioBackendKind::PipeWire => {
+            tracing::info!("Initializing Linux PipeWire audio backend");
+            pipewire::create_backend(controls)
+        }
+        AudioBackendKind::Pulse => {
+            tracing::info!("Initializing Linux PulseAudio audio backend");
+            pulse::create_backend(controls)
+        }
+        AudioBackendKind::Alsa => {
+            tracing::info!("Initializing Linux ALSA audio backend");
+            alsa::create_backend(controls)
+        }
+        AudioBackendKind::Mock => {
+            return Err(FlowSttError::BackendUnavailable(
+                "the mock backend is not a Linux backend".to_string(),
+            ));
+        }
+    }?;
 
     BACKEND
         .set(backend)
-        .map_err(|_| "Backend already initialized".to_string())?;
+        .map_err(|_| FlowSttError::Other("Backend already initialized".to_string()))?;
 
-    tracing::info!("Linux PipeWire audio backend initialized");
+    tracing::info!("Linux audio backend initialized");
     Ok(())
 }
 
@@ -3,7 +3,8 @@
 //! This module provides audio capture from input devices and system audio (sink monitors)
 //! using PipeWire directly. It integrates with the existing audio processing pipeline.
 //! When capturing from multiple sources, they are mixed together before being sent
-//! to the processing pipeline.
+//! to the processing pipeline. A single source can be captured as 16kHz mono,
+//! with PipeWire's stream adapter doing the conversion.
 
 use pipewire::{
     context::Context,
@@ -17,17 +18,23 @@ use pipewire::{
     stream::{Stream, StreamFlags},
     types::ObjectType,
 };
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
 use std::mem;
 use std::rc::Rc;
 use std::sync::mpsc;
 use std::sync::{Arc, Mutex};
 use std::thread::{self, JoinHandle};
-
-use crate::platform::backend::{AudioBackend, AudioData};
-use aec3::voip::VoipAec3;
-use flowstt_common::{AudioDevice, AudioSourceType, RecordingMode};
+use std::time::Duration;
+
+use crate::platform::backend::{AudioBackend, AudioData, SPEECH_SAMPLE_RATE};
+use crate::platform::clock;
+use crate::platform::mixer::{
+    capture_buffer_frames, downmix_channels, mixed_queue, report_capture_latency,
+    report_source_format, selected_channels, speech_capture, AudioMixer, MixedReceiver,
+    MixedSender, MixerControls, MIXER_SAMPLE_RATE,
+};
+use flowstt_common::{AudioDevice, AudioSourceType, FlowSttError, RecordingMode};
 
 /// Commands sent to the PipeWire thread
 #[derive(Debug)]
@@ -36,23 +43,19 @@ enum PwCommand {
     StartCaptureSources {
         source1_id: Option<u32>,
         source2_id: Option<u32>,
+        result_tx: mpsc::Sender<Result<(), FlowSttError>>,
     },
     /// Stop all capture
     StopCapture,
 }
 
-/// Internal audio samples type for PipeWire thread communication
-struct PwAudioSamples {
-    samples: Vec<f32>,
-    channels: u16,
-}
-
 /// Handle to the PipeWire audio backend
 pub struct PipeWireBackend {
     /// Channel to send commands to PipeWire thread
     cmd_tx: mpsc::Sender<PwCommand>,
-    /// Channel to receive audio samples (wrapped in Mutex for Sync)
-    audio_rx: Mutex<mpsc::Receiver<PwAudioSamples>>,
+    /// Queue of mixed audio from the capture thread (in a Mutex for Sync;
+    /// only the processing thread locks it, so it is never contended)
+    audio_rx: Mutex<MixedReceiver>,
     /// Cached input devices
     input_devices: Arc<Mutex<Vec<AudioDevice>>>,
     /// Cached system devices
@@ -61,20 +64,15 @@ pub struct PipeWireBackend {
     _thread_handle: JoinHandle<()>,
     /// Sample rate from PipeWire
     sample_rate: Arc<Mutex<u32>>,
-    /// Echo cancellation enabled flag (shared with mixer)
-    aec_enabled: Arc<Mutex<bool>>,
-    /// Recording mode (shared with mixer)
-    recording_mode: Arc<Mutex<RecordingMode>>,
+    /// AEC flag and recording mode (shared with mixer)
+    controls: Arc<MixerControls>,
 }
 
 impl PipeWireBackend {
-    /// Create and start the PipeWire backend with shared AEC enabled flag and recording mode
-    pub fn new(
-        aec_enabled: Arc<Mutex<bool>>,
-        recording_mode: Arc<Mutex<RecordingMode>>,
-    ) -> Result<Self, String> {
+    /// Create and start the PipeWire backend with shared mixer controls
+    pub fn new(controls: Arc<MixerControls>) -> Result<Self, FlowSttError> {
         let (cmd_tx, cmd_rx) = mpsc::channel();
-        let (audio_tx, audio_rx) = mpsc::channel();
+        let (audio_tx, audio_rx) = mixed_queue();
         let input_devices = Arc::new(Mutex::new(Vec::new()));
         let system_devices = Arc::new(Mutex::new(Vec::new()));
         let sample_rate = Arc::new(Mutex::new(48000u32));
@@ -82,8 +80,7 @@ impl PipeWireBackend {
         let input_devices_clone = Arc::clone(&input_devices);
         let system_devices_clone = Arc::clone(&system_devices);
         let sample_rate_clone = Arc::clone(&sample_rate);
-        let aec_enabled_clone = Arc::clone(&aec_enabled);
-        let recording_mode_clone = Arc::clone(&recording_mode);
+        let controls_clone = Arc::clone(&controls);
 
         let thread_handle = thread::spawn(move || {
             if let Err(e) = run_pipewire_thread(
@@ -92,8 +89,7 @@ impl PipeWireBackend {
                 input_devices_clone,
                 system_devices_clone,
                 sample_rate_clone,
-                aec_enabled_clone,
-                recording_mode_clone,
+                controls_clone,
             ) {
                 tracing::error!("PipeWire thread error: {}", e);
             }
@@ -109,8 +105,7 @@ impl PipeWireBackend {
             system_devices,
             _thread_handle: thread_handle,
             sample_rate,
-            aec_enabled,
-            recording_mode,
+            controls,
         })
     }
 }
@@ -128,260 +123,88 @@ impl AudioBackend for PipeWireBackend {
         *self.sample_rate.lock().unwrap()
     }
 
+    fn captures_speech_format(&self, source1_id: Option<&str>, source2_id: Option<&str>) -> bool {
+        speech_capture(source1_id, source2_id)
+    }
+
     fn start_capture_sources(
         &self,
         source1_id: Option<String>,
         source2_id: Option<String>,
-    ) -> Result<(), String> {
-        // Convert string IDs to u32 for PipeWire
-        let source1: Option<u32> = source1_id.as_ref().and_then(|s| s.parse().ok());
-        let source2: Option<u32> = source2_id.as_ref().and_then(|s| s.parse().ok());
+    ) -> Result<(), FlowSttError> {
+        // PipeWire node IDs are numbers
+        let parse = |id: Option<String>| -> Result<Option<u32>, FlowSttError> {
+            id.map(|id| id.parse().map_err(|_| FlowSttError::DeviceNotFound(id)))
+                .transpose()
+        };
+        let source1 = parse(source1_id)?;
+        let source2 = parse(source2_id)?;
+        let (result_tx, result_rx) = mpsc::channel();
 
         self.cmd_tx
             .send(PwCommand::StartCaptureSources {
                 source1_id: source1,
                 source2_id: source2,
+                result_tx,
             })
-            .map_err(|e| format!("Failed to send start command: {}", e))
+            .map_err(|e| FlowSttError::Capture(format!("Failed to send start command: {}", e)))?;
+
+        match result_rx.recv_timeout(std::time::Duration::from_secs(5)) {
+            Ok(result) => result,
+            Err(mpsc::RecvTimeoutError::Timeout) => Err(FlowSttError::Capture(
+                "Timeout waiting for audio capture to start".to_string(),
+            )),
+            Err(mpsc::RecvTimeoutError::Disconnected) => Err(FlowSttError::Capture(
+                "PipeWire thread disconnected".to_string(),
+            )),
+        }
     }
 
-    fn stop_capture(&self) -> Result<(), String> {
+    fn stop_capture(&self) -> Result<(), FlowSttError> {
         self.cmd_tx
             .send(PwCommand::StopCapture)
-            .map_err(|e| format!("Failed to send stop command: {}", e))
+            .map_err(|e| FlowSttError::Capture(format!("Failed to send stop command: {}", e)))
     }
 
-    fn try_recv(&self) -> Option<AudioData> {
-        let sample_rate = *self.sample_rate.lock().unwrap();
+    fn recv_timeout(&self, timeout: Duration) -> Option<AudioData> {
         self.audio_rx
             .lock()
             .unwrap()
-            .try_recv()
-            .ok()
+            .recv_timeout(timeout)
             .map(|pw_samples| AudioData {
                 samples: pw_samples.samples,
                 channels: pw_samples.channels,
-                sample_rate,
+                sample_rate: pw_samples.sample_rate,
+                sources: pw_samples.sources,
             })
     }
 
     fn set_aec_enabled(&self, enabled: bool) {
-        *self.aec_enabled.lock().unwrap() = enabled;
+        self.controls.set_aec_enabled(enabled);
     }
 
     fn set_recording_mode(&self, mode: RecordingMode) {
-        *self.recording_mode.lock().unwrap() = mode;
+        self.controls.set_recording_mode(mode);
     }
 }
 
-/// Create a Linux audio backend using PipeWire
-pub fn create_backend(
-    aec_enabled: Arc<Mutex<bool>>,
-    recording_mode: Arc<Mutex<RecordingMode>>,
-) -> Result<Box<dyn AudioBackend>, String> {
-    let backend = PipeWireBackend::new(aec_enabled, recording_mode)?;
-    Ok(Box::new(backend))
-}
+/// Check whether a PipeWire daemon is reachable
+pub fn is_available() -> bool {
+    pipewire::init();
 
-/// AEC3 frame size: 10ms at 48kHz = 480 samples per channel
-const AEC_FRAME_SAMPLES: usize = 480;
-
-/// Mixer state for combining audio from multiple streams
-/// Uses separate render-first AEC processing pattern for proper echo cancellation.
-struct AudioMixer {
-    /// Buffer for capture samples (microphone/input)
-    capture_buffer: Vec<f32>,
-    /// Buffer for render samples (system audio/reference) - fed to AEC
-    render_buffer: Vec<f32>,
-    /// Buffer for render samples to mix with processed capture (for Mixed mode)
-    render_mix_buffer: Vec<f32>,
-    /// Number of active streams (1 or 2)
-    num_streams: usize,
-    /// Channels per stream
-    channels: u16,
-    /// Output sender
-    output_tx: mpsc::Sender<PwAudioSamples>,
-    /// Flag to enable/disable AEC (shared with main thread)
-    aec_enabled: Arc<Mutex<bool>>,
-    /// Recording mode - Mixed or EchoCancel (shared with main thread)
-    recording_mode: Arc<Mutex<RecordingMode>>,
-    /// AEC3 pipeline (created when in mixed mode with 2 streams)
-    aec: Option<VoipAec3>,
+    let Ok(mainloop) = MainLoop::new(None) else {
+        return false;
+    };
+    let Ok(context) = Context::new(&mainloop) else {
+        return false;
+    };
+    context.connect(None).is_ok()
 }
 
-impl AudioMixer {
-    fn new(
-        output_tx: mpsc::Sender<PwAudioSamples>,
-        aec_enabled: Arc<Mutex<bool>>,
-        recording_mode: Arc<Mutex<RecordingMode>>,
-    ) -> Self {
-        Self {
-            capture_buffer: Vec::new(),
-            render_buffer: Vec::new(),
-            render_mix_buffer: Vec::new(),
-            num_streams: 0,
-            channels: 2,
-            output_tx,
-            aec_enabled,
-            recording_mode,
-            aec: None,
-        }
-    }
-
-    fn set_num_streams(&mut self, num: usize) {
-        self.num_streams = num;
-        self.capture_buffer.clear();
-        self.render_buffer.clear();
-        self.render_mix_buffer.clear();
-
-        // Create AEC3 pipeline when we have 2 streams (mic + system audio)
-        if num == 2 {
-            // Initial delay hint: start with 0ms and let AEC adapt
-            match VoipAec3::builder(48000, self.channels as usize, self.channels as usize)
-                .enable_high_pass(true)
-                .initial_delay_ms(0)
-                .build()
-            {
-                Ok(aec) => {
-                    tracing::info!(
-                        "PipeWire: AEC3 initialized: 48kHz, {} channels, {}ms frames",
-                        self.channels,
-                        AEC_FRAME_SAMPLES * 1000 / 48000
-                    );
-                    self.aec = Some(aec);
-                }
-                Err(e) => {
-                    tracing::error!("PipeWire: Failed to initialize AEC3: {:?}", e);
-                    self.aec = None;
-                }
-            }
-        } else {
-            self.aec = None;
-        }
-    }
-
-    fn set_channels(&mut self, channels: u16) {
-        self.channels = channels;
-    }
-
-    /// Add samples from a stream, routing based on source type
-    /// - Sink capture (system audio) is fed IMMEDIATELY to AEC render path
-    /// - Input capture (mic) is buffered and processed when enough data available
-    fn push_samples(&mut self, samples: &[f32], is_sink_capture: bool) {
-        if self.num_streams == 1 {
-            // Only one stream - send directly (no AEC possible)
-            let _ = self.output_tx.send(PwAudioSamples {
-                samples: samples.to_vec(),
-                channels: self.channels,
-            });
-            return;
-        }
-
-        // Two streams mode
-        let frame_size = AEC_FRAME_SAMPLES * self.channels as usize;
-
-        if is_sink_capture {
-            // System audio (render) - feed to AEC immediately in frame-sized chunks
-            // This is critical: AEC needs to see render BEFORE corresponding capture
-            self.render_buffer.extend_from_slice(samples);
-            // Also keep a copy for mixing in Mixed mode
-            self.render_mix_buffer.extend_from_slice(samples);
-
-            // Feed render frames to AEC immediately
-            if let Some(ref mut aec) = self.aec {
-                while self.render_buffer.len() >= frame_size {
-                    let render_frame: Vec<f32> = self.render_buffer.drain(0..frame_size).collect();
-                    if let Err(e) = aec.handle_render_frame(&render_frame) {
-                        tracing::error!("PipeWire: AEC3 handle_render_frame error: {:?}", e);
-                    }
-                }
-            }
-        } else {
-            // Microphone (capture) - buffer and process
-            self.capture_buffer.extend_from_slice(samples);
-            self.process_capture();
-        }
-    }
-
-    /// Process buffered capture samples through AEC
-    fn process_capture(&mut self) {
-        let aec_enabled = *self.aec_enabled.lock().unwrap();
-        let recording_mode = *self.recording_mode.lock().unwrap();
-
-        let frame_size = AEC_FRAME_SAMPLES * self.channels as usize;
-
-        // Process capture frames when we have enough data from both sources
-        while self.capture_buffer.len() >= frame_size && self.render_mix_buffer.len() >= frame_size
-        {
-            let capture_frame: Vec<f32> = self.capture_buffer.drain(0..frame_size).collect();
-            let render_frame: Vec<f32> = self.render_mix_buffer.drain(0..frame_size).collect();
-
-            // Apply AEC if enabled and we have an AEC instance
-            let processed_capture = if aec_enabled {
-                if let Some(ref mut aec) = self.aec {
-                    let mut out = vec![0.0f32; capture_frame.len()];
-
-                    match aec.process_capture_frame(&capture_frame, false, &mut out) {
-                        Ok(_metrics) => out,
-                        Err(e) => {
-                            tracing::error!("PipeWire: AEC3 process_capture_frame error: {:?}", e);
-                            capture_frame
-                        }
-                    }
-                } else {
-                    capture_frame
-                }
-            } else {
-                capture_frame
-            };
-
-            // Generate output based on recording mode
-            let output: Vec<f32> = match recording_mode {
-                RecordingMode::Mixed => {
-                    // Mix processed capture with system audio (0.5 gain each to prevent clipping)
-                    processed_capture
-                        .iter()
-                        .zip(render_frame.iter())
-                        .map(|(&s1, &s2)| (s1 + s2) * 0.5)
-                        .collect()
-                }
-                RecordingMode::EchoCancel => {
-                    // Output only the processed capture signal - no mixing
-                    processed_capture
-                }
-            };
-
-            // Debug logging (periodic)
-            static LOG_COUNTER: std::sync::atomic::AtomicU32 = std::sync::atomic::AtomicU32::new(0);
-            let count = LOG_COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-            if count.is_multiple_of(500) {
-                let render_rms: f32 = if !render_frame.is_empty() {
-                    (render_frame.iter().map(|s| s * s).sum::<f32>() / render_frame.len() as f32)
-                        .sqrt()
-                } else {
-                    0.0
-                };
-                let out_rms: f32 = if !output.is_empty() {
-                    (output.iter().map(|s| s * s).sum::<f32>() / output.len() as f32).sqrt()
-                } else {
-                    0.0
-                };
-                tracing::debug!(
-                    "PipeWire AudioMixer: mode={:?}, aec={}, render_rms={:.4}, out_rms={:.4}",
-                    recording_mode,
-                    aec_enabled,
-                    render_rms,
-                    out_rms
-                );
-            }
-
-            // Send output
-            let _ = self.output_tx.send(PwAudioSamples {
-                samples: output,
-                channels: self.channels,
-            });
-        }
-    }
+/// Create a Linux audio backend using PipeWire
+pub fn create_backend(controls: Arc<MixerControls>) -> Result<Box<dyn AudioBackend>, FlowSttError> {
+    let backend = PipeWireBackend::new(controls)?;
+    Ok(Box::new(backend))
 }
 
 /// Held stream state - keeps stream and listener alive
@@ -403,25 +226,27 @@ struct PwThreadState {
 /// Run the PipeWire main loop thread
 fn run_pipewire_thread(
     cmd_rx: mpsc::Receiver<PwCommand>,
-    audio_tx: mpsc::Sender<PwAudioSamples>,
+    audio_tx: MixedSender,
     input_devices: Arc<Mutex<Vec<AudioDevice>>>,
     system_devices: Arc<Mutex<Vec<AudioDevice>>>,
     sample_rate: Arc<Mutex<u32>>,
-    aec_enabled: Arc<Mutex<bool>>,
-    recording_mode: Arc<Mutex<RecordingMode>>,
-) -> Result<(), String> {
+    controls: Arc<MixerControls>,
+) -> Result<(), FlowSttError> {
     // Initialize PipeWire
     pipewire::init();
 
-    let mainloop = MainLoop::new(None).map_err(|e| format!("Failed to create main loop: {}", e))?;
+    let unavailable = |what: &str, e: pipewire::Error| {
+        FlowSttError::BackendUnavailable(format!("{}: {}", what, e))
+    };
+    let mainloop = MainLoop::new(None).map_err(|e| unavailable("Failed to create main loop", e))?;
     let context =
-        Context::new(&mainloop).map_err(|e| format!("Failed to create context: {}", e))?;
+        Context::new(&mainloop).map_err(|e| unavailable("Failed to create context", e))?;
     let core = context
         .connect(None)
-        .map_err(|e| format!("Failed to connect to PipeWire: {}", e))?;
+        .map_err(|e| unavailable("Failed to connect to PipeWire", e))?;
     let registry = core
         .get_registry()
-        .map_err(|e| format!("Failed to get registry: {}", e))?;
+        .map_err(|e| unavailable("Failed to get registry", e))?;
 
     // Device maps for enumeration
     let input_map: Rc<RefCell<HashMap<u32, AudioDevice>>> = Rc::new(RefCell::new(HashMap::new()));
@@ -486,11 +311,9 @@ fn run_pipewire_thread(
         })
         .register();
 
-    // Create mixer with AEC enabled flag and recording mode
+    // Create mixer with the shared AEC flag and recording mode
     let mixer = Rc::new(RefCell::new(AudioMixer::new(
-        audio_tx,
-        aec_enabled,
-        recording_mode,
+        "PipeWire", audio_tx, controls,
     )));
 
     // Thread state - share system_map to know which IDs are sinks
@@ -528,6 +351,7 @@ fn run_pipewire_thread(
                     PwCommand::StartCaptureSources {
                         source1_id,
                         source2_id,
+                        result_tx,
                     } => {
                         // First, check which sources are sinks (before borrowing state mutably)
                         let is_sink1 = source1_id
@@ -544,7 +368,22 @@ fn run_pipewire_thread(
                         // Count how many streams we'll have
                         let num_streams =
                             source1_id.is_some() as usize + source2_id.is_some() as usize;
-                        mixer_for_timer.borrow_mut().set_num_streams(num_streams);
+                        let speech = speech_capture(
+                            source1_id.map(|id| id.to_string()).as_deref(),
+                            source2_id.map(|id| id.to_string()).as_deref(),
+                        );
+                        {
+                            let mut mixer = mixer_for_timer.borrow_mut();
+                            if speech {
+                                mixer.set_sample_rate(SPEECH_SAMPLE_RATE);
+                                mixer.set_channels(1);
+                            } else {
+                                mixer.set_sample_rate(MIXER_SAMPLE_RATE);
+                            }
+                            mixer.set_num_streams(num_streams);
+                        }
+
+                        let mut failure = None;
 
                         // Create stream for source1 if specified
                         if let Some(id) = source1_id {
@@ -554,12 +393,14 @@ fn run_pipewire_thread(
                                 Some(id),
                                 is_sink1,
                                 1, // stream index
+                                speech,
                                 mixer_clone,
                                 Arc::clone(&state.sample_rate),
                             ) {
                                 Ok(stream) => state.streams.push(stream),
                                 Err(e) => {
-                                    tracing::error!("Failed to create stream for source1: {}", e)
+                                    tracing::error!("Failed to create stream for source1: {}", e);
+                                    failure.get_or_insert(e);
                                 }
                             }
                         }
@@ -572,15 +413,28 @@ fn run_pipewire_thread(
                                 Some(id),
                                 is_sink2,
                                 2, // stream index
+                                speech,
                                 mixer_clone,
                                 Arc::clone(&state.sample_rate),
                             ) {
                                 Ok(stream) => state.streams.push(stream),
                                 Err(e) => {
-                                    tracing::error!("Failed to create stream for source2: {}", e)
+                                    tracing::error!("Failed to create stream for source2: {}", e);
+                                    failure.get_or_insert(e);
                                 }
                             }
                         }
+
+                        // Capture starts with every source or not at all
+                        let result = match failure {
+                            Some(e) => {
+                                state.streams.clear();
+                                mixer_for_timer.borrow_mut().set_num_streams(0);
+                                Err(e)
+                            }
+                            None => Ok(()),
+                        };
+                        let _ = result_tx.send(result);
                     }
                     PwCommand::StopCapture => {
                         state_for_timer.borrow_mut().streams.clear();
@@ -603,11 +457,15 @@ fn run_pipewire_thread(
     Ok(())
 }
 
-/// Create an audio format pod for stream connection
-fn create_audio_format_pod() -> Vec<u8> {
+/// Create an audio format pod for stream connection. `speech` asks for
+/// 16kHz mono; otherwise the graph's native format is accepted.
+fn create_audio_format_pod(speech: bool) -> Vec<u8> {
     let mut audio_info = AudioInfoRaw::new();
     audio_info.set_format(AudioFormat::F32LE);
-    // Leave rate and channels unset to accept native graph format
+    if speech {
+        audio_info.set_rate(SPEECH_SAMPLE_RATE);
+        audio_info.set_channels(1);
+    }
 
     let obj = pipewire::spa::pod::Object {
         type_: pipewire::spa::utils::SpaTypes::ObjectParamFormat.as_raw(),
@@ -630,16 +488,17 @@ fn create_capture_stream(
     device_id: Option<u32>,
     capture_sink: bool,
     stream_index: usize, // 1 or 2
+    speech: bool,
     mixer: Rc<RefCell<AudioMixer>>,
     sample_rate: Arc<Mutex<u32>>,
-) -> Result<ActiveStream, String> {
+) -> Result<ActiveStream, FlowSttError> {
     let stream_name = if capture_sink {
         format!("flowstt-system-capture-{}", stream_index)
     } else {
         format!("flowstt-input-capture-{}", stream_index)
     };
 
-    let props = if capture_sink {
+    let mut props = if capture_sink {
         properties! {
             *pipewire::keys::MEDIA_TYPE => "Audio",
             *pipewire::keys::MEDIA_CATEGORY => "Capture",
@@ -654,15 +513,30 @@ fn create_capture_stream(
         }
     };
 
+    // Ask the graph for a shorter or longer quantum. node.latency is a
+    // duration, so it holds whatever rate the graph runs at
+    if let Some(frames) = capture_buffer_frames(MIXER_SAMPLE_RATE) {
+        props.insert(
+            *pipewire::keys::NODE_LATENCY,
+            format!("{}/{}", frames, MIXER_SAMPLE_RATE),
+        );
+    }
+
     let stream = Stream::new(core, &stream_name, props)
-        .map_err(|e| format!("Failed to create stream: {}", e))?;
+        .map_err(|e| FlowSttError::Capture(format!("Failed to create stream: {}", e)))?;
 
     // Track format info from param_changed
     let format_info: Rc<RefCell<AudioInfoRaw>> = Rc::new(RefCell::new(AudioInfoRaw::default()));
     let format_info_for_param = Rc::clone(&format_info);
+    let format_info_for_process = Rc::clone(&format_info);
     let sample_rate_for_param = Arc::clone(&sample_rate);
     let mixer_for_param = Rc::clone(&mixer);
     let mixer_for_process = mixer;
+    let latency_reported = Cell::new(false);
+    // Channels downmixed to mono when only some are captured
+    let selected: Rc<RefCell<Option<Vec<usize>>>> = Rc::default();
+    let selected_for_param = Rc::clone(&selected);
+    let selected_for_process = selected;
 
     let listener = stream
         .add_local_listener_with_user_data(())
@@ -692,7 +566,18 @@ fn create_capture_stream(
                         channels
                     );
                     *sample_rate_for_param.lock().unwrap() = rate;
-                    mixer_for_param.borrow_mut().set_channels(channels as u16);
+                    let selected = device_id
+                        .and_then(|id| selected_channels(&id.to_string(), channels as usize));
+                    let mixer_channels = if selected.is_some() {
+                        2
+                    } else {
+                        channels as u16
+                    };
+                    *selected_for_param.borrow_mut() = selected;
+                    let mut mixer = mixer_for_param.borrow_mut();
+                    mixer.set_channels(mixer_channels);
+                    mixer.set_sample_rate(rate);
+                    report_source_format(capture_sink, rate, channels as u16);
                 }
             }
         })
@@ -700,6 +585,9 @@ fn create_capture_stream(
             tracing::debug!("Stream {} state: {:?} -> {:?}", stream_index, old, new);
         })
         .process(move |stream, _user_data| {
+            // Runs on the data thread right after the graph cycle, so the
+            // arrival time is close to the capture time
+            let arrived = clock::now();
             if let Some(mut buffer) = stream.dequeue_buffer() {
                 let datas = buffer.datas_mut();
                 if datas.is_empty() {
@@ -727,16 +615,32 @@ fn create_capture_stream(
                         // - Sink capture (system audio) goes to reference buffer for AEC
                         // - Input capture (mic) goes to capture buffer for AEC
                         let mut mixer = mixer_for_process.borrow_mut();
-                        mixer.push_samples(&samples, capture_sink);
+                        let format = format_info_for_process.borrow();
+                        let frames = samples.len() / format.channels().max(1) as usize;
+                        let timestamp =
+                            arrived.saturating_sub(clock::frames_to_ns(frames, format.rate()));
+                        // The first buffer shows the quantum the graph settled on
+                        if !latency_reported.replace(true) {
+                            report_capture_latency(capture_sink, frames, format.rate());
+                        }
+                        let samples = match &*selected_for_process.borrow() {
+                            Some(selected) => downmix_channels(
+                                &samples,
+                                format.channels().max(1) as usize,
+                                selected,
+                            ),
+                            None => samples,
+                        };
+                        mixer.push_samples(&samples, capture_sink, timestamp);
                     }
                 }
             }
         })
         .register()
-        .map_err(|e| format!("Failed to register stream listener: {}", e))?;
+        .map_err(|e| FlowSttError::Capture(format!("Failed to register stream listener: {}", e)))?;
 
     // Create audio format parameters
-    let format_pod = create_audio_format_pod();
+    let format_pod = create_audio_format_pod(speech);
     let mut params = [Pod::from_bytes(&format_pod).unwrap()];
 
     // Connect to device (or default if None)
@@ -744,7 +648,7 @@ fn create_capture_stream(
 
     stream
         .connect(Direction::Input, device_id, flags, &mut params)
-        .map_err(|e| format!("Failed to connect stream: {}", e))?;
+        .map_err(|e| FlowSttError::Capture(format!("Failed to connect stream: {}", e)))?;
 
     // Leak the listener to keep it alive - it will be cleaned up when stream is dropped
     std::mem::forget(listener);
@@ -0,0 +1,589 @@
+//! PulseAudio capture backend for Linux
+//!
+//! Used when no PipeWire daemon is reachable. Devices are enumerated through the
+//! libpulse introspection API and each capture source is read on its own thread
+//! using the simple API. Streams request 48kHz float stereo so the server does any
+//! resampling, and sink monitor sources are exposed as system audio devices.
+//! Sources with a channel selection are read with their own channel map instead,
+//! so the server passes their channels through unmixed.
+
+use libpulse_binding::callbacks::ListResult;
+use libpulse_binding::channelmap::Map;
+use libpulse_binding::context::{Context, FlagSet as ContextFlagSet, State as ContextState};
+use libpulse_binding::def::BufferAttr;
+use libpulse_binding::mainloop::standard::{IterateResult, Mainloop};
+use libpulse_binding::operation::State as OperationState;
+use libpulse_binding::sample::{Format, Spec};
+use libpulse_binding::stream::Direction;
+use libpulse_simple_binding::Simple;
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::platform::backend::{AudioBackend, AudioData};
+use crate::platform::clock;
+use crate::platform::mixer::{
+    capture_buffer_frames, channel_selection, downmix_channels, mixed_queue,
+    report_capture_latency, report_source_format, selected_channels, AudioMixer, MixedReceiver,
+    MixedSender, MixerControls, MIXER_SAMPLE_RATE,
+};
+use flowstt_common::{AudioDevice, AudioSourceType, FlowSttError, RecordingMode};
+
+/// Application name reported to the PulseAudio server
+const APP_NAME: &str = "FlowSTT";
+
+/// Channels requested from every capture stream
+const CAPTURE_CHANNELS: u8 = 2;
+
+/// Frames per blocking read unless a buffer duration is configured (10ms at
+/// 48kHz)
+const READ_FRAMES: usize = 480;
+
+/// Samples from a stream thread to the mixer
+struct StreamSamples {
+    samples: Vec<f32>,
+    /// Whether this stream is a sink monitor (system audio) - used for AEC routing
+    is_monitor: bool,
+    /// Host clock time of the first frame, in nanoseconds
+    timestamp: u64,
+}
+
+/// Commands sent to the capture thread
+enum CaptureCommand {
+    StartSources {
+        source1_id: Option<String>,
+        source2_id: Option<String>,
+        result_tx: mpsc::Sender<Result<(), FlowSttError>>,
+    },
+    Stop,
+    Shutdown,
+}
+
+/// PulseAudio audio backend for Linux
+pub struct PulseBackend {
+    /// Channel to send commands to capture thread
+    cmd_tx: mpsc::Sender<CaptureCommand>,
+    /// Queue of mixed audio from the capture thread (in a Mutex for Sync;
+    /// only the processing thread locks it, so it is never contended)
+    audio_rx: Mutex<MixedReceiver>,
+    /// Cached input devices
+    input_devices: Arc<Mutex<Vec<AudioDevice>>>,
+    /// Cached system devices (sink monitors)
+    system_devices: Arc<Mutex<Vec<AudioDevice>>>,
+    /// Capture thread handle
+    _thread_handle: JoinHandle<()>,
+    /// AEC flag and recording mode (shared with mixer)
+    controls: Arc<MixerControls>,
+}
+
+impl PulseBackend {
+    /// Create a new PulseAudio backend
+    pub fn new(controls: Arc<MixerControls>) -> Result<Self, FlowSttError> {
+        let (inputs, monitors) = enumerate_devices()?;
+
+        let (cmd_tx, cmd_rx) = mpsc::channel();
+        let (audio_tx, audio_rx) = mixed_queue();
+        let input_devices = Arc::new(Mutex::new(inputs));
+        let system_devices = Arc::new(Mutex::new(monitors));
+
+        let system_devices_clone = Arc::clone(&system_devices);
+        let controls_clone = Arc::clone(&controls);
+
+        let thread_handle = thread::spawn(move || {
+            run_capture_thread(cmd_rx, audio_tx, system_devices_clone, controls_clone);
+        });
+
+        Ok(Self {
+            cmd_tx,
+            audio_rx: Mutex::new(audio_rx),
+            input_devices,
+            system_devices,
+            _thread_handle: thread_handle,
+            controls,
+        })
+    }
+
+    /// Re-enumerate sources, keeping the cached lists if the server is unreachable
+    fn refresh_devices(&self) {
+        match enumerate_devices() {
+            Ok((inputs, monitors)) => {
+                *self.input_devices.lock().unwrap() = inputs;
+                *self.system_devices.lock().unwrap() = monitors;
+            }
+            Err(e) => tracing::warn!("PulseAudio: Failed to refresh devices: {}", e),
+        }
+    }
+}
+
+impl Drop for PulseBackend {
+    fn drop(&mut self) {
+        let _ = self.cmd_tx.send(CaptureCommand::Shutdown);
+    }
+}
+
+impl AudioBackend for PulseBackend {
+    fn list_input_devices(&self) -> Vec<AudioDevice> {
+        self.refresh_devices();
+        self.input_devices.lock().unwrap().clone()
+    }
+
+    fn list_system_devices(&self) -> Vec<AudioDevice> {
+        self.refresh_devices();
+        self.system_devices.lock().unwrap().clone()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        MIXER_SAMPLE_RATE
+    }
+
+    fn start_capture_sources(
+        &self,
+        source1_id: Option<String>,
+        source2_id: Option<String>,
+    ) -> Result<(), FlowSttError> {
+        let (result_tx, result_rx) = mpsc::channel();
+
+        self.cmd_tx
+            .send(CaptureCommand::StartSources {
+                source1_id,
+                source2_id,
+                result_tx,
+            })
+            .map_err(|e| FlowSttError::Capture(format!("Failed to send start command: {}", e)))?;
+
+        match result_rx.recv_timeout(std::time::Duration::from_secs(5)) {
+            Ok(result) => result,
+            Err(mpsc::RecvTimeoutError::Timeout) => Err(FlowSttError::Capture(
+                "Timeout waiting for audio capture to start".to_string(),
+            )),
+            Err(mpsc::RecvTimeoutError::Disconnected) => Err(FlowSttError::Capture(
+                "Capture thread disconnected".to_string(),
+            )),
+        }
+    }
+
+    fn stop_capture(&self) -> Result<(), FlowSttError> {
+        self.cmd_tx
+            .send(CaptureCommand::Stop)
+            .map_err(|e| FlowSttError::Capture(format!("Failed to send stop command: {}", e)))
+    }
+
+    fn recv_timeout(&self, timeout: Duration) -> Option<AudioData> {
+        self.audio_rx
+            .lock()
+            .unwrap()
+            .recv_timeout(timeout)
+            .map(|samples| AudioData {
+                samples: samples.samples,
+                channels: samples.channels,
+                sample_rate: MIXER_SAMPLE_RATE,
+                sources: samples.sources,
+            })
+    }
+
+    fn set_aec_enabled(&self, enabled: bool) {
+        self.controls.set_aec_enabled(enabled);
+    }
+
+    fn set_recording_mode(&self, mode: RecordingMode) {
+        self.controls.set_recording_mode(mode);
+    }
+}
+
+/// Create a Linux audio backend using PulseAudio
+pub fn create_backend(controls: Arc<MixerControls>) -> Result<Box<dyn AudioBackend>, FlowSttError> {
+    let backend = PulseBackend::new(controls)?;
+    Ok(Box::new(backend))
+}
+
+/// Run one blocking iteration of the main loop
+fn iterate(mainloop: &mut Mainloop) -> Result<(), FlowSttError> {
+    match mainloop.iterate(true) {
+        IterateResult::Success(_) => Ok(()),
+        IterateResult::Quit(_) => Err(FlowSttError::BackendUnavailable(
+            "PulseAudio main loop quit".to_string(),
+        )),
+        IterateResult::Err(e) => Err(FlowSttError::BackendUnavailable(format!(
+            "PulseAudio main loop error: {}",
+            e
+        ))),
+    }
+}
+
+/// Connect a context to the server on a new main loop
+fn connect() -> Result<(Mainloop, Context), FlowSttError> {
+    let mainloop = Mainloop::new().ok_or_else(|| {
+        FlowSttError::BackendUnavailable("Failed to create PulseAudio main loop".to_string())
+    })?;
+    let mut context = Context::new(&mainloop, APP_NAME).ok_or_else(|| {
+        FlowSttError::BackendUnavailable("Failed to create PulseAudio context".to_string())
+    })?;
+    context
+        .connect(None, ContextFlagSet::NOAUTOSPAWN, None)
+        .map_err(|e| {
+            FlowSttError::BackendUnavailable(format!("Failed to connect to PulseAudio: {}", e))
+        })?;
+    Ok((mainloop, context))
+}
+
+/// Enumerate capture sources, split into inputs and sink monitors
+fn enumerate_devices() -> Result<(Vec<AudioDevice>, Vec<AudioDevice>), FlowSttError> {
+    let (mut mainloop, mut context) = connect()?;
+
+    let result = wait_for_context(&mut mainloop, &context)
+        .and_then(|_| list_sources(&mut mainloop, &context));
+
+    context.disconnect();
+    result
+}
+
+/// Channel map of a source, to read its channels as they are
+fn source_channel_map(name: &str) -> Result<Map, FlowSttError> {
+    let (mut mainloop, mut context) = connect()?;
+
+    let result = wait_for_context(&mut mainloop, &context).and_then(|_| {
+        let map: Rc<RefCell<Option<Map>>> = Rc::default();
+        let map_for_callback = Rc::clone(&map);
+        let operation = context
+            .introspect()
+            .get_source_info_by_name(name, move |result| {
+                if let ListResult::Item(info) = result {
+                    *map_for_callback.borrow_mut() = Some(info.channel_map);
+                }
+            });
+        while operation.get_state() == OperationState::Running {
+            iterate(&mut mainloop)?;
+        }
+        map.take()
+            .ok_or_else(|| FlowSttError::DeviceNotFound(name.to_string()))
+    });
+
+    context.disconnect();
+    result
+}
+
+/// Wait until the context is ready or has failed
+fn wait_for_context(mainloop: &mut Mainloop, context: &Context) -> Result<(), FlowSttError> {
+    loop {
+        iterate(mainloop)?;
+        match context.get_state() {
+            ContextState::Ready => return Ok(()),
+            ContextState::Failed | ContextState::Terminated => {
+                return Err(FlowSttError::BackendUnavailable(
+                    "PulseAudio connection failed".to_string(),
+                ))
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Query the server's source list
+fn list_sources(
+    mainloop: &mut Mainloop,
+    context: &Context,
+) -> Result<(Vec<AudioDevice>, Vec<AudioDevice>), FlowSttError> {
+    let devices: Rc<RefCell<(Vec<AudioDevice>, Vec<AudioDevice>)>> = Rc::default();
+    let devices_for_callback = Rc::clone(&devices);
+
+    let operation = context.introspect().get_source_info_list(move |result| {
+        let ListResult::Item(info) = result else {
+            return;
+        };
+        let Some(name) = info.name.as_ref() else {
+            return;
+        };
+        let description = info
+            .description
+            .as_ref()
+            .map(|d| d.to_string())
+            .unwrap_or_else(|| name.to_string());
+
+        let mut devices = devices_for_callback.borrow_mut();
+        if info.monitor_of_sink.is_some() {
+            // Monitor sources are described as "Monitor of <sink>"
+            let sink = description
+                .strip_prefix("Monitor of ")
+                .unwrap_or(&description);
+            devices.1.push(AudioDevice {
+                id: name.to_string(),
+                name: format!("{} (Monitor)", sink),
+                source_type: AudioSourceType::System,
+            });
+        } else {
+            devices.0.push(AudioDevice {
+                id: name.to_string(),
+                name: description,
+                source_type: AudioSourceType::Input,
+            });
+        }
+    });
+
+    while operation.get_state() == OperationState::Running {
+        iterate(mainloop)?;
+    }
+
+    let (inputs, monitors) = devices.take();
+    Ok((inputs, monitors))
+}
+
+/// Run the capture thread, which owns the mixer and the stream threads
+fn run_capture_thread(
+    cmd_rx: mpsc::Receiver<CaptureCommand>,
+    audio_tx: MixedSender,
+    system_devices: Arc<Mutex<Vec<AudioDevice>>>,
+    controls: Arc<MixerControls>,
+) {
+    tracing::info!("PulseAudio: Capture thread started");
+
+    let mut mixer = AudioMixer::new("PulseAudio", audio_tx, controls);
+    let (stream_tx, stream_rx) = mpsc::channel::<StreamSamples>();
+    let mut streams: Vec<CaptureStream> = Vec::new();
+
+    loop {
+        // Process any samples from stream threads first
+        while let Ok(stream_samples) = stream_rx.try_recv() {
+            mixer.push_samples(
+                &stream_samples.samples,
+                stream_samples.is_monitor,
+                stream_samples.timestamp,
+            );
+        }
+
+        let timeout = if streams.is_empty() {
+            std::time::Duration::from_secs(1)
+        } else {
+            std::time::Duration::from_millis(1)
+        };
+
+        match cmd_rx.recv_timeout(timeout) {
+            Ok(CaptureCommand::StartSources {
+                source1_id,
+                source2_id,
+                result_tx,
+            }) => {
+                // Stop any existing capture
+                streams.clear();
+
+                let monitor_ids: HashSet<String> = system_devices
+                    .lock()
+                    .unwrap()
+                    .iter()
+                    .map(|d| d.id.clone())
+                    .collect();
+
+                let sources: Vec<String> = [source1_id, source2_id].into_iter().flatten().collect();
+                mixer.set_num_streams(sources.len());
+
+                let result: Result<Vec<CaptureStream>, FlowSttError> = sources
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, id)| {
+                        let is_monitor = monitor_ids.contains(&id);
+                        CaptureStream::start(id, is_monitor, i + 1, stream_tx.clone())
+                    })
+                    .collect();
+
+                match result {
+                    Ok(started) => {
+                        tracing::info!(
+                            "PulseAudio: Started capture with {} sources",
+                            started.len()
+                        );
+                        streams = started;
+                        let _ = result_tx.send(Ok(()));
+                    }
+                    Err(e) => {
+                        tracing::error!("PulseAudio: Failed to start capture: {}", e);
+                        mixer.set_num_streams(0);
+                        let _ = result_tx.send(Err(e));
+                    }
+                }
+            }
+            Ok(CaptureCommand::Stop) => {
+                if !streams.is_empty() {
+                    tracing::info!("PulseAudio: Stopping capture");
+                    streams.clear();
+                }
+                mixer.set_num_streams(0);
+            }
+            Ok(CaptureCommand::Shutdown) | Err(mpsc::RecvTimeoutError::Disconnected) => {
+                streams.clear();
+                break;
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                // Continue processing samples
+            }
+        }
+    }
+}
+
+/// A capture stream reading from one PulseAudio source on its own thread
+struct CaptureStream {
+    stop_flag: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl CaptureStream {
+    /// Spawn the stream thread and wait until the source has been opened
+    fn start(
+        device_id: String,
+        is_monitor: bool,
+        stream_index: usize,
+        stream_tx: mpsc::Sender<StreamSamples>,
+    ) -> Result<Self, FlowSttError> {
+        let stop_flag = Arc::new(AtomicBool::new(false));
+        let stop_flag_clone = Arc::clone(&stop_flag);
+        let (ready_tx, ready_rx) = mpsc::channel();
+
+        let handle = thread::spawn(move || {
+            run_stream_capture(
+                device_id,
+                is_monitor,
+                stream_index,
+                stream_tx,
+                stop_flag_clone,
+                ready_tx,
+            );
+        });
+
+        match ready_rx.recv() {
+            Ok(Ok(())) => Ok(Self {
+                stop_flag,
+                handle: Some(handle),
+            }),
+            Ok(Err(e)) => {
+                let _ = handle.join();
+                Err(e)
+            }
+            Err(_) => Err(FlowSttError::Capture(format!(
+                "Stream {} capture thread exited",
+                stream_index
+            ))),
+        }
+    }
+}
+
+impl Drop for CaptureStream {
+    fn drop(&mut self) {
+        self.stop_flag.store(true, Ordering::SeqCst);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Read from a single source until stopped
+fn run_stream_capture(
+    device_id: String,
+    is_monitor: bool,
+    stream_index: usize,
+    stream_tx: mpsc::Sender<StreamSamples>,
+    stop_flag: Arc<AtomicBool>,
+    ready_tx: mpsc::Sender<Result<(), FlowSttError>>,
+) {
+    // A channel selection is read from the source's own channels
+    let selection = channel_selection(&device_id)
+        .and_then(|_| match source_channel_map(&device_id) {
+            Ok(map) => Some(map),
+            Err(e) => {
+                tracing::warn!("PulseAudio: {}; capturing stereo", e);
+                None
+            }
+        })
+        .and_then(|map| {
+            selected_channels(&device_id, map.len() as usize).map(|selected| (map, selected))
+        });
+    let channels = selection
+        .as_ref()
+        .map_or(CAPTURE_CHANNELS, |(map, _)| map.len());
+
+    let spec = Spec {
+        format: Format::F32le,
+        channels,
+        rate: MIXER_SAMPLE_RATE,
+    };
+    let frame_bytes = channels as usize * std::mem::size_of::<f32>();
+    let read_frames = capture_buffer_frames(MIXER_SAMPLE_RATE).unwrap_or(READ_FRAMES);
+    let buffer_attr = BufferAttr {
+        maxlength: u32::MAX,
+        tlength: u32::MAX,
+        prealloc: u32::MAX,
+        minreq: u32::MAX,
+        fragsize: (read_frames * frame_bytes) as u32,
+    };
+
+    let stream_name = if is_monitor {
+        format!("flowstt-system-capture-{}", stream_index)
+    } else {
+        format!("flowstt-input-capture-{}", stream_index)
+    };
+
+    let simple = match Simple::new(
+        None,
+        APP_NAME,
+        Direction::Record,
+        Some(&device_id),
+        &stream_name,
+        &spec,
+        selection.as_ref().map(|(map, _)| map),
+        Some(&buffer_attr),
+    ) {
+        Ok(simple) => {
+            let _ = ready_tx.send(Ok(()));
+            simple
+        }
+        Err(e) => {
+            let _ = ready_tx.send(Err(FlowSttError::Capture(format!(
+                "Failed to open PulseAudio source {}: {}",
+                device_id, e
+            ))));
+            return;
+        }
+    };
+
+    tracing::info!(
+        "PulseAudio: Stream {} capture started (device={}, monitor={})",
+        stream_index,
+        device_id,
+        is_monitor
+    );
+    report_source_format(is_monitor, MIXER_SAMPLE_RATE, channels as u16);
+    report_capture_latency(is_monitor, read_frames, MIXER_SAMPLE_RATE);
+
+    let mut buffer = vec![0u8; read_frames * frame_bytes];
+    while !stop_flag.load(Ordering::SeqCst) {
+        if let Err(e) = simple.read(&mut buffer) {
+            tracing::error!("PulseAudio: Stream {} read error: {}", stream_index, e);
+            break;
+        }
+        let timestamp = clock::before_now(read_frames, MIXER_SAMPLE_RATE);
+
+        let samples: Vec<f32> = buffer
+            .chunks_exact(4)
+            .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+            .collect();
+        let samples = match &selection {
+            Some((_, selected)) => downmix_channels(&samples, channels as usize, selected),
+            None => samples,
+        };
+
+        if stream_tx
+            .send(StreamSamples {
+                samples,
+                is_monitor,
+                timestamp,
+            })
+            .is_err()
+        {
+            break;
+        }
+    }
+
+    tracing::info!("PulseAudio: Stream {} capture stopped", stream_index);
+}
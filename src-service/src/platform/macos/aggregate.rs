@@ -0,0 +1,259 @@
+//! CoreAudio aggregate devices for capturing two microphones in sync
+//!
+//! Separate input devices run on separate clocks, so their samples drift
+//! apart. An aggregate device clocks its sub-devices from one master and
+//! compensates the others' drift, so when both capture sources are input
+//! devices a private aggregate of the two is created for the session (hidden
+//! from other applications and destroyed when capture stops) and captured as
+//! one source: the first microphone on the left channel, the second on the
+//! right.
+//!
+//! Aggregates the user made in Audio MIDI Setup are captured the same way,
+//! taking the first channel of each of their first two input sub-devices.
+
+use core_foundation::array::CFArray;
+use core_foundation::base::{CFType, TCFType};
+use core_foundation::dictionary::CFDictionary;
+use core_foundation::number::CFNumber;
+use core_foundation::string::{CFString, CFStringRef};
+use coreaudio::sys;
+use flowstt_common::FlowSttError;
+use std::os::raw::c_void;
+use std::ptr;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+/// Name shown for aggregates created for a session
+const AGGREGATE_NAME: &str = "FlowSTT Microphones";
+
+/// Source of unique aggregate UIDs within this process
+static NEXT_AGGREGATE: AtomicU32 = AtomicU32::new(1);
+
+/// `kAudioHardwareBadObjectError` ('!obj'): no device has the ID
+const BAD_OBJECT: i32 = 0x216F_626A;
+
+/// A private aggregate device, destroyed when dropped
+pub struct AggregateDevice {
+    id: sys::AudioObjectID,
+}
+
+impl AggregateDevice {
+    /// Create a private aggregate of two input devices, clocked from the first.
+    pub fn create(first: u32, second: u32) -> Result<Self, FlowSttError> {
+        let master = device_uid(first)?;
+        let uids = [master.clone(), device_uid(second)?];
+
+        let sub_devices: Vec<CFDictionary<CFString, CFType>> = uids
+            .iter()
+            .enumerate()
+            .map(|(i, uid)| {
+                // The master is the clock; only the other device is resampled
+                let drift = CFNumber::from((i > 0) as i32);
+                CFDictionary::from_CFType_pairs(&[
+                    (CFString::new("uid"), CFString::new(uid).as_CFType()),
+                    (CFString::new("drift"), drift.as_CFType()),
+                ])
+            })
+            .collect();
+        let uid = format!(
+            "com.flowstt.aggregate.{}.{}",
+            std::process::id(),
+            NEXT_AGGREGATE.fetch_add(1, Ordering::SeqCst)
+        );
+        let description = CFDictionary::from_CFType_pairs(&[
+            (
+                CFString::new("name"),
+                CFString::new(AGGREGATE_NAME).as_CFType(),
+            ),
+            (CFString::new("uid"), CFString::new(&uid).as_CFType()),
+            (CFString::new("private"), CFNumber::from(1).as_CFType()),
+            (
+                CFString::new("subdevices"),
+                CFArray::from_CFTypes(&sub_devices).as_CFType(),
+            ),
+            (CFString::new("master"), CFString::new(&master).as_CFType()),
+        ]);
+
+        let mut id: sys::AudioObjectID = 0;
+        let status = unsafe {
+            sys::AudioHardwareCreateAggregateDevice(description.as_concrete_TypeRef() as _, &mut id)
+        };
+        if status != 0 {
+            return Err(FlowSttError::Capture(format!(
+                "Failed to create aggregate device: OSStatus {}",
+                status
+            )));
+        }
+
+        tracing::info!(
+            "CoreAudio: Created aggregate device {} of devices {} and {}",
+            id,
+            first,
+            second
+        );
+        Ok(Self { id })
+    }
+
+    /// CoreAudio device ID of the aggregate
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+}
+
+impl Drop for AggregateDevice {
+    fn drop(&mut self) {
+        let status = unsafe { sys::AudioHardwareDestroyAggregateDevice(self.id) };
+        if status != 0 {
+            tracing::warn!(
+                "CoreAudio: Failed to destroy aggregate device {}: OSStatus {}",
+                self.id,
+                status
+            );
+        }
+    }
+}
+
+/// Channels to capture as left and right when `device_id` is an aggregate
+/// of two or more input devices: the first channel of each of the first two.
+pub fn channel_map(device_id: u32) -> Option<[usize; 2]> {
+    let transport: u32 = get_property(
+        device_id,
+        sys::kAudioDevicePropertyTransportType,
+        sys::kAudioObjectPropertyScopeGlobal,
+    )
+    .ok()?;
+    if transport != sys::kAudioDeviceTransportTypeAggregate {
+        return None;
+    }
+
+    let sub_devices = sub_devices(device_id).ok()?;
+    let mut first_channel = 0;
+    let mut firsts = Vec::new();
+    for sub_device in sub_devices {
+        let channels = input_channels(sub_device).unwrap_or(0);
+        if channels > 0 {
+            firsts.push(first_channel);
+        }
+        first_channel += channels;
+    }
+    match firsts[..] {
+        [left, right, ..] => Some([left, right]),
+        _ => None,
+    }
+}
+
+/// Persistent UID of a device, which aggregates refer to sub-devices by.
+fn device_uid(device_id: u32) -> Result<String, FlowSttError> {
+    let uid: CFStringRef = get_property(
+        device_id,
+        sys::kAudioDevicePropertyDeviceUID,
+        sys::kAudioObjectPropertyScopeGlobal,
+    )?;
+    if uid.is_null() {
+        return Err(FlowSttError::Capture(format!(
+            "Device {} has no UID",
+            device_id
+        )));
+    }
+    Ok(unsafe { CFString::wrap_under_create_rule(uid) }.to_string())
+}
+
+/// Number of input channels of a device.
+fn input_channels(device_id: u32) -> Result<usize, FlowSttError> {
+    // An AudioBufferList, kept in u64s for its pointer alignment
+    let buffer = get_property_bytes(
+        device_id,
+        sys::kAudioDevicePropertyStreamConfiguration,
+        sys::kAudioObjectPropertyScopeInput,
+    )?;
+    if buffer.is_empty() {
+        return Ok(0);
+    }
+    let list = unsafe { &*(buffer.as_ptr() as *const sys::AudioBufferList) };
+    let buffers =
+        unsafe { std::slice::from_raw_parts(list.mBuffers.as_ptr(), list.mNumberBuffers as usize) };
+    Ok(buffers
+        .iter()
+        .map(|buffer| buffer.mNumberChannels as usize)
+        .sum())
+}
+
+/// Sub-devices of an aggregate device, in channel order.
+fn sub_devices(device_id: u32) -> Result<Vec<u32>, FlowSttError> {
+    let buffer = get_property_bytes(
+        device_id,
+        sys::kAudioAggregateDevicePropertyActiveSubDeviceList,
+        sys::kAudioObjectPropertyScopeGlobal,
+    )?;
+    let count = buffer.len() * std::mem::size_of::<u64>() / std::mem::size_of::<u32>();
+    let ids = unsafe { std::slice::from_raw_parts(buffer.as_ptr() as *const u32, count) };
+    Ok(ids.iter().copied().filter(|&id| id != 0).collect())
+}
+
+fn address(selector: u32, scope: u32) -> sys::AudioObjectPropertyAddress {
+    sys::AudioObjectPropertyAddress {
+        mSelector: selector,
+        mScope: scope,
+        mElement: sys::kAudioObjectPropertyElementMaster,
+    }
+}
+
+/// Read a fixed-size property.
+fn get_property<T: Copy>(device_id: u32, selector: u32, scope: u32) -> Result<T, FlowSttError> {
+    let address = address(selector, scope);
+    let mut value = std::mem::MaybeUninit::<T>::zeroed();
+    let mut size = std::mem::size_of::<T>() as u32;
+    let status = unsafe {
+        sys::AudioObjectGetPropertyData(
+            device_id,
+            &address,
+            0,
+            ptr::null(),
+            &mut size,
+            value.as_mut_ptr() as *mut c_void,
+        )
+    };
+    if status != 0 {
+        return Err(property_error("read", device_id, selector, status));
+    }
+    Ok(unsafe { value.assume_init() })
+}
+
+/// Read a variable-size property into 8-byte aligned storage.
+fn get_property_bytes(device_id: u32, selector: u32, scope: u32) -> Result<Vec<u64>, FlowSttError> {
+    let address = address(selector, scope);
+    let mut size: u32 = 0;
+    let status = unsafe {
+        sys::AudioObjectGetPropertyDataSize(device_id, &address, 0, ptr::null(), &mut size)
+    };
+    if status != 0 {
+        return Err(property_error("size", device_id, selector, status));
+    }
+
+    let mut buffer = vec![0u64; (size as usize).div_ceil(std::mem::size_of::<u64>())];
+    let status = unsafe {
+        sys::AudioObjectGetPropertyData(
+            device_id,
+            &address,
+            0,
+            ptr::null(),
+            &mut size,
+            buffer.as_mut_ptr() as *mut c_void,
+        )
+    };
+    if status != 0 {
+        return Err(property_error("read", device_id, selector, status));
+    }
+    buffer.truncate((size as usize).div_ceil(std::mem::size_of::<u64>()));
+    Ok(buffer)
+}
+
+/// Error for a failed property access; an unknown device is not found.
+fn property_error(action: &str, device_id: u32, selector: u32, status: i32) -> FlowSttError {
+    if status == BAD_OBJECT {
+        return FlowSttError::DeviceNotFound(device_id.to_string());
+    }
+    FlowSttError::Capture(format!(
+        "Failed to {} property {:#x} of device {}: OSStatus {}",
+        action, selector, device_id, status
+    ))
+}
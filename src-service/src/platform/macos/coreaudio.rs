@@ -2,13 +2,23 @@
 //!
 //! This module provides full audio capture functionality for macOS:
 //! - Input device enumeration (microphones) via CoreAudio
-//! - System audio enumeration and capture via ScreenCaptureKit (macOS 12.3+)
-//! - Multi-source capture with mixing
+//! - System audio enumeration and capture via ScreenCaptureKit (macOS 13+),
+//!   or from a virtual loopback driver (see `loopback`)
+//! - Multi-source capture with mixing, and two microphones captured in sync
+//!   through an aggregate device (see `aggregate`)
 //! - Echo cancellation using AEC3
 
+use crate::permissions;
 use crate::platform::backend::{AudioBackend, AudioData};
+use crate::platform::clock;
+use crate::platform::macos::aggregate::{self, AggregateDevice};
+use crate::platform::macos::loopback;
 use crate::platform::macos::screencapturekit::{self, SCKAudioCapture};
-use aec3::voip::VoipAec3;
+use crate::platform::mixer::{
+    capture_buffer_frames, mixed_queue, report_capture_latency, report_source_format,
+    selected_channels, AudioMixer, MixedReceiver, MixedSender, MixerControls, Resampler,
+    MIXER_SAMPLE_RATE,
+};
 use coreaudio::audio_unit::macos_helpers::{
     get_audio_device_ids, get_audio_device_supports_scope, get_default_device_id, get_device_name,
 };
@@ -17,20 +27,22 @@ use coreaudio::sys::{
     self, kAudioOutputUnitProperty_SetInputCallback, kAudioUnitProperty_StreamFormat, AudioBuffer,
     AudioBufferList, AudioUnitRenderActionFlags,
 };
-use flowstt_common::{AudioDevice, AudioSourceType, RecordingMode};
+use flowstt_common::{AudioDevice, AudioSourceType, FlowSttError, Permission, RecordingMode};
+use rtrb::{Consumer, Producer, RingBuffer};
 use std::collections::HashSet;
 use std::os::raw::c_void;
 use std::ptr;
-use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc;
 use std::sync::{Arc, Mutex};
 use std::thread::{self, JoinHandle};
+use std::time::Duration;
 
-/// Target sample rate for output (matches Linux/Windows backends)
-const TARGET_SAMPLE_RATE: f64 = 48000.0;
+/// Target sample rate for output (the mixer's rate)
+const TARGET_SAMPLE_RATE: f64 = MIXER_SAMPLE_RATE as f64;
 
-/// AEC3 frame size: 10ms at 48kHz = 480 samples per channel
-const AEC_FRAME_SAMPLES: usize = 480;
+/// Buffers the queue from the input callback to the capture thread holds
+const STREAM_QUEUE_CAPACITY: usize = 256;
 
 /// System audio device ID prefix
 const SYSTEM_AUDIO_PREFIX: &str = "sck:";
@@ -38,11 +50,17 @@ const SYSTEM_AUDIO_PREFIX: &str = "sck:";
 /// Context passed to the input callback
 struct InputCallbackContext {
     audio_unit: sys::AudioUnit,
-    audio_tx: mpsc::Sender<StreamSamples>,
-    resampler: Option<Mutex<Resampler>>,
+    audio_tx: Producer<StreamSamples>,
+    resampler: Option<Resampler>,
+    /// Device sample rate, before resampling
+    sample_rate: u32,
     num_channels: usize,
     is_non_interleaved: bool,
+    /// Channels averaged into the left and right channel
+    channel_map: [Vec<usize>; 2],
     stream_index: usize,
+    /// Whether this is a loopback driver (system audio)
+    is_loopback: bool,
 }
 
 /// Raw input callback procedure for CoreAudio
@@ -54,7 +72,9 @@ extern "C" fn input_callback_proc(
     in_number_frames: u32,
     _io_data: *mut AudioBufferList,
 ) -> sys::OSStatus {
-    let context = unsafe { &*(in_ref_con as *const InputCallbackContext) };
+    // CoreAudio calls the input callback serially on its IO thread, and the
+    // context outlives the audio unit, so this is the only reference to it
+    let context = unsafe { &mut *(in_ref_con as *mut InputCallbackContext) };
 
     // Allocate buffer list for the audio data
     let num_buffers = if context.is_non_interleaved {
@@ -126,70 +146,52 @@ extern "C" fn input_callback_proc(
 
     unsafe {
         let buffer_list_ref = &*buffer_list;
-
-        if context.is_non_interleaved {
-            let buffers_ptr = buffer_list_ref.mBuffers.as_ptr();
-
-            let mut channel_ptrs: Vec<*const f32> = Vec::with_capacity(num_buffers);
-            for i in 0..num_buffers {
-                let buffer = &*buffers_ptr.add(i);
-                channel_ptrs.push(buffer.mData as *const f32);
+        let buffers_ptr = buffer_list_ref.mBuffers.as_ptr();
+        let sample = |channel: usize, i: usize| {
+            if context.is_non_interleaved {
+                *((*buffers_ptr.add(channel)).mData as *const f32).add(i)
+            } else {
+                *((*buffers_ptr).mData as *const f32).add(i * context.num_channels + channel)
             }
+        };
 
-            // Interleave to stereo
-            for i in 0..num_frames {
-                let left = *channel_ptrs[0].add(i);
-                let right = if num_buffers > 1 {
-                    *channel_ptrs[1].add(i)
-                } else {
-                    left
-                };
-                samples.push(left);
-                samples.push(right);
-            }
-        } else {
-            let buffer = &buffer_list_ref.mBuffers[0];
-            let data_ptr = buffer.mData as *const f32;
-
-            if context.num_channels == 1 {
-                for i in 0..num_frames {
-                    let sample = *data_ptr.add(i);
-                    samples.push(sample);
-                    samples.push(sample);
-                }
-            } else {
-                let total_samples = num_frames * context.num_channels;
-                for i in 0..total_samples {
-                    samples.push(*data_ptr.add(i));
-                }
+        // Interleave the mapped channels to stereo
+        for i in 0..num_frames {
+            for channels in &context.channel_map {
+                let sum: f32 = channels.iter().map(|&channel| sample(channel, i)).sum();
+                samples.push(sum / channels.len() as f32);
             }
         }
     }
 
+    // The device's capture time of the first frame, or the arrival time when
+    // the host time isn't valid
+    let time_stamp = unsafe { &*in_time_stamp };
+    let timestamp = if time_stamp.mFlags & sys::kAudioTimeStampHostTimeValid != 0 {
+        clock::host_time_to_ns(time_stamp.mHostTime)
+    } else {
+        clock::before_now(num_frames, context.sample_rate)
+    };
+
     // Resample if needed
-    let samples = if let Some(ref resampler) = context.resampler {
-        resampler.lock().unwrap().process(&samples, 2)
+    let samples = if let Some(resampler) = context.resampler.as_mut() {
+        resampler.process(&samples, 2)
     } else {
         samples
     };
 
     if !samples.is_empty() {
-        let _ = context.audio_tx.send(StreamSamples {
+        let _ = context.audio_tx.push(StreamSamples {
             stream_index: context.stream_index,
             samples,
-            is_loopback: false,
+            is_loopback: context.is_loopback,
+            timestamp,
         });
     }
 
     0 // noErr
 }
 
-/// Internal audio samples for channel communication
-struct CoreAudioSamples {
-    samples: Vec<f32>,
-    channels: u16,
-}
-
 /// Samples from a stream thread to the mixer
 struct StreamSamples {
     #[allow(dead_code)]
@@ -197,6 +199,8 @@ struct StreamSamples {
     samples: Vec<f32>,
     /// Whether this stream is loopback (system audio) - used for AEC routing
     is_loopback: bool,
+    /// Host clock time of the first frame, in nanoseconds
+    timestamp: u64,
 }
 
 /// Commands sent to the capture thread
@@ -204,213 +208,70 @@ enum CaptureCommand {
     StartSources {
         source1_id: Option<String>,
         source2_id: Option<String>,
-        result_tx: mpsc::Sender<Result<(), String>>,
+        result_tx: mpsc::Sender<Result<(), FlowSttError>>,
     },
     Stop,
     Shutdown,
 }
 
-/// Audio mixer for combining samples from multiple streams
-struct AudioMixer {
-    /// Buffer for capture samples (microphone/input)
-    capture_buffer: Vec<f32>,
-    /// Buffer for render samples (system audio/reference) - fed to AEC and kept for mixing
-    render_buffer: Vec<f32>,
-    /// Buffer for render samples to mix with processed capture (for Mixed mode)
-    render_mix_buffer: Vec<f32>,
-    /// Number of active streams (1 or 2)
-    num_streams: usize,
-    /// Channels per stream
-    channels: u16,
-    /// Output sender
-    output_tx: mpsc::Sender<CoreAudioSamples>,
-    /// Flag to enable/disable AEC (shared with main thread)
-    aec_enabled: Arc<Mutex<bool>>,
-    /// Recording mode - Mixed or EchoCancel (shared with main thread)
-    recording_mode: Arc<Mutex<RecordingMode>>,
-    /// AEC3 pipeline (created when in mixed mode with 2 streams)
-    aec: Option<VoipAec3>,
+/// A CoreAudio input stream running on its own thread
+struct InputStream {
+    handle: JoinHandle<()>,
+    stop_flag: Arc<AtomicBool>,
+    /// Samples from the stream's callback
+    rx: Consumer<StreamSamples>,
 }
 
-impl AudioMixer {
-    fn new(
-        output_tx: mpsc::Sender<CoreAudioSamples>,
-        aec_enabled: Arc<Mutex<bool>>,
-        recording_mode: Arc<Mutex<RecordingMode>>,
-    ) -> Self {
-        Self {
-            capture_buffer: Vec::new(),
-            render_buffer: Vec::new(),
-            render_mix_buffer: Vec::new(),
-            num_streams: 0,
-            channels: 2,
-            output_tx,
-            aec_enabled,
-            recording_mode,
-            aec: None,
-        }
-    }
-
-    fn set_num_streams(&mut self, num: usize) {
-        self.num_streams = num;
-        self.capture_buffer.clear();
-        self.render_buffer.clear();
-        self.render_mix_buffer.clear();
-
-        // Create AEC3 pipeline when we have 2 streams (mic + system audio)
-        if num == 2 {
-            match VoipAec3::builder(48000, self.channels as usize, self.channels as usize)
-                .enable_high_pass(true)
-                .initial_delay_ms(0)
-                .build()
-            {
-                Ok(aec) => {
-                    tracing::info!(
-                        "CoreAudio: AEC3 initialized: 48kHz, {} channels, {}ms frames",
-                        self.channels,
-                        AEC_FRAME_SAMPLES * 1000 / 48000
-                    );
-                    self.aec = Some(aec);
-                }
-                Err(e) => {
-                    tracing::error!("CoreAudio: Failed to initialize AEC3: {:?}", e);
-                    self.aec = None;
-                }
-            }
-        } else {
-            self.aec = None;
-        }
-    }
-
-    /// Add samples from a stream, routing based on source type
-    fn push_samples(&mut self, samples: &[f32], is_loopback: bool) {
-        if self.num_streams == 1 {
-            // Single stream - send directly (no AEC possible)
-            let _ = self.output_tx.send(CoreAudioSamples {
-                samples: samples.to_vec(),
-                channels: self.channels,
-            });
-            return;
-        }
-
-        // Two streams mode
-        let frame_size = AEC_FRAME_SAMPLES * self.channels as usize;
-
-        if is_loopback {
-            // System audio (render) - feed to AEC immediately
-            self.render_buffer.extend_from_slice(samples);
-            self.render_mix_buffer.extend_from_slice(samples);
-
-            // Feed render frames to AEC immediately
-            if let Some(ref mut aec) = self.aec {
-                while self.render_buffer.len() >= frame_size {
-                    let render_frame: Vec<f32> = self.render_buffer.drain(0..frame_size).collect();
-                    if let Err(e) = aec.handle_render_frame(&render_frame) {
-                        tracing::error!("CoreAudio: AEC3 handle_render_frame error: {:?}", e);
-                    }
-                }
-            }
-        } else {
-            // Microphone (capture) - buffer and process
-            self.capture_buffer.extend_from_slice(samples);
-            self.process_capture();
-        }
-    }
-
-    /// Process buffered capture samples through AEC
-    fn process_capture(&mut self) {
-        let aec_enabled = *self.aec_enabled.lock().unwrap();
-        let recording_mode = *self.recording_mode.lock().unwrap();
-
-        let frame_size = AEC_FRAME_SAMPLES * self.channels as usize;
-
-        // Process capture frames when we have enough data from both sources
-        while self.capture_buffer.len() >= frame_size && self.render_mix_buffer.len() >= frame_size
-        {
-            let capture_frame: Vec<f32> = self.capture_buffer.drain(0..frame_size).collect();
-            let render_frame: Vec<f32> = self.render_mix_buffer.drain(0..frame_size).collect();
-
-            // Apply AEC if enabled
-            let processed_capture = if aec_enabled {
-                if let Some(ref mut aec) = self.aec {
-                    let mut out = vec![0.0f32; capture_frame.len()];
-
-                    match aec.process_capture_frame(&capture_frame, false, &mut out) {
-                        Ok(_metrics) => out,
-                        Err(e) => {
-                            tracing::error!("CoreAudio: AEC3 process_capture_frame error: {:?}", e);
-                            capture_frame
-                        }
-                    }
-                } else {
-                    capture_frame
-                }
-            } else {
-                capture_frame
-            };
-
-            // Generate output based on recording mode
-            let output: Vec<f32> = match recording_mode {
-                RecordingMode::Mixed => {
-                    // Mix processed capture with system audio using soft clipping
-                    processed_capture
-                        .iter()
-                        .zip(render_frame.iter())
-                        .map(|(&s1, &s2)| {
-                            let sum = s1 + s2;
-                            if sum > 1.0 {
-                                1.0 - (-2.0 * (sum - 1.0)).exp() * 0.5
-                            } else if sum < -1.0 {
-                                -1.0 + (-2.0 * (-sum - 1.0)).exp() * 0.5
-                            } else {
-                                sum
-                            }
-                        })
-                        .collect()
-                }
-                RecordingMode::EchoCancel => {
-                    // Output only the processed capture signal
-                    processed_capture
-                }
-            };
+impl InputStream {
+    /// Spawn the stream thread and wait until its audio unit has started
+    fn start(
+        device_id: String,
+        stream_index: usize,
+        is_loopback: bool,
+    ) -> Result<Self, FlowSttError> {
+        let stop_flag = Arc::new(AtomicBool::new(false));
+        let stop_flag_clone = Arc::clone(&stop_flag);
+        let (tx, rx) = RingBuffer::new(STREAM_QUEUE_CAPACITY);
+        let (ready_tx, ready_rx) = mpsc::channel();
+
+        let handle = thread::spawn(move || {
+            run_input_capture(
+                device_id,
+                stream_index,
+                is_loopback,
+                tx,
+                stop_flag_clone,
+                ready_tx,
+            );
+        });
 
-            // Debug logging (periodic)
-            static LOG_COUNTER: AtomicU32 = AtomicU32::new(0);
-            let count = LOG_COUNTER.fetch_add(1, Ordering::Relaxed);
-            if count.is_multiple_of(500) {
-                let render_rms: f32 = if !render_frame.is_empty() {
-                    (render_frame.iter().map(|s| s * s).sum::<f32>() / render_frame.len() as f32)
-                        .sqrt()
-                } else {
-                    0.0
-                };
-                let out_rms: f32 = if !output.is_empty() {
-                    (output.iter().map(|s| s * s).sum::<f32>() / output.len() as f32).sqrt()
-                } else {
-                    0.0
-                };
-                tracing::debug!(
-                    "CoreAudio AudioMixer: mode={:?}, aec={}, render_rms={:.4}, out_rms={:.4}",
-                    recording_mode,
-                    aec_enabled,
-                    render_rms,
-                    out_rms
-                );
+        match ready_rx.recv() {
+            Ok(Ok(())) => Ok(Self {
+                handle,
+                stop_flag,
+                rx,
+            }),
+            Ok(Err(e)) => {
+                let _ = handle.join();
+                Err(e)
             }
-
-            // Send output
-            let _ = self.output_tx.send(CoreAudioSamples {
-                samples: output,
-                channels: self.channels,
-            });
+            Err(_) => Err(FlowSttError::Capture(format!(
+                "Stream {} capture thread exited",
+                stream_index
+            ))),
         }
     }
 }
 
 /// Manager for multiple capture streams
 struct MultiCaptureManager {
-    /// CoreAudio input stream thread handle and stop flag
-    input_stream: Option<(JoinHandle<()>, Arc<AtomicBool>)>,
+    /// CoreAudio input stream
+    input_stream: Option<InputStream>,
+    /// Aggregate of two microphones captured by the input stream, destroyed
+    /// after the stream stops
+    aggregate: Option<AggregateDevice>,
+    /// Loopback driver capturing system audio, when used instead of ScreenCaptureKit
+    loopback_stream: Option<InputStream>,
     /// ScreenCaptureKit system audio capture
     system_capture: Option<SCKAudioCapture>,
     /// Stop flag for system audio polling
@@ -425,83 +286,90 @@ impl MultiCaptureManager {
         is_loopback1: bool,
         source2_id: Option<String>,
         is_loopback2: bool,
-        stream_tx: mpsc::Sender<StreamSamples>,
-    ) -> Result<Self, String> {
-        let mut input_stream = None;
-        let mut system_capture: Option<SCKAudioCapture> = None;
-        let system_thread = None;
-        let system_stop_flag = Arc::new(AtomicBool::new(false));
-
-        // Start stream 1
-        if let Some(device_id) = source1_id {
-            if is_loopback1 {
-                // System audio via ScreenCaptureKit
-                let capture = SCKAudioCapture::new()?;
-                capture.start()?;
-                system_capture = Some(capture);
-            } else {
-                // Input device via CoreAudio
-                let stop_flag = Arc::new(AtomicBool::new(false));
-                let stop_flag_clone = Arc::clone(&stop_flag);
-                let tx = stream_tx.clone();
+    ) -> Result<Self, FlowSttError> {
+        // Dropping the manager stops the streams already started if a later
+        // one fails to start
+        let mut manager = Self {
+            input_stream: None,
+            aggregate: None,
+            loopback_stream: None,
+            system_capture: None,
+            system_stop_flag: Arc::new(AtomicBool::new(false)),
+            system_thread: None,
+        };
 
-                let handle = thread::spawn(move || {
-                    run_input_capture(device_id, 1, tx, stop_flag_clone);
+        // Two microphones are captured in sync as one stereo source
+        if let (Some(first), Some(second)) = (&source1_id, &source2_id) {
+            if !is_loopback1 && !is_loopback2 && first != second {
+                let started = create_aggregate(first, second).and_then(|device| {
+                    let stream = InputStream::start(device.id().to_string(), 1, false)?;
+                    Ok((stream, device))
                 });
-
-                input_stream = Some((handle, stop_flag));
+                match started {
+                    Ok((stream, device)) => {
+                        manager.input_stream = Some(stream);
+                        manager.aggregate = Some(device);
+                    }
+                    Err(e) => {
+                        tracing::warn!("CoreAudio: {}; capturing only the first microphone", e)
+                    }
+                }
             }
         }
 
-        // Start stream 2
-        if let Some(device_id) = source2_id {
-            if is_loopback2 {
-                // System audio via ScreenCaptureKit
-                if system_capture.is_none() {
-                    let capture = SCKAudioCapture::new()?;
-                    capture.start()?;
-                    system_capture = Some(capture);
-                }
-            } else {
+        let sources = [(source1_id, is_loopback1, 1), (source2_id, is_loopback2, 2)];
+        for (device_id, is_loopback, stream_index) in sources {
+            let Some(device_id) = device_id else {
+                continue;
+            };
+            if !is_loopback {
                 // Input device via CoreAudio
-                if input_stream.is_none() {
-                    let stop_flag = Arc::new(AtomicBool::new(false));
-                    let stop_flag_clone = Arc::clone(&stop_flag);
-                    let tx = stream_tx;
-
-                    let handle = thread::spawn(move || {
-                        run_input_capture(device_id, 2, tx, stop_flag_clone);
-                    });
-
-                    input_stream = Some((handle, stop_flag));
+                if manager.input_stream.is_none() {
+                    manager.input_stream =
+                        Some(InputStream::start(device_id, stream_index, false)?);
                 }
+            } else if manager.system_capture.is_some() || manager.loopback_stream.is_some() {
+                continue;
+            } else if let Some(driver_id) = loopback::device_id(&device_id) {
+                // System audio from a loopback driver, captured like an input
+                manager.loopback_stream = Some(InputStream::start(
+                    driver_id.to_string(),
+                    stream_index,
+                    true,
+                )?);
+            } else {
+                // System audio via ScreenCaptureKit
+                let capture = SCKAudioCapture::new()?;
+                capture.start(sck_device_id(&device_id))?;
+                manager.system_capture = Some(capture);
             }
         }
 
-        Ok(Self {
-            input_stream,
-            system_capture,
-            system_stop_flag,
-            system_thread,
-        })
+        Ok(manager)
+    }
+
+    /// Poll the input and loopback driver streams for samples
+    fn poll_input_audio(&mut self) -> Option<StreamSamples> {
+        [&mut self.input_stream, &mut self.loopback_stream]
+            .into_iter()
+            .flatten()
+            .find_map(|stream| stream.rx.pop().ok())
     }
 
     /// Poll system audio capture for samples
-    fn poll_system_audio(&self) -> Option<Vec<f32>> {
-        if let Some(ref capture) = self.system_capture {
-            if let Some(samples) = capture.try_recv() {
-                return Some(samples.samples);
-            }
-        }
-        None
+    fn poll_system_audio(&self) -> Option<screencapturekit::SCKAudioSamples> {
+        self.system_capture.as_ref()?.try_recv()
     }
 }
 
 impl Drop for MultiCaptureManager {
     fn drop(&mut self) {
         // Signal streams to stop
-        if let Some((_, ref stop_flag)) = self.input_stream {
-            stop_flag.store(true, Ordering::SeqCst);
+        for stream in [&self.input_stream, &self.loopback_stream]
+            .into_iter()
+            .flatten()
+        {
+            stream.stop_flag.store(true, Ordering::SeqCst);
         }
         self.system_stop_flag.store(true, Ordering::SeqCst);
 
@@ -511,8 +379,11 @@ impl Drop for MultiCaptureManager {
         }
 
         // Wait for threads to finish
-        if let Some((handle, _)) = self.input_stream.take() {
-            let _ = handle.join();
+        for stream in [self.input_stream.take(), self.loopback_stream.take()]
+            .into_iter()
+            .flatten()
+        {
+            let _ = stream.handle.join();
         }
         if let Some(handle) = self.system_thread.take() {
             let _ = handle.join();
@@ -520,23 +391,36 @@ impl Drop for MultiCaptureManager {
     }
 }
 
+/// Create a private aggregate of two input devices, by their source IDs.
+fn create_aggregate(first: &str, second: &str) -> Result<AggregateDevice, FlowSttError> {
+    let parse = |id: &str| {
+        id.parse::<u32>()
+            .map_err(|_| FlowSttError::DeviceNotFound(id.to_string()))
+    };
+    AggregateDevice::create(parse(first)?, parse(second)?)
+}
+
 /// Run capture thread for CoreAudio input
 fn run_input_capture(
     device_id: String,
     stream_index: usize,
-    stream_tx: mpsc::Sender<StreamSamples>,
+    is_loopback: bool,
+    stream_tx: Producer<StreamSamples>,
     stop_flag: Arc<AtomicBool>,
+    ready_tx: mpsc::Sender<Result<(), FlowSttError>>,
 ) {
     tracing::info!(
-        "CoreAudio: Input capture thread started (device={}, index={})",
+        "CoreAudio: Input capture thread started (device={}, index={}, loopback={})",
         device_id,
-        stream_index
+        stream_index,
+        is_loopback
     );
 
     let device_id: u32 = match device_id.parse() {
         Ok(id) => id,
         Err(_) => {
             tracing::error!("CoreAudio: Invalid device ID: {}", device_id);
+            let _ = ready_tx.send(Err(FlowSttError::DeviceNotFound(device_id)));
             return;
         }
     };
@@ -546,6 +430,7 @@ fn run_input_capture(
         Ok(unit) => unit,
         Err(e) => {
             tracing::error!("CoreAudio: Failed to create audio unit: {}", e);
+            let _ = ready_tx.send(Err(e));
             return;
         }
     };
@@ -558,17 +443,54 @@ fn run_input_capture(
             unsafe {
                 sys::AudioComponentInstanceDispose(audio_unit);
             }
+            let _ = ready_tx.send(Err(e));
             return;
         }
     };
 
+    if num_channels == 0 {
+        tracing::error!("CoreAudio: Device {} has no input channels", device_id);
+        unsafe {
+            sys::AudioComponentInstanceDispose(audio_unit);
+        }
+        let _ = ready_tx.send(Err(FlowSttError::Capture(format!(
+            "Device {} has no input channels",
+            device_id
+        ))));
+        return;
+    }
+
+    // Selected channels downmixed to mono, one channel from each of an
+    // aggregate's first two devices, or the first two channels
+    let channel_map = match (
+        selected_channels(&device_id.to_string(), num_channels),
+        aggregate::channel_map(device_id),
+    ) {
+        (Some(selected), _) => [selected.clone(), selected],
+        (None, Some([left, right])) if left.max(right) < num_channels => {
+            tracing::info!(
+                "CoreAudio: Capturing aggregate device channels {} and {}",
+                left,
+                right
+            );
+            [vec![left], vec![right]]
+        }
+        _ => [vec![0], vec![1.min(num_channels - 1)]],
+    };
+
+    report_source_format(is_loopback, sample_rate as u32, num_channels as u16);
+    match apply_buffer_frame_size(audio_unit, sample_rate as u32) {
+        Ok(frames) => report_capture_latency(is_loopback, frames as usize, sample_rate as u32),
+        Err(e) => tracing::warn!("CoreAudio: {}", e),
+    }
+
     // Create resampler if needed
     let needs_resampling = (sample_rate - TARGET_SAMPLE_RATE).abs() > 1.0;
     let resampler = if needs_resampling {
-        Some(Mutex::new(Resampler::new(
+        Some(Resampler::new(
             sample_rate as u32,
             TARGET_SAMPLE_RATE as u32,
-        )))
+        ))
     } else {
         None
     };
@@ -578,9 +500,12 @@ fn run_input_capture(
         audio_unit,
         audio_tx: stream_tx,
         resampler,
+        sample_rate: sample_rate as u32,
         num_channels,
         is_non_interleaved,
+        channel_map,
         stream_index,
+        is_loopback,
     });
     let context_ptr = Box::into_raw(callback_context);
 
@@ -610,6 +535,10 @@ fn run_input_capture(
             let _ = Box::from_raw(context_ptr);
             sys::AudioComponentInstanceDispose(audio_unit);
         }
+        let _ = ready_tx.send(Err(FlowSttError::Capture(format!(
+            "Failed to set input callback: OSStatus {}",
+            status
+        ))));
         return;
     }
 
@@ -621,10 +550,15 @@ fn run_input_capture(
             let _ = Box::from_raw(context_ptr);
             sys::AudioComponentInstanceDispose(audio_unit);
         }
+        let _ = ready_tx.send(Err(FlowSttError::Capture(format!(
+            "Failed to start audio unit: OSStatus {}",
+            status
+        ))));
         return;
     }
 
     tracing::info!("CoreAudio: Input capture started");
+    let _ = ready_tx.send(Ok(()));
 
     // Wait for stop signal
     while !stop_flag.load(Ordering::SeqCst) {
@@ -645,8 +579,9 @@ fn run_input_capture(
 pub struct CoreAudioBackend {
     /// Channel to send commands to capture thread
     cmd_tx: mpsc::Sender<CaptureCommand>,
-    /// Channel to receive audio samples from capture thread
-    audio_rx: Mutex<mpsc::Receiver<CoreAudioSamples>>,
+    /// Queue of mixed audio from the capture thread (in a Mutex for Sync;
+    /// only the processing thread locks it, so it is never contended)
+    audio_rx: Mutex<MixedReceiver>,
     /// Cached input devices
     input_devices: Arc<Mutex<Vec<AudioDevice>>>,
     /// Cached system devices
@@ -658,20 +593,15 @@ pub struct CoreAudioBackend {
     /// Flag indicating if capture is active
     #[allow(dead_code)]
     is_capturing: Arc<AtomicBool>,
-    /// AEC enabled flag
-    aec_enabled: Arc<Mutex<bool>>,
-    /// Recording mode
-    recording_mode: Arc<Mutex<RecordingMode>>,
+    /// AEC flag and recording mode (shared with mixer)
+    controls: Arc<MixerControls>,
 }
 
 impl CoreAudioBackend {
     /// Create a new CoreAudio backend
-    pub fn new(
-        aec_enabled: Arc<Mutex<bool>>,
-        recording_mode: Arc<Mutex<RecordingMode>>,
-    ) -> Result<Self, String> {
+    pub fn new(controls: Arc<MixerControls>) -> Result<Self, FlowSttError> {
         let (cmd_tx, cmd_rx) = mpsc::channel();
-        let (audio_tx, audio_rx) = mpsc::channel();
+        let (audio_tx, audio_rx) = mixed_queue();
         let input_devices = Arc::new(Mutex::new(Vec::new()));
         let system_devices = Arc::new(Mutex::new(Vec::new()));
         let is_capturing = Arc::new(AtomicBool::new(false));
@@ -685,8 +615,7 @@ impl CoreAudioBackend {
 
         let system_devices_clone = Arc::clone(&system_devices);
         let is_capturing_clone = Arc::clone(&is_capturing);
-        let aec_enabled_clone = Arc::clone(&aec_enabled);
-        let recording_mode_clone = Arc::clone(&recording_mode);
+        let controls_clone = Arc::clone(&controls);
 
         let thread_handle = thread::spawn(move || {
             run_capture_thread(
@@ -694,8 +623,7 @@ impl CoreAudioBackend {
                 audio_tx,
                 system_devices_clone,
                 is_capturing_clone,
-                aec_enabled_clone,
-                recording_mode_clone,
+                controls_clone,
             );
         });
 
@@ -707,8 +635,7 @@ impl CoreAudioBackend {
             sample_rate: TARGET_SAMPLE_RATE as u32,
             _thread_handle: thread_handle,
             is_capturing,
-            aec_enabled,
-            recording_mode,
+            controls,
         })
     }
 }
@@ -725,7 +652,10 @@ impl AudioBackend for CoreAudioBackend {
     }
 
     fn list_system_devices(&self) -> Vec<AudioDevice> {
-        self.system_devices.lock().unwrap().clone()
+        // Re-enumerate so newly launched applications show up
+        let devices = enumerate_system_devices();
+        *self.system_devices.lock().unwrap() = devices.clone();
+        devices
     }
 
     fn sample_rate(&self) -> u32 {
@@ -736,7 +666,7 @@ impl AudioBackend for CoreAudioBackend {
         &self,
         source1_id: Option<String>,
         source2_id: Option<String>,
-    ) -> Result<(), String> {
+    ) -> Result<(), FlowSttError> {
         let (result_tx, result_rx) = mpsc::channel();
 
         self.cmd_tx
@@ -745,78 +675,79 @@ impl AudioBackend for CoreAudioBackend {
                 source2_id,
                 result_tx,
             })
-            .map_err(|e| format!("Failed to send start command: {}", e))?;
+            .map_err(|e| FlowSttError::Capture(format!("Failed to send start command: {}", e)))?;
 
         match result_rx.recv_timeout(std::time::Duration::from_secs(10)) {
             Ok(result) => result,
-            Err(mpsc::RecvTimeoutError::Timeout) => {
-                Err("Timeout waiting for audio capture to start".to_string())
-            }
-            Err(mpsc::RecvTimeoutError::Disconnected) => {
-                Err("Capture thread disconnected".to_string())
-            }
+            Err(mpsc::RecvTimeoutError::Timeout) => Err(FlowSttError::Capture(
+                "Timeout waiting for audio capture to start".to_string(),
+            )),
+            Err(mpsc::RecvTimeoutError::Disconnected) => Err(FlowSttError::Capture(
+                "Capture thread disconnected".to_string(),
+            )),
         }
     }
 
-    fn stop_capture(&self) -> Result<(), String> {
+    fn stop_capture(&self) -> Result<(), FlowSttError> {
         self.cmd_tx
             .send(CaptureCommand::Stop)
-            .map_err(|e| format!("Failed to send stop command: {}", e))?;
+            .map_err(|e| FlowSttError::Capture(format!("Failed to send stop command: {}", e)))?;
         Ok(())
     }
 
-    fn try_recv(&self) -> Option<AudioData> {
+    fn recv_timeout(&self, timeout: Duration) -> Option<AudioData> {
         self.audio_rx
             .lock()
             .unwrap()
-            .try_recv()
-            .ok()
+            .recv_timeout(timeout)
             .map(|samples| AudioData {
                 samples: samples.samples,
                 channels: samples.channels,
                 sample_rate: self.sample_rate,
+                sources: samples.sources,
             })
     }
 
     fn set_aec_enabled(&self, enabled: bool) {
-        *self.aec_enabled.lock().unwrap() = enabled;
+        self.controls.set_aec_enabled(enabled);
     }
 
     fn set_recording_mode(&self, mode: RecordingMode) {
-        *self.recording_mode.lock().unwrap() = mode;
+        self.controls.set_recording_mode(mode);
     }
 }
 
 /// Run the capture thread
 fn run_capture_thread(
     cmd_rx: mpsc::Receiver<CaptureCommand>,
-    audio_tx: mpsc::Sender<CoreAudioSamples>,
+    audio_tx: MixedSender,
     system_devices: Arc<Mutex<Vec<AudioDevice>>>,
     is_capturing: Arc<AtomicBool>,
-    aec_enabled: Arc<Mutex<bool>>,
-    recording_mode: Arc<Mutex<RecordingMode>>,
+    controls: Arc<MixerControls>,
 ) {
     tracing::debug!("CoreAudio: Capture thread started and ready to receive commands");
 
     // Create mixer (owned by this thread)
-    let mut mixer = AudioMixer::new(audio_tx, aec_enabled, recording_mode);
-
-    // Channel for receiving samples from stream threads
-    let (stream_tx, stream_rx) = mpsc::channel::<StreamSamples>();
+    let mut mixer = AudioMixer::new("CoreAudio", audio_tx, controls);
 
     // Active capture state
     let mut capture_manager: Option<MultiCaptureManager> = None;
 
     loop {
-        // Process any samples from stream threads first
-        while let Ok(stream_samples) = stream_rx.try_recv() {
-            mixer.push_samples(&stream_samples.samples, stream_samples.is_loopback);
-        }
+        if let Some(ref mut manager) = capture_manager {
+            // Process any samples from the input stream first
+            while let Some(stream_samples) = manager.poll_input_audio() {
+                mixer.push_samples(
+                    &stream_samples.samples,
+                    stream_samples.is_loopback,
+                    stream_samples.timestamp,
+                );
+            }
 
-        // Poll system audio if we have an active capture
-        if let Some(ref manager) = capture_manager {
+            // Poll system audio
             if let Some(samples) = manager.poll_system_audio() {
-                mixer.push_samples(&samples, true); // is_loopback = true for system audio
+                // is_loopback = true for system audio
+                mixer.push_samples(&samples.samples, true, samples.timestamp);
             }
         }
 
@@ -851,17 +782,48 @@ fn run_capture_thread(
                     .map(|d| d.id.clone())
                     .collect();
 
-                let is_loopback1 = source1_id
-                    .as_ref()
-                    .map(|id| system_ids.contains(id) || id.starts_with(SYSTEM_AUDIO_PREFIX))
-                    .unwrap_or(false);
-                let is_loopback2 = source2_id
-                    .as_ref()
-                    .map(|id| system_ids.contains(id) || id.starts_with(SYSTEM_AUDIO_PREFIX))
-                    .unwrap_or(false);
-
-                // Count streams
-                let num_streams = source1_id.is_some() as usize + source2_id.is_some() as usize;
+                let is_system = |id: &String| {
+                    system_ids.contains(id)
+                        || id.starts_with(SYSTEM_AUDIO_PREFIX)
+                        || id.starts_with(loopback::DEVICE_PREFIX)
+                };
+                let is_loopback1 = source1_id.as_ref().is_some_and(is_system);
+                let is_loopback2 = source2_id.as_ref().is_some_and(is_system);
+
+                // Without access, capture runs but delivers silence. Loopback
+                // drivers are captured like microphones.
+                let is_driver = |id: &Option<String>| {
+                    id.as_deref()
+                        .is_some_and(|id| loopback::device_id(id).is_some())
+                };
+                let uses_input = (source1_id.is_some() && !is_loopback1)
+                    || (source2_id.is_some() && !is_loopback2)
+                    || is_driver(&source1_id)
+                    || is_driver(&source2_id);
+                let uses_system = (is_loopback1 && !is_driver(&source1_id))
+                    || (is_loopback2 && !is_driver(&source2_id));
+                let mut required = Vec::new();
+                if uses_input {
+                    required.push(Permission::Microphone);
+                }
+                if uses_system {
+                    required.push(Permission::ScreenRecording);
+                }
+                if let Err(e) = required.into_iter().try_for_each(permissions::require) {
+                    tracing::error!("CoreAudio: {}", e);
+                    is_capturing.store(false, Ordering::SeqCst);
+                    let _ = result_tx.send(Err(e));
+                    continue;
+                }
+
+                // Count streams; two microphones are captured as one
+                let both_inputs =
+                    source1_id.is_some() && source2_id.is_some() && !is_loopback1 && !is_loopback2;
+                let num_streams = if both_inputs {
+                    1
+                } else {
+                    source1_id.is_some() as usize + source2_id.is_some() as usize
+                };
                 mixer.set_num_streams(num_streams);
 
                 // Start capture
@@ -869,13 +831,7 @@ fn run_capture_thread(
                     "CoreAudio: Starting MultiCaptureManager (source1={:?}, loopback1={}, source2={:?}, loopback2={})",
                     source1_id, is_loopback1, source2_id, is_loopback2
                 );
-                match MultiCaptureManager::new(
-                    source1_id,
-                    is_loopback1,
-                    source2_id,
-                    is_loopback2,
-                    stream_tx.clone(),
-                ) {
+                match MultiCaptureManager::new(source1_id, is_loopback1, source2_id, is_loopback2) {
                     Ok(manager) => {
                         tracing::info!("CoreAudio: Started capture with {} sources", num_streams);
                         is_capturing.store(true, Ordering::SeqCst);
@@ -917,7 +873,7 @@ fn run_capture_thread(
 }
 
 /// Create an input audio unit for the given device ID
-fn create_input_audio_unit(device_id: u32) -> Result<sys::AudioUnit, String> {
+fn create_input_audio_unit(device_id: u32) -> Result<sys::AudioUnit, FlowSttError> {
     let desc = sys::AudioComponentDescription {
         componentType: sys::kAudioUnitType_Output,
         componentSubType: sys::kAudioUnitSubType_HALOutput,
@@ -928,16 +884,18 @@ fn create_input_audio_unit(device_id: u32) -> Result<sys::AudioUnit, String> {
 
     let component = unsafe { sys::AudioComponentFindNext(ptr::null_mut(), &desc) };
     if component.is_null() {
-        return Err("Failed to find HAL Output audio component".to_string());
+        return Err(FlowSttError::BackendUnavailable(
+            "Failed to find HAL Output audio component".to_string(),
+        ));
     }
 
     let mut audio_unit: sys::AudioUnit = ptr::null_mut();
     let status = unsafe { sys::AudioComponentInstanceNew(component, &mut audio_unit) };
     if status != 0 {
-        return Err(format!(
+        return Err(FlowSttError::Capture(format!(
             "Failed to create audio unit instance: OSStatus {}",
             status
-        ));
+        )));
     }
 
     // Enable input on element 1
@@ -956,7 +914,10 @@ fn create_input_audio_unit(device_id: u32) -> Result<sys::AudioUnit, String> {
         unsafe {
             sys::AudioComponentInstanceDispose(audio_unit);
         }
-        return Err(format!("Failed to enable input: OSStatus {}", status));
+        return Err(FlowSttError::Capture(format!(
+            "Failed to enable input: OSStatus {}",
+            status
+        )));
     }
 
     // Disable output on element 0
@@ -975,7 +936,10 @@ fn create_input_audio_unit(device_id: u32) -> Result<sys::AudioUnit, String> {
         unsafe {
             sys::AudioComponentInstanceDispose(audio_unit);
         }
-        return Err(format!("Failed to disable output: OSStatus {}", status));
+        return Err(FlowSttError::Capture(format!(
+            "Failed to disable output: OSStatus {}",
+            status
+        )));
     }
 
     // Set the input device
@@ -993,7 +957,10 @@ fn create_input_audio_unit(device_id: u32) -> Result<sys::AudioUnit, String> {
         unsafe {
             sys::AudioComponentInstanceDispose(audio_unit);
         }
-        return Err(format!("Failed to set device: OSStatus {}", status));
+        return Err(FlowSttError::Capture(format!(
+            "Failed to set device: OSStatus {}",
+            status
+        )));
     }
 
     // Get the input format from the device
@@ -1013,7 +980,10 @@ fn create_input_audio_unit(device_id: u32) -> Result<sys::AudioUnit, String> {
         unsafe {
             sys::AudioComponentInstanceDispose(audio_unit);
         }
-        return Err(format!("Failed to get device format: OSStatus {}", status));
+        return Err(FlowSttError::Capture(format!(
+            "Failed to get device format: OSStatus {}",
+            status
+        )));
     }
 
     // Set the output format to match the device
@@ -1031,7 +1001,10 @@ fn create_input_audio_unit(device_id: u32) -> Result<sys::AudioUnit, String> {
         unsafe {
             sys::AudioComponentInstanceDispose(audio_unit);
         }
-        return Err(format!("Failed to set output format: OSStatus {}", status));
+        return Err(FlowSttError::Capture(format!(
+            "Failed to set output format: OSStatus {}",
+            status
+        )));
     }
 
     // Initialize the audio unit
@@ -1040,17 +1013,65 @@ fn create_input_audio_unit(device_id: u32) -> Result<sys::AudioUnit, String> {
         unsafe {
             sys::AudioComponentInstanceDispose(audio_unit);
         }
-        return Err(format!(
+        return Err(FlowSttError::Capture(format!(
             "Failed to initialize audio unit: OSStatus {}",
             status
-        ));
+        )));
     }
 
     Ok(audio_unit)
 }
 
+/// Request the configured buffer duration from the audio unit's device and
+/// return the buffer size it ended up with, in frames.
+fn apply_buffer_frame_size(
+    audio_unit: sys::AudioUnit,
+    sample_rate: u32,
+) -> Result<u32, FlowSttError> {
+    if let Some(frames) = capture_buffer_frames(sample_rate) {
+        let frames = frames as u32;
+        let status = unsafe {
+            sys::AudioUnitSetProperty(
+                audio_unit,
+                sys::kAudioDevicePropertyBufferFrameSize,
+                sys::kAudioUnitScope_Global,
+                0,
+                &frames as *const _ as *const c_void,
+                std::mem::size_of::<u32>() as u32,
+            )
+        };
+        if status != 0 {
+            tracing::warn!(
+                "CoreAudio: Failed to set buffer size to {} frames: OSStatus {}",
+                frames,
+                status
+            );
+        }
+    }
+
+    let mut frames: u32 = 0;
+    let mut size = std::mem::size_of::<u32>() as u32;
+    let status = unsafe {
+        sys::AudioUnitGetProperty(
+            audio_unit,
+            sys::kAudioDevicePropertyBufferFrameSize,
+            sys::kAudioUnitScope_Global,
+            0,
+            &mut frames as *mut _ as *mut c_void,
+            &mut size,
+        )
+    };
+    if status != 0 {
+        return Err(FlowSttError::Capture(format!(
+            "Failed to get buffer size: OSStatus {}",
+            status
+        )));
+    }
+    Ok(frames)
+}
+
 /// Get the stream format for an audio unit's input
-fn get_stream_format(audio_unit: sys::AudioUnit) -> Result<(f64, usize, bool), String> {
+fn get_stream_format(audio_unit: sys::AudioUnit) -> Result<(f64, usize, bool), FlowSttError> {
     let mut asbd: sys::AudioStreamBasicDescription = unsafe { std::mem::zeroed() };
     let mut size = std::mem::size_of::<sys::AudioStreamBasicDescription>() as u32;
 
@@ -1066,7 +1087,10 @@ fn get_stream_format(audio_unit: sys::AudioUnit) -> Result<(f64, usize, bool), S
     };
 
     if status != 0 {
-        return Err(format!("Failed to get stream format: OSStatus {}", status));
+        return Err(FlowSttError::Capture(format!(
+            "Failed to get stream format: OSStatus {}",
+            status
+        )));
     }
 
     let is_non_interleaved = (asbd.mFormatFlags & sys::kAudioFormatFlagIsNonInterleaved) != 0;
@@ -1076,9 +1100,10 @@ fn get_stream_format(audio_unit: sys::AudioUnit) -> Result<(f64, usize, bool), S
 }
 
 /// Enumerate available input devices
-fn enumerate_input_devices() -> Result<Vec<AudioDevice>, String> {
-    let device_ids =
-        get_audio_device_ids().map_err(|e| format!("Failed to get audio devices: {:?}", e))?;
+fn enumerate_input_devices() -> Result<Vec<AudioDevice>, FlowSttError> {
+    let device_ids = get_audio_device_ids().map_err(|e| {
+        FlowSttError::BackendUnavailable(format!("Failed to get audio devices: {:?}", e))
+    })?;
 
     let default_input_id = get_default_device_id(true);
 
@@ -1113,101 +1138,35 @@ fn enumerate_input_devices() -> Result<Vec<AudioDevice>, String> {
     Ok(input_devices)
 }
 
-/// Enumerate available system audio devices (via ScreenCaptureKit)
+/// ScreenCaptureKit device ID for a system audio source ID.
+fn sck_device_id(source_id: &str) -> &str {
+    source_id
+        .strip_prefix(SYSTEM_AUDIO_PREFIX)
+        .unwrap_or(source_id)
+}
+
+/// Enumerate available system audio devices: ScreenCaptureKit's, then any
+/// loopback drivers
 fn enumerate_system_devices() -> Vec<AudioDevice> {
-    if !screencapturekit::is_available() {
-        return Vec::new();
-    }
+    let mut devices = Vec::new();
 
-    match screencapturekit::enumerate_system_devices() {
-        Ok(devices) => devices
-            .into_iter()
-            .map(|d| AudioDevice {
+    if screencapturekit::is_available() {
+        match screencapturekit::enumerate_system_devices() {
+            Ok(sck_devices) => devices.extend(sck_devices.into_iter().map(|d| AudioDevice {
                 id: format!("{}{}", SYSTEM_AUDIO_PREFIX, d.id),
                 name: d.name,
                 source_type: AudioSourceType::System,
-            })
-            .collect(),
-        Err(e) => {
-            tracing::error!("CoreAudio: Failed to enumerate system devices: {}", e);
-            Vec::new()
+            })),
+            Err(e) => tracing::error!("CoreAudio: Failed to enumerate system devices: {}", e),
         }
     }
-}
-
-/// Simple linear resampler
-struct Resampler {
-    source_rate: u32,
-    target_rate: u32,
-    buffer: Vec<f32>,
-    position: f64,
-}
-
-impl Resampler {
-    fn new(source_rate: u32, target_rate: u32) -> Self {
-        Self {
-            source_rate,
-            target_rate,
-            buffer: Vec::new(),
-            position: 0.0,
-        }
-    }
-
-    fn process(&mut self, samples: &[f32], channels: usize) -> Vec<f32> {
-        self.buffer.extend_from_slice(samples);
-
-        let ratio = self.source_rate as f64 / self.target_rate as f64;
-        let input_frames = self.buffer.len() / channels;
-        let output_frames = ((input_frames as f64 - self.position) / ratio) as usize;
-
-        if output_frames == 0 {
-            return Vec::new();
-        }
-
-        let mut output = Vec::with_capacity(output_frames * channels);
-
-        for _ in 0..output_frames {
-            let src_frame = self.position as usize;
-            let frac = self.position - src_frame as f64;
 
-            for ch in 0..channels {
-                let idx0 = src_frame * channels + ch;
-                let idx1 = (src_frame + 1) * channels + ch;
-
-                let sample = if idx1 < self.buffer.len() {
-                    self.buffer[idx0] * (1.0 - frac as f32) + self.buffer[idx1] * frac as f32
-                } else if idx0 < self.buffer.len() {
-                    self.buffer[idx0]
-                } else {
-                    0.0
-                };
-                output.push(sample);
-            }
-
-            self.position += ratio;
-        }
-
-        let consumed_frames = self.position as usize;
-        if consumed_frames > 0 {
-            let consumed_samples = consumed_frames * channels;
-            if consumed_samples < self.buffer.len() {
-                self.buffer.drain(0..consumed_samples);
-                self.position -= consumed_frames as f64;
-            } else {
-                self.buffer.clear();
-                self.position = 0.0;
-            }
-        }
-
-        output
-    }
+    devices.extend(loopback::enumerate_devices());
+    devices
 }
 
 /// Create a macOS CoreAudio backend
-pub fn create_backend(
-    aec_enabled: Arc<Mutex<bool>>,
-    recording_mode: Arc<Mutex<RecordingMode>>,
-) -> Result<Box<dyn AudioBackend>, String> {
-    let backend = CoreAudioBackend::new(aec_enabled, recording_mode)?;
+pub fn create_backend(controls: Arc<MixerControls>) -> Result<Box<dyn AudioBackend>, FlowSttError> {
+    let backend = CoreAudioBackend::new(controls)?;
     Ok(Box::new(backend))
 }
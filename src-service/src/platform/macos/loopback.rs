@@ -0,0 +1,106 @@
+//! Virtual loopback drivers as a system audio fallback for macOS
+//!
+//! ScreenCaptureKit only captures audio on macOS 13 and later, and only with
+//! Screen Recording permission. Without it, system audio can still be captured
+//! from a virtual loopback driver such as BlackHole: the user routes output to
+//! the driver (usually through a Multi-Output Device that also includes their
+//! speakers) and the driver's input side is captured like a microphone.
+//!
+//! Installed drivers are recognized by name and listed as system sources, so
+//! echo cancellation treats them as the far end.
+
+use coreaudio::audio_unit::macos_helpers::{
+    get_audio_device_ids, get_audio_device_supports_scope, get_device_name,
+};
+use coreaudio::audio_unit::Scope;
+use flowstt_common::{AudioDevice, AudioSourceType, Permission, SystemAudioSupport};
+
+use crate::permissions;
+use crate::platform::macos::screencapturekit;
+
+/// Device ID prefix for loopback driver sources, followed by the CoreAudio
+/// device ID
+pub const DEVICE_PREFIX: &str = "loopback:";
+
+/// Names of virtual loopback drivers, matched case-insensitively against
+/// the start of device names
+const DRIVERS: &[&str] = &["BlackHole", "Soundflower", "VB-Cable", "Loopback Audio"];
+
+/// Where to get a loopback driver when none is installed
+const DRIVER_URL: &str = "https://existential.audio/blackhole/";
+
+/// Whether a device name belongs to a known loopback driver.
+fn is_driver(name: &str) -> bool {
+    DRIVERS.iter().any(|driver| {
+        name.get(..driver.len())
+            .is_some_and(|prefix| prefix.eq_ignore_ascii_case(driver))
+    })
+}
+
+/// CoreAudio device ID of a loopback source ID, if it is one.
+pub fn device_id(source_id: &str) -> Option<&str> {
+    source_id.strip_prefix(DEVICE_PREFIX)
+}
+
+/// Installed loopback drivers, as system sources.
+pub fn enumerate_devices() -> Vec<AudioDevice> {
+    let device_ids = match get_audio_device_ids() {
+        Ok(ids) => ids,
+        Err(e) => {
+            tracing::warn!(
+                "CoreAudio: Failed to list devices for loopback drivers: {:?}",
+                e
+            );
+            return Vec::new();
+        }
+    };
+
+    device_ids
+        .into_iter()
+        .filter(|&id| get_audio_device_supports_scope(id, Scope::Input).unwrap_or(false))
+        .filter_map(|id| {
+            let name = get_device_name(id).ok()?;
+            is_driver(&name).then(|| AudioDevice {
+                id: format!("{}{}", DEVICE_PREFIX, id),
+                name: format!("{} (loopback)", name),
+                source_type: AudioSourceType::System,
+            })
+        })
+        .collect()
+}
+
+/// How system audio can be captured, with setup guidance when
+/// ScreenCaptureKit can't be used.
+pub fn support() -> SystemAudioSupport {
+    let native_error = if !screencapturekit::is_available() {
+        Some("System audio capture with ScreenCaptureKit needs macOS 13 or later".to_string())
+    } else {
+        permissions::require(Permission::ScreenRecording)
+            .err()
+            .map(|e| e.to_string())
+    };
+    let loopback_devices = enumerate_devices();
+
+    let setup = match (&native_error, loopback_devices.first()) {
+        (None, _) => None,
+        (Some(_), Some(device)) => Some(format!(
+            "Capture system audio from {}: in Audio MIDI Setup, create a Multi-Output \
+             Device with your speakers and the loopback driver, and select it as the \
+             sound output",
+            device.name
+        )),
+        (Some(_), None) => Some(format!(
+            "Install a loopback driver such as BlackHole ({}), then create a Multi-Output \
+             Device with your speakers and the driver in Audio MIDI Setup and select it as \
+             the sound output; the driver is then listed as a system source",
+            DRIVER_URL
+        )),
+    };
+
+    SystemAudioSupport {
+        native: native_error.is_none(),
+        native_error,
+        loopback_devices,
+        setup,
+    }
+}
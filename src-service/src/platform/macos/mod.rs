@@ -1,33 +1,41 @@
 //! macOS audio backend using CoreAudio and ScreenCaptureKit.
 
+mod aggregate;
 mod coreaudio;
+mod loopback;
 pub mod screencapturekit;
 
+use super::mixer::MixerControls;
 use super::AudioBackend;
-use flowstt_common::RecordingMode;
-use std::sync::{Arc, Mutex, OnceLock};
+use flowstt_common::FlowSttError;
+use std::sync::{Arc, OnceLock};
 
 /// Global backend instance
 static BACKEND: OnceLock<Box<dyn AudioBackend>> = OnceLock::new();
 
 /// Initialize the macOS audio backend.
-pub fn init() -> Result<(), String> {
+pub fn init() -> Result<(), FlowSttError> {
     tracing::info!("Initializing macOS CoreAudio audio backend");
 
     // Create shared state for AEC and recording mode
-    let aec_enabled = Arc::new(Mutex::new(false));
-    let recording_mode = Arc::new(Mutex::new(RecordingMode::default()));
+    let controls = Arc::new(MixerControls::default());
 
-    let backend = coreaudio::create_backend(aec_enabled, recording_mode)?;
+    let backend = coreaudio::create_backend(controls)?;
 
     BACKEND
         .set(backend)
-        .map_err(|_| "Backend already initialized".to_string())?;
+        .map_err(|_| FlowSttError::Other("Backend already initialized".to_string()))?;
 
     tracing::info!("macOS CoreAudio audio backend initialized");
     Ok(())
 }
 
+/// How system audio can be captured, with loopback driver setup guidance
+/// when ScreenCaptureKit can't be used.
+pub fn system_audio_support() -> flowstt_common::SystemAudioSupport {
+    loopback::support()
+}
+
 /// Get the macOS audio backend.
 pub fn get_backend() -> Option<&'static dyn AudioBackend> {
     BACKEND.get().map(|b| b.as_ref())
@@ -1,15 +1,18 @@
 //! ScreenCaptureKit system audio capture for macOS
 //!
 //! This module provides system audio capture functionality using Apple's ScreenCaptureKit API.
-//! ScreenCaptureKit requires macOS 12.3+ and Screen Recording permission.
+//! Capturing audio with ScreenCaptureKit requires macOS 13+ and Screen Recording
+//! permission; without them, `loopback` drivers stand in.
 //!
 //! Key features:
-//! - System audio enumeration (lists available audio outputs)
+//! - System audio enumeration: the whole system mix plus each running
+//!   application as its own source
 //! - Audio-only capture (no video overhead minimized)
 //! - Excludes app's own audio to prevent feedback
 //! - Converts audio to f32 stereo at 48kHz
 
 use core_foundation::runloop::{kCFRunLoopDefaultMode, CFRunLoop};
+use flowstt_common::FlowSttError;
 use screencapturekit_sys::{
     cm_sample_buffer_ref::CMSampleBufferRef,
     content_filter::{UnsafeContentFilter, UnsafeInitParams},
@@ -26,6 +29,9 @@ use std::sync::mpsc;
 use std::sync::{Arc, Mutex};
 use std::thread::{self, JoinHandle};
 
+use crate::platform::clock;
+use crate::platform::mixer::report_source_format;
+
 /// Target sample rate for output (matches encoder expectations)
 const TARGET_SAMPLE_RATE: u32 = 48000;
 
@@ -35,6 +41,12 @@ const TARGET_CHANNELS: u32 = 2;
 /// Audio output type constant (matches SCStreamOutputType)
 const SC_STREAM_OUTPUT_TYPE_AUDIO: u8 = 1;
 
+/// Device ID for the whole system audio mix
+const SYSTEM_AUDIO_ID: &str = "system-audio";
+
+/// Device ID prefix for a single application's audio, followed by its bundle ID
+const APP_AUDIO_PREFIX: &str = "app:";
+
 /// System audio device representation
 #[derive(Debug, Clone)]
 pub struct SystemAudioDevice {
@@ -47,9 +59,12 @@ pub struct SCKAudioSamples {
     pub samples: Vec<f32>,
     #[allow(dead_code)]
     pub channels: u16,
+    /// Host clock time of the first frame, in nanoseconds, taken on arrival
+    pub timestamp: u64,
 }
 
-/// Check if system audio capture is available (macOS 12.3+)
+/// Check if system audio capture is available (macOS 13+; ScreenCaptureKit
+/// itself arrived in 12.3, but without audio)
 pub fn is_available() -> bool {
     use std::process::Command;
 
@@ -58,21 +73,15 @@ pub fn is_available() -> bool {
     match output {
         Ok(output) => {
             let version_str = String::from_utf8_lossy(&output.stdout);
-            let parts: Vec<&str> = version_str.trim().split('.').collect();
-
-            if parts.len() >= 2 {
-                let major: u32 = parts[0].parse().unwrap_or(0);
-                let minor: u32 = parts[1].parse().unwrap_or(0);
-
-                // ScreenCaptureKit requires macOS 12.3+
-                if major > 12 {
-                    return true;
-                }
-                if major == 12 && minor >= 3 {
-                    return true;
-                }
-            }
-            false
+            let major: u32 = version_str
+                .trim()
+                .split('.')
+                .next()
+                .and_then(|major| major.parse().ok())
+                .unwrap_or(0);
+
+            // Audio capture requires macOS 13+
+            major >= 13
         }
         Err(_) => {
             // If we can't determine the version, assume it's available
@@ -101,23 +110,58 @@ pub fn request_permission() {
 }
 
 /// Enumerate available system audio devices
-pub fn enumerate_system_devices() -> Result<Vec<SystemAudioDevice>, String> {
+pub fn enumerate_system_devices() -> Result<Vec<SystemAudioDevice>, FlowSttError> {
     if !is_available() {
         return Ok(Vec::new());
     }
 
-    // Return a single "System Audio" device
-    // ScreenCaptureKit captures all system audio, not individual outputs
-    Ok(vec![SystemAudioDevice {
-        id: "system-audio".to_string(),
+    // ScreenCaptureKit captures all system audio, not individual outputs,
+    // but it can filter the mix down to one application
+    let mut devices = vec![SystemAudioDevice {
+        id: SYSTEM_AUDIO_ID.to_string(),
         name: "System Audio".to_string(),
-    }])
+    }];
+
+    // Listing applications needs Screen Recording permission; don't prompt
+    // for it just to enumerate devices
+    if !check_permission() {
+        return Ok(devices);
+    }
+
+    let content = UnsafeSCShareableContent::get().map_err(|e| {
+        FlowSttError::BackendUnavailable(format!("Failed to get shareable content: {}", e))
+    })?;
+    let own_pid = std::process::id() as i32;
+    let mut apps: Vec<SystemAudioDevice> = Vec::new();
+    for app in content.applications() {
+        if app.get_process_id() == own_pid {
+            continue;
+        }
+        let (Some(bundle_id), Some(name)) =
+            (app.get_bundle_identifier(), app.get_application_name())
+        else {
+            continue;
+        };
+        let id = format!("{}{}", APP_AUDIO_PREFIX, bundle_id);
+        if name.is_empty() || apps.iter().any(|d| d.id == id) {
+            continue;
+        }
+        apps.push(SystemAudioDevice {
+            id,
+            name: format!("{} (App Audio)", name),
+        });
+    }
+    apps.sort_by_key(|d| d.name.to_lowercase());
+    devices.extend(apps);
+
+    Ok(devices)
 }
 
 /// Commands for the ScreenCaptureKit thread
 enum SCKCommand {
     Start {
-        result_tx: mpsc::Sender<Result<(), String>>,
+        device_id: String,
+        result_tx: mpsc::Sender<Result<(), FlowSttError>>,
     },
     Stop,
     Shutdown,
@@ -136,9 +180,11 @@ pub struct SCKAudioCapture {
 
 impl SCKAudioCapture {
     /// Create a new system audio capture
-    pub fn new() -> Result<Self, String> {
+    pub fn new() -> Result<Self, FlowSttError> {
         if !is_available() {
-            return Err("System audio capture is not available (requires macOS 12.3+)".to_string());
+            return Err(FlowSttError::Unsupported(
+                "System audio capture is not available (requires macOS 12.3+)".to_string(),
+            ));
         }
 
         tracing::info!("System Audio: Using ScreenCaptureKit (macOS 12.3+)");
@@ -151,11 +197,11 @@ impl SCKAudioCapture {
             std::thread::sleep(std::time::Duration::from_millis(100));
 
             if !check_permission() {
-                return Err(
+                return Err(FlowSttError::PermissionDenied(
                     "Screen Recording permission is required for system audio capture. \
                     Please enable it in System Settings > Privacy & Security > Screen Recording."
                         .to_string(),
-                );
+                ));
             }
         }
 
@@ -177,8 +223,9 @@ impl SCKAudioCapture {
         })
     }
 
-    /// Start capturing system audio
-    pub fn start(&self) -> Result<(), String> {
+    /// Start capturing the system audio device `device_id`: the whole
+    /// system mix or a single application.
+    pub fn start(&self, device_id: &str) -> Result<(), FlowSttError> {
         if self.is_capturing.load(Ordering::SeqCst) {
             return Ok(());
         }
@@ -186,24 +233,29 @@ impl SCKAudioCapture {
         let (result_tx, result_rx) = mpsc::channel();
 
         self.cmd_tx
-            .send(SCKCommand::Start { result_tx })
-            .map_err(|_| "Failed to send start command".to_string())?;
+            .send(SCKCommand::Start {
+                device_id: device_id.to_string(),
+                result_tx,
+            })
+            .map_err(|_| FlowSttError::Capture("Failed to send start command".to_string()))?;
 
         match result_rx.recv_timeout(std::time::Duration::from_secs(10)) {
             Ok(result) => result,
-            Err(_) => Err("Timeout starting system audio capture".to_string()),
+            Err(_) => Err(FlowSttError::Capture(
+                "Timeout starting system audio capture".to_string(),
+            )),
         }
     }
 
     /// Stop capturing system audio
-    pub fn stop(&self) -> Result<(), String> {
+    pub fn stop(&self) -> Result<(), FlowSttError> {
         if !self.is_capturing.load(Ordering::SeqCst) {
             return Ok(());
         }
 
         self.cmd_tx
             .send(SCKCommand::Stop)
-            .map_err(|_| "Failed to send stop command".to_string())?;
+            .map_err(|_| FlowSttError::Capture("Failed to send stop command".to_string()))?;
 
         // Wait briefly for stop to take effect
         std::thread::sleep(std::time::Duration::from_millis(100));
@@ -264,14 +316,17 @@ fn run_sck_thread(
 
         // Check for commands (non-blocking)
         match cmd_rx.try_recv() {
-            Ok(SCKCommand::Start { result_tx }) => {
+            Ok(SCKCommand::Start {
+                device_id,
+                result_tx,
+            }) => {
                 // Stop any existing capture
                 if let Some(state) = capture_state.take() {
                     drop(state);
                 }
 
                 // Start new capture
-                match start_capture(audio_tx.clone()) {
+                match start_capture(audio_tx.clone(), &device_id) {
                     Ok(state) => {
                         capture_state = Some(state);
                         is_capturing.store(true, Ordering::SeqCst);
@@ -325,6 +380,8 @@ impl UnsafeSCStreamError for AudioCaptureErrorHandler {
 struct AudioOutputHandler {
     tx: mpsc::Sender<SCKAudioSamples>,
     stop_flag: Arc<AtomicBool>,
+    /// Whether the stream format has been reported for this capture
+    format_reported: AtomicBool,
 }
 
 impl UnsafeSCStreamOutput for AudioOutputHandler {
@@ -367,6 +424,10 @@ impl UnsafeSCStreamOutput for AudioOutputHandler {
         let is_non_interleaved = (asbd.format_flags & 32) != 0;
         let channel_count = asbd.channels_per_frame as usize;
 
+        if !self.format_reported.swap(true, Ordering::Relaxed) {
+            report_source_format(true, sample_rate, channel_count as u16);
+        }
+
         // Log first sample's format info for debugging
         static LOGGED_FORMAT: std::sync::atomic::AtomicBool =
             std::sync::atomic::AtomicBool::new(false);
@@ -449,6 +510,7 @@ impl UnsafeSCStreamOutput for AudioOutputHandler {
         if interleaved_samples.is_empty() {
             return;
         }
+        let timestamp = clock::before_now(interleaved_samples.len() / 2, sample_rate);
 
         // Resample to target rate if needed
         let final_samples = if sample_rate != TARGET_SAMPLE_RATE {
@@ -461,6 +523,7 @@ impl UnsafeSCStreamOutput for AudioOutputHandler {
         let _ = self.tx.send(SCKAudioSamples {
             samples: final_samples,
             channels: TARGET_CHANNELS as u16,
+            timestamp,
         });
     }
 }
@@ -517,12 +580,15 @@ impl Drop for SCKCaptureState {
 }
 
 /// Start a ScreenCaptureKit capture session
-fn start_capture(audio_tx: mpsc::Sender<SCKAudioSamples>) -> Result<SCKCaptureState, String> {
+fn start_capture(
+    audio_tx: mpsc::Sender<SCKAudioSamples>,
+    device_id: &str,
+) -> Result<SCKCaptureState, FlowSttError> {
     tracing::info!("ScreenCaptureKit: Starting capture - getting shareable content...");
 
     // Get shareable content
     let content = UnsafeSCShareableContent::get()
-        .map_err(|e| format!("Failed to get shareable content: {}", e))?;
+        .map_err(|e| FlowSttError::Capture(format!("Failed to get shareable content: {}", e)))?;
 
     tracing::info!("ScreenCaptureKit: Got shareable content, looking for display...");
 
@@ -531,7 +597,7 @@ fn start_capture(audio_tx: mpsc::Sender<SCKAudioSamples>) -> Result<SCKCaptureSt
         .displays()
         .into_iter()
         .next()
-        .ok_or_else(|| "No display found".to_string())?;
+        .ok_or_else(|| FlowSttError::Capture("No display found".to_string()))?;
 
     let display_width = display.get_width();
     let display_height = display.get_height();
@@ -541,9 +607,26 @@ fn start_capture(audio_tx: mpsc::Sender<SCKAudioSamples>) -> Result<SCKCaptureSt
         display_height
     );
 
-    // Create content filter for the display
+    // Create content filter for the display, or for one application on it
     tracing::debug!("ScreenCaptureKit: Creating content filter...");
-    let filter = UnsafeContentFilter::init(UnsafeInitParams::Display(display));
+    let filter = match device_id.strip_prefix(APP_AUDIO_PREFIX) {
+        Some(bundle_id) => {
+            let app = content
+                .applications()
+                .into_iter()
+                .find(|app| app.get_bundle_identifier().as_deref() == Some(bundle_id))
+                .ok_or_else(|| FlowSttError::DeviceNotFound(device_id.to_string()))?;
+            tracing::info!("ScreenCaptureKit: Capturing audio from {} only", bundle_id);
+            UnsafeContentFilter::init(
+                UnsafeInitParams::DisplayIncludingApplicationsExceptingWindows(
+                    display,
+                    vec![app],
+                    Vec::new(),
+                ),
+            )
+        }
+        None => UnsafeContentFilter::init(UnsafeInitParams::Display(display)),
+    };
 
     // Configure stream with audio enabled
     // Use minimal video settings since we only want audio
@@ -572,6 +655,7 @@ fn start_capture(audio_tx: mpsc::Sender<SCKAudioSamples>) -> Result<SCKCaptureSt
     let handler = AudioOutputHandler {
         tx: audio_tx,
         stop_flag: stop_flag.clone(),
+        format_reported: AtomicBool::new(false),
     };
     stream.add_stream_output(handler, SC_STREAM_OUTPUT_TYPE_AUDIO);
 
@@ -579,7 +663,7 @@ fn start_capture(audio_tx: mpsc::Sender<SCKAudioSamples>) -> Result<SCKCaptureSt
     tracing::debug!("ScreenCaptureKit: Starting capture...");
     stream
         .start_capture()
-        .map_err(|e| format!("Failed to start capture: {}", e))?;
+        .map_err(|e| FlowSttError::Capture(format!("Failed to start capture: {}", e)))?;
 
     tracing::info!("ScreenCaptureKit: Audio capture started successfully");
 
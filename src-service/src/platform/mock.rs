@@ -0,0 +1,409 @@
+//! Mock audio backend that plays WAV files back as capture devices.
+//!
+//! Every WAV file in the fixtures directory is listed as both an input and a
+//! system device, so the whole pipeline (mixer, speech detection,
+//! transcription and events) can run without audio hardware: in CI, or
+//! locally to replay a recording that shows a problem. Files are converted to
+//! 48kHz stereo when capture starts and fed to the mixer in 10ms chunks at
+//! `speed` times real time. A file that has ended delivers silence, like a
+//! device gone quiet, so speech at its end still ends; with `looped` it
+//! starts over instead.
+//!
+//! Select it with `"backend": "mock"` and an `audio.mock` section in the
+//! config file, or by pointing `FLOWSTT_MOCK_AUDIO` at a fixtures directory.
+
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use flowstt_common::{AudioDevice, AudioSourceType, FlowSttError, RecordingMode};
+
+use super::backend::{AudioBackend, AudioData};
+use super::clock::frames_to_ns;
+use super::mixer::{
+    downmix_channels, mixed_queue, mono_to_stereo, report_capture_latency, report_source_format,
+    selected_channels, AudioMixer, MixedReceiver, MixedSender, MixerControls, Resampler,
+    MIXER_SAMPLE_RATE,
+};
+use crate::audio::read_wav;
+use crate::config::{AudioBackendKind, AudioConfig, MockAudioConfig};
+
+/// Environment variable selecting the mock backend, set to a fixtures directory
+pub const FIXTURES_ENV: &str = "FLOWSTT_MOCK_AUDIO";
+
+/// Environment variable overriding the playback speed
+pub const SPEED_ENV: &str = "FLOWSTT_MOCK_AUDIO_SPEED";
+
+/// Frames fed to the mixer per chunk (10ms at 48kHz)
+const CHUNK_FRAMES: usize = 480;
+
+/// Real time one chunk covers
+const CHUNK_DURATION: Duration = Duration::from_millis(10);
+
+/// Global backend instance
+static BACKEND: OnceLock<Box<dyn AudioBackend>> = OnceLock::new();
+
+/// Whether the mock backend is selected, by the config or `FLOWSTT_MOCK_AUDIO`.
+pub fn is_selected(config: &AudioConfig) -> bool {
+    config.backend == AudioBackendKind::Mock || std::env::var_os(FIXTURES_ENV).is_some()
+}
+
+/// Initialize the mock backend in place of the native one.
+pub fn init(config: &AudioConfig) -> Result<(), FlowSttError> {
+    let settings = settings(config)?;
+    tracing::info!(
+        "Initializing mock audio backend (fixtures={}, speed={}x)",
+        settings.fixtures_dir.display(),
+        settings.speed
+    );
+    let backend = MockAudioBackend::new(settings)?;
+
+    BACKEND
+        .set(Box::new(backend))
+        .map_err(|_| FlowSttError::Other("Backend already initialized".to_string()))
+}
+
+/// Get the mock backend, if it was initialized.
+pub fn get_backend() -> Option<&'static dyn AudioBackend> {
+    BACKEND.get().map(|b| b.as_ref())
+}
+
+/// The `audio.mock` settings with the environment applied over them.
+fn settings(config: &AudioConfig) -> Result<MockAudioConfig, FlowSttError> {
+    let mut settings = config.mock.clone().unwrap_or_default();
+    if let Some(dir) = std::env::var_os(FIXTURES_ENV) {
+        settings.fixtures_dir = PathBuf::from(dir);
+    }
+    if let Ok(speed) = std::env::var(SPEED_ENV) {
+        settings.speed = speed.parse().map_err(|_| {
+            FlowSttError::BackendUnavailable(format!(
+                "{} must be a whole number, got '{}'",
+                SPEED_ENV, speed
+            ))
+        })?;
+    }
+    if settings.fixtures_dir.as_os_str().is_empty() {
+        return Err(FlowSttError::BackendUnavailable(format!(
+            "The mock audio backend needs audio.mock.fixtures_dir or {}",
+            FIXTURES_ENV
+        )));
+    }
+    settings.speed = settings.speed.max(1);
+    Ok(settings)
+}
+
+/// Commands sent to the playback thread
+enum PlaybackCommand {
+    Start(Vec<Track>),
+    Stop,
+    Shutdown,
+}
+
+/// Mock audio backend playing WAV fixtures
+pub struct MockAudioBackend {
+    /// Fixtures directory, speed and looping
+    settings: MockAudioConfig,
+    /// Channel to send commands to the playback thread
+    cmd_tx: mpsc::Sender<PlaybackCommand>,
+    /// Queue of mixed audio from the playback thread (in a Mutex for Sync;
+    /// only the processing thread locks it, so it is never contended)
+    audio_rx: Mutex<MixedReceiver>,
+    /// Playback thread handle
+    _thread_handle: JoinHandle<()>,
+    /// AEC flag and recording mode (shared with mixer)
+    controls: Arc<MixerControls>,
+}
+
+impl MockAudioBackend {
+    /// Create a mock backend playing the WAV files in `settings.fixtures_dir`
+    pub fn new(settings: MockAudioConfig) -> Result<Self, FlowSttError> {
+        if !settings.fixtures_dir.is_dir() {
+            return Err(FlowSttError::BackendUnavailable(format!(
+                "Mock audio fixtures directory {} not found",
+                settings.fixtures_dir.display()
+            )));
+        }
+
+        let (cmd_tx, cmd_rx) = mpsc::channel();
+        let (audio_tx, audio_rx) = mixed_queue();
+        let controls = Arc::new(MixerControls::default());
+
+        let controls_clone = Arc::clone(&controls);
+        let chunk_interval = CHUNK_DURATION / settings.speed.max(1);
+        let looped = settings.looped;
+        let thread_handle = thread::spawn(move || {
+            run_playback_thread(cmd_rx, audio_tx, controls_clone, chunk_interval, looped);
+        });
+
+        Ok(Self {
+            settings,
+            cmd_tx,
+            audio_rx: Mutex::new(audio_rx),
+            _thread_handle: thread_handle,
+            controls,
+        })
+    }
+
+    /// A device for each fixture
+    fn devices(&self, source_type: AudioSourceType) -> Vec<AudioDevice> {
+        list_fixtures(&self.settings.fixtures_dir)
+            .into_iter()
+            .filter_map(|path| {
+                Some(AudioDevice {
+                    id: path.file_name()?.to_string_lossy().to_string(),
+                    name: path.file_stem()?.to_string_lossy().to_string(),
+                    source_type,
+                })
+            })
+            .collect()
+    }
+}
+
+impl Drop for MockAudioBackend {
+    fn drop(&mut self) {
+        let _ = self.cmd_tx.send(PlaybackCommand::Shutdown);
+    }
+}
+
+impl AudioBackend for MockAudioBackend {
+    fn sample_rate(&self) -> u32 {
+        MIXER_SAMPLE_RATE
+    }
+
+    fn list_input_devices(&self) -> Vec<AudioDevice> {
+        self.devices(AudioSourceType::Input)
+    }
+
+    fn list_system_devices(&self) -> Vec<AudioDevice> {
+        self.devices(AudioSourceType::System)
+    }
+
+    fn start_capture_sources(
+        &self,
+        source1_id: Option<String>,
+        source2_id: Option<String>,
+    ) -> Result<(), FlowSttError> {
+        let fixtures = list_fixtures(&self.settings.fixtures_dir);
+        let tracks = [source1_id, source2_id]
+            .into_iter()
+            .flatten()
+            .map(|id| {
+                let path = fixtures
+                    .iter()
+                    .find(|path| path.file_name().is_some_and(|name| name == id.as_str()))
+                    .ok_or_else(|| FlowSttError::DeviceNotFound(id.clone()))?;
+                Track::load(path, &id)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        self.cmd_tx
+            .send(PlaybackCommand::Start(tracks))
+            .map_err(|e| FlowSttError::Capture(format!("Failed to send start command: {}", e)))
+    }
+
+    fn stop_capture(&self) -> Result<(), FlowSttError> {
+        self.cmd_tx
+            .send(PlaybackCommand::Stop)
+            .map_err(|e| FlowSttError::Capture(format!("Failed to send stop command: {}", e)))
+    }
+
+    fn recv_timeout(&self, timeout: Duration) -> Option<AudioData> {
+        self.audio_rx
+            .lock()
+            .unwrap()
+            .recv_timeout(timeout)
+            .map(|samples| AudioData {
+                samples: samples.samples,
+                channels: samples.channels,
+                sample_rate: MIXER_SAMPLE_RATE,
+                sources: samples.sources,
+            })
+    }
+
+    fn set_aec_enabled(&self, enabled: bool) {
+        self.controls.set_aec_enabled(enabled);
+    }
+
+    fn set_recording_mode(&self, mode: RecordingMode) {
+        self.controls.set_recording_mode(mode);
+    }
+}
+
+/// WAV files in `dir`, sorted by name
+fn list_fixtures(dir: &Path) -> Vec<PathBuf> {
+    let mut fixtures: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| {
+                    path.extension()
+                        .is_some_and(|ext| ext.eq_ignore_ascii_case("wav"))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    fixtures.sort();
+    fixtures
+}
+
+/// A fixture converted for the mixer, and how far it has played
+struct Track {
+    /// 48kHz interleaved stereo samples
+    samples: Vec<f32>,
+    /// Next sample to play
+    position: usize,
+    /// Sample rate of the file
+    native_rate: u32,
+    /// Channels in the file
+    native_channels: u16,
+}
+
+impl Track {
+    /// Read a WAV file and convert it to 48kHz stereo, keeping only the
+    /// channels selected for `device_id`
+    fn load(path: &Path, device_id: &str) -> Result<Self, FlowSttError> {
+        let wav = read_wav(path).map_err(FlowSttError::Capture)?;
+        let selected = selected_channels(device_id, wav.channels as usize);
+        let stereo = match (selected, wav.channels) {
+            (Some(selected), n) => downmix_channels(&wav.samples, n as usize, &selected),
+            (None, 1) => mono_to_stereo(&wav.samples),
+            (None, 2) => wav.samples,
+            // Only the first two channels of multichannel files are played
+            (None, n) => wav
+                .samples
+                .chunks_exact(n as usize)
+                .flat_map(|frame| [frame[0], frame[1]])
+                .collect(),
+        };
+        let samples = if wav.sample_rate == MIXER_SAMPLE_RATE {
+            stereo
+        } else {
+            Resampler::new(wav.sample_rate, MIXER_SAMPLE_RATE).process(&stereo, 2)
+        };
+
+        Ok(Self {
+            samples,
+            position: 0,
+            native_rate: wav.sample_rate,
+            native_channels: wav.channels,
+        })
+    }
+
+    /// The next 10ms of the file, padded with silence past its end or
+    /// wrapped around when `looped`
+    fn next_chunk(&mut self, looped: bool) -> Vec<f32> {
+        let len = CHUNK_FRAMES * 2;
+        let mut chunk = Vec::with_capacity(len);
+        while chunk.len() < len {
+            if self.position >= self.samples.len() {
+                if !looped || self.samples.is_empty() {
+                    break;
+                }
+                self.position = 0;
+            }
+            let take = (len - chunk.len()).min(self.samples.len() - self.position);
+            chunk.extend_from_slice(&self.samples[self.position..self.position + take]);
+            self.position += take;
+        }
+        chunk.resize(len, 0.0);
+        chunk
+    }
+}
+
+/// Run the playback thread, which owns the mixer and paces the tracks
+fn run_playback_thread(
+    cmd_rx: mpsc::Receiver<PlaybackCommand>,
+    audio_tx: MixedSender,
+    controls: Arc<MixerControls>,
+    chunk_interval: Duration,
+    looped: bool,
+) {
+    tracing::info!("Mock: Playback thread started");
+
+    let mut mixer = AudioMixer::new("Mock", audio_tx, controls);
+    let mut tracks: Vec<Track> = Vec::new();
+    let mut started = Instant::now();
+    let mut chunks_played: u32 = 0;
+
+    loop {
+        // Sleep until the next chunk is due, waking early for commands
+        let timeout = if tracks.is_empty() {
+            Duration::from_secs(1)
+        } else {
+            (started + chunk_interval * chunks_played).saturating_duration_since(Instant::now())
+        };
+
+        match cmd_rx.recv_timeout(timeout) {
+            Ok(PlaybackCommand::Start(new_tracks)) => {
+                mixer.set_num_streams(new_tracks.len());
+                for (i, track) in new_tracks.iter().enumerate() {
+                    let is_render = i == 1;
+                    report_source_format(is_render, track.native_rate, track.native_channels);
+                    report_capture_latency(is_render, CHUNK_FRAMES, MIXER_SAMPLE_RATE);
+                }
+                tracing::info!("Mock: Started playback of {} sources", new_tracks.len());
+                tracks = new_tracks;
+                started = Instant::now();
+                chunks_played = 0;
+            }
+            Ok(PlaybackCommand::Stop) => {
+                if !tracks.is_empty() {
+                    tracing::info!("Mock: Stopping playback");
+                    tracks.clear();
+                }
+                mixer.set_num_streams(0);
+            }
+            Ok(PlaybackCommand::Shutdown) | Err(mpsc::RecvTimeoutError::Disconnected) => {
+                break;
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                if tracks.is_empty() {
+                    continue;
+                }
+                // Both sources are stamped on the playback timeline, so they
+                // never drift apart
+                let timestamp =
+                    frames_to_ns(chunks_played as usize * CHUNK_FRAMES, MIXER_SAMPLE_RATE);
+                // System audio goes first so the AEC sees it before its echo
+                for (i, track) in tracks.iter_mut().enumerate().rev() {
+                    let chunk = track.next_chunk(looped);
+                    mixer.push_samples(&chunk, i == 1, timestamp);
+                }
+                chunks_played += 1;
+            }
+        }
+    }
+
+    tracing::info!("Mock: Playback thread stopped");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_track_pads_or_loops_at_end() {
+        let track = || Track {
+            samples: (0..CHUNK_FRAMES * 3).map(|i| i as f32 + 1.0).collect(),
+            position: 0,
+            native_rate: MIXER_SAMPLE_RATE,
+            native_channels: 2,
+        };
+
+        let mut padded = track();
+        assert_eq!(padded.next_chunk(false)[0], 1.0);
+        let end = padded.next_chunk(false);
+        assert_eq!(end.len(), CHUNK_FRAMES * 2);
+        assert_eq!(end[CHUNK_FRAMES - 1], (CHUNK_FRAMES * 3) as f32);
+        assert_eq!(end[CHUNK_FRAMES], 0.0);
+        assert!(padded.next_chunk(false).iter().all(|&s| s == 0.0));
+
+        let mut looped = track();
+        looped.next_chunk(true);
+        let wrapped = looped.next_chunk(true);
+        assert_eq!(wrapped[CHUNK_FRAMES], 1.0);
+    }
+}
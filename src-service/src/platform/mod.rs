@@ -1,9 +1,13 @@
 //! Platform-specific audio backends.
 //!
 //! This module provides audio capture functionality through platform-native APIs:
-//! - Linux: PipeWire
+//! - Linux: PipeWire, PulseAudio or ALSA
 //! - Windows: WASAPI
 //! - macOS: CoreAudio + ScreenCaptureKit
+//!
+//! Configured network streams (RTP/UDP) are layered on top of the native backend
+//! as additional input devices. For testing without audio hardware, a mock
+//! backend plays WAV files back in place of the native one.
 
 #[cfg(target_os = "linux")]
 pub mod linux;
@@ -15,34 +19,349 @@ pub mod windows;
 pub mod macos;
 
 mod backend;
+#[cfg(any(target_os = "linux", target_os = "windows", target_os = "macos"))]
+mod clock;
+#[cfg(all(
+    debug_assertions,
+    any(target_os = "linux", target_os = "windows", target_os = "macos")
+))]
+mod fault;
+#[cfg(any(target_os = "linux", target_os = "windows", target_os = "macos"))]
+mod mock;
+#[cfg(any(target_os = "linux", target_os = "windows", target_os = "macos"))]
+mod network;
+#[cfg(any(target_os = "linux", target_os = "windows", target_os = "macos"))]
+mod source_recording;
+
+#[cfg(any(target_os = "linux", target_os = "windows", target_os = "macos"))]
+use flowstt_audio::mixer;
+
+pub use backend::{AudioBackend, AudioData};
+
+use std::time::Duration;
+
+use flowstt_common::FlowSttError;
+
+use crate::config::{AudioBackendKind, AudioConfig, CaptureMode};
+
+/// Initialize the platform-specific audio backend and any network sources.
+///
+/// Network sources remain available even if the native backend fails to start.
+pub fn init_audio_backend(config: &AudioConfig) -> Result<(), FlowSttError> {
+    #[cfg(any(target_os = "linux", target_os = "windows", target_os = "macos"))]
+    let result = if mock::is_selected(config) {
+        mock::init(config)
+    } else {
+        init_native_backend(config.backend)
+    };
+
+    #[cfg(not(any(target_os = "linux", target_os = "windows", target_os = "macos")))]
+    let result = init_native_backend(config.backend);
+
+    #[cfg(any(target_os = "linux", target_os = "windows", target_os = "macos"))]
+    network::init(native_backend(), &config.network_sources);
 
-pub use backend::AudioBackend;
+    result
+}
 
-/// Initialize the platform-specific audio backend.
-pub fn init_audio_backend() -> Result<(), String> {
+/// Initialize the native audio backend.
+///
+/// `kind` selects between the Linux backends; other platforms only have one and
+/// ignore any explicit selection.
+fn init_native_backend(kind: AudioBackendKind) -> Result<(), FlowSttError> {
     #[cfg(target_os = "linux")]
     {
-        linux::init()
+        linux::init(kind)
     }
 
     #[cfg(target_os = "windows")]
     {
+        if kind != AudioBackendKind::Auto {
+            tracing::warn!(
+                "Audio backend {:?} is not available on Windows, using WASAPI",
+                kind
+            );
+        }
         windows::init()
     }
 
     #[cfg(target_os = "macos")]
     {
+        if kind != AudioBackendKind::Auto {
+            tracing::warn!(
+                "Audio backend {:?} is not available on macOS, using CoreAudio",
+                kind
+            );
+        }
         macos::init()
     }
 
     #[cfg(not(any(target_os = "linux", target_os = "windows", target_os = "macos")))]
     {
-        Err("Unsupported platform".to_string())
+        let _ = kind;
+        Err(FlowSttError::BackendUnavailable(
+            "unsupported platform".to_string(),
+        ))
+    }
+}
+
+/// Save each source's raw, pre-AEC capture to separate files in `dir`,
+/// or stop doing so with `None`. Applies from the next capture start.
+pub fn set_source_recording(dir: Option<std::path::PathBuf>) {
+    #[cfg(any(target_os = "linux", target_os = "windows", target_os = "macos"))]
+    mixer::set_source_recording(dir.map(source_recording::to_files));
+
+    #[cfg(not(any(target_os = "linux", target_os = "windows", target_os = "macos")))]
+    let _ = dir;
+}
+
+/// Set the echo cancellation tuning; a running AEC restarts with it.
+pub fn set_aec_settings(settings: flowstt_common::AecSettings) {
+    #[cfg(any(target_os = "linux", target_os = "windows", target_os = "macos"))]
+    mixer::set_aec_settings(settings);
+
+    #[cfg(not(any(target_os = "linux", target_os = "windows", target_os = "macos")))]
+    let _ = settings;
+}
+
+/// Start measuring the echo delay between system audio and the microphone.
+pub fn start_delay_estimation() {
+    #[cfg(any(target_os = "linux", target_os = "windows", target_os = "macos"))]
+    mixer::start_delay_estimation();
+}
+
+/// Stop measuring and return the estimated echo delay in milliseconds.
+pub fn finish_delay_estimation() -> Result<u32, FlowSttError> {
+    #[cfg(any(target_os = "linux", target_os = "windows", target_os = "macos"))]
+    {
+        mixer::finish_delay_estimation()
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "windows", target_os = "macos")))]
+    {
+        Err(FlowSttError::BackendUnavailable(
+            "unsupported platform".to_string(),
+        ))
+    }
+}
+
+/// Take the pre-mix per-source levels published since the last call.
+pub fn take_source_levels() -> Option<flowstt_common::SourceLevels> {
+    #[cfg(any(target_os = "linux", target_os = "windows", target_os = "macos"))]
+    {
+        mixer::take_source_levels()
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "windows", target_os = "macos")))]
+    {
+        None
+    }
+}
+
+/// Latest echo cancellation metrics, if echo cancellation is running.
+pub fn aec_metrics() -> Option<flowstt_common::AecMetrics> {
+    #[cfg(any(target_os = "linux", target_os = "windows", target_os = "macos"))]
+    {
+        mixer::aec_metrics()
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "windows", target_os = "macos")))]
+    {
+        None
+    }
+}
+
+/// Latest clock synchronization metrics, if two sources are being mixed.
+pub fn sync_metrics() -> Option<flowstt_common::SyncMetrics> {
+    #[cfg(any(target_os = "linux", target_os = "windows", target_os = "macos"))]
+    {
+        mixer::sync_metrics()
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "windows", target_os = "macos")))]
+    {
+        None
+    }
+}
+
+/// Buffer latency of each source in the current capture session.
+pub fn capture_latency() -> Vec<flowstt_common::CaptureLatency> {
+    #[cfg(any(target_os = "linux", target_os = "windows", target_os = "macos"))]
+    {
+        mixer::capture_latency()
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "windows", target_os = "macos")))]
+    {
+        Vec::new()
+    }
+}
+
+/// Set the capture buffer duration to request when capture next starts;
+/// `None` keeps each backend's default.
+pub fn set_capture_buffer_ms(ms: Option<u32>) {
+    #[cfg(any(target_os = "linux", target_os = "windows", target_os = "macos"))]
+    mixer::set_capture_buffer_ms(ms);
+
+    #[cfg(not(any(target_os = "linux", target_os = "windows", target_os = "macos")))]
+    let _ = ms;
+}
+
+/// Set the channels captured from multichannel devices, by device ID, when
+/// capture next starts.
+pub fn set_channel_selections(
+    selections: std::collections::BTreeMap<String, flowstt_common::ChannelSelection>,
+) {
+    #[cfg(any(target_os = "linux", target_os = "windows", target_os = "macos"))]
+    mixer::set_channel_selections(selections);
+
+    #[cfg(not(any(target_os = "linux", target_os = "windows", target_os = "macos")))]
+    let _ = selections;
+}
+
+/// Set the sample rate to capture at when capture next starts; 16 kHz
+/// captures single sources as 16 kHz mono where the backend can convert.
+pub fn set_capture_sample_rate(sample_rate: Option<u32>) {
+    #[cfg(any(target_os = "linux", target_os = "windows", target_os = "macos"))]
+    mixer::set_capture_sample_rate(sample_rate);
+
+    #[cfg(not(any(target_os = "linux", target_os = "windows", target_os = "macos")))]
+    let _ = sample_rate;
+}
+
+/// Sample rate and channel count capturing these sources will deliver.
+pub fn capture_format(source1_id: Option<&str>, source2_id: Option<&str>) -> (u32, u16) {
+    match get_backend() {
+        Some(backend) if backend.captures_speech_format(source1_id, source2_id) => {
+            (backend::SPEECH_SAMPLE_RATE, 1)
+        }
+        Some(backend) => (backend.sample_rate(), 2),
+        None => (48000, 2),
+    }
+}
+
+/// Set how input devices are opened when capture next starts. Only WASAPI
+/// has a choice; other backends ignore it.
+pub fn set_capture_mode(mode: CaptureMode) {
+    #[cfg(target_os = "windows")]
+    windows::set_capture_mode(mode);
+
+    #[cfg(not(target_os = "windows"))]
+    let _ = mode;
+}
+
+/// Take the echo cancellation metrics published since the last call.
+pub fn take_aec_metrics() -> Option<flowstt_common::AecMetrics> {
+    #[cfg(any(target_os = "linux", target_os = "windows", target_os = "macos"))]
+    {
+        mixer::take_aec_metrics()
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "windows", target_os = "macos")))]
+    {
+        None
+    }
+}
+
+/// Take the native source formats reported since the last call.
+pub fn take_source_formats() -> Vec<flowstt_common::SourceFormat> {
+    #[cfg(any(target_os = "linux", target_os = "windows", target_os = "macos"))]
+    {
+        mixer::take_source_formats()
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "windows", target_os = "macos")))]
+    {
+        Vec::new()
+    }
+}
+
+/// Inject a capture fault for resilience testing; `device_id` is the device
+/// a disconnect applies to. Only available in debug builds.
+pub fn inject_fault(
+    fault: flowstt_common::AudioFault,
+    device_id: Option<String>,
+) -> Result<(), FlowSttError> {
+    #[cfg(all(
+        debug_assertions,
+        any(target_os = "linux", target_os = "windows", target_os = "macos")
+    ))]
+    {
+        fault::inject(fault, device_id);
+        Ok(())
+    }
+
+    #[cfg(not(all(
+        debug_assertions,
+        any(target_os = "linux", target_os = "windows", target_os = "macos")
+    )))]
+    {
+        let _ = (fault, device_id);
+        Err(FlowSttError::Unsupported(
+            "Fault injection is only available in debug builds".to_string(),
+        ))
     }
 }
 
 /// Get the current audio backend.
 pub fn get_backend() -> Option<&'static dyn AudioBackend> {
+    #[cfg(all(
+        debug_assertions,
+        any(target_os = "linux", target_os = "windows", target_os = "macos")
+    ))]
+    if fault::is_active() {
+        return Some(fault::backend());
+    }
+
+    backend_without_faults()
+}
+
+/// Get the current audio backend, ignoring injected faults.
+fn backend_without_faults() -> Option<&'static dyn AudioBackend> {
+    #[cfg(any(target_os = "linux", target_os = "windows", target_os = "macos"))]
+    if let Some(backend) = network::get_backend() {
+        return Some(backend);
+    }
+
+    native_backend()
+}
+
+/// How system audio can be captured on this machine. On macOS, virtual
+/// loopback drivers stand in when ScreenCaptureKit can't be used.
+pub fn system_audio_support() -> flowstt_common::SystemAudioSupport {
+    #[cfg(target_os = "macos")]
+    if mock::get_backend().is_none() {
+        return macos::system_audio_support();
+    }
+
+    let native = get_backend().is_some_and(|backend| !backend.list_system_devices().is_empty());
+    flowstt_common::SystemAudioSupport {
+        native,
+        native_error: (!native)
+            .then(|| "The audio backend has no system audio sources".to_string()),
+        ..Default::default()
+    }
+}
+
+/// Receive captured audio, waiting up to `timeout` for the backend to deliver
+/// some. Without a backend this just waits out the timeout.
+pub fn recv_audio(timeout: Duration) -> Option<AudioData> {
+    match get_backend() {
+        Some(backend) => backend.recv_timeout(timeout),
+        None => {
+            std::thread::sleep(timeout);
+            None
+        }
+    }
+}
+
+/// Get the native audio backend (or the mock one), without network sources.
+fn native_backend() -> Option<&'static dyn AudioBackend> {
+    #[cfg(any(target_os = "linux", target_os = "windows", target_os = "macos"))]
+    if let Some(backend) = mock::get_backend() {
+        return Some(backend);
+    }
+
     #[cfg(target_os = "linux")]
     {
         linux::get_backend()
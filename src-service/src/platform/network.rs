@@ -0,0 +1,560 @@
+//! Network audio sources.
+//!
+//! Streams configured under `audio.network_sources` are received on a local UDP
+//! port, either as RTP packets or bare datagrams, carrying 16-bit PCM or Opus.
+//! They are listed as input devices alongside the native backend's devices and,
+//! when selected, replace native capture: packets are decoded, converted to
+//! 48kHz stereo and handed to the audio loop like any other capture.
+//!
+//! A network source can't be combined with a second source, since the native
+//! backends only expose their already-mixed output.
+
+use std::net::UdpSocket;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex, OnceLock};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use flowstt_common::{AudioDevice, AudioSourceType, FlowSttError, RecordingMode};
+
+use super::backend::{AudioBackend, AudioData};
+use super::mixer::{mono_to_stereo, report_source_format, Resampler, MIXER_SAMPLE_RATE};
+use crate::config::{NetworkCodec, NetworkProtocol, NetworkSource};
+
+/// Device ID prefix for network sources; the rest is the listen address
+pub const NETWORK_DEVICE_PREFIX: &str = "net:";
+
+/// How often the receive thread checks whether it should stop
+const RECV_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Largest Opus frame (120ms at 48kHz), per channel
+const MAX_OPUS_FRAME: usize = 5760;
+
+/// Fixed RTP header size, before CSRCs and extensions
+const RTP_HEADER_LEN: usize = 12;
+
+/// Global network-aware backend, set only when network sources are configured
+static BACKEND: OnceLock<NetworkBackend> = OnceLock::new();
+
+/// Wrap the native backend with the configured network sources.
+///
+/// Invalid sources are skipped with a warning. Nothing is wrapped when no
+/// sources remain, so the native backend is used directly.
+pub fn init(native: Option<&'static dyn AudioBackend>, sources: &[NetworkSource]) {
+    let sources: Vec<NetworkSource> = sources
+        .iter()
+        .filter(|source| match validate(source) {
+            Ok(()) => true,
+            Err(e) => {
+                tracing::warn!("Ignoring network source {:?}: {}", source.name, e);
+                false
+            }
+        })
+        .cloned()
+        .collect();
+
+    if sources.is_empty() {
+        return;
+    }
+
+    tracing::info!("Network audio sources configured: {}", sources.len());
+    let _ = BACKEND.set(NetworkBackend {
+        native,
+        sources,
+        receiver: Mutex::new(None),
+        receiving: AtomicBool::new(false),
+    });
+}
+
+/// Get the network-aware backend, if network sources are configured.
+pub fn get_backend() -> Option<&'static dyn AudioBackend> {
+    BACKEND.get().map(|b| b as &dyn AudioBackend)
+}
+
+/// Check a source's settings before listing it.
+fn validate(source: &NetworkSource) -> Result<(), String> {
+    if source.name.trim().is_empty() {
+        return Err("name must not be empty".to_string());
+    }
+    if !(1..=2).contains(&source.channels) {
+        return Err(format!("unsupported channel count {}", source.channels));
+    }
+    if source.codec == NetworkCodec::Pcm && !(8000..=192000).contains(&source.sample_rate) {
+        return Err(format!("unsupported sample rate {}", source.sample_rate));
+    }
+    Ok(())
+}
+
+/// Native backend plus network sources
+struct NetworkBackend {
+    /// Native platform backend, if it initialized
+    native: Option<&'static dyn AudioBackend>,
+    /// Configured network sources
+    sources: Vec<NetworkSource>,
+    /// Active network receiver, replacing native capture while set
+    receiver: Mutex<Option<NetworkReceiver>>,
+    /// Whether `receiver` is set, so polling native capture skips its lock
+    receiving: AtomicBool,
+}
+
+impl NetworkBackend {
+    /// Look up the network source for a device ID
+    fn find_source(&self, id: &Option<String>) -> Option<&NetworkSource> {
+        let address = id.as_deref()?.strip_prefix(NETWORK_DEVICE_PREFIX)?;
+        self.sources.iter().find(|s| s.address == address)
+    }
+
+    fn stop_receiver(&self) {
+        self.receiving.store(false, Ordering::SeqCst);
+        if let Some(receiver) = self.receiver.lock().unwrap().take() {
+            tracing::info!("Stopping network source {}", receiver.name);
+        }
+    }
+}
+
+impl AudioBackend for NetworkBackend {
+    fn sample_rate(&self) -> u32 {
+        if self.receiver.lock().unwrap().is_some() {
+            return MIXER_SAMPLE_RATE;
+        }
+        self.native
+            .map(|b| b.sample_rate())
+            .unwrap_or(MIXER_SAMPLE_RATE)
+    }
+
+    fn captures_speech_format(&self, source1_id: Option<&str>, source2_id: Option<&str>) -> bool {
+        let is_network =
+            |id: Option<&str>| id.is_some_and(|id| id.starts_with(NETWORK_DEVICE_PREFIX));
+        if is_network(source1_id) || is_network(source2_id) {
+            return false;
+        }
+        self.native
+            .is_some_and(|b| b.captures_speech_format(source1_id, source2_id))
+    }
+
+    fn list_input_devices(&self) -> Vec<AudioDevice> {
+        let mut devices = self
+            .native
+            .map(|b| b.list_input_devices())
+            .unwrap_or_default();
+        devices.extend(self.sources.iter().map(|source| AudioDevice {
+            id: format!("{}{}", NETWORK_DEVICE_PREFIX, source.address),
+            name: format!("{} (Network)", source.name),
+            source_type: AudioSourceType::Network,
+        }));
+        devices
+    }
+
+    fn list_system_devices(&self) -> Vec<AudioDevice> {
+        self.native
+            .map(|b| b.list_system_devices())
+            .unwrap_or_default()
+    }
+
+    fn start_capture_sources(
+        &self,
+        source1_id: Option<String>,
+        source2_id: Option<String>,
+    ) -> Result<(), FlowSttError> {
+        let network_source = match (self.find_source(&source1_id), self.find_source(&source2_id)) {
+            (None, None) => None,
+            (Some(source), None) if source2_id.is_none() => Some(source),
+            (None, Some(source)) if source1_id.is_none() => Some(source),
+            _ => {
+                return Err(FlowSttError::Capture(
+                    "Network sources can't be combined with another source".to_string(),
+                ))
+            }
+        };
+
+        self.stop_receiver();
+
+        let Some(source) = network_source else {
+            let native = self.native.ok_or_else(|| {
+                FlowSttError::BackendUnavailable("no audio backend was initialized".to_string())
+            })?;
+            return native.start_capture_sources(source1_id, source2_id);
+        };
+
+        if let Some(native) = self.native {
+            let _ = native.stop_capture();
+        }
+        let receiver = NetworkReceiver::start(source.clone())?;
+        *self.receiver.lock().unwrap() = Some(receiver);
+        self.receiving.store(true, Ordering::SeqCst);
+        Ok(())
+    }
+
+    fn stop_capture(&self) -> Result<(), FlowSttError> {
+        self.stop_receiver();
+        match self.native {
+            Some(native) => native.stop_capture(),
+            None => Ok(()),
+        }
+    }
+
+    fn recv_timeout(&self, timeout: Duration) -> Option<AudioData> {
+        if self.receiving.load(Ordering::Relaxed) {
+            // Wait outside the receiver lock so it can be stopped meanwhile
+            let audio_rx = self
+                .receiver
+                .lock()
+                .unwrap()
+                .as_ref()
+                .map(|receiver| Arc::clone(&receiver.audio_rx));
+            if let Some(audio_rx) = audio_rx {
+                return match audio_rx.lock().unwrap().recv_timeout(timeout) {
+                    Ok(data) => Some(data),
+                    Err(mpsc::RecvTimeoutError::Timeout) => None,
+                    Err(mpsc::RecvTimeoutError::Disconnected) => {
+                        // The receive thread failed; wait like an idle source
+                        thread::sleep(timeout);
+                        None
+                    }
+                };
+            }
+        }
+        match self.native {
+            Some(native) => native.recv_timeout(timeout),
+            None => {
+                thread::sleep(timeout);
+                None
+            }
+        }
+    }
+
+    fn set_aec_enabled(&self, enabled: bool) {
+        if let Some(native) = self.native {
+            native.set_aec_enabled(enabled);
+        }
+    }
+
+    fn set_recording_mode(&self, mode: RecordingMode) {
+        if let Some(native) = self.native {
+            native.set_recording_mode(mode);
+        }
+    }
+}
+
+/// A running receive thread for one network source
+struct NetworkReceiver {
+    name: String,
+    /// Decoded 48kHz stereo audio (wrapped in Mutex for Sync)
+    audio_rx: Arc<Mutex<mpsc::Receiver<AudioData>>>,
+    stop_flag: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl NetworkReceiver {
+    /// Bind the source's address and start receiving
+    fn start(source: NetworkSource) -> Result<Self, FlowSttError> {
+        let socket = UdpSocket::bind(&source.address).map_err(|e| {
+            let message = format!("Failed to listen on {}: {}", source.address, e);
+            if e.kind() == std::io::ErrorKind::PermissionDenied {
+                FlowSttError::PermissionDenied(message)
+            } else {
+                FlowSttError::Capture(message)
+            }
+        })?;
+        socket
+            .set_read_timeout(Some(RECV_TIMEOUT))
+            .map_err(|e| FlowSttError::Capture(format!("Failed to configure socket: {}", e)))?;
+        let decoder = PayloadDecoder::new(&source)?;
+
+        tracing::info!(
+            "Receiving network audio for {} on {} ({:?}/{:?}, {} channels)",
+            source.name,
+            source.address,
+            source.protocol,
+            source.codec,
+            source.channels
+        );
+
+        let (audio_tx, audio_rx) = mpsc::channel();
+        let stop_flag = Arc::new(AtomicBool::new(false));
+        let stop_flag_clone = Arc::clone(&stop_flag);
+        let name = source.name.clone();
+
+        let handle = thread::spawn(move || {
+            run_receiver(socket, source, decoder, audio_tx, stop_flag_clone);
+        });
+
+        Ok(Self {
+            name,
+            audio_rx: Arc::new(Mutex::new(audio_rx)),
+            stop_flag,
+            handle: Some(handle),
+        })
+    }
+}
+
+impl Drop for NetworkReceiver {
+    fn drop(&mut self) {
+        self.stop_flag.store(true, Ordering::SeqCst);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Receive, decode and convert packets until stopped
+fn run_receiver(
+    socket: UdpSocket,
+    source: NetworkSource,
+    mut decoder: PayloadDecoder,
+    audio_tx: mpsc::Sender<AudioData>,
+    stop_flag: Arc<AtomicBool>,
+) {
+    let channels = source.channels as usize;
+    let sample_rate = decoder.sample_rate();
+    report_source_format(false, sample_rate, source.channels);
+
+    let mut resampler = if sample_rate != MIXER_SAMPLE_RATE {
+        Some(Resampler::new(sample_rate, MIXER_SAMPLE_RATE))
+    } else {
+        None
+    };
+    let mut sequence = RtpSequence::default();
+    let mut packet = vec![0u8; 65536];
+
+    while !stop_flag.load(Ordering::SeqCst) {
+        let len = match socket.recv(&mut packet) {
+            Ok(len) => len,
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                ) =>
+            {
+                continue;
+            }
+            Err(e) => {
+                tracing::error!("Network source {} receive error: {}", source.name, e);
+                break;
+            }
+        };
+
+        let payload = match source.protocol {
+            NetworkProtocol::Udp => &packet[..len],
+            NetworkProtocol::Rtp => match parse_rtp(&packet[..len]) {
+                Some((seq, payload)) if sequence.accept(seq) => payload,
+                Some(_) => continue,
+                None => {
+                    tracing::debug!(
+                        "Network source {}: dropped malformed RTP packet",
+                        source.name
+                    );
+                    continue;
+                }
+            },
+        };
+
+        let samples = match decoder.decode(payload) {
+            Ok(samples) => samples,
+            Err(e) => {
+                tracing::debug!("Network source {}: {}", source.name, e);
+                continue;
+            }
+        };
+
+        let stereo = if channels == 1 {
+            mono_to_stereo(&samples)
+        } else {
+            samples
+        };
+        let output = match resampler.as_mut() {
+            Some(resampler) => resampler.process(&stereo, 2),
+            None => stereo,
+        };
+
+        if output.is_empty() {
+            continue;
+        }
+        let data = AudioData {
+            samples: output,
+            channels: 2,
+            sample_rate: MIXER_SAMPLE_RATE,
+            sources: None,
+        };
+        if audio_tx.send(data).is_err() {
+            break;
+        }
+    }
+
+    tracing::info!("Network source {} stopped", source.name);
+}
+
+/// Decodes packet payloads to interleaved f32 samples
+enum PayloadDecoder {
+    Pcm {
+        big_endian: bool,
+        sample_rate: u32,
+    },
+    Opus {
+        decoder: opus::Decoder,
+        buffer: Vec<f32>,
+    },
+}
+
+impl PayloadDecoder {
+    fn new(source: &NetworkSource) -> Result<Self, FlowSttError> {
+        match source.codec {
+            NetworkCodec::Pcm => Ok(Self::Pcm {
+                big_endian: source.protocol == NetworkProtocol::Rtp,
+                sample_rate: source.sample_rate,
+            }),
+            NetworkCodec::Opus => {
+                let channels = if source.channels == 1 {
+                    opus::Channels::Mono
+                } else {
+                    opus::Channels::Stereo
+                };
+                let decoder = opus::Decoder::new(MIXER_SAMPLE_RATE, channels).map_err(|e| {
+                    FlowSttError::Capture(format!("Failed to create Opus decoder: {}", e))
+                })?;
+                Ok(Self::Opus {
+                    decoder,
+                    buffer: vec![0.0; MAX_OPUS_FRAME * source.channels as usize],
+                })
+            }
+        }
+    }
+
+    /// Rate of the decoded samples
+    fn sample_rate(&self) -> u32 {
+        match self {
+            Self::Pcm { sample_rate, .. } => *sample_rate,
+            Self::Opus { .. } => MIXER_SAMPLE_RATE,
+        }
+    }
+
+    fn decode(&mut self, payload: &[u8]) -> Result<Vec<f32>, FlowSttError> {
+        match self {
+            Self::Pcm { big_endian, .. } => Ok(decode_pcm16(payload, *big_endian)),
+            Self::Opus { decoder, buffer } => {
+                let frames = decoder
+                    .decode_float(payload, buffer, false)
+                    .map_err(|e| FlowSttError::Capture(format!("Opus decode failed: {}", e)))?;
+                let channels = buffer.len() / MAX_OPUS_FRAME;
+                Ok(buffer[..frames * channels].to_vec())
+            }
+        }
+    }
+}
+
+/// Convert 16-bit PCM bytes to f32 samples
+fn decode_pcm16(payload: &[u8], big_endian: bool) -> Vec<f32> {
+    payload
+        .chunks_exact(2)
+        .map(|bytes| {
+            let sample = if big_endian {
+                i16::from_be_bytes([bytes[0], bytes[1]])
+            } else {
+                i16::from_le_bytes([bytes[0], bytes[1]])
+            };
+            sample as f32 / 32768.0
+        })
+        .collect()
+}
+
+/// Split an RTP packet into its sequence number and payload.
+///
+/// Returns `None` for packets that aren't RTP version 2 or are truncated.
+fn parse_rtp(packet: &[u8]) -> Option<(u16, &[u8])> {
+    if packet.len() < RTP_HEADER_LEN || packet[0] >> 6 != 2 {
+        return None;
+    }
+
+    let has_padding = packet[0] & 0x20 != 0;
+    let has_extension = packet[0] & 0x10 != 0;
+    let csrc_count = (packet[0] & 0x0F) as usize;
+    let sequence = u16::from_be_bytes([packet[2], packet[3]]);
+
+    let mut offset = RTP_HEADER_LEN + csrc_count * 4;
+    if has_extension {
+        let header = packet.get(offset..offset + 4)?;
+        let words = u16::from_be_bytes([header[2], header[3]]) as usize;
+        offset += 4 + words * 4;
+    }
+
+    let mut end = packet.len();
+    if has_padding {
+        let padding = *packet.last()? as usize;
+        end = end.checked_sub(padding)?;
+    }
+
+    if offset > end {
+        return None;
+    }
+    Some((sequence, &packet[offset..end]))
+}
+
+/// Drops duplicate and late RTP packets
+#[derive(Default)]
+struct RtpSequence {
+    last: Option<u16>,
+}
+
+impl RtpSequence {
+    /// Whether a packet with this sequence number should be played
+    fn accept(&mut self, sequence: u16) -> bool {
+        if let Some(last) = self.last {
+            // Newer packets are ahead of the last one by less than half the range
+            let delta = sequence.wrapping_sub(last);
+            if delta == 0 || delta >= 0x8000 {
+                return false;
+            }
+        }
+        self.last = Some(sequence);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rtp_packet(first_byte: u8, sequence: u16, rest: &[u8]) -> Vec<u8> {
+        let mut packet = vec![first_byte, 96];
+        packet.extend_from_slice(&sequence.to_be_bytes());
+        packet.extend_from_slice(&[0; 8]); // timestamp + SSRC
+        packet.extend_from_slice(rest);
+        packet
+    }
+
+    #[test]
+    fn test_parse_rtp() {
+        let packet = rtp_packet(0x80, 7, &[1, 2, 3, 4]);
+        assert_eq!(parse_rtp(&packet), Some((7, &[1u8, 2, 3, 4][..])));
+
+        // One CSRC, a one-word extension and two bytes of padding
+        let packet = rtp_packet(
+            0xB1,
+            8,
+            &[0, 0, 0, 9, 0xBE, 0xDE, 0, 1, 0, 0, 0, 0, 5, 6, 0, 2],
+        );
+        assert_eq!(parse_rtp(&packet), Some((8, &[5u8, 6][..])));
+
+        // Wrong version and truncated header
+        assert_eq!(parse_rtp(&rtp_packet(0x40, 1, &[1, 2])), None);
+        assert_eq!(parse_rtp(&[0x80, 96, 0, 1]), None);
+    }
+
+    #[test]
+    fn test_rtp_sequence_drops_late_packets() {
+        let mut sequence = RtpSequence::default();
+        assert!(sequence.accept(65534));
+        assert!(sequence.accept(65535));
+        assert!(sequence.accept(1)); // wraps around, one packet lost
+        assert!(!sequence.accept(1)); // duplicate
+        assert!(!sequence.accept(0)); // late
+        assert!(sequence.accept(2));
+    }
+
+    #[test]
+    fn test_decode_pcm16() {
+        assert_eq!(decode_pcm16(&[0x40, 0x00], true), vec![0.5]);
+        assert_eq!(decode_pcm16(&[0x00, 0x40], false), vec![0.5]);
+        assert_eq!(decode_pcm16(&[0x80, 0x00, 0xFF], true), vec![-1.0]);
+    }
+}
@@ -0,0 +1,96 @@
+//! Raw per-source recordings.
+//!
+//! With source recording enabled, the mixer hands each source's pre-AEC
+//! capture to a [`SourceFiles`], which saves it next to the mixed recordings,
+//! named and encoded the same way.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use flowstt_audio::mixer::{SourceRecorder, SourceRecording};
+use flowstt_common::RecordingFormat;
+
+use crate::audio::{generate_recording_filename, recording_format};
+use crate::encoder::AudioEncoder;
+
+/// Record each capture session's sources to files in `dir`.
+pub fn to_files(dir: PathBuf) -> Arc<SourceRecording> {
+    Arc::new(
+        move |sample_rate: u32, channels: u16| -> Box<dyn SourceRecorder> {
+            Box::new(SourceFiles::new(dir.clone(), sample_rate, channels))
+        },
+    )
+}
+
+/// Writes each source's raw capture to `<recording>-mic.<ext>` and
+/// `<recording>-system.<ext>`. Files are created when a source first delivers
+/// audio and finalized when the recorder is dropped.
+struct SourceFiles {
+    /// Output path without the source suffix
+    base_path: PathBuf,
+    format: RecordingFormat,
+    sample_rate: u32,
+    channels: u16,
+    mic: Option<AudioEncoder>,
+    system: Option<AudioEncoder>,
+    /// Set after a write error so it is only logged once
+    failed: bool,
+}
+
+impl SourceFiles {
+    fn new(dir: PathBuf, sample_rate: u32, channels: u16) -> Self {
+        let filename = generate_recording_filename();
+        let stem = Path::new(&filename).file_stem().unwrap_or_default();
+        Self {
+            base_path: dir.join(stem),
+            format: recording_format(),
+            sample_rate,
+            channels,
+            mic: None,
+            system: None,
+            failed: false,
+        }
+    }
+
+    fn try_write(&mut self, samples: &[f32], is_render: bool) -> Result<(), String> {
+        let (writer, suffix) = if is_render {
+            (&mut self.system, "system")
+        } else {
+            (&mut self.mic, "mic")
+        };
+
+        if writer.is_none() {
+            let path = PathBuf::from(format!(
+                "{}-{}.{}",
+                self.base_path.display(),
+                suffix,
+                self.format.extension()
+            ));
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)
+                    .map_err(|e| format!("Failed to create {:?}: {}", parent, e))?;
+            }
+            let created =
+                AudioEncoder::create(&path, self.format, self.sample_rate, self.channels)?;
+            tracing::info!("Recording raw {} audio to {:?}", suffix, path);
+            *writer = Some(created);
+        }
+
+        match writer.as_mut() {
+            Some(writer) => writer.write(samples),
+            None => Ok(()),
+        }
+    }
+}
+
+impl SourceRecorder for SourceFiles {
+    fn write(&mut self, samples: &[f32], is_render: bool) {
+        if self.failed {
+            return;
+        }
+        if let Err(e) = self.try_write(samples, is_render) {
+            tracing::error!("Source recording stopped: {}", e);
+            self.failed = true;
+        }
+    }
+}
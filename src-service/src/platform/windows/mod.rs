@@ -1,27 +1,30 @@
 //! Windows audio backend using WASAPI.
 
+mod process_loopback;
 mod wasapi;
 
+pub use wasapi::set_capture_mode;
+
+use super::mixer::MixerControls;
 use super::AudioBackend;
-use flowstt_common::RecordingMode;
-use std::sync::{Arc, Mutex, OnceLock};
+use flowstt_common::FlowSttError;
+use std::sync::{Arc, OnceLock};
 
 /// Global backend instance
 static BACKEND: OnceLock<Box<dyn AudioBackend>> = OnceLock::new();
 
 /// Initialize the Windows audio backend.
-pub fn init() -> Result<(), String> {
+pub fn init() -> Result<(), FlowSttError> {
     tracing::info!("Initializing Windows WASAPI audio backend");
 
     // Create shared state for AEC and recording mode
-    let aec_enabled = Arc::new(Mutex::new(false));
-    let recording_mode = Arc::new(Mutex::new(RecordingMode::default()));
+    let controls = Arc::new(MixerControls::default());
 
-    let backend = wasapi::create_backend(aec_enabled, recording_mode)?;
+    let backend = wasapi::create_backend(controls)?;
 
     BACKEND
         .set(backend)
-        .map_err(|_| "Backend already initialized".to_string())?;
+        .map_err(|_| FlowSttError::Other("Backend already initialized".to_string()))?;
 
     tracing::info!("Windows WASAPI audio backend initialized");
     Ok(())
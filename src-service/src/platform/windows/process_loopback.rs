@@ -0,0 +1,269 @@
+//! Per-process loopback capture for Windows
+//!
+//! Windows 10 2004 (build 19041) and later can capture the audio rendered by a
+//! single process tree instead of the whole endpoint mix. Processes that currently
+//! own an audio session are listed as additional system devices with a
+//! `process:<pid>` id, and are activated through `ActivateAudioInterfaceAsync`
+//! with `AUDIOCLIENT_ACTIVATION_TYPE_PROCESS_LOOPBACK`.
+
+use crate::platform::mixer::MIXER_SAMPLE_RATE;
+use flowstt_common::{AudioDevice, AudioSourceType, FlowSttError};
+use std::collections::HashSet;
+use std::mem::ManuallyDrop;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+use windows::core::{implement, IUnknown, Interface, HRESULT, PROPVARIANT, PWSTR};
+use windows::Win32::Foundation::CloseHandle;
+use windows::Win32::Media::Audio::{
+    eConsole, eRender, ActivateAudioInterfaceAsync, AudioSessionStateExpired,
+    IActivateAudioInterfaceAsyncOperation, IActivateAudioInterfaceCompletionHandler,
+    IActivateAudioInterfaceCompletionHandler_Impl, IAudioClient, IAudioSessionControl2,
+    IAudioSessionManager2, IMMDeviceEnumerator, MMDeviceEnumerator, AUDIOCLIENT_ACTIVATION_PARAMS,
+    AUDIOCLIENT_ACTIVATION_PARAMS_0, AUDIOCLIENT_ACTIVATION_TYPE_PROCESS_LOOPBACK,
+    AUDIOCLIENT_PROCESS_LOOPBACK_PARAMS, PROCESS_LOOPBACK_MODE_INCLUDE_TARGET_PROCESS_TREE,
+    VIRTUAL_AUDIO_DEVICE_PROCESS_LOOPBACK, WAVEFORMATEX,
+};
+use windows::Win32::System::Com::{CoCreateInstance, IAgileObject, IAgileObject_Impl, CLSCTX_ALL};
+use windows::Win32::System::Threading::{
+    OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_WIN32, PROCESS_QUERY_LIMITED_INFORMATION,
+};
+use windows::Win32::System::Variant::VT_BLOB;
+
+/// Device ID prefix for per-process loopback sources
+pub const PROCESS_DEVICE_PREFIX: &str = "process:";
+
+/// WAVE_FORMAT_IEEE_FLOAT constant (3)
+const WAVE_FORMAT_IEEE_FLOAT: u16 = 3;
+
+/// Channels requested from a process loopback stream
+const CAPTURE_CHANNELS: u16 = 2;
+
+/// How long to wait for the asynchronous activation to complete
+const ACTIVATION_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Extract the target process id from a per-process device id
+pub fn process_id(device_id: &str) -> Option<u32> {
+    device_id
+        .strip_prefix(PROCESS_DEVICE_PREFIX)
+        .and_then(|pid| pid.parse().ok())
+}
+
+/// Enumerate processes that own an audio session on the default render endpoint.
+///
+/// COM must be initialized on the calling thread. Failures are logged and result in
+/// an empty list so the regular loopback devices are still available.
+pub fn enumerate_audio_processes() -> Vec<AudioDevice> {
+    match unsafe { enumerate_session_pids() } {
+        Ok(pids) => {
+            let own_pid = std::process::id();
+            let mut devices: Vec<AudioDevice> = pids
+                .into_iter()
+                .filter(|&pid| pid != 0 && pid != own_pid)
+                .filter_map(|pid| {
+                    let name = process_name(pid)?;
+                    Some(AudioDevice {
+                        id: format!("{}{}", PROCESS_DEVICE_PREFIX, pid),
+                        name: format!("{} (App Audio)", name),
+                        source_type: AudioSourceType::System,
+                    })
+                })
+                .collect();
+            devices.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+            devices
+        }
+        Err(e) => {
+            tracing::warn!("WASAPI: Failed to enumerate audio sessions: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// Collect the process ids of all live audio sessions on the default render device
+unsafe fn enumerate_session_pids() -> Result<HashSet<u32>, FlowSttError> {
+    let enumerator: IMMDeviceEnumerator = CoCreateInstance(&MMDeviceEnumerator, None, CLSCTX_ALL)
+        .map_err(|e| {
+        FlowSttError::BackendUnavailable(format!("Failed to create device enumerator: {}", e))
+    })?;
+
+    let device = enumerator
+        .GetDefaultAudioEndpoint(eRender, eConsole)
+        .map_err(|e| {
+            FlowSttError::BackendUnavailable(format!("Failed to get default render device: {}", e))
+        })?;
+
+    let manager: IAudioSessionManager2 = device.Activate(CLSCTX_ALL, None).map_err(|e| {
+        FlowSttError::BackendUnavailable(format!("Failed to activate session manager: {}", e))
+    })?;
+
+    let sessions = manager.GetSessionEnumerator().map_err(|e| {
+        FlowSttError::BackendUnavailable(format!("Failed to get session enumerator: {}", e))
+    })?;
+
+    let count = sessions.GetCount().map_err(|e| {
+        FlowSttError::BackendUnavailable(format!("Failed to get session count: {}", e))
+    })?;
+
+    let mut pids = HashSet::new();
+    for i in 0..count {
+        let Ok(control) = sessions.GetSession(i) else {
+            continue;
+        };
+        if control.GetState().ok() == Some(AudioSessionStateExpired) {
+            continue;
+        }
+        if let Ok(control2) = control.cast::<IAudioSessionControl2>() {
+            if let Ok(pid) = control2.GetProcessId() {
+                pids.insert(pid);
+            }
+        }
+    }
+
+    Ok(pids)
+}
+
+/// Look up a process's executable name without the extension (e.g. "Zoom")
+fn process_name(pid: u32) -> Option<String> {
+    unsafe {
+        let handle = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid).ok()?;
+
+        let mut buffer = [0u16; 1024];
+        let mut len = buffer.len() as u32;
+        let result = QueryFullProcessImageNameW(
+            handle,
+            PROCESS_NAME_WIN32,
+            PWSTR(buffer.as_mut_ptr()),
+            &mut len,
+        );
+        let _ = CloseHandle(handle);
+        result.ok()?;
+
+        let path = String::from_utf16_lossy(&buffer[..len as usize]);
+        std::path::Path::new(&path)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .filter(|name| !name.is_empty())
+    }
+}
+
+/// Completion handler that signals when asynchronous activation has finished
+#[implement(IActivateAudioInterfaceCompletionHandler, IAgileObject)]
+struct ActivationHandler {
+    done: Arc<(Mutex<bool>, Condvar)>,
+}
+
+impl IActivateAudioInterfaceCompletionHandler_Impl for ActivationHandler_Impl {
+    fn ActivateCompleted(
+        &self,
+        _operation: Option<&IActivateAudioInterfaceAsyncOperation>,
+    ) -> windows::core::Result<()> {
+        let (lock, cvar) = &*self.done;
+        *lock.lock().unwrap() = true;
+        cvar.notify_all();
+        Ok(())
+    }
+}
+
+impl IAgileObject_Impl for ActivationHandler_Impl {}
+
+/// Activate an audio client that captures the given process tree's output
+pub unsafe fn activate(pid: u32) -> Result<IAudioClient, FlowSttError> {
+    let params = AUDIOCLIENT_ACTIVATION_PARAMS {
+        ActivationType: AUDIOCLIENT_ACTIVATION_TYPE_PROCESS_LOOPBACK,
+        Anonymous: AUDIOCLIENT_ACTIVATION_PARAMS_0 {
+            ProcessLoopbackParams: AUDIOCLIENT_PROCESS_LOOPBACK_PARAMS {
+                TargetProcessId: pid,
+                ProcessLoopbackMode: PROCESS_LOOPBACK_MODE_INCLUDE_TARGET_PROCESS_TREE,
+            },
+        },
+    };
+
+    // The activation parameters are passed as a VT_BLOB that borrows `params`, so the
+    // PROPVARIANT must not be cleared on drop.
+    let raw = windows::core::imp::PROPVARIANT {
+        Anonymous: windows::core::imp::PROPVARIANT_0 {
+            Anonymous: windows::core::imp::PROPVARIANT_0_0 {
+                vt: VT_BLOB.0,
+                wReserved1: 0,
+                wReserved2: 0,
+                wReserved3: 0,
+                Anonymous: windows::core::imp::PROPVARIANT_0_0_0 {
+                    blob: windows::core::imp::BLOB {
+                        cbSize: std::mem::size_of::<AUDIOCLIENT_ACTIVATION_PARAMS>() as u32,
+                        pBlobData: &params as *const _ as *mut u8,
+                    },
+                },
+            },
+        },
+    };
+    let prop = ManuallyDrop::new(PROPVARIANT::from_raw(raw));
+
+    let done = Arc::new((Mutex::new(false), Condvar::new()));
+    let handler: IActivateAudioInterfaceCompletionHandler = ActivationHandler {
+        done: Arc::clone(&done),
+    }
+    .into();
+
+    let operation = ActivateAudioInterfaceAsync(
+        VIRTUAL_AUDIO_DEVICE_PROCESS_LOOPBACK,
+        &IAudioClient::IID,
+        Some(&*prop as *const PROPVARIANT),
+        &handler,
+    )
+    .map_err(|e| {
+        FlowSttError::Unsupported(format!(
+            "Failed to activate process loopback (requires Windows 10 2004 or later): {}",
+            e
+        ))
+    })?;
+
+    {
+        let (lock, cvar) = &*done;
+        let guard = lock.lock().unwrap();
+        let (guard, timeout) = cvar
+            .wait_timeout_while(guard, ACTIVATION_TIMEOUT, |completed| !*completed)
+            .unwrap();
+        if timeout.timed_out() && !*guard {
+            return Err(FlowSttError::Capture(format!(
+                "Timed out activating loopback for process {}",
+                pid
+            )));
+        }
+    }
+
+    let mut activate_result = HRESULT(0);
+    let mut interface: Option<IUnknown> = None;
+    operation
+        .GetActivateResult(&mut activate_result, &mut interface)
+        .map_err(|e| FlowSttError::Capture(format!("Failed to get activation result: {}", e)))?;
+    activate_result.ok().map_err(|e| {
+        FlowSttError::Capture(format!(
+            "Process loopback activation failed for {}: {}",
+            pid, e
+        ))
+    })?;
+
+    interface
+        .ok_or_else(|| {
+            FlowSttError::Capture("Process loopback activation returned no interface".to_string())
+        })?
+        .cast::<IAudioClient>()
+        .map_err(|e| FlowSttError::Capture(format!("Failed to get audio client: {}", e)))
+}
+
+/// Capture format for process loopback streams.
+///
+/// Process loopback clients have no mix format of their own, so a float stereo
+/// stream at the mixer rate is requested and the audio engine converts to it.
+pub fn capture_format() -> WAVEFORMATEX {
+    let block_align = CAPTURE_CHANNELS * 4;
+    WAVEFORMATEX {
+        wFormatTag: WAVE_FORMAT_IEEE_FLOAT,
+        nChannels: CAPTURE_CHANNELS,
+        nSamplesPerSec: MIXER_SAMPLE_RATE,
+        nAvgBytesPerSec: MIXER_SAMPLE_RATE * block_align as u32,
+        nBlockAlign: block_align,
+        wBitsPerSample: 32,
+        cbSize: 0,
+    }
+}
@@ -3,23 +3,39 @@
 //! This module provides full audio capture functionality using Windows Audio Session API (WASAPI):
 //! - Input device capture (microphones)
 //! - System audio capture (loopback from render endpoints)
+//! - Per-process loopback capture (see `process_loopback`)
 //! - Multi-source capture with mixing
 //! - Echo cancellation using AEC3
-
-use crate::platform::backend::{AudioBackend, AudioData};
-use flowstt_common::{AudioDevice, AudioSourceType, RecordingMode};
-use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+//! - Raw and exclusive-mode input capture (see [`set_capture_mode`])
+//! - 16kHz mono capture of a single source, converted by the audio engine
+
+use super::process_loopback;
+use crate::config::CaptureMode;
+use crate::permissions;
+use crate::platform::backend::{AudioBackend, AudioData, SPEECH_SAMPLE_RATE};
+use crate::platform::clock;
+use crate::platform::mixer::{
+    capture_buffer_ms, downmix_channels, mixed_queue, mono_to_stereo, report_capture_latency,
+    report_source_format, selected_channels, speech_capture, AudioMixer, MixedReceiver,
+    MixedSender, MixerControls, Resampler, MIXER_SAMPLE_RATE,
+};
+use flowstt_common::{AudioDevice, AudioSourceType, FlowSttError, Permission, RecordingMode};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc;
 use std::sync::{Arc, Mutex};
 use std::thread::{self, JoinHandle};
+use std::time::Duration;
 
-use aec3::voip::VoipAec3;
-use windows::core::{GUID, PCWSTR, PWSTR};
+use windows::core::{Interface, GUID, PCWSTR, PWSTR};
 use windows::Win32::Devices::FunctionDiscovery::PKEY_Device_FriendlyName;
+use windows::Win32::Foundation::{E_ACCESSDENIED, S_OK};
 use windows::Win32::Media::Audio::{
-    eCapture, eRender, IAudioCaptureClient, IAudioClient, IMMDevice, IMMDeviceCollection,
-    IMMDeviceEnumerator, MMDeviceEnumerator, AUDCLNT_SHAREMODE_SHARED,
-    AUDCLNT_STREAMFLAGS_EVENTCALLBACK, AUDCLNT_STREAMFLAGS_LOOPBACK, WAVEFORMATEX,
+    eCapture, eRender, AudioCategory_Other, AudioClientProperties, IAudioCaptureClient,
+    IAudioClient, IAudioClient2, IMMDevice, IMMDeviceCollection, IMMDeviceEnumerator,
+    MMDeviceEnumerator, AUDCLNT_E_BUFFER_SIZE_NOT_ALIGNED, AUDCLNT_SHAREMODE_EXCLUSIVE,
+    AUDCLNT_SHAREMODE_SHARED, AUDCLNT_STREAMFLAGS_AUTOCONVERTPCM,
+    AUDCLNT_STREAMFLAGS_EVENTCALLBACK, AUDCLNT_STREAMFLAGS_LOOPBACK,
+    AUDCLNT_STREAMFLAGS_SRC_DEFAULT_QUALITY, AUDCLNT_STREAMOPTIONS_RAW, WAVEFORMATEX,
     WAVEFORMATEXTENSIBLE,
 };
 use windows::Win32::System::Com::{
@@ -37,20 +53,20 @@ const WAVE_FORMAT_PCM: u16 = 1;
 /// WAVE_FORMAT_IEEE_FLOAT constant (3)
 const WAVE_FORMAT_IEEE_FLOAT: u16 = 3;
 
+/// AUDCLNT_BUFFERFLAGS_TIMESTAMP_ERROR: the buffer's QPC position is invalid
+const AUDCLNT_BUFFERFLAGS_TIMESTAMP_ERROR: u32 = 0x4;
+
 /// KSDATAFORMAT_SUBTYPE_IEEE_FLOAT GUID
 const KSDATAFORMAT_SUBTYPE_IEEE_FLOAT: GUID =
     GUID::from_u128(0x00000003_0000_0010_8000_00aa00389b71);
 
-/// Target sample rate for output (matches Linux backend)
-const TARGET_SAMPLE_RATE: u32 = 48000;
-
-/// AEC3 frame size: 10ms at 48kHz = 480 samples per channel
-const AEC_FRAME_SAMPLES: usize = 480;
+/// How input devices are opened when capture next starts
+static CAPTURE_MODE: Mutex<CaptureMode> = Mutex::new(CaptureMode::Shared);
 
-/// Internal audio samples for channel communication
-struct WasapiAudioSamples {
-    samples: Vec<f32>,
-    channels: u16,
+/// Set how input devices are opened when capture next starts. Loopback
+/// sources are always captured in shared mode.
+pub fn set_capture_mode(mode: CaptureMode) {
+    *CAPTURE_MODE.lock().unwrap() = mode;
 }
 
 /// Samples from a stream thread to the mixer
@@ -58,6 +74,8 @@ struct StreamSamples {
     samples: Vec<f32>,
     /// Whether this stream is loopback (system audio) - used for AEC routing
     is_loopback: bool,
+    /// Host clock time of the first frame, in nanoseconds
+    timestamp: u64,
 }
 
 /// Commands sent to the capture thread
@@ -65,7 +83,7 @@ enum CaptureCommand {
     StartSources {
         source1_id: Option<String>,
         source2_id: Option<String>,
-        result_tx: mpsc::Sender<Result<(), String>>,
+        result_tx: mpsc::Sender<Result<(), FlowSttError>>,
     },
     Stop,
     Shutdown,
@@ -75,30 +93,26 @@ enum CaptureCommand {
 pub struct WasapiBackend {
     /// Channel to send commands to capture thread
     cmd_tx: mpsc::Sender<CaptureCommand>,
-    /// Channel to receive audio samples from capture thread (wrapped in Mutex for Sync)
-    audio_rx: Mutex<mpsc::Receiver<WasapiAudioSamples>>,
+    /// Queue of mixed audio from the capture thread (in a Mutex for Sync;
+    /// only the processing thread locks it, so it is never contended)
+    audio_rx: Mutex<MixedReceiver>,
     /// Cached input devices
     input_devices: Arc<Mutex<Vec<AudioDevice>>>,
-    /// Cached system devices (loopback sources)
+    /// Cached system devices (loopback sources, including per-process sources)
     system_devices: Arc<Mutex<Vec<AudioDevice>>>,
-    /// Sample rate (always 48kHz after resampling)
+    /// Sample rate outside speech capture (always 48kHz after resampling)
     sample_rate: u32,
     /// Capture thread handle
     _thread_handle: JoinHandle<()>,
-    /// AEC enabled flag (shared with mixer)
-    aec_enabled: Arc<Mutex<bool>>,
-    /// Recording mode (shared with mixer)
-    recording_mode: Arc<Mutex<RecordingMode>>,
+    /// AEC flag and recording mode (shared with mixer)
+    controls: Arc<MixerControls>,
 }
 
 impl WasapiBackend {
     /// Create a new WASAPI backend
-    pub fn new(
-        aec_enabled: Arc<Mutex<bool>>,
-        recording_mode: Arc<Mutex<RecordingMode>>,
-    ) -> Result<Self, String> {
+    pub fn new(controls: Arc<MixerControls>) -> Result<Self, FlowSttError> {
         let (cmd_tx, cmd_rx) = mpsc::channel();
-        let (audio_tx, audio_rx) = mpsc::channel();
+        let (audio_tx, audio_rx) = mixed_queue();
         let input_devices = Arc::new(Mutex::new(Vec::new()));
         let system_devices = Arc::new(Mutex::new(Vec::new()));
         let is_capturing = Arc::new(AtomicBool::new(false));
@@ -111,7 +125,10 @@ impl WasapiBackend {
 
         // Enumerate devices
         let input_devs = enumerate_input_devices();
-        let system_devs = enumerate_render_devices();
+        let system_devs = enumerate_render_devices().map(|mut devices| {
+            devices.extend(process_loopback::enumerate_audio_processes());
+            devices
+        });
 
         // Uninitialize COM if we initialized it
         if com_initialized {
@@ -128,8 +145,7 @@ impl WasapiBackend {
 
         let system_devices_clone = Arc::clone(&system_devices);
         let is_capturing_clone = Arc::clone(&is_capturing);
-        let aec_enabled_clone = Arc::clone(&aec_enabled);
-        let recording_mode_clone = Arc::clone(&recording_mode);
+        let controls_clone = Arc::clone(&controls);
 
         let thread_handle = thread::spawn(move || {
             run_capture_thread(
@@ -137,8 +153,7 @@ impl WasapiBackend {
                 audio_tx,
                 system_devices_clone,
                 is_capturing_clone,
-                aec_enabled_clone,
-                recording_mode_clone,
+                controls_clone,
             );
         });
 
@@ -147,10 +162,9 @@ impl WasapiBackend {
             audio_rx: Mutex::new(audio_rx),
             input_devices,
             system_devices,
-            sample_rate: TARGET_SAMPLE_RATE,
+            sample_rate: MIXER_SAMPLE_RATE,
             _thread_handle: thread_handle,
-            aec_enabled,
-            recording_mode,
+            controls,
         })
     }
 }
@@ -167,18 +181,34 @@ impl AudioBackend for WasapiBackend {
     }
 
     fn list_system_devices(&self) -> Vec<AudioDevice> {
-        self.system_devices.lock().unwrap().clone()
+        // Processes start and stop playing audio, so refresh them on every listing
+        let com_initialized = unsafe { CoInitializeEx(None, COINIT_MULTITHREADED).is_ok() };
+        let processes = process_loopback::enumerate_audio_processes();
+        if com_initialized {
+            unsafe {
+                CoUninitialize();
+            }
+        }
+
+        let mut devices = self.system_devices.lock().unwrap();
+        devices.retain(|d| process_loopback::process_id(&d.id).is_none());
+        devices.extend(processes);
+        devices.clone()
     }
 
     fn sample_rate(&self) -> u32 {
         self.sample_rate
     }
 
+    fn captures_speech_format(&self, source1_id: Option<&str>, source2_id: Option<&str>) -> bool {
+        speech_capture(source1_id, source2_id)
+    }
+
     fn start_capture_sources(
         &self,
         source1_id: Option<String>,
         source2_id: Option<String>,
-    ) -> Result<(), String> {
+    ) -> Result<(), FlowSttError> {
         let (result_tx, result_rx) = mpsc::channel();
 
         self.cmd_tx
@@ -187,71 +217,78 @@ impl AudioBackend for WasapiBackend {
                 source2_id,
                 result_tx,
             })
-            .map_err(|e| format!("Failed to send start command: {}", e))?;
+            .map_err(|e| FlowSttError::Capture(format!("Failed to send start command: {}", e)))?;
 
-        match result_rx.recv_timeout(std::time::Duration::from_secs(5)) {
+        // Each stream may wait out a process loopback activation
+        match result_rx.recv_timeout(std::time::Duration::from_secs(15)) {
             Ok(result) => result,
-            Err(mpsc::RecvTimeoutError::Timeout) => {
-                Err("Timeout waiting for audio capture to start".to_string())
-            }
-            Err(mpsc::RecvTimeoutError::Disconnected) => {
-                Err("Capture thread disconnected".to_string())
-            }
+            Err(mpsc::RecvTimeoutError::Timeout) => Err(FlowSttError::Capture(
+                "Timeout waiting for audio capture to start".to_string(),
+            )),
+            Err(mpsc::RecvTimeoutError::Disconnected) => Err(FlowSttError::Capture(
+                "Capture thread disconnected".to_string(),
+            )),
         }
     }
 
-    fn stop_capture(&self) -> Result<(), String> {
+    fn stop_capture(&self) -> Result<(), FlowSttError> {
         self.cmd_tx
             .send(CaptureCommand::Stop)
-            .map_err(|e| format!("Failed to send stop command: {}", e))?;
+            .map_err(|e| FlowSttError::Capture(format!("Failed to send stop command: {}", e)))?;
         Ok(())
     }
 
-    fn try_recv(&self) -> Option<AudioData> {
+    fn recv_timeout(&self, timeout: Duration) -> Option<AudioData> {
         self.audio_rx
             .lock()
             .unwrap()
-            .try_recv()
-            .ok()
+            .recv_timeout(timeout)
             .map(|samples| AudioData {
                 samples: samples.samples,
                 channels: samples.channels,
-                sample_rate: self.sample_rate,
+                sample_rate: samples.sample_rate,
+                sources: samples.sources,
             })
     }
 
     fn set_aec_enabled(&self, enabled: bool) {
-        *self.aec_enabled.lock().unwrap() = enabled;
+        self.controls.set_aec_enabled(enabled);
     }
 
     fn set_recording_mode(&self, mode: RecordingMode) {
-        *self.recording_mode.lock().unwrap() = mode;
+        self.controls.set_recording_mode(mode);
     }
 }
 
 /// Create a Windows audio backend using WASAPI
-pub fn create_backend(
-    aec_enabled: Arc<Mutex<bool>>,
-    recording_mode: Arc<Mutex<RecordingMode>>,
-) -> Result<Box<dyn AudioBackend>, String> {
-    let backend = WasapiBackend::new(aec_enabled, recording_mode)?;
+pub fn create_backend(controls: Arc<MixerControls>) -> Result<Box<dyn AudioBackend>, FlowSttError> {
+    let backend = WasapiBackend::new(controls)?;
     Ok(Box::new(backend))
 }
 
 /// Enumerate available input devices (microphones)
-fn enumerate_input_devices() -> Result<Vec<AudioDevice>, String> {
+fn enumerate_input_devices() -> Result<Vec<AudioDevice>, FlowSttError> {
     unsafe {
         let enumerator: IMMDeviceEnumerator =
-            CoCreateInstance(&MMDeviceEnumerator, None, CLSCTX_ALL)
-                .map_err(|e| format!("Failed to create device enumerator: {}", e))?;
+            CoCreateInstance(&MMDeviceEnumerator, None, CLSCTX_ALL).map_err(|e| {
+                FlowSttError::BackendUnavailable(format!(
+                    "Failed to create device enumerator: {}",
+                    e
+                ))
+            })?;
 
         let collection: IMMDeviceCollection = enumerator
             .EnumAudioEndpoints(eCapture, windows::Win32::Media::Audio::DEVICE_STATE_ACTIVE)
-            .map_err(|e| format!("Failed to enumerate audio endpoints: {}", e))?;
+            .map_err(|e| {
+                FlowSttError::BackendUnavailable(format!(
+                    "Failed to enumerate audio endpoints: {}",
+                    e
+                ))
+            })?;
 
-        let count = collection
-            .GetCount()
-            .map_err(|e| format!("Failed to get device count: {}", e))?;
+        let count = collection.GetCount().map_err(|e| {
+            FlowSttError::BackendUnavailable(format!("Failed to get device count: {}", e))
+        })?;
 
         let mut devices = Vec::new();
 
@@ -270,19 +307,28 @@ fn enumerate_input_devices() -> Result<Vec<AudioDevice>, String> {
 }
 
 /// Enumerate available render devices (for loopback capture)
-fn enumerate_render_devices() -> Result<Vec<AudioDevice>, String> {
+fn enumerate_render_devices() -> Result<Vec<AudioDevice>, FlowSttError> {
     unsafe {
         let enumerator: IMMDeviceEnumerator =
-            CoCreateInstance(&MMDeviceEnumerator, None, CLSCTX_ALL)
-                .map_err(|e| format!("Failed to create device enumerator: {}", e))?;
+            CoCreateInstance(&MMDeviceEnumerator, None, CLSCTX_ALL).map_err(|e| {
+                FlowSttError::BackendUnavailable(format!(
+                    "Failed to create device enumerator: {}",
+                    e
+                ))
+            })?;
 
         let collection: IMMDeviceCollection = enumerator
             .EnumAudioEndpoints(eRender, windows::Win32::Media::Audio::DEVICE_STATE_ACTIVE)
-            .map_err(|e| format!("Failed to enumerate render endpoints: {}", e))?;
+            .map_err(|e| {
+                FlowSttError::BackendUnavailable(format!(
+                    "Failed to enumerate render endpoints: {}",
+                    e
+                ))
+            })?;
 
-        let count = collection
-            .GetCount()
-            .map_err(|e| format!("Failed to get render device count: {}", e))?;
+        let count = collection.GetCount().map_err(|e| {
+            FlowSttError::BackendUnavailable(format!("Failed to get render device count: {}", e))
+        })?;
 
         let mut devices = Vec::new();
 
@@ -341,211 +387,13 @@ fn pwstr_to_string(pwstr: PWSTR) -> String {
     }
 }
 
-/// Audio mixer for combining samples from multiple streams
-struct AudioMixer {
-    /// Buffer for capture samples (microphone/input)
-    capture_buffer: Vec<f32>,
-    /// Buffer for render samples (system audio/reference) - fed to AEC and kept for mixing
-    render_buffer: Vec<f32>,
-    /// Buffer for render samples to mix with processed capture (for Mixed mode)
-    render_mix_buffer: Vec<f32>,
-    /// Number of active streams (1 or 2)
-    num_streams: usize,
-    /// Channels per stream
-    channels: u16,
-    /// Output sender
-    output_tx: mpsc::Sender<WasapiAudioSamples>,
-    /// Flag to enable/disable AEC (shared with main thread)
-    aec_enabled: Arc<Mutex<bool>>,
-    /// Recording mode - Mixed or EchoCancel (shared with main thread)
-    recording_mode: Arc<Mutex<RecordingMode>>,
-    /// AEC3 pipeline (created when in mixed mode with 2 streams)
-    aec: Option<VoipAec3>,
-}
-
-impl AudioMixer {
-    fn new(
-        output_tx: mpsc::Sender<WasapiAudioSamples>,
-        aec_enabled: Arc<Mutex<bool>>,
-        recording_mode: Arc<Mutex<RecordingMode>>,
-    ) -> Self {
-        Self {
-            capture_buffer: Vec::new(),
-            render_buffer: Vec::new(),
-            render_mix_buffer: Vec::new(),
-            num_streams: 0,
-            channels: 2,
-            output_tx,
-            aec_enabled,
-            recording_mode,
-            aec: None,
-        }
-    }
-
-    fn set_num_streams(&mut self, num: usize) {
-        self.num_streams = num;
-        self.capture_buffer.clear();
-        self.render_buffer.clear();
-        self.render_mix_buffer.clear();
-
-        // Create AEC3 pipeline when we have 2 streams (mic + system audio)
-        if num == 2 {
-            match VoipAec3::builder(48000, self.channels as usize, self.channels as usize)
-                .enable_high_pass(true)
-                .initial_delay_ms(0)
-                .build()
-            {
-                Ok(aec) => {
-                    tracing::info!(
-                        "WASAPI: AEC3 initialized: 48kHz, {} channels, {}ms frames",
-                        self.channels,
-                        AEC_FRAME_SAMPLES * 1000 / 48000
-                    );
-                    self.aec = Some(aec);
-                }
-                Err(e) => {
-                    tracing::error!("WASAPI: Failed to initialize AEC3: {:?}", e);
-                    self.aec = None;
-                }
-            }
-        } else {
-            self.aec = None;
-        }
-    }
-
-    /// Add samples from a stream, routing based on source type
-    fn push_samples(&mut self, samples: &[f32], is_loopback: bool) {
-        if self.num_streams == 1 {
-            // Single stream - send directly (no AEC possible)
-            let _ = self.output_tx.send(WasapiAudioSamples {
-                samples: samples.to_vec(),
-                channels: self.channels,
-            });
-            return;
-        }
-
-        // Two streams mode
-        let frame_size = AEC_FRAME_SAMPLES * self.channels as usize;
-
-        if is_loopback {
-            // System audio (render) - feed to AEC immediately
-            self.render_buffer.extend_from_slice(samples);
-            self.render_mix_buffer.extend_from_slice(samples);
-
-            // Feed render frames to AEC immediately
-            if let Some(ref mut aec) = self.aec {
-                while self.render_buffer.len() >= frame_size {
-                    let render_frame: Vec<f32> = self.render_buffer.drain(0..frame_size).collect();
-                    if let Err(e) = aec.handle_render_frame(&render_frame) {
-                        tracing::error!("WASAPI: AEC3 handle_render_frame error: {:?}", e);
-                    }
-                }
-            }
-        } else {
-            // Microphone (capture) - buffer and process
-            self.capture_buffer.extend_from_slice(samples);
-            self.process_capture();
-        }
-    }
-
-    /// Process buffered capture samples through AEC
-    fn process_capture(&mut self) {
-        let aec_enabled = *self.aec_enabled.lock().unwrap();
-        let recording_mode = *self.recording_mode.lock().unwrap();
-
-        let frame_size = AEC_FRAME_SAMPLES * self.channels as usize;
-
-        // Process capture frames when we have enough data from both sources
-        while self.capture_buffer.len() >= frame_size && self.render_mix_buffer.len() >= frame_size
-        {
-            let capture_frame: Vec<f32> = self.capture_buffer.drain(0..frame_size).collect();
-            let render_frame: Vec<f32> = self.render_mix_buffer.drain(0..frame_size).collect();
-
-            // Apply AEC if enabled
-            let processed_capture = if aec_enabled {
-                if let Some(ref mut aec) = self.aec {
-                    let mut out = vec![0.0f32; capture_frame.len()];
-
-                    match aec.process_capture_frame(&capture_frame, false, &mut out) {
-                        Ok(_metrics) => out,
-                        Err(e) => {
-                            tracing::error!("WASAPI: AEC3 process_capture_frame error: {:?}", e);
-                            capture_frame
-                        }
-                    }
-                } else {
-                    capture_frame
-                }
-            } else {
-                capture_frame
-            };
-
-            // Generate output based on recording mode
-            let output: Vec<f32> = match recording_mode {
-                RecordingMode::Mixed => {
-                    // Mix processed capture with system audio using soft clipping
-                    processed_capture
-                        .iter()
-                        .zip(render_frame.iter())
-                        .map(|(&s1, &s2)| {
-                            let sum = s1 + s2;
-                            if sum > 1.0 {
-                                1.0 - (-2.0 * (sum - 1.0)).exp() * 0.5
-                            } else if sum < -1.0 {
-                                -1.0 + (-2.0 * (-sum - 1.0)).exp() * 0.5
-                            } else {
-                                sum
-                            }
-                        })
-                        .collect()
-                }
-                RecordingMode::EchoCancel => {
-                    // Output only the processed capture signal
-                    processed_capture
-                }
-            };
-
-            // Debug logging (periodic)
-            static LOG_COUNTER: AtomicU32 = AtomicU32::new(0);
-            let count = LOG_COUNTER.fetch_add(1, Ordering::Relaxed);
-            if count.is_multiple_of(500) {
-                let render_rms: f32 = if !render_frame.is_empty() {
-                    (render_frame.iter().map(|s| s * s).sum::<f32>() / render_frame.len() as f32)
-                        .sqrt()
-                } else {
-                    0.0
-                };
-                let out_rms: f32 = if !output.is_empty() {
-                    (output.iter().map(|s| s * s).sum::<f32>() / output.len() as f32).sqrt()
-                } else {
-                    0.0
-                };
-                tracing::debug!(
-                    "WASAPI AudioMixer: mode={:?}, aec={}, render_rms={:.4}, out_rms={:.4}",
-                    recording_mode,
-                    aec_enabled,
-                    render_rms,
-                    out_rms
-                );
-            }
-
-            // Send output
-            let _ = self.output_tx.send(WasapiAudioSamples {
-                samples: output,
-                channels: self.channels,
-            });
-        }
-    }
-}
-
 /// Run the capture thread
 fn run_capture_thread(
     cmd_rx: mpsc::Receiver<CaptureCommand>,
-    audio_tx: mpsc::Sender<WasapiAudioSamples>,
+    audio_tx: MixedSender,
     system_devices: Arc<Mutex<Vec<AudioDevice>>>,
     is_capturing: Arc<AtomicBool>,
-    aec_enabled: Arc<Mutex<bool>>,
-    recording_mode: Arc<Mutex<RecordingMode>>,
+    controls: Arc<MixerControls>,
 ) {
     tracing::info!("WASAPI: Capture thread started");
 
@@ -558,8 +406,10 @@ fn run_capture_thread(
             );
             while let Ok(cmd) = cmd_rx.try_recv() {
                 if let CaptureCommand::StartSources { result_tx, .. } = cmd {
-                    let _ =
-                        result_tx.send(Err(format!("COM initialization failed: {:?}", com_result)));
+                    let _ = result_tx.send(Err(FlowSttError::BackendUnavailable(format!(
+                        "COM initialization failed: {:?}",
+                        com_result
+                    ))));
                 }
             }
             return;
@@ -567,7 +417,7 @@ fn run_capture_thread(
         tracing::debug!("WASAPI: COM initialized on capture thread");
 
         // Create mixer (owned by this thread)
-        let mut mixer = AudioMixer::new(audio_tx, aec_enabled, recording_mode);
+        let mut mixer = AudioMixer::new("WASAPI", audio_tx, controls);
 
         // Channel for receiving samples from stream threads
         let (stream_tx, stream_rx) = mpsc::channel::<StreamSamples>();
@@ -578,7 +428,11 @@ fn run_capture_thread(
         loop {
             // Process any samples from stream threads first
             while let Ok(stream_samples) = stream_rx.try_recv() {
-                mixer.push_samples(&stream_samples.samples, stream_samples.is_loopback);
+                mixer.push_samples(
+                    &stream_samples.samples,
+                    stream_samples.is_loopback,
+                    stream_samples.timestamp,
+                );
             }
 
             let timeout = if capture_manager.is_some() {
@@ -606,17 +460,34 @@ fn run_capture_thread(
                         .map(|d| d.id.clone())
                         .collect();
 
-                    let is_loopback1 = source1_id
-                        .as_ref()
-                        .map(|id| system_ids.contains(id))
-                        .unwrap_or(false);
-                    let is_loopback2 = source2_id
-                        .as_ref()
-                        .map(|id| system_ids.contains(id))
-                        .unwrap_or(false);
+                    let is_system = |id: &String| {
+                        system_ids.contains(id) || process_loopback::process_id(id).is_some()
+                    };
+                    let is_loopback1 = source1_id.as_ref().map(is_system).unwrap_or(false);
+                    let is_loopback2 = source2_id.as_ref().map(is_system).unwrap_or(false);
+
+                    // With microphone access off, input devices deliver silence
+                    let uses_input = (source1_id.is_some() && !is_loopback1)
+                        || (source2_id.is_some() && !is_loopback2);
+                    if uses_input {
+                        if let Err(e) = permissions::require(Permission::Microphone) {
+                            tracing::error!("WASAPI: {}", e);
+                            is_capturing.store(false, Ordering::SeqCst);
+                            let _ = result_tx.send(Err(e));
+                            continue;
+                        }
+                    }
 
-                    // Count streams
+                    // Count streams; a single one may be captured as 16kHz mono
                     let num_streams = source1_id.is_some() as usize + source2_id.is_some() as usize;
+                    let speech = speech_capture(source1_id.as_deref(), source2_id.as_deref());
+                    if speech {
+                        mixer.set_sample_rate(SPEECH_SAMPLE_RATE);
+                        mixer.set_channels(1);
+                    } else {
+                        mixer.set_sample_rate(MIXER_SAMPLE_RATE);
+                        mixer.set_channels(2);
+                    }
                     mixer.set_num_streams(num_streams);
 
                     // Start capture
@@ -625,6 +496,7 @@ fn run_capture_thread(
                         is_loopback1,
                         source2_id,
                         is_loopback2,
+                        speech,
                         stream_tx.clone(),
                     ) {
                         Ok(manager) => {
@@ -682,38 +554,69 @@ impl MultiCaptureManager {
         is_loopback1: bool,
         source2_id: Option<String>,
         is_loopback2: bool,
+        speech: bool,
         stream_tx: mpsc::Sender<StreamSamples>,
-    ) -> Result<Self, String> {
-        let mut stream1 = None;
-        let mut stream2 = None;
+    ) -> Result<Self, FlowSttError> {
+        // Dropping the manager stops stream 1 if stream 2 fails to start
+        let mut manager = Self {
+            stream1: None,
+            stream2: None,
+        };
 
         // Start stream 1 if specified
         if let Some(device_id) = source1_id {
-            let stop_flag = Arc::new(AtomicBool::new(false));
-            let stop_flag_clone = Arc::clone(&stop_flag);
-            let tx = stream_tx.clone();
-
-            let handle = thread::spawn(move || {
-                run_stream_capture(device_id, is_loopback1, 1, tx, stop_flag_clone);
-            });
-
-            stream1 = Some((handle, stop_flag));
+            manager.stream1 = Some(spawn_stream(
+                device_id,
+                is_loopback1,
+                1,
+                speech,
+                stream_tx.clone(),
+            )?);
         }
 
         // Start stream 2 if specified
         if let Some(device_id) = source2_id {
-            let stop_flag = Arc::new(AtomicBool::new(false));
-            let stop_flag_clone = Arc::clone(&stop_flag);
-            let tx = stream_tx;
+            manager.stream2 = Some(spawn_stream(device_id, is_loopback2, 2, speech, stream_tx)?);
+        }
 
-            let handle = thread::spawn(move || {
-                run_stream_capture(device_id, is_loopback2, 2, tx, stop_flag_clone);
-            });
+        Ok(manager)
+    }
+}
 
-            stream2 = Some((handle, stop_flag));
-        }
+/// Spawn a stream thread and wait until its device has started
+fn spawn_stream(
+    device_id: String,
+    is_loopback: bool,
+    stream_index: usize,
+    speech: bool,
+    stream_tx: mpsc::Sender<StreamSamples>,
+) -> Result<(JoinHandle<()>, Arc<AtomicBool>), FlowSttError> {
+    let stop_flag = Arc::new(AtomicBool::new(false));
+    let stop_flag_clone = Arc::clone(&stop_flag);
+    let (ready_tx, ready_rx) = mpsc::channel();
+
+    let handle = thread::spawn(move || {
+        run_stream_capture(
+            device_id,
+            is_loopback,
+            stream_index,
+            speech,
+            stream_tx,
+            stop_flag_clone,
+            ready_tx,
+        );
+    });
 
-        Ok(Self { stream1, stream2 })
+    match ready_rx.recv() {
+        Ok(Ok(())) => Ok((handle, stop_flag)),
+        Ok(Err(e)) => {
+            let _ = handle.join();
+            Err(e)
+        }
+        Err(_) => Err(FlowSttError::Capture(format!(
+            "Stream {} capture thread exited",
+            stream_index
+        ))),
     }
 }
 
@@ -742,8 +645,10 @@ fn run_stream_capture(
     device_id: String,
     is_loopback: bool,
     stream_index: usize,
+    speech: bool,
     stream_tx: mpsc::Sender<StreamSamples>,
     stop_flag: Arc<AtomicBool>,
+    ready_tx: mpsc::Sender<Result<(), FlowSttError>>,
 ) {
     tracing::info!(
         "WASAPI: Stream {} capture thread started (device={}, loopback={})",
@@ -761,17 +666,30 @@ fn run_stream_capture(
                 stream_index,
                 com_result
             );
+            let _ = ready_tx.send(Err(FlowSttError::BackendUnavailable(format!(
+                "COM initialization failed: {:?}",
+                com_result
+            ))));
             return;
         }
 
         // Start capture
-        match start_capture(&device_id, is_loopback) {
+        match start_capture(&device_id, is_loopback, speech) {
             Ok(mut state) => {
                 tracing::info!(
                     "WASAPI: Stream {} capture started from device {}",
                     stream_index,
                     device_id
                 );
+                let _ = ready_tx.send(Ok(()));
+                if speech {
+                    // The audio engine already delivers 16kHz mono
+                    state.resampler = None;
+                    state.speech = true;
+                } else {
+                    state.selected_channels =
+                        selected_channels(&device_id, state.format.channels as usize);
+                }
 
                 // Capture loop
                 while !stop_flag.load(Ordering::SeqCst) {
@@ -789,6 +707,7 @@ fn run_stream_capture(
                     stream_index,
                     e
                 );
+                let _ = ready_tx.send(Err(e));
             }
         }
 
@@ -803,6 +722,10 @@ struct CaptureState {
     format: CaptureFormat,
     event_handle: windows::Win32::Foundation::HANDLE,
     resampler: Option<Resampler>,
+    /// Channels downmixed to mono when only some are captured
+    selected_channels: Option<Vec<usize>>,
+    /// Captured as 16kHz mono, which is passed on as it is
+    speech: bool,
 }
 
 impl Drop for CaptureState {
@@ -824,79 +747,307 @@ struct CaptureFormat {
     is_float: bool,
 }
 
-/// Start capturing from a device
-unsafe fn start_capture(device_id: &str, is_loopback: bool) -> Result<CaptureState, String> {
-    let enumerator: IMMDeviceEnumerator =
-        CoCreateInstance(&MMDeviceEnumerator, None, CLSCTX_ALL)
-            .map_err(|e| format!("Failed to create device enumerator: {}", e))?;
+/// 16kHz mono float, the format speech capture asks the audio engine for
+fn speech_wave_format() -> WAVEFORMATEX {
+    WAVEFORMATEX {
+        wFormatTag: WAVE_FORMAT_IEEE_FLOAT,
+        nChannels: 1,
+        nSamplesPerSec: SPEECH_SAMPLE_RATE,
+        nAvgBytesPerSec: SPEECH_SAMPLE_RATE * 4,
+        nBlockAlign: 4,
+        wBitsPerSample: 32,
+        cbSize: 0,
+    }
+}
+
+/// Start capturing from a device. With `speech`, the audio engine converts
+/// the device's audio to 16kHz mono.
+unsafe fn start_capture(
+    device_id: &str,
+    is_loopback: bool,
+    speech: bool,
+) -> Result<CaptureState, FlowSttError> {
+    // Per-process loopback sources are activated directly and have no mix format
+    if let Some(pid) = process_loopback::process_id(device_id) {
+        let audio_client = process_loopback::activate(pid)?;
+        let wave_format = if speech {
+            speech_wave_format()
+        } else {
+            process_loopback::capture_format()
+        };
+        return initialize_capture(
+            audio_client,
+            &wave_format,
+            AUDCLNT_STREAMFLAGS_LOOPBACK
+                | AUDCLNT_STREAMFLAGS_EVENTCALLBACK
+                | AUDCLNT_STREAMFLAGS_AUTOCONVERTPCM,
+            true,
+        );
+    }
+
+    let enumerator: IMMDeviceEnumerator = CoCreateInstance(&MMDeviceEnumerator, None, CLSCTX_ALL)
+        .map_err(|e| {
+        FlowSttError::BackendUnavailable(format!("Failed to create device enumerator: {}", e))
+    })?;
 
     let device_id_wide: Vec<u16> = device_id.encode_utf16().chain(std::iter::once(0)).collect();
     let device: IMMDevice = enumerator
         .GetDevice(PCWSTR(device_id_wide.as_ptr()))
-        .map_err(|e| format!("Failed to get device {}: {}", device_id, e))?;
+        .map_err(|e| {
+            tracing::warn!("WASAPI: Failed to get device {}: {}", device_id, e);
+            FlowSttError::DeviceNotFound(device_id.to_string())
+        })?;
 
-    let audio_client: IAudioClient = device
-        .Activate(CLSCTX_ALL, None)
-        .map_err(|e| format!("Failed to activate audio client: {}", e))?;
+    let audio_client = activate_client(&device, is_loopback)?;
 
     let mix_format_ptr = audio_client
         .GetMixFormat()
-        .map_err(|e| format!("Failed to get mix format: {}", e))?;
-
-    let mix_format = &*mix_format_ptr;
-    let format = parse_wave_format(mix_format)?;
-
-    tracing::debug!(
-        "WASAPI: Device format: {}Hz, {} channels, {} bits, float={}, loopback={}",
-        format.sample_rate,
-        format.channels,
-        format.bits_per_sample,
-        format.is_float,
-        is_loopback
-    );
-
-    let event_handle = CreateEventW(None, false, false, None)
-        .map_err(|e| format!("Failed to create event: {}", e))?;
-
-    let buffer_duration: i64 = 1_000_000; // 100ms
+        .map_err(|e| FlowSttError::Capture(format!("Failed to get mix format: {}", e)))?;
 
     // Use loopback flag for system audio capture
-    let stream_flags = if is_loopback {
+    let mut stream_flags = if is_loopback {
         AUDCLNT_STREAMFLAGS_LOOPBACK | AUDCLNT_STREAMFLAGS_EVENTCALLBACK
     } else {
         AUDCLNT_STREAMFLAGS_EVENTCALLBACK
     };
 
+    // Speech capture has the engine convert from the mix format
+    let speech_format = speech_wave_format();
+    let capture_format: *const WAVEFORMATEX = if speech {
+        stream_flags |=
+            AUDCLNT_STREAMFLAGS_AUTOCONVERTPCM | AUDCLNT_STREAMFLAGS_SRC_DEFAULT_QUALITY;
+        &speech_format
+    } else {
+        mix_format_ptr
+    };
+
+    // Raw and exclusive streams get a client of their own, keeping the first
+    // one untouched for the shared-mode fallback
+    let mode = if is_loopback {
+        CaptureMode::Shared
+    } else {
+        *CAPTURE_MODE.lock().unwrap()
+    };
+    let attempt = match mode {
+        CaptureMode::Shared => None,
+        CaptureMode::Raw => Some(activate_client(&device, false).and_then(|client| {
+            enable_raw_mode(&client)?;
+            initialize_capture(client, capture_format, stream_flags, false)
+        })),
+        // Exclusive streams get the device's own format, with no conversion
+        CaptureMode::Exclusive if speech => {
+            tracing::info!(
+                "WASAPI: Exclusive mode can't capture {} as 16kHz mono; using shared mode",
+                device_id
+            );
+            None
+        }
+        CaptureMode::Exclusive => Some(initialize_exclusive(&device, mix_format_ptr)),
+    };
+
+    let result = match attempt {
+        Some(Ok(state)) => {
+            tracing::info!("WASAPI: Capturing {} in {} mode", device_id, mode.name());
+            Ok(state)
+        }
+        Some(Err(e)) => {
+            tracing::warn!(
+                "WASAPI: {} mode failed for {} ({}); using shared mode",
+                mode.name(),
+                device_id,
+                e
+            );
+            initialize_capture(audio_client, capture_format, stream_flags, is_loopback)
+        }
+        None => initialize_capture(audio_client, capture_format, stream_flags, is_loopback),
+    };
+
+    windows::Win32::System::Com::CoTaskMemFree(Some(mix_format_ptr as *const _ as *const _));
+
+    result
+}
+
+/// Activate an audio client on a device
+unsafe fn activate_client(
+    device: &IMMDevice,
+    is_loopback: bool,
+) -> Result<IAudioClient, FlowSttError> {
+    device.Activate(CLSCTX_ALL, None).map_err(|e| {
+        match permissions::require(Permission::Microphone) {
+            Err(reason) if !is_loopback && e.code() == E_ACCESSDENIED => reason,
+            _ => FlowSttError::Capture(format!("Failed to activate audio client: {}", e)),
+        }
+    })
+}
+
+/// Ask for a raw stream, bypassing the device's effects. Must be called
+/// before the client is initialized.
+unsafe fn enable_raw_mode(audio_client: &IAudioClient) -> Result<(), FlowSttError> {
+    let client: IAudioClient2 = audio_client
+        .cast()
+        .map_err(|e| FlowSttError::Capture(format!("Raw mode needs IAudioClient2: {}", e)))?;
+    let properties = AudioClientProperties {
+        cbSize: std::mem::size_of::<AudioClientProperties>() as u32,
+        bIsOffload: false.into(),
+        eCategory: AudioCategory_Other,
+        Options: AUDCLNT_STREAMOPTIONS_RAW,
+    };
+    client
+        .SetClientProperties(&properties)
+        .map_err(|e| FlowSttError::Capture(format!("Failed to request raw mode: {}", e)))
+}
+
+/// Initialize and start an input device in exclusive mode.
+///
+/// Exclusive streams bypass the audio engine, so the device has to accept
+/// the format as-is: its mix format if it can, otherwise 16-bit PCM at the
+/// same rate. The buffer is one device period, at least the minimum period
+/// and otherwise the configured capture buffer.
+unsafe fn initialize_exclusive(
+    device: &IMMDevice,
+    mix_format: *const WAVEFORMATEX,
+) -> Result<CaptureState, FlowSttError> {
+    let audio_client = activate_client(device, false)?;
+
+    let mix = &*mix_format;
+    let pcm16 = WAVEFORMATEX {
+        wFormatTag: WAVE_FORMAT_PCM,
+        nChannels: mix.nChannels,
+        nSamplesPerSec: mix.nSamplesPerSec,
+        nAvgBytesPerSec: mix.nSamplesPerSec * mix.nChannels as u32 * 2,
+        nBlockAlign: mix.nChannels * 2,
+        wBitsPerSample: 16,
+        cbSize: 0,
+    };
+    let wave_format = [mix_format, &pcm16 as *const WAVEFORMATEX]
+        .into_iter()
+        .find(|&format| {
+            audio_client.IsFormatSupported(AUDCLNT_SHAREMODE_EXCLUSIVE, format, None) == S_OK
+        })
+        .ok_or_else(|| {
+            FlowSttError::Capture(
+                "the device accepts neither its mix format nor 16-bit PCM".to_string(),
+            )
+        })?;
+    let format = parse_wave_format(&*wave_format)?;
+
+    let mut min_period: i64 = 0;
+    audio_client
+        .GetDevicePeriod(None, Some(&mut min_period))
+        .map_err(|e| FlowSttError::Capture(format!("Failed to get device period: {}", e)))?;
+    let period = capture_buffer_ms().map_or(min_period, |ms| (ms as i64 * 10_000).max(min_period));
+
+    // Event-driven exclusive streams use the same buffer duration and period
+    let audio_client = match audio_client.Initialize(
+        AUDCLNT_SHAREMODE_EXCLUSIVE,
+        AUDCLNT_STREAMFLAGS_EVENTCALLBACK,
+        period,
+        period,
+        wave_format,
+        None,
+    ) {
+        Ok(()) => audio_client,
+        Err(e) if e.code() == AUDCLNT_E_BUFFER_SIZE_NOT_ALIGNED => {
+            // Retry on a new client with the aligned size the device reported
+            let frames = audio_client.GetBufferSize().map_err(|e| {
+                FlowSttError::Capture(format!("Failed to get aligned buffer size: {}", e))
+            })?;
+            let aligned = (10_000_000.0 * frames as f64 / format.sample_rate as f64).round() as i64;
+            let audio_client = activate_client(device, false)?;
+            audio_client
+                .Initialize(
+                    AUDCLNT_SHAREMODE_EXCLUSIVE,
+                    AUDCLNT_STREAMFLAGS_EVENTCALLBACK,
+                    aligned,
+                    aligned,
+                    wave_format,
+                    None,
+                )
+                .map_err(|e| {
+                    FlowSttError::Capture(format!("Failed to initialize exclusive stream: {}", e))
+                })?;
+            audio_client
+        }
+        Err(e) => {
+            return Err(FlowSttError::Capture(format!(
+                "Failed to initialize exclusive stream: {}",
+                e
+            )))
+        }
+    };
+
+    start_stream(audio_client, format, false)
+}
+
+/// Initialize an activated audio client in shared mode with the given format
+/// and start it
+unsafe fn initialize_capture(
+    audio_client: IAudioClient,
+    wave_format: *const WAVEFORMATEX,
+    stream_flags: u32,
+    is_loopback: bool,
+) -> Result<CaptureState, FlowSttError> {
+    let format = parse_wave_format(&*wave_format)?;
+
+    // Buffer duration in 100ns units; 100ms unless configured
+    let buffer_duration: i64 = capture_buffer_ms().map_or(1_000_000, |ms| ms as i64 * 10_000);
+
     audio_client
         .Initialize(
             AUDCLNT_SHAREMODE_SHARED,
             stream_flags,
             buffer_duration,
             0,
-            mix_format_ptr,
+            wave_format,
             None,
         )
-        .map_err(|e| format!("Failed to initialize audio client: {}", e))?;
+        .map_err(|e| FlowSttError::Capture(format!("Failed to initialize audio client: {}", e)))?;
+
+    start_stream(audio_client, format, is_loopback)
+}
+
+/// Start an initialized audio client, delivering buffers through an event
+unsafe fn start_stream(
+    audio_client: IAudioClient,
+    format: CaptureFormat,
+    is_loopback: bool,
+) -> Result<CaptureState, FlowSttError> {
+    tracing::debug!(
+        "WASAPI: Device format: {}Hz, {} channels, {} bits, float={}, loopback={}",
+        format.sample_rate,
+        format.channels,
+        format.bits_per_sample,
+        format.is_float,
+        is_loopback
+    );
+    report_source_format(is_loopback, format.sample_rate, format.channels);
+
+    let event_handle = CreateEventW(None, false, false, None)
+        .map_err(|e| FlowSttError::Capture(format!("Failed to create event: {}", e)))?;
+
+    // The engine may round the buffer up to its period
+    match audio_client.GetBufferSize() {
+        Ok(frames) => report_capture_latency(is_loopback, frames as usize, format.sample_rate),
+        Err(e) => tracing::warn!("WASAPI: Failed to get buffer size: {}", e),
+    }
 
     audio_client
         .SetEventHandle(event_handle)
-        .map_err(|e| format!("Failed to set event handle: {}", e))?;
+        .map_err(|e| FlowSttError::Capture(format!("Failed to set event handle: {}", e)))?;
 
     let capture_client: IAudioCaptureClient = audio_client
         .GetService()
-        .map_err(|e| format!("Failed to get capture client: {}", e))?;
+        .map_err(|e| FlowSttError::Capture(format!("Failed to get capture client: {}", e)))?;
 
-    let resampler = if format.sample_rate != TARGET_SAMPLE_RATE {
-        Some(Resampler::new(format.sample_rate, TARGET_SAMPLE_RATE))
+    let resampler = if format.sample_rate != MIXER_SAMPLE_RATE {
+        Some(Resampler::new(format.sample_rate, MIXER_SAMPLE_RATE))
     } else {
         None
     };
 
     audio_client
         .Start()
-        .map_err(|e| format!("Failed to start capture: {}", e))?;
-
-    windows::Win32::System::Com::CoTaskMemFree(Some(mix_format_ptr as *const _ as *const _));
+        .map_err(|e| FlowSttError::Capture(format!("Failed to start capture: {}", e)))?;
 
     Ok(CaptureState {
         audio_client,
@@ -904,11 +1055,13 @@ unsafe fn start_capture(device_id: &str, is_loopback: bool) -> Result<CaptureSta
         format,
         event_handle,
         resampler,
+        selected_channels: None,
+        speech: false,
     })
 }
 
 /// Parse WAVEFORMATEX into CaptureFormat
-fn parse_wave_format(format: &WAVEFORMATEX) -> Result<CaptureFormat, String> {
+fn parse_wave_format(format: &WAVEFORMATEX) -> Result<CaptureFormat, FlowSttError> {
     let is_float;
     let bits_per_sample;
 
@@ -932,7 +1085,10 @@ fn parse_wave_format(format: &WAVEFORMATEX) -> Result<CaptureFormat, String> {
         is_float = false;
         bits_per_sample = bits;
     } else {
-        return Err(format!("Unsupported audio format tag: {}", format_tag));
+        return Err(FlowSttError::Capture(format!(
+            "Unsupported audio format tag: {}",
+            format_tag
+        )));
     }
 
     Ok(CaptureFormat {
@@ -948,7 +1104,7 @@ unsafe fn process_capture(
     state: &mut CaptureState,
     is_loopback: bool,
     stream_tx: &mpsc::Sender<StreamSamples>,
-) -> Result<(), String> {
+) -> Result<(), FlowSttError> {
     let wait_result = WaitForSingleObject(state.event_handle, 10);
     if wait_result.0 != 0 {
         return Ok(());
@@ -958,19 +1114,28 @@ unsafe fn process_capture(
         let mut buffer_ptr: *mut u8 = std::ptr::null_mut();
         let mut num_frames: u32 = 0;
         let mut flags: u32 = 0;
+        let mut qpc_position: u64 = 0;
 
         let result = state.capture_client.GetBuffer(
             &mut buffer_ptr,
             &mut num_frames,
             &mut flags,
             None,
-            None,
+            Some(&mut qpc_position),
         );
 
         if result.is_err() || num_frames == 0 {
             break;
         }
 
+        // The device's capture time of the first frame, or the arrival time
+        // when the device couldn't provide one
+        let timestamp = if qpc_position == 0 || flags & AUDCLNT_BUFFERFLAGS_TIMESTAMP_ERROR != 0 {
+            clock::before_now(num_frames as usize, state.format.sample_rate)
+        } else {
+            clock::from_qpc_position(qpc_position)
+        };
+
         let samples = convert_to_f32(buffer_ptr, num_frames as usize, &state.format);
 
         let _ = state.capture_client.ReleaseBuffer(num_frames);
@@ -979,15 +1144,24 @@ unsafe fn process_capture(
             continue;
         }
 
+        // Keep only the selected channels
+        let (samples, channels) = match &state.selected_channels {
+            Some(selected) => (
+                downmix_channels(&samples, state.format.channels as usize, selected),
+                2,
+            ),
+            None => (samples, state.format.channels as usize),
+        };
+
         // Resample if needed
         let final_samples = if let Some(ref mut resampler) = state.resampler {
-            resampler.process(&samples, state.format.channels as usize)
+            resampler.process(&samples, channels)
         } else {
             samples
         };
 
         // Convert mono to stereo if needed
-        let stereo_samples = if state.format.channels == 1 {
+        let stereo_samples = if channels == 1 && !state.speech {
             mono_to_stereo(&final_samples)
         } else {
             final_samples
@@ -997,6 +1171,7 @@ unsafe fn process_capture(
         let _ = stream_tx.send(StreamSamples {
             samples: stereo_samples,
             is_loopback,
+            timestamp,
         });
     }
 
@@ -1038,81 +1213,3 @@ unsafe fn convert_to_f32(buffer: *const u8, num_frames: usize, format: &CaptureF
         Vec::new()
     }
 }
-
-/// Convert mono audio to stereo by duplicating channels
-fn mono_to_stereo(mono: &[f32]) -> Vec<f32> {
-    let mut stereo = Vec::with_capacity(mono.len() * 2);
-    for &sample in mono {
-        stereo.push(sample);
-        stereo.push(sample);
-    }
-    stereo
-}
-
-/// Simple linear resampler
-struct Resampler {
-    source_rate: u32,
-    target_rate: u32,
-    buffer: Vec<f32>,
-    position: f64,
-}
-
-impl Resampler {
-    fn new(source_rate: u32, target_rate: u32) -> Self {
-        Self {
-            source_rate,
-            target_rate,
-            buffer: Vec::new(),
-            position: 0.0,
-        }
-    }
-
-    fn process(&mut self, samples: &[f32], channels: usize) -> Vec<f32> {
-        self.buffer.extend_from_slice(samples);
-
-        let ratio = self.source_rate as f64 / self.target_rate as f64;
-        let input_frames = self.buffer.len() / channels;
-        let output_frames = ((input_frames as f64 - self.position) / ratio) as usize;
-
-        if output_frames == 0 {
-            return Vec::new();
-        }
-
-        let mut output = Vec::with_capacity(output_frames * channels);
-
-        for _ in 0..output_frames {
-            let src_frame = self.position as usize;
-            let frac = self.position - src_frame as f64;
-
-            for ch in 0..channels {
-                let idx0 = src_frame * channels + ch;
-                let idx1 = (src_frame + 1) * channels + ch;
-
-                let sample = if idx1 < self.buffer.len() {
-                    self.buffer[idx0] * (1.0 - frac as f32) + self.buffer[idx1] * frac as f32
-                } else if idx0 < self.buffer.len() {
-                    self.buffer[idx0]
-                } else {
-                    0.0
-                };
-                output.push(sample);
-            }
-
-            self.position += ratio;
-        }
-
-        let consumed_frames = self.position as usize;
-        if consumed_frames > 0 {
-            let consumed_samples = consumed_frames * channels;
-            if consumed_samples < self.buffer.len() {
-                self.buffer.drain(0..consumed_samples);
-                self.position -= consumed_frames as f64;
-            } else {
-                self.buffer.clear();
-                self.position = 0.0;
-            }
-        }
-
-        output
-    }
-}
@@ -0,0 +1,295 @@
+//! Playback of saved recordings.
+//!
+//! One recording plays at a time through the default output device (PipeWire
+//! or ALSA, WASAPI or CoreAudio, through cpal). While it plays, a
+//! `playback_position` event is broadcast a few times per second with the index
+//! of the word being spoken, so an editor can highlight it; a
+//! `playback_stopped` event follows when it ends or is stopped.
+
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{FromSample, SampleFormat, SizedSample, StreamConfig};
+use flowstt_common::ipc::{EventType, Response};
+use flowstt_common::{PlaybackStatus, WordTiming};
+use tracing::{info, warn};
+
+use crate::audio::{convert_to_mono, read_wav, resample};
+use crate::ipc::broadcast_event;
+
+/// How often the playback position is broadcast
+const POSITION_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The recording being played.
+struct Playback {
+    /// Distinguishes this playback from later ones
+    id: u64,
+    session_id: Option<String>,
+    path: String,
+    words: Vec<WordTiming>,
+    /// Output frames per second
+    rate: u32,
+    /// Length in frames
+    len: usize,
+    /// Next frame to play
+    position: Arc<AtomicUsize>,
+    paused: Arc<AtomicBool>,
+    stop: Arc<AtomicBool>,
+}
+
+impl Playback {
+    fn status(&self) -> PlaybackStatus {
+        let position = self.position.load(Ordering::Relaxed).min(self.len);
+        let position_ms = frames_to_ms(position, self.rate);
+        PlaybackStatus {
+            session_id: self.session_id.clone(),
+            path: self.path.clone(),
+            position_ms,
+            duration_ms: frames_to_ms(self.len, self.rate),
+            word_index: word_at(&self.words, position_ms),
+            paused: self.paused.load(Ordering::Relaxed),
+        }
+    }
+}
+
+static CURRENT: Mutex<Option<Playback>> = Mutex::new(None);
+
+/// Source of playback IDs
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Start playing the WAV file at `path` from `position_ms`, stopping anything
+/// already playing. `words` are the session's word timings, used to report the
+/// word being spoken.
+///
+/// Blocks while the file is decoded and the output device opened.
+pub fn play(
+    path: &Path,
+    session_id: Option<String>,
+    words: Vec<WordTiming>,
+    position_ms: u64,
+) -> Result<PlaybackStatus, String> {
+    if !path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("wav"))
+    {
+        return Err(format!(
+            "Only WAV recordings can be played: {}",
+            path.display()
+        ));
+    }
+    let raw = read_wav(path)?;
+    let mono = if raw.channels > 1 {
+        convert_to_mono(&raw.samples, raw.channels as usize)
+    } else {
+        raw.samples
+    };
+
+    stop();
+
+    let device = cpal::default_host()
+        .default_output_device()
+        .ok_or("No default output device")?;
+    let supported = device
+        .default_output_config()
+        .map_err(|e| format!("Failed to query output device: {}", e))?;
+    let rate = supported.sample_rate().0;
+    let samples = Arc::new(resample(&mono, raw.sample_rate, rate));
+
+    let playback = Playback {
+        id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+        session_id,
+        path: path.to_string_lossy().to_string(),
+        words,
+        rate,
+        len: samples.len(),
+        position: Arc::new(AtomicUsize::new(ms_to_frames(position_ms, rate))),
+        paused: Arc::new(AtomicBool::new(false)),
+        stop: Arc::new(AtomicBool::new(false)),
+    };
+    let id = playback.id;
+    let shared = (playback.position.clone(), playback.paused.clone());
+    let stop_flag = playback.stop.clone();
+
+    // cpal streams can't move between threads, so the stream lives on the
+    // thread that reports progress
+    let (ready_tx, ready_rx) = mpsc::channel();
+    thread::spawn(move || {
+        let config: StreamConfig = supported.config();
+        let stream = match supported.sample_format() {
+            SampleFormat::F32 => build_stream::<f32>(&device, &config, samples, shared),
+            SampleFormat::I16 => build_stream::<i16>(&device, &config, samples, shared),
+            SampleFormat::U16 => build_stream::<u16>(&device, &config, samples, shared),
+            other => Err(format!("Unsupported output sample format: {}", other)),
+        }
+        .and_then(|stream| {
+            stream
+                .play()
+                .map_err(|e| format!("Failed to start output stream: {}", e))?;
+            Ok(stream)
+        });
+        let stream = match stream {
+            Ok(stream) => {
+                let _ = ready_tx.send(Ok(()));
+                stream
+            }
+            Err(e) => {
+                let _ = ready_tx.send(Err(e));
+                return;
+            }
+        };
+
+        report_progress(id, &stop_flag);
+        drop(stream);
+    });
+
+    ready_rx
+        .recv()
+        .map_err(|_| "Playback thread exited".to_string())??;
+
+    let status = playback.status();
+    info!("Playing {} from {}ms", status.path, status.position_ms);
+    *CURRENT.lock().unwrap() = Some(playback);
+    Ok(status)
+}
+
+/// Broadcast the position of playback `id` until it finishes or is stopped.
+/// Nothing is sent while it is paused.
+fn report_progress(id: u64, stop: &AtomicBool) {
+    let mut last: Option<PlaybackStatus> = None;
+    while !stop.load(Ordering::Relaxed) {
+        thread::sleep(POSITION_INTERVAL);
+        let current = CURRENT.lock().unwrap();
+        let Some(playback) = current.as_ref().filter(|p| p.id == id) else {
+            // Not registered yet
+            continue;
+        };
+        let status = playback.status();
+        let finished = status.position_ms >= status.duration_ms;
+        drop(current);
+
+        if last.as_ref() == Some(&status) {
+            continue;
+        }
+        last = Some(status.clone());
+        broadcast_event(Response::Event {
+            event: EventType::PlaybackPosition(status),
+        });
+        if finished {
+            break;
+        }
+    }
+
+    let mut current = CURRENT.lock().unwrap();
+    let session_id = match current.take() {
+        Some(playback) if playback.id == id => playback.session_id,
+        // Replaced by a newer playback, which announces itself
+        other => {
+            *current = other;
+            return;
+        }
+    };
+    drop(current);
+    broadcast_event(Response::Event {
+        event: EventType::PlaybackStopped { session_id },
+    });
+}
+
+/// Move the current playback to `position_ms`.
+pub fn seek(position_ms: u64) -> Result<PlaybackStatus, String> {
+    let current = CURRENT.lock().unwrap();
+    let playback = current.as_ref().ok_or("Nothing is playing")?;
+    let frame = ms_to_frames(position_ms, playback.rate).min(playback.len);
+    playback.position.store(frame, Ordering::Relaxed);
+    Ok(playback.status())
+}
+
+/// Pause or resume the current playback.
+pub fn set_paused(paused: bool) -> Result<PlaybackStatus, String> {
+    let current = CURRENT.lock().unwrap();
+    let playback = current.as_ref().ok_or("Nothing is playing")?;
+    playback.paused.store(paused, Ordering::Relaxed);
+    Ok(playback.status())
+}
+
+/// Stop the current playback, if any.
+pub fn stop() {
+    if let Some(playback) = CURRENT.lock().unwrap().as_ref() {
+        playback.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Output stream that plays `samples` on every channel from `position`,
+/// then silence. Silence is also played while `paused` is set.
+fn build_stream<T>(
+    device: &cpal::Device,
+    config: &StreamConfig,
+    samples: Arc<Vec<f32>>,
+    (position, paused): (Arc<AtomicUsize>, Arc<AtomicBool>),
+) -> Result<cpal::Stream, String>
+where
+    T: SizedSample + FromSample<f32>,
+{
+    let channels = config.channels as usize;
+    device
+        .build_output_stream(
+            config,
+            move |data: &mut [T], _| {
+                if paused.load(Ordering::Relaxed) {
+                    data.fill(T::EQUILIBRIUM);
+                    return;
+                }
+                let start = position.load(Ordering::Relaxed);
+                let mut next = start;
+                for frame in data.chunks_mut(channels) {
+                    let sample = samples.get(next).copied().unwrap_or(0.0);
+                    frame.fill(T::from_sample(sample));
+                    next = (next + 1).min(samples.len());
+                }
+                // A seek that raced this buffer wins
+                let _ =
+                    position.compare_exchange(start, next, Ordering::Relaxed, Ordering::Relaxed);
+            },
+            |e| warn!("Playback stream error: {}", e),
+            None,
+        )
+        .map_err(|e| format!("Failed to open output stream: {}", e))
+}
+
+fn frames_to_ms(frames: usize, rate: u32) -> u64 {
+    frames as u64 * 1000 / rate as u64
+}
+
+fn ms_to_frames(ms: u64, rate: u32) -> usize {
+    (ms * rate as u64 / 1000) as usize
+}
+
+/// Index of the word being spoken at `position_ms`; None between words.
+fn word_at(words: &[WordTiming], position_ms: u64) -> Option<usize> {
+    let index = words.iter().rposition(|w| w.start_ms <= position_ms)?;
+    (position_ms < words[index].end_ms).then_some(index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_word_at() {
+        let word = |start_ms, end_ms| WordTiming {
+            word: "word".to_string(),
+            start_ms,
+            end_ms,
+            probability: 1.0,
+        };
+        let words = [word(0, 400), word(500, 900), word(900, 1200)];
+        assert_eq!(word_at(&words, 0), Some(0));
+        assert_eq!(word_at(&words, 450), None);
+        assert_eq!(word_at(&words, 900), Some(2));
+        assert_eq!(word_at(&words, 1200), None);
+        assert_eq!(word_at(&[], 100), None);
+    }
+}
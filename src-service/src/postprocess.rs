@@ -0,0 +1,721 @@
+//! Post-processing of transcribed text.
+//!
+//! Completed and partial transcripts pass through a configurable pipeline
+//! before any event is emitted: spelled-out numbers are converted to digits,
+//! profanity is masked, missing punctuation and capitalization are restored
+//! (only in the output modes that want it) and custom regex replacements from
+//! the config are applied. The transcript records whether the pipeline changed its text so
+//! clients can tell edited output from what the model produced.
+//!
+//! The technical profile replaces number conversion and punctuation with a
+//! reading suited to code and terminals: spoken symbols become characters
+//! ("foo underscore bar open paren close paren" -> "foo_bar()"), every number
+//! becomes digits, and Whisper's own punctuation is dropped. "literal" keeps the
+//! word after it as spoken, so "literal dot" types "dot".
+
+use flowstt_common::OutputMode;
+use regex::{Captures, Regex};
+use std::sync::Mutex;
+
+use crate::config::{PostProcessConfig, TextProfile};
+use crate::output;
+use crate::transcription::{Transcript, NO_SPEECH_TEXT};
+
+/// Words masked when profanity filtering is enabled
+const PROFANITY: &[&str] = &[
+    "ass",
+    "asshole",
+    "assholes",
+    "bastard",
+    "bastards",
+    "bitch",
+    "bitches",
+    "bullshit",
+    "crap",
+    "cunt",
+    "damn",
+    "dick",
+    "dickhead",
+    "fuck",
+    "fucked",
+    "fucker",
+    "fuckers",
+    "fucking",
+    "goddamn",
+    "motherfucker",
+    "motherfuckers",
+    "piss",
+    "pissed",
+    "shit",
+    "shits",
+    "shitty",
+    "slut",
+    "twat",
+    "wanker",
+    "whore",
+];
+
+/// Words that mark a sentence as a question when they open it
+const QUESTION_WORDS: &[&str] = &[
+    "am", "are", "can", "could", "did", "do", "does", "has", "have", "how", "is", "may", "shall",
+    "should", "was", "were", "what", "when", "where", "which", "who", "why", "will", "would",
+];
+
+/// Word that keeps the next word from being read as a symbol or number
+const LITERAL_WORD: &str = "literal";
+
+/// How a spoken symbol is spaced against the words around it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Spacing {
+    /// Spaces on both sides, like a word ("a = b")
+    Spaced,
+    /// Attached to both neighbours ("foo_bar")
+    Join,
+    /// Attached to the word before ("f(x), y")
+    Left,
+    /// Attached to the word after ("-la")
+    Right,
+    /// Opens a quotation the first time and closes it the next
+    Quote,
+}
+
+/// Spoken names of symbols in the technical profile
+const SYMBOLS: &[(&str, &str, Spacing)] = &[
+    ("open paren", "(", Spacing::Join),
+    ("close paren", ")", Spacing::Left),
+    ("open bracket", "[", Spacing::Join),
+    ("close bracket", "]", Spacing::Left),
+    ("open brace", "{", Spacing::Spaced),
+    ("close brace", "}", Spacing::Spaced),
+    ("open angle", "<", Spacing::Join),
+    ("close angle", ">", Spacing::Left),
+    ("less than", "<", Spacing::Spaced),
+    ("greater than", ">", Spacing::Spaced),
+    ("underscore", "_", Spacing::Join),
+    ("dot", ".", Spacing::Join),
+    ("point", ".", Spacing::Join),
+    ("comma", ",", Spacing::Left),
+    ("colon", ":", Spacing::Left),
+    ("double colon", "::", Spacing::Join),
+    ("semicolon", ";", Spacing::Left),
+    ("slash", "/", Spacing::Join),
+    ("backslash", "\\", Spacing::Join),
+    ("dash", "-", Spacing::Right),
+    ("hyphen", "-", Spacing::Join),
+    ("minus", "-", Spacing::Spaced),
+    ("plus", "+", Spacing::Spaced),
+    ("star", "*", Spacing::Spaced),
+    ("asterisk", "*", Spacing::Spaced),
+    ("equals", "=", Spacing::Spaced),
+    ("double equals", "==", Spacing::Spaced),
+    ("not equals", "!=", Spacing::Spaced),
+    ("arrow", "->", Spacing::Spaced),
+    ("fat arrow", "=>", Spacing::Spaced),
+    ("pipe", "|", Spacing::Spaced),
+    ("ampersand", "&", Spacing::Spaced),
+    ("percent", "%", Spacing::Left),
+    ("caret", "^", Spacing::Join),
+    ("tilde", "~", Spacing::Right),
+    ("bang", "!", Spacing::Right),
+    ("exclamation mark", "!", Spacing::Left),
+    ("question mark", "?", Spacing::Left),
+    ("hash", "#", Spacing::Right),
+    ("at sign", "@", Spacing::Right),
+    ("dollar sign", "$", Spacing::Right),
+    ("quote", "\"", Spacing::Quote),
+    ("double quote", "\"", Spacing::Quote),
+    ("single quote", "'", Spacing::Quote),
+    ("backtick", "`", Spacing::Quote),
+    ("space", " ", Spacing::Join),
+    ("tab", "\t", Spacing::Join),
+    ("new line", "\n", Spacing::Join),
+    ("newline", "\n", Spacing::Join),
+];
+
+/// Active pipeline (`None` when every step is disabled)
+static PROCESSOR: Mutex<Option<PostProcessor>> = Mutex::new(None);
+
+/// Replace the post-processing configuration.
+///
+/// Replacement patterns that fail to compile are logged and skipped.
+pub fn set_config(config: &PostProcessConfig) {
+    let processor = PostProcessor::new(config);
+    *PROCESSOR.lock().unwrap() = (!processor.is_empty()).then_some(processor);
+}
+
+/// Run the pipeline over a transcript, marking it as modified if its text changed.
+pub fn apply(transcript: &mut Transcript) {
+    let guard = PROCESSOR.lock().unwrap();
+    let Some(processor) = guard.as_ref() else {
+        return;
+    };
+    if transcript.text == NO_SPEECH_TEXT {
+        return;
+    }
+    let punctuate = processor
+        .restore_punctuation
+        .contains(&transcript.output.unwrap_or_else(output::get_output_mode));
+
+    for segment in &mut transcript.segments {
+        segment.text = processor.process(&segment.text, punctuate);
+    }
+    for segment in &mut transcript.confidence {
+        segment.text = processor.process(&segment.text, punctuate);
+    }
+    // Word timings are masked too so filtered words don't leak through them
+    if let Some(profanity) = &processor.profanity {
+        for word in &mut transcript.words {
+            word.word = mask_profanity(profanity, &word.word);
+        }
+    }
+
+    let text = processor.process(&transcript.text, punctuate);
+    if text != transcript.text {
+        transcript.text = text;
+        transcript.modified = true;
+    }
+}
+
+/// Compiled post-processing steps.
+struct PostProcessor {
+    profile: TextProfile,
+    normalize_numbers: bool,
+    profanity: Option<Regex>,
+    restore_punctuation: Vec<OutputMode>,
+    replacements: Vec<(Regex, String)>,
+}
+
+impl PostProcessor {
+    fn new(config: &PostProcessConfig) -> Self {
+        let profanity = config.mask_profanity.then(|| {
+            let words: Vec<String> = PROFANITY
+                .iter()
+                .copied()
+                .chain(config.profanity_words.iter().map(String::as_str))
+                .map(|word| word.trim())
+                .filter(|word| !word.is_empty())
+                .map(regex::escape)
+                .collect();
+            Regex::new(&format!(r"(?i)\b(?:{})\b", words.join("|")))
+                .expect("escaped word list is a valid pattern")
+        });
+
+        let replacements = config
+            .replacements
+            .iter()
+            .filter_map(|r| match Regex::new(&r.pattern) {
+                Ok(regex) => Some((regex, r.replacement.clone())),
+                Err(e) => {
+                    tracing::warn!(
+                        "Ignoring invalid replacement pattern {:?}: {}",
+                        r.pattern,
+                        e
+                    );
+                    None
+                }
+            })
+            .collect();
+
+        Self {
+            profile: config.profile,
+            normalize_numbers: config.normalize_numbers,
+            profanity,
+            restore_punctuation: config.restore_punctuation.clone(),
+            replacements,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.profile == TextProfile::Prose
+            && !self.normalize_numbers
+            && self.profanity.is_none()
+            && self.restore_punctuation.is_empty()
+            && self.replacements.is_empty()
+    }
+
+    fn process(&self, text: &str, punctuate: bool) -> String {
+        let technical = self.profile == TextProfile::Technical;
+        let mut text = if technical {
+            technical_text(text)
+        } else if self.normalize_numbers {
+            normalize_numbers(text)
+        } else {
+            text.to_string()
+        };
+        if let Some(profanity) = &self.profanity {
+            text = mask_profanity(profanity, &text);
+        }
+        if punctuate && !technical {
+            text = restore_punctuation(&text);
+        }
+        for (regex, replacement) in &self.replacements {
+            text = regex.replace_all(&text, replacement.as_str()).into_owned();
+        }
+        text
+    }
+}
+
+/// Replace all but the first character of each matched word with `*`.
+fn mask_profanity(profanity: &Regex, text: &str) -> String {
+    profanity
+        .replace_all(text, |caps: &Captures| {
+            let mut chars = caps[0].chars();
+            let first = chars.next().map(String::from).unwrap_or_default();
+            first + &"*".repeat(chars.count())
+        })
+        .into_owned()
+}
+
+/// Capitalize sentence starts and "I", and end unterminated text with a period
+/// (or a question mark when it opens with a question word).
+///
+/// Whisper usually punctuates well on its own; this mostly fixes up the short
+/// segments push-to-talk produces, so existing punctuation is left in place.
+fn restore_punctuation(text: &str) -> String {
+    let mut words: Vec<String> = Vec::new();
+    let mut sentence_start = 0;
+
+    for token in text.split_whitespace() {
+        let (lead, core, trail) = split_punctuation(token);
+        let lower = core.to_lowercase();
+        let starts_sentence = words
+            .last()
+            .is_none_or(|prev| prev.ends_with(['.', '!', '?']));
+        if starts_sentence {
+            sentence_start = words.len();
+        }
+
+        let mut word = token.to_string();
+        if starts_sentence || lower == "i" || lower.starts_with("i'") {
+            if let Some(first) = core.chars().next().filter(|c| c.is_lowercase()) {
+                let upper: String = first.to_uppercase().collect();
+                word = format!("{}{}{}{}", lead, upper, &core[first.len_utf8()..], trail);
+            }
+        }
+        words.push(word);
+    }
+
+    let Some(last) = words.last_mut() else {
+        return text.to_string();
+    };
+    let trimmed_len = last.trim_end_matches([',', ';', ':']).len();
+    if last[..trimmed_len].ends_with(char::is_alphanumeric) {
+        last.truncate(trimmed_len);
+        let (_, opener, _) = split_punctuation(&words[sentence_start]);
+        let question = QUESTION_WORDS.contains(&opener.to_lowercase().as_str());
+        words
+            .last_mut()
+            .unwrap()
+            .push(if question { '?' } else { '.' });
+    }
+
+    words.join(" ")
+}
+
+/// A spelled-out number word.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum NumberWord {
+    /// zero through nine
+    Unit(u64),
+    /// ten through nineteen
+    Teen(u64),
+    /// twenty, thirty, ... ninety
+    Ten(u64),
+    Hundred,
+    /// thousand, million, billion
+    Scale(u64),
+}
+
+fn number_word(word: &str) -> Option<NumberWord> {
+    use NumberWord::*;
+    Some(match word {
+        "zero" => Unit(0),
+        "one" => Unit(1),
+        "two" => Unit(2),
+        "three" => Unit(3),
+        "four" => Unit(4),
+        "five" => Unit(5),
+        "six" => Unit(6),
+        "seven" => Unit(7),
+        "eight" => Unit(8),
+        "nine" => Unit(9),
+        "ten" => Teen(10),
+        "eleven" => Teen(11),
+        "twelve" => Teen(12),
+        "thirteen" => Teen(13),
+        "fourteen" => Teen(14),
+        "fifteen" => Teen(15),
+        "sixteen" => Teen(16),
+        "seventeen" => Teen(17),
+        "eighteen" => Teen(18),
+        "nineteen" => Teen(19),
+        "twenty" => Ten(20),
+        "thirty" => Ten(30),
+        "forty" => Ten(40),
+        "fifty" => Ten(50),
+        "sixty" => Ten(60),
+        "seventy" => Ten(70),
+        "eighty" => Ten(80),
+        "ninety" => Ten(90),
+        "hundred" => Hundred,
+        "thousand" => Scale(1_000),
+        "million" => Scale(1_000_000),
+        "billion" => Scale(1_000_000_000),
+        _ => return None,
+    })
+}
+
+/// Accumulates a run of number words, rejecting words that can't continue it.
+#[derive(Debug, Clone)]
+struct NumberParser {
+    total: u64,
+    current: u64,
+    last: Option<NumberWord>,
+    last_scale: u64,
+    words: usize,
+}
+
+impl NumberParser {
+    fn new() -> Self {
+        Self {
+            total: 0,
+            current: 0,
+            last: None,
+            last_scale: u64::MAX,
+            words: 0,
+        }
+    }
+
+    fn value(&self) -> u64 {
+        self.total + self.current
+    }
+
+    /// Add a word to the run, returning false if it doesn't continue the number.
+    fn push(&mut self, word: NumberWord) -> bool {
+        use NumberWord::*;
+        let allowed = match (self.last, word) {
+            (None, Unit(_) | Teen(_) | Ten(_)) => true,
+            (None, _) => false,
+            (Some(Unit(0)), _) => false,
+            (Some(Unit(_) | Teen(_)), Hundred) => self.current < 100,
+            (Some(Unit(_) | Teen(_) | Ten(_) | Hundred), Scale(scale)) => scale < self.last_scale,
+            (Some(Ten(_)), Unit(n)) => n > 0,
+            (Some(Hundred | Scale(_)), Unit(n)) => n > 0,
+            (Some(Hundred | Scale(_)), Teen(_) | Ten(_)) => true,
+            _ => false,
+        };
+        if !allowed {
+            return false;
+        }
+
+        match word {
+            Unit(n) | Teen(n) | Ten(n) => self.current += n,
+            Hundred => self.current *= 100,
+            Scale(scale) => {
+                self.total += self.current * scale;
+                self.current = 0;
+                self.last_scale = scale;
+            }
+        }
+        self.last = Some(word);
+        self.words += 1;
+        true
+    }
+
+    /// Add every part of a (possibly hyphenated) word, e.g. "twenty-three".
+    fn push_word(&mut self, core: &str) -> bool {
+        let mut next = self.clone();
+        let ok = !core.is_empty()
+            && core
+                .split('-')
+                .all(|part| number_word(part).is_some_and(|w| next.push(w)));
+        if ok {
+            *self = next;
+        }
+        ok
+    }
+}
+
+/// Split a token into leading punctuation, the word and trailing punctuation.
+fn split_punctuation(token: &str) -> (&str, &str, &str) {
+    let start = token
+        .find(|c: char| c.is_alphanumeric())
+        .unwrap_or(token.len());
+    let end = token
+        .rfind(|c: char| c.is_alphanumeric())
+        .map(|i| i + token[i..].chars().next().map_or(1, char::len_utf8))
+        .unwrap_or(start)
+        .max(start);
+    (&token[..start], &token[start..end], &token[end..])
+}
+
+/// Parse the longest number at the start of `tokens`.
+///
+/// Returns the number of tokens consumed and the value. Lone words below ten
+/// ("one of them") are left alone, as is anything that doesn't read as a
+/// single number ("two three").
+fn parse_number(tokens: &[&str]) -> Option<(usize, u64)> {
+    let mut parser = NumberParser::new();
+    let mut consumed = 0;
+
+    while consumed < tokens.len() {
+        let (lead, core, trail) = split_punctuation(tokens[consumed]);
+        if consumed > 0 && !lead.is_empty() {
+            break;
+        }
+        let core = core.to_lowercase();
+
+        // "one hundred and five": "and" only joins a scale word to what follows
+        if core == "and"
+            && trail.is_empty()
+            && matches!(
+                parser.last,
+                Some(NumberWord::Hundred | NumberWord::Scale(_))
+            )
+        {
+            let Some(&next) = tokens.get(consumed + 1) else {
+                break;
+            };
+            let (next_lead, next_core, next_trail) = split_punctuation(next);
+            if !next_lead.is_empty() || !parser.push_word(&next_core.to_lowercase()) {
+                break;
+            }
+            consumed += 2;
+            if !next_trail.is_empty() {
+                break;
+            }
+            continue;
+        }
+
+        if !parser.push_word(&core) {
+            break;
+        }
+        consumed += 1;
+        if !trail.is_empty() {
+            break;
+        }
+    }
+
+    (parser.words >= 2 || parser.value() >= 10).then(|| (consumed, parser.value()))
+}
+
+/// Convert spelled-out numbers to digits ("twenty three apples" -> "23 apples").
+fn normalize_numbers(text: &str) -> String {
+    let tokens: Vec<&str> = text.split(' ').collect();
+    let mut output = Vec::with_capacity(tokens.len());
+    let mut i = 0;
+
+    while i < tokens.len() {
+        match parse_number(&tokens[i..]) {
+            Some((len, value)) => {
+                let (lead, _, _) = split_punctuation(tokens[i]);
+                let (_, _, trail) = split_punctuation(tokens[i + len - 1]);
+                output.push(format!("{}{}{}", lead, value, trail));
+                i += len;
+            }
+            None => {
+                output.push(tokens[i].to_string());
+                i += 1;
+            }
+        }
+    }
+
+    output.join(" ")
+}
+
+/// Match the longest spoken symbol name at the start of `tokens`, returning
+/// the number of tokens it spans and the symbol.
+fn match_symbol(tokens: &[&str]) -> Option<(usize, &'static str, Spacing)> {
+    SYMBOLS
+        .iter()
+        .filter_map(|&(name, symbol, spacing)| {
+            let words: Vec<&str> = name.split(' ').collect();
+            let matches = tokens.len() >= words.len()
+                && words
+                    .iter()
+                    .zip(tokens)
+                    .all(|(word, token)| split_punctuation(token).1.eq_ignore_ascii_case(word));
+            matches.then_some((words.len(), symbol, spacing))
+        })
+        .max_by_key(|&(len, _, _)| len)
+}
+
+/// Read text as code or a command line (the technical profile).
+fn technical_text(text: &str) -> String {
+    let tokens: Vec<&str> = text.split_whitespace().collect();
+    let mut output = String::new();
+    let mut open_quotes: Vec<&str> = Vec::new();
+    // Whether the last piece attaches to whatever follows it
+    let mut attach_next = false;
+    let mut i = 0;
+
+    while i < tokens.len() {
+        // Whisper's punctuation is dropped; symbols are spoken
+        let (_, core, _) = split_punctuation(tokens[i]);
+        if core.is_empty() {
+            i += 1;
+            continue;
+        }
+
+        let (piece, spacing, len) =
+            if core.eq_ignore_ascii_case(LITERAL_WORD) && i + 1 < tokens.len() {
+                let (_, word, _) = split_punctuation(tokens[i + 1]);
+                let word = if word.is_empty() { tokens[i + 1] } else { word };
+                (word.to_string(), Spacing::Spaced, 2)
+            } else if let Some((len, symbol, spacing)) = match_symbol(&tokens[i..]) {
+                let spacing = match spacing {
+                    Spacing::Quote => match open_quotes.iter().position(|&q| q == symbol) {
+                        Some(open) => {
+                            open_quotes.remove(open);
+                            Spacing::Left
+                        }
+                        None => {
+                            open_quotes.push(symbol);
+                            Spacing::Right
+                        }
+                    },
+                    spacing => spacing,
+                };
+                (symbol.to_string(), spacing, len)
+            } else if let Some((len, value)) = parse_number(&tokens[i..]) {
+                (value.to_string(), Spacing::Spaced, len)
+            } else if let Some(NumberWord::Unit(n)) = number_word(&core.to_lowercase()) {
+                // Unlike prose, a lone "five" is a digit here
+                (n.to_string(), Spacing::Spaced, 1)
+            } else {
+                (core.to_string(), Spacing::Spaced, 1)
+            };
+
+        let attach_previous = matches!(spacing, Spacing::Join | Spacing::Left);
+        if !output.is_empty() && !attach_next && !attach_previous {
+            output.push(' ');
+        }
+        output.push_str(&piece);
+        attach_next = matches!(spacing, Spacing::Join | Spacing::Right);
+        i += len;
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::TextReplacement;
+
+    #[test]
+    fn test_normalize_numbers() {
+        assert_eq!(normalize_numbers("twenty three apples"), "23 apples");
+        assert_eq!(normalize_numbers("Twenty-three."), "23.");
+        assert_eq!(
+            normalize_numbers("one hundred and five thousand two hundred"),
+            "105200"
+        );
+        assert_eq!(normalize_numbers("nineteen hundred"), "1900");
+        assert_eq!(normalize_numbers("two million, three"), "2000000, three");
+        assert_eq!(normalize_numbers("ten people"), "10 people");
+
+        // Lone small numbers and word sequences that aren't one number stay as-is
+        assert_eq!(normalize_numbers("one of them"), "one of them");
+        assert_eq!(normalize_numbers("two three"), "two three");
+        assert_eq!(normalize_numbers("rock and roll"), "rock and roll");
+        assert_eq!(normalize_numbers("a hundred"), "a hundred");
+    }
+
+    #[test]
+    fn test_mask_profanity() {
+        let processor = PostProcessor::new(&PostProcessConfig {
+            mask_profanity: true,
+            profanity_words: vec!["Heck".to_string()],
+            ..Default::default()
+        });
+        assert_eq!(
+            processor.process("Well, damn. What the heck?", false),
+            "Well, d***. What the h***?"
+        );
+        // Only whole words are masked
+        assert_eq!(
+            processor.process("Assess the class", false),
+            "Assess the class"
+        );
+    }
+
+    #[test]
+    fn test_replacements_run_last() {
+        let processor = PostProcessor::new(&PostProcessConfig {
+            normalize_numbers: true,
+            replacements: vec![
+                TextReplacement {
+                    pattern: r"(\d+) percent".to_string(),
+                    replacement: "$1%".to_string(),
+                },
+                TextReplacement {
+                    pattern: "(unclosed".to_string(),
+                    replacement: String::new(),
+                },
+            ],
+            ..Default::default()
+        });
+        assert_eq!(processor.replacements.len(), 1);
+        assert_eq!(processor.process("fifty percent done", false), "50% done");
+        assert!(!PostProcessor::new(&PostProcessConfig::default()).is_empty());
+        assert!(PostProcessor::new(&PostProcessConfig {
+            restore_punctuation: Vec::new(),
+            ..Default::default()
+        })
+        .is_empty());
+    }
+
+    #[test]
+    fn test_technical_profile() {
+        assert_eq!(
+            technical_text("Print open paren foo underscore bar comma two close paren."),
+            "Print(foo_bar, 2)"
+        );
+        assert_eq!(
+            technical_text("ls dash la space slash tmp slash logs"),
+            "ls -la /tmp/logs"
+        );
+        assert_eq!(
+            technical_text("x equals twenty three point five semicolon"),
+            "x = 23.5;"
+        );
+        assert_eq!(
+            technical_text("echo quote hello world quote, pipe grep hello."),
+            "echo \"hello world\" | grep hello"
+        );
+        assert_eq!(
+            technical_text("Self double colon new open paren close paren"),
+            "Self::new()"
+        );
+        // "literal" keeps the next word as spoken
+        assert_eq!(technical_text("literal dot dot com"), "dot.com");
+        assert_eq!(technical_text("literal five"), "five");
+
+        // Auto-punctuation is skipped even in modes that want it
+        let processor = PostProcessor::new(&PostProcessConfig {
+            profile: TextProfile::Technical,
+            ..Default::default()
+        });
+        assert!(!processor.is_empty());
+        assert_eq!(processor.process("git status", true), "git status");
+    }
+
+    #[test]
+    fn test_restore_punctuation() {
+        assert_eq!(restore_punctuation("hello world"), "Hello world.");
+        assert_eq!(restore_punctuation("what time is it"), "What time is it?");
+        assert_eq!(
+            restore_punctuation("i think i'm done. okay then,"),
+            "I think I'm done. Okay then."
+        );
+        assert_eq!(
+            restore_punctuation("Is that right? yes"),
+            "Is that right? Yes."
+        );
+        // Already punctuated text is left alone
+        assert_eq!(restore_punctuation("Done!"), "Done!");
+        assert_eq!(restore_punctuation("(aside)"), "(Aside)");
+        assert_eq!(restore_punctuation(""), "");
+    }
+}
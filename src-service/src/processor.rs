@@ -2,10 +2,19 @@
 //!
 //! This module contains the SpeechDetector and VisualizationProcessor which
 //! analyze audio streams for speech activity and generate visualization data.
+//! Speech detection sits behind the [`VoiceActivityDetector`] trait so the
+//! heuristic detector can be swapped for the Silero model.
 
+use flowstt_common::{
+    AudioSourceType, NoiseCalibration, SourceWaveform, SpectrogramSettings, SpectrogramWindow,
+    SpeechDetectorConfig, VadBackend,
+};
 use rustfft::{num_complex::Complex, FftPlanner};
 use serde::Serialize;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::silero_vad::SileroVad;
 
 /// Speech state change events detected by the speech detector
 #[derive(Clone, Debug)]
@@ -71,7 +80,7 @@ pub struct WordBreakPayload {
 }
 
 /// Callback trait for receiving speech events
-pub trait SpeechEventCallback: Send {
+pub trait SpeechEventCallback: Send + Sync {
     /// Called when speech starts
     fn on_speech_started(&self, payload: SpeechEventPayload);
     /// Called when speech ends
@@ -80,6 +89,44 @@ pub trait SpeechEventCallback: Send {
     fn on_word_break(&self, payload: WordBreakPayload);
 }
 
+/// A voice activity detector fed with mono audio at the capture sample rate.
+///
+/// After each `process` call the caller takes the state change and word
+/// break it produced, if any. Lookback sample counts are in mono samples at
+/// the capture rate, whatever rate the detector analyzes internally.
+pub trait VoiceActivityDetector: Send {
+    /// The backend this detector implements
+    fn backend(&self) -> VadBackend;
+    /// Process a chunk of mono samples
+    fn process(&mut self, samples: &[f32]);
+    /// Get the current speech detection metrics
+    fn get_metrics(&self) -> SpeechMetrics;
+    /// Take the speech state change detected during the last `process` call
+    fn take_state_change(&mut self) -> SpeechStateChange;
+    /// Take the word break detected during the last `process` call
+    fn take_word_break_event(&mut self) -> Option<WordBreakEvent>;
+    /// Apply new settings without interrupting an ongoing speech segment
+    fn set_config(&mut self, config: &SpeechDetectorConfig);
+    /// Set the callback for speech events
+    fn set_callback(&mut self, callback: Arc<dyn SpeechEventCallback>);
+}
+
+/// Create the detector selected by `config.backend`.
+///
+/// Falls back to the energy detector if the Silero model can't be loaded.
+pub fn create_speech_detector(
+    sample_rate: u32,
+    config: &SpeechDetectorConfig,
+) -> Box<dyn VoiceActivityDetector> {
+    if config.backend == VadBackend::Silero {
+        match SileroVad::new(sample_rate, config) {
+            Ok(detector) => return Box::new(detector),
+            Err(e) => tracing::warn!("Silero VAD unavailable, using energy detector: {}", e),
+        }
+    }
+    Box::new(SpeechDetector::new(sample_rate, config))
+}
+
 /// Configuration for a speech detection mode (voiced or whisper)
 #[derive(Clone)]
 struct SpeechModeConfig {
@@ -93,6 +140,11 @@ struct SpeechModeConfig {
     onset_samples: u32,
 }
 
+/// Convert a duration in milliseconds to a sample count.
+fn ms_to_samples(sample_rate: u32, ms: u32) -> u32 {
+    (sample_rate as u64 * ms as u64 / 1000) as u32
+}
+
 /// Speech detector that detects when speech starts and ends.
 ///
 /// Uses multi-feature analysis for robust speech detection:
@@ -197,44 +249,40 @@ pub struct SpeechDetector {
 }
 
 impl SpeechDetector {
-    /// Create a new speech detector with specified sample rate.
-    /// Uses default dual-mode configuration optimized for speech detection.
-    pub fn new(sample_rate: u32) -> Self {
-        Self::with_defaults(sample_rate)
-    }
-
-    /// Create a speech detector with default dual-mode configuration.
+    /// Create a speech detector with the given dual-mode thresholds.
     ///
-    /// Default parameters:
-    /// - Voiced mode: -42dB threshold, ZCR 0.01-0.30, centroid 200-5500Hz, 80ms onset
-    /// - Whisper mode: -52dB threshold, ZCR 0.08-0.45, centroid 300-7000Hz, 120ms onset
-    /// - Transient rejection: ZCR > 0.45 AND centroid > 6500Hz
+    /// Default thresholds (`SpeechDetectorConfig::default()`):
+    /// - Voiced mode: -42dB threshold, ZCR 0.01-0.30, 80ms onset
+    /// - Whisper mode: -52dB threshold, ZCR 0.08-0.45, 120ms onset
     /// - Hold time: 300ms
+    /// - Lookback threshold: -55dB (more sensitive to catch speech starts)
+    ///
+    /// Fixed parameters:
+    /// - Centroid ranges: 200-5500Hz voiced, 300-7000Hz whisper
+    /// - Transient rejection: ZCR > 0.45 AND centroid > 6500Hz
     /// - Onset grace period: 30ms (brief dips in features don't reset onset counters)
     /// - Lookback buffer: 200ms (covers max onset time + margin)
-    /// - Lookback threshold: -55dB (more sensitive to catch speech starts)
-    pub fn with_defaults(sample_rate: u32) -> Self {
-        let hold_samples = (sample_rate as u64 * 300 / 1000) as u32;
+    pub fn new(sample_rate: u32, config: &SpeechDetectorConfig) -> Self {
         // 200ms lookback buffer
         let lookback_capacity = (sample_rate as u64 * 200 / 1000) as usize;
 
         Self {
             sample_rate,
             voiced_config: SpeechModeConfig {
-                threshold_db: -42.0,
-                zcr_range: (0.01, 0.30),
+                threshold_db: config.voiced_threshold_db,
+                zcr_range: config.voiced_zcr_range,
                 centroid_range: (200.0, 5500.0),
-                onset_samples: (sample_rate as u64 * 80 / 1000) as u32,
+                onset_samples: ms_to_samples(sample_rate, config.voiced_onset_ms),
             },
             whisper_config: SpeechModeConfig {
-                threshold_db: -52.0,
-                zcr_range: (0.08, 0.45),
+                threshold_db: config.whisper_threshold_db,
+                zcr_range: config.whisper_zcr_range,
                 centroid_range: (300.0, 7000.0),
-                onset_samples: (sample_rate as u64 * 120 / 1000) as u32,
+                onset_samples: ms_to_samples(sample_rate, config.whisper_onset_ms),
             },
             transient_zcr_threshold: 0.45,
             transient_centroid_threshold: 6500.0,
-            hold_samples,
+            hold_samples: ms_to_samples(sample_rate, config.hold_ms),
             is_speaking: false,
             is_pending_voiced: false,
             is_pending_whisper: false,
@@ -255,7 +303,7 @@ impl SpeechDetector {
             lookback_write_index: 0,
             lookback_capacity,
             lookback_filled: false,
-            lookback_threshold_db: -55.0,
+            lookback_threshold_db: config.lookback_threshold_db,
             last_lookback_offset_ms: None,
             last_state_change: SpeechStateChange::None,
 
@@ -282,6 +330,29 @@ impl SpeechDetector {
         }
     }
 
+    /// Apply new thresholds to a running detector.
+    ///
+    /// Pending onsets restart so they are judged by the new thresholds; an
+    /// ongoing speech segment continues.
+    pub fn set_config(&mut self, config: &SpeechDetectorConfig) {
+        self.voiced_config.threshold_db = config.voiced_threshold_db;
+        self.voiced_config.zcr_range = config.voiced_zcr_range;
+        self.voiced_config.onset_samples = ms_to_samples(self.sample_rate, config.voiced_onset_ms);
+        self.whisper_config.threshold_db = config.whisper_threshold_db;
+        self.whisper_config.zcr_range = config.whisper_zcr_range;
+        self.whisper_config.onset_samples =
+            ms_to_samples(self.sample_rate, config.whisper_onset_ms);
+        self.hold_samples = ms_to_samples(self.sample_rate, config.hold_ms);
+        self.lookback_threshold_db = config.lookback_threshold_db;
+
+        self.is_pending_voiced = false;
+        self.is_pending_whisper = false;
+        self.voiced_onset_count = 0;
+        self.whisper_onset_count = 0;
+        self.voiced_grace_count = 0;
+        self.whisper_grace_count = 0;
+    }
+
     /// Set the callback for speech events
     pub fn set_callback(&mut self, callback: Arc<dyn SpeechEventCallback>) {
         self.callback = Some(callback);
@@ -321,7 +392,7 @@ impl SpeechDetector {
     }
 
     /// Estimate spectral centroid using first-difference approximation.
-    fn estimate_spectral_centroid(&self, samples: &[f32], amplitude_db: f32) -> f32 {
+    fn estimate_spectral_centroid(sample_rate: u32, samples: &[f32], amplitude_db: f32) -> f32 {
         const CENTROID_GATE_DB: f32 = -55.0;
         if samples.len() < 2 || amplitude_db < CENTROID_GATE_DB {
             return 0.0;
@@ -339,7 +410,7 @@ impl SpeechDetector {
             return 0.0;
         }
 
-        self.sample_rate as f32 * mean_diff / (2.0 * mean_abs)
+        sample_rate as f32 * mean_diff / (2.0 * mean_abs)
     }
 
     /// Check if features indicate a transient sound
@@ -510,7 +581,7 @@ impl SpeechDetector {
         let rms = Self::calculate_rms(samples);
         let db = Self::amplitude_to_db(rms);
         let zcr = Self::calculate_zcr(samples);
-        let centroid = self.estimate_spectral_centroid(samples, db);
+        let centroid = Self::estimate_spectral_centroid(self.sample_rate, samples, db);
 
         // Store metrics
         self.last_amplitude_db = db;
@@ -727,6 +798,160 @@ impl SpeechDetector {
     }
 }
 
+impl VoiceActivityDetector for SpeechDetector {
+    fn backend(&self) -> VadBackend {
+        VadBackend::Energy
+    }
+
+    fn process(&mut self, samples: &[f32]) {
+        SpeechDetector::process(self, samples)
+    }
+
+    fn get_metrics(&self) -> SpeechMetrics {
+        SpeechDetector::get_metrics(self)
+    }
+
+    fn take_state_change(&mut self) -> SpeechStateChange {
+        SpeechDetector::take_state_change(self)
+    }
+
+    fn take_word_break_event(&mut self) -> Option<WordBreakEvent> {
+        SpeechDetector::take_word_break_event(self)
+    }
+
+    fn set_config(&mut self, config: &SpeechDetectorConfig) {
+        SpeechDetector::set_config(self, config)
+    }
+
+    fn set_callback(&mut self, callback: Arc<dyn SpeechEventCallback>) {
+        SpeechDetector::set_callback(self, callback)
+    }
+}
+
+// ============================================================================
+// Noise Calibration
+// ============================================================================
+
+/// Frame size for noise measurement
+const CALIBRATION_FRAME_MS: u32 = 20;
+
+/// Shortest measurement that gives a usable noise floor
+const MIN_CALIBRATION_MS: u32 = 1000;
+
+/// Ambient level above which the room is too loud to calibrate, in dBFS
+const MAX_NOISE_FLOOR_DB: f32 = -30.0;
+
+/// Threshold margins above the noise floor, in dB
+const VOICED_MARGIN_DB: f32 = 15.0;
+const WHISPER_MARGIN_DB: f32 = 8.0;
+const LOOKBACK_MARGIN_DB: f32 = 4.0;
+
+/// Whisper margin for hiss-like noise, which would otherwise pass as whispering
+const HISS_WHISPER_MARGIN_DB: f32 = 12.0;
+
+/// Measures ambient noise and derives speech detector thresholds from it.
+///
+/// Audio is analyzed in 20ms frames using the same features as the
+/// SpeechDetector. The noise floor is the level 90% of frames stay below,
+/// so occasional bumps don't inflate it.
+pub struct NoiseCalibrator {
+    sample_rate: u32,
+    frame_samples: usize,
+    frame: Vec<f32>,
+    levels_db: Vec<f32>,
+    zcrs: Vec<f32>,
+    centroids_hz: Vec<f32>,
+}
+
+impl NoiseCalibrator {
+    pub fn new(sample_rate: u32) -> Self {
+        let frame_samples = ms_to_samples(sample_rate, CALIBRATION_FRAME_MS) as usize;
+        Self {
+            sample_rate,
+            frame_samples,
+            frame: Vec::with_capacity(frame_samples),
+            levels_db: Vec::new(),
+            zcrs: Vec::new(),
+            centroids_hz: Vec::new(),
+        }
+    }
+
+    /// Add mono samples of ambient audio.
+    pub fn process(&mut self, samples: &[f32]) {
+        for &sample in samples {
+            self.frame.push(sample);
+            if self.frame.len() < self.frame_samples {
+                continue;
+            }
+
+            let db = SpeechDetector::amplitude_to_db(SpeechDetector::calculate_rms(&self.frame));
+            let centroid =
+                SpeechDetector::estimate_spectral_centroid(self.sample_rate, &self.frame, db);
+            self.levels_db.push(db);
+            self.zcrs.push(SpeechDetector::calculate_zcr(&self.frame));
+            if centroid > 0.0 {
+                self.centroids_hz.push(centroid);
+            }
+            self.frame.clear();
+        }
+    }
+
+    /// Measure the noise and derive thresholds, keeping the other settings of `base`.
+    pub fn finish(&self, base: &SpeechDetectorConfig) -> Result<NoiseCalibration, String> {
+        let min_frames = (MIN_CALIBRATION_MS / CALIBRATION_FRAME_MS) as usize;
+        if self.levels_db.len() < min_frames {
+            return Err("Not enough audio captured to calibrate".to_string());
+        }
+
+        let noise_floor_db = percentile(&self.levels_db, 0.9);
+        if noise_floor_db > MAX_NOISE_FLOOR_DB {
+            return Err(format!(
+                "Too loud to calibrate (noise floor {:.0} dB); calibrate in silence",
+                noise_floor_db
+            ));
+        }
+        let noise_zcr = percentile(&self.zcrs, 0.5);
+        let noise_centroid_hz = if self.centroids_hz.is_empty() {
+            0.0
+        } else {
+            percentile(&self.centroids_hz, 0.5)
+        };
+
+        let (zcr_min, zcr_max) = base.whisper_zcr_range;
+        let is_hiss = noise_centroid_hz > 0.0 && noise_zcr >= zcr_min && noise_zcr <= zcr_max;
+        let whisper_margin = if is_hiss {
+            HISS_WHISPER_MARGIN_DB
+        } else {
+            WHISPER_MARGIN_DB
+        };
+
+        let voiced_threshold_db = (noise_floor_db + VOICED_MARGIN_DB).clamp(-50.0, -20.0);
+        let whisper_threshold_db =
+            (noise_floor_db + whisper_margin).clamp(-60.0, voiced_threshold_db - 4.0);
+        let lookback_threshold_db =
+            (noise_floor_db + LOOKBACK_MARGIN_DB).clamp(-70.0, whisper_threshold_db);
+
+        Ok(NoiseCalibration {
+            noise_floor_db,
+            noise_zcr,
+            noise_centroid_hz,
+            config: SpeechDetectorConfig {
+                voiced_threshold_db,
+                whisper_threshold_db,
+                lookback_threshold_db,
+                ..base.clone()
+            },
+        })
+    }
+}
+
+/// Value below which `fraction` of `values` fall.
+fn percentile(values: &[f32], fraction: f32) -> f32 {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f32::total_cmp);
+    sorted[((sorted.len() - 1) as f32 * fraction) as usize]
+}
+
 // ============================================================================
 // Visualization Processor
 // ============================================================================
@@ -747,6 +972,9 @@ pub struct VisualizationPayload {
     pub spectrogram: Option<SpectrogramColumn>,
     /// Speech detection metrics (present when speech processor is active)
     pub speech_metrics: Option<SpeechMetrics>,
+    /// Per-source waveforms, downsampled like `waveform` (empty unless
+    /// source samples were pushed)
+    pub source_waveforms: Vec<SourceWaveform>,
 }
 
 /// Callback trait for receiving visualization data
@@ -763,26 +991,68 @@ struct ColorStop {
     b: u8,
 }
 
+/// Spectrogram settings for visualization processors; `None` uses the defaults
+static SPECTROGRAM_SETTINGS: Mutex<Option<SpectrogramSettings>> = Mutex::new(None);
+
+/// Bumped whenever `SPECTROGRAM_SETTINGS` changes, so running processors
+/// notice on their next buffer
+static SPECTROGRAM_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Set the spectrogram settings; running processors pick them up immediately.
+pub fn set_spectrogram_settings(settings: SpectrogramSettings) {
+    *SPECTROGRAM_SETTINGS.lock().unwrap() = Some(settings);
+    SPECTROGRAM_GENERATION.fetch_add(1, Ordering::SeqCst);
+}
+
+fn spectrogram_settings() -> SpectrogramSettings {
+    SPECTROGRAM_SETTINGS.lock().unwrap().unwrap_or_default()
+}
+
+/// Window coefficients of `size` samples.
+fn window_coefficients(window: SpectrogramWindow, size: usize) -> Vec<f32> {
+    let denom = (size.max(2) - 1) as f32;
+    (0..size)
+        .map(|i| {
+            let phase = 2.0 * std::f32::consts::PI * i as f32 / denom;
+            match window {
+                SpectrogramWindow::Hann => 0.5 * (1.0 - phase.cos()),
+                SpectrogramWindow::Hamming => 0.54 - 0.46 * phase.cos(),
+                SpectrogramWindow::Blackman => {
+                    0.42 - 0.5 * phase.cos() + 0.08 * (2.0 * phase).cos()
+                }
+                SpectrogramWindow::Rectangular => 1.0,
+            }
+        })
+        .collect()
+}
+
 /// Visualization processor that computes render-ready waveform and spectrogram data.
 pub struct VisualizationProcessor {
     /// Sample rate for frequency calculations
     sample_rate: u32,
     /// Target height for spectrogram output (pixels)
     output_height: usize,
+    /// FFT size, window and emit rate in use
+    settings: SpectrogramSettings,
+    /// `SPECTROGRAM_GENERATION` the settings were read at
+    settings_generation: u64,
     /// FFT size (must be power of 2)
     fft_size: usize,
     /// FFT planner/executor
     fft: Arc<dyn rustfft::Fft<f32>>,
-    /// Pre-computed Hanning window
-    hanning_window: Vec<f32>,
-    /// Buffer for accumulating samples for FFT
+    /// Pre-computed window coefficients
+    window: Vec<f32>,
+    /// Samples for the next spectrogram column; with an emit rate set, the
+    /// latest `fft_size` samples
     fft_buffer: Vec<f32>,
-    /// Current write position in FFT buffer
-    fft_write_index: usize,
+    /// Samples received since the last event, with an emit rate set
+    samples_since_emit: usize,
     /// Pre-computed color lookup table (256 entries, RGB)
     color_lut: Vec<[u8; 3]>,
     /// Waveform accumulator for downsampling
     waveform_buffer: Vec<f32>,
+    /// Per-source waveform accumulators, in the order sources first arrived
+    source_buffers: Vec<(AudioSourceType, Vec<f32>)>,
     /// Target waveform output samples per emit
     waveform_target_samples: usize,
     /// Speech metrics to include in next visualization event
@@ -794,35 +1064,46 @@ pub struct VisualizationProcessor {
 impl VisualizationProcessor {
     /// Create a new visualization processor
     pub fn new(sample_rate: u32, output_height: usize) -> Self {
-        let fft_size = 512;
+        let settings_generation = SPECTROGRAM_GENERATION.load(Ordering::SeqCst);
+        let settings = spectrogram_settings();
+        let fft_size = settings.fft_size as usize;
 
         let mut planner = FftPlanner::new();
         let fft = planner.plan_fft_forward(fft_size);
 
-        let hanning_window: Vec<f32> = (0..fft_size)
-            .map(|i| {
-                0.5 * (1.0 - (2.0 * std::f32::consts::PI * i as f32 / (fft_size - 1) as f32).cos())
-            })
-            .collect();
-
         let color_lut = Self::build_color_lut();
 
         Self {
             sample_rate,
             output_height,
+            settings,
+            settings_generation,
             fft_size,
             fft,
-            hanning_window,
+            window: window_coefficients(settings.window, fft_size),
             fft_buffer: Vec::with_capacity(fft_size),
-            fft_write_index: 0,
+            samples_since_emit: 0,
             color_lut,
             waveform_buffer: Vec::with_capacity(256),
+            source_buffers: Vec::new(),
             waveform_target_samples: 64,
             pending_speech_metrics: None,
             callback: None,
         }
     }
 
+    /// Change the FFT size, window and emit rate, starting a fresh column.
+    pub fn set_settings(&mut self, settings: SpectrogramSettings) {
+        if settings.fft_size != self.settings.fft_size {
+            self.fft_size = settings.fft_size as usize;
+            self.fft = FftPlanner::new().plan_fft_forward(self.fft_size);
+        }
+        self.window = window_coefficients(settings.window, self.fft_size);
+        self.fft_buffer.clear();
+        self.samples_since_emit = 0;
+        self.settings = settings;
+    }
+
     /// Set the callback for visualization events
     pub fn set_callback(&mut self, callback: Arc<dyn VisualizationCallback>) {
         self.callback = Some(callback);
@@ -833,6 +1114,15 @@ impl VisualizationProcessor {
         self.pending_speech_metrics = Some(metrics);
     }
 
+    /// Accumulate one source's mono samples for its own waveform; call
+    /// before `process` with the buffer the mix was made from.
+    pub fn push_source_samples(&mut self, source: AudioSourceType, samples: &[f32]) {
+        match self.source_buffers.iter_mut().find(|(s, _)| *s == source) {
+            Some((_, buffer)) => buffer.extend_from_slice(samples),
+            None => self.source_buffers.push((source, samples.to_vec())),
+        }
+    }
+
     /// Build the color lookup table
     fn build_color_lut() -> Vec<[u8; 3]> {
         let stops = [
@@ -968,7 +1258,7 @@ impl VisualizationProcessor {
         let mut complex_buffer: Vec<Complex<f32>> = self
             .fft_buffer
             .iter()
-            .zip(self.hanning_window.iter())
+            .zip(self.window.iter())
             .map(|(&sample, &window)| Complex::new(sample * window, 0.0))
             .collect();
 
@@ -1029,34 +1319,67 @@ impl VisualizationProcessor {
 
     /// Process audio samples for visualization
     pub fn process(&mut self, samples: &[f32]) {
-        // Accumulate samples for FFT
-        for &sample in samples {
-            if self.fft_write_index < self.fft_size {
-                if self.fft_buffer.len() <= self.fft_write_index {
-                    self.fft_buffer.push(sample);
-                } else {
-                    self.fft_buffer[self.fft_write_index] = sample;
-                }
-                self.fft_write_index += 1;
-            }
+        // Pick up settings changed since the last buffer
+        let generation = SPECTROGRAM_GENERATION.load(Ordering::SeqCst);
+        if generation != self.settings_generation {
+            self.settings_generation = generation;
+            self.set_settings(spectrogram_settings());
         }
 
         // Accumulate samples for waveform
         self.waveform_buffer.extend_from_slice(samples);
 
-        // Check if FFT buffer is full
-        let spectrogram = if self.fft_write_index >= self.fft_size {
-            let column = self.process_fft();
-            self.fft_write_index = 0;
-            Some(column)
+        let emit_interval = self.sample_rate.checked_div(self.settings.emit_rate_hz);
+        let spectrogram = if let Some(interval) = emit_interval {
+            // Keep the latest samples; columns overlap when the emit interval
+            // is shorter than the FFT
+            self.fft_buffer.extend_from_slice(samples);
+            let excess = self.fft_buffer.len().saturating_sub(self.fft_size);
+            self.fft_buffer.drain(..excess);
+
+            // Carry the remainder over so the rate holds on average when the
+            // interval isn't a whole number of buffers
+            let interval = interval.max(1) as usize;
+            self.samples_since_emit += samples.len();
+            if self.samples_since_emit < interval {
+                return;
+            }
+            self.samples_since_emit = (self.samples_since_emit - interval).min(interval - 1);
+
+            (self.fft_buffer.len() >= self.fft_size).then(|| self.process_fft())
         } else {
-            None
+            // Accumulate samples for FFT, computing a column once it is full
+            let needed = self.fft_size - self.fft_buffer.len();
+            self.fft_buffer
+                .extend_from_slice(&samples[..samples.len().min(needed)]);
+            if self.fft_buffer.len() >= self.fft_size {
+                let column = self.process_fft();
+                self.fft_buffer.clear();
+                Some(column)
+            } else {
+                None
+            }
         };
 
         // Downsample waveform
         let waveform = self.downsample_waveform(&self.waveform_buffer);
         self.waveform_buffer.clear();
 
+        // Sources that stopped arriving (back to one stream) are dropped
+        let source_buffers = std::mem::take(&mut self.source_buffers);
+        let mut source_waveforms = Vec::with_capacity(source_buffers.len());
+        for (source, mut buffer) in source_buffers {
+            if buffer.is_empty() {
+                continue;
+            }
+            source_waveforms.push(SourceWaveform {
+                source,
+                waveform: self.downsample_waveform(&buffer),
+            });
+            buffer.clear();
+            self.source_buffers.push((source, buffer));
+        }
+
         // Take speech metrics
         let speech_metrics = self.pending_speech_metrics.take();
 
@@ -1065,6 +1388,7 @@ impl VisualizationProcessor {
             waveform,
             spectrogram,
             speech_metrics,
+            source_waveforms,
         };
 
         if let Some(ref callback) = self.callback {
@@ -1072,3 +1396,111 @@ impl VisualizationProcessor {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic white noise with peak amplitude `amplitude`
+    fn noise(amplitude: f32, len: usize) -> Vec<f32> {
+        let mut seed = 12345u32;
+        (0..len)
+            .map(|_| {
+                seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+                ((seed >> 16) as f32 / 32768.0 - 1.0) * amplitude
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_noise_calibration() {
+        let base = SpeechDetectorConfig::default();
+
+        let mut calibrator = NoiseCalibrator::new(16000);
+        calibrator.process(&noise(0.01, 8000));
+        assert!(calibrator.finish(&base).is_err());
+        calibrator.process(&noise(0.01, 24000));
+        let result = calibrator.finish(&base).unwrap();
+        assert!((-47.0..-43.0).contains(&result.noise_floor_db));
+        assert!((result.config.voiced_threshold_db - (result.noise_floor_db + 15.0)).abs() < 0.01);
+        assert!(result.config.whisper_threshold_db < result.config.voiced_threshold_db);
+        assert!(result.config.lookback_threshold_db <= result.config.whisper_threshold_db);
+        assert_eq!(result.config.hold_ms, base.hold_ms);
+        assert!(result.config.validate().is_ok());
+
+        let mut loud = NoiseCalibrator::new(16000);
+        loud.process(&noise(0.5, 32000));
+        assert!(loud.finish(&base).is_err());
+    }
+
+    struct CollectVisualization(Mutex<Vec<VisualizationPayload>>);
+
+    impl VisualizationCallback for CollectVisualization {
+        fn on_visualization_data(&self, payload: VisualizationPayload) {
+            self.0.lock().unwrap().push(payload);
+        }
+    }
+
+    #[test]
+    fn test_visualization_emit_rate() {
+        let samples = noise(0.1, 480);
+        let collected = Arc::new(CollectVisualization(Mutex::new(Vec::new())));
+
+        // Without an emit rate, every buffer emits and each 512 samples make a column
+        let mut processor = VisualizationProcessor::new(48000, 64);
+        processor.set_settings(SpectrogramSettings::default());
+        processor.set_callback(collected.clone());
+        for _ in 0..100 {
+            processor.process(&samples);
+        }
+        {
+            let payloads = collected.0.lock().unwrap();
+            assert_eq!(payloads.len(), 100);
+            let columns = payloads.iter().filter(|p| p.spectrogram.is_some()).count();
+            assert!((45..=50).contains(&columns));
+        }
+
+        // At 30 Hz, events are paced to the rate whatever the FFT size
+        collected.0.lock().unwrap().clear();
+        processor.set_settings(SpectrogramSettings {
+            fft_size: 2048,
+            window: SpectrogramWindow::Blackman,
+            emit_rate_hz: 30,
+        });
+        for _ in 0..100 {
+            processor.process(&samples);
+        }
+        let payloads = collected.0.lock().unwrap();
+        assert_eq!(payloads.len(), 30);
+        assert!(payloads.iter().skip(1).all(|p| p.spectrogram.is_some()));
+        let column = payloads.last().unwrap().spectrogram.as_ref().unwrap();
+        assert_eq!(column.colors.len(), 64 * 3);
+    }
+
+    #[test]
+    fn test_visualization_source_waveforms() {
+        let collected = Arc::new(CollectVisualization(Mutex::new(Vec::new())));
+        let mut processor = VisualizationProcessor::new(48000, 64);
+        processor.set_callback(collected.clone());
+
+        let input = vec![0.5; 480];
+        let system = vec![-0.25; 480];
+        let mix: Vec<f32> = input.iter().zip(&system).map(|(a, b)| a + b).collect();
+        processor.push_source_samples(AudioSourceType::Input, &input);
+        processor.push_source_samples(AudioSourceType::System, &system);
+        processor.process(&mix);
+
+        // Back to one stream: no source traces
+        processor.process(&mix);
+
+        let payloads = collected.0.lock().unwrap();
+        let sources = &payloads[0].source_waveforms;
+        assert_eq!(sources.len(), 2);
+        assert_eq!(sources[0].source, AudioSourceType::Input);
+        assert_eq!(sources[0].waveform.len(), payloads[0].waveform.len());
+        assert!(sources[0].waveform.iter().all(|&v| v == 0.5));
+        assert_eq!(sources[1].source, AudioSourceType::System);
+        assert!(sources[1].waveform.iter().all(|&v| v == -0.25));
+        assert!(payloads[1].source_waveforms.is_empty());
+    }
+}
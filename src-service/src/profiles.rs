@@ -0,0 +1,193 @@
+//! Settings profiles.
+//!
+//! A profile bundles sources, recording mode, echo cancellation, model and
+//! output mode under a name such as "meetings". Using a profile replays the
+//! equivalent individual requests, so every setting goes through the same
+//! validation and side effects as when it is changed on its own.
+//!
+//! Profiles that list applications are switched to automatically when one of
+//! those applications comes to the front.
+
+use std::time::Duration;
+
+use flowstt_common::ipc::{Request, Response};
+use flowstt_common::{FocusedApp, Profile};
+use tracing::{info, warn};
+
+use crate::focus;
+use crate::ipc::handlers::{get_transcribe_state, handle_request};
+use crate::state::get_service_state;
+
+/// How often the focused application is checked for automatic switching
+const AUTO_SWITCH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Find a saved profile by name, ignoring case.
+pub fn find<'a>(profiles: &'a [Profile], name: &str) -> Option<&'a Profile> {
+    profiles
+        .iter()
+        .find(|profile| profile.name.eq_ignore_ascii_case(name.trim()))
+}
+
+/// The first profile that lists `app`.
+pub fn for_app<'a>(profiles: &'a [Profile], app: &FocusedApp) -> Option<&'a Profile> {
+    profiles
+        .iter()
+        .find(|profile| profile.apps.iter().any(|pattern| app.matches(pattern)))
+}
+
+/// Switch profiles as applications come to the front, until shutdown.
+///
+/// Only a change of focused application triggers a switch, so a profile
+/// chosen by hand stays in use until the user moves to another application.
+/// Switching waits while speech is being recorded and never happens in
+/// privacy mode.
+pub async fn run_auto_switch() {
+    let mut last_app: Option<String> = None;
+    while !crate::is_shutdown_requested() {
+        tokio::time::sleep(AUTO_SWITCH_INTERVAL).await;
+
+        let (profiles, active, muted) = {
+            let state_arc = get_service_state();
+            let state = state_arc.lock().await;
+            (
+                state.profiles.clone(),
+                state.active_profile.clone(),
+                state.muted,
+            )
+        };
+        if muted || profiles.iter().all(|p| p.apps.is_empty()) {
+            continue;
+        }
+        let in_speech = get_transcribe_state()
+            .try_lock()
+            .map_or(true, |transcribe| transcribe.in_speech);
+        if in_speech {
+            continue;
+        }
+
+        let Ok(Ok(app)) = tokio::task::spawn_blocking(focus::focused_app).await else {
+            continue;
+        };
+        if last_app.as_deref() == Some(app.id.as_str()) {
+            continue;
+        }
+        last_app = Some(app.id.clone());
+
+        let Some(profile) = for_app(&profiles, &app) else {
+            continue;
+        };
+        if active.as_deref() == Some(profile.name.as_str()) {
+            continue;
+        }
+        info!(
+            "{} is in front, switching to profile {}",
+            app.id, profile.name
+        );
+        let request = Request::UseProfile {
+            name: profile.name.clone(),
+        };
+        if let Response::Error { message, .. } = handle_request(request).await {
+            warn!("Automatic profile switch failed: {}", message);
+        }
+    }
+}
+
+/// Requests that apply `profile`, in order. Sources come last because
+/// setting them restarts capture, which then picks up the other settings.
+pub fn requests(profile: &Profile) -> Vec<Request> {
+    let mut requests = Vec::new();
+    if let Some(mode) = profile.output_mode {
+        requests.push(Request::SetOutputMode { mode });
+    }
+    if let Some(model_name) = &profile.model_name {
+        requests.push(Request::SetConfigValue {
+            key: "model.name".to_string(),
+            value: model_name.clone(),
+        });
+    }
+    if let Some(mode) = profile.recording_mode {
+        requests.push(Request::SetRecordingMode { mode });
+    }
+    if let Some(enabled) = profile.aec_enabled {
+        requests.push(Request::SetAecEnabled { enabled });
+    }
+    if profile.source1_id.is_some() {
+        requests.push(Request::SetSources {
+            source1_id: profile.source1_id.clone(),
+            source2_id: profile.source2_id.clone(),
+            source1_channels: None,
+            source2_channels: None,
+        });
+    }
+    requests
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flowstt_common::{OutputMode, RecordingMode};
+
+    #[test]
+    fn test_sources_are_applied_last() {
+        let profile = Profile {
+            name: "Meetings".to_string(),
+            source1_id: Some("mic".to_string()),
+            source2_id: Some("monitor".to_string()),
+            recording_mode: Some(RecordingMode::EchoCancel),
+            aec_enabled: Some(true),
+            output_mode: Some(OutputMode::EventsOnly),
+            ..Default::default()
+        };
+        let requests = requests(&profile);
+        assert_eq!(requests.len(), 4);
+        assert!(matches!(requests[0], Request::SetOutputMode { .. }));
+        assert!(matches!(
+            requests.last(),
+            Some(Request::SetSources {
+                source1_id: Some(_),
+                source2_id: Some(_),
+                ..
+            })
+        ));
+
+        let profiles = [profile];
+        assert!(find(&profiles, "meetings").is_some());
+        assert!(find(&profiles, "gaming").is_none());
+    }
+
+    #[test]
+    fn test_profile_for_app() {
+        let profiles = [
+            Profile {
+                name: "meetings".to_string(),
+                apps: vec!["zoom".to_string(), "us.zoom.xos".to_string()],
+                ..Default::default()
+            },
+            Profile {
+                name: "dictation".to_string(),
+                apps: vec!["Code".to_string()],
+                ..Default::default()
+            },
+        ];
+        let app = |id: &str, window_class: Option<&str>| FocusedApp {
+            id: id.to_string(),
+            name: None,
+            window_class: window_class.map(str::to_string),
+        };
+
+        let name = |app: &FocusedApp| for_app(&profiles, app).map(|p| p.name.as_str());
+        assert_eq!(name(&app("Zoom.exe", None)), Some("meetings"));
+        assert_eq!(name(&app("us.zoom.xos", None)), Some("meetings"));
+        assert_eq!(name(&app("code", Some("Code"))), Some("dictation"));
+        assert_eq!(name(&app("firefox", Some("firefox"))), None);
+    }
+
+    #[test]
+    fn test_unset_settings_are_left_alone() {
+        let profile = Profile {
+            name: "empty".to_string(),
+            ..Default::default()
+        };
+        assert!(requests(&profile).is_empty());
+    }
+}
@@ -3,27 +3,44 @@
 //! This module manages the PTT lifecycle:
 //! - In PTT mode, audio capture is only active while the hotkey is held
 //! - Polls for hotkey events independently of audio loop
-//! - Starts/stops audio capture on key press/release
+//! - Dispatches each key to its bound action: push-to-talk variants start and
+//!   stop capture on press/release, toggle actions act on press
 
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
+use std::borrow::Cow;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::Duration;
 
-use flowstt_common::ipc::{EventType, Response};
-use tracing::{debug, error, info};
+use flowstt_common::ipc::{EventType, Request, Response};
+use flowstt_common::{
+    AudioSourceType, FeedbackCue, FlowSttError, HotkeyAction, KeyCode, OutputMode,
+};
+use tokio::runtime::Handle;
+use tracing::{debug, error, info, warn};
 
+use crate::cues;
 use crate::hotkey::{self, HotkeyEvent};
 use crate::ipc::broadcast_event;
-use crate::ipc::handlers::get_transcribe_state;
+use crate::ipc::handlers::{get_transcribe_state, handle_request};
 use crate::platform;
 use crate::processor::{VisualizationCallback, VisualizationPayload, VisualizationProcessor};
 use crate::state::get_service_state;
 
+/// Longest the PTT audio loop waits for audio before checking whether to stop
+const AUDIO_RECV_TIMEOUT: Duration = Duration::from_millis(50);
+
 /// Global PTT controller state
 static PTT_ACTIVE: std::sync::OnceLock<Arc<AtomicBool>> = std::sync::OnceLock::new();
 static PTT_THREAD_RUNNING: std::sync::OnceLock<Arc<AtomicBool>> = std::sync::OnceLock::new();
 
+/// Incremented on every start, so a loop stopped and restarted from its own
+/// thread (e.g. by the mute hotkey) exits instead of running twice
+static PTT_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Push-to-talk key that started the current recording (None for toggle-session)
+static ACTIVE_KEY: Mutex<Option<KeyCode>> = Mutex::new(None);
+
 fn get_ptt_active() -> Arc<AtomicBool> {
     PTT_ACTIVE
         .get_or_init(|| Arc::new(AtomicBool::new(false)))
@@ -48,13 +65,18 @@ pub fn start_ptt_controller() -> Result<(), String> {
         return Ok(()); // Already running
     }
 
+    // Mute toggles go through the IPC handlers, which need the service runtime
+    let runtime =
+        Handle::try_current().map_err(|e| format!("Service runtime not available: {}", e))?;
+
     info!("[PTT Controller] Starting...");
 
     get_ptt_thread_running().store(true, Ordering::SeqCst);
     get_ptt_active().store(false, Ordering::SeqCst);
+    let generation = PTT_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
 
     thread::spawn(move || {
-        ptt_controller_loop();
+        ptt_controller_loop(generation, runtime);
     });
 
     Ok(())
@@ -72,15 +94,18 @@ pub fn stop_ptt_controller() {
     // If PTT was active, stop capture
     if get_ptt_active().load(Ordering::SeqCst) {
         get_ptt_active().store(false, Ordering::SeqCst);
+        *ACTIVE_KEY.lock().unwrap() = None;
         stop_ptt_capture();
     }
 }
 
 /// Main PTT controller loop
-fn ptt_controller_loop() {
+fn ptt_controller_loop(generation: u64, runtime: Handle) {
     info!("[PTT] Controller polling for hotkey events...");
 
-    while get_ptt_thread_running().load(Ordering::SeqCst) {
+    while get_ptt_thread_running().load(Ordering::SeqCst)
+        && PTT_GENERATION.load(Ordering::SeqCst) == generation
+    {
         // Check if we should stop
         if crate::is_shutdown_requested() {
             break;
@@ -88,14 +113,7 @@ fn ptt_controller_loop() {
 
         // Check for hotkey events
         if let Some(event) = hotkey::try_recv_hotkey() {
-            match event {
-                HotkeyEvent::Pressed => {
-                    handle_ptt_pressed();
-                }
-                HotkeyEvent::Released => {
-                    handle_ptt_released();
-                }
-            }
+            handle_hotkey_event(event, &runtime);
         }
 
         // Sleep briefly to avoid busy-waiting
@@ -103,17 +121,94 @@ fn ptt_controller_loop() {
     }
 
     info!("[PTT] Controller stopped");
-    get_ptt_thread_running().store(false, Ordering::SeqCst);
+    if PTT_GENERATION.load(Ordering::SeqCst) == generation {
+        get_ptt_thread_running().store(false, Ordering::SeqCst);
+    }
+}
+
+/// Output mode a push-to-talk action delivers its transcription with, or None
+/// for the configured output mode.
+fn output_for(action: HotkeyAction) -> Option<OutputMode> {
+    match action {
+        HotkeyAction::PushToTalkType => Some(OutputMode::Type),
+        HotkeyAction::PushToTalkClipboard => Some(OutputMode::Clipboard),
+        _ => None,
+    }
+}
+
+/// Run the action bound to the key of a hotkey event
+fn handle_hotkey_event(event: HotkeyEvent, runtime: &Handle) {
+    let (HotkeyEvent::Pressed(key) | HotkeyEvent::Released(key)) = event;
+    let (action, muted) = {
+        let state_arc = get_service_state();
+        let state = futures::executor::block_on(state_arc.lock());
+        let action = state
+            .hotkey_bindings()
+            .into_iter()
+            .find(|b| b.key == key)
+            .map(|b| b.action);
+        (action, state.muted)
+    };
+    let Some(action) = action else {
+        return;
+    };
+
+    // Only the mute toggle stays live in privacy mode
+    if muted && action != HotkeyAction::ToggleMute {
+        return;
+    }
+
+    match (event, action) {
+        (HotkeyEvent::Pressed(_), action) if action.is_push_to_talk() => {
+            handle_ptt_pressed(Some(key), output_for(action));
+        }
+        (HotkeyEvent::Released(_), action) if action.is_push_to_talk() => {
+            // Another key may have started the recording; its release ends it
+            if *ACTIVE_KEY.lock().unwrap() == Some(key) {
+                handle_ptt_released();
+            }
+        }
+        (HotkeyEvent::Pressed(_), HotkeyAction::ToggleSession) => {
+            if get_ptt_active().load(Ordering::SeqCst) {
+                handle_ptt_released();
+            } else {
+                handle_ptt_pressed(None, None);
+            }
+        }
+        (HotkeyEvent::Pressed(_), HotkeyAction::ToggleMute) => {
+            info!(
+                "[PTT] Toggling privacy mode {}",
+                if muted { "off" } else { "on" }
+            );
+            let request = Request::SetMuted { muted: !muted };
+            if let Response::Error { message, .. } = runtime.block_on(handle_request(request)) {
+                warn!("[PTT] Failed to toggle privacy mode: {}", message);
+            }
+        }
+        (HotkeyEvent::Pressed(_), HotkeyAction::RepeatLast) => {
+            info!("[PTT] Repeating the last transcription");
+            match crate::output::repeat_last(None) {
+                Ok(Some(_)) => {}
+                Ok(None) => info!("[PTT] Nothing to repeat yet"),
+                Err(e) => warn!("[PTT] Failed to repeat the last transcription: {}", e),
+            }
+        }
+        _ => {}
+    }
 }
 
 /// Handle PTT key press - start audio capture
-fn handle_ptt_pressed() {
+///
+/// `key` is the push-to-talk key whose release ends the recording, and
+/// `output` overrides the output mode of the recorded segment.
+fn handle_ptt_pressed(key: Option<KeyCode>, output: Option<OutputMode>) {
     if get_ptt_active().load(Ordering::SeqCst) {
         return;
     }
 
     info!("[PTT] Recording STARTED");
     get_ptt_active().store(true, Ordering::SeqCst);
+    *ACTIVE_KEY.lock().unwrap() = key;
 
     // Update state
     {
@@ -128,14 +223,14 @@ fn handle_ptt_pressed() {
     });
 
     // Start capture
-    if let Err(e) = start_ptt_capture() {
+    if let Err(e) = start_ptt_capture(output) {
         error!("[PTT] Failed to start recording: {}", e);
         get_ptt_active().store(false, Ordering::SeqCst);
 
         broadcast_event(Response::Event {
             event: EventType::CaptureStateChanged {
                 capturing: false,
-                error: Some(e),
+                error: Some(e.to_string()),
             },
         });
     } else {
@@ -150,6 +245,7 @@ fn handle_ptt_pressed() {
                 error: None,
             },
         });
+        cues::emit(FeedbackCue::PttStart);
     }
 }
 
@@ -161,6 +257,7 @@ fn handle_ptt_released() {
 
     info!("[PTT] Recording STOPPED - submitting for transcription");
     get_ptt_active().store(false, Ordering::SeqCst);
+    *ACTIVE_KEY.lock().unwrap() = None;
 
     // Update state
     {
@@ -195,6 +292,7 @@ fn handle_ptt_released() {
     broadcast_event(Response::Event {
         event: EventType::PttReleased,
     });
+    cues::emit(FeedbackCue::PttStop);
 
     broadcast_event(Response::Event {
         event: EventType::SpeechEnded { duration_ms: 0 },
@@ -209,17 +307,21 @@ fn handle_ptt_released() {
 }
 
 /// Start audio capture for PTT session
-fn start_ptt_capture() -> Result<(), String> {
+fn start_ptt_capture(output: Option<OutputMode>) -> Result<(), FlowSttError> {
     let state_arc = get_service_state();
     let (source1_id, source2_id, aec_enabled, recording_mode) = {
         let state = futures::executor::block_on(state_arc.lock());
 
+        if state.muted {
+            return Err(FlowSttError::Muted);
+        }
+
         if !state.app_ready {
-            return Err("App not ready".to_string());
+            return Err(FlowSttError::NotReady);
         }
 
         if !state.has_primary_source() {
-            return Err("No primary audio source configured".to_string());
+            return Err(FlowSttError::NoSource);
         }
 
         (
@@ -230,17 +332,17 @@ fn start_ptt_capture() -> Result<(), String> {
         )
     };
 
-    // Get sample rate from backend
-    let sample_rate = platform::get_backend()
-        .map(|b| b.sample_rate())
-        .unwrap_or(48000);
+    // Get the format capture will deliver
+    let (sample_rate, channels) =
+        platform::capture_format(source1_id.as_deref(), source2_id.as_deref());
 
     // Initialize transcribe state for PTT mode
     {
         let transcribe_state = get_transcribe_state();
         let mut transcribe = transcribe_state.lock().unwrap();
-        transcribe.init_for_capture(sample_rate, 2);
+        transcribe.init_for_capture(sample_rate, channels);
         transcribe.set_ptt_mode(true); // Disable automatic segmentation
+        transcribe.set_output_override(output);
         transcribe.activate();
         // Immediately start speech segment (no lookback in PTT mode)
         transcribe.on_speech_started(0);
@@ -251,11 +353,11 @@ fn start_ptt_capture() -> Result<(), String> {
         backend.set_aec_enabled(aec_enabled);
         backend.set_recording_mode(recording_mode);
 
-        if let Err(e) = backend.start_capture_sources(source1_id, source2_id) {
-            return Err(e);
-        }
+        backend.start_capture_sources(source1_id, source2_id)?;
     } else {
-        return Err("Audio backend not available".to_string());
+        return Err(FlowSttError::BackendUnavailable(
+            "no audio backend was initialized".to_string(),
+        ));
     }
 
     // Start PTT audio processing loop (simpler than the main audio loop - no VAD)
@@ -283,6 +385,7 @@ fn stop_ptt_capture() {
         transcribe.finalize();
         transcribe.deactivate();
         transcribe.set_ptt_mode(false); // Restore automatic segmentation for next use
+        transcribe.set_output_override(None);
     }
 
     // Stop capture
@@ -320,7 +423,7 @@ fn start_ptt_audio_loop() {
     loop_active.store(true, Ordering::SeqCst);
 
     // Get sample rate from backend
-    let sample_rate = platform::get_backend()
+    let mut sample_rate = platform::get_backend()
         .map(|b| b.sample_rate())
         .unwrap_or(48000);
 
@@ -346,14 +449,35 @@ fn start_ptt_audio_loop() {
                 break;
             }
 
-            // Try to receive audio from backend
-            let audio_data = platform::get_backend().and_then(|b| b.try_recv());
+            // Wait for audio from the backend
+            let audio_data = platform::recv_audio(AUDIO_RECV_TIMEOUT);
 
             if let Some(data) = audio_data {
+                crate::rolling_buffer::push(&data.samples, data.sample_rate, data.channels);
+
+                // Follow the rate capture delivers, which is 16kHz with
+                // speech capture
+                if data.sample_rate != sample_rate {
+                    sample_rate = data.sample_rate;
+                    viz_processor = VisualizationProcessor::new(sample_rate, 256);
+                    viz_processor.set_callback(Arc::new(PttVisualizationBroadcaster));
+                }
+
                 // Convert to mono for visualization
                 let mono_samples = convert_to_mono(&data.samples, data.channels as usize);
 
-                // Process visualization
+                // Process visualization, with separate traces for each source
+                if let Some(sources) = data.sources.as_ref() {
+                    let channels = sources.channels as usize;
+                    viz_processor.push_source_samples(
+                        AudioSourceType::Input,
+                        &convert_to_mono(&sources.input, channels),
+                    );
+                    viz_processor.push_source_samples(
+                        AudioSourceType::System,
+                        &convert_to_mono(&sources.system, channels),
+                    );
+                }
                 viz_processor.process(&mono_samples);
 
                 // Write audio to transcribe state (no VAD - PTT controller manages segments)
@@ -362,9 +486,6 @@ fn start_ptt_audio_loop() {
                         transcribe.process_samples(&data.samples);
                     }
                 }
-            } else {
-                // No data available, sleep briefly
-                thread::sleep(Duration::from_millis(1));
             }
         }
 
@@ -377,10 +498,10 @@ fn stop_ptt_audio_loop() {
     get_ptt_audio_loop_active().store(false, Ordering::SeqCst);
 }
 
-/// Convert multi-channel audio to mono
-fn convert_to_mono(samples: &[f32], channels: usize) -> Vec<f32> {
+/// Convert multi-channel audio to mono; mono audio is borrowed as it is
+fn convert_to_mono(samples: &[f32], channels: usize) -> Cow<'_, [f32]> {
     if channels <= 1 {
-        return samples.to_vec();
+        return Cow::Borrowed(samples);
     }
     samples
         .chunks(channels)
@@ -412,6 +533,7 @@ impl VisualizationCallback for PttVisualizationBroadcaster {
                     is_lookback_speech: m.is_lookback_speech,
                     is_word_break: m.is_word_break,
                 }),
+            source_waveforms: payload.source_waveforms,
         };
         broadcast_event(Response::Event {
             event: EventType::VisualizationData(data),
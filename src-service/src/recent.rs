@@ -0,0 +1,134 @@
+//! Recent transcript of the active capture session.
+//!
+//! Completed transcriptions are kept for the last `MAX_RECENT_TRANSCRIPT_SECS`
+//! of the session so companion tools can ask for the last few minutes of
+//! context when they need it instead of following every event. The
+//! transcript starts over when capture starts, and its text is redacted as
+//! for events since it goes to the same clients.
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use flowstt_common::{RecentSegment, RecentTranscript, MAX_RECENT_TRANSCRIPT_SECS};
+
+static TRANSCRIPT: Mutex<RecentBuffer> = Mutex::new(RecentBuffer::new());
+
+/// Transcriptions completed within the longest stretch that can be requested.
+#[derive(Debug, Default)]
+pub struct RecentBuffer {
+    segments: VecDeque<(Instant, RecentSegment)>,
+}
+
+impl RecentBuffer {
+    pub const fn new() -> Self {
+        Self {
+            segments: VecDeque::new(),
+        }
+    }
+
+    /// Add a transcription completed at `at`, dropping those that have aged
+    /// out of the buffer.
+    pub fn push(&mut self, at: Instant, segment: RecentSegment) {
+        let window = Duration::from_secs(MAX_RECENT_TRANSCRIPT_SECS as u64);
+        while let Some((completed, _)) = self.segments.front() {
+            if at.saturating_duration_since(*completed) <= window {
+                break;
+            }
+            self.segments.pop_front();
+        }
+        self.segments.push_back((at, segment));
+    }
+
+    /// Transcriptions completed in the `seconds` before `now`.
+    pub fn last(&self, now: Instant, seconds: u32) -> RecentTranscript {
+        let window = Duration::from_secs(seconds as u64);
+        let segments: Vec<RecentSegment> = self
+            .segments
+            .iter()
+            .filter(|(completed, _)| now.saturating_duration_since(*completed) <= window)
+            .map(|(_, segment)| segment.clone())
+            .collect();
+        let text = segments
+            .iter()
+            .map(|segment| segment.text.trim())
+            .filter(|text| !text.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+        RecentTranscript { text, segments }
+    }
+
+    pub fn clear(&mut self) {
+        self.segments.clear();
+    }
+}
+
+/// Start the transcript over for a new capture session.
+pub fn clear() {
+    TRANSCRIPT.lock().unwrap().clear();
+}
+
+/// Add a completed transcription saved to history as `id`.
+pub fn push(id: &str, text: String, speaker: Option<String>) {
+    TRANSCRIPT.lock().unwrap().push(
+        Instant::now(),
+        RecentSegment {
+            id: id.to_string(),
+            completed_at: chrono::Local::now().to_rfc3339(),
+            text,
+            speaker,
+        },
+    );
+}
+
+/// Text transcribed in the last `seconds` of the active session.
+pub fn last(seconds: u32) -> RecentTranscript {
+    TRANSCRIPT.lock().unwrap().last(Instant::now(), seconds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(text: &str) -> RecentSegment {
+        RecentSegment {
+            id: text.to_string(),
+            completed_at: String::new(),
+            text: text.to_string(),
+            speaker: None,
+        }
+    }
+
+    #[test]
+    fn test_last_covers_requested_stretch() {
+        let start = Instant::now();
+        let mut buffer = RecentBuffer::new();
+        buffer.push(start, segment("Good morning."));
+        buffer.push(start + Duration::from_secs(60), segment(" Budget first. "));
+        buffer.push(start + Duration::from_secs(90), segment("Then hiring."));
+
+        let now = start + Duration::from_secs(120);
+        let recent = buffer.last(now, 60);
+        assert_eq!(recent.text, "Budget first. Then hiring.");
+        assert_eq!(recent.segments.len(), 2);
+        assert_eq!(
+            buffer.last(now, 300).text,
+            "Good morning. Budget first. Then hiring."
+        );
+        assert!(buffer.last(now, 10).segments.is_empty());
+
+        buffer.clear();
+        assert!(buffer.last(now, 300).text.is_empty());
+    }
+
+    #[test]
+    fn test_old_segments_are_dropped() {
+        let start = Instant::now();
+        let mut buffer = RecentBuffer::new();
+        buffer.push(start, segment("old"));
+        let later = start + Duration::from_secs(MAX_RECENT_TRANSCRIPT_SECS as u64 + 1);
+        buffer.push(later, segment("new"));
+        assert_eq!(buffer.segments.len(), 1);
+        assert_eq!(buffer.last(later, MAX_RECENT_TRANSCRIPT_SECS).text, "new");
+    }
+}
@@ -0,0 +1,337 @@
+//! Redaction of sensitive data in transcripts.
+//!
+//! Email addresses, phone numbers, card numbers and custom patterns are
+//! replaced with placeholders such as `[email]` before text reaches the
+//! channels redaction is enabled for. Word timings are redacted along with the
+//! text: the words a match spans are merged into one placeholder word covering
+//! their time, so playback and subtitles stay in sync without leaking the data.
+
+use std::ops::Range;
+use std::sync::Mutex;
+
+use flowstt_common::{TranscriptSession, WordTiming};
+use regex::Regex;
+
+use crate::config::{RedactionChannel, RedactionConfig};
+use crate::transcription::{Transcript, NO_SPEECH_TEXT};
+
+/// Written email addresses
+const EMAIL_PATTERN: &str = r"(?i)\b[a-z0-9._%+-]+@[a-z0-9-]+(?:\.[a-z0-9-]+)*\.[a-z]{2,}\b";
+
+/// Spoken email addresses as whisper tends to write them ("jane at example dot com")
+const SPOKEN_EMAIL_PATTERN: &str = r"(?i)\b[a-z0-9._%+-]+ at [a-z0-9-]+(?: dot [a-z0-9-]+)* dot (?:com|org|net|edu|gov|io|co|uk|de|fr|ca|au)\b";
+
+/// North American numbers ("(555) 123-4567", "555.123.4567") and
+/// international ones written with a leading `+`
+const PHONE_PATTERN: &str = r"(?:\+\d{1,3}[\s.-]?)?(?:\(\d{3}\)\s?|\b\d{3}[\s.-]?)\d{3}[\s.-]?\d{4}\b|\+\d{1,3}(?:[\s.-]?\d{2,4}){2,5}\b";
+
+/// 13 to 19 digits, optionally grouped with spaces or dashes
+const CARD_PATTERN: &str = r"\b\d(?:[ -]?\d){12,18}\b";
+
+/// Active redactor (`None` when no channel is redacted)
+static REDACTOR: Mutex<Option<Redactor>> = Mutex::new(None);
+
+/// Replace the redaction settings.
+///
+/// Custom patterns that fail to compile are logged and skipped.
+pub fn set_config(config: &RedactionConfig) {
+    let redactor = (!config.channels.is_empty()).then(|| Redactor::new(config));
+    *REDACTOR.lock().unwrap() = redactor;
+}
+
+/// Whether text sent to `channel` is redacted.
+pub fn is_enabled(channel: RedactionChannel) -> bool {
+    REDACTOR
+        .lock()
+        .unwrap()
+        .as_ref()
+        .is_some_and(|r| r.channels.contains(&channel))
+}
+
+/// `text` as it may be sent to `channel`.
+pub fn text(channel: RedactionChannel, text: &str) -> String {
+    let guard = REDACTOR.lock().unwrap();
+    match guard.as_ref().filter(|r| r.channels.contains(&channel)) {
+        Some(redactor) => redactor.redact(text),
+        None => text.to_string(),
+    }
+}
+
+/// Redact a transcript for `channel`, marking it as modified if anything was
+/// masked.
+pub fn transcript(channel: RedactionChannel, transcript: &mut Transcript) {
+    let guard = REDACTOR.lock().unwrap();
+    let Some(redactor) = guard.as_ref().filter(|r| r.channels.contains(&channel)) else {
+        return;
+    };
+    if transcript.text == NO_SPEECH_TEXT {
+        return;
+    }
+
+    for segment in &mut transcript.segments {
+        segment.text = redactor.redact(&segment.text);
+    }
+    for segment in &mut transcript.confidence {
+        segment.text = redactor.redact(&segment.text);
+    }
+    if let Some(translation) = &mut transcript.translation {
+        *translation = redactor.redact(translation);
+    }
+    redactor.redact_words(&mut transcript.words);
+
+    let text = redactor.redact(&transcript.text);
+    if text != transcript.text {
+        transcript.text = text;
+        transcript.modified = true;
+    }
+}
+
+/// Redact a saved session for `channel`.
+pub fn session(channel: RedactionChannel, session: &mut TranscriptSession) {
+    let guard = REDACTOR.lock().unwrap();
+    let Some(redactor) = guard.as_ref().filter(|r| r.channels.contains(&channel)) else {
+        return;
+    };
+    session.text = redactor.redact(&session.text);
+    for segment in &mut session.segments {
+        segment.text = redactor.redact(&segment.text);
+    }
+    if let Some(translation) = &mut session.translation {
+        *translation = redactor.redact(translation);
+    }
+    redactor.redact_words(&mut session.words);
+}
+
+/// A kind of sensitive data and how to find it.
+struct Rule {
+    regex: Regex,
+    /// Placeholder that replaces matches
+    label: &'static str,
+    /// Extra check a match must pass
+    accept: fn(&str) -> bool,
+}
+
+/// Compiled redaction rules.
+struct Redactor {
+    channels: Vec<RedactionChannel>,
+    rules: Vec<Rule>,
+}
+
+impl Redactor {
+    fn new(config: &RedactionConfig) -> Self {
+        let rule = |pattern: &str, label: &'static str, accept: fn(&str) -> bool| Rule {
+            regex: Regex::new(pattern).expect("built-in pattern is valid"),
+            label,
+            accept,
+        };
+
+        // Cards go first so their digit groups aren't taken for phone numbers
+        let mut rules = Vec::new();
+        if config.card_numbers {
+            rules.push(rule(CARD_PATTERN, "[card]", passes_luhn));
+        }
+        if config.emails {
+            rules.push(rule(EMAIL_PATTERN, "[email]", |_| true));
+            rules.push(rule(SPOKEN_EMAIL_PATTERN, "[email]", |_| true));
+        }
+        if config.phone_numbers {
+            rules.push(rule(PHONE_PATTERN, "[phone]", |_| true));
+        }
+        for pattern in &config.patterns {
+            match Regex::new(pattern) {
+                Ok(regex) => rules.push(Rule {
+                    regex,
+                    label: "[redacted]",
+                    accept: |_| true,
+                }),
+                Err(e) => {
+                    tracing::warn!("Ignoring invalid redaction pattern {:?}: {}", pattern, e);
+                }
+            }
+        }
+
+        Self {
+            channels: config.channels.clone(),
+            rules,
+        }
+    }
+
+    /// Non-overlapping matches in `text` in order, with their placeholders.
+    /// Earlier rules win where matches overlap.
+    fn find(&self, text: &str) -> Vec<(Range<usize>, &'static str)> {
+        let mut found: Vec<(Range<usize>, &'static str)> = Vec::new();
+        for rule in &self.rules {
+            for m in rule.regex.find_iter(text) {
+                let overlaps = found
+                    .iter()
+                    .any(|(range, _)| range.start < m.end() && m.start() < range.end);
+                if !m.is_empty() && !overlaps && (rule.accept)(m.as_str()) {
+                    found.push((m.range(), rule.label));
+                }
+            }
+        }
+        found.sort_by_key(|(range, _)| range.start);
+        found
+    }
+
+    fn redact(&self, text: &str) -> String {
+        splice(text, 0, &self.find(text))
+    }
+
+    /// Redact word timings. Words a match spans are merged into one word
+    /// holding the placeholder and any text around the match.
+    fn redact_words(&self, words: &mut Vec<WordTiming>) {
+        let mut joined = String::new();
+        let mut spans = Vec::with_capacity(words.len());
+        for word in words.iter() {
+            if !joined.is_empty() {
+                joined.push(' ');
+            }
+            let start = joined.len();
+            joined.push_str(word.word.trim());
+            spans.push(start..joined.len());
+        }
+        let matches = self.find(&joined);
+        if matches.is_empty() {
+            return;
+        }
+
+        let mut redacted = Vec::with_capacity(words.len());
+        let mut next = 0;
+        let mut i = 0;
+        while i < words.len() {
+            // Extend the group until it covers every match that starts in it
+            let first = next;
+            let mut last = i;
+            while next < matches.len() && matches[next].0.start < spans[last].end {
+                while spans[last].end < matches[next].0.end && last + 1 < words.len() {
+                    last += 1;
+                }
+                next += 1;
+            }
+            if next == first {
+                redacted.push(words[i].clone());
+                i += 1;
+                continue;
+            }
+
+            let range = spans[i].start..spans[last].end;
+            redacted.push(WordTiming {
+                word: splice(&joined[range.clone()], range.start, &matches[first..next]),
+                start_ms: words[i].start_ms,
+                end_ms: words[last].end_ms,
+                probability: words[i..=last]
+                    .iter()
+                    .map(|w| w.probability)
+                    .fold(f32::INFINITY, f32::min),
+            });
+            i = last + 1;
+        }
+        *words = redacted;
+    }
+}
+
+/// Replace `matches` in `text`, a slice starting at byte `offset` of the text
+/// they were found in, with their placeholders.
+fn splice(text: &str, offset: usize, matches: &[(Range<usize>, &str)]) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut copied = 0;
+    for (range, label) in matches {
+        let start = range.start.saturating_sub(offset).max(copied);
+        out.push_str(&text[copied..start]);
+        out.push_str(label);
+        copied = (range.end - offset).min(text.len());
+    }
+    out.push_str(&text[copied..]);
+    out
+}
+
+/// Whether the digits in `number` pass the Luhn checksum used by card numbers.
+fn passes_luhn(number: &str) -> bool {
+    let digits: Vec<u32> = number.chars().filter_map(|c| c.to_digit(10)).collect();
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &d)| match (i % 2, d * 2) {
+            (1, doubled) if doubled > 9 => doubled - 9,
+            (1, doubled) => doubled,
+            _ => d,
+        })
+        .sum();
+    sum % 10 == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn redactor() -> Redactor {
+        Redactor::new(&RedactionConfig {
+            channels: vec![RedactionChannel::History],
+            patterns: vec![r"\bACCT-\d+\b".to_string(), "(unclosed".to_string()],
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_redact_text() {
+        let redactor = redactor();
+        assert_eq!(
+            redactor.redact("Mail jane.doe@example.co.uk or call (555) 123-4567."),
+            "Mail [email] or call [phone]."
+        );
+        assert_eq!(
+            redactor.redact("It's jane at example dot com, +44 20 7946 0958"),
+            "It's [email], [phone]"
+        );
+        assert_eq!(
+            redactor.redact("Card 4111 1111 1111 1111, account ACCT-829"),
+            "Card [card], account [redacted]"
+        );
+        // Digit runs that fail the checksum aren't cards
+        assert_eq!(
+            redactor.redact("Order 4111 1111 1111 1112"),
+            "Order 4111 1111 1111 1112"
+        );
+        assert_eq!(redactor.redact("Meet at 3 on May 5"), "Meet at 3 on May 5");
+    }
+
+    #[test]
+    fn test_redact_words() {
+        let word = |word: &str, start_ms| WordTiming {
+            word: word.to_string(),
+            start_ms,
+            end_ms: start_ms + 100,
+            probability: 0.9,
+        };
+        let mut words = vec![
+            word("Call", 0),
+            word("555-123-4567,", 100),
+            word("or", 200),
+            word("jane", 300),
+            word("at", 400),
+            word("example", 500),
+            word("dot", 600),
+            word("com.", 700),
+        ];
+        redactor().redact_words(&mut words);
+
+        let text: Vec<&str> = words.iter().map(|w| w.word.as_str()).collect();
+        assert_eq!(text, ["Call", "[phone],", "or", "[email]."]);
+        assert_eq!((words[3].start_ms, words[3].end_ms), (300, 800));
+    }
+
+    #[test]
+    fn test_detectors_can_be_disabled() {
+        let redactor = Redactor::new(&RedactionConfig {
+            channels: vec![RedactionChannel::Events],
+            emails: false,
+            ..Default::default()
+        });
+        assert_eq!(
+            redactor.redact("jane@example.com 555 123 4567"),
+            "jane@example.com [phone]"
+        );
+    }
+}
@@ -0,0 +1,242 @@
+//! Recording retention.
+//!
+//! Segment recordings would otherwise accumulate in the recordings directory
+//! forever. A background task removes the recordings of saved transcriptions
+//! once they are older than the age limit, then the oldest ones until the
+//! rest fit in the size limit. Files that no saved transcription refers to are
+//! left alone, since the recordings directory may be shared with other
+//! applications.
+
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use flowstt_common::{PruneResult, RecordingFormat, StorageStatus};
+use tracing::{info, warn};
+
+use crate::audio::recordings_dir;
+use crate::config::RetentionConfig;
+use crate::history::{get_history, History};
+
+/// How often the limits are enforced
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+const BYTES_PER_MB: u64 = 1024 * 1024;
+
+static CONFIG: Mutex<Option<RetentionConfig>> = Mutex::new(None);
+
+/// Replace the retention limits; they are enforced on the next pass.
+pub fn set_config(config: RetentionConfig) {
+    *CONFIG.lock().unwrap() = Some(config);
+}
+
+fn config() -> RetentionConfig {
+    CONFIG.lock().unwrap().unwrap_or_default()
+}
+
+/// A saved transcription's recording.
+#[derive(Debug, Clone, PartialEq)]
+struct Recording {
+    session_id: String,
+    created_at: DateTime<Utc>,
+    size: u64,
+}
+
+/// Enforce the retention limits hourly until shutdown.
+pub async fn run() {
+    while !crate::is_shutdown_requested() {
+        match tokio::task::spawn_blocking(prune).await {
+            Ok(result) if result.files_removed > 0 => info!(
+                "Retention removed {} recordings ({} bytes)",
+                result.files_removed, result.bytes_freed
+            ),
+            Ok(_) => {}
+            Err(e) => warn!("Retention pass failed: {}", e),
+        }
+        tokio::time::sleep(PRUNE_INTERVAL).await;
+    }
+}
+
+/// Remove recordings beyond the retention limits.
+pub fn prune() -> PruneResult {
+    let config = config();
+    let mut result = PruneResult::default();
+    if config.max_size_mb.is_none() && config.max_age_days.is_none() {
+        return result;
+    }
+
+    let mut history = get_history().lock().unwrap();
+    let recordings = managed_recordings(&history);
+    let expired = select_expired(&recordings, &config, Utc::now());
+    if expired.is_empty() {
+        return result;
+    }
+
+    for recording in expired {
+        let Some(path) = history
+            .get(&recording.session_id)
+            .and_then(|s| s.audio_path.clone())
+        else {
+            continue;
+        };
+        if let Err(e) = fs::remove_file(&path) {
+            warn!("Failed to remove recording {}: {}", path, e);
+            continue;
+        }
+        result.files_removed += 1;
+        result.bytes_freed += recording.size;
+
+        if config.keep_transcripts {
+            history.clear_audio(&recording.session_id);
+        } else if history.remove(&recording.session_id).is_some() {
+            result.sessions_removed += 1;
+        }
+    }
+
+    if let Err(e) = history.save() {
+        warn!("Failed to save history: {}", e);
+    }
+    result
+}
+
+/// Disk usage of recordings and history, with the current limits.
+pub fn status() -> StorageStatus {
+    let config = config();
+    let dir = recordings_dir();
+    let (recording_count, recordings_bytes) = audio_files(&dir);
+
+    let history = get_history().lock().unwrap();
+    let managed = managed_recordings(&history);
+    StorageStatus {
+        recordings_dir: dir.to_string_lossy().to_string(),
+        recording_count,
+        recordings_bytes,
+        managed_count: managed.len(),
+        managed_bytes: managed.iter().map(|r| r.size).sum(),
+        history_sessions: history.sessions().len(),
+        history_bytes: fs::metadata(History::history_path())
+            .map(|m| m.len())
+            .unwrap_or(0),
+        max_size_mb: config.max_size_mb,
+        max_age_days: config.max_age_days,
+        keep_transcripts: config.keep_transcripts,
+    }
+}
+
+/// Recordings of saved transcriptions that are still on disk, oldest first.
+fn managed_recordings(history: &History) -> Vec<Recording> {
+    history
+        .sessions()
+        .iter()
+        .filter_map(|session| {
+            let size = fs::metadata(session.audio_path.as_ref()?).ok()?.len();
+            let created_at = DateTime::parse_from_rfc3339(&session.created_at).ok()?;
+            Some(Recording {
+                session_id: session.id.clone(),
+                created_at: created_at.with_timezone(&Utc),
+                size,
+            })
+        })
+        .collect()
+}
+
+/// Recordings to remove: those past the age limit, then the oldest until the
+/// rest fit in the size limit. `recordings` are oldest first.
+fn select_expired<'a>(
+    recordings: &'a [Recording],
+    config: &RetentionConfig,
+    now: DateTime<Utc>,
+) -> Vec<&'a Recording> {
+    let cutoff = config
+        .max_age_days
+        .map(|days| now - chrono::Duration::days(days as i64));
+    let mut remaining: u64 = recordings.iter().map(|r| r.size).sum();
+    let limit = config.max_size_mb.map(|mb| mb * BYTES_PER_MB);
+
+    recordings
+        .iter()
+        .filter(|recording| {
+            let too_old = cutoff.is_some_and(|cutoff| recording.created_at < cutoff);
+            let over_size = limit.is_some_and(|limit| remaining > limit);
+            if too_old || over_size {
+                remaining -= recording.size;
+                true
+            } else {
+                false
+            }
+        })
+        .collect()
+}
+
+/// Number and total size of the audio files directly in `dir`.
+fn audio_files(dir: &Path) -> (usize, u64) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return (0, 0);
+    };
+    entries
+        .flatten()
+        .filter(|entry| {
+            entry
+                .path()
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .and_then(RecordingFormat::from_name)
+                .is_some()
+        })
+        .filter_map(|entry| entry.metadata().ok())
+        .filter(|metadata| metadata.is_file())
+        .fold((0, 0), |(count, bytes), metadata| {
+            (count + 1, bytes + metadata.len())
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn recording(id: &str, day: u32, mb: u64) -> Recording {
+        Recording {
+            session_id: id.to_string(),
+            created_at: Utc.with_ymd_and_hms(2026, 3, day, 12, 0, 0).unwrap(),
+            size: mb * BYTES_PER_MB,
+        }
+    }
+
+    #[test]
+    fn test_select_expired() {
+        let recordings = [
+            recording("a", 1, 40),
+            recording("b", 10, 40),
+            recording("c", 20, 40),
+            recording("d", 30, 40),
+        ];
+        let now = Utc.with_ymd_and_hms(2026, 3, 31, 0, 0, 0).unwrap();
+        let ids = |config: RetentionConfig| -> Vec<&str> {
+            select_expired(&recordings, &config, now)
+                .into_iter()
+                .map(|r| r.session_id.as_str())
+                .collect()
+        };
+
+        assert!(ids(RetentionConfig::default()).is_empty());
+        let by_age = RetentionConfig {
+            max_age_days: Some(15),
+            ..Default::default()
+        };
+        assert_eq!(ids(by_age), ["a", "b"]);
+        let by_size = RetentionConfig {
+            max_size_mb: Some(100),
+            ..Default::default()
+        };
+        assert_eq!(ids(by_size), ["a", "b"]);
+        let both = RetentionConfig {
+            max_size_mb: Some(50),
+            max_age_days: Some(25),
+            ..Default::default()
+        };
+        assert_eq!(ids(both), ["a", "b", "c"]);
+    }
+}
@@ -0,0 +1,361 @@
+//! Background re-transcription of saved transcriptions.
+//!
+//! A job transcribes the recordings of some saved sessions again with the
+//! configured model, for instance after switching to a larger one. Jobs run
+//! one at a time on a worker thread, which returns the model to the pool
+//! between sessions so live transcription isn't held up for the whole job.
+//! Each session's previous transcript is kept as a version in the history
+//! store, and progress is broadcast as `JobProgress` events.
+
+use flowstt_common::ipc::{EventType, Response};
+use flowstt_common::{JobError, JobState, RetranscribeJob};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::audio::{process_recorded_audio, process_split_sources, read_wav};
+use crate::config::RedactionChannel;
+use crate::history;
+use crate::ipc::broadcast_event;
+use crate::transcription::queue::{add_translation, PreparedAudio};
+use crate::transcription::{model, GpuSettings, Transcriber};
+
+/// How often the worker looks for queued jobs
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Finished jobs kept for `ListJobs`; older ones are forgotten
+const MAX_FINISHED_JOBS: usize = 20;
+
+/// Transcription settings captured when a job is queued.
+#[derive(Debug, Clone)]
+pub struct JobSettings {
+    pub gpu: GpuSettings,
+    pub language: String,
+}
+
+/// A job with what the worker needs to run it.
+struct Job {
+    status: RetranscribeJob,
+    settings: JobSettings,
+    /// Set to cancel the job, aborting the session being transcribed
+    abort: Arc<AtomicBool>,
+}
+
+/// Queued, running and recently finished jobs, oldest first.
+#[derive(Default)]
+struct Jobs {
+    jobs: Vec<Job>,
+}
+
+impl Jobs {
+    /// Queue a job and return its status.
+    fn push(
+        &mut self,
+        id: String,
+        session_ids: Vec<String>,
+        settings: JobSettings,
+    ) -> RetranscribeJob {
+        let status = RetranscribeJob {
+            id,
+            session_ids,
+            completed: 0,
+            current: None,
+            state: JobState::Queued,
+            model: model::model_label(),
+            errors: Vec::new(),
+            error: None,
+        };
+        self.jobs.push(Job {
+            status: status.clone(),
+            settings,
+            abort: Arc::new(AtomicBool::new(false)),
+        });
+        self.prune();
+        status
+    }
+
+    /// Start the oldest queued job, returning what the worker needs to run it.
+    fn start_next(&mut self) -> Option<(RetranscribeJob, JobSettings, Arc<AtomicBool>)> {
+        let job = self
+            .jobs
+            .iter_mut()
+            .find(|job| job.status.state == JobState::Queued)?;
+        job.status.state = JobState::Running;
+        Some((job.status.clone(), job.settings.clone(), job.abort.clone()))
+    }
+
+    /// Cancel a job that hasn't finished. Returns its status, or None if
+    /// there is no such job.
+    fn cancel(&mut self, id: &str) -> Option<RetranscribeJob> {
+        let job = self.jobs.iter_mut().find(|job| job.status.id == id)?;
+        if !job.status.is_finished() {
+            job.abort.store(true, Ordering::SeqCst);
+            // A running job notices the flag and reports itself cancelled
+            if job.status.state == JobState::Queued {
+                job.status.state = JobState::Cancelled;
+            }
+        }
+        Some(job.status.clone())
+    }
+
+    /// Record a running job's progress.
+    fn update(&mut self, status: &RetranscribeJob) {
+        if let Some(job) = self.jobs.iter_mut().find(|job| job.status.id == status.id) {
+            job.status = status.clone();
+        }
+        self.prune();
+    }
+
+    /// Forget the oldest finished jobs beyond [`MAX_FINISHED_JOBS`].
+    fn prune(&mut self) {
+        let finished = self
+            .jobs
+            .iter()
+            .filter(|job| job.status.is_finished())
+            .count();
+        let mut excess = finished.saturating_sub(MAX_FINISHED_JOBS);
+        self.jobs.retain(|job| {
+            if excess > 0 && job.status.is_finished() {
+                excess -= 1;
+                return false;
+            }
+            true
+        });
+    }
+
+    fn statuses(&self) -> Vec<RetranscribeJob> {
+        self.jobs.iter().map(|job| job.status.clone()).collect()
+    }
+}
+
+/// All jobs
+static JOBS: Mutex<Jobs> = Mutex::new(Jobs { jobs: Vec::new() });
+
+/// Source of job IDs
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Whether the worker thread has been started
+static WORKER_STARTED: AtomicBool = AtomicBool::new(false);
+
+/// Queue a job re-transcribing `session_ids` and return its status.
+pub fn submit(session_ids: Vec<String>, settings: JobSettings) -> RetranscribeJob {
+    let id = format!("job-{}", NEXT_ID.fetch_add(1, Ordering::SeqCst));
+    let status = JOBS.lock().unwrap().push(id, session_ids, settings);
+    info!(
+        "[Retranscribe] Queued {} ({} sessions)",
+        status.id,
+        status.session_ids.len()
+    );
+    broadcast(&status);
+    status
+}
+
+/// All jobs still known, oldest first.
+pub fn list() -> Vec<RetranscribeJob> {
+    JOBS.lock().unwrap().statuses()
+}
+
+/// Cancel a job. Returns its status, or None if there is no such job.
+pub fn cancel(id: &str) -> Option<RetranscribeJob> {
+    let status = JOBS.lock().unwrap().cancel(id)?;
+    if status.state == JobState::Cancelled {
+        info!("[Retranscribe] Cancelled {}", id);
+        broadcast(&status);
+    }
+    Some(status)
+}
+
+/// Start the worker thread that runs queued jobs.
+pub fn start_worker() {
+    if WORKER_STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    thread::spawn(|| {
+        while !crate::is_shutdown_requested() {
+            let next = JOBS.lock().unwrap().start_next();
+            match next {
+                Some((status, settings, abort)) => run(status, &settings, &abort),
+                None => thread::sleep(POLL_INTERVAL),
+            }
+        }
+    });
+}
+
+/// Run a job to completion, failure or cancellation.
+fn run(mut status: RetranscribeJob, settings: &JobSettings, abort: &Arc<AtomicBool>) {
+    info!("[Retranscribe] Running {} with {}", status.id, status.model);
+    report(&status);
+
+    let mut transcriber = Transcriber::new();
+    transcriber.set_gpu(settings.gpu);
+    transcriber.set_abort_flag(Some(abort.clone()));
+
+    // Fail the whole job up front rather than every session in turn
+    if let Err(e) = transcriber.load_model() {
+        warn!("[Retranscribe] {} failed: {}", status.id, e);
+        status.state = JobState::Failed;
+        status.error = Some(e.to_string());
+        report(&status);
+        return;
+    }
+    transcriber.release();
+
+    for id in status.session_ids.clone() {
+        if abort.load(Ordering::SeqCst) {
+            break;
+        }
+        status.current = Some(id.clone());
+        report(&status);
+
+        let result = retranscribe_session(&mut transcriber, &id, &settings.language, &status.model);
+        // Let live transcription have the model between sessions
+        transcriber.release();
+        match result {
+            Ok(()) => info!("[Retranscribe] Transcribed {} again", id),
+            Err(_) if abort.load(Ordering::SeqCst) => break,
+            Err(message) => {
+                warn!("[Retranscribe] Failed to transcribe {}: {}", id, message);
+                status.errors.push(JobError {
+                    session_id: id,
+                    message,
+                });
+            }
+        }
+        status.completed += 1;
+    }
+
+    status.current = None;
+    status.state = if abort.load(Ordering::SeqCst) {
+        JobState::Cancelled
+    } else {
+        JobState::Completed
+    };
+    info!(
+        "[Retranscribe] {} {:?} after {} of {} sessions",
+        status.id,
+        status.state,
+        status.completed,
+        status.session_ids.len()
+    );
+    report(&status);
+}
+
+/// Transcribe a session's recording again and replace its transcript.
+fn retranscribe_session(
+    transcriber: &mut Transcriber,
+    id: &str,
+    language: &str,
+    model: &str,
+) -> Result<(), String> {
+    let (audio_path, split, translated) = {
+        let history = history::get_history().lock().unwrap();
+        let session = history
+            .get(id)
+            .ok_or_else(|| format!("Session not found: {}", id))?;
+        let audio_path = session
+            .audio_path
+            .clone()
+            .ok_or_else(|| format!("Session {} has no saved recording", id))?;
+        (
+            audio_path,
+            !session.segments.is_empty(),
+            session.translation.is_some(),
+        )
+    };
+
+    let raw = read_wav(Path::new(&audio_path))?;
+    // Sessions transcribed per source were recorded with one source per channel
+    let prepared = if split && raw.channels == 2 {
+        process_split_sources(raw).map(|(mic, system)| PreparedAudio::Split { mic, system })?
+    } else {
+        PreparedAudio::Mixed(process_recorded_audio(raw)?)
+    };
+
+    let mut transcript = match &prepared {
+        PreparedAudio::Mixed(audio) => transcriber.transcribe(audio, language),
+        PreparedAudio::Split { mic, system } => {
+            transcriber.transcribe_sources(mic, system, language)
+        }
+    }
+    .map_err(|e| e.to_string())?;
+    if translated {
+        add_translation(transcriber, &prepared, &mut transcript);
+    }
+    crate::postprocess::apply(&mut transcript);
+    crate::redact::transcript(RedactionChannel::History, &mut transcript);
+
+    let mut history = history::get_history().lock().unwrap();
+    if !history.replace_transcript(id, &transcript, model.to_string()) {
+        return Err(format!("Session not found: {}", id));
+    }
+    history
+        .save()
+        .map_err(|e| format!("Failed to save history: {}", e))
+}
+
+/// Store a running job's progress and tell clients about it.
+fn report(status: &RetranscribeJob) {
+    JOBS.lock().unwrap().update(status);
+    broadcast(status);
+}
+
+fn broadcast(status: &RetranscribeJob) {
+    broadcast_event(Response::Event {
+        event: EventType::JobProgress(status.clone()),
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings() -> JobSettings {
+        JobSettings {
+            gpu: GpuSettings::default(),
+            language: "en".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_jobs_run_in_order_and_cancel() {
+        let mut jobs = Jobs::default();
+        jobs.push("job-1".to_string(), vec!["a".to_string()], settings());
+        jobs.push("job-2".to_string(), vec!["b".to_string()], settings());
+
+        let (mut first, _, abort) = jobs.start_next().unwrap();
+        assert_eq!(first.id, "job-1");
+        assert_eq!(first.state, JobState::Running);
+
+        // A queued job is cancelled at once, a running one when it notices
+        assert_eq!(jobs.cancel("job-2").unwrap().state, JobState::Cancelled);
+        assert_eq!(jobs.cancel("job-1").unwrap().state, JobState::Running);
+        assert!(abort.load(Ordering::SeqCst));
+        assert!(jobs.cancel("job-3").is_none());
+        assert!(jobs.start_next().is_none());
+
+        first.state = JobState::Cancelled;
+        jobs.update(&first);
+        let states: Vec<_> = jobs.statuses().iter().map(|job| job.state).collect();
+        assert_eq!(states, vec![JobState::Cancelled, JobState::Cancelled]);
+    }
+
+    #[test]
+    fn test_jobs_forget_oldest_finished() {
+        let mut jobs = Jobs::default();
+        for i in 0..MAX_FINISHED_JOBS + 2 {
+            let id = format!("job-{}", i);
+            jobs.push(id.clone(), Vec::new(), settings());
+            jobs.cancel(&id);
+        }
+        jobs.push("queued".to_string(), Vec::new(), settings());
+
+        let statuses = jobs.statuses();
+        assert_eq!(statuses.len(), MAX_FINISHED_JOBS + 1);
+        assert_eq!(statuses[0].id, "job-2");
+        assert_eq!(statuses.last().unwrap().id, "queued");
+    }
+}
@@ -0,0 +1,145 @@
+//! Rolling buffer of recent capture audio ("retroactive record").
+//!
+//! While capture runs, the last minute or so of audio is kept whether or not
+//! speech is being transcribed or recorded, so `CaptureLastN` can save and
+//! transcribe what was just said after the fact. The buffer holds audio as
+//! captured (interleaved, at the capture rate) and starts over when the capture
+//! format changes. In push-to-talk mode audio is only captured while the key is
+//! held, so the buffer only covers those stretches.
+
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crate::audio::{
+    generate_recording_filename, recording_format, recordings_dir, RawRecordedAudio,
+};
+use crate::config::RollingBufferConfig;
+use crate::encoder;
+
+static CONFIG: Mutex<Option<RollingBufferConfig>> = Mutex::new(None);
+
+static BUFFER: Mutex<Option<RollingBuffer>> = Mutex::new(None);
+
+/// Replace the buffer settings. Shrinking the buffer drops its oldest audio.
+pub fn set_config(config: RollingBufferConfig) {
+    *CONFIG.lock().unwrap() = Some(config);
+    let mut buffer = BUFFER.lock().unwrap();
+    if config.secs == 0 {
+        *buffer = None;
+    } else if let Some(buffer) = buffer.as_mut() {
+        buffer.trim(config.secs);
+    }
+}
+
+fn config() -> RollingBufferConfig {
+    CONFIG.lock().unwrap().unwrap_or_default()
+}
+
+/// Add captured audio to the buffer.
+pub fn push(samples: &[f32], sample_rate: u32, channels: u16) {
+    let secs = config().secs;
+    if secs == 0 {
+        return;
+    }
+
+    let mut buffer = BUFFER.lock().unwrap();
+    let buffer = match buffer.as_mut() {
+        Some(b) if b.sample_rate == sample_rate && b.channels == channels => b,
+        _ => buffer.insert(RollingBuffer::new(sample_rate, channels)),
+    };
+    buffer.push(samples, secs);
+}
+
+/// Copy of the last `secs` seconds of audio (all of it when None), or None if
+/// nothing has been captured.
+pub fn last(secs: Option<u32>) -> Option<RawRecordedAudio> {
+    let buffer = BUFFER.lock().unwrap();
+    let buffer = buffer.as_ref()?;
+    let samples = buffer.last(secs);
+    (!samples.is_empty()).then(|| RawRecordedAudio {
+        samples,
+        sample_rate: buffer.sample_rate,
+        channels: buffer.channels,
+    })
+}
+
+/// Save audio to the recordings directory.
+pub fn save(audio: &RawRecordedAudio) -> Result<PathBuf, String> {
+    let dir = recordings_dir();
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create recordings directory: {}", e))?;
+    let path = dir.join(generate_recording_filename());
+    encoder::save(
+        &audio.samples,
+        audio.sample_rate,
+        audio.channels,
+        &path,
+        recording_format(),
+    )?;
+    Ok(path)
+}
+
+/// Interleaved audio limited to a number of seconds.
+struct RollingBuffer {
+    samples: VecDeque<f32>,
+    sample_rate: u32,
+    channels: u16,
+}
+
+impl RollingBuffer {
+    fn new(sample_rate: u32, channels: u16) -> Self {
+        Self {
+            samples: VecDeque::new(),
+            sample_rate,
+            channels,
+        }
+    }
+
+    /// Number of interleaved samples in `secs` seconds
+    fn samples_in(&self, secs: u32) -> usize {
+        secs as usize * self.sample_rate as usize * self.channels as usize
+    }
+
+    fn push(&mut self, samples: &[f32], secs: u32) {
+        self.samples.extend(samples);
+        self.trim(secs);
+    }
+
+    /// Drop the oldest audio beyond `secs` seconds.
+    fn trim(&mut self, secs: u32) {
+        let excess = self.samples.len().saturating_sub(self.samples_in(secs));
+        self.samples.drain(..excess);
+    }
+
+    fn last(&self, secs: Option<u32>) -> Vec<f32> {
+        let count = secs.map_or(self.samples.len(), |secs| {
+            self.samples_in(secs).min(self.samples.len())
+        });
+        self.samples
+            .range(self.samples.len() - count..)
+            .copied()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rolling_buffer_keeps_latest_audio() {
+        // 10 Hz stereo keeps 20 samples per second
+        let mut buffer = RollingBuffer::new(10, 2);
+        let samples: Vec<f32> = (0..50).map(|i| i as f32).collect();
+        buffer.push(&samples, 2);
+
+        assert_eq!(buffer.samples.len(), 40);
+        assert_eq!(buffer.last(None).first(), Some(&10.0));
+        assert_eq!(buffer.last(Some(1)), samples[30..].to_vec());
+        assert_eq!(buffer.last(Some(5)).len(), 40);
+
+        buffer.trim(1);
+        assert_eq!(buffer.last(None), samples[30..].to_vec());
+    }
+}
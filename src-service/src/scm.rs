@@ -0,0 +1,76 @@
+//! Running under the Windows Service Control Manager.
+//!
+//! `flowstt service install --system` registers the executable with the SCM
+//! with [`SERVICE_ARG`], so it starts at boot before anyone logs in. A
+//! service runs as LocalSystem in session 0: it has its own configuration
+//! and log directory under the system profile, global push-to-talk hotkeys
+//! are unavailable since there is no interactive desktop, and only elevated
+//! clients may connect to its pipe.
+
+use std::ffi::OsString;
+use std::time::Duration;
+
+use tracing::{error, info};
+use windows_service::service::{
+    ServiceControl, ServiceControlAccept, ServiceExitCode, ServiceState, ServiceStatus, ServiceType,
+};
+use windows_service::service_control_handler::{self, ServiceControlHandlerResult};
+use windows_service::{define_windows_service, service_dispatcher};
+
+/// Name the service is registered with
+pub const SERVICE_NAME: &str = "FlowSTT";
+
+/// Argument the SCM starts the executable with
+pub const SERVICE_ARG: &str = "--windows-service";
+
+define_windows_service!(ffi_service_main, service_main);
+
+/// Whether the process was started by the SCM.
+pub fn is_service_mode() -> bool {
+    std::env::args().any(|arg| arg == SERVICE_ARG)
+}
+
+/// Hand the main thread to the SCM dispatcher. Returns once the service stopped.
+pub fn run() -> Result<(), String> {
+    service_dispatcher::start(SERVICE_NAME, ffi_service_main)
+        .map_err(|e| format!("Failed to connect to the service control manager: {}", e))
+}
+
+fn service_main(_arguments: Vec<OsString>) {
+    if let Err(e) = run_service() {
+        error!("Windows service error: {}", e);
+    }
+}
+
+fn run_service() -> windows_service::Result<()> {
+    let status_handle = service_control_handler::register(SERVICE_NAME, |control| match control {
+        ServiceControl::Stop | ServiceControl::Shutdown => {
+            info!("Stop requested by the service control manager");
+            crate::request_shutdown();
+            ServiceControlHandlerResult::NoError
+        }
+        ServiceControl::Interrogate => ServiceControlHandlerResult::NoError,
+        _ => ServiceControlHandlerResult::NotImplemented,
+    })?;
+
+    status_handle.set_service_status(status(
+        ServiceState::Running,
+        ServiceControlAccept::STOP | ServiceControlAccept::SHUTDOWN,
+    ))?;
+
+    crate::run();
+
+    status_handle.set_service_status(status(ServiceState::Stopped, ServiceControlAccept::empty()))
+}
+
+fn status(state: ServiceState, controls_accepted: ServiceControlAccept) -> ServiceStatus {
+    ServiceStatus {
+        service_type: ServiceType::OWN_PROCESS,
+        current_state: state,
+        controls_accepted,
+        exit_code: ServiceExitCode::Win32(0),
+        checkpoint: 0,
+        wait_hint: Duration::default(),
+        process_id: None,
+    }
+}
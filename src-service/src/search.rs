@@ -0,0 +1,274 @@
+//! Full-text search over saved transcriptions.
+//!
+//! History is small enough to keep in memory, so searching scans it rather
+//! than maintaining an index. A session matches when every query term starts
+//! one of the words of its text, translation, summary, title, notes, tags or
+//! participants, ignoring case. Matches in the title and tags count for more
+//! than matches in the text; hits are ordered by that score, then newest first.
+
+use flowstt_common::{DateRange, Highlight, SearchHit, TranscriptSession};
+
+/// Words of text shown before the first match in a snippet
+const SNIPPET_WORDS_BEFORE: usize = 8;
+
+/// Words of text shown from the first match on in a snippet
+const SNIPPET_WORDS_AFTER: usize = 16;
+
+/// Score of a term matched in the title or tags, per occurrence
+const TITLE_WEIGHT: u32 = 5;
+
+/// Split text into lowercase words with their byte ranges in `text`.
+fn words(text: &str) -> Vec<(String, usize, usize)> {
+    let mut words = Vec::new();
+    let mut start = None;
+    for (i, c) in text
+        .char_indices()
+        .chain(std::iter::once((text.len(), ' ')))
+    {
+        let in_word = c.is_alphanumeric() || (c == '\'' && start.is_some());
+        match (in_word, start) {
+            (true, None) => start = Some(i),
+            (false, Some(s)) => {
+                let word = text[s..i].trim_end_matches('\'');
+                words.push((word.to_lowercase(), s, s + word.len()));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    words
+}
+
+/// Query terms: its lowercase words, without duplicates.
+fn terms(query: &str) -> Vec<String> {
+    let mut terms: Vec<String> = Vec::new();
+    for (word, _, _) in words(query) {
+        if !terms.contains(&word) {
+            terms.push(word);
+        }
+    }
+    terms
+}
+
+fn matches_any(word: &str, terms: &[String]) -> bool {
+    terms.iter().any(|term| word.starts_with(term.as_str()))
+}
+
+/// Search `sessions` for `query`, returning at most `limit` hits, best first.
+pub fn search(
+    sessions: &[TranscriptSession],
+    query: &str,
+    date_range: Option<&DateRange>,
+    limit: Option<usize>,
+) -> Vec<SearchHit> {
+    let terms = terms(query);
+    if terms.is_empty() {
+        return Vec::new();
+    }
+
+    let mut hits: Vec<SearchHit> = sessions
+        .iter()
+        .rev()
+        .filter(|session| date_range.is_none_or(|range| range.contains(&session.created_at)))
+        .filter_map(|session| match_session(session, &terms))
+        .collect();
+    // Stable, so equal scores stay newest first
+    hits.sort_by_key(|hit| std::cmp::Reverse(hit.score));
+    hits.truncate(limit.unwrap_or(usize::MAX));
+    hits
+}
+
+/// Build the hit for a session, if it contains every term.
+fn match_session(session: &TranscriptSession, terms: &[String]) -> Option<SearchHit> {
+    let metadata = &session.metadata;
+    let text_words = words(&session.text);
+    let title_words: Vec<String> = metadata
+        .title
+        .iter()
+        .chain(&metadata.tags)
+        .flat_map(|text| words(text))
+        .map(|(word, _, _)| word)
+        .collect();
+    let other_words: Vec<String> = session
+        .translation
+        .iter()
+        .chain(&session.summary)
+        .chain(&metadata.notes)
+        .chain(&metadata.participants)
+        .flat_map(|text| words(text))
+        .map(|(word, _, _)| word)
+        .collect();
+
+    let mut score = 0;
+    for term in terms {
+        let starts = |word: &String| word.starts_with(term.as_str());
+        let in_text = text_words
+            .iter()
+            .filter(|(word, _, _)| starts(word))
+            .count() as u32;
+        let in_title = title_words.iter().filter(|word| starts(word)).count() as u32;
+        if in_text + in_title == 0 && !other_words.iter().any(starts) {
+            return None;
+        }
+        score += in_text + TITLE_WEIGHT * in_title;
+    }
+
+    let first = text_words
+        .iter()
+        .position(|(word, _, _)| matches_any(word, terms));
+    let (snippet, highlights) = snippet(&session.text, &text_words, first, terms);
+    let offset_ms = first.and_then(|_| {
+        session
+            .words
+            .iter()
+            .find(|timing| {
+                words(&timing.word)
+                    .iter()
+                    .any(|(w, _, _)| matches_any(w, terms))
+            })
+            .map(|timing| timing.start_ms)
+    });
+
+    Some(SearchHit {
+        id: session.id.clone(),
+        created_at: session.created_at.clone(),
+        title: metadata.title.clone(),
+        snippet,
+        highlights,
+        offset_ms,
+        score: score.max(1),
+    })
+}
+
+/// Cut the text around the word at index `first`, or the start of the text
+/// when nothing matched in it, and mark the matched words.
+fn snippet(
+    text: &str,
+    text_words: &[(String, usize, usize)],
+    first: Option<usize>,
+    terms: &[String],
+) -> (String, Vec<Highlight>) {
+    if text_words.is_empty() {
+        return (text.trim().to_string(), Vec::new());
+    }
+    let from = first.map_or(0, |i| i.saturating_sub(SNIPPET_WORDS_BEFORE));
+    let to = (first.unwrap_or(0) + SNIPPET_WORDS_AFTER).min(text_words.len());
+    let start = if from == 0 { 0 } else { text_words[from].1 };
+    let end = if to == text_words.len() {
+        text.len()
+    } else {
+        text_words[to - 1].2
+    };
+
+    let mut snippet = String::new();
+    if start > 0 {
+        snippet.push('…');
+    }
+    let base = snippet.len() as isize - start as isize;
+    snippet.push_str(&text[start..end]);
+    if end < text.len() {
+        snippet.push('…');
+    }
+
+    let highlights = text_words[from..to]
+        .iter()
+        .filter(|(word, _, _)| first.is_some() && matches_any(word, terms))
+        .map(|&(_, s, e)| Highlight {
+            start: (s as isize + base) as usize,
+            end: (e as isize + base) as usize,
+        })
+        .collect();
+    (snippet.trim().to_string(), highlights)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flowstt_common::WordTiming;
+
+    fn session(id: &str, created_at: &str, text: &str) -> TranscriptSession {
+        TranscriptSession {
+            id: id.to_string(),
+            created_at: created_at.to_string(),
+            text: text.to_string(),
+            duration_ms: 1000,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_search_requires_every_term() {
+        let sessions = vec![
+            session(
+                "a",
+                "2026-03-01T10:00:00+00:00",
+                "The budget review is on Monday.",
+            ),
+            session("b", "2026-03-02T10:00:00+00:00", "Budgets were cut."),
+            session("c", "2026-03-03T10:00:00+00:00", "Nothing to see here."),
+        ];
+
+        let hits = search(&sessions, "budget", None, None);
+        let ids: Vec<&str> = hits.iter().map(|h| h.id.as_str()).collect();
+        assert_eq!(ids, ["b", "a"]);
+
+        let hits = search(&sessions, "Budget REVIEW", None, None);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].id, "a");
+        assert_eq!(hits[0].snippet, "The budget review is on Monday.");
+        let marked: Vec<&str> = hits[0]
+            .highlights
+            .iter()
+            .map(|h| &hits[0].snippet[h.start..h.end])
+            .collect();
+        assert_eq!(marked, ["budget", "review"]);
+
+        assert!(search(&sessions, "  ", None, None).is_empty());
+        assert_eq!(search(&sessions, "budget", None, Some(1)).len(), 1);
+    }
+
+    #[test]
+    fn test_search_date_range_and_metadata() {
+        let mut tagged = session("a", "2026-03-01T10:00:00+00:00", "Let's begin.");
+        tagged.metadata.title = Some("Budget review".to_string());
+        let mut noted = session("b", "2026-03-05T10:00:00+00:00", "Budget numbers.");
+        noted.words = vec![WordTiming {
+            word: "Budget".to_string(),
+            start_ms: 1200,
+            end_ms: 1500,
+            probability: 0.9,
+        }];
+        let sessions = vec![tagged, noted];
+
+        // Title matches outrank text matches
+        let hits = search(&sessions, "budget", None, None);
+        assert_eq!(hits[0].id, "a");
+        assert!(hits[0].highlights.is_empty());
+        assert_eq!(hits[0].offset_ms, None);
+        assert_eq!(hits[1].offset_ms, Some(1200));
+
+        let march_5 = DateRange {
+            from: Some("2026-03-02".to_string()),
+            to: Some("2026-03-05".to_string()),
+        };
+        let hits = search(&sessions, "budget", Some(&march_5), None);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].id, "b");
+    }
+
+    #[test]
+    fn test_snippet_is_cut_around_the_first_match() {
+        let text = (1..=40)
+            .map(|i| format!("word{}", i))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let text_words = words(&text);
+        let terms = vec!["word20".to_string()];
+        let (snippet, highlights) = snippet(&text, &text_words, Some(19), &terms);
+        assert!(snippet.starts_with("…word12 "));
+        assert!(snippet.ends_with(" word35…"));
+        assert_eq!(highlights.len(), 1);
+        let h = highlights[0];
+        assert_eq!(&snippet[h.start..h.end], "word20");
+    }
+}
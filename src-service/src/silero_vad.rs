@@ -0,0 +1,529 @@
+//! Silero VAD speech detector.
+//!
+//! Runs the Silero voice activity model with ONNX Runtime. The model scores
+//! each 32ms window of 16kHz audio with a speech probability, which holds up
+//! against music and steady background noise far better than the level and
+//! zero-crossing heuristics of the energy detector. Speech starts once
+//! windows have scored above `speech_probability` for the onset time, and
+//! ends after `hold_ms` of windows scoring below it by a hysteresis margin.
+//!
+//! The model is downloaded next to the whisper model on first use.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use flowstt_common::{SpeechDetectorConfig, VadBackend};
+use ort::session::Session;
+use ort::value::Tensor;
+
+use crate::processor::{
+    SpeechEventCallback, SpeechEventPayload, SpeechMetrics, SpeechStateChange,
+    VoiceActivityDetector, WordBreakEvent, WordBreakPayload,
+};
+
+const MODEL_URL: &str =
+    "https://github.com/snakers4/silero-vad/raw/master/src/silero_vad/data/silero_vad.onnx";
+
+/// Sample rate the model is run at
+const SILERO_SAMPLE_RATE: u32 = 16000;
+
+/// Samples per model window (32ms)
+const WINDOW_SAMPLES: usize = 512;
+
+/// Trailing samples of the previous window prepended to each model input
+const CONTEXT_SAMPLES: usize = 64;
+
+/// Size of the model's recurrent state, shaped [2, 1, 128]
+const STATE_SAMPLES: usize = 2 * 128;
+
+/// Duration of one model window in milliseconds
+const WINDOW_MS: u32 = 32;
+
+/// How far below `speech_probability` a window must score to count as silence
+const HYSTERESIS: f32 = 0.15;
+
+/// Audio before the first speech window included in the lookback
+const LOOKBACK_MARGIN_MS: u32 = 64;
+
+/// Gaps between these durations are reported as word breaks
+const MIN_WORD_BREAK_MS: u32 = 80;
+const MAX_WORD_BREAK_MS: u32 = 250;
+
+/// Set while a model download is running
+static DOWNLOADING: AtomicBool = AtomicBool::new(false);
+
+/// Path of the Silero model, next to the whisper model.
+pub fn model_path() -> PathBuf {
+    let cache_dir = directories::BaseDirs::new()
+        .map(|d| d.cache_dir().to_path_buf())
+        .unwrap_or_else(|| PathBuf::from("."));
+    cache_dir.join("whisper").join("silero_vad.onnx")
+}
+
+/// Download the Silero model to the specified path.
+pub fn download_model(model_path: &Path) -> Result<(), String> {
+    if let Some(parent) = model_path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create directory: {}", e))?;
+    }
+
+    tracing::info!("Downloading Silero VAD model to: {}", model_path.display());
+
+    let response = reqwest::blocking::get(MODEL_URL)
+        .map_err(|e| format!("Failed to download model: {}", e))?;
+
+    if !response.status().is_success() {
+        return Err(format!(
+            "Failed to download model: HTTP {}",
+            response.status()
+        ));
+    }
+
+    let bytes = response
+        .bytes()
+        .map_err(|e| format!("Failed to read response: {}", e))?;
+
+    std::fs::write(model_path, &bytes).map_err(|e| format!("Failed to write file: {}", e))?;
+
+    tracing::info!("Silero VAD model downloaded ({} bytes)", bytes.len());
+
+    Ok(())
+}
+
+/// Download the model in the background if `config` selects Silero and it's missing.
+///
+/// The audio loop runs the energy detector until the download finishes, then
+/// switches over.
+pub fn ensure_model(config: &SpeechDetectorConfig) {
+    if config.backend != VadBackend::Silero || model_path().exists() {
+        return;
+    }
+    if DOWNLOADING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    std::thread::spawn(|| {
+        match download_model(&model_path()) {
+            Ok(()) => crate::refresh_vad(),
+            Err(e) => tracing::error!("Failed to download Silero VAD model: {}", e),
+        }
+        DOWNLOADING.store(false, Ordering::SeqCst);
+    });
+}
+
+fn ort_error(e: ort::Error) -> String {
+    format!("ONNX Runtime error: {}", e)
+}
+
+/// Linear-interpolating resampler down to the model's sample rate.
+///
+/// There is no anti-aliasing filter; the model only needs the speech band,
+/// which sits well below the 8kHz Nyquist limit.
+struct Resampler {
+    /// Input samples per output sample
+    step: f64,
+    /// Position of the next output sample, relative to the next input chunk
+    pos: f64,
+    /// Last sample of the previous chunk
+    last: f32,
+}
+
+impl Resampler {
+    fn new(sample_rate: u32) -> Self {
+        Self {
+            step: sample_rate as f64 / SILERO_SAMPLE_RATE as f64,
+            pos: 0.0,
+            last: 0.0,
+        }
+    }
+
+    fn process(&mut self, samples: &[f32], out: &mut Vec<f32>) {
+        let Some(&last) = samples.last() else {
+            return;
+        };
+        let at = |i: isize| {
+            if i < 0 {
+                self.last
+            } else {
+                samples[i as usize]
+            }
+        };
+        while self.pos <= (samples.len() - 1) as f64 {
+            let index = self.pos.floor();
+            let frac = (self.pos - index) as f32;
+            let a = at(index as isize);
+            let b = if frac > 0.0 {
+                at(index as isize + 1)
+            } else {
+                a
+            };
+            out.push(a + (b - a) * frac);
+            self.pos += self.step;
+        }
+        self.pos -= samples.len() as f64;
+        self.last = last;
+    }
+}
+
+/// Result of feeding one window's speech probability to a [`SpeechGate`]
+#[derive(Debug, PartialEq)]
+enum GateEvent {
+    /// Speech confirmed after `onset_windows` speech windows
+    Started { onset_windows: u32 },
+    /// Speech ended after `speech_windows` speech windows
+    Ended { speech_windows: u32 },
+    /// A gap of `gap_windows` ended after `offset_windows` speech windows
+    WordBreak {
+        offset_windows: u32,
+        gap_windows: u32,
+    },
+}
+
+/// Turns per-window speech probabilities into speech start, end and word breaks.
+struct SpeechGate {
+    speech_threshold: f32,
+    onset_windows: u32,
+    hold_windows: u32,
+    is_speaking: bool,
+    onset_count: u32,
+    silence_count: u32,
+    speech_count: u32,
+}
+
+impl SpeechGate {
+    fn new(config: &SpeechDetectorConfig) -> Self {
+        let mut gate = Self {
+            speech_threshold: 0.0,
+            onset_windows: 0,
+            hold_windows: 0,
+            is_speaking: false,
+            onset_count: 0,
+            silence_count: 0,
+            speech_count: 0,
+        };
+        gate.set_config(config);
+        gate
+    }
+
+    /// Apply new settings; a pending onset restarts.
+    fn set_config(&mut self, config: &SpeechDetectorConfig) {
+        self.speech_threshold = config.speech_probability;
+        self.onset_windows = config.voiced_onset_ms.div_ceil(WINDOW_MS).max(1);
+        self.hold_windows = config.hold_ms.div_ceil(WINDOW_MS).max(1);
+        self.onset_count = 0;
+    }
+
+    /// Whether the current silence is long enough to be a word break
+    fn is_word_break(&self) -> bool {
+        let gap_ms = self.silence_count * WINDOW_MS;
+        self.is_speaking && (MIN_WORD_BREAK_MS..=MAX_WORD_BREAK_MS).contains(&gap_ms)
+    }
+
+    fn push(&mut self, probability: f32) -> Option<GateEvent> {
+        if !self.is_speaking {
+            if probability < self.speech_threshold {
+                self.onset_count = 0;
+                return None;
+            }
+            self.onset_count += 1;
+            if self.onset_count < self.onset_windows {
+                return None;
+            }
+            self.is_speaking = true;
+            self.speech_count = self.onset_count;
+            self.silence_count = 0;
+            self.onset_count = 0;
+            return Some(GateEvent::Started {
+                onset_windows: self.speech_count,
+            });
+        }
+
+        if probability >= self.speech_threshold - HYSTERESIS {
+            let event = self.is_word_break().then_some(GateEvent::WordBreak {
+                offset_windows: self.speech_count,
+                gap_windows: self.silence_count,
+            });
+            self.speech_count += 1;
+            self.silence_count = 0;
+            return event;
+        }
+
+        self.silence_count += 1;
+        if self.silence_count < self.hold_windows {
+            return None;
+        }
+        self.is_speaking = false;
+        self.silence_count = 0;
+        Some(GateEvent::Ended {
+            speech_windows: std::mem::take(&mut self.speech_count),
+        })
+    }
+}
+
+/// Speech detector backed by the Silero VAD model.
+pub struct SileroVad {
+    session: Session,
+    sample_rate: u32,
+    resampler: Resampler,
+    /// Resampled audio waiting for a full window
+    pending: Vec<f32>,
+    /// Model recurrent state carried between windows
+    state: Vec<f32>,
+    /// Tail of the previous window
+    context: Vec<f32>,
+    gate: SpeechGate,
+    last_amplitude_db: f32,
+    last_lookback_offset_ms: Option<u32>,
+    last_state_change: SpeechStateChange,
+    last_word_break_event: Option<WordBreakEvent>,
+    callback: Option<Arc<dyn SpeechEventCallback>>,
+}
+
+impl SileroVad {
+    /// Load the model for audio at `sample_rate`.
+    pub fn new(sample_rate: u32, config: &SpeechDetectorConfig) -> Result<Self, String> {
+        let path = model_path();
+        if !path.exists() {
+            return Err(format!("Model not found at {}", path.display()));
+        }
+        let session = Session::builder()
+            .and_then(|builder| builder.with_intra_threads(1))
+            .and_then(|builder| builder.commit_from_file(&path))
+            .map_err(ort_error)?;
+
+        Ok(Self {
+            session,
+            sample_rate,
+            resampler: Resampler::new(sample_rate),
+            pending: Vec::with_capacity(WINDOW_SAMPLES * 2),
+            state: vec![0.0; STATE_SAMPLES],
+            context: vec![0.0; CONTEXT_SAMPLES],
+            gate: SpeechGate::new(config),
+            last_amplitude_db: -100.0,
+            last_lookback_offset_ms: None,
+            last_state_change: SpeechStateChange::None,
+            last_word_break_event: None,
+            callback: None,
+        })
+    }
+
+    /// Run the model on one window and return its speech probability.
+    fn infer(&mut self, window: &[f32]) -> Result<f32, String> {
+        let mut input = Vec::with_capacity(CONTEXT_SAMPLES + WINDOW_SAMPLES);
+        input.extend_from_slice(&self.context);
+        input.extend_from_slice(window);
+        self.context
+            .copy_from_slice(&window[WINDOW_SAMPLES - CONTEXT_SAMPLES..]);
+
+        let input = Tensor::from_array(([1, input.len()], input)).map_err(ort_error)?;
+        let state = Tensor::from_array(([2, 1, 128], self.state.clone())).map_err(ort_error)?;
+        let sr = Tensor::from_array(([1], vec![SILERO_SAMPLE_RATE as i64])).map_err(ort_error)?;
+
+        let outputs = self
+            .session
+            .run(ort::inputs!["input" => input, "state" => state, "sr" => sr].map_err(ort_error)?)
+            .map_err(ort_error)?;
+        let (_, probability) = outputs["output"]
+            .try_extract_raw_tensor::<f32>()
+            .map_err(ort_error)?;
+        let (_, state) = outputs["stateN"]
+            .try_extract_raw_tensor::<f32>()
+            .map_err(ort_error)?;
+        self.state.copy_from_slice(state);
+
+        Ok(probability[0])
+    }
+
+    /// Convert a count of model samples to capture-rate samples
+    fn to_capture_samples(&self, samples: usize) -> usize {
+        (samples as u64 * self.sample_rate as u64 / SILERO_SAMPLE_RATE as u64) as usize
+    }
+
+    fn handle_event(&mut self, event: GateEvent) {
+        match event {
+            GateEvent::Started { onset_windows } => {
+                // Reach back over the onset windows, plus audio not yet analyzed
+                let lookback_ms = onset_windows * WINDOW_MS + LOOKBACK_MARGIN_MS;
+                let lookback =
+                    (lookback_ms * SILERO_SAMPLE_RATE / 1000) as usize + self.pending.len();
+                let lookback_offset_ms =
+                    (lookback as u64 * 1000 / SILERO_SAMPLE_RATE as u64) as u32;
+                self.last_lookback_offset_ms = Some(lookback_offset_ms);
+                self.last_state_change = SpeechStateChange::Started {
+                    lookback_samples: self.to_capture_samples(lookback),
+                };
+
+                if let Some(ref callback) = self.callback {
+                    callback.on_speech_started(SpeechEventPayload {
+                        duration_ms: None,
+                        lookback_offset_ms: Some(lookback_offset_ms),
+                    });
+                }
+
+                tracing::debug!(
+                    "Speech started (silero, lookback: {}ms)",
+                    lookback_offset_ms
+                );
+            }
+            GateEvent::Ended { speech_windows } => {
+                let duration_ms = (speech_windows * WINDOW_MS) as u64;
+                self.last_state_change = SpeechStateChange::Ended { duration_ms };
+
+                if let Some(ref callback) = self.callback {
+                    callback.on_speech_ended(SpeechEventPayload {
+                        duration_ms: Some(duration_ms),
+                        lookback_offset_ms: None,
+                    });
+                }
+
+                tracing::debug!("Speech ended (silero, duration: {}ms)", duration_ms);
+            }
+            GateEvent::WordBreak {
+                offset_windows,
+                gap_windows,
+            } => {
+                let offset_ms = offset_windows * WINDOW_MS;
+                let gap_duration_ms = gap_windows * WINDOW_MS;
+
+                if let Some(ref callback) = self.callback {
+                    callback.on_word_break(WordBreakPayload {
+                        offset_ms,
+                        gap_duration_ms,
+                    });
+                }
+
+                self.last_word_break_event = Some(WordBreakEvent {
+                    offset_ms,
+                    gap_duration_ms,
+                });
+            }
+        }
+    }
+}
+
+impl VoiceActivityDetector for SileroVad {
+    fn backend(&self) -> VadBackend {
+        VadBackend::Silero
+    }
+
+    fn process(&mut self, samples: &[f32]) {
+        self.last_state_change = SpeechStateChange::None;
+        self.last_word_break_event = None;
+        self.last_lookback_offset_ms = None;
+
+        if !samples.is_empty() {
+            let sum_squares: f32 = samples.iter().map(|s| s * s).sum();
+            let rms = (sum_squares / samples.len() as f32).sqrt();
+            self.last_amplitude_db = if rms > 0.0 {
+                20.0 * rms.log10()
+            } else {
+                -100.0
+            };
+        }
+
+        self.resampler.process(samples, &mut self.pending);
+
+        while self.pending.len() >= WINDOW_SAMPLES {
+            let window: Vec<f32> = self.pending.drain(..WINDOW_SAMPLES).collect();
+            let probability = match self.infer(&window) {
+                Ok(probability) => probability,
+                Err(e) => {
+                    tracing::error!("Silero VAD inference failed: {}", e);
+                    continue;
+                }
+            };
+
+            if let Some(event) = self.gate.push(probability) {
+                let is_state_change = !matches!(event, GateEvent::WordBreak { .. });
+                self.handle_event(event);
+                // Leave remaining windows for the next call so each call
+                // reports at most one state change
+                if is_state_change {
+                    break;
+                }
+            }
+        }
+    }
+
+    fn get_metrics(&self) -> SpeechMetrics {
+        SpeechMetrics {
+            amplitude_db: self.last_amplitude_db,
+            zcr: 0.0,
+            centroid_hz: 0.0,
+            is_speaking: self.gate.is_speaking,
+            is_voiced_pending: self.gate.onset_count > 0,
+            is_whisper_pending: false,
+            is_transient: false,
+            is_lookback_speech: false,
+            lookback_offset_ms: self.last_lookback_offset_ms,
+            is_word_break: self.gate.is_word_break(),
+        }
+    }
+
+    fn take_state_change(&mut self) -> SpeechStateChange {
+        std::mem::replace(&mut self.last_state_change, SpeechStateChange::None)
+    }
+
+    fn take_word_break_event(&mut self) -> Option<WordBreakEvent> {
+        self.last_word_break_event.take()
+    }
+
+    fn set_config(&mut self, config: &SpeechDetectorConfig) {
+        self.gate.set_config(config);
+    }
+
+    fn set_callback(&mut self, callback: Arc<dyn SpeechEventCallback>) {
+        self.callback = Some(callback);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resampler_chunks() {
+        let mut resampler = Resampler::new(48000);
+        let mut out = Vec::new();
+        let input: Vec<f32> = (0..480).map(|i| i as f32).collect();
+        resampler.process(&input[..100], &mut out);
+        resampler.process(&input[100..], &mut out);
+        assert_eq!(out.len(), 160);
+        assert!(out.iter().enumerate().all(|(i, &s)| s == (i * 3) as f32));
+    }
+
+    #[test]
+    fn test_speech_gate() {
+        let config = SpeechDetectorConfig {
+            voiced_onset_ms: 64,
+            hold_ms: 300,
+            ..Default::default()
+        };
+        let mut gate = SpeechGate::new(&config);
+
+        assert_eq!(gate.push(0.9), None);
+        assert_eq!(gate.push(0.1), None);
+        assert_eq!(gate.push(0.9), None);
+        assert_eq!(
+            gate.push(0.8),
+            Some(GateEvent::Started { onset_windows: 2 })
+        );
+
+        // A 96ms gap is a word break; within the hysteresis margin is speech
+        for _ in 0..3 {
+            assert_eq!(gate.push(0.1), None);
+        }
+        assert_eq!(
+            gate.push(0.4),
+            Some(GateEvent::WordBreak {
+                offset_windows: 2,
+                gap_windows: 3
+            })
+        );
+
+        for _ in 0..9 {
+            assert_eq!(gate.push(0.0), None);
+        }
+        assert_eq!(gate.push(0.0), Some(GateEvent::Ended { speech_windows: 3 }));
+    }
+}
@@ -3,7 +3,17 @@
 //! This module manages the shared state for the FlowSTT service,
 //! including transcription status and audio backend state.
 
-use flowstt_common::{KeyCode, RecordingMode, TranscribeStatus, TranscriptionMode};
+use crate::config::{
+    AudioConfig, CalendarConfig, ChapterConfig, ConfidenceConfig, CueConfig, OscConfig,
+    PostProcessConfig, RecordingProcessingConfig, RedactionConfig, RetentionConfig,
+    RollingBufferConfig, VadRecordingConfig, WebhookConfig,
+};
+use flowstt_common::{
+    AecSettings, HotkeyAction, HotkeyBinding, KeyCode, LatencyPreset, OutputMode, Profile,
+    RecordingFormat, RecordingMode, SessionHook, SpectrogramSettings, SpeechDetectorConfig,
+    TranscribeStatus, TranscriptionMode, VoiceCommand,
+};
+use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
@@ -26,8 +36,84 @@ pub struct ServiceState {
     pub transcription_mode: TranscriptionMode,
     /// Configured push-to-talk hotkey
     pub ptt_key: KeyCode,
+    /// Hotkeys for actions other than push-to-talk
+    pub hotkeys: Vec<HotkeyBinding>,
     /// Whether PTT key is currently pressed
     pub is_ptt_active: bool,
+    /// Transcription language code ("auto" for detection)
+    pub language: String,
+    /// Translate non-English transcriptions to English
+    pub translate: bool,
+    /// Where completed transcriptions are delivered
+    pub output_mode: OutputMode,
+    /// Template delivered text is formatted with; the bare text when unset
+    pub output_template: Option<String>,
+    /// Trade-off between transcription latency and accuracy
+    pub latency_preset: LatencyPreset,
+    /// Whether GPU acceleration is used for transcription
+    pub use_gpu: bool,
+    /// Index of the GPU used for transcription
+    pub gpu_device: u32,
+    /// Directories watched for new recordings to transcribe
+    pub watch_folders: Vec<PathBuf>,
+    /// Registered voice command phrases
+    pub voice_commands: Vec<VoiceCommand>,
+    /// Configured recordings directory (default when unset)
+    pub recordings_dir: Option<PathBuf>,
+    /// Configured whisper model file (default when unset)
+    pub model_path: Option<PathBuf>,
+    /// Selected catalog model (default when unset)
+    pub model_name: Option<String>,
+    /// Filename template for recordings
+    pub recording_filename_template: String,
+    /// File format recordings are saved in
+    pub recording_format: RecordingFormat,
+    /// Silence trimming and loudness normalization of segment recordings
+    pub recording_processing: RecordingProcessingConfig,
+    /// Speech detector thresholds
+    pub vad_config: SpeechDetectorConfig,
+    /// Echo cancellation tuning
+    pub aec_settings: AecSettings,
+    /// Spectrogram resolution and visualization rate
+    pub spectrogram_settings: SpectrogramSettings,
+    /// Address the HTTP API listens on, if enabled
+    pub http_address: Option<String>,
+    /// Bearer token the HTTP API requires, if set
+    pub http_token: Option<String>,
+    /// Base URL webhook receivers reach the HTTP API at, if exposed to them
+    pub http_public_url: Option<String>,
+    /// Audio capture settings (applied at startup)
+    pub audio_config: AudioConfig,
+    /// Text post-processing steps (applied at startup)
+    pub post_processing: PostProcessConfig,
+    /// Low-confidence segment thresholds
+    pub confidence: ConfidenceConfig,
+    /// Audible feedback cues
+    pub cues: CueConfig,
+    /// Open Sound Control output
+    pub osc: OscConfig,
+    /// Limits on kept recordings
+    pub retention: RetentionConfig,
+    /// Recording of captured audio while speech is detected
+    pub vad_recording: VadRecordingConfig,
+    /// Splitting of capture sessions into chapters
+    pub chapters: ChapterConfig,
+    /// Recent audio kept for retroactive capture
+    pub rolling_buffer: RollingBufferConfig,
+    /// Masking of sensitive data in transcripts
+    pub redaction: RedactionConfig,
+    /// Privacy mode: capture is stopped and refuses to start
+    pub muted: bool,
+    /// Saved settings profiles
+    pub profiles: Vec<Profile>,
+    /// Name of the profile last used
+    pub active_profile: Option<String>,
+    /// Hooks run on the transcript of every capture session
+    pub hooks: Vec<SessionHook>,
+    /// Endpoints notified of session lifecycle events
+    pub webhooks: Vec<WebhookConfig>,
+    /// Naming of sessions after calendar meetings
+    pub calendar: CalendarConfig,
 }
 
 impl ServiceState {
@@ -40,6 +126,16 @@ impl ServiceState {
     pub fn should_capture(&self) -> bool {
         self.app_ready && self.has_primary_source()
     }
+
+    /// All hotkey bindings, push-to-talk first
+    pub fn hotkey_bindings(&self) -> Vec<HotkeyBinding> {
+        std::iter::once(HotkeyBinding {
+            key: self.ptt_key,
+            action: HotkeyAction::PushToTalk,
+        })
+        .chain(self.hotkeys.iter().copied())
+        .collect()
+    }
 }
 
 /// Thread-safe wrapper for service state
@@ -0,0 +1,261 @@
+//! Speaking statistics of saved transcriptions.
+//!
+//! Speech is measured from a transcription's per-source segments when its
+//! sources were transcribed separately, and from its word timings otherwise.
+//! Pauses shorter than `PAUSE_MS` count as speech, so the gaps between the
+//! words of a sentence aren't silence; longer ones separate segments. A
+//! transcription without timings counts as one segment of speech.
+
+use std::collections::BTreeMap;
+
+use flowstt_common::{SessionStats, SourceLabel, SpeakerStats, TranscriptSession};
+
+/// Longest pause counted as part of the speech around it, in milliseconds
+const PAUSE_MS: u64 = 750;
+
+/// A timed piece of speech
+struct Span<'a> {
+    start_ms: u64,
+    end_ms: u64,
+    speaker: Option<&'a str>,
+    words: usize,
+}
+
+/// Talk time, pace and speaker shares of a saved transcription.
+pub fn compute(session: &TranscriptSession) -> SessionStats {
+    let spans = spans(session);
+    let (speech_ms, segments) = measure(spans.iter());
+    let speech_ms = match session.duration_ms {
+        0 => speech_ms,
+        duration_ms => speech_ms.min(duration_ms),
+    };
+    let words = spans.iter().map(|span| span.words).sum();
+
+    // Speakers are only reported when every span has one
+    let mut by_speaker: BTreeMap<&str, Vec<&Span>> = BTreeMap::new();
+    for span in &spans {
+        match span.speaker {
+            Some(speaker) => by_speaker.entry(speaker).or_default().push(span),
+            None => {
+                by_speaker.clear();
+                break;
+            }
+        }
+    }
+    let talk: Vec<(&str, u64, usize)> = by_speaker
+        .into_iter()
+        .map(|(speaker, spans)| {
+            let words = spans.iter().map(|span| span.words).sum();
+            (speaker, measure(spans.into_iter()).0, words)
+        })
+        .collect();
+    let total_talk_ms: u64 = talk.iter().map(|&(_, speech_ms, _)| speech_ms).sum();
+    let mut speakers: Vec<SpeakerStats> = talk
+        .into_iter()
+        .map(|(speaker, speech_ms, words)| SpeakerStats {
+            speaker: speaker.to_string(),
+            speech_ms,
+            talk_ratio: ratio(speech_ms, total_talk_ms),
+            words,
+        })
+        .collect();
+    speakers.sort_by_key(|speaker| std::cmp::Reverse(speaker.speech_ms));
+
+    SessionStats {
+        duration_ms: session.duration_ms,
+        speech_ms,
+        silence_ratio: 1.0 - ratio(speech_ms, session.duration_ms),
+        words,
+        words_per_minute: match speech_ms {
+            0 => 0.0,
+            _ => words as f32 * 60_000.0 / speech_ms as f32,
+        },
+        segments,
+        speakers,
+    }
+}
+
+/// Timed speech of a transcription, from its per-source segments, its word
+/// timings or, lacking both, the whole transcription.
+fn spans(session: &TranscriptSession) -> Vec<Span<'_>> {
+    if !session.segments.is_empty() {
+        return session
+            .segments
+            .iter()
+            .map(|segment| Span {
+                start_ms: segment.start_ms,
+                end_ms: segment.end_ms,
+                speaker: Some(source_name(segment.source)),
+                words: segment.text.split_whitespace().count(),
+            })
+            .filter(|span| span.words > 0)
+            .collect();
+    }
+
+    let speaker = session.speaker.as_deref();
+    if !session.words.is_empty() {
+        return session
+            .words
+            .iter()
+            .map(|word| Span {
+                start_ms: word.start_ms,
+                end_ms: word.end_ms,
+                speaker,
+                words: 1,
+            })
+            .collect();
+    }
+
+    let words = session.text.split_whitespace().count();
+    if words == 0 {
+        return Vec::new();
+    }
+    vec![Span {
+        start_ms: 0,
+        end_ms: session.duration_ms,
+        speaker,
+        words,
+    }]
+}
+
+/// Time covered by spans, counting short pauses between them, and the number
+/// of segments they form.
+fn measure<'a>(spans: impl Iterator<Item = &'a Span<'a>>) -> (u64, usize) {
+    let mut times: Vec<(u64, u64)> = spans
+        .map(|span| (span.start_ms, span.end_ms.max(span.start_ms)))
+        .collect();
+    times.sort_unstable();
+
+    let mut speech_ms = 0;
+    let mut segments = 0;
+    let mut current: Option<(u64, u64)> = None;
+    for (start_ms, end_ms) in times {
+        match current.as_mut() {
+            Some((_, current_end)) if start_ms < *current_end + PAUSE_MS => {
+                *current_end = (*current_end).max(end_ms);
+            }
+            _ => {
+                if let Some((start, end)) = current.replace((start_ms, end_ms)) {
+                    speech_ms += end - start;
+                    segments += 1;
+                }
+            }
+        }
+    }
+    if let Some((start, end)) = current {
+        speech_ms += end - start;
+        segments += 1;
+    }
+    (speech_ms, segments)
+}
+
+/// `part` as a share of `whole`, or 0 when `whole` is empty.
+fn ratio(part: u64, whole: u64) -> f32 {
+    match whole {
+        0 => 0.0,
+        _ => (part as f32 / whole as f32).min(1.0),
+    }
+}
+
+/// Name reported for a source's talk time.
+fn source_name(source: SourceLabel) -> &'static str {
+    match source {
+        SourceLabel::Mic => "mic",
+        SourceLabel::System => "system",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flowstt_common::{SourceSegment, WordTiming};
+
+    fn session(text: &str, duration_ms: u64) -> TranscriptSession {
+        TranscriptSession {
+            id: "20260101-120000-000".to_string(),
+            created_at: "2026-01-01T12:00:00+00:00".to_string(),
+            text: text.to_string(),
+            duration_ms,
+            ..Default::default()
+        }
+    }
+
+    fn word(word: &str, start_ms: u64, end_ms: u64) -> WordTiming {
+        WordTiming {
+            word: word.to_string(),
+            start_ms,
+            end_ms,
+            probability: 0.9,
+        }
+    }
+
+    fn segment(source: SourceLabel, text: &str, start_ms: u64, end_ms: u64) -> SourceSegment {
+        SourceSegment {
+            source,
+            text: text.to_string(),
+            start_ms,
+            end_ms,
+        }
+    }
+
+    #[test]
+    fn test_stats_from_word_timings() {
+        let mut session = session("Hello there. How are you? Fine.", 6000);
+        session.words = vec![
+            word("Hello", 0, 400),
+            word("there.", 400, 900),
+            word("How", 1500, 1700),
+            word("are", 1700, 1850),
+            word("you?", 1850, 2300),
+            word("Fine.", 5000, 5500),
+        ];
+
+        let stats = compute(&session);
+        assert_eq!(stats.speech_ms, 2800);
+        assert_eq!(stats.segments, 2);
+        assert_eq!(stats.words, 6);
+        assert!((stats.words_per_minute - 6.0 * 60.0 / 2.8).abs() < 0.01);
+        assert!((stats.silence_ratio - 3200.0 / 6000.0).abs() < 1e-6);
+        assert!(stats.speakers.is_empty());
+    }
+
+    #[test]
+    fn test_stats_per_source() {
+        let mut session = session("", 10_000);
+        session.segments = vec![
+            segment(SourceLabel::Mic, "hello there friend", 0, 3000),
+            segment(SourceLabel::System, "hi", 3500, 5000),
+            segment(SourceLabel::Mic, "bye", 8000, 9000),
+        ];
+
+        let stats = compute(&session);
+        assert_eq!(stats.speech_ms, 6000);
+        assert_eq!(stats.segments, 2);
+        assert_eq!(stats.words, 5);
+        let speakers: Vec<(&str, u64, usize)> = stats
+            .speakers
+            .iter()
+            .map(|s| (s.speaker.as_str(), s.speech_ms, s.words))
+            .collect();
+        assert_eq!(speakers, [("mic", 4000, 4), ("system", 1500, 1)]);
+        assert!((stats.speakers[0].talk_ratio - 4000.0 / 5500.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_stats_without_timings() {
+        let mut session = session("one two three", 3000);
+        session.speaker = Some("Speaker 1".to_string());
+
+        let stats = compute(&session);
+        assert_eq!(stats.speech_ms, 3000);
+        assert_eq!(stats.segments, 1);
+        assert_eq!(stats.silence_ratio, 0.0);
+        assert_eq!(stats.words_per_minute, 60.0);
+        assert_eq!(stats.speakers.len(), 1);
+        assert_eq!(stats.speakers[0].talk_ratio, 1.0);
+
+        let empty = compute(&self::session("", 3000));
+        assert_eq!((empty.speech_ms, empty.segments), (0, 0));
+        assert_eq!(empty.silence_ratio, 1.0);
+    }
+}
@@ -0,0 +1,195 @@
+//! Transcription throughput benchmark.
+//!
+//! An audio file is run through a separately loaded [`Transcriber`] a number of
+//! times, reporting the real-time factor and decoding speed so users can pick a
+//! model size that keeps up on their hardware. With GPU acceleration enabled the
+//! runs can be repeated CPU-only for comparison.
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use flowstt_common::{BenchmarkResult, BenchmarkRun};
+
+use crate::audio::{process_recorded_audio, read_wav};
+
+use super::{model, GpuSettings, Transcriber};
+
+/// Sample used when no audio file is given (11s of JFK's inaugural address)
+const SAMPLE_URL: &str = "https://github.com/ggerganov/whisper.cpp/raw/master/samples/jfk.wav";
+
+/// Sample rate of the audio passed to whisper
+const WHISPER_SAMPLE_RATE: u64 = 16000;
+
+/// Benchmark transcription of `audio_path` (or the default sample).
+///
+/// Blocks for the duration of the benchmark; call from a blocking task.
+pub fn run(
+    audio_path: Option<PathBuf>,
+    language: &str,
+    iterations: u32,
+    gpu: GpuSettings,
+    compare_cpu: bool,
+) -> Result<BenchmarkResult, String> {
+    let audio_path = match audio_path {
+        Some(path) => path,
+        None => ensure_sample()?,
+    };
+    let audio = process_recorded_audio(read_wav(&audio_path)?)?;
+    if audio.is_empty() {
+        return Err(format!("{} contains no audio", audio_path.display()));
+    }
+    let audio_duration_ms = audio.len() as u64 * 1000 / WHISPER_SAMPLE_RATE;
+
+    let mut configs = vec![gpu];
+    if gpu.enabled && compare_cpu {
+        configs.push(GpuSettings {
+            enabled: false,
+            ..gpu
+        });
+    }
+
+    let mut model_path = String::new();
+    let mut runs = Vec::with_capacity(configs.len());
+    for gpu in configs {
+        let mut transcriber = Transcriber::new();
+        transcriber.set_gpu(gpu);
+        model_path = transcriber.get_model_path().to_string_lossy().to_string();
+
+        tracing::info!(
+            "Benchmarking {} ({} runs, gpu: {})",
+            audio_path.display(),
+            iterations,
+            gpu.enabled
+        );
+        runs.push(time_runs(
+            &mut transcriber,
+            &audio,
+            language,
+            iterations,
+            gpu.enabled,
+            audio_duration_ms,
+        )?);
+    }
+
+    Ok(BenchmarkResult {
+        model_path,
+        audio_path: audio_path.to_string_lossy().to_string(),
+        audio_duration_ms,
+        iterations,
+        runs,
+    })
+}
+
+/// Load the model and time repeated transcriptions of the audio.
+fn time_runs(
+    transcriber: &mut Transcriber,
+    audio: &[f32],
+    language: &str,
+    iterations: u32,
+    gpu: bool,
+    audio_duration_ms: u64,
+) -> Result<BenchmarkRun, String> {
+    let start = Instant::now();
+    transcriber.load_model()?;
+    let load_ms = start.elapsed().as_millis() as u64;
+
+    // The first run warms up caches and GPU kernels and isn't counted
+    transcriber.transcribe(audio, language)?;
+
+    let mut times = Vec::with_capacity(iterations as usize);
+    let mut tokens = 0;
+    for _ in 0..iterations {
+        let start = Instant::now();
+        transcriber.transcribe(audio, language)?;
+        times.push(start.elapsed());
+        tokens += transcriber.last_token_count()?;
+    }
+
+    Ok(summarize(gpu, load_ms, &times, tokens, audio_duration_ms))
+}
+
+/// Reduce per-run timings to the reported statistics.
+fn summarize(
+    gpu: bool,
+    load_ms: u64,
+    times: &[Duration],
+    tokens: usize,
+    audio_duration_ms: u64,
+) -> BenchmarkRun {
+    let total: Duration = times.iter().sum();
+    let mean = total / times.len().max(1) as u32;
+    let audio_secs = audio_duration_ms as f32 / 1000.0;
+
+    BenchmarkRun {
+        gpu,
+        load_ms,
+        mean_ms: mean.as_millis() as u64,
+        min_ms: times.iter().min().map_or(0, |t| t.as_millis() as u64),
+        max_ms: times.iter().max().map_or(0, |t| t.as_millis() as u64),
+        real_time_factor: if audio_secs > 0.0 {
+            mean.as_secs_f32() / audio_secs
+        } else {
+            0.0
+        },
+        tokens_per_sec: if total.is_zero() {
+            0.0
+        } else {
+            tokens as f32 / total.as_secs_f32()
+        },
+    }
+}
+
+/// Path of the default sample, downloading it to the model cache on first use.
+fn ensure_sample() -> Result<PathBuf, String> {
+    let path = model::default_model_path()
+        .parent()
+        .unwrap_or(Path::new("."))
+        .join("samples")
+        .join("jfk.wav");
+    if path.exists() {
+        return Ok(path);
+    }
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create directory: {}", e))?;
+    }
+
+    tracing::info!("Downloading benchmark sample to: {}", path.display());
+    let response = reqwest::blocking::get(SAMPLE_URL)
+        .map_err(|e| format!("Failed to download benchmark sample: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!(
+            "Failed to download benchmark sample: HTTP {}",
+            response.status()
+        ));
+    }
+    let bytes = response
+        .bytes()
+        .map_err(|e| format!("Failed to read response: {}", e))?;
+    std::fs::write(&path, &bytes).map_err(|e| format!("Failed to write file: {}", e))?;
+
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summarize() {
+        let times = [
+            Duration::from_millis(400),
+            Duration::from_millis(600),
+            Duration::from_millis(500),
+        ];
+        let run = summarize(true, 250, &times, 90, 10_000);
+        assert!(run.gpu);
+        assert_eq!(run.load_ms, 250);
+        assert_eq!(run.mean_ms, 500);
+        assert_eq!(run.min_ms, 400);
+        assert_eq!(run.max_ms, 600);
+        assert!((run.real_time_factor - 0.05).abs() < 1e-6);
+        assert!((run.tokens_per_sec - 60.0).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,238 @@
+//! Lightweight speaker diarization.
+//!
+//! Each speech segment is reduced to a spectral embedding (the mean and spread
+//! of log band energies over its voiced frames) and clustered online against the
+//! speakers seen so far. This is much simpler than a neural speaker model: it
+//! separates voices with clearly different timbre, such as two people on a
+//! call, but it can merge similar voices or split one speaker across very
+//! different recording conditions.
+
+use rustfft::{num_complex::Complex, Fft, FftPlanner};
+use std::sync::Arc;
+
+/// Sample rate of the audio passed to the diarizer (same as whisper)
+const SAMPLE_RATE: f32 = 16000.0;
+
+/// Analysis frame size (32ms at 16kHz)
+const FRAME_SIZE: usize = 512;
+
+/// Hop between analysis frames (10ms at 16kHz)
+const HOP_SIZE: usize = 160;
+
+/// Number of log-spaced frequency bands in the embedding
+const NUM_BANDS: usize = 20;
+
+/// Lowest band edge in Hz
+const MIN_BAND_FREQ: f32 = 80.0;
+
+/// Highest band edge in Hz
+const MAX_BAND_FREQ: f32 = 7600.0;
+
+/// Frames quieter than this RMS are ignored
+const VOICED_FRAME_RMS: f32 = 0.005;
+
+/// Minimum number of voiced frames (~0.3s) needed for a usable embedding
+const MIN_VOICED_FRAMES: usize = 30;
+
+/// Cosine similarity above which a segment is assigned to an existing speaker
+const SAME_SPEAKER_SIMILARITY: f32 = 0.8;
+
+/// Maximum number of distinct speakers; further segments join the closest one
+const MAX_SPEAKERS: usize = 8;
+
+/// A speaker cluster.
+struct Speaker {
+    /// Normalized mean embedding of the segments assigned so far
+    centroid: Vec<f32>,
+    /// Number of segments assigned
+    count: usize,
+}
+
+/// Assigns `speaker_N` labels to speech segments.
+pub struct Diarizer {
+    fft: Arc<dyn Fft<f32>>,
+    window: Vec<f32>,
+    speakers: Vec<Speaker>,
+}
+
+impl Diarizer {
+    /// Create a diarizer with no known speakers.
+    pub fn new() -> Self {
+        let fft = FftPlanner::new().plan_fft_forward(FRAME_SIZE);
+        let window = (0..FRAME_SIZE)
+            .map(|i| {
+                0.5 * (1.0
+                    - (2.0 * std::f32::consts::PI * i as f32 / (FRAME_SIZE - 1) as f32).cos())
+            })
+            .collect();
+
+        Self {
+            fft,
+            window,
+            speakers: Vec::new(),
+        }
+    }
+
+    /// Assign a speaker label to a segment of mono 16kHz audio.
+    ///
+    /// Returns `None` if the segment has too little voiced audio to tell.
+    pub fn assign(&mut self, audio: &[f32]) -> Option<String> {
+        let embedding = self.embed(audio)?;
+
+        let best = self
+            .speakers
+            .iter()
+            .enumerate()
+            .map(|(i, s)| (i, dot(&s.centroid, &embedding)))
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+
+        let index = match best {
+            Some((i, similarity))
+                if similarity >= SAME_SPEAKER_SIMILARITY || self.speakers.len() >= MAX_SPEAKERS =>
+            {
+                let speaker = &mut self.speakers[i];
+                let weight = speaker.count as f32;
+                for (c, e) in speaker.centroid.iter_mut().zip(&embedding) {
+                    *c = (*c * weight + e) / (weight + 1.0);
+                }
+                normalize(&mut speaker.centroid);
+                speaker.count += 1;
+                i
+            }
+            _ => {
+                self.speakers.push(Speaker {
+                    centroid: embedding,
+                    count: 1,
+                });
+                self.speakers.len() - 1
+            }
+        };
+
+        Some(format!("speaker_{}", index + 1))
+    }
+
+    /// Compute a unit-length spectral embedding of the voiced frames in `audio`.
+    fn embed(&self, audio: &[f32]) -> Option<Vec<f32>> {
+        let bin_hz = SAMPLE_RATE / FRAME_SIZE as f32;
+        let band_edges: Vec<usize> = (0..=NUM_BANDS)
+            .map(|b| {
+                let freq = MIN_BAND_FREQ
+                    * (MAX_BAND_FREQ / MIN_BAND_FREQ).powf(b as f32 / NUM_BANDS as f32);
+                ((freq / bin_hz).round() as usize).min(FRAME_SIZE / 2)
+            })
+            .collect();
+
+        let mut sum = [0.0f32; NUM_BANDS];
+        let mut sum_sq = [0.0f32; NUM_BANDS];
+        let mut voiced_frames = 0;
+        let mut buffer = vec![Complex::new(0.0f32, 0.0); FRAME_SIZE];
+
+        for frame in audio.windows(FRAME_SIZE).step_by(HOP_SIZE) {
+            let rms = (frame.iter().map(|s| s * s).sum::<f32>() / FRAME_SIZE as f32).sqrt();
+            if rms < VOICED_FRAME_RMS {
+                continue;
+            }
+
+            for ((out, &sample), &w) in buffer.iter_mut().zip(frame).zip(&self.window) {
+                *out = Complex::new(sample * w, 0.0);
+            }
+            self.fft.process(&mut buffer);
+
+            // Log energy per band, with the frame's mean removed so loudness
+            // doesn't affect the embedding
+            let mut energies = [0.0f32; NUM_BANDS];
+            for (band, energy) in energies.iter_mut().enumerate() {
+                let (lo, hi) = (
+                    band_edges[band],
+                    band_edges[band + 1].max(band_edges[band] + 1),
+                );
+                let power: f32 = buffer[lo..hi].iter().map(|c| c.norm_sqr()).sum();
+                *energy = (power / (hi - lo) as f32 + 1e-10).ln();
+            }
+            let mean = energies.iter().sum::<f32>() / NUM_BANDS as f32;
+
+            for (band, energy) in energies.iter().enumerate() {
+                let e = energy - mean;
+                sum[band] += e;
+                sum_sq[band] += e * e;
+            }
+            voiced_frames += 1;
+        }
+
+        if voiced_frames < MIN_VOICED_FRAMES {
+            return None;
+        }
+
+        let n = voiced_frames as f32;
+        let mut embedding: Vec<f32> = sum.iter().map(|s| s / n).collect();
+        let spreads: Vec<f32> = sum_sq
+            .iter()
+            .zip(&embedding)
+            .map(|(sq, mean)| (sq / n - mean * mean).max(0.0).sqrt())
+            .collect();
+        embedding.extend(spreads);
+
+        normalize(&mut embedding);
+        Some(embedding)
+    }
+}
+
+impl Default for Diarizer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Scale a vector to unit length (no-op for a zero vector).
+fn normalize(v: &mut [f32]) {
+    let norm = dot(v, v).sqrt();
+    if norm > 0.0 {
+        v.iter_mut().for_each(|x| *x /= norm);
+    }
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// One second of a harmonic tone with the given fundamental and
+    /// per-harmonic amplitude decay.
+    fn voice(fundamental: f32, decay: f32) -> Vec<f32> {
+        (0..16000)
+            .map(|i| {
+                let t = i as f32 / SAMPLE_RATE;
+                (1..=20)
+                    .map(|h| {
+                        let freq = fundamental * h as f32;
+                        if freq >= SAMPLE_RATE / 2.0 {
+                            return 0.0;
+                        }
+                        0.1 * decay.powi(h - 1) * (2.0 * std::f32::consts::PI * freq * t).sin()
+                    })
+                    .sum()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_diarizer_separates_distinct_voices() {
+        let mut diarizer = Diarizer::new();
+        let low = voice(110.0, 0.6);
+        let high = voice(900.0, 0.95);
+
+        assert_eq!(diarizer.assign(&low).as_deref(), Some("speaker_1"));
+        assert_eq!(diarizer.assign(&high).as_deref(), Some("speaker_2"));
+        assert_eq!(diarizer.assign(&low).as_deref(), Some("speaker_1"));
+    }
+
+    #[test]
+    fn test_diarizer_ignores_silence() {
+        let mut diarizer = Diarizer::new();
+        assert_eq!(diarizer.assign(&vec![0.0; 16000]), None);
+        assert!(diarizer.speakers.is_empty());
+    }
+}
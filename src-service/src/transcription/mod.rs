@@ -5,10 +5,18 @@
 //! # Components
 //!
 //! - [`whisper_ffi`]: Low-level FFI bindings to whisper.cpp
+//! - [`pool`]: Loaded models shared between transcribers
 //! - [`transcriber`]: High-level transcription API
 //! - [`queue`]: Async transcription queue with worker thread
 //! - [`transcribe_state`]: State management for continuous transcription mode
+//! - [`diarization`]: Speaker labels for transcribed segments
+//! - [`benchmark`]: Transcription throughput measurement
+//! - [`model`]: Model file location and validation
 
+pub mod benchmark;
+pub mod diarization;
+pub mod model;
+pub mod pool;
 pub mod queue;
 pub mod transcribe_state;
 pub mod transcriber;
@@ -17,4 +25,7 @@ pub mod whisper_ffi;
 // Re-export main types
 pub use queue::{TranscriptionCallback, TranscriptionQueue};
 pub use transcribe_state::TranscribeState;
-pub use transcriber::{download_model, Transcriber};
+pub use transcriber::{
+    download_model, set_whisper_stub, GpuSettings, Transcriber, Transcript, NO_SPEECH_TEXT,
+    WHISPER_STUB_ENV,
+};
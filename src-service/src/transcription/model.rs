@@ -0,0 +1,340 @@
+//! Whisper model location and metadata.
+//!
+//! Models are downloaded from the whisper.cpp repository on Hugging Face into
+//! the user cache directory. The catalog lists each size in full precision and
+//! in the quantized variants that are published, so users can trade accuracy
+//! for speed and memory with `model.name`. An existing model file can be used
+//! instead by setting `model.path`. Model files are validated by reading their
+//! ggml header, which also tells the model size and quantization shown by
+//! `model status`.
+
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use flowstt_common::{ModelInfo, ModelVariant};
+
+/// Where downloadable models are fetched from
+const MODEL_BASE_URL: &str = "https://huggingface.co/ggerganov/whisper.cpp/resolve/main";
+
+/// Model used when none is selected
+pub const DEFAULT_MODEL_NAME: &str = "base.en";
+
+/// Downloadable models with their approximate file size in MB.
+///
+/// Names follow the upstream files (`ggml-<name>.bin`): size, `.en` for
+/// English-only models and the quantization suffix, if any.
+const CATALOG: &[(&str, u32)] = &[
+    ("tiny", 75),
+    ("tiny-q5_1", 31),
+    ("tiny-q8_0", 42),
+    ("tiny.en", 75),
+    ("tiny.en-q5_1", 31),
+    ("tiny.en-q8_0", 42),
+    ("base", 142),
+    ("base-q5_1", 57),
+    ("base-q8_0", 78),
+    ("base.en", 142),
+    ("base.en-q5_1", 57),
+    ("base.en-q8_0", 78),
+    ("small", 466),
+    ("small-q5_1", 181),
+    ("small-q8_0", 252),
+    ("small.en", 466),
+    ("small.en-q5_1", 181),
+    ("small.en-q8_0", 252),
+    ("medium", 1530),
+    ("medium-q5_0", 514),
+    ("medium-q8_0", 785),
+    ("medium.en", 1530),
+    ("medium.en-q5_0", 514),
+    ("medium.en-q8_0", 785),
+    ("large-v3", 3100),
+    ("large-v3-q5_0", 1080),
+    ("large-v3-turbo", 1620),
+    ("large-v3-turbo-q5_0", 547),
+    ("large-v3-turbo-q8_0", 834),
+];
+
+/// Magic number at the start of ggml whisper model files ("ggml" as a little-endian u32)
+const GGML_MAGIC: u32 = 0x6767_6d6c;
+
+/// Magic bytes of GGUF files, which whisper.cpp can't load
+const GGUF_MAGIC: &[u8; 4] = b"GGUF";
+
+/// Header size: magic followed by eleven i32 hyperparameters
+const HEADER_LEN: usize = 4 + 11 * 4;
+
+/// Vocabulary size of the English-only models (multilingual ones are larger)
+const ENGLISH_VOCAB_SIZE: i32 = 51864;
+
+/// whisper.cpp stores the quantization version multiplied by this in the ftype field
+const QUANTIZATION_VERSION_FACTOR: i32 = 1000;
+
+/// User-configured model file (`None` for the default location)
+static MODEL_PATH: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+/// Selected catalog model (`None` for [`DEFAULT_MODEL_NAME`])
+static MODEL_NAME: Mutex<Option<String>> = Mutex::new(None);
+
+/// Select the catalog model to use, or `None` for the default.
+pub fn set_model_name(name: Option<String>) {
+    *MODEL_NAME.lock().unwrap() = name;
+}
+
+/// Name of the selected catalog model.
+pub fn model_name() -> String {
+    MODEL_NAME
+        .lock()
+        .unwrap()
+        .clone()
+        .unwrap_or_else(|| DEFAULT_MODEL_NAME.to_string())
+}
+
+/// Whether a model name is in the catalog.
+pub fn is_known_model(name: &str) -> bool {
+    CATALOG.iter().any(|(n, _)| *n == name)
+}
+
+/// Download URL of a catalog model.
+pub fn model_url(name: &str) -> String {
+    format!("{}/ggml-{}.bin", MODEL_BASE_URL, name)
+}
+
+/// Where a catalog model is stored once downloaded.
+pub fn variant_path(name: &str) -> PathBuf {
+    let cache_dir = directories::BaseDirs::new()
+        .map(|d| d.cache_dir().to_path_buf())
+        .unwrap_or_else(|| PathBuf::from("."));
+    cache_dir.join("whisper").join(format!("ggml-{}.bin", name))
+}
+
+/// The model catalog with download state and memory estimates.
+pub fn list_variants() -> Vec<ModelVariant> {
+    let active = (!is_custom_path()).then(model_name);
+    CATALOG
+        .iter()
+        .map(|&(name, size_mb)| ModelVariant {
+            name: name.to_string(),
+            quantization: variant_quantization(name).to_string(),
+            multilingual: !name.contains(".en"),
+            size_mb,
+            memory_mb: estimated_memory_mb(name, size_mb),
+            downloaded: variant_path(name).exists(),
+            active: active.as_deref() == Some(name),
+        })
+        .collect()
+}
+
+/// Quantization of a catalog model from its name suffix.
+fn variant_quantization(name: &str) -> &str {
+    name.rsplit_once('-')
+        .map(|(_, suffix)| suffix)
+        .filter(|suffix| suffix.starts_with('q'))
+        .unwrap_or("f16")
+}
+
+/// Rough memory needed to run a model: the weights plus whisper.cpp's
+/// compute buffers and KV cache, which depend on the model size only.
+fn estimated_memory_mb(name: &str, size_mb: u32) -> u32 {
+    let overhead = if name.starts_with("large-v3-turbo") {
+        700
+    } else if name.starts_with("large") {
+        1000
+    } else if name.starts_with("medium") {
+        600
+    } else if name.starts_with("small") {
+        400
+    } else if name.starts_with("base") {
+        250
+    } else {
+        200
+    };
+    size_mb + overhead
+}
+
+/// Use a model file at a custom location, or `None` for the default.
+pub fn set_model_path(path: Option<PathBuf>) {
+    *MODEL_PATH.lock().unwrap() = path;
+}
+
+/// Whether a custom model file is configured.
+pub fn is_custom_path() -> bool {
+    MODEL_PATH.lock().unwrap().is_some()
+}
+
+/// Path of the model file to load.
+pub fn model_path() -> PathBuf {
+    MODEL_PATH
+        .lock()
+        .unwrap()
+        .clone()
+        .unwrap_or_else(default_model_path)
+}
+
+/// Name of the model in use as recorded with transcripts: the catalog name,
+/// or the file name of a custom model.
+pub fn model_label() -> String {
+    match MODEL_PATH.lock().unwrap().as_ref() {
+        Some(path) => path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| path.to_string_lossy().to_string()),
+        None => model_name(),
+    }
+}
+
+/// Where the selected catalog model is downloaded when no custom path is configured.
+pub fn default_model_path() -> PathBuf {
+    variant_path(&model_name())
+}
+
+/// Check that a file is a whisper model whisper.cpp can load and describe it.
+pub fn inspect(path: &Path) -> Result<ModelInfo, String> {
+    let mut file =
+        File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let size_bytes = file
+        .metadata()
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?
+        .len();
+
+    let mut header = [0u8; HEADER_LEN];
+    file.read_exact(&mut header)
+        .map_err(|_| format!("{} is too small to be a whisper model", path.display()))?;
+
+    parse_header(&header)
+        .map(|info| ModelInfo { size_bytes, ..info })
+        .map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// Parse the ggml header of a whisper model (`size_bytes` is left at 0).
+fn parse_header(header: &[u8; HEADER_LEN]) -> Result<ModelInfo, String> {
+    if &header[..4] == GGUF_MAGIC {
+        return Err(
+            "GGUF files aren't supported by whisper.cpp, use a ggml model (ggml-*.bin)".to_string(),
+        );
+    }
+    let magic = u32::from_le_bytes([header[0], header[1], header[2], header[3]]);
+    if magic != GGML_MAGIC {
+        return Err("not a ggml whisper model".to_string());
+    }
+
+    let hparam = |i: usize| {
+        let at = 4 + i * 4;
+        i32::from_le_bytes([header[at], header[at + 1], header[at + 2], header[at + 3]])
+    };
+    let n_vocab = hparam(0);
+    let n_audio_layer = hparam(4);
+    let n_text_layer = hparam(8);
+    let n_mels = hparam(9);
+    let ftype = hparam(10) % QUANTIZATION_VERSION_FACTOR;
+
+    if n_vocab < ENGLISH_VOCAB_SIZE || !matches!(n_mels, 80 | 128) {
+        return Err("unrecognized whisper model hyperparameters".to_string());
+    }
+
+    Ok(ModelInfo {
+        size_bytes: 0,
+        model_type: model_type(n_audio_layer, n_text_layer, n_mels),
+        quantization: quantization_name(ftype),
+        multilingual: n_vocab > ENGLISH_VOCAB_SIZE,
+    })
+}
+
+/// Name of the model size from its layer counts.
+fn model_type(n_audio_layer: i32, n_text_layer: i32, n_mels: i32) -> String {
+    let name = match n_audio_layer {
+        4 => "tiny",
+        6 => "base",
+        12 => "small",
+        24 => "medium",
+        32 if n_mels == 128 && n_text_layer == 4 => "large-v3-turbo",
+        32 if n_mels == 128 => "large-v3",
+        32 => "large",
+        n => return format!("unknown ({} layers)", n),
+    };
+    name.to_string()
+}
+
+/// Name of a ggml file type.
+fn quantization_name(ftype: i32) -> String {
+    let name = match ftype {
+        0 => "f32",
+        1 => "f16",
+        2 => "q4_0",
+        3 => "q4_1",
+        7 => "q8_0",
+        8 => "q5_0",
+        9 => "q5_1",
+        10 => "q2_k",
+        11 => "q3_k",
+        12 => "q4_k",
+        13 => "q5_k",
+        14 => "q6_k",
+        n => return format!("unknown ({})", n),
+    };
+    name.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(n_vocab: i32, n_audio_layer: i32, n_mels: i32, ftype: i32) -> [u8; HEADER_LEN] {
+        let hparams = [
+            n_vocab,
+            1500,
+            512,
+            8,
+            n_audio_layer,
+            448,
+            512,
+            8,
+            n_audio_layer,
+            n_mels,
+            ftype,
+        ];
+        let mut header = [0u8; HEADER_LEN];
+        header[..4].copy_from_slice(&GGML_MAGIC.to_le_bytes());
+        for (i, value) in hparams.iter().enumerate() {
+            header[4 + i * 4..8 + i * 4].copy_from_slice(&value.to_le_bytes());
+        }
+        header
+    }
+
+    #[test]
+    fn test_parse_header() {
+        let info = parse_header(&header(51864, 6, 80, 1)).unwrap();
+        assert_eq!(info.model_type, "base");
+        assert_eq!(info.quantization, "f16");
+        assert!(!info.multilingual);
+
+        // Quantized models carry the quantization version in the ftype
+        let info = parse_header(&header(51866, 32, 128, 2009)).unwrap();
+        assert_eq!(info.model_type, "large-v3");
+        assert_eq!(info.quantization, "q5_1");
+        assert!(info.multilingual);
+    }
+
+    #[test]
+    fn test_parse_header_rejects_other_files() {
+        let mut gguf = header(51864, 6, 80, 1);
+        gguf[..4].copy_from_slice(GGUF_MAGIC);
+        assert!(parse_header(&gguf).unwrap_err().contains("GGUF"));
+
+        assert!(parse_header(&[0u8; HEADER_LEN]).is_err());
+        assert!(parse_header(&header(51864, 6, 40, 1)).is_err());
+    }
+
+    #[test]
+    fn test_catalog_variants() {
+        assert!(is_known_model(DEFAULT_MODEL_NAME));
+        assert!(!is_known_model("base.en-q4_0"));
+        assert_eq!(variant_quantization("small.en-q5_1"), "q5_1");
+        assert_eq!(variant_quantization("large-v3-turbo"), "f16");
+        assert_eq!(variant_quantization("large-v3-turbo-q8_0"), "q8_0");
+        assert_eq!(estimated_memory_mb("base.en-q5_1", 57), 307);
+        assert!(model_url("tiny.en").ends_with("/ggml-tiny.en.bin"));
+    }
+}
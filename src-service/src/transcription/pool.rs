@@ -0,0 +1,271 @@
+//! Shared whisper contexts.
+//!
+//! A loaded model holds hundreds of megabytes to gigabytes of memory, so
+//! transcribers don't own their contexts: they lease one from this pool and
+//! return it when done, and it stays loaded for the next lease. A model is only
+//! loaded when it is first leased, and [`unload`] frees it until it is needed
+//! again.
+//!
+//! A context keeps the results of its last run, so it is leased to one
+//! transcriber at a time. Asking for a model that is leased out waits for its
+//! return rather than loading a second copy.
+
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::{Condvar, Mutex};
+
+use super::transcriber::GpuSettings;
+use super::whisper_ffi::Context;
+
+/// What a context was loaded with; leases only reuse a context with the same key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContextKey {
+    pub model_path: PathBuf,
+    pub gpu: GpuSettings,
+}
+
+static POOL: Mutex<Pool<Context>> = Mutex::new(Pool::new());
+
+/// Signalled whenever a lease ends
+static RETURNED: Condvar = Condvar::new();
+
+/// A context leased from the pool; returned to it when dropped.
+pub struct PooledContext {
+    key: ContextKey,
+    generation: u64,
+    ctx: Option<Context>,
+}
+
+impl Deref for PooledContext {
+    type Target = Context;
+
+    fn deref(&self) -> &Context {
+        self.ctx.as_ref().expect("context is held until drop")
+    }
+}
+
+impl Drop for PooledContext {
+    fn drop(&mut self) {
+        let stale = POOL
+            .lock()
+            .unwrap()
+            .checkin(&self.key, self.ctx.take(), self.generation);
+        RETURNED.notify_all();
+        // Freeing a model can take a while, so do it outside the lock
+        drop(stale);
+    }
+}
+
+/// Lease a context for `key`, loading the model if it isn't loaded yet.
+///
+/// Blocks while another transcriber holds the same model, and while it loads.
+pub fn acquire(key: &ContextKey) -> Result<PooledContext, String> {
+    let mut pool = POOL.lock().unwrap();
+    let generation = loop {
+        match pool.checkout(key) {
+            Checkout::Ready(ctx) => {
+                return Ok(PooledContext {
+                    key: key.clone(),
+                    generation: pool.generation,
+                    ctx: Some(ctx),
+                })
+            }
+            Checkout::Leased => pool = RETURNED.wait(pool).unwrap(),
+            Checkout::Load => break pool.generation,
+        }
+    };
+    let evicted = pool.take_idle();
+    drop(pool);
+    drop(evicted);
+
+    tracing::info!(
+        "Loading whisper model from: {} (gpu: {}, device: {})",
+        key.model_path.display(),
+        key.gpu.enabled,
+        key.gpu.device
+    );
+    match Context::new(&key.model_path, key.gpu.enabled, key.gpu.device) {
+        Ok(ctx) => {
+            tracing::info!("Whisper model loaded successfully");
+            Ok(PooledContext {
+                key: key.clone(),
+                generation,
+                ctx: Some(ctx),
+            })
+        }
+        Err(e) => {
+            POOL.lock().unwrap().checkin(key, None, generation);
+            RETURNED.notify_all();
+            Err(e)
+        }
+    }
+}
+
+/// Free the loaded models. Models leased out are freed when they are returned.
+///
+/// Returns the number of models freed now.
+pub fn unload() -> usize {
+    let (freed, leased) = {
+        let mut pool = POOL.lock().unwrap();
+        let leased = pool.leased.len();
+        (pool.unload(), leased)
+    };
+    let count = freed.len();
+    drop(freed);
+    tracing::info!(
+        "Unloaded {} whisper model(s){}",
+        count,
+        if leased > 0 {
+            format!(", {} more when their transcription finishes", leased)
+        } else {
+            String::new()
+        }
+    );
+    count
+}
+
+/// Whether the model at `model_path` is loaded, idle or leased out.
+pub fn is_loaded(model_path: &Path) -> bool {
+    let pool = POOL.lock().unwrap();
+    pool.idle
+        .iter()
+        .map(|(key, _)| key)
+        .chain(&pool.leased)
+        .any(|key| key.model_path == model_path)
+}
+
+/// Result of asking the pool for a context.
+#[derive(Debug, PartialEq)]
+enum Checkout<T> {
+    /// An idle context, now leased
+    Ready(T),
+    /// The context is leased out; wait for it to come back
+    Leased,
+    /// Nothing is loaded for the key; the caller loads it and holds the lease
+    Load,
+}
+
+/// Bookkeeping of loaded contexts, generic so it can be tested without models.
+struct Pool<T> {
+    idle: Vec<(ContextKey, T)>,
+    leased: Vec<ContextKey>,
+    /// Incremented by `unload`; leases from earlier generations are freed on return
+    generation: u64,
+}
+
+impl<T> Pool<T> {
+    const fn new() -> Self {
+        Self {
+            idle: Vec::new(),
+            leased: Vec::new(),
+            generation: 0,
+        }
+    }
+
+    fn checkout(&mut self, key: &ContextKey) -> Checkout<T> {
+        if let Some(index) = self.idle.iter().position(|(k, _)| k == key) {
+            let (key, item) = self.idle.swap_remove(index);
+            self.leased.push(key);
+            return Checkout::Ready(item);
+        }
+        if self.leased.contains(key) {
+            return Checkout::Leased;
+        }
+        self.leased.push(key.clone());
+        Checkout::Load
+    }
+
+    /// Idle contexts, removed so a newly loaded model doesn't sit beside models
+    /// nobody is using.
+    fn take_idle(&mut self) -> Vec<(ContextKey, T)> {
+        std::mem::take(&mut self.idle)
+    }
+
+    /// End a lease, keeping `item` for the next one. Returns it instead when
+    /// the pool was unloaded during the lease, for the caller to free.
+    fn checkin(&mut self, key: &ContextKey, item: Option<T>, generation: u64) -> Option<T> {
+        if let Some(index) = self.leased.iter().position(|k| k == key) {
+            self.leased.swap_remove(index);
+        }
+        let item = item?;
+        if generation != self.generation {
+            return Some(item);
+        }
+        self.idle.push((key.clone(), item));
+        None
+    }
+
+    fn unload(&mut self) -> Vec<(ContextKey, T)> {
+        self.generation += 1;
+        self.take_idle()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transcription::whisper_ffi::WhisperLibrary;
+    use crate::transcription::Transcriber;
+
+    fn key(model: &str) -> ContextKey {
+        ContextKey {
+            model_path: PathBuf::from(model),
+            gpu: GpuSettings::default(),
+        }
+    }
+
+    #[test]
+    fn test_leases_reuse_loaded_context() {
+        let mut pool = Pool::new();
+        let base = key("base.bin");
+        assert_eq!(pool.checkout(&base), Checkout::Load);
+        assert_eq!(pool.checkout(&base), Checkout::Leased);
+        assert_eq!(pool.checkin(&base, Some(1), pool.generation), None);
+        assert_eq!(pool.checkout(&base), Checkout::Ready(1));
+        pool.checkin(&base, Some(1), pool.generation);
+
+        // A different model or GPU setting needs its own context
+        let cpu = ContextKey {
+            gpu: GpuSettings {
+                enabled: false,
+                device: 0,
+            },
+            ..base.clone()
+        };
+        assert_eq!(pool.checkout(&cpu), Checkout::Load);
+        assert_eq!(pool.take_idle().len(), 1);
+
+        // A failed load frees the lease for the next attempt
+        pool.checkin(&cpu, None, pool.generation);
+        assert_eq!(pool.checkout(&cpu), Checkout::Load);
+    }
+
+    #[test]
+    fn test_unload_frees_returned_leases() {
+        let mut pool = Pool::new();
+        let (base, small) = (key("base.bin"), key("small.bin"));
+        pool.checkout(&base);
+        pool.checkin(&base, Some(1), pool.generation);
+        pool.checkout(&small);
+        let generation = pool.generation;
+
+        assert_eq!(pool.unload().len(), 1);
+        assert_eq!(pool.checkin(&small, Some(2), generation), Some(2));
+        assert!(pool.idle.is_empty() && pool.leased.is_empty());
+        assert_eq!(pool.checkout(&base), Checkout::Load);
+    }
+
+    fn assert_send<T: Send>() {}
+    fn assert_sync<T: Sync>() {}
+
+    #[test]
+    fn test_thread_safety() {
+        // Contexts and transcribers move to worker threads, but a context is
+        // never shared: it is not Sync, and the pool leases it to one holder
+        assert_send::<Context>();
+        assert_send::<PooledContext>();
+        assert_send::<Transcriber>();
+        assert_send::<WhisperLibrary>();
+        assert_sync::<WhisperLibrary>();
+    }
+}
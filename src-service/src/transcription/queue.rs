@@ -8,9 +8,16 @@ use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread;
+use std::time::Instant;
 
-use crate::audio::{process_recorded_audio, RawRecordedAudio};
+use crate::audio::{process_recorded_audio, process_split_sources, RawRecordedAudio};
+use crate::config::{ConfidenceConfig, RedactionChannel};
 
+use flowstt_common::{FlowSttError, LatencyPreset, LatencySettings, OutputMode, DEFAULT_LANGUAGE};
+
+use super::diarization::Diarizer;
+use super::model;
+use super::transcriber::{GpuSettings, TokenHandler, Transcript, NO_SPEECH_TEXT};
 use super::Transcriber;
 
 /// Maximum queue size for transcription segments
@@ -26,6 +33,13 @@ pub struct QueuedSegment {
     pub channels: u16,
     /// Path to saved WAV file (if saved)
     pub wav_path: Option<PathBuf>,
+    /// Whether this is an interim snapshot of a segment still being recorded
+    pub is_partial: bool,
+    /// Audio file from a watched folder; the transcript is written next to it
+    /// instead of being emitted as a live transcription
+    pub source_file: Option<PathBuf>,
+    /// Output mode overriding the configured one (set by hotkey actions)
+    pub output: Option<OutputMode>,
 }
 
 /// Callback trait for transcription events.
@@ -36,10 +50,17 @@ pub trait TranscriptionCallback: Send + Sync + 'static {
     fn on_transcription_started(&self);
 
     /// Called when transcription completes successfully.
-    fn on_transcription_complete(&self, text: String);
+    fn on_transcription_complete(&self, transcript: Transcript);
+
+    /// Called when an interim transcription of an in-progress segment completes.
+    fn on_transcription_partial(&self, transcript: Transcript);
+
+    /// Called with the text decoded so far while a completed segment is
+    /// still being transcribed.
+    fn on_transcription_token(&self, text: String);
 
     /// Called when transcription fails.
-    fn on_transcription_error(&self, error: String);
+    fn on_transcription_error(&self, error: FlowSttError);
 
     /// Called when transcription finishes (GPU no longer active).
     fn on_transcription_finished(&self);
@@ -58,6 +79,32 @@ pub struct TranscriptionQueue {
     queue_count: Arc<AtomicUsize>,
     /// Callback for transcription events
     callback: Arc<Mutex<Option<Arc<dyn TranscriptionCallback>>>>,
+    /// Language code passed to whisper ("auto" for detection)
+    language: Arc<Mutex<String>>,
+    /// Add an English translation to completed non-English transcripts
+    translate: Arc<AtomicBool>,
+    /// Transcribe the left (mic) and right (system) channels separately
+    split_sources: Arc<AtomicBool>,
+    /// Speaker clustering state, present while diarization is enabled
+    diarizer: Arc<Mutex<Option<Diarizer>>>,
+    /// GPU settings for the whisper context
+    gpu: Arc<Mutex<GpuSettings>>,
+    /// Thresholds for flagging low-confidence segments
+    confidence: Arc<Mutex<ConfidenceConfig>>,
+    /// Decoding settings from the latency preset
+    latency: Arc<Mutex<LatencySettings>>,
+    /// Set to abort the segment being transcribed; cleared when the next one starts
+    cancelled: Arc<AtomicBool>,
+    /// Set while the worker is transcribing a segment it took off the queue
+    busy: Arc<AtomicBool>,
+}
+
+/// Audio prepared for whisper.
+pub enum PreparedAudio {
+    /// All sources mixed to mono
+    Mixed(Vec<f32>),
+    /// One mono stream per source
+    Split { mic: Vec<f32>, system: Vec<f32> },
 }
 
 impl TranscriptionQueue {
@@ -68,6 +115,15 @@ impl TranscriptionQueue {
             worker_active: Arc::new(AtomicBool::new(false)),
             queue_count: Arc::new(AtomicUsize::new(0)),
             callback: Arc::new(Mutex::new(None)),
+            language: Arc::new(Mutex::new(DEFAULT_LANGUAGE.to_string())),
+            translate: Arc::new(AtomicBool::new(false)),
+            split_sources: Arc::new(AtomicBool::new(false)),
+            diarizer: Arc::new(Mutex::new(None)),
+            gpu: Arc::new(Mutex::new(GpuSettings::default())),
+            confidence: Arc::new(Mutex::new(ConfidenceConfig::default())),
+            latency: Arc::new(Mutex::new(LatencyPreset::default().settings())),
+            cancelled: Arc::new(AtomicBool::new(false)),
+            busy: Arc::new(AtomicBool::new(false)),
         }
     }
 
@@ -81,6 +137,48 @@ impl TranscriptionQueue {
         *self.callback.lock().unwrap() = None;
     }
 
+    /// Set the transcription language for subsequent segments.
+    pub fn set_language(&self, language: String) {
+        *self.language.lock().unwrap() = language;
+    }
+
+    /// Set whether completed transcripts are also translated to English.
+    pub fn set_translate(&self, enabled: bool) {
+        self.translate.store(enabled, Ordering::SeqCst);
+    }
+
+    /// Set whether split-channel segments are transcribed per source.
+    ///
+    /// Only meaningful when capture uses `RecordingMode::SplitChannels` with two
+    /// sources, so the mic is on the left channel and system audio on the right.
+    pub fn set_split_sources(&self, enabled: bool) {
+        self.split_sources.store(enabled, Ordering::SeqCst);
+    }
+
+    /// Enable or disable speaker diarization.
+    ///
+    /// Enabling starts with no known speakers, so labels restart at `speaker_1`.
+    pub fn set_diarization(&self, enabled: bool) {
+        *self.diarizer.lock().unwrap() = enabled.then(Diarizer::new);
+    }
+
+    /// Set the GPU settings.
+    ///
+    /// The worker re-initializes the whisper context before the next segment.
+    pub fn set_gpu(&self, gpu: GpuSettings) {
+        *self.gpu.lock().unwrap() = gpu;
+    }
+
+    /// Set the thresholds for flagging (or suppressing) low-confidence segments.
+    pub fn set_confidence(&self, confidence: ConfidenceConfig) {
+        *self.confidence.lock().unwrap() = confidence;
+    }
+
+    /// Set the beam size and temperature fallback for subsequent segments.
+    pub fn set_latency(&self, latency: LatencySettings) {
+        *self.latency.lock().unwrap() = latency;
+    }
+
     /// Get the current queue depth.
     pub fn queue_depth(&self) -> usize {
         self.queue_count.load(Ordering::SeqCst)
@@ -91,12 +189,25 @@ impl TranscriptionQueue {
         self.worker_active.load(Ordering::SeqCst)
     }
 
+    /// Check if every queued segment has been transcribed and delivered.
+    pub fn is_idle(&self) -> bool {
+        let queue = self.queue.lock().unwrap();
+        queue.is_empty() && !self.busy.load(Ordering::SeqCst)
+    }
+
     /// Enqueue a segment for transcription.
     /// Returns false if queue is full (segment was not added).
     pub fn enqueue(&self, segment: QueuedSegment) -> bool {
         let mut queue = self.queue.lock().unwrap();
+
+        // Pending partials are superseded by any newer segment
+        queue.retain(|s| !s.is_partial);
+
         if queue.len() >= MAX_QUEUE_SIZE {
             // Queue is full, don't add
+            crate::metrics::add_dropped_frames(
+                segment.samples.len() / segment.channels.max(1) as usize,
+            );
             return false;
         }
         queue.push_back(segment);
@@ -111,8 +222,21 @@ impl TranscriptionQueue {
         true
     }
 
+    /// Enqueue an interim snapshot of an in-progress segment.
+    /// Partials are only accepted when the queue is idle so they never delay
+    /// final results. Returns false if the snapshot was not added.
+    pub fn enqueue_partial(&self, segment: QueuedSegment) -> bool {
+        let mut queue = self.queue.lock().unwrap();
+        if !queue.is_empty() {
+            return false;
+        }
+        queue.push_back(segment);
+        self.queue_count.store(queue.len(), Ordering::SeqCst);
+        true
+    }
+
     /// Start the transcription worker thread.
-    pub fn start_worker(&self, model_path: PathBuf) {
+    pub fn start_worker(&self) {
         if self.worker_active.load(Ordering::SeqCst) {
             return; // Already running
         }
@@ -123,12 +247,23 @@ impl TranscriptionQueue {
         let worker_active = Arc::clone(&self.worker_active);
         let queue_count = Arc::clone(&self.queue_count);
         let callback = Arc::clone(&self.callback);
+        let language = Arc::clone(&self.language);
+        let translate = Arc::clone(&self.translate);
+        let split_sources = Arc::clone(&self.split_sources);
+        let diarizer = Arc::clone(&self.diarizer);
+        let gpu = Arc::clone(&self.gpu);
+        let confidence = Arc::clone(&self.confidence);
+        let latency = Arc::clone(&self.latency);
+        let cancelled = Arc::clone(&self.cancelled);
+        let busy = Arc::clone(&self.busy);
 
         thread::spawn(move || {
             let mut transcriber = Transcriber::new();
+            transcriber.set_gpu(*gpu.lock().unwrap());
+            transcriber.set_abort_flag(Some(Arc::clone(&cancelled)));
 
             // Try to load model at start
-            if model_path.exists() {
+            if transcriber.is_model_available() {
                 if let Err(e) = transcriber.load_model() {
                     tracing::error!("[TranscriptionQueue] Failed to load model: {}", e);
                 }
@@ -148,6 +283,24 @@ impl TranscriptionQueue {
                     // Continue processing remaining items
                 }
 
+                // Re-initialize the whisper context when the GPU settings or model change
+                let gpu_settings = *gpu.lock().unwrap();
+                let gpu_changed = transcriber.set_gpu(gpu_settings);
+                let model_changed = transcriber.set_model_path(model::model_path());
+                if (gpu_changed || model_changed) && transcriber.is_model_available() {
+                    tracing::info!(
+                        "[TranscriptionQueue] Reloading model {} ({:?})",
+                        transcriber.get_model_path().display(),
+                        gpu_settings
+                    );
+                    if let Err(e) = transcriber.load_model() {
+                        tracing::error!("[TranscriptionQueue] Failed to reload model: {}", e);
+                    }
+                }
+
+                transcriber.set_confidence(*confidence.lock().unwrap());
+                transcriber.set_latency(*latency.lock().unwrap());
+
                 // Try to get a segment from queue
                 let segment = {
                     let mut q = queue.lock().unwrap();
@@ -155,6 +308,12 @@ impl TranscriptionQueue {
                     let depth = q.len();
                     queue_count.store(depth, Ordering::SeqCst);
 
+                    // A cancel before this point only applies to earlier segments
+                    if seg.is_some() {
+                        cancelled.store(false, Ordering::SeqCst);
+                        busy.store(true, Ordering::SeqCst);
+                    }
+
                     // Notify callback of queue update
                     if seg.is_some() {
                         if let Some(ref cb) = *callback.lock().unwrap() {
@@ -167,6 +326,11 @@ impl TranscriptionQueue {
 
                 match segment {
                     Some(seg) => {
+                        let is_partial = seg.is_partial;
+                        let wav_path = seg.wav_path;
+                        let source_file = seg.source_file;
+                        let output = seg.output;
+
                         // Process the segment
                         let raw_audio = RawRecordedAudio {
                             samples: seg.samples,
@@ -175,20 +339,116 @@ impl TranscriptionQueue {
                         };
 
                         // Convert to format suitable for Whisper
-                        match process_recorded_audio(raw_audio) {
-                            Ok(processed) => {
+                        let prepared = if split_sources.load(Ordering::SeqCst)
+                            && raw_audio.channels == 2
+                            && source_file.is_none()
+                        {
+                            process_split_sources(raw_audio)
+                                .map(|(mic, system)| PreparedAudio::Split { mic, system })
+                        } else {
+                            process_recorded_audio(raw_audio).map(PreparedAudio::Mixed)
+                        };
+
+                        match prepared {
+                            Ok(prepared) => {
                                 // Notify that transcription is starting
                                 if let Some(ref cb) = *callback.lock().unwrap() {
                                     cb.on_transcription_started();
                                 }
 
+                                // Stream tokens of live speech as they decode
+                                let token_handler = callback
+                                    .lock()
+                                    .unwrap()
+                                    .clone()
+                                    .filter(|_| !is_partial && source_file.is_none())
+                                    .map(|cb| -> TokenHandler {
+                                        Box::new(move |text: &str| {
+                                            cb.on_transcription_token(text.to_string())
+                                        })
+                                    });
+                                transcriber.set_token_handler(token_handler);
+
                                 // Transcribe
-                                match transcriber.transcribe(&processed) {
-                                    Ok(text) => {
+                                let started = Instant::now();
+                                let language = language.lock().unwrap().clone();
+                                let result = match &prepared {
+                                    PreparedAudio::Mixed(audio) => transcriber
+                                        .transcribe(audio, &language)
+                                        .map(|mut transcript| {
+                                            // Label the speaker of completed speech
+                                            if !is_partial
+                                                && source_file.is_none()
+                                                && transcript.text != NO_SPEECH_TEXT
+                                            {
+                                                if let Some(d) = diarizer.lock().unwrap().as_mut() {
+                                                    transcript.speaker = d.assign(audio);
+                                                }
+                                            }
+                                            transcript
+                                        }),
+                                    PreparedAudio::Split { mic, system } => {
+                                        transcriber.transcribe_sources(mic, system, &language)
+                                    }
+                                };
+                                transcriber.set_token_handler(None);
+                                let result = result.map(|mut transcript| {
+                                    transcript.output = output;
+                                    if !is_partial && translate.load(Ordering::SeqCst) {
+                                        add_translation(
+                                            &mut transcriber,
+                                            &prepared,
+                                            &mut transcript,
+                                        );
+                                    }
+                                    crate::postprocess::apply(&mut transcript);
+                                    transcript
+                                });
+                                match result {
+                                    _ if cancelled.load(Ordering::SeqCst) => {
+                                        tracing::info!(
+                                            "[TranscriptionQueue] Discarded cancelled segment"
+                                        );
+                                    }
+                                    Ok(transcript)
+                                        if is_partial && transcript.text == NO_SPEECH_TEXT =>
+                                    {
+                                        // Nothing useful to show yet
+                                    }
+                                    Ok(mut transcript) if source_file.is_some() => {
+                                        let path = source_file.as_deref().unwrap();
+                                        crate::redact::transcript(
+                                            RedactionChannel::History,
+                                            &mut transcript,
+                                        );
+                                        crate::watch::write_sidecars(path, &transcript);
+                                    }
+                                    Ok(mut transcript) => {
+                                        if !is_partial {
+                                            crate::metrics::record_transcription(started.elapsed());
+                                        }
+                                        transcript.audio_path = wav_path;
                                         if let Some(ref cb) = *callback.lock().unwrap() {
-                                            cb.on_transcription_complete(text);
+                                            if is_partial {
+                                                cb.on_transcription_partial(transcript);
+                                            } else {
+                                                cb.on_transcription_complete(transcript);
+                                            }
                                         }
                                     }
+                                    Err(e) if source_file.is_some() => {
+                                        tracing::warn!(
+                                            "[TranscriptionQueue] Failed to transcribe {:?}: {}",
+                                            source_file,
+                                            e
+                                        );
+                                    }
+                                    Err(e) if is_partial => {
+                                        tracing::debug!(
+                                            "[TranscriptionQueue] Partial transcription failed: {}",
+                                            e
+                                        );
+                                    }
                                     Err(e) => {
                                         if let Some(ref cb) = *callback.lock().unwrap() {
                                             cb.on_transcription_error(e);
@@ -203,12 +463,16 @@ impl TranscriptionQueue {
                             }
                             Err(e) => {
                                 if let Some(ref cb) = *callback.lock().unwrap() {
-                                    cb.on_transcription_error(e);
+                                    cb.on_transcription_error(FlowSttError::Transcription(e));
                                 }
                             }
                         }
+                        busy.store(false, Ordering::SeqCst);
                     }
                     None => {
+                        // Let the benchmark use the model, or unloading free it,
+                        // while there's nothing to transcribe
+                        transcriber.release();
                         // No segment available, sleep briefly
                         thread::sleep(std::time::Duration::from_millis(50));
                     }
@@ -224,6 +488,24 @@ impl TranscriptionQueue {
         self.worker_active.store(false, Ordering::SeqCst);
     }
 
+    /// Discard pending segments and abort the one being transcribed.
+    ///
+    /// Returns the number of queued segments that were discarded.
+    pub fn cancel(&self) -> usize {
+        let mut queue = self.queue.lock().unwrap();
+        let discarded = queue.len();
+        queue.clear();
+        self.queue_count.store(0, Ordering::SeqCst);
+        // Set under the queue lock so the worker can't start a new segment in between
+        self.cancelled.store(true, Ordering::SeqCst);
+        drop(queue);
+
+        if let Some(ref cb) = *self.callback.lock().unwrap() {
+            cb.on_queue_update(0);
+        }
+        discarded
+    }
+
     /// Clear the queue (discard pending segments).
     pub fn clear(&self) {
         let mut queue = self.queue.lock().unwrap();
@@ -237,6 +519,44 @@ impl TranscriptionQueue {
     }
 }
 
+/// Translate a completed transcript to English when it's in another language.
+///
+/// Split sources are mixed back together so the translation covers both.
+pub fn add_translation(
+    transcriber: &mut Transcriber,
+    prepared: &PreparedAudio,
+    transcript: &mut Transcript,
+) {
+    if transcript.text == NO_SPEECH_TEXT {
+        return;
+    }
+    let Some(language) = transcript.language.clone() else {
+        return;
+    };
+    if language == DEFAULT_LANGUAGE {
+        return;
+    }
+
+    let mixed;
+    let audio = match prepared {
+        PreparedAudio::Mixed(audio) => audio.as_slice(),
+        PreparedAudio::Split { mic, system } => {
+            mixed = mic
+                .iter()
+                .zip(system)
+                .map(|(m, s)| (m + s) * 0.5)
+                .collect::<Vec<f32>>();
+            mixed.as_slice()
+        }
+    };
+
+    match transcriber.translate(audio, &language) {
+        Ok(text) if !text.is_empty() => transcript.translation = Some(text),
+        Ok(_) => {}
+        Err(e) => tracing::warn!("[TranscriptionQueue] Translation failed: {}", e),
+    }
+}
+
 impl Default for TranscriptionQueue {
     fn default() -> Self {
         Self::new()
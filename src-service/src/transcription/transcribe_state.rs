@@ -4,10 +4,14 @@
 //! - `SegmentRingBuffer`: A ring buffer for continuous audio capture
 //! - `TranscribeState`: State management for transcribe mode
 
-use std::path::PathBuf;
 use std::sync::Arc;
 
-use crate::audio::{generate_recording_filename, save_to_wav};
+use flowstt_common::{LatencyPreset, OutputMode};
+
+use crate::audio::{
+    generate_recording_filename, preprocess_recording, recording_format, recordings_dir,
+};
+use crate::encoder;
 
 use super::queue::{QueuedSegment, TranscriptionQueue};
 
@@ -18,8 +22,9 @@ const RING_BUFFER_CAPACITY: usize = 48000 * 30 * 2;
 /// Overflow threshold: 90% of buffer capacity
 const OVERFLOW_THRESHOLD_PERCENT: usize = 90;
 
-/// Maximum segment duration before seeking word break
-const MAX_SEGMENT_DURATION_MS: u64 = 4000;
+/// Maximum segment duration in dictation mode. Longer segments give whisper
+/// whole sentences to punctuate instead of fragments.
+const DICTATION_MAX_SEGMENT_DURATION_MS: u64 = 12000;
 
 /// Grace period after duration threshold before forcing segment submission (500ms)
 const WORD_BREAK_GRACE_MS: u64 = 750;
@@ -36,6 +41,9 @@ const MIN_AUDIO_RMS_THRESHOLD: f32 = 0.01;
 /// The extraction point will be (gap_start - margin) rather than gap_midpoint
 const WORD_BREAK_PRE_MARGIN_MS: u64 = 30;
 
+/// Interval between interim (partial) transcriptions of an in-progress segment
+const PARTIAL_RESULT_INTERVAL_MS: u64 = 1000;
+
 // ============================================================================
 // Segment Ring Buffer
 // ============================================================================
@@ -209,10 +217,18 @@ pub struct TranscribeState {
     word_break_seek_start_samples: u64,
     /// Number of lookback samples at the start of the current segment
     lookback_sample_count: usize,
+    /// Segment sample count at the time the last partial snapshot was submitted
+    last_partial_sample_count: u64,
     /// Callback for state events
     callback: Option<Arc<dyn TranscribeStateCallback>>,
     /// PTT mode - disables automatic segmentation
     ptt_mode: bool,
+    /// Dictation mode - allows longer segments before seeking a word break
+    dictation_mode: bool,
+    /// Segment duration before seeking a word break outside dictation mode
+    max_segment_ms: u64,
+    /// Output mode for segments submitted from now on, overriding the configured one
+    output_override: Option<OutputMode>,
 }
 
 impl TranscribeState {
@@ -230,8 +246,12 @@ impl TranscribeState {
             seeking_word_break: false,
             word_break_seek_start_samples: 0,
             lookback_sample_count: 0,
+            last_partial_sample_count: 0,
             callback: None,
             ptt_mode: false,
+            dictation_mode: false,
+            max_segment_ms: LatencyPreset::default().settings().max_segment_ms,
+            output_override: None,
         }
     }
 
@@ -245,6 +265,36 @@ impl TranscribeState {
         }
     }
 
+    /// Enable or disable dictation mode.
+    /// In dictation mode, segments run up to a full sentence before a word
+    /// break is sought, so sentence boundaries come from whisper's punctuation
+    /// rather than from the segment length.
+    pub fn set_dictation_mode(&mut self, enabled: bool) {
+        self.dictation_mode = enabled;
+        tracing::debug!("[TranscribeState] Dictation mode: {}", enabled);
+    }
+
+    /// Set the segment duration after which a word break is sought, from the
+    /// latency preset. Dictation mode keeps its longer segments.
+    pub fn set_max_segment_duration_ms(&mut self, ms: u64) {
+        self.max_segment_ms = ms;
+    }
+
+    /// Deliver segments submitted from now on with `mode` instead of the
+    /// configured output mode (None restores it).
+    pub fn set_output_override(&mut self, mode: Option<OutputMode>) {
+        self.output_override = mode;
+    }
+
+    /// Segment duration after which a word break is sought
+    fn max_segment_duration_ms(&self) -> u64 {
+        if self.dictation_mode {
+            DICTATION_MAX_SEGMENT_DURATION_MS.max(self.max_segment_ms)
+        } else {
+            self.max_segment_ms
+        }
+    }
+
     /// Set the callback for state events.
     pub fn set_callback(&mut self, callback: Arc<dyn TranscribeStateCallback>) {
         self.callback = Some(callback);
@@ -266,6 +316,7 @@ impl TranscribeState {
         self.seeking_word_break = false;
         self.word_break_seek_start_samples = 0;
         self.lookback_sample_count = 0;
+        self.last_partial_sample_count = 0;
     }
 
     /// Activate transcribe mode
@@ -277,6 +328,7 @@ impl TranscribeState {
         self.seeking_word_break = false;
         self.word_break_seek_start_samples = 0;
         self.lookback_sample_count = 0;
+        self.last_partial_sample_count = 0;
     }
 
     /// Deactivate transcribe mode
@@ -299,6 +351,7 @@ impl TranscribeState {
             self.ring_buffer.write(samples);
             if self.in_speech {
                 self.segment_sample_count += samples.len() as u64;
+                self.maybe_submit_partial();
             }
             return None;
         }
@@ -315,6 +368,7 @@ impl TranscribeState {
             // Update segment start to current write position
             self.segment_start_idx = self.ring_buffer.write_position();
             self.segment_sample_count = 0;
+            self.last_partial_sample_count = 0;
             self.seeking_word_break = false;
             self.lookback_sample_count = 0; // No lookback for continuation segments
 
@@ -338,7 +392,7 @@ impl TranscribeState {
 
             // Check if we've exceeded max duration and should start seeking word break
             let duration_ms = self.samples_to_ms(self.segment_sample_count);
-            if !self.seeking_word_break && duration_ms >= MAX_SEGMENT_DURATION_MS {
+            if !self.seeking_word_break && duration_ms >= self.max_segment_duration_ms() {
                 self.seeking_word_break = true;
                 self.word_break_seek_start_samples = self.segment_sample_count;
                 tracing::debug!(
@@ -361,6 +415,8 @@ impl TranscribeState {
                     }
                 }
             }
+
+            self.maybe_submit_partial();
         }
 
         // If we extracted a segment due to overflow, queue it
@@ -389,6 +445,7 @@ impl TranscribeState {
             .index_from_lookback(lookback_stereo_samples);
         // Start duration tracking from zero (lookback samples are pre-speech)
         self.segment_sample_count = 0;
+        self.last_partial_sample_count = 0;
         self.seeking_word_break = false;
         // Remember lookback count (in stereo samples) for proper word break extraction
         self.lookback_sample_count = lookback_stereo_samples;
@@ -411,6 +468,7 @@ impl TranscribeState {
 
         self.in_speech = false;
         self.segment_sample_count = 0;
+        self.last_partial_sample_count = 0;
         self.seeking_word_break = false;
         self.lookback_sample_count = 0;
 
@@ -532,6 +590,7 @@ impl TranscribeState {
         self.segment_sample_count = self
             .segment_sample_count
             .saturating_sub(extraction_point_samples);
+        self.last_partial_sample_count = 0;
         self.seeking_word_break = false;
 
         Some(segment)
@@ -571,12 +630,42 @@ impl TranscribeState {
         // Update state for next segment - remain in speech
         self.segment_start_idx = self.ring_buffer.write_position();
         self.segment_sample_count = 0;
+        self.last_partial_sample_count = 0;
         self.lookback_sample_count = 0;
         self.seeking_word_break = false;
 
         Some(segment)
     }
 
+    /// Submit an interim snapshot of the in-progress segment for partial
+    /// transcription once enough new audio has accumulated since the last one.
+    fn maybe_submit_partial(&mut self) {
+        let new_samples = self.segment_sample_count - self.last_partial_sample_count;
+        if new_samples < self.ms_to_samples(PARTIAL_RESULT_INTERVAL_MS) {
+            return;
+        }
+        self.last_partial_sample_count = self.segment_sample_count;
+
+        let snapshot = self.ring_buffer.extract_segment(self.segment_start_idx);
+        if !self.is_segment_valid_for_transcription(&snapshot) {
+            return;
+        }
+
+        let queued = QueuedSegment {
+            samples: snapshot,
+            sample_rate: self.sample_rate,
+            channels: self.channels,
+            wav_path: None,
+            is_partial: true,
+            source_file: None,
+            output: None,
+        };
+
+        if !self.transcription_queue.enqueue_partial(queued) {
+            tracing::debug!("[TranscribeState] Transcription queue busy, partial skipped");
+        }
+    }
+
     /// Check if a segment has sufficient audio content for transcription
     /// Returns false if segment is too short or too quiet (likely to produce [BLANK_AUDIO])
     fn is_segment_valid_for_transcription(&self, samples: &[f32]) -> bool {
@@ -623,12 +712,11 @@ impl TranscribeState {
         if !self.is_segment_valid_for_transcription(&samples) {
             return;
         }
+        let samples = preprocess_recording(samples, self.sample_rate, self.channels);
 
-        // Save to WAV file
+        // Save the recording
         let filename = generate_recording_filename();
-        let recordings_dir = directories::BaseDirs::new()
-            .map(|d| d.home_dir().join("Documents").join("Recordings"))
-            .unwrap_or_else(|| PathBuf::from(".").join("Recordings"));
+        let recordings_dir = recordings_dir();
 
         // Create directory if it doesn't exist
         if let Err(e) = std::fs::create_dir_all(&recordings_dir) {
@@ -639,7 +727,13 @@ impl TranscribeState {
         }
 
         let output_path = recordings_dir.join(&filename);
-        let wav_path = match save_to_wav(&samples, self.sample_rate, self.channels, &output_path) {
+        let wav_path = match encoder::save(
+            &samples,
+            self.sample_rate,
+            self.channels,
+            &output_path,
+            recording_format(),
+        ) {
             Ok(()) => {
                 tracing::info!("[TranscribeState] Saved segment to: {:?}", output_path);
                 if let Some(ref cb) = self.callback {
@@ -648,7 +742,7 @@ impl TranscribeState {
                 Some(output_path)
             }
             Err(e) => {
-                tracing::error!("[TranscribeState] Failed to save WAV: {}", e);
+                tracing::error!("[TranscribeState] Failed to save recording: {}", e);
                 None
             }
         };
@@ -659,6 +753,9 @@ impl TranscribeState {
             sample_rate: self.sample_rate,
             channels: self.channels,
             wav_path,
+            is_partial: false,
+            source_file: None,
+            output: self.output_override,
         };
 
         // Enqueue for transcription
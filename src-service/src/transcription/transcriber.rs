@@ -9,12 +9,33 @@
 //! - Whisper parameter tuning to reduce hallucinations at the source
 //! - Post-processing to detect and remove repetition loops
 
+use std::ffi::CString;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
-use super::whisper_ffi::{self, Context, WhisperSamplingStrategy};
+use flowstt_common::{
+    FlowSttError, LatencyPreset, LatencySettings, OutputMode, SegmentConfidence, SourceLabel,
+    SourceSegment, WordTiming, AUTO_DETECT_LANGUAGE, DEFAULT_LANGUAGE,
+};
 
-const MODEL_URL: &str =
-    "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-base.en.bin";
+use crate::config::ConfidenceConfig;
+
+use super::model;
+use super::pool::{self, ContextKey, PooledContext};
+use super::whisper_ffi::{self, Context, WhisperFullParams, WhisperSamplingStrategy};
+
+/// Placeholder text returned when a segment contains no recognizable speech
+pub const NO_SPEECH_TEXT: &str = "(No speech detected)";
+
+/// Error returned when a transcription is aborted through the abort flag
+pub const CANCELLED_ERROR: &str = "Transcription cancelled";
+
+/// Sample rate of the audio passed to whisper
+const WHISPER_SAMPLE_RATE: u64 = 16000;
+
+/// RMS level below which a source is treated as silent and not transcribed
+const SILENT_SOURCE_RMS: f32 = 1e-4;
 
 /// Minimum number of repetitions to consider text as a hallucination loop
 const MIN_REPETITIONS_FOR_LOOP: usize = 3;
@@ -22,21 +43,112 @@ const MIN_REPETITIONS_FOR_LOOP: usize = 3;
 /// Minimum phrase length (in chars) to check for repetition
 const MIN_PHRASE_LENGTH: usize = 10;
 
+/// Environment variable that replaces whisper with [`set_whisper_stub`]'s stub
+pub const WHISPER_STUB_ENV: &str = "FLOWSTT_WHISPER_STUB";
+
+/// Whether transcription returns stub text instead of running whisper
+static WHISPER_STUB: AtomicBool = AtomicBool::new(false);
+
+/// Replace whisper with a stub that needs no model or library and returns
+/// "Stub transcript of N.NNs of audio" for every segment. For tests and for
+/// running the pipeline on machines without a model.
+pub fn set_whisper_stub(enabled: bool) {
+    WHISPER_STUB.store(enabled, Ordering::Relaxed);
+}
+
+fn whisper_stubbed() -> bool {
+    WHISPER_STUB.load(Ordering::Relaxed)
+}
+
+/// Text produced by a single transcription run.
+#[derive(Debug, Clone)]
+pub struct Transcript {
+    /// Transcribed text
+    pub text: String,
+    /// Language code used for decoding (the detected language in auto-detect mode)
+    pub language: Option<String>,
+    /// Word-level timestamps relative to the start of the audio
+    pub words: Vec<WordTiming>,
+    /// Per-source text in time order (only for [`Transcriber::transcribe_sources`])
+    pub segments: Vec<SourceSegment>,
+    /// Per-segment decoder confidence in time order
+    pub confidence: Vec<SegmentConfidence>,
+    /// English translation (filled in by the transcription queue when translating)
+    pub translation: Option<String>,
+    /// Speaker label (filled in by the transcription queue when diarizing)
+    pub speaker: Option<String>,
+    /// Whether post-processing changed the text
+    pub modified: bool,
+    /// Duration of the transcribed audio in milliseconds
+    pub duration_ms: u64,
+    /// Saved WAV file of the segment (filled in by the transcription queue)
+    pub audio_path: Option<PathBuf>,
+    /// Output mode overriding the configured one (filled in by the transcription queue)
+    pub output: Option<OutputMode>,
+}
+
+/// A decoded text token with its timing, used to assemble word timings.
+struct TokenPiece {
+    /// Raw token bytes (may be a partial UTF-8 sequence)
+    bytes: Vec<u8>,
+    /// Start time in centiseconds
+    t0: i64,
+    /// End time in centiseconds
+    t1: i64,
+    /// Token probability
+    p: f32,
+}
+
+/// GPU settings used when creating the whisper context.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GpuSettings {
+    /// Use GPU acceleration if the library has a GPU backend
+    pub enabled: bool,
+    /// Index of the GPU to use
+    pub device: u32,
+}
+
+impl Default for GpuSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            device: 0,
+        }
+    }
+}
+
 /// Wrapper around whisper.cpp for transcription.
 pub struct Transcriber {
-    ctx: Option<Context>,
+    /// Context leased from the pool, held from `load_model` until `release`
+    ctx: Option<PooledContext>,
     model_path: PathBuf,
     library_initialized: bool,
+    gpu: GpuSettings,
+    confidence: ConfidenceConfig,
+    /// Beam size and temperature fallback for decoding
+    latency: LatencySettings,
+    /// Receives the text decoded so far while `transcribe` runs
+    token_handler: Option<TokenHandler>,
+    /// Aborts the whisper run in progress when set
+    abort: Option<Arc<AtomicBool>>,
 }
 
+/// Callback for text decoded while a transcription is still running
+pub type TokenHandler = Box<dyn FnMut(&str) + Send>;
+
 impl Transcriber {
     /// Create a new transcriber with the default model path.
     pub fn new() -> Self {
-        let model_path = get_default_model_path();
+        let model_path = model::model_path();
         Self {
             ctx: None,
             model_path,
             library_initialized: false,
+            gpu: GpuSettings::default(),
+            confidence: ConfidenceConfig::default(),
+            latency: LatencyPreset::default().settings(),
+            token_handler: None,
+            abort: None,
         }
     }
 
@@ -47,7 +159,91 @@ impl Transcriber {
 
     /// Check if the model file exists.
     pub fn is_model_available(&self) -> bool {
-        self.model_path.exists()
+        whisper_stubbed() || self.model_path.exists()
+    }
+
+    /// Change the GPU settings.
+    ///
+    /// Returns true if they changed, in which case the loaded model is dropped
+    /// and must be loaded again to take effect.
+    pub fn set_gpu(&mut self, gpu: GpuSettings) -> bool {
+        if gpu == self.gpu {
+            return false;
+        }
+        self.gpu = gpu;
+        self.ctx = None;
+        true
+    }
+
+    /// Change the model file.
+    ///
+    /// Returns true if it changed, in which case the loaded model is dropped
+    /// and must be loaded again to take effect.
+    pub fn set_model_path(&mut self, model_path: PathBuf) -> bool {
+        if model_path == self.model_path {
+            return false;
+        }
+        self.model_path = model_path;
+        self.ctx = None;
+        true
+    }
+
+    /// Change the thresholds for flagging low-confidence segments.
+    pub fn set_confidence(&mut self, confidence: ConfidenceConfig) {
+        self.confidence = confidence;
+    }
+
+    /// Change the beam size and temperature fallback used for decoding.
+    pub fn set_latency(&mut self, latency: LatencySettings) {
+        self.latency = latency;
+    }
+
+    /// Stream the text decoded so far to `handler` during `transcribe`, or stop
+    /// streaming with `None`.
+    pub fn set_token_handler(&mut self, handler: Option<TokenHandler>) {
+        self.token_handler = handler;
+    }
+
+    /// Abort whisper runs whenever `flag` is set; they fail with [`CANCELLED_ERROR`].
+    pub fn set_abort_flag(&mut self, flag: Option<Arc<AtomicBool>>) {
+        self.abort = flag;
+    }
+
+    /// Params for a full decode with hallucination mitigation, using beam
+    /// search when the latency settings ask for it.
+    fn decoding_params(&self) -> Result<WhisperFullParams, String> {
+        let strategy = if self.latency.beam_size > 1 {
+            WhisperSamplingStrategy::BeamSearch
+        } else {
+            WhisperSamplingStrategy::Greedy
+        };
+        let mut params = whisper_ffi::full_default_params(strategy)?;
+        params.configure_with_hallucination_mitigation();
+        if self.latency.beam_size > 1 {
+            params.beam_search_beam_size = self.latency.beam_size as i32;
+        }
+        params.temperature_inc = self.latency.temperature_inc;
+        Ok(params)
+    }
+
+    /// Make whisper poll the abort flag, if there is one.
+    fn apply_abort_flag(&self, params: &mut WhisperFullParams) {
+        if let Some(flag) = &self.abort {
+            params.abort_when(flag);
+        }
+    }
+
+    /// Report a failed whisper run as cancelled when the abort flag caused it.
+    fn cancelled_or(&self, error: String) -> String {
+        if self
+            .abort
+            .as_ref()
+            .is_some_and(|f| f.load(Ordering::SeqCst))
+        {
+            CANCELLED_ERROR.to_string()
+        } else {
+            error
+        }
     }
 
     /// Ensure the whisper library is loaded.
@@ -59,57 +255,257 @@ impl Transcriber {
         Ok(())
     }
 
-    /// Load the whisper model. This is called automatically by transcribe() if needed.
-    pub fn load_model(&mut self) -> Result<(), String> {
-        if self.ctx.is_some() {
+    /// Lease the whisper model from the pool, loading it if needed. This is
+    /// called automatically by transcribe() if needed.
+    pub fn load_model(&mut self) -> Result<(), FlowSttError> {
+        if self.ctx.is_some() || whisper_stubbed() {
             return Ok(());
         }
 
-        self.ensure_library()?;
+        self.ensure_library().map_err(FlowSttError::Transcription)?;
 
         if !self.model_path.exists() {
-            return Err(format!(
-                "Whisper model not found at: {}\n\n\
-                Please download a model file:\n\
-                1. Visit: https://huggingface.co/ggerganov/whisper.cpp/tree/main\n\
-                2. Download 'ggml-base.en.bin' (or another model)\n\
-                3. Place it at: {}",
-                self.model_path.display(),
-                self.model_path.display()
-            ));
-        }
-
-        tracing::info!("Loading whisper model from: {}", self.model_path.display());
-        let ctx = Context::new(&self.model_path)?;
+            return Err(FlowSttError::ModelMissing(self.model_path.clone()));
+        }
+
+        let ctx = pool::acquire(&ContextKey {
+            model_path: self.model_path.clone(),
+            gpu: self.gpu,
+        })
+        .map_err(FlowSttError::Transcription)?;
         self.ctx = Some(ctx);
-        tracing::info!("Whisper model loaded successfully");
         Ok(())
     }
 
+    /// Return the model to the pool so other transcribers can use it. It stays
+    /// loaded, and the next transcription leases it again.
+    pub fn release(&mut self) {
+        self.ctx = None;
+    }
+
     /// Transcribe audio samples (mono, 16kHz).
     ///
     /// The audio should already be converted to mono 16kHz format.
+    /// `language` is a whisper language code such as "en" or "de", or "auto"
+    /// to detect the spoken language; the language actually used is reported
+    /// in the returned [`Transcript`].
     /// The output is post-processed to remove hallucination loops (repeated phrases).
-    pub fn transcribe(&mut self, audio_data: &[f32]) -> Result<String, String> {
+    pub fn transcribe(
+        &mut self,
+        audio_data: &[f32],
+        language: &str,
+    ) -> Result<Transcript, FlowSttError> {
+        if whisper_stubbed() {
+            return Ok(Self::stub_transcript(audio_data.len()));
+        }
         self.load_model()?;
+        self.decode(audio_data, language)
+            .map_err(FlowSttError::Transcription)
+    }
 
+    /// Transcribe with the model loaded.
+    fn decode(&mut self, audio_data: &[f32], language: &str) -> Result<Transcript, String> {
         let ctx = self.ctx.as_ref().unwrap();
 
-        // Get default params with greedy strategy
-        let mut params = whisper_ffi::full_default_params(WhisperSamplingStrategy::Greedy)?;
+        // English-only models can neither detect nor decode other languages
+        let language = if language != DEFAULT_LANGUAGE && !ctx.is_multilingual()? {
+            tracing::warn!(
+                "Model is English-only, ignoring language '{}' (use a multilingual model)",
+                language
+            );
+            DEFAULT_LANGUAGE
+        } else {
+            language
+        };
+        let c_language =
+            CString::new(language).map_err(|e| format!("Invalid language code: {}", e))?;
 
-        // Apply hallucination mitigation settings
-        params.configure_with_hallucination_mitigation();
+        // Hallucination mitigation plus the latency preset's decoding settings
+        let mut params = self.decoding_params()?;
+
+        // "auto" makes whisper detect the language before decoding
+        // (detect_language would stop after detection without transcribing)
+        params.language = c_language.as_ptr();
+        params.detect_language = false;
+
+        // Per-token timing for word timestamps
+        params.token_timestamps = true;
 
         // Run transcription
-        ctx.full(&params, audio_data)?;
+        self.apply_abort_flag(&mut params);
+        let result = match self.token_handler.as_mut() {
+            Some(handler) => ctx.full_streaming(&params, audio_data, handler.as_mut()),
+            None => ctx.full(&params, audio_data),
+        };
+        result.map_err(|e| self.cancelled_or(e))?;
+
+        let detected_language = if language == AUTO_DETECT_LANGUAGE {
+            ctx.full_lang().ok()
+        } else {
+            Some(language.to_string())
+        };
 
+        let duration_ms = audio_data.len() as u64 * 1000 / WHISPER_SAMPLE_RATE;
         let num_segments = ctx.full_n_segments()?;
 
         if num_segments == 0 {
-            return Ok("(No speech detected)".to_string());
+            return Ok(Transcript {
+                text: NO_SPEECH_TEXT.to_string(),
+                language: detected_language,
+                words: Vec::new(),
+                segments: Vec::new(),
+                confidence: Vec::new(),
+                translation: None,
+                modified: false,
+                speaker: None,
+                duration_ms,
+                audio_path: None,
+                output: None,
+            });
+        }
+
+        let mut words = match Self::collect_word_timings(ctx, num_segments) {
+            Ok(words) => words,
+            Err(e) => {
+                tracing::debug!("Failed to collect word timings: {}", e);
+                Vec::new()
+            }
+        };
+
+        let mut confidence = match self.collect_segment_confidence(ctx, num_segments) {
+            Ok(confidence) => confidence,
+            Err(e) => {
+                tracing::debug!("Failed to collect segment confidence: {}", e);
+                Vec::new()
+            }
+        };
+
+        let result = if self.confidence.suppress && confidence.iter().any(|s| s.low_confidence) {
+            // Drop low-confidence segments along with the words spoken during them
+            words.retain(|w| {
+                !confidence
+                    .iter()
+                    .any(|s| s.low_confidence && w.start_ms < s.end_ms && w.end_ms > s.start_ms)
+            });
+            confidence.retain(|s| !s.low_confidence);
+            confidence
+                .iter()
+                .map(|s| s.text.as_str())
+                .collect::<Vec<_>>()
+                .join(" ")
+        } else {
+            Self::collect_segment_text(ctx, num_segments)
+        };
+
+        // Post-process to remove hallucination loops
+        let cleaned = Self::remove_repetition_loops(&result);
+
+        // Word timings no longer line up with text that had a loop removed
+        if cleaned != result {
+            words.clear();
+        }
+
+        let text = if cleaned.is_empty() {
+            words.clear();
+            confidence.clear();
+            NO_SPEECH_TEXT.to_string()
+        } else {
+            cleaned
+        };
+
+        Ok(Transcript {
+            text,
+            language: detected_language,
+            words,
+            segments: Vec::new(),
+            confidence,
+            translation: None,
+            modified: false,
+            speaker: None,
+            duration_ms,
+            audio_path: None,
+            output: None,
+        })
+    }
+
+    /// Translate audio samples (mono, 16kHz) to English.
+    ///
+    /// `language` is the spoken language, usually the one reported by a previous
+    /// [`Transcriber::transcribe`] call. Requires a multilingual model.
+    pub fn translate(
+        &mut self,
+        audio_data: &[f32],
+        language: &str,
+    ) -> Result<String, FlowSttError> {
+        if whisper_stubbed() {
+            return Ok(Self::stub_transcript(audio_data.len()).text);
+        }
+        self.load_model()?;
+        self.decode_translation(audio_data, language)
+            .map_err(FlowSttError::Transcription)
+    }
+
+    /// Translate with the model loaded.
+    fn decode_translation(&mut self, audio_data: &[f32], language: &str) -> Result<String, String> {
+        let ctx = self.ctx.as_ref().unwrap();
+        if !ctx.is_multilingual()? {
+            return Err("Translation requires a multilingual model".to_string());
+        }
+        let c_language =
+            CString::new(language).map_err(|e| format!("Invalid language code: {}", e))?;
+
+        let mut params = self.decoding_params()?;
+        params.language = c_language.as_ptr();
+        params.detect_language = false;
+        params.translate = true;
+        self.apply_abort_flag(&mut params);
+
+        ctx.full(&params, audio_data)
+            .map_err(|e| self.cancelled_or(e))?;
+
+        let num_segments = ctx.full_n_segments()?;
+        let text = Self::collect_segment_text(ctx, num_segments);
+        Ok(Self::remove_repetition_loops(&text))
+    }
+
+    /// Count the text tokens decoded by the last run (special tokens excluded).
+    pub fn last_token_count(&self) -> Result<usize, String> {
+        let ctx = self.ctx.as_ref().ok_or("Model not loaded")?;
+        let eot = ctx.token_eot()?;
+        let mut count = 0;
+        for i in 0..ctx.full_n_segments()? {
+            for j in 0..ctx.full_n_tokens(i)? {
+                if ctx.full_get_token_data(i, j)?.id < eot {
+                    count += 1;
+                }
+            }
         }
+        Ok(count)
+    }
 
+    /// The transcript the whisper stub returns for `samples` of 16kHz audio.
+    fn stub_transcript(samples: usize) -> Transcript {
+        let duration_ms = samples as u64 * 1000 / WHISPER_SAMPLE_RATE;
+        Transcript {
+            text: format!(
+                "Stub transcript of {:.2}s of audio",
+                duration_ms as f64 / 1000.0
+            ),
+            language: Some(DEFAULT_LANGUAGE.to_string()),
+            words: Vec::new(),
+            segments: Vec::new(),
+            confidence: Vec::new(),
+            translation: None,
+            speaker: None,
+            modified: false,
+            duration_ms,
+            audio_path: None,
+            output: None,
+        }
+    }
+
+    /// Join the trimmed text of all segments of the last run.
+    fn collect_segment_text(ctx: &Context, num_segments: i32) -> String {
         let mut result = String::new();
         for i in 0..num_segments {
             if let Ok(segment) = ctx.full_get_segment_text(i) {
@@ -122,15 +518,222 @@ impl Transcriber {
                 }
             }
         }
+        result
+    }
 
-        // Post-process to remove hallucination loops
-        let result = Self::remove_repetition_loops(&result);
+    /// Transcribe each audio source separately (mono, 16kHz each).
+    ///
+    /// Whisper segments from both sources are tagged with their source and
+    /// interleaved by start time, giving a basic "who said what" transcript.
+    /// Sources that are silent are skipped.
+    pub fn transcribe_sources(
+        &mut self,
+        mic: &[f32],
+        system: &[f32],
+        language: &str,
+    ) -> Result<Transcript, FlowSttError> {
+        if whisper_stubbed() {
+            return Ok(Self::stub_transcript(mic.len().max(system.len())));
+        }
+        let mut segments = Vec::new();
+        let mut words = Vec::new();
+        let mut confidence = Vec::new();
+        let mut detected_language = None;
+
+        for (source, audio) in [(SourceLabel::Mic, mic), (SourceLabel::System, system)] {
+            if rms(audio) < SILENT_SOURCE_RMS {
+                continue;
+            }
 
-        if result.is_empty() {
-            Ok("(No speech detected)".to_string())
+            let transcript = self.transcribe(audio, language)?;
+            if transcript.text == NO_SPEECH_TEXT {
+                continue;
+            }
+
+            segments.extend(
+                self.collect_source_segments(source)
+                    .map_err(FlowSttError::Transcription)?,
+            );
+            words.extend(transcript.words);
+            confidence.extend(transcript.confidence.into_iter().map(|mut c| {
+                c.source = Some(source);
+                c
+            }));
+            if detected_language.is_none() {
+                detected_language = transcript.language;
+            }
+        }
+
+        words.sort_by_key(|w| w.start_ms);
+        confidence.sort_by_key(|c| c.start_ms);
+        let text = join_source_segments(&mut segments);
+        let duration_ms = mic.len().max(system.len()) as u64 * 1000 / WHISPER_SAMPLE_RATE;
+
+        Ok(Transcript {
+            text: if text.is_empty() {
+                NO_SPEECH_TEXT.to_string()
+            } else {
+                text
+            },
+            language: detected_language,
+            words,
+            segments,
+            confidence,
+            translation: None,
+            modified: false,
+            speaker: None,
+            duration_ms,
+            audio_path: None,
+            output: None,
+        })
+    }
+
+    /// Collect the segments of the last transcription, tagged with their source.
+    fn collect_source_segments(&self, source: SourceLabel) -> Result<Vec<SourceSegment>, String> {
+        let ctx = self.ctx.as_ref().ok_or("Model not loaded")?;
+        let eot = ctx.token_eot()?;
+        let mut segments = Vec::new();
+
+        for i in 0..ctx.full_n_segments()? {
+            let text = Self::remove_repetition_loops(ctx.full_get_segment_text(i)?.trim());
+            if text.is_empty() {
+                continue;
+            }
+            if self.confidence.suppress {
+                let (avg_logprob, no_speech_prob) = Self::segment_scores(ctx, i, eot)?;
+                if self.confidence.is_low(avg_logprob, no_speech_prob) {
+                    continue;
+                }
+            }
+
+            // Segment times are in centiseconds
+            let (t0, t1) = ctx.full_get_segment_times(i)?;
+            segments.push(SourceSegment {
+                source,
+                text,
+                start_ms: t0.max(0) as u64 * 10,
+                end_ms: t1.max(0) as u64 * 10,
+            });
+        }
+
+        Ok(segments)
+    }
+
+    /// Collect the confidence of each non-empty segment of the last run,
+    /// flagging segments that fall outside the configured thresholds.
+    fn collect_segment_confidence(
+        &self,
+        ctx: &Context,
+        num_segments: i32,
+    ) -> Result<Vec<SegmentConfidence>, String> {
+        let eot = ctx.token_eot()?;
+        let mut segments = Vec::new();
+
+        for i in 0..num_segments {
+            let text = ctx.full_get_segment_text(i)?.trim().to_string();
+            if text.is_empty() {
+                continue;
+            }
+
+            let (avg_logprob, no_speech_prob) = Self::segment_scores(ctx, i, eot)?;
+            // Segment times are in centiseconds
+            let (t0, t1) = ctx.full_get_segment_times(i)?;
+            segments.push(SegmentConfidence {
+                text,
+                source: None,
+                start_ms: t0.max(0) as u64 * 10,
+                end_ms: t1.max(0) as u64 * 10,
+                avg_logprob,
+                no_speech_prob,
+                low_confidence: self.confidence.is_low(avg_logprob, no_speech_prob),
+            });
+        }
+
+        Ok(segments)
+    }
+
+    /// Mean log-probability of a segment's text tokens and its no-speech probability.
+    fn segment_scores(ctx: &Context, i_segment: i32, eot: i32) -> Result<(f32, f32), String> {
+        let mut logprob_sum = 0.0;
+        let mut text_tokens = 0;
+        for j in 0..ctx.full_n_tokens(i_segment)? {
+            let data = ctx.full_get_token_data(i_segment, j)?;
+            // Special tokens (timestamps, end-of-text) don't reflect the text
+            if data.id < eot {
+                logprob_sum += data.plog;
+                text_tokens += 1;
+            }
+        }
+        let avg_logprob = if text_tokens > 0 {
+            logprob_sum / text_tokens as f32
         } else {
-            Ok(result)
+            0.0
+        };
+        Ok((avg_logprob, ctx.full_get_segment_no_speech_prob(i_segment)?))
+    }
+
+    /// Collect word timings from the tokens of the last transcription.
+    fn collect_word_timings(ctx: &Context, num_segments: i32) -> Result<Vec<WordTiming>, String> {
+        let eot = ctx.token_eot()?;
+        let mut words = Vec::new();
+
+        for i in 0..num_segments {
+            let mut pieces = Vec::new();
+            for j in 0..ctx.full_n_tokens(i)? {
+                let data = ctx.full_get_token_data(i, j)?;
+                // Skip special tokens (timestamps, end-of-text, language tags)
+                if data.id >= eot {
+                    continue;
+                }
+                pieces.push(TokenPiece {
+                    bytes: ctx.full_get_token_text(i, j)?,
+                    t0: data.t0,
+                    t1: data.t1,
+                    p: data.p,
+                });
+            }
+            words.extend(Self::group_tokens_into_words(&pieces));
+        }
+
+        Ok(words)
+    }
+
+    /// Merge sub-word tokens into words.
+    ///
+    /// Whisper tokens that begin with a space start a new word; all other
+    /// tokens (word continuations, punctuation) extend the current word.
+    fn group_tokens_into_words(pieces: &[TokenPiece]) -> Vec<WordTiming> {
+        let mut groups: Vec<Vec<&TokenPiece>> = Vec::new();
+        for piece in pieces {
+            match groups.last_mut() {
+                Some(group) if piece.bytes.first() != Some(&b' ') => group.push(piece),
+                _ => groups.push(vec![piece]),
+            }
         }
+
+        groups
+            .into_iter()
+            .filter_map(|group| {
+                // Join bytes before decoding: tokens can split multi-byte characters
+                let bytes: Vec<u8> = group.iter().flat_map(|p| p.bytes.iter().copied()).collect();
+                let word = String::from_utf8_lossy(&bytes).trim().to_string();
+                if word.is_empty() {
+                    return None;
+                }
+
+                let start = group[0].t0.max(0);
+                let end = group[group.len() - 1].t1.max(start);
+                let probability = group.iter().map(|p| p.p).sum::<f32>() / group.len() as f32;
+
+                // Whisper token times are in centiseconds
+                Some(WordTiming {
+                    word,
+                    start_ms: start as u64 * 10,
+                    end_ms: end as u64 * 10,
+                    probability,
+                })
+            })
+            .collect()
     }
 
     /// Transcribe audio with duration hint for optimization.
@@ -160,12 +763,14 @@ impl Transcriber {
         }
 
         // Run transcription
-        ctx.full(&params, audio_data)?;
+        self.apply_abort_flag(&mut params);
+        ctx.full(&params, audio_data)
+            .map_err(|e| self.cancelled_or(e))?;
 
         let num_segments = ctx.full_n_segments()?;
 
         if num_segments == 0 {
-            return Ok("(No speech detected)".to_string());
+            return Ok(NO_SPEECH_TEXT.to_string());
         }
 
         let mut result = String::new();
@@ -185,7 +790,7 @@ impl Transcriber {
         let result = Self::remove_repetition_loops(&result);
 
         if result.is_empty() {
-            Ok("(No speech detected)".to_string())
+            Ok(NO_SPEECH_TEXT.to_string())
         } else {
             Ok(result)
         }
@@ -285,16 +890,26 @@ impl Default for Transcriber {
     }
 }
 
-/// Get the default model path.
-fn get_default_model_path() -> PathBuf {
-    let cache_dir = directories::BaseDirs::new()
-        .map(|d| d.cache_dir().to_path_buf())
-        .unwrap_or_else(|| PathBuf::from("."));
-    cache_dir.join("whisper").join("ggml-base.en.bin")
+/// Sort source segments by start time and join their text.
+fn join_source_segments(segments: &mut [SourceSegment]) -> String {
+    segments.sort_by_key(|s| s.start_ms);
+    segments
+        .iter()
+        .map(|s| s.text.as_str())
+        .collect::<Vec<_>>()
+        .join(" ")
 }
 
-/// Download the Whisper model to the specified path.
-pub fn download_model(model_path: &PathBuf) -> Result<(), String> {
+/// Root-mean-square level of an audio buffer.
+fn rms(samples: &[f32]) -> f32 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+}
+
+/// Download a catalog model to the specified path.
+pub fn download_model(name: &str, model_path: &PathBuf) -> Result<(), String> {
     // Create parent directory if it doesn't exist
     if let Some(parent) = model_path.parent() {
         std::fs::create_dir_all(parent)
@@ -304,7 +919,7 @@ pub fn download_model(model_path: &PathBuf) -> Result<(), String> {
     tracing::info!("Downloading whisper model to: {}", model_path.display());
 
     // Download the model
-    let response = reqwest::blocking::get(MODEL_URL)
+    let response = reqwest::blocking::get(model::model_url(name))
         .map_err(|e| format!("Failed to download model: {}", e))?;
 
     if !response.status().is_success() {
@@ -337,6 +952,74 @@ mod tests {
         assert!(!transcriber.get_model_path().as_os_str().is_empty());
     }
 
+    fn piece(text: &str, t0: i64, t1: i64) -> TokenPiece {
+        TokenPiece {
+            bytes: text.as_bytes().to_vec(),
+            t0,
+            t1,
+            p: 0.5,
+        }
+    }
+
+    #[test]
+    fn test_group_tokens_into_words() {
+        let pieces = vec![
+            piece(" Hello", 0, 30),
+            piece(" wor", 35, 50),
+            piece("ld", 50, 62),
+            piece(",", 62, 64),
+            piece(" again", 70, 90),
+        ];
+        let words = Transcriber::group_tokens_into_words(&pieces);
+        let texts: Vec<&str> = words.iter().map(|w| w.word.as_str()).collect();
+        assert_eq!(texts, vec!["Hello", "world,", "again"]);
+        assert_eq!(words[1].start_ms, 350);
+        assert_eq!(words[1].end_ms, 640);
+        assert!((words[1].probability - 0.5).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn test_group_tokens_joins_split_utf8() {
+        // "é" split across two tokens
+        let pieces = vec![
+            TokenPiece {
+                bytes: b" caf\xC3".to_vec(),
+                t0: 0,
+                t1: 10,
+                p: 1.0,
+            },
+            TokenPiece {
+                bytes: vec![0xA9],
+                t0: 10,
+                t1: 20,
+                p: 1.0,
+            },
+        ];
+        let words = Transcriber::group_tokens_into_words(&pieces);
+        assert_eq!(words.len(), 1);
+        assert_eq!(words[0].word, "café");
+        assert_eq!(words[0].end_ms, 200);
+    }
+
+    #[test]
+    fn test_join_source_segments_interleaves_by_time() {
+        let segment = |source, text: &str, start_ms| SourceSegment {
+            source,
+            text: text.to_string(),
+            start_ms,
+            end_ms: start_ms + 500,
+        };
+        let mut segments = vec![
+            segment(SourceLabel::Mic, "Hi there.", 0),
+            segment(SourceLabel::Mic, "Doing well.", 2000),
+            segment(SourceLabel::System, "Hello, how are you?", 800),
+        ];
+
+        let text = join_source_segments(&mut segments);
+        assert_eq!(text, "Hi there. Hello, how are you? Doing well.");
+        assert_eq!(segments[1].source, SourceLabel::System);
+    }
+
     #[test]
     fn test_remove_repetition_loops_basic() {
         // Classic hallucination loop
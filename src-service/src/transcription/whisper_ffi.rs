@@ -8,6 +8,7 @@
 use libloading::Library;
 use std::ffi::{c_char, c_float, c_int, CStr, CString};
 use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::OnceLock;
 
 /// Opaque pointer to whisper_context
@@ -17,10 +18,23 @@ type WhisperContext = *mut std::ffi::c_void;
 type WhisperNewSegmentCallback = *const std::ffi::c_void;
 type WhisperProgressCallback = *const std::ffi::c_void;
 type WhisperEncoderBeginCallback = *const std::ffi::c_void;
-type WhisperAbortCallback = *const std::ffi::c_void;
-type WhisperLogitsFilterCallback = *const std::ffi::c_void;
 type WhisperGrammarElement = *const std::ffi::c_void;
 
+/// Polled during computation; returning true aborts whisper_full
+type WhisperAbortCallback = Option<unsafe extern "C" fn(user_data: *mut std::ffi::c_void) -> bool>;
+
+/// Called before each token is sampled with the tokens decoded so far
+type WhisperLogitsFilterCallback = Option<
+    unsafe extern "C" fn(
+        ctx: WhisperContext,
+        state: *mut std::ffi::c_void,
+        tokens: *const WhisperTokenData,
+        n_tokens: c_int,
+        logits: *mut c_float,
+        user_data: *mut std::ffi::c_void,
+    ),
+>;
+
 /// VAD parameters struct
 #[repr(C)]
 #[derive(Clone, Copy)]
@@ -33,6 +47,47 @@ pub struct WhisperVadParams {
     pub samples_overlap: c_float,
 }
 
+/// whisper_token_data matching the C struct layout from whisper.h
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct WhisperTokenData {
+    pub id: c_int,
+    pub tid: c_int,
+    pub p: c_float,
+    pub plog: c_float,
+    pub pt: c_float,
+    pub ptsum: c_float,
+    /// Token start time in centiseconds (requires token_timestamps)
+    pub t0: i64,
+    /// Token end time in centiseconds (requires token_timestamps)
+    pub t1: i64,
+    pub t_dtw: i64,
+    pub vlen: c_float,
+}
+
+/// whisper_aheads matching the C struct layout from whisper.h
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct WhisperAheads {
+    pub n_heads: usize,
+    pub heads: *const std::ffi::c_void,
+}
+
+/// whisper_context_params matching the C struct layout from whisper.h
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct WhisperContextParams {
+    pub use_gpu: bool,
+    pub flash_attn: bool,
+    /// GPU device index (CUDA device when using the CUDA backend)
+    pub gpu_device: c_int,
+    pub dtw_token_timestamps: bool,
+    pub dtw_aheads_preset: c_int, // enum whisper_alignment_heads_preset
+    pub dtw_n_top: c_int,
+    pub dtw_aheads: WhisperAheads,
+    pub dtw_mem_size: usize,
+}
+
 /// whisper_full_params matching the C struct layout from whisper.h
 /// IMPORTANT: This must match the exact layout of whisper_full_params in whisper.cpp
 #[repr(C)]
@@ -132,6 +187,14 @@ pub struct WhisperFullParams {
 }
 
 impl WhisperFullParams {
+    /// Abort `whisper_full` as soon as `flag` is set.
+    ///
+    /// The flag must outlive every `whisper_full` call made with these params.
+    pub fn abort_when(&mut self, flag: &AtomicBool) {
+        self.abort_callback = Some(abort_on_flag);
+        self.abort_callback_user_data = flag as *const AtomicBool as *mut std::ffi::c_void;
+    }
+
     /// Configure parameters optimized for short audio segments (real-time transcription)
     /// - n_samples: total samples in the (possibly padded) audio buffer
     /// - duration_ms: actual speech duration in milliseconds (before padding)
@@ -240,7 +303,6 @@ impl WhisperFullParams {
 
 /// Sampling strategy enum matching whisper.cpp
 #[repr(C)]
-#[allow(dead_code)]
 pub enum WhisperSamplingStrategy {
     Greedy = 0,
     BeamSearch = 1,
@@ -368,7 +430,11 @@ fn try_load_cuda_backend(_ggml_lib: &GgmlLibrary, _lib_dir: &Path) {
 pub struct WhisperLibrary {
     _lib: Library,
     // Function pointers
-    init_from_file: unsafe extern "C" fn(path_model: *const c_char) -> WhisperContext,
+    context_default_params: unsafe extern "C" fn() -> WhisperContextParams,
+    init_from_file_with_params: unsafe extern "C" fn(
+        path_model: *const c_char,
+        params: WhisperContextParams,
+    ) -> WhisperContext,
     free: unsafe extern "C" fn(ctx: WhisperContext),
     full_default_params: unsafe extern "C" fn(strategy: c_int) -> WhisperFullParams,
     full: unsafe extern "C" fn(
@@ -380,6 +446,27 @@ pub struct WhisperLibrary {
     full_n_segments: unsafe extern "C" fn(ctx: WhisperContext) -> c_int,
     full_get_segment_text:
         unsafe extern "C" fn(ctx: WhisperContext, i_segment: c_int) -> *const c_char,
+    full_get_segment_t0: unsafe extern "C" fn(ctx: WhisperContext, i_segment: c_int) -> i64,
+    full_get_segment_t1: unsafe extern "C" fn(ctx: WhisperContext, i_segment: c_int) -> i64,
+    full_get_segment_no_speech_prob:
+        unsafe extern "C" fn(ctx: WhisperContext, i_segment: c_int) -> c_float,
+    full_n_tokens: unsafe extern "C" fn(ctx: WhisperContext, i_segment: c_int) -> c_int,
+    full_get_token_text: unsafe extern "C" fn(
+        ctx: WhisperContext,
+        i_segment: c_int,
+        i_token: c_int,
+    ) -> *const c_char,
+    full_get_token_data: unsafe extern "C" fn(
+        ctx: WhisperContext,
+        i_segment: c_int,
+        i_token: c_int,
+    ) -> WhisperTokenData,
+    token_eot: unsafe extern "C" fn(ctx: WhisperContext) -> c_int,
+    token_to_str: unsafe extern "C" fn(ctx: WhisperContext, token: c_int) -> *const c_char,
+    full_lang_id: unsafe extern "C" fn(ctx: WhisperContext) -> c_int,
+    is_multilingual: unsafe extern "C" fn(ctx: WhisperContext) -> c_int,
+    lang_id: unsafe extern "C" fn(lang: *const c_char) -> c_int,
+    lang_str: unsafe extern "C" fn(id: c_int) -> *const c_char,
     print_system_info: unsafe extern "C" fn() -> *const c_char,
 }
 
@@ -395,11 +482,17 @@ impl WhisperLibrary {
                 .map_err(|e| format!("Failed to load whisper library: {}", e))?;
 
             // Load all required symbols - dereference immediately to get raw fn pointers
-            let init_from_file = *lib
-                .get::<unsafe extern "C" fn(*const c_char) -> WhisperContext>(
-                    b"whisper_init_from_file\0",
+            let context_default_params = *lib
+                .get::<unsafe extern "C" fn() -> WhisperContextParams>(
+                    b"whisper_context_default_params\0",
                 )
-                .map_err(|e| format!("Failed to load whisper_init_from_file: {}", e))?;
+                .map_err(|e| format!("Failed to load whisper_context_default_params: {}", e))?;
+
+            let init_from_file_with_params = *lib
+                .get::<unsafe extern "C" fn(*const c_char, WhisperContextParams) -> WhisperContext>(
+                    b"whisper_init_from_file_with_params\0",
+                )
+                .map_err(|e| format!("Failed to load whisper_init_from_file_with_params: {}", e))?;
 
             let free = *lib
                 .get::<unsafe extern "C" fn(WhisperContext)>(b"whisper_free\0")
@@ -430,18 +523,98 @@ impl WhisperLibrary {
                 )
                 .map_err(|e| format!("Failed to load whisper_full_get_segment_text: {}", e))?;
 
+            let full_get_segment_t0 = *lib
+                .get::<unsafe extern "C" fn(WhisperContext, c_int) -> i64>(
+                    b"whisper_full_get_segment_t0\0",
+                )
+                .map_err(|e| format!("Failed to load whisper_full_get_segment_t0: {}", e))?;
+
+            let full_get_segment_t1 = *lib
+                .get::<unsafe extern "C" fn(WhisperContext, c_int) -> i64>(
+                    b"whisper_full_get_segment_t1\0",
+                )
+                .map_err(|e| format!("Failed to load whisper_full_get_segment_t1: {}", e))?;
+
+            let full_get_segment_no_speech_prob = *lib
+                .get::<unsafe extern "C" fn(WhisperContext, c_int) -> c_float>(
+                    b"whisper_full_get_segment_no_speech_prob\0",
+                )
+                .map_err(|e| {
+                    format!(
+                        "Failed to load whisper_full_get_segment_no_speech_prob: {}",
+                        e
+                    )
+                })?;
+
+            let full_n_tokens = *lib
+                .get::<unsafe extern "C" fn(WhisperContext, c_int) -> c_int>(
+                    b"whisper_full_n_tokens\0",
+                )
+                .map_err(|e| format!("Failed to load whisper_full_n_tokens: {}", e))?;
+
+            let full_get_token_text = *lib
+                .get::<unsafe extern "C" fn(WhisperContext, c_int, c_int) -> *const c_char>(
+                    b"whisper_full_get_token_text\0",
+                )
+                .map_err(|e| format!("Failed to load whisper_full_get_token_text: {}", e))?;
+
+            let full_get_token_data = *lib
+                .get::<unsafe extern "C" fn(WhisperContext, c_int, c_int) -> WhisperTokenData>(
+                    b"whisper_full_get_token_data\0",
+                )
+                .map_err(|e| format!("Failed to load whisper_full_get_token_data: {}", e))?;
+
+            let token_eot = *lib
+                .get::<unsafe extern "C" fn(WhisperContext) -> c_int>(b"whisper_token_eot\0")
+                .map_err(|e| format!("Failed to load whisper_token_eot: {}", e))?;
+
+            let token_to_str = *lib
+                .get::<unsafe extern "C" fn(WhisperContext, c_int) -> *const c_char>(
+                    b"whisper_token_to_str\0",
+                )
+                .map_err(|e| format!("Failed to load whisper_token_to_str: {}", e))?;
+
+            let full_lang_id = *lib
+                .get::<unsafe extern "C" fn(WhisperContext) -> c_int>(b"whisper_full_lang_id\0")
+                .map_err(|e| format!("Failed to load whisper_full_lang_id: {}", e))?;
+
+            let is_multilingual = *lib
+                .get::<unsafe extern "C" fn(WhisperContext) -> c_int>(b"whisper_is_multilingual\0")
+                .map_err(|e| format!("Failed to load whisper_is_multilingual: {}", e))?;
+
+            let lang_id = *lib
+                .get::<unsafe extern "C" fn(*const c_char) -> c_int>(b"whisper_lang_id\0")
+                .map_err(|e| format!("Failed to load whisper_lang_id: {}", e))?;
+
+            let lang_str = *lib
+                .get::<unsafe extern "C" fn(c_int) -> *const c_char>(b"whisper_lang_str\0")
+                .map_err(|e| format!("Failed to load whisper_lang_str: {}", e))?;
+
             let print_system_info = *lib
                 .get::<unsafe extern "C" fn() -> *const c_char>(b"whisper_print_system_info\0")
                 .map_err(|e| format!("Failed to load whisper_print_system_info: {}", e))?;
 
             Ok(Self {
                 _lib: lib,
-                init_from_file,
+                context_default_params,
+                init_from_file_with_params,
                 free,
                 full_default_params,
                 full,
                 full_n_segments,
                 full_get_segment_text,
+                full_get_segment_t0,
+                full_get_segment_t1,
+                full_get_segment_no_speech_prob,
+                full_n_tokens,
+                full_get_token_text,
+                full_get_token_data,
+                token_eot,
+                token_to_str,
+                full_lang_id,
+                is_multilingual,
+                lang_id,
+                lang_str,
                 print_system_info,
             })
         }
@@ -636,23 +809,40 @@ fn get_lib() -> Result<&'static WhisperLibrary, String> {
         .ok_or_else(|| "Whisper library not loaded".to_string())
 }
 
-/// Safe wrapper around whisper context
+/// Safe wrapper around whisper context.
+///
+/// Contexts are leased through [`pool`](super::pool) rather than created
+/// directly, so each model is loaded once.
 pub struct Context {
     ptr: WhisperContext,
 }
 
-// SAFETY: WhisperContext is thread-safe according to whisper.cpp documentation
+// SAFETY: a whisper_context may be used from any thread, but not from two at
+// once: whisper_full stores its results in the context's default state, which
+// the accessors below read back. Context is therefore Send but not Sync, and
+// every method that touches the context requires it to be borrowed.
 unsafe impl Send for Context {}
 
 impl Context {
-    /// Create a new context from a model file
-    pub fn new<P: AsRef<Path>>(model_path: P) -> Result<Self, String> {
+    /// Create a new context from a model file.
+    ///
+    /// `use_gpu` selects GPU acceleration (when the library was built with a GPU
+    /// backend) and `gpu_device` picks which GPU to use.
+    pub fn new<P: AsRef<Path>>(
+        model_path: P,
+        use_gpu: bool,
+        gpu_device: u32,
+    ) -> Result<Self, String> {
         let lib = get_lib()?;
 
         let path_str = model_path.as_ref().to_str().ok_or("Invalid model path")?;
         let c_path = CString::new(path_str).map_err(|e| format!("Invalid path: {}", e))?;
 
-        let ptr = unsafe { (lib.init_from_file)(c_path.as_ptr()) };
+        let mut params = unsafe { (lib.context_default_params)() };
+        params.use_gpu = use_gpu;
+        params.gpu_device = gpu_device as c_int;
+
+        let ptr = unsafe { (lib.init_from_file_with_params)(c_path.as_ptr(), params) };
 
         if ptr.is_null() {
             return Err(format!(
@@ -684,6 +874,29 @@ impl Context {
         Ok(())
     }
 
+    /// Run full transcription, calling `on_text` with the text of the current
+    /// segment each time another token has been decoded.
+    ///
+    /// The text is provisional: temperature fallback restarts decoding, and
+    /// each 30 second window starts over, so it can shrink between calls.
+    pub fn full_streaming(
+        &self,
+        params: &WhisperFullParams,
+        samples: &[f32],
+        on_text: &mut dyn FnMut(&str),
+    ) -> Result<(), String> {
+        let mut stream = TokenStream {
+            eot: self.token_eot()?,
+            text: String::new(),
+            on_text,
+        };
+        let mut params = params.clone();
+        params.logits_filter_callback = Some(token_stream_callback);
+        params.logits_filter_callback_user_data =
+            &mut stream as *mut TokenStream as *mut std::ffi::c_void;
+        self.full(&params, samples)
+    }
+
     /// Get the number of segments in the transcription result
     pub fn full_n_segments(&self) -> Result<i32, String> {
         let lib = get_lib()?;
@@ -706,6 +919,128 @@ impl Context {
             .map(|s| s.to_string())
             .map_err(|e| format!("Invalid UTF-8 in segment: {}", e))
     }
+
+    /// Get the start and end time of a specific segment in centiseconds
+    pub fn full_get_segment_times(&self, i_segment: i32) -> Result<(i64, i64), String> {
+        let lib = get_lib()?;
+        let t0 = unsafe { (lib.full_get_segment_t0)(self.ptr, i_segment) };
+        let t1 = unsafe { (lib.full_get_segment_t1)(self.ptr, i_segment) };
+        Ok((t0, t1))
+    }
+
+    /// Get the probability that a specific segment contains no speech
+    pub fn full_get_segment_no_speech_prob(&self, i_segment: i32) -> Result<f32, String> {
+        let lib = get_lib()?;
+        Ok(unsafe { (lib.full_get_segment_no_speech_prob)(self.ptr, i_segment) })
+    }
+
+    /// Get the number of tokens in a specific segment
+    pub fn full_n_tokens(&self, i_segment: i32) -> Result<i32, String> {
+        let lib = get_lib()?;
+        Ok(unsafe { (lib.full_n_tokens)(self.ptr, i_segment) })
+    }
+
+    /// Get the text of a specific token.
+    /// Tokens may split multi-byte characters, so the raw bytes are returned.
+    pub fn full_get_token_text(&self, i_segment: i32, i_token: i32) -> Result<Vec<u8>, String> {
+        let lib = get_lib()?;
+
+        let ptr = unsafe { (lib.full_get_token_text)(self.ptr, i_segment, i_token) };
+
+        if ptr.is_null() {
+            return Err(format!(
+                "Failed to get token {} text in segment {}",
+                i_token, i_segment
+            ));
+        }
+
+        Ok(unsafe { CStr::from_ptr(ptr) }.to_bytes().to_vec())
+    }
+
+    /// Get the timing and probability data of a specific token
+    pub fn full_get_token_data(
+        &self,
+        i_segment: i32,
+        i_token: i32,
+    ) -> Result<WhisperTokenData, String> {
+        let lib = get_lib()?;
+        Ok(unsafe { (lib.full_get_token_data)(self.ptr, i_segment, i_token) })
+    }
+
+    /// Get the end-of-text token id. Ids at or above this are special tokens
+    /// (timestamps, language tags, etc.) rather than text.
+    pub fn token_eot(&self) -> Result<i32, String> {
+        let lib = get_lib()?;
+        Ok(unsafe { (lib.token_eot)(self.ptr) })
+    }
+
+    /// Get the language code used for the last transcription.
+    /// When auto-detection is enabled this is the detected language.
+    pub fn full_lang(&self) -> Result<String, String> {
+        let lib = get_lib()?;
+        let id = unsafe { (lib.full_lang_id)(self.ptr) };
+        lang_str(id).ok_or_else(|| format!("Unknown language id: {}", id))
+    }
+
+    /// Check whether the loaded model supports languages other than English
+    pub fn is_multilingual(&self) -> Result<bool, String> {
+        let lib = get_lib()?;
+        Ok(unsafe { (lib.is_multilingual)(self.ptr) } != 0)
+    }
+}
+
+/// Abort callback that reads the flag passed to [`WhisperFullParams::abort_when`].
+unsafe extern "C" fn abort_on_flag(user_data: *mut std::ffi::c_void) -> bool {
+    !user_data.is_null() && (*(user_data as *const AtomicBool)).load(Ordering::SeqCst)
+}
+
+/// State of [`Context::full_streaming`] shared with the logits filter callback
+struct TokenStream<'a> {
+    eot: c_int,
+    /// Text last passed to `on_text`
+    text: String,
+    on_text: &'a mut dyn FnMut(&str),
+}
+
+/// Logits filter that reports decoded text without changing the logits.
+unsafe extern "C" fn token_stream_callback(
+    ctx: WhisperContext,
+    _state: *mut std::ffi::c_void,
+    tokens: *const WhisperTokenData,
+    n_tokens: c_int,
+    _logits: *mut c_float,
+    user_data: *mut std::ffi::c_void,
+) {
+    let Ok(lib) = get_lib() else {
+        return;
+    };
+    if user_data.is_null() || tokens.is_null() || n_tokens <= 0 {
+        return;
+    }
+    let stream = &mut *(user_data as *mut TokenStream);
+    let tokens = std::slice::from_raw_parts(tokens, n_tokens as usize);
+
+    // Tokens may split multi-byte characters, so join the bytes before decoding
+    let mut bytes = Vec::new();
+    for token in tokens.iter().filter(|t| t.id < stream.eot) {
+        let ptr = (lib.token_to_str)(ctx, token.id);
+        if !ptr.is_null() {
+            bytes.extend_from_slice(CStr::from_ptr(ptr).to_bytes());
+        }
+    }
+    let text = String::from_utf8_lossy(&bytes);
+    let text = text.trim();
+    if text.is_empty() || text == stream.text {
+        return;
+    }
+    stream.text = text.to_string();
+
+    // Unwinding into whisper.cpp is undefined behavior
+    let on_text = &mut stream.on_text;
+    let text = stream.text.as_str();
+    if std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| on_text(text))).is_err() {
+        tracing::error!("Token stream handler panicked");
+    }
 }
 
 impl Drop for Context {
@@ -722,6 +1057,27 @@ pub fn full_default_params(strategy: WhisperSamplingStrategy) -> Result<WhisperF
     Ok(unsafe { (lib.full_default_params)(strategy as c_int) })
 }
 
+/// Check whether whisper.cpp recognizes the given language code (e.g. "en", "de")
+pub fn is_valid_language(language: &str) -> Result<bool, String> {
+    let lib = get_lib()?;
+    let c_lang = CString::new(language).map_err(|e| format!("Invalid language: {}", e))?;
+    Ok(unsafe { (lib.lang_id)(c_lang.as_ptr()) } >= 0)
+}
+
+/// Get the short language code for a whisper language id
+fn lang_str(id: c_int) -> Option<String> {
+    let lib = get_lib().ok()?;
+    if id < 0 {
+        return None;
+    }
+    let ptr = unsafe { (lib.lang_str)(id) };
+    if ptr.is_null() {
+        return None;
+    }
+    let c_str = unsafe { CStr::from_ptr(ptr) };
+    c_str.to_str().ok().map(|s| s.to_string())
+}
+
 /// Get whisper.cpp system info string
 /// This includes information about available backends (CPU, CUDA, Metal, etc.)
 pub fn get_system_info() -> Result<String, String> {
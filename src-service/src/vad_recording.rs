@@ -0,0 +1,318 @@
+//! Voice-activated recording.
+//!
+//! Writes the captured audio to the recordings directory only while speech is
+//! detected. Recording starts with the audio from before speech was confirmed
+//! (the detector's lookback plus a configurable pre-roll) and continues for a
+//! post-roll after speech ends; speech resuming within the post-roll continues
+//! the same utterance. Utterances are written to their own files, or one file
+//! per capture session with the silence between them left out.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use tracing::{error, info};
+
+use crate::audio::{generate_recording_filename, recording_format, recordings_dir};
+use crate::config::{VadRecordingConfig, VadRecordingSplit};
+use crate::encoder::AudioEncoder;
+use crate::processor::SpeechStateChange;
+
+/// Longest lookback a speech detector reports, in milliseconds
+const MAX_LOOKBACK_MS: u32 = 1000;
+
+/// Longest pre-roll or post-roll accepted, in milliseconds
+pub const MAX_ROLL_MS: u32 = 10_000;
+
+static CONFIG: Mutex<Option<VadRecordingConfig>> = Mutex::new(None);
+
+/// Set when `CONFIG` changes so the audio loop replaces its recorder
+static CONFIG_CHANGED: AtomicBool = AtomicBool::new(false);
+
+/// Replace the recording settings, applying them to a running capture.
+pub fn set_config(config: VadRecordingConfig) {
+    *CONFIG.lock().unwrap() = Some(config);
+    CONFIG_CHANGED.store(true, Ordering::SeqCst);
+}
+
+fn config() -> VadRecordingConfig {
+    CONFIG.lock().unwrap().unwrap_or_default()
+}
+
+/// The recorder for a new capture, if voice-activated recording is on.
+pub fn recorder() -> Option<VadRecorder> {
+    CONFIG_CHANGED.store(false, Ordering::SeqCst);
+    let config = config();
+    config.enabled.then(|| VadRecorder::new(config))
+}
+
+/// Whether the settings changed since the last `recorder` call.
+pub fn config_changed() -> bool {
+    CONFIG_CHANGED.load(Ordering::SeqCst)
+}
+
+/// Writes utterances from the capture stream to files.
+///
+/// The current file is finished when the recorder is dropped.
+pub struct VadRecorder {
+    config: VadRecordingConfig,
+    /// Format of the audio fed in; the gate is rebuilt when it changes
+    format: Option<(u32, u16)>,
+    gate: Option<Gate>,
+    writer: Option<AudioEncoder>,
+    /// Set after a write error so it is only logged once
+    failed: bool,
+}
+
+impl VadRecorder {
+    fn new(config: VadRecordingConfig) -> Self {
+        Self {
+            config,
+            format: None,
+            gate: None,
+            writer: None,
+            failed: false,
+        }
+    }
+
+    /// Feed interleaved capture audio along with the speech detector's state
+    /// change for it.
+    pub fn process(
+        &mut self,
+        samples: &[f32],
+        sample_rate: u32,
+        channels: u16,
+        change: &SpeechStateChange,
+    ) {
+        if self.format != Some((sample_rate, channels)) {
+            self.finish();
+            self.format = Some((sample_rate, channels));
+            self.gate = Some(Gate::new(&self.config, sample_rate, channels));
+        }
+        let Some(gate) = self.gate.as_mut() else {
+            return;
+        };
+
+        let output = gate.process(samples, change);
+        if self.failed {
+            return;
+        }
+        if output.started && self.writer.is_none() {
+            self.writer = match create_writer(sample_rate, channels) {
+                Ok(writer) => Some(writer),
+                Err(e) => {
+                    error!("Voice-activated recording stopped: {}", e);
+                    self.failed = true;
+                    return;
+                }
+            };
+        }
+        if let Some(writer) = self.writer.as_mut() {
+            if let Err(e) = writer.write(&output.audio) {
+                error!("Voice-activated recording stopped: {}", e);
+                self.failed = true;
+                self.writer = None;
+                return;
+            }
+        }
+        if output.ended && self.config.split == VadRecordingSplit::Utterance {
+            self.finish();
+        }
+    }
+
+    fn finish(&mut self) {
+        if let Some(writer) = self.writer.take() {
+            if let Err(e) = writer.finish() {
+                error!("Failed to finish voice-activated recording: {}", e);
+            }
+        }
+    }
+}
+
+impl Drop for VadRecorder {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+fn create_writer(sample_rate: u32, channels: u16) -> Result<AudioEncoder, String> {
+    let dir = recordings_dir();
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {:?}: {}", dir, e))?;
+    let path = dir.join(generate_recording_filename());
+    let writer = AudioEncoder::create(&path, recording_format(), sample_rate, channels)?;
+    info!("Recording speech to {:?}", path);
+    Ok(writer)
+}
+
+/// Audio a chunk of capture contributes to the recording.
+#[derive(Debug, Default, PartialEq)]
+struct GateOutput {
+    /// An utterance starts with this audio
+    started: bool,
+    /// Samples to write
+    audio: Vec<f32>,
+    /// The utterance ends with this audio
+    ended: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Phase {
+    Silent,
+    Speaking,
+    /// Speech ended; this many frames of post-roll are still to be written
+    Trailing(usize),
+}
+
+/// Decides which captured audio belongs to an utterance.
+struct Gate {
+    channels: usize,
+    pre_roll_frames: usize,
+    post_roll_frames: usize,
+    /// Recent audio while silent, for the lookback and pre-roll
+    history: VecDeque<f32>,
+    /// Samples kept in `history`
+    history_capacity: usize,
+    phase: Phase,
+}
+
+impl Gate {
+    fn new(config: &VadRecordingConfig, sample_rate: u32, channels: u16) -> Self {
+        let frames = |ms: u32| (sample_rate as u64 * ms as u64 / 1000) as usize;
+        let channels = channels.max(1) as usize;
+        let pre_roll_frames = frames(config.pre_roll_ms);
+        Self {
+            channels,
+            pre_roll_frames,
+            post_roll_frames: frames(config.post_roll_ms),
+            history: VecDeque::new(),
+            history_capacity: (pre_roll_frames + frames(MAX_LOOKBACK_MS)) * channels,
+            phase: Phase::Silent,
+        }
+    }
+
+    /// `change` is what the speech detector reported after processing
+    /// `samples`; lookbacks count back from the end of `samples`.
+    fn process(&mut self, samples: &[f32], change: &SpeechStateChange) -> GateOutput {
+        let mut output = GateOutput::default();
+        match (self.phase, change) {
+            (Phase::Silent, SpeechStateChange::Started { lookback_samples }) => {
+                self.remember(samples);
+                let keep = (lookback_samples + self.pre_roll_frames) * self.channels;
+                let skip = self.history.len().saturating_sub(keep);
+                output.started = true;
+                output.audio = self.history.drain(..).skip(skip).collect();
+                self.phase = Phase::Speaking;
+            }
+            (Phase::Silent, _) => self.remember(samples),
+            (Phase::Speaking | Phase::Trailing(_), SpeechStateChange::Started { .. })
+            | (Phase::Speaking, SpeechStateChange::None) => {
+                output.audio = samples.to_vec();
+                self.phase = Phase::Speaking;
+            }
+            (Phase::Speaking, SpeechStateChange::Ended { .. }) => {
+                output.audio = samples.to_vec();
+                self.phase = Phase::Trailing(self.post_roll_frames);
+                if self.post_roll_frames == 0 {
+                    output.ended = true;
+                    self.phase = Phase::Silent;
+                }
+            }
+            (Phase::Trailing(remaining), _) => {
+                let frames = samples.len() / self.channels;
+                let take = remaining.min(frames) * self.channels;
+                output.audio = samples[..take].to_vec();
+                if remaining > frames {
+                    self.phase = Phase::Trailing(remaining - frames);
+                } else {
+                    output.ended = true;
+                    self.phase = Phase::Silent;
+                    self.remember(&samples[take..]);
+                }
+            }
+        }
+        output
+    }
+
+    fn remember(&mut self, samples: &[f32]) {
+        self.history.extend(samples);
+        let excess = self.history.len().saturating_sub(self.history_capacity);
+        self.history.drain(..excess);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 1kHz mono gate with 2 frames of pre-roll and 3 of post-roll
+    fn gate() -> Gate {
+        let config = VadRecordingConfig {
+            enabled: true,
+            pre_roll_ms: 2,
+            post_roll_ms: 3,
+            ..Default::default()
+        };
+        Gate::new(&config, 1000, 1)
+    }
+
+    #[test]
+    fn test_gate_keeps_lookback_pre_roll_and_post_roll() {
+        let mut gate = gate();
+        let none = SpeechStateChange::None;
+        assert_eq!(
+            gate.process(&[1.0, 2.0, 3.0, 4.0], &none),
+            GateOutput::default()
+        );
+
+        let started = gate.process(
+            &[5.0, 6.0],
+            &SpeechStateChange::Started {
+                lookback_samples: 1,
+            },
+        );
+        assert!(started.started);
+        assert_eq!(started.audio, [4.0, 5.0, 6.0]);
+
+        assert_eq!(gate.process(&[7.0], &none).audio, [7.0]);
+        let ended = gate.process(&[8.0], &SpeechStateChange::Ended { duration_ms: 4 });
+        assert_eq!((ended.audio.as_slice(), ended.ended), (&[8.0][..], false));
+
+        let trailing = gate.process(&[9.0, 10.0, 11.0, 12.0], &none);
+        assert_eq!(
+            (trailing.audio.as_slice(), trailing.ended),
+            (&[9.0, 10.0, 11.0][..], true)
+        );
+
+        // Audio after the post-roll is available as pre-roll for the next utterance
+        let next = gate.process(
+            &[13.0],
+            &SpeechStateChange::Started {
+                lookback_samples: 0,
+            },
+        );
+        assert_eq!(next.audio, [12.0, 13.0]);
+    }
+
+    #[test]
+    fn test_gate_continues_utterance_resumed_in_post_roll() {
+        let mut gate = gate();
+        gate.process(
+            &[1.0],
+            &SpeechStateChange::Started {
+                lookback_samples: 0,
+            },
+        );
+        gate.process(&[2.0], &SpeechStateChange::Ended { duration_ms: 1 });
+        gate.process(&[3.0], &SpeechStateChange::None);
+
+        let resumed = gate.process(
+            &[4.0],
+            &SpeechStateChange::Started {
+                lookback_samples: 5,
+            },
+        );
+        assert!(!resumed.started && !resumed.ended);
+        assert_eq!(resumed.audio, [4.0]);
+        assert_eq!(gate.phase, Phase::Speaking);
+    }
+}
@@ -0,0 +1,178 @@
+//! Watch folders for automatic transcription of recordings.
+//!
+//! A background thread polls each watched directory. Audio files that appear
+//! after the folder was added are queued for transcription once their size
+//! stops changing, and the transcript is written next to the file as `.txt`
+//! and `.srt` sidecars.
+
+use flowstt_common::export;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::audio::read_wav;
+use crate::ipc::handlers::get_transcription_queue;
+use crate::transcription::queue::QueuedSegment;
+use crate::transcription::{Transcript, NO_SPEECH_TEXT};
+
+/// How often watched folders are scanned
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// File extensions that are picked up (lowercase)
+const AUDIO_EXTENSIONS: &[&str] = &["wav"];
+
+/// A watched directory and the files it contained.
+struct WatchedFolder {
+    path: PathBuf,
+    /// Files already queued, or present when the folder was added
+    seen: HashSet<PathBuf>,
+    /// New files waiting for their size to settle, with the last size seen
+    pending: HashMap<PathBuf, u64>,
+}
+
+impl WatchedFolder {
+    /// Start watching a folder. Files already in it are not transcribed.
+    fn new(path: PathBuf) -> Self {
+        let seen = audio_files(&path).into_iter().map(|(p, _)| p).collect();
+        Self {
+            path,
+            seen,
+            pending: HashMap::new(),
+        }
+    }
+
+    /// Scan for new files and queue those that have finished being written.
+    fn poll(&mut self) {
+        for (file, size) in audio_files(&self.path) {
+            if self.seen.contains(&file) {
+                continue;
+            }
+
+            // Wait until the size is unchanged between two scans
+            match self.pending.insert(file.clone(), size) {
+                Some(previous) if previous == size && size > 0 => {}
+                _ => continue,
+            }
+
+            match read_wav(&file) {
+                Ok(audio) => {
+                    let queued = get_transcription_queue().enqueue(QueuedSegment {
+                        samples: audio.samples,
+                        sample_rate: audio.sample_rate,
+                        channels: audio.channels,
+                        wav_path: None,
+                        is_partial: false,
+                        source_file: Some(file.clone()),
+                        output: None,
+                    });
+                    if !queued {
+                        // Queue is full, try again on the next scan
+                        continue;
+                    }
+                    info!("[Watch] Queued {:?} for transcription", file);
+                }
+                Err(e) => warn!("[Watch] Skipping {:?}: {}", file, e),
+            }
+
+            self.pending.remove(&file);
+            self.seen.insert(file);
+        }
+    }
+}
+
+/// List audio files in a directory with their sizes.
+fn audio_files(dir: &Path) -> Vec<(PathBuf, u64)> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            warn!("[Watch] Failed to read {:?}: {}", dir, e);
+            return Vec::new();
+        }
+    };
+
+    entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().map(|t| t.is_file()).unwrap_or(false))
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension()
+                .and_then(|ext| ext.to_str())
+                .map(|ext| AUDIO_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+                .unwrap_or(false)
+        })
+        .filter_map(|path| {
+            let size = fs::metadata(&path).ok()?.len();
+            Some((path, size))
+        })
+        .collect()
+}
+
+/// Watched folders
+static FOLDERS: OnceLock<Mutex<Vec<WatchedFolder>>> = OnceLock::new();
+
+/// Whether the watcher thread has been started
+static WATCHER_STARTED: AtomicBool = AtomicBool::new(false);
+
+fn get_folders() -> &'static Mutex<Vec<WatchedFolder>> {
+    FOLDERS.get_or_init(|| Mutex::new(Vec::new()))
+}
+
+/// Replace the set of watched folders.
+///
+/// Folders that were already watched keep their state; newly added folders
+/// only pick up files created from now on.
+pub fn set_folders(paths: &[PathBuf]) {
+    let mut folders = get_folders().lock().unwrap();
+    folders.retain(|f| paths.contains(&f.path));
+    for path in paths {
+        if !folders.iter().any(|f| &f.path == path) {
+            info!("[Watch] Watching {:?}", path);
+            folders.push(WatchedFolder::new(path.clone()));
+        }
+    }
+}
+
+/// Start the background thread that polls watched folders.
+pub fn start_watcher() {
+    if WATCHER_STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    thread::spawn(|| {
+        while !crate::is_shutdown_requested() {
+            for folder in get_folders().lock().unwrap().iter_mut() {
+                folder.poll();
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+    });
+}
+
+/// Write the `.txt` and `.srt` sidecars for a transcribed file.
+pub fn write_sidecars(audio_path: &Path, transcript: &Transcript) {
+    let text = if transcript.text == NO_SPEECH_TEXT {
+        ""
+    } else {
+        transcript.text.as_str()
+    };
+    let cues = export::cues_from_words(text, &transcript.words, transcript.duration_ms);
+
+    let sidecars = [
+        (audio_path.with_extension("txt"), format!("{}\n", text)),
+        (
+            audio_path.with_extension("srt"),
+            export::to_srt(&cues, &crate::chapters::detect(transcript)),
+        ),
+    ];
+    for (path, contents) in sidecars {
+        match fs::write(&path, contents) {
+            Ok(()) => info!("[Watch] Wrote {:?}", path),
+            Err(e) => warn!("[Watch] Failed to write {:?}: {}", path, e),
+        }
+    }
+}
@@ -0,0 +1,273 @@
+//! Webhook notifications of session lifecycle events.
+//!
+//! Every configured webhook gets a JSON POST for the events it subscribes to:
+//!
+//! - `session_started` when capture starts, with the capture session's ID
+//! - `session_ended` once capture stops and the rest of its speech is
+//!   transcribed, with the same ID, the combined text, the IDs of the saved
+//!   transcriptions, the text formatted with the output template if one is set
+//!   and, when `http_public_url` is configured, the session's URL
+//! - `error` when capture or transcription fails
+//!
+//! Failed deliveries are retried with exponential backoff; client errors other
+//! than 408 and 429 are not retried, since sending the same body again won't
+//! help. With a secret set, the body is signed with HMAC-SHA256 and the hex
+//! digest sent as `X-FlowSTT-Signature: sha256=<digest>`.
+
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use chrono::{DateTime, Local};
+use flowstt_common::ipc::{EventType, Response};
+use flowstt_common::{CaptureSession, TranscriptSession};
+use hmac::{Hmac, Mac};
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+use sha2::Sha256;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{debug, warn};
+
+use crate::config::{RedactionChannel, WebhookConfig, WebhookEvent};
+use crate::ipc::get_event_sender;
+use crate::output::{self, Segment};
+use crate::redact;
+
+/// Longest a delivery attempt may take
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Wait before the first retry, doubled for each one after it
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Longest wait between retries
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+
+/// Header carrying the body's signature
+const SIGNATURE_HEADER: &str = "X-FlowSTT-Signature";
+
+/// Header naming the event, so receivers can route without parsing the body
+const EVENT_HEADER: &str = "X-FlowSTT-Event";
+
+/// Configured webhooks and the base URL capture session links point at
+static CONFIG: Mutex<(Vec<WebhookConfig>, Option<String>)> = Mutex::new((Vec::new(), None));
+
+/// Replace the configured webhooks; `public_url` is where receivers reach the
+/// HTTP API, if it is exposed to them.
+pub fn set_config(webhooks: &[WebhookConfig], public_url: Option<&str>) {
+    let webhooks = webhooks
+        .iter()
+        .filter(|webhook| {
+            let valid = webhook.url.starts_with("http://") || webhook.url.starts_with("https://");
+            if !valid {
+                warn!("Ignoring webhook with invalid URL {:?}", webhook.url);
+            }
+            valid
+        })
+        .cloned()
+        .collect();
+    *CONFIG.lock().unwrap() = (webhooks, public_url.map(str::to_string));
+}
+
+/// Send `session_started` for the capture session `id`.
+pub fn session_started(id: &str, started_at: DateTime<Local>) {
+    let webhooks = CONFIG.lock().unwrap().0.clone();
+    let data = json!({
+        "id": id,
+        "started_at": started_at.to_rfc3339(),
+    });
+    send(&webhooks, WebhookEvent::SessionStarted, data);
+}
+
+/// Send `session_ended` for a finished capture session and its combined
+/// transcript.
+pub fn session_ended(capture: &CaptureSession, session: &TranscriptSession) {
+    let (webhooks, public_url) = CONFIG.lock().unwrap().clone();
+    if webhooks.is_empty() {
+        return;
+    }
+    let mut session = session.clone();
+    redact::session(RedactionChannel::Hooks, &mut session);
+
+    let mut data = json!({
+        "id": capture.id,
+        "started_at": capture.started_at,
+        "ended_at": capture.ended_at,
+        "duration_ms": capture.duration_ms,
+        "text": session.text,
+        "title": session.metadata.title,
+        "session_ids": capture.session_ids,
+        "formatted": output::format(&Segment::from_session(&session)),
+    });
+    if let Some(base) = public_url {
+        // Captures without transcriptions aren't recorded, so have no URL
+        data["transcript_url"] = if capture.session_ids.is_empty() {
+            Value::Null
+        } else {
+            json!(capture_url(&base, &capture.id))
+        };
+    }
+    send(&webhooks, WebhookEvent::SessionEnded, data);
+}
+
+/// Where the HTTP API at `base` serves the capture session `id`.
+fn capture_url(base: &str, id: &str) -> String {
+    format!("{}/captures/{}", base.trim_end_matches('/'), id)
+}
+
+/// Send `error` for service events until shutdown.
+pub async fn run() {
+    let mut events = get_event_sender().subscribe();
+
+    while !crate::is_shutdown_requested() {
+        let event = match events.recv().await {
+            Ok(Response::Event { event }) => event,
+            Ok(_) => continue,
+            // Visualization data can outpace us; the events we want are rare
+            Err(RecvError::Lagged(_)) => continue,
+            Err(RecvError::Closed) => break,
+        };
+        let (event, data) = match event {
+            EventType::Error { message, .. }
+            | EventType::CaptureStateChanged {
+                error: Some(message),
+                ..
+            } => (WebhookEvent::Error, json!({ "message": message })),
+            _ => continue,
+        };
+        let webhooks = CONFIG.lock().unwrap().0.clone();
+        send(&webhooks, event, data);
+    }
+}
+
+/// Deliver `event` to the webhooks subscribed to it, each on its own thread so
+/// a slow or failing endpoint doesn't hold up the others.
+fn send(webhooks: &[WebhookConfig], event: WebhookEvent, data: Value) {
+    let body = json!({
+        "event": event,
+        "timestamp": chrono::Local::now().to_rfc3339(),
+        "data": data,
+    })
+    .to_string();
+
+    for webhook in webhooks.iter().filter(|webhook| webhook.subscribes(event)) {
+        let webhook = webhook.clone();
+        let body = body.clone();
+        thread::spawn(move || match deliver(&webhook, event, &body) {
+            Ok(()) => debug!("Sent {} webhook to {}", event.name(), webhook.url),
+            Err(e) => warn!(
+                "Giving up on {} webhook to {}: {}",
+                event.name(),
+                webhook.url,
+                e
+            ),
+        });
+    }
+}
+
+/// POST `body` to the webhook, retrying failures up to its retry limit.
+fn deliver(webhook: &WebhookConfig, event: WebhookEvent, body: &str) -> Result<(), String> {
+    let client = reqwest::blocking::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+    let mut attempt = 0;
+    loop {
+        let mut request = client
+            .post(&webhook.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(EVENT_HEADER, event.name())
+            .body(body.to_string());
+        if let Some(secret) = &webhook.secret {
+            request = request.header(SIGNATURE_HEADER, signature(secret, body));
+        }
+
+        let (error, retryable) = match request.send() {
+            Ok(response) if response.status().is_success() => return Ok(()),
+            Ok(response) => (
+                format!("Server returned {}", response.status()),
+                is_retryable(response.status()),
+            ),
+            Err(e) => (format!("Request failed: {}", e), true),
+        };
+        if !retryable || attempt >= webhook.max_retries {
+            return Err(error);
+        }
+        let delay = backoff(attempt);
+        debug!(
+            "{} webhook to {} failed ({}), retrying in {}s",
+            event.name(),
+            webhook.url,
+            error,
+            delay.as_secs()
+        );
+        thread::sleep(delay);
+        attempt += 1;
+    }
+}
+
+/// Whether a request answered with `status` is worth sending again.
+fn is_retryable(status: StatusCode) -> bool {
+    status.is_server_error()
+        || status == StatusCode::REQUEST_TIMEOUT
+        || status == StatusCode::TOO_MANY_REQUESTS
+}
+
+/// Wait before retry number `attempt` (from 0).
+fn backoff(attempt: u32) -> Duration {
+    INITIAL_BACKOFF
+        .saturating_mul(2u32.saturating_pow(attempt))
+        .min(MAX_BACKOFF)
+}
+
+/// `sha256=` and the hex HMAC-SHA256 of `body` keyed with `secret`.
+fn signature(secret: &str, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC takes keys of any size");
+    mac.update(body.as_bytes());
+    let digest = mac.finalize().into_bytes();
+    let hex: String = digest.iter().map(|byte| format!("{:02x}", byte)).collect();
+    format!("sha256={}", hex)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signature() {
+        // RFC 4231 test case 2
+        assert_eq!(
+            signature("Jefe", "what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn test_capture_url() {
+        assert_eq!(
+            capture_url("https://stt.example.com/", "20260302-100000-000"),
+            "https://stt.example.com/captures/20260302-100000-000"
+        );
+        assert_eq!(
+            capture_url("http://192.168.1.20:7878", "x"),
+            "http://192.168.1.20:7878/captures/x"
+        );
+    }
+
+    #[test]
+    fn test_backoff_doubles_up_to_the_limit() {
+        assert_eq!(backoff(0), Duration::from_secs(1));
+        assert_eq!(backoff(3), Duration::from_secs(8));
+        assert_eq!(backoff(20), MAX_BACKOFF);
+        assert_eq!(backoff(u32::MAX), MAX_BACKOFF);
+    }
+
+    #[test]
+    fn test_only_transient_failures_are_retried() {
+        assert!(is_retryable(StatusCode::BAD_GATEWAY));
+        assert!(is_retryable(StatusCode::TOO_MANY_REQUESTS));
+        assert!(!is_retryable(StatusCode::NOT_FOUND));
+        assert!(!is_retryable(StatusCode::UNAUTHORIZED));
+    }
+}
@@ -4,7 +4,8 @@
 //! It handles connection management, service auto-spawn, and event forwarding.
 
 use flowstt_common::ipc::{
-    get_socket_path, read_json, write_json, EventType, IpcError, Request, Response,
+    get_socket_path, read_json, write_json, Capability, EventType, IpcError, Request, Response,
+    MIN_PROTOCOL_VERSION, PROTOCOL_VERSION,
 };
 use std::path::PathBuf;
 use std::process::{Command, Stdio};
@@ -19,12 +20,17 @@ pub struct IpcClient {
     stream: Option<tokio::net::UnixStream>,
     #[cfg(windows)]
     stream: Option<tokio::net::windows::named_pipe::NamedPipeClient>,
+    /// Optional features of the connected service
+    capabilities: Vec<Capability>,
 }
 
 impl IpcClient {
     /// Create a new client (not connected).
     pub fn new() -> Self {
-        Self { stream: None }
+        Self {
+            stream: None,
+            capabilities: Vec::new(),
+        }
     }
 
     /// Check if the client is connected.
@@ -54,9 +60,40 @@ impl IpcClient {
             self.stream = Some(stream);
         }
 
+        if let Err(e) = self.handshake().await {
+            self.stream = None;
+            return Err(e);
+        }
         Ok(())
     }
 
+    /// Agree on a protocol version; the service refuses other requests until then.
+    async fn handshake(&mut self) -> Result<(), IpcError> {
+        let hello = Request::Hello {
+            protocol_version: PROTOCOL_VERSION,
+        };
+        match self.request(hello).await? {
+            Response::Welcome {
+                protocol_version,
+                capabilities,
+                ..
+            } if protocol_version >= MIN_PROTOCOL_VERSION => {
+                self.capabilities = capabilities;
+                Ok(())
+            }
+            Response::Welcome {
+                protocol_version,
+                service_version,
+                ..
+            } => Err(IpcError::ParseError(format!(
+                "Service {} speaks protocol version {}, which is no longer supported",
+                service_version, protocol_version
+            ))),
+            Response::Error { message, .. } => Err(IpcError::ParseError(message)),
+            _ => Err(IpcError::ParseError("Unexpected handshake response".into())),
+        }
+    }
+
     /// Disconnect from the service.
     #[allow(dead_code)]
     pub fn disconnect(&mut self) {
@@ -88,6 +125,11 @@ impl IpcClient {
         ))
     }
 
+    /// Whether the connected service supports `capability`.
+    pub fn supports(&self, capability: Capability) -> bool {
+        self.capabilities.contains(&capability)
+    }
+
     /// Send a request and receive a response.
     pub async fn request(&mut self, request: Request) -> Result<Response, IpcError> {
         // Ensure we're connected
@@ -95,6 +137,13 @@ impl IpcClient {
             self.connect_or_spawn().await?;
         }
 
+        // Don't send what the service wouldn't understand
+        if let Some(capability) = Capability::required_by(&request) {
+            if !self.supports(capability) {
+                return Err(IpcError::Unsupported(capability));
+            }
+        }
+
         #[cfg(unix)]
         {
             let stream = self
@@ -152,7 +201,7 @@ impl IpcClient {
                 Response::Event { event } => {
                     forward_event_to_tauri(&app_handle, event);
                 }
-                Response::Error { message } => {
+                Response::Error { message, .. } => {
                     eprintln!("[IpcClient] Event stream error: {}", message);
                     break;
                 }
@@ -173,9 +222,38 @@ fn forward_event_to_tauri(app_handle: &AppHandle, event: EventType) {
             // Emit visualization data to frontend
             let _ = app_handle.emit("visualization-data", &data);
         }
+        EventType::Levels(levels) => {
+            let _ = app_handle.emit("levels", &levels);
+        }
+        EventType::SourceFormat(format) => {
+            let _ = app_handle.emit("source-format", &format);
+        }
+        EventType::AecMetrics(metrics) => {
+            let _ = app_handle.emit("aec-metrics", &metrics);
+        }
         EventType::TranscriptionComplete(result) => {
             let _ = app_handle.emit("transcription-complete", &result.text);
         }
+        EventType::TranscriptionPartial(result) => {
+            let _ = app_handle.emit("transcription-partial", &result.text);
+        }
+        EventType::TranscriptionToken { text } => {
+            let _ = app_handle.emit("transcription-token", &text);
+        }
+        EventType::TranscriptionCancelled { discarded } => {
+            let _ = app_handle.emit("transcription-cancelled", discarded);
+        }
+        EventType::DictationUpdated { text } => {
+            let _ = app_handle.emit("dictation-updated", &text);
+        }
+        EventType::CommandDetected { phrase, text } => {
+            #[derive(serde::Serialize, Clone)]
+            struct CommandDetected {
+                phrase: String,
+                text: String,
+            }
+            let _ = app_handle.emit("command-detected", CommandDetected { phrase, text });
+        }
         EventType::SpeechStarted => {
             let _ = app_handle.emit("speech-started", ());
         }
@@ -183,6 +261,7 @@ fn forward_event_to_tauri(app_handle: &AppHandle, event: EventType) {
             let _ = app_handle.emit("speech-ended", duration_ms);
         }
         EventType::CaptureStateChanged { capturing, error } => {
+            crate::tray::update(app_handle, |t| t.capturing = capturing);
             #[derive(serde::Serialize, Clone)]
             struct CaptureState {
                 capturing: bool,
@@ -190,21 +269,77 @@ fn forward_event_to_tauri(app_handle: &AppHandle, event: EventType) {
             }
             let _ = app_handle.emit("capture-state-changed", CaptureState { capturing, error });
         }
+        EventType::ControllerChanged { controller } => {
+            #[derive(serde::Serialize, Clone)]
+            struct ControllerChanged {
+                controller: Option<String>,
+                is_self: bool,
+            }
+            let is_self = controller.as_deref() == Some(crate::CLIENT_NAME);
+            let _ = app_handle.emit(
+                "controller-changed",
+                ControllerChanged {
+                    controller,
+                    is_self,
+                },
+            );
+        }
+        EventType::ProfileChanged { name } => {
+            let _ = app_handle.emit("profile-changed", name);
+        }
+        EventType::PlaybackPosition(status) => {
+            let _ = app_handle.emit("playback-position", status);
+        }
+        EventType::PlaybackStopped { session_id } => {
+            let _ = app_handle.emit("playback-stopped", session_id);
+        }
+        EventType::SessionSummarized { id, summary } => {
+            #[derive(serde::Serialize, Clone)]
+            struct SessionSummarized {
+                id: String,
+                summary: String,
+            }
+            let _ = app_handle.emit("session-summarized", SessionSummarized { id, summary });
+        }
+        EventType::PrivacyMode { muted } => {
+            crate::tray::update(app_handle, |t| t.muted = muted);
+            let _ = app_handle.emit("privacy-mode", muted);
+        }
+        EventType::SourceSwitched { previous, current } => {
+            #[derive(serde::Serialize, Clone)]
+            struct SourceSwitched {
+                previous: String,
+                current: String,
+            }
+            let _ = app_handle.emit("source-switched", SourceSwitched { previous, current });
+        }
         EventType::ModelDownloadProgress { percent } => {
             let _ = app_handle.emit("model-download-progress", percent);
         }
         EventType::ModelDownloadComplete { success } => {
             let _ = app_handle.emit("model-download-complete", success);
         }
+        EventType::JobProgress(job) => {
+            let _ = app_handle.emit("job-progress", job);
+        }
         EventType::PttPressed => {
             let _ = app_handle.emit("ptt-pressed", ());
         }
         EventType::PttReleased => {
             let _ = app_handle.emit("ptt-released", ());
         }
+        EventType::FeedbackCue { cue } => {
+            let _ = app_handle.emit("feedback-cue", cue);
+        }
         EventType::TranscriptionModeChanged { mode } => {
+            crate::tray::update(app_handle, |t| {
+                t.push_to_talk = mode == flowstt_common::TranscriptionMode::PushToTalk
+            });
             let _ = app_handle.emit("transcription-mode-changed", mode);
         }
+        EventType::Error { message, .. } => {
+            let _ = app_handle.emit("service-error", &message);
+        }
         EventType::Shutdown => {
             let _ = app_handle.emit("service-shutdown", ());
         }
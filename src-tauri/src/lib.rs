@@ -4,10 +4,19 @@
 //! All audio capture and transcription is handled by the service via IPC.
 
 mod ipc_client;
-
-use flowstt_common::ipc::{Request, Response};
-use flowstt_common::{AudioDevice, KeyCode, RecordingMode, TranscriptionMode};
+mod tray;
+
+use flowstt_common::export::{self, ExportFormat};
+use flowstt_common::ipc::{ErrorCode, Request, Response};
+use flowstt_common::{
+    AecSettings, AudioDevice, ChannelSelection, DateRange, KeyCode, NoiseCalibration, OutputMode,
+    Permission, Permissions, PlaybackStatus, RecordingMode, RetranscribeJob, SearchHit,
+    SessionFilter, SessionMetadata, SessionStats, SessionSummary, SpectrogramSettings,
+    SpeechDetectorConfig, SystemAudioSupport, TranscriptSession, TranscriptionMode, WordTiming,
+    MIN_CALIBRATION_SECS,
+};
 use ipc_client::{IpcClient, SharedIpcClient};
+use std::collections::BTreeMap;
 use std::env;
 use std::sync::Arc;
 use tauri::{AppHandle, State};
@@ -42,8 +51,16 @@ struct AppState {
     ipc: SharedIpcClient,
     /// Handle to the event forwarding task
     event_task_running: Arc<Mutex<bool>>,
+    /// What the system tray shows
+    tray: std::sync::Mutex<tray::TrayState>,
 }
 
+/// Launch flag that keeps the main window hidden, leaving only the tray icon
+const START_MINIMIZED_ARG: &str = "--minimized";
+
+/// Name the app takes control of capture under
+pub(crate) const CLIENT_NAME: &str = "FlowSTT app";
+
 /// Helper to send a request to the service and handle errors.
 async fn send_request(ipc: &SharedIpcClient, request: Request) -> Result<Response, String> {
     let mut client = ipc.client.lock().await;
@@ -53,37 +70,82 @@ async fn send_request(ipc: &SharedIpcClient, request: Request) -> Result<Respons
         .map_err(|e| format!("IPC error: {}", e))
 }
 
+/// Take control of capture before starting it, so other clients can't drive
+/// capture the app started.
+async fn acquire_control(ipc: &SharedIpcClient) -> Result<(), String> {
+    let request = Request::AcquireControl {
+        client_name: CLIENT_NAME.into(),
+        force: false,
+    };
+    match send_request(ipc, request).await? {
+        Response::Ok => Ok(()),
+        Response::Error { message, .. } => Err(message),
+        _ => Err("Unexpected response".into()),
+    }
+}
+
 /// List all available audio sources (both input devices and system audio monitors)
 #[tauri::command]
-async fn list_all_sources(state: State<'_, AppState>) -> Result<Vec<AudioDevice>, String> {
+async fn list_all_sources(
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<Vec<AudioDevice>, String> {
     let response = send_request(&state.ipc, Request::ListDevices { source_type: None }).await?;
 
     match response {
-        Response::Devices { devices } => Ok(devices),
-        Response::Error { message } => Err(message),
+        Response::Devices { devices } => {
+            tray::update(&app_handle, |t| t.set_devices(&devices));
+            Ok(devices)
+        }
+        Response::Error { message, .. } => Err(message),
+        _ => Err("Unexpected response".into()),
+    }
+}
+
+/// How system audio can be captured, with setup guidance when the platform can't
+#[tauri::command]
+async fn get_system_audio_support(
+    state: State<'_, AppState>,
+) -> Result<SystemAudioSupport, String> {
+    let response = send_request(&state.ipc, Request::GetSystemAudioSupport).await?;
+
+    match response {
+        Response::SystemAudioSupport(support) => Ok(support),
+        Response::Error { message, .. } => Err(message),
         _ => Err("Unexpected response".into()),
     }
 }
 
-/// Set audio sources - capture starts automatically when valid sources are configured
+/// Set audio sources - capture starts automatically when valid sources are configured.
+/// Channel selections are remembered for the sources' devices.
 #[tauri::command]
 async fn set_sources(
     source1_id: Option<String>,
     source2_id: Option<String>,
+    source1_channels: Option<ChannelSelection>,
+    source2_channels: Option<ChannelSelection>,
     state: State<'_, AppState>,
     app_handle: AppHandle,
 ) -> Result<(), String> {
+    if source1_id.is_some() {
+        acquire_control(&state.ipc).await?;
+    }
     let response = send_request(
         &state.ipc,
         Request::SetSources {
-            source1_id,
-            source2_id,
+            source1_id: source1_id.clone(),
+            source2_id: source2_id.clone(),
+            source1_channels,
+            source2_channels,
         },
     )
     .await?;
 
     match response {
         Response::Ok => {
+            if source1_id.is_some() {
+                tray::update(&app_handle, |t| t.set_sources(source1_id, source2_id));
+            }
             // Start event forwarding if not already running
             start_event_forwarding(
                 state.ipc.clone(),
@@ -93,19 +155,26 @@ async fn set_sources(
             .await;
             Ok(())
         }
-        Response::Error { message } => Err(message),
+        Response::Error { message, .. } => Err(message),
         _ => Err("Unexpected response".into()),
     }
 }
 
 /// Set echo cancellation enabled/disabled
 #[tauri::command]
-async fn set_aec_enabled(enabled: bool, state: State<'_, AppState>) -> Result<(), String> {
+async fn set_aec_enabled(
+    enabled: bool,
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<(), String> {
     let response = send_request(&state.ipc, Request::SetAecEnabled { enabled }).await?;
 
     match response {
-        Response::Ok => Ok(()),
-        Response::Error { message } => Err(message),
+        Response::Ok => {
+            tray::update(&app_handle, |t| t.aec_enabled = enabled);
+            Ok(())
+        }
+        Response::Error { message, .. } => Err(message),
         _ => Err("Unexpected response".into()),
     }
 }
@@ -117,7 +186,7 @@ async fn set_recording_mode(mode: RecordingMode, state: State<'_, AppState>) ->
 
     match response {
         Response::Ok => Ok(()),
-        Response::Error { message } => Err(message),
+        Response::Error { message, .. } => Err(message),
         _ => Err("Unexpected response".into()),
     }
 }
@@ -132,7 +201,7 @@ async fn check_model_status(state: State<'_, AppState>) -> Result<LocalModelStat
             available: status.available,
             path: status.path,
         }),
-        Response::Error { message } => Err(message),
+        Response::Error { message, .. } => Err(message),
         _ => Err("Unexpected response".into()),
     }
 }
@@ -140,11 +209,11 @@ async fn check_model_status(state: State<'_, AppState>) -> Result<LocalModelStat
 /// Download the Whisper model
 #[tauri::command]
 async fn download_model(state: State<'_, AppState>) -> Result<(), String> {
-    let response = send_request(&state.ipc, Request::DownloadModel).await?;
+    let response = send_request(&state.ipc, Request::DownloadModel { name: None }).await?;
 
     match response {
         Response::Ok => Ok(()),
-        Response::Error { message } => Err(message),
+        Response::Error { message, .. } => Err(message),
         _ => Err("Unexpected response".into()),
     }
 }
@@ -175,7 +244,411 @@ async fn get_cuda_status(state: State<'_, AppState>) -> Result<LocalCudaStatus,
             runtime_available: status.runtime_available,
             system_info: status.system_info,
         }),
-        Response::Error { message } => Err(message),
+        Response::Error { message, .. } => Err(message),
+        _ => Err("Unexpected response".into()),
+    }
+}
+
+/// Get the access the service has to the microphone, screen recording and accessibility
+#[tauri::command]
+async fn get_permissions(state: State<'_, AppState>) -> Result<Permissions, String> {
+    let response = send_request(&state.ipc, Request::GetPermissions).await?;
+
+    match response {
+        Response::Permissions(permissions) => Ok(permissions),
+        Response::Error { message, .. } => Err(message),
+        _ => Err("Unexpected response".into()),
+    }
+}
+
+/// Show the system prompt for a permission; resolves once the user answers
+#[tauri::command]
+async fn request_permission(
+    permission: Permission,
+    state: State<'_, AppState>,
+) -> Result<Permissions, String> {
+    let response = send_request(&state.ipc, Request::RequestPermission { permission }).await?;
+
+    match response {
+        Response::Permissions(permissions) => Ok(permissions),
+        Response::Error { message, .. } => Err(message),
+        _ => Err("Unexpected response".into()),
+    }
+}
+
+/// Export a saved transcription, or the combined transcript of a capture
+/// session, in the given format and return the file contents.
+#[tauri::command]
+async fn export_session(
+    id: String,
+    format: ExportFormat,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let response = send_request(&state.ipc, Request::GetSession { id: id.clone() }).await?;
+    let response = match response {
+        Response::Error {
+            code: ErrorCode::SessionNotFound { .. },
+            ..
+        } => send_request(&state.ipc, Request::GetCaptureTranscript { id }).await?,
+        response => response,
+    };
+
+    match response {
+        Response::Session(session) => Ok(export::export_session(&session, format)),
+        Response::Error { message, .. } => Err(message),
+        _ => Err("Unexpected response".into()),
+    }
+}
+
+/// Load a saved transcription for the editor
+#[tauri::command]
+async fn get_session(id: String, state: State<'_, AppState>) -> Result<TranscriptSession, String> {
+    let response = send_request(&state.ipc, Request::GetSession { id }).await?;
+
+    match response {
+        Response::Session(session) => Ok(*session),
+        Response::Error { message, .. } => Err(message),
+        _ => Err("Unexpected response".into()),
+    }
+}
+
+/// Talk time, pace and speaker shares of a saved transcription or capture
+/// session
+#[tauri::command]
+async fn get_session_stats(id: String, state: State<'_, AppState>) -> Result<SessionStats, String> {
+    let response = send_request(&state.ipc, Request::GetSessionStats { id }).await?;
+
+    match response {
+        Response::SessionStats(stats) => Ok(stats),
+        Response::Error { message, .. } => Err(message),
+        _ => Err("Unexpected response".into()),
+    }
+}
+
+/// Save corrections made in the editor back to the history store
+#[tauri::command]
+async fn update_session(
+    id: String,
+    text: String,
+    words: Option<Vec<WordTiming>>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let response = send_request(&state.ipc, Request::UpdateSession { id, text, words }).await?;
+
+    match response {
+        Response::Ok => Ok(()),
+        Response::Error { message, .. } => Err(message),
+        _ => Err("Unexpected response".into()),
+    }
+}
+
+/// Set a saved transcription's title, tags, participants and notes
+#[tauri::command]
+async fn set_session_metadata(
+    id: String,
+    metadata: SessionMetadata,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let response = send_request(&state.ipc, Request::SetSessionMetadata { id, metadata }).await?;
+
+    match response {
+        Response::Ok => Ok(()),
+        Response::Error { message, .. } => Err(message),
+        _ => Err("Unexpected response".into()),
+    }
+}
+
+/// List saved transcriptions, newest first, optionally only those whose
+/// metadata matches `filter`
+#[tauri::command]
+async fn list_sessions(
+    limit: Option<usize>,
+    filter: Option<SessionFilter>,
+    state: State<'_, AppState>,
+) -> Result<Vec<SessionSummary>, String> {
+    let request = Request::ListSessions {
+        limit,
+        filter: filter.unwrap_or_default(),
+    };
+    let response = send_request(&state.ipc, request).await?;
+
+    match response {
+        Response::Sessions { sessions } => Ok(sessions),
+        Response::Error { message, .. } => Err(message),
+        _ => Err("Unexpected response".into()),
+    }
+}
+
+/// Search saved transcriptions for sessions containing every word of `query`,
+/// best matches first
+#[tauri::command]
+async fn search_transcripts(
+    query: String,
+    date_range: Option<DateRange>,
+    limit: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<Vec<SearchHit>, String> {
+    let request = Request::SearchTranscripts {
+        query,
+        date_range,
+        limit,
+    };
+    let response = send_request(&state.ipc, request).await?;
+
+    match response {
+        Response::SearchResults { hits } => Ok(hits),
+        Response::Error { message, .. } => Err(message),
+        _ => Err("Unexpected response".into()),
+    }
+}
+
+/// Transcribe saved transcriptions again with the configured model in the
+/// background; progress arrives as `job-progress` events
+#[tauri::command]
+async fn retranscribe_sessions(
+    ids: Vec<String>,
+    state: State<'_, AppState>,
+) -> Result<RetranscribeJob, String> {
+    let response = send_request(&state.ipc, Request::RetranscribeSessions { ids }).await?;
+
+    match response {
+        Response::Job(job) => Ok(job),
+        Response::Error { message, .. } => Err(message),
+        _ => Err("Unexpected response".into()),
+    }
+}
+
+/// List re-transcription jobs, oldest first
+#[tauri::command]
+async fn list_jobs(state: State<'_, AppState>) -> Result<Vec<RetranscribeJob>, String> {
+    let response = send_request(&state.ipc, Request::ListJobs).await?;
+
+    match response {
+        Response::Jobs { jobs } => Ok(jobs),
+        Response::Error { message, .. } => Err(message),
+        _ => Err("Unexpected response".into()),
+    }
+}
+
+/// Cancel a re-transcription job
+#[tauri::command]
+async fn cancel_job(id: String, state: State<'_, AppState>) -> Result<(), String> {
+    let response = send_request(&state.ipc, Request::CancelJob { id }).await?;
+
+    match response {
+        Response::Ok => Ok(()),
+        Response::Error { message, .. } => Err(message),
+        _ => Err("Unexpected response".into()),
+    }
+}
+
+/// Play a saved transcription's recording; progress arrives as
+/// `playback-position` events
+#[tauri::command]
+async fn play_session(
+    id: String,
+    position_ms: u64,
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<PlaybackStatus, String> {
+    let response = send_request(&state.ipc, Request::PlaySession { id, position_ms }).await?;
+
+    match response {
+        Response::Playback(status) => {
+            start_event_forwarding(
+                state.ipc.clone(),
+                app_handle,
+                state.event_task_running.clone(),
+            )
+            .await;
+            Ok(status)
+        }
+        Response::Error { message, .. } => Err(message),
+        _ => Err("Unexpected response".into()),
+    }
+}
+
+/// Jump to a position in the recording being played
+#[tauri::command]
+async fn seek_playback(
+    position_ms: u64,
+    state: State<'_, AppState>,
+) -> Result<PlaybackStatus, String> {
+    let response = send_request(&state.ipc, Request::SeekPlayback { position_ms }).await?;
+
+    match response {
+        Response::Playback(status) => Ok(status),
+        Response::Error { message, .. } => Err(message),
+        _ => Err("Unexpected response".into()),
+    }
+}
+
+/// Pause or resume playback
+#[tauri::command]
+async fn pause_playback(
+    paused: bool,
+    state: State<'_, AppState>,
+) -> Result<PlaybackStatus, String> {
+    let response = send_request(&state.ipc, Request::PausePlayback { paused }).await?;
+
+    match response {
+        Response::Playback(status) => Ok(status),
+        Response::Error { message, .. } => Err(message),
+        _ => Err("Unexpected response".into()),
+    }
+}
+
+/// Stop playback
+#[tauri::command]
+async fn stop_playback(state: State<'_, AppState>) -> Result<(), String> {
+    let response = send_request(&state.ipc, Request::StopPlayback).await?;
+
+    match response {
+        Response::Ok => Ok(()),
+        Response::Error { message, .. } => Err(message),
+        _ => Err("Unexpected response".into()),
+    }
+}
+
+/// Get the speech detector thresholds
+#[tauri::command]
+async fn get_vad_config(state: State<'_, AppState>) -> Result<SpeechDetectorConfig, String> {
+    let response = send_request(&state.ipc, Request::GetVadConfig).await?;
+
+    match response {
+        Response::VadConfig(config) => Ok(config),
+        Response::Error { message, .. } => Err(message),
+        _ => Err("Unexpected response".into()),
+    }
+}
+
+/// Set the speech detector thresholds; takes effect immediately
+#[tauri::command]
+async fn set_vad_config(
+    config: SpeechDetectorConfig,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let response = send_request(&state.ipc, Request::SetVadConfig { config }).await?;
+
+    match response {
+        Response::Ok => Ok(()),
+        Response::Error { message, .. } => Err(message),
+        _ => Err("Unexpected response".into()),
+    }
+}
+
+/// Get the echo cancellation tuning
+#[tauri::command]
+async fn get_aec_settings(state: State<'_, AppState>) -> Result<AecSettings, String> {
+    let response = send_request(&state.ipc, Request::GetAecSettings).await?;
+
+    match response {
+        Response::AecSettings(settings) => Ok(settings),
+        Response::Error { message, .. } => Err(message),
+        _ => Err("Unexpected response".into()),
+    }
+}
+
+/// Set the echo cancellation tuning; a running AEC restarts with it
+#[tauri::command]
+async fn set_aec_settings(
+    settings: AecSettings,
+    state: State<'_, AppState>,
+) -> Result<AecSettings, String> {
+    let response = send_request(&state.ipc, Request::SetAecSettings { settings }).await?;
+
+    match response {
+        Response::AecSettings(settings) => Ok(settings),
+        Response::Error { message, .. } => Err(message),
+        _ => Err("Unexpected response".into()),
+    }
+}
+
+/// Get the spectrogram FFT size, window and emit rate
+#[tauri::command]
+async fn get_spectrogram_settings(
+    state: State<'_, AppState>,
+) -> Result<SpectrogramSettings, String> {
+    let response = send_request(&state.ipc, Request::GetSpectrogramSettings).await?;
+
+    match response {
+        Response::SpectrogramSettings(settings) => Ok(settings),
+        Response::Error { message, .. } => Err(message),
+        _ => Err("Unexpected response".into()),
+    }
+}
+
+/// Set the spectrogram FFT size, window and emit rate; applies to the running display
+#[tauri::command]
+async fn set_spectrogram_settings(
+    settings: SpectrogramSettings,
+    state: State<'_, AppState>,
+) -> Result<SpectrogramSettings, String> {
+    let response = send_request(&state.ipc, Request::SetSpectrogramSettings { settings }).await?;
+
+    match response {
+        Response::SpectrogramSettings(settings) => Ok(settings),
+        Response::Error { message, .. } => Err(message),
+        _ => Err("Unexpected response".into()),
+    }
+}
+
+/// Measure the echo delay while audio plays and seed the AEC with it
+#[tauri::command]
+async fn estimate_aec_delay(
+    duration_secs: Option<u32>,
+    state: State<'_, AppState>,
+) -> Result<AecSettings, String> {
+    let duration_secs = duration_secs.unwrap_or(5);
+    let response = send_request(&state.ipc, Request::EstimateAecDelay { duration_secs }).await?;
+
+    match response {
+        Response::AecSettings(settings) => Ok(settings),
+        Response::Error { message, .. } => Err(message),
+        _ => Err("Unexpected response".into()),
+    }
+}
+
+/// Measure ambient noise on the running capture and tune the speech detector to it
+#[tauri::command]
+async fn calibrate_noise(
+    duration_secs: Option<u32>,
+    state: State<'_, AppState>,
+) -> Result<NoiseCalibration, String> {
+    let duration_secs = duration_secs.unwrap_or(MIN_CALIBRATION_SECS);
+    let response = send_request(&state.ipc, Request::CalibrateNoise { duration_secs }).await?;
+
+    match response {
+        Response::NoiseCalibration(calibration) => Ok(calibration),
+        Response::Error { message, .. } => Err(message),
+        _ => Err("Unexpected response".into()),
+    }
+}
+
+/// Get the service settings shown in the settings view
+#[tauri::command]
+async fn get_config(state: State<'_, AppState>) -> Result<BTreeMap<String, String>, String> {
+    let response = send_request(&state.ipc, Request::GetConfig).await?;
+
+    match response {
+        Response::ConfigValues { values } => Ok(values),
+        Response::Error { message, .. } => Err(message),
+        _ => Err("Unexpected response".into()),
+    }
+}
+
+/// Change a service setting; an empty value restores its default
+#[tauri::command]
+async fn set_config_value(
+    key: String,
+    value: String,
+    state: State<'_, AppState>,
+) -> Result<BTreeMap<String, String>, String> {
+    let response = send_request(&state.ipc, Request::SetConfigValue { key, value }).await?;
+
+    match response {
+        Response::ConfigValues { values } => Ok(values),
+        Response::Error { message, .. } => Err(message),
         _ => Err("Unexpected response".into()),
     }
 }
@@ -187,6 +660,7 @@ struct LocalStatus {
     in_speech: bool,
     queue_depth: usize,
     error: Option<String>,
+    permission_error: Option<String>,
 }
 
 /// Get current status
@@ -200,8 +674,9 @@ async fn get_status(state: State<'_, AppState>) -> Result<LocalStatus, String> {
             in_speech: status.in_speech,
             queue_depth: status.queue_depth,
             error: status.error,
+            permission_error: status.permission_error,
         }),
-        Response::Error { message } => Err(message),
+        Response::Error { message, .. } => Err(message),
         _ => Err("Unexpected response".into()),
     }
 }
@@ -226,7 +701,7 @@ async fn set_transcription_mode(
 
     match response {
         Response::Ok => Ok(()),
-        Response::Error { message } => Err(message),
+        Response::Error { message, .. } => Err(message),
         _ => Err("Unexpected response".into()),
     }
 }
@@ -238,7 +713,22 @@ async fn set_ptt_key(key: KeyCode, state: State<'_, AppState>) -> Result<(), Str
 
     match response {
         Response::Ok => Ok(()),
-        Response::Error { message } => Err(message),
+        Response::Error { message, .. } => Err(message),
+        _ => Err("Unexpected response".into()),
+    }
+}
+
+/// Deliver the last transcription again with `mode`, or the output mode
+#[tauri::command]
+async fn repeat_last_output(
+    mode: Option<OutputMode>,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let response = send_request(&state.ipc, Request::RepeatLastOutput { mode }).await?;
+
+    match response {
+        Response::RepeatedOutput { text } => Ok(text),
+        Response::Error { message, .. } => Err(message),
         _ => Err("Unexpected response".into()),
     }
 }
@@ -256,7 +746,7 @@ async fn get_ptt_status(state: State<'_, AppState>) -> Result<LocalPttStatus, St
             available: status.available,
             error: status.error,
         }),
-        Response::Error { message } => Err(message),
+        Response::Error { message, .. } => Err(message),
         _ => Err("Unexpected response".into()),
     }
 }
@@ -277,7 +767,7 @@ async fn app_ready(state: State<'_, AppState>, app_handle: AppHandle) -> Result<
             .await;
             Ok(())
         }
-        Response::Error { message } => Err(message),
+        Response::Error { message, .. } => Err(message),
         _ => Err("Unexpected response".into()),
     }
 }
@@ -289,7 +779,7 @@ async fn app_disconnect(state: State<'_, AppState>) -> Result<(), String> {
 
     match response {
         Response::Ok => Ok(()),
-        Response::Error { message } => Err(message),
+        Response::Error { message, .. } => Err(message),
         _ => Err("Unexpected response".into()),
     }
 }
@@ -347,6 +837,14 @@ pub fn run() {
         .manage(AppState {
             ipc: SharedIpcClient::new(),
             event_task_running: Arc::new(Mutex::new(false)),
+            tray: std::sync::Mutex::new(tray::TrayState::default()),
+        })
+        .setup(|app| {
+            tray::init(app.handle())?;
+            if !env::args().any(|arg| arg == START_MINIMIZED_ARG) {
+                tray::show_main_window(app.handle());
+            }
+            Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             list_all_sources,
@@ -357,8 +855,36 @@ pub fn run() {
             download_model,
             get_status,
             get_cuda_status,
+            get_permissions,
+            request_permission,
+            get_system_audio_support,
+            export_session,
+            get_session,
+            get_session_stats,
+            update_session,
+            set_session_metadata,
+            list_sessions,
+            search_transcripts,
+            retranscribe_sessions,
+            list_jobs,
+            cancel_job,
+            play_session,
+            seek_playback,
+            pause_playback,
+            stop_playback,
+            get_config,
+            set_config_value,
+            get_vad_config,
+            set_vad_config,
+            calibrate_noise,
+            get_aec_settings,
+            set_aec_settings,
+            estimate_aec_delay,
+            get_spectrogram_settings,
+            set_spectrogram_settings,
             set_transcription_mode,
             set_ptt_key,
+            repeat_last_output,
             get_ptt_status,
             app_ready,
             app_disconnect,
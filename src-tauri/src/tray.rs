@@ -0,0 +1,390 @@
+//! System tray icon and menu.
+//!
+//! The tray offers quick start/stop, push-to-talk, echo cancellation and mute
+//! toggles, and a list of recently used sources. Its menu is rebuilt from
+//! [`TrayState`] whenever that changes, either through tray actions, GUI
+//! commands, or service events forwarded by the IPC client.
+
+use std::collections::HashMap;
+
+use flowstt_common::ipc::{Request, Response};
+use flowstt_common::{AudioDevice, TranscriptionMode};
+use tauri::image::Image;
+use tauri::menu::{CheckMenuItem, IsMenuItem, Menu, MenuItem, PredefinedMenuItem, Submenu};
+use tauri::tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent};
+use tauri::{AppHandle, Emitter, Manager, Wry};
+
+use crate::{acquire_control, send_request, AppState};
+
+/// ID of the single tray icon
+const TRAY_ID: &str = "main";
+
+/// Menu item IDs
+const TOGGLE_CAPTURE_ID: &str = "toggle-capture";
+const PUSH_TO_TALK_ID: &str = "push-to-talk";
+const AEC_ID: &str = "aec";
+const MUTE_ID: &str = "mute";
+const SHOW_ID: &str = "show";
+const QUIT_ID: &str = "quit";
+/// Prefix of the recent source items, followed by the device ID
+const SOURCE_PREFIX: &str = "source:";
+
+/// Number of recently used sources offered in the menu
+const MAX_RECENT_SOURCES: usize = 5;
+
+/// Color of the dot drawn on the icon while capturing (RGBA)
+const RECORDING_COLOR: [u8; 4] = [0xe5, 0x39, 0x35, 0xff];
+
+/// What the tray shows.
+#[derive(Debug, Clone, Default)]
+pub struct TrayState {
+    /// Whether audio capture is running
+    pub capturing: bool,
+    /// Whether push-to-talk mode is active
+    pub push_to_talk: bool,
+    /// Whether echo cancellation is enabled
+    pub aec_enabled: bool,
+    /// Whether privacy mode keeps capture off
+    pub muted: bool,
+    /// Primary source last configured
+    pub source1_id: Option<String>,
+    /// Secondary source last configured
+    pub source2_id: Option<String>,
+    /// Recently used primary sources, most recent first
+    pub recent_sources: Vec<String>,
+    /// Device names by ID, from the last device listing
+    pub device_names: HashMap<String, String>,
+}
+
+impl TrayState {
+    /// Record newly configured sources.
+    pub fn set_sources(&mut self, source1_id: Option<String>, source2_id: Option<String>) {
+        if let Some(id) = &source1_id {
+            self.recent_sources.retain(|recent| recent != id);
+            self.recent_sources.insert(0, id.clone());
+            self.recent_sources.truncate(MAX_RECENT_SOURCES);
+        }
+        self.source1_id = source1_id;
+        self.source2_id = source2_id;
+    }
+
+    /// Remember device names for the source menu.
+    pub fn set_devices(&mut self, devices: &[AudioDevice]) {
+        self.device_names = devices
+            .iter()
+            .map(|d| (d.id.clone(), d.name.clone()))
+            .collect();
+    }
+
+    fn device_name<'a>(&'a self, id: &'a str) -> &'a str {
+        self.device_names.get(id).map_or(id, String::as_str)
+    }
+}
+
+/// Sources changed from the tray, so the GUI can update its selection.
+#[derive(Clone, serde::Serialize)]
+struct SourcesChanged {
+    source1_id: Option<String>,
+    source2_id: Option<String>,
+}
+
+/// Create the tray icon.
+pub fn init(app: &AppHandle) -> tauri::Result<()> {
+    let state = TrayState::default();
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .tooltip("FlowSTT")
+        .menu(&build_menu(app, &state)?)
+        .show_menu_on_left_click(false)
+        .on_menu_event(|app, event| on_menu_event(app, event.id().as_ref()))
+        .on_tray_icon_event(|tray, event| {
+            if let TrayIconEvent::Click {
+                button: MouseButton::Left,
+                button_state: MouseButtonState::Up,
+                ..
+            } = event
+            {
+                show_main_window(tray.app_handle());
+            }
+        });
+    if let Some(icon) = app.default_window_icon() {
+        builder = builder.icon(icon.clone());
+    }
+    builder.build(app)?;
+
+    // Pick up the mode the service is already in
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let ipc = app.state::<AppState>().ipc.clone();
+        if let Ok(Response::PttStatus(status)) = send_request(&ipc, Request::GetPttStatus).await {
+            update(&app, |t| {
+                t.push_to_talk = status.mode == TranscriptionMode::PushToTalk
+            });
+        }
+        if let Ok(Response::Status(status)) = send_request(&ipc, Request::GetStatus).await {
+            update(&app, |t| t.muted = status.muted);
+        }
+    });
+
+    Ok(())
+}
+
+/// Change the tray state and refresh the icon and menu.
+pub fn update(app: &AppHandle, f: impl FnOnce(&mut TrayState)) {
+    let state = {
+        let app_state = app.state::<AppState>();
+        let mut tray_state = app_state.tray.lock().unwrap();
+        f(&mut tray_state);
+        tray_state.clone()
+    };
+
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return;
+    };
+    match build_menu(app, &state) {
+        Ok(menu) => {
+            let _ = tray.set_menu(Some(menu));
+        }
+        Err(e) => eprintln!("[Tray] Failed to build menu: {}", e),
+    }
+    if let Some(icon) = app.default_window_icon() {
+        let icon = if state.capturing {
+            with_recording_dot(icon)
+        } else {
+            icon.clone().to_owned()
+        };
+        let _ = tray.set_icon(Some(icon));
+    }
+    let tooltip = if state.muted {
+        "FlowSTT - Muted"
+    } else if state.capturing {
+        "FlowSTT - Transcribing"
+    } else {
+        "FlowSTT"
+    };
+    let _ = tray.set_tooltip(Some(tooltip));
+}
+
+fn build_menu(app: &AppHandle, state: &TrayState) -> tauri::Result<Menu<Wry>> {
+    let toggle_label = if state.capturing {
+        "Stop Transcription"
+    } else {
+        "Start Transcription"
+    };
+    let toggle = MenuItem::with_id(
+        app,
+        TOGGLE_CAPTURE_ID,
+        toggle_label,
+        !state.muted,
+        None::<&str>,
+    )?;
+    let push_to_talk = CheckMenuItem::with_id(
+        app,
+        PUSH_TO_TALK_ID,
+        "Push-to-Talk",
+        true,
+        state.push_to_talk,
+        None::<&str>,
+    )?;
+    let aec = CheckMenuItem::with_id(
+        app,
+        AEC_ID,
+        "Echo Cancellation",
+        true,
+        state.aec_enabled,
+        None::<&str>,
+    )?;
+    let mute = CheckMenuItem::with_id(
+        app,
+        MUTE_ID,
+        "Mute (Privacy Mode)",
+        true,
+        state.muted,
+        None::<&str>,
+    )?;
+
+    let source_items = state
+        .recent_sources
+        .iter()
+        .map(|id| {
+            CheckMenuItem::with_id(
+                app,
+                format!("{}{}", SOURCE_PREFIX, id),
+                state.device_name(id),
+                true,
+                state.source1_id.as_deref() == Some(id.as_str()),
+                None::<&str>,
+            )
+        })
+        .collect::<tauri::Result<Vec<_>>>()?;
+    let placeholder = MenuItem::new(app, "No recent sources", false, None::<&str>)?;
+    let source_refs: Vec<&dyn IsMenuItem<Wry>> = if source_items.is_empty() {
+        vec![&placeholder]
+    } else {
+        source_items
+            .iter()
+            .map(|item| item as &dyn IsMenuItem<Wry>)
+            .collect()
+    };
+    let sources = Submenu::with_items(app, "Sources", true, &source_refs)?;
+
+    let show = MenuItem::with_id(app, SHOW_ID, "Open FlowSTT", true, None::<&str>)?;
+    let quit = MenuItem::with_id(app, QUIT_ID, "Quit", true, None::<&str>)?;
+
+    Menu::with_items(
+        app,
+        &[
+            &toggle,
+            &push_to_talk,
+            &aec,
+            &mute,
+            &sources,
+            &PredefinedMenuItem::separator(app)?,
+            &show,
+            &quit,
+        ],
+    )
+}
+
+fn on_menu_event(app: &AppHandle, id: &str) {
+    match id {
+        SHOW_ID => show_main_window(app),
+        QUIT_ID => app.exit(0),
+        _ => {
+            let app = app.clone();
+            let id = id.to_string();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = run_action(&app, &id).await {
+                    eprintln!("[Tray] {}", e);
+                    let _ = app.emit("service-error", &e);
+                }
+            });
+        }
+    }
+}
+
+/// Carry out a menu action that talks to the service.
+async fn run_action(app: &AppHandle, id: &str) -> Result<(), String> {
+    let state = app.state::<AppState>().tray.lock().unwrap().clone();
+
+    match id {
+        TOGGLE_CAPTURE_ID if state.capturing => set_sources(app, None, None).await,
+        TOGGLE_CAPTURE_ID => {
+            let source1_id = state
+                .source1_id
+                .clone()
+                .or_else(|| state.recent_sources.first().cloned());
+            if source1_id.is_none() {
+                // Nothing to capture from yet; let the user pick a source
+                show_main_window(app);
+                return Ok(());
+            }
+            set_sources(app, source1_id, state.source2_id.clone()).await
+        }
+        PUSH_TO_TALK_ID => {
+            let mode = if state.push_to_talk {
+                TranscriptionMode::Automatic
+            } else {
+                TranscriptionMode::PushToTalk
+            };
+            request_ok(app, Request::SetTranscriptionMode { mode }).await?;
+            update(app, |t| t.push_to_talk = !state.push_to_talk);
+            Ok(())
+        }
+        AEC_ID => {
+            let enabled = !state.aec_enabled;
+            request_ok(app, Request::SetAecEnabled { enabled }).await?;
+            update(app, |t| t.aec_enabled = enabled);
+            Ok(())
+        }
+        MUTE_ID => {
+            let muted = !state.muted;
+            request_ok(app, Request::SetMuted { muted }).await?;
+            update(app, |t| t.muted = muted);
+            Ok(())
+        }
+        _ => match id.strip_prefix(SOURCE_PREFIX) {
+            Some(source1_id) => {
+                set_sources(app, Some(source1_id.to_string()), state.source2_id.clone()).await
+            }
+            None => Err(format!("Unknown tray menu item: {}", id)),
+        },
+    }
+}
+
+/// Configure sources from the tray and tell the GUI about it.
+async fn set_sources(
+    app: &AppHandle,
+    source1_id: Option<String>,
+    source2_id: Option<String>,
+) -> Result<(), String> {
+    if source1_id.is_some() {
+        let ipc = app.state::<AppState>().ipc.clone();
+        acquire_control(&ipc).await?;
+    }
+    request_ok(
+        app,
+        Request::SetSources {
+            source1_id: source1_id.clone(),
+            source2_id: source2_id.clone(),
+            source1_channels: None,
+            source2_channels: None,
+        },
+    )
+    .await?;
+
+    // Stopping forgets the sources in the service, but the tray keeps them
+    // so the next start resumes with the same devices
+    if source1_id.is_some() {
+        update(app, |t| {
+            t.set_sources(source1_id.clone(), source2_id.clone())
+        });
+    }
+    let _ = app.emit(
+        "tray-sources-changed",
+        SourcesChanged {
+            source1_id,
+            source2_id,
+        },
+    );
+    Ok(())
+}
+
+async fn request_ok(app: &AppHandle, request: Request) -> Result<(), String> {
+    let ipc = app.state::<AppState>().ipc.clone();
+    match send_request(&ipc, request).await? {
+        Response::Ok => Ok(()),
+        Response::Error { message, .. } => Err(message),
+        _ => Err("Unexpected response".into()),
+    }
+}
+
+/// Show and focus the main window.
+pub fn show_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.show();
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+    }
+}
+
+/// Copy of the icon with a recording dot in the lower right corner.
+fn with_recording_dot(icon: &Image<'_>) -> Image<'static> {
+    let (width, height) = (icon.width(), icon.height());
+    let mut rgba = icon.rgba().to_vec();
+
+    let radius = width.min(height) as f32 * 0.22;
+    let center_x = width as f32 - radius - 1.0;
+    let center_y = height as f32 - radius - 1.0;
+    for y in 0..height {
+        for x in 0..width {
+            let dx = x as f32 + 0.5 - center_x;
+            let dy = y as f32 + 0.5 - center_y;
+            if dx * dx + dy * dy <= radius * radius {
+                let i = ((y * width + x) * 4) as usize;
+                rgba[i..i + 4].copy_from_slice(&RECORDING_COLOR);
+            }
+        }
+    }
+
+    Image::new_owned(rgba, width, height)
+}
@@ -80,6 +80,7 @@ let pttIndicator: HTMLElement | null;
 
 // State
 let isCapturing = false;
+let isMuted = false;
 let inSpeechSegment = false;
 let transcribeQueueDepth = 0;
 let allDevices: AudioDevice[] = [];
@@ -90,12 +91,19 @@ let isPttActive = false;
 // Event listeners
 let visualizationUnlisten: UnlistenFn | null = null;
 let transcriptionCompleteUnlisten: UnlistenFn | null = null;
+let transcriptionPartialUnlisten: UnlistenFn | null = null;
+let transcriptionTokenUnlisten: UnlistenFn | null = null;
+let transcriptionCancelledUnlisten: UnlistenFn | null = null;
+let traySourcesChangedUnlisten: UnlistenFn | null = null;
+let controllerChangedUnlisten: UnlistenFn | null = null;
+let privacyModeUnlisten: UnlistenFn | null = null;
 let transcriptionErrorUnlisten: UnlistenFn | null = null;
 let speechStartedUnlisten: UnlistenFn | null = null;
 let speechEndedUnlisten: UnlistenFn | null = null;
 let captureStateChangedUnlisten: UnlistenFn | null = null;
 let pttPressedUnlisten: UnlistenFn | null = null;
 let pttReleasedUnlisten: UnlistenFn | null = null;
+let feedbackCueUnlisten: UnlistenFn | null = null;
 let transcriptionModeChangedUnlisten: UnlistenFn | null = null;
 
 let miniWaveformRenderer: MiniWaveformRenderer | null = null;
@@ -225,6 +233,11 @@ function setStatus(message: string, type: "normal" | "progress" | "warning" | "e
 
 // Update status based on current state
 function updateStatusDisplay() {
+  if (isMuted) {
+    setStatus("Muted - not listening", "warning");
+    return;
+  }
+
   if (!isCapturing) {
     setStatus("Ready - select an audio source to begin");
     return;
@@ -266,6 +279,60 @@ async function setupEventListeners() {
     });
   }
 
+  // Interim results for the segment still being captured
+  if (!transcriptionPartialUnlisten) {
+    transcriptionPartialUnlisten = await listen<string>("transcription-partial", (event) => {
+      setPartialTranscription(event.payload);
+    });
+  }
+
+  // Text of the finished segment as whisper decodes it, for a typing-out effect
+  if (!transcriptionTokenUnlisten) {
+    transcriptionTokenUnlisten = await listen<string>("transcription-token", (event) => {
+      setPartialTranscription(event.payload);
+    });
+  }
+
+  // Cancelled transcriptions won't complete, so drop their interim text
+  if (!transcriptionCancelledUnlisten) {
+    transcriptionCancelledUnlisten = await listen<number>("transcription-cancelled", () => {
+      setPartialTranscription("");
+    });
+  }
+
+  // Sources picked or capture toggled from the tray menu
+  if (!traySourcesChangedUnlisten) {
+    traySourcesChangedUnlisten = await listen<{source1_id: string | null, source2_id: string | null}>(
+      "tray-sources-changed",
+      (event) => {
+        if (source1Select) source1Select.value = event.payload.source1_id ?? "";
+        if (source2Select) source2Select.value = event.payload.source2_id ?? "";
+      }
+    );
+  }
+
+  // Another client (CLI or HTTP) took over capture
+  if (!controllerChangedUnlisten) {
+    controllerChangedUnlisten = await listen<{controller: string | null, is_self: boolean}>(
+      "controller-changed",
+      (event) => {
+        if (event.payload.controller && !event.payload.is_self) {
+          setStatus(`Capture is controlled by ${event.payload.controller}`, "warning");
+        } else {
+          updateStatusDisplay();
+        }
+      }
+    );
+  }
+
+  // Privacy mode toggled from the tray, CLI or HTTP API
+  if (!privacyModeUnlisten) {
+    privacyModeUnlisten = await listen<boolean>("privacy-mode", (event) => {
+      isMuted = event.payload;
+      updateStatusDisplay();
+    });
+  }
+
   // Speech events
   if (!speechStartedUnlisten) {
     speechStartedUnlisten = await listen("speech-started", () => {
@@ -326,6 +393,16 @@ async function setupEventListeners() {
     });
   }
 
+  // Flash the indicator the cue belongs to
+  if (!feedbackCueUnlisten) {
+    feedbackCueUnlisten = await listen<"ptt_start" | "ptt_stop" | "transcription_done">(
+      "feedback-cue",
+      (event) => {
+        flashCue(event.payload === "transcription_done" ? resultEl : pttIndicator);
+      }
+    );
+  }
+
   // Mode changed
   if (!transcriptionModeChangedUnlisten) {
     transcriptionModeChangedUnlisten = await listen<TranscriptionMode>(
@@ -346,6 +423,19 @@ function cleanupEventListeners() {
   
   transcriptionCompleteUnlisten?.();
   transcriptionCompleteUnlisten = null;
+
+  transcriptionPartialUnlisten?.();
+  transcriptionPartialUnlisten = null;
+  transcriptionTokenUnlisten?.();
+  transcriptionTokenUnlisten = null;
+  transcriptionCancelledUnlisten?.();
+  transcriptionCancelledUnlisten = null;
+  traySourcesChangedUnlisten?.();
+  traySourcesChangedUnlisten = null;
+  controllerChangedUnlisten?.();
+  controllerChangedUnlisten = null;
+  privacyModeUnlisten?.();
+  privacyModeUnlisten = null;
   
   transcriptionErrorUnlisten?.();
   transcriptionErrorUnlisten = null;
@@ -364,6 +454,8 @@ function cleanupEventListeners() {
   
   pttReleasedUnlisten?.();
   pttReleasedUnlisten = null;
+  feedbackCueUnlisten?.();
+  feedbackCueUnlisten = null;
   
   transcriptionModeChangedUnlisten?.();
   transcriptionModeChangedUnlisten = null;
@@ -372,6 +464,8 @@ function cleanupEventListeners() {
 // ============== Transcription Display ==============
 
 let transcriptionBuffer = "";
+// Interim text for the segment currently being captured (replaced on each update)
+let partialTranscription = "";
 let resultTextSpan: HTMLSpanElement | null = null;
 // Track if display needs refresh when window becomes visible
 let transcriptionDisplayDirty = false;
@@ -409,8 +503,11 @@ function updateTranscriptionDisplay(): void {
     }
     
     // Insert new text before cursor
-    if (transcriptionBuffer.length > 0 && cursor) {
-      const textNode = document.createTextNode(transcriptionBuffer);
+    const displayText = partialTranscription
+      ? (transcriptionBuffer ? transcriptionBuffer + " " : "") + partialTranscription
+      : transcriptionBuffer;
+    if (displayText.length > 0 && cursor) {
+      const textNode = document.createTextNode(displayText);
       resultTextSpan.insertBefore(textNode, cursor);
     }
   }
@@ -437,6 +534,9 @@ function appendTranscription(newText: string): void {
 
   console.log("[Transcription] Received:", trimmedText);
 
+  // Final text supersedes any interim result
+  partialTranscription = "";
+
   if (transcriptionBuffer.length > 0) {
     transcriptionBuffer += " " + trimmedText;
   } else {
@@ -458,6 +558,13 @@ function appendTranscription(newText: string): void {
   updateTranscriptionDisplay();
 }
 
+function setPartialTranscription(text: string): void {
+  if (!resultEl) return;
+
+  partialTranscription = text.trim();
+  updateTranscriptionDisplay();
+}
+
 // ============== PTT and Mode Control ==============
 
 async function loadPttStatus() {
@@ -506,6 +613,14 @@ function updatePttIndicator() {
   }
 }
 
+function flashCue(el: HTMLElement | null) {
+  if (!el) return;
+  el.classList.remove("cue-flash");
+  // Force a reflow so the animation restarts when cues arrive back to back
+  void el.offsetWidth;
+  el.classList.add("cue-flash");
+}
+
 async function onModeToggleChange() {
   if (!modeToggle) return;
   
@@ -20,10 +20,17 @@ export interface SpeechMetrics {
   is_word_break: boolean;    // Whether a word break (inter-word gap) is detected
 }
 
+// Waveform of one source when microphone and system audio are both captured
+export interface SourceWaveform {
+  source: "input" | "system";
+  waveform: number[];                    // Aligned with the mixed waveform
+}
+
 export interface VisualizationPayload {
   waveform: number[];                    // Pre-downsampled amplitudes
   spectrogram: SpectrogramColumn | null; // Present when FFT buffer fills
   speech_metrics: SpeechMetrics | null;  // Present when speech processor is active
+  source_waveforms?: SourceWaveform[];   // Present with two sources
 }
 
 // Ring buffer for storing waveform samples